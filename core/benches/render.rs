@@ -0,0 +1,92 @@
+//! The drawing paths every app goes through each frame, at the device's
+//! 640x480. Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use palette::LinSrgb;
+
+use rg35xx::app::Frame;
+use rg35xx::surface::Surface;
+
+const WIDTH: usize = 640;
+const HEIGHT: usize = 480;
+const FONT: &str = "fonts/Ubuntu-B.ttf";
+
+fn fill_rect(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fill_rect");
+    for bytespp in [4, 2] {
+        let mut data = vec![0; WIDTH * HEIGHT * bytespp];
+        group.bench_function(format!("full screen, {} bytes", bytespp), |b| {
+            let mut frame = Frame::new(WIDTH, HEIGHT, bytespp, &mut data);
+            b.iter(|| frame.fill_rect(0, 0, WIDTH, HEIGHT, black_box(LinSrgb::new(255, 0, 0))))
+        });
+    }
+    group.finish();
+}
+
+fn text(c: &mut Criterion) {
+    let mut group = c.benchmark_group("text");
+    let mut data = vec![0; WIDTH * HEIGHT * 4];
+    let line = "The quick brown fox jumps over the lazy dog";
+    group.bench_function("cached font", |b| {
+        let mut frame = Frame::new(WIDTH, HEIGHT, 4, &mut data);
+        b.iter(|| {
+            frame.text(
+                FONT,
+                20,
+                50,
+                36.0,
+                LinSrgb::new(255, 255, 255),
+                black_box(line),
+            )
+        })
+    });
+    // Loading and parsing the font again each time, like the first frame
+    group.bench_function("cold font", |b| {
+        let mut frame = Frame::new(WIDTH, HEIGHT, 4, &mut data);
+        b.iter(|| {
+            rg35xx::assets::clear_cache();
+            frame.text(
+                FONT,
+                20,
+                50,
+                36.0,
+                LinSrgb::new(255, 255, 255),
+                black_box(line),
+            )
+        })
+    });
+    group.finish();
+}
+
+fn copies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("full frame");
+    let mut surface = Surface::new(WIDTH, HEIGHT);
+    surface
+        .frame()
+        .fill_rect(0, 0, WIDTH, HEIGHT, LinSrgb::new(10, 200, 30));
+
+    let mut data = vec![0; WIDTH * HEIGHT * 4];
+    group.bench_function("draw surface", |b| {
+        let mut frame = Frame::new(WIDTH, HEIGHT, 4, &mut data);
+        b.iter(|| surface.draw(&mut frame, 0, 0))
+    });
+    group.bench_function("draw surface at half alpha", |b| {
+        let mut frame = Frame::new(WIDTH, HEIGHT, 4, &mut data);
+        b.iter(|| surface.draw_alpha(&mut frame, 0, 0, 0.5))
+    });
+
+    // Converting to and from the device's RGB565 framebuffer
+    let mut data = vec![0; WIDTH * HEIGHT * 2];
+    group.bench_function("draw surface to RGB565", |b| {
+        let mut frame = Frame::new(WIDTH, HEIGHT, 2, &mut data);
+        b.iter(|| surface.draw(&mut frame, 0, 0))
+    });
+    group.bench_function("capture RGB565", |b| {
+        let frame = Frame::new(WIDTH, HEIGHT, 2, &mut data);
+        b.iter(|| surface.copy_from(&frame))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, fill_rect, text, copies);
+criterion_main!(benches);
@@ -0,0 +1,55 @@
+//! Goals apps can declare in their [`Manifest`](crate::app::Manifest) and
+//! mark as reached with [`unlock`]. Unlocked achievements are kept in the
+//! current profile, and the menu announces them and lists them all in its
+//! gallery.
+
+use crate::events::{publish, AchievementUnlocked};
+use crate::profiles::Store;
+
+/// Something to aim for in an app
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Achievement {
+    /// Unique across apps, e.g. `snake_100`
+    pub id: &'static str,
+    pub title: &'static str,
+    /// How to get it
+    pub description: &'static str,
+}
+
+impl Achievement {
+    pub const fn new(id: &'static str, title: &'static str, description: &'static str) -> Self {
+        Self {
+            id,
+            title,
+            description,
+        }
+    }
+}
+
+fn store() -> Store {
+    Store::open("achievements")
+}
+
+/// Marks the achievement with `id` as reached in the current profile and
+/// publishes [`AchievementUnlocked`]. Does nothing if it already was, so
+/// apps can call it whenever the condition holds, or during a
+/// [demo](crate::demo).
+pub fn unlock(id: &str) {
+    if crate::demo::playing() {
+        return;
+    }
+    let mut store = store();
+    if store.get::<String>(id).is_some() {
+        return;
+    }
+    store.set(id, crate::storage::date_of_day(crate::storage::today()));
+    if let Err(e) = store.save() {
+        println!("Couldn't save achievement {}: {}", id, e);
+    }
+    publish(AchievementUnlocked { id: id.to_string() });
+}
+
+/// The date `id` was unlocked in the current profile, if it has been
+pub fn unlocked_on(id: &str) -> Option<String> {
+    store().get(id)
+}
@@ -0,0 +1,255 @@
+//! Semantic actions like "confirm" and "back", so apps don't hard-code which
+//! button does what.
+//!
+//! The defaults can be changed for every app in `controls.txt` next to the
+//! launcher binary, and for one app in `controls/<app>.txt`, e.g.
+//!
+//! ```text
+//! # B confirms and A goes back, like most non-Nintendo handhelds
+//! swap_confirm = true
+//! start = START, X
+//! ```
+
+use std::{io, path::PathBuf, str::FromStr};
+
+use enum_iterator::{all, Sequence};
+use enum_map::{Enum, EnumMap};
+
+use crate::app::{Button, Input};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum, Sequence)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+    /// Start or pause a game
+    Start,
+    /// More about the selected thing
+    Info,
+}
+
+impl Action {
+    /// The name used in control files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Down => "down",
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::Confirm => "confirm",
+            Self::Back => "back",
+            Self::Start => "start",
+            Self::Info => "info",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        all::<Action>()
+            .find(|action| action.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown action `{}`", s))
+    }
+}
+
+/// What the button is labelled on the device
+pub fn button_label(button: Button) -> &'static str {
+    match button {
+        Button::PovUp => "UP",
+        Button::PovDown => "DOWN",
+        Button::PovLeft => "LEFT",
+        Button::PovRight => "RIGHT",
+        Button::BumperL => "L1",
+        Button::BumperR => "R1",
+        Button::MenuL => "SELECT",
+        Button::MenuR => "START",
+        Button::ActionA => "A",
+        Button::ActionB => "B",
+        Button::ActionV => "X",
+        Button::ActionH => "Y",
+    }
+}
+
+pub(crate) fn parse_button(s: &str) -> Result<Button, String> {
+    all::<Button>()
+        .find(|&button| button_label(button).eq_ignore_ascii_case(s))
+        .ok_or_else(|| format!("unknown button `{}`", s))
+}
+
+/// Launcher-wide overrides
+fn global_path() -> PathBuf {
+    crate::storage::base_dir().join("controls.txt")
+}
+
+/// Overrides for the app with the given slug, e.g. `distance-integrator`
+fn app_path(app: &str) -> PathBuf {
+    crate::storage::base_dir()
+        .join("controls")
+        .join(format!("{}.txt", app))
+}
+
+/// Which buttons trigger each [`Action`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionMap {
+    bindings: EnumMap<Action, Vec<Button>>,
+}
+
+impl Default for ActionMap {
+    /// The built-in bindings, with A to confirm
+    fn default() -> Self {
+        Self {
+            bindings: EnumMap::from_fn(|action| {
+                vec![match action {
+                    Action::Up => Button::PovUp,
+                    Action::Down => Button::PovDown,
+                    Action::Left => Button::PovLeft,
+                    Action::Right => Button::PovRight,
+                    Action::Confirm => Button::ActionA,
+                    Action::Back => Button::ActionB,
+                    Action::Start => Button::MenuR,
+                    Action::Info => Button::MenuL,
+                }]
+            }),
+        }
+    }
+}
+
+impl ActionMap {
+    /// The defaults with the launcher-wide overrides applied
+    pub fn global() -> Self {
+        let mut map = Self::default();
+        if let Ok(text) = std::fs::read_to_string(global_path()) {
+            map.apply(&text);
+        }
+        map
+    }
+
+    /// The launcher-wide bindings with `app`'s own overrides on top
+    pub fn for_app(app: &str) -> Self {
+        let mut map = Self::global();
+        if let Ok(text) = std::fs::read_to_string(app_path(app)) {
+            map.apply(&text);
+        }
+        map
+    }
+
+    /// Applies the lines of a control file, logging any it doesn't understand
+    pub fn apply(&mut self, text: &str) {
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Err(e) = self.apply_line(line) {
+                println!("Ignoring `{}` in controls: {}", line, e);
+            }
+        }
+    }
+
+    fn apply_line(&mut self, line: &str) -> Result<(), String> {
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| "expected name = value".to_string())?;
+        let (key, value) = (key.trim(), value.trim());
+        if key == "swap_confirm" {
+            let swap = value
+                .parse::<bool>()
+                .map_err(|_| "expected true or false".to_string())?;
+            if swap != self.confirm_swapped() {
+                self.swap_confirm();
+            }
+            return Ok(());
+        }
+        let action = key.parse::<Action>()?;
+        self.bindings[action] = value
+            .split(',')
+            .map(|button| parse_button(button.trim()))
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// Exchanges the confirm and back buttons
+    pub fn swap_confirm(&mut self) {
+        let confirm = std::mem::take(&mut self.bindings[Action::Confirm]);
+        self.bindings[Action::Confirm] =
+            std::mem::replace(&mut self.bindings[Action::Back], confirm);
+    }
+
+    /// Whether B confirms instead of A
+    pub fn confirm_swapped(&self) -> bool {
+        self.bindings[Action::Confirm].first() == Some(&Button::ActionB)
+    }
+
+    pub fn bind(&mut self, action: Action, buttons: &[Button]) {
+        self.bindings[action] = buttons.to_vec();
+    }
+
+    pub fn buttons(&self, action: Action) -> &[Button] {
+        &self.bindings[action]
+    }
+
+    /// The label of the main button for `action`, for on-screen hints
+    pub fn label(&self, action: Action) -> &'static str {
+        self.bindings[action]
+            .first()
+            .map_or("?", |&b| button_label(b))
+    }
+
+    pub fn pressed(&self, input: &Input, action: Action) -> bool {
+        self.bindings[action].iter().any(|&b| input.pressed(b))
+    }
+
+    pub fn just_pressed(&self, input: &Input, action: Action) -> bool {
+        self.bindings[action].iter().any(|&b| input.just_pressed(b))
+    }
+
+    /// See [`Input::repeated`]
+    pub fn repeated(&self, input: &Input, action: Action) -> bool {
+        self.bindings[action].iter().any(|&b| input.repeated(b))
+    }
+
+    pub fn just_released(&self, input: &Input, action: Action) -> bool {
+        self.bindings[action]
+            .iter()
+            .any(|&b| input.just_released(b))
+    }
+}
+
+/// Saves whether B confirms for every app, keeping the rest of `controls.txt`
+pub fn set_confirm_swapped(swapped: bool) -> io::Result<()> {
+    let path = global_path();
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut lines = existing
+        .lines()
+        .filter(|line| {
+            line.split_once('=')
+                .is_none_or(|(key, _)| key.trim() != "swap_confirm")
+        })
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    lines.push(format!("swap_confirm = {}", swapped));
+    std::fs::write(path, lines.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut map = ActionMap::default();
+        map.apply("# comment\nswap_confirm = true\nstart = START, x\nnonsense\n");
+        assert_eq!(map.buttons(Action::Confirm), [Button::ActionB]);
+        assert_eq!(map.buttons(Action::Back), [Button::ActionA]);
+        assert_eq!(map.buttons(Action::Start), [Button::MenuR, Button::ActionV]);
+        assert!(map.confirm_swapped());
+
+        map.apply("swap_confirm = false");
+        assert_eq!(map.label(Action::Confirm), "A");
+    }
+}
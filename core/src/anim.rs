@@ -0,0 +1,381 @@
+//! Tweens, timers and springs for animating UI instead of snapping between
+//! states. Each is stepped with the time since the last frame, from
+//! [`Input::delta`](crate::app::Input::delta), so they play at the same speed
+//! whatever the frame rate.
+//!
+//! Apps should report [`Activity::Busy`](crate::app::Activity::Busy) while
+//! anything is still animating, or the backend may not draw the next frame.
+
+use palette::LinSrgb;
+
+use crate::time::Duration;
+
+/// How a tween's progress maps to how far it's moved, from 0 to 1
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slowly
+    QuadIn,
+    /// Stops slowly
+    QuadOut,
+    /// Starts and stops slowly
+    QuadInOut,
+    /// Stops more slowly than [`Easing::QuadOut`], for things settling into
+    /// place
+    CubicOut,
+    /// Overshoots slightly before settling
+    BackOut,
+}
+
+impl Easing {
+    /// Eases `t`, which is clamped to 0..1
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::QuadIn => t * t,
+            Self::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::QuadInOut if t < 0.5 => 2.0 * t * t,
+            Self::QuadInOut => 1.0 - 2.0 * (1.0 - t) * (1.0 - t),
+            Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Self::BackOut => {
+                const OVERSHOOT: f32 = 1.70158;
+                let t = t - 1.0;
+                1.0 + (OVERSHOOT + 1.0) * t.powi(3) + OVERSHOOT * t * t
+            }
+        }
+    }
+}
+
+/// Values a [`Tween`] can move between
+pub trait Lerp: Copy {
+    /// `self` when `t` is 0, `to` when it's 1. Eased `t` can go a little past
+    /// either end.
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for (f32, f32) {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        (self.0.lerp(to.0, t), self.1.lerp(to.1, t))
+    }
+}
+
+impl Lerp for LinSrgb<u8> {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        let channel =
+            |from: u8, to: u8| (from as f32).lerp(to as f32, t).round().clamp(0.0, 255.0) as u8;
+        LinSrgb::new(
+            channel(self.red, to.red),
+            channel(self.green, to.green),
+            channel(self.blue, to.blue),
+        )
+    }
+}
+
+/// Moves a value to a target over a fixed time. Changing the target part way
+/// through carries on from wherever the value had got to.
+#[derive(Debug, Clone)]
+pub struct Tween<T> {
+    from: T,
+    to: T,
+    elapsed: Duration,
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl<T: Lerp + PartialEq> Tween<T> {
+    /// A tween resting at `value` until it's given a target
+    pub fn new(value: T, duration: Duration, easing: Easing) -> Self {
+        Self {
+            from: value,
+            to: value,
+            elapsed: duration,
+            duration,
+            easing,
+        }
+    }
+
+    /// Starts moving towards `target`, unless it's already headed there
+    pub fn to(&mut self, target: T) {
+        if target == self.to {
+            return;
+        }
+        self.from = self.value();
+        self.to = target;
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Jumps straight to `value`
+    pub fn set(&mut self, value: T) {
+        self.from = value;
+        self.to = value;
+        self.elapsed = self.duration;
+    }
+
+    /// Moves on by `delta`, the time since the last frame
+    pub fn update(&mut self, delta: Duration) {
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+    }
+
+    pub fn value(&self) -> T {
+        if self.duration.is_zero() {
+            return self.to;
+        }
+        let t = self.elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        self.from.lerp(self.to, self.easing.apply(t))
+    }
+
+    pub fn target(&self) -> T {
+        self.to
+    }
+
+    /// Whether the value is still moving
+    pub fn is_active(&self) -> bool {
+        self.elapsed < self.duration
+    }
+}
+
+/// Goes off after a fixed time, once or over and over
+#[derive(Debug, Clone)]
+pub struct Timer {
+    pub duration: Duration,
+    elapsed: Duration,
+    repeating: bool,
+}
+
+impl Timer {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::ZERO,
+            repeating: false,
+        }
+    }
+
+    /// A timer that starts again each time it goes off
+    pub fn repeating(duration: Duration) -> Self {
+        Self {
+            repeating: true,
+            ..Self::new(duration)
+        }
+    }
+
+    /// Moves on by `delta`, returning whether the timer went off. A repeating
+    /// timer goes off at most once per update, however long the frame was.
+    pub fn update(&mut self, delta: Duration) -> bool {
+        if self.is_done() {
+            return false;
+        }
+        self.elapsed += delta;
+        if self.elapsed < self.duration {
+            return false;
+        }
+        if self.repeating {
+            self.elapsed = if self.duration.is_zero() {
+                Duration::ZERO
+            } else {
+                Duration::from_nanos((self.elapsed.as_nanos() % self.duration.as_nanos()) as u64)
+            };
+        } else {
+            self.elapsed = self.duration;
+        }
+        true
+    }
+
+    /// How far through the current run the timer is, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    /// Whether a one-off timer has gone off. Repeating timers never finish.
+    pub fn is_done(&self) -> bool {
+        !self.repeating && self.elapsed >= self.duration
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+/// Runs a simulation in steps of a fixed length, however long each frame
+/// was, so a game plays the same at any frame rate and repeats exactly
+#[derive(Debug, Clone)]
+pub struct FixedStep {
+    pub step: Duration,
+    /// Time passed that hasn't made up a whole step yet
+    accumulated: Duration,
+}
+
+impl FixedStep {
+    /// The most steps run for one frame, so a stall doesn't leave the game
+    /// racing to catch up
+    const MAX_STEPS: u32 = 10;
+
+    pub fn new(step: Duration) -> Self {
+        Self {
+            step,
+            accumulated: Duration::ZERO,
+        }
+    }
+
+    /// Moves on by `delta`, returning how many steps are due
+    pub fn update(&mut self, delta: Duration) -> u32 {
+        if self.step.is_zero() {
+            return 0;
+        }
+        self.accumulated += delta;
+        let steps = (self.accumulated.as_nanos() / self.step.as_nanos()) as u32;
+        if steps > Self::MAX_STEPS {
+            self.accumulated = Duration::ZERO;
+            return Self::MAX_STEPS;
+        }
+        self.accumulated -= self.step * steps;
+        steps
+    }
+
+    /// How far through the next step the leftover time is, from 0 to 1, for
+    /// drawing things part way between steps
+    pub fn alpha(&self) -> f32 {
+        if self.step.is_zero() {
+            return 0.0;
+        }
+        self.accumulated.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulated = Duration::ZERO;
+    }
+}
+
+/// A value pulled towards a target as if on a damped spring, which follows
+/// a moving target more naturally than restarting a tween each time
+#[derive(Debug, Clone)]
+pub struct Spring {
+    pub value: f32,
+    pub velocity: f32,
+    pub target: f32,
+    /// How hard the spring pulls, per unit of distance
+    pub stiffness: f32,
+    /// How much the motion is slowed, per unit of velocity. Below
+    /// `2 * stiffness.sqrt()` the value overshoots and wobbles.
+    pub damping: f32,
+}
+
+impl Spring {
+    /// How close to the target, in position and speed, counts as settled
+    const SETTLED: f32 = 0.01;
+    /// The longest step simulated at once, so long frames stay stable
+    const MAX_STEP: f32 = 1.0 / 120.0;
+
+    /// A spring resting at `value`, damped just enough not to overshoot
+    pub fn new(value: f32) -> Self {
+        Self {
+            value,
+            velocity: 0.0,
+            target: value,
+            stiffness: 300.0,
+            damping: 2.0 * 300f32.sqrt(),
+        }
+    }
+
+    /// Moves on by `delta`, the time since the last frame
+    pub fn update(&mut self, delta: Duration) {
+        if self.is_settled() {
+            self.value = self.target;
+            self.velocity = 0.0;
+            return;
+        }
+        let mut remaining = delta.as_secs_f32();
+        while remaining > 0.0 {
+            let step = remaining.min(Self::MAX_STEP);
+            let acceleration =
+                self.stiffness * (self.target - self.value) - self.damping * self.velocity;
+            self.velocity += acceleration * step;
+            self.value += self.velocity * step;
+            remaining -= step;
+        }
+    }
+
+    /// Whether the value has come to rest at the target
+    pub fn is_settled(&self) -> bool {
+        (self.target - self.value).abs() < Self::SETTLED && self.velocity.abs() < Self::SETTLED
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_easing() {
+        for easing in [
+            Easing::Linear,
+            Easing::QuadIn,
+            Easing::QuadOut,
+            Easing::QuadInOut,
+            Easing::CubicOut,
+            Easing::BackOut,
+        ] {
+            assert!(easing.apply(0.0).abs() < 1e-6, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6, "{:?}", easing);
+            assert_eq!(easing.apply(2.0), easing.apply(1.0));
+        }
+        assert!(Easing::QuadIn.apply(0.5) < 0.5);
+        assert!(Easing::QuadOut.apply(0.5) > 0.5);
+        assert!(Easing::BackOut.apply(0.8) > 1.0);
+    }
+
+    #[test]
+    fn test_tween() {
+        let frame = Duration::from_millis(50);
+        let mut tween = Tween::new(0.0, Duration::from_millis(200), Easing::Linear);
+        assert!(!tween.is_active());
+        tween.to(100.0);
+        tween.update(frame);
+        assert_eq!(tween.value(), 25.0);
+        // Heading back carries on from where it got to
+        tween.to(0.0);
+        assert_eq!(tween.value(), 25.0);
+        tween.update(frame * 2);
+        assert_eq!(tween.value(), 12.5);
+        tween.update(frame * 10);
+        assert_eq!(tween.value(), 0.0);
+        assert!(!tween.is_active());
+    }
+
+    #[test]
+    fn test_timer_and_spring() {
+        let mut timer = Timer::repeating(Duration::from_millis(100));
+        assert!(!timer.update(Duration::from_millis(60)));
+        assert!(timer.update(Duration::from_millis(60)));
+        assert!((timer.progress() - 0.2).abs() < 1e-3);
+        let mut once = Timer::new(Duration::from_millis(100));
+        assert!(once.update(Duration::from_secs(1)));
+        assert!(once.is_done() && !once.update(Duration::from_secs(1)));
+
+        let mut fixed = FixedStep::new(Duration::from_millis(50));
+        assert_eq!(fixed.update(Duration::from_millis(30)), 0);
+        assert_eq!(fixed.update(Duration::from_millis(80)), 2);
+        assert!((fixed.alpha() - 0.2).abs() < 1e-3);
+        assert_eq!(fixed.update(Duration::from_secs(10)), 10);
+        assert_eq!(fixed.alpha(), 0.0);
+
+        let mut spring = Spring::new(0.0);
+        spring.target = 10.0;
+        for _ in 0..60 {
+            spring.update(Duration::from_millis(16));
+            assert!(spring.value <= 10.0 + Spring::SETTLED);
+        }
+        assert!(spring.is_settled());
+    }
+}
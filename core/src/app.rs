@@ -0,0 +1,1174 @@
+use std::{collections::HashMap, process::Command, str::FromStr};
+
+use enum_iterator::{all, Sequence};
+use enum_map::{Enum, EnumMap};
+use palette::{LinSrgb, Mix};
+use rusttype::{point, Scale};
+
+use crate::achievements::Achievement;
+use crate::audio::{self, Playing, Sound};
+use crate::axes::Axis;
+use crate::config::config;
+use crate::events::{self, Notice};
+use crate::input_macros::InputMacros;
+use crate::profiles::Store;
+use crate::system::{self, Battery};
+use crate::tasks::{Task, Tasks};
+use crate::time::{Duration, Instant};
+
+mod combinators;
+
+pub use combinators::{
+    paused_when, sequence, split_screen, with_overlay, AppSequence, PausedWhen, SplitScreen,
+    WithOverlay,
+};
+
+#[derive(Default, Clone, Copy)]
+pub struct ButtonState {
+    pub(crate) pressed: bool,
+    pub(crate) previous: bool,
+    /// When a held button next repeats
+    pub(crate) repeat_at: Option<Instant>,
+    /// Whether a repeat is due this frame
+    pub(crate) repeating: bool,
+}
+
+impl ButtonState {
+    pub fn pressed(&self) -> bool {
+        self.pressed
+    }
+
+    pub fn just_pressed(&self) -> bool {
+        self.pressed && !self.previous
+    }
+
+    pub fn just_released(&self) -> bool {
+        !self.pressed && self.previous
+    }
+
+    pub fn just_changed(&self) -> bool {
+        self.pressed != self.previous
+    }
+
+    /// True when the button is first pressed, and then now and again while
+    /// it's held, for stepping through lists
+    pub fn repeated(&self) -> bool {
+        self.just_pressed() || (self.pressed && self.repeating)
+    }
+}
+
+/// How a held button repeats, see [`Input::repeated`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repeat {
+    /// From the press to the first repeat
+    pub delay: Duration,
+    /// Between repeats after that
+    pub interval: Duration,
+}
+
+impl Repeat {
+    pub const DEFAULT: Self = Self {
+        delay: Duration::from_millis(400),
+        interval: Duration::from_millis(80),
+    };
+
+    /// [`Repeat::DEFAULT`], slowed down with
+    /// [extra time](crate::config::Config::extra_time) on
+    pub fn standard() -> Self {
+        let config = config();
+        Self {
+            delay: config.timed(Self::DEFAULT.delay),
+            interval: config.timed(Self::DEFAULT.interval),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum, Sequence)]
+pub enum Button {
+    PovDown,
+    PovUp,
+    PovLeft,
+    PovRight,
+    BumperL,
+    BumperR,
+    MenuL,
+    MenuR,
+    ActionH,
+    ActionV,
+    ActionB,
+    ActionA,
+}
+
+/// A touchscreen or mouse
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Pointer {
+    /// In frame coordinates, or `None` if it's off the screen
+    pub(crate) position: Option<(usize, usize)>,
+    pub(crate) pressed: bool,
+    pub(crate) previous: bool,
+    /// Whether it moved while pressed since the last update
+    pub(crate) dragged: bool,
+    /// Where the current press started
+    pub(crate) start: Option<(usize, usize)>,
+}
+
+#[derive(Clone)]
+pub struct Input {
+    pub(crate) buttons: EnumMap<Button, ButtonState>,
+    /// How each button repeats when held, if it does
+    repeat: EnumMap<Button, Option<Repeat>>,
+    pub(crate) pointer: Pointer,
+    /// Uncalibrated axis readings
+    pub(crate) axes: EnumMap<Axis, f32>,
+    /// Whether an axis moved since the last update
+    pub(crate) axes_moved: bool,
+    /// Each controller on its own, for games with a player per controller.
+    /// Everything else is every controller combined.
+    players: Vec<Input>,
+    /// When [`Input::update`] was last called
+    last_update: Option<Instant>,
+    /// See [`Input::delta`]
+    pub(crate) delta: Duration,
+}
+
+/// The longest [`Input::delta`] reported, so animations skip ahead rather
+/// than jumping to the end after the backend slept
+const MAX_DELTA: Duration = Duration::from_millis(100);
+
+impl Default for Input {
+    /// Only the d-pad repeats, at [`Repeat::DEFAULT`]
+    fn default() -> Self {
+        Self::with_repeat(Repeat::DEFAULT)
+    }
+}
+
+impl Input {
+    /// Only the d-pad repeats, at `repeat`. Backends pass
+    /// [`Repeat::standard`], worked out once rather than for every `Input`.
+    pub fn with_repeat(repeat: Repeat) -> Self {
+        Self {
+            buttons: Default::default(),
+            repeat: EnumMap::from_fn(|button| {
+                matches!(
+                    button,
+                    Button::PovUp | Button::PovDown | Button::PovLeft | Button::PovRight
+                )
+                .then_some(repeat)
+            }),
+            pointer: Pointer::default(),
+            axes: Default::default(),
+            axes_moved: false,
+            players: vec![],
+            last_update: None,
+            delta: Duration::ZERO,
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.pointer.previous = self.pointer.pressed;
+        self.pointer.dragged = false;
+        self.axes_moved = false;
+        for player in &mut self.players {
+            player.update();
+        }
+        let now = Instant::now();
+        if let Some(last) = self.last_update {
+            self.delta = now.saturating_duration_since(last).min(MAX_DELTA);
+        }
+        self.last_update = Some(now);
+        for button in all::<Button>() {
+            let state = &mut self.buttons[button];
+            state.previous = state.pressed;
+            state.repeating = false;
+            let Some(repeat) = self.repeat[button].filter(|_| state.pressed) else {
+                state.repeat_at = None;
+                continue;
+            };
+            match state.repeat_at {
+                None => state.repeat_at = Some(now + repeat.delay),
+                Some(at) if now >= at => {
+                    state.repeating = true;
+                    // Don't try to catch up on repeats after a slow frame
+                    state.repeat_at = Some((at + repeat.interval).max(now));
+                }
+                Some(_) => (),
+            }
+        }
+    }
+
+    /// How long it's been since the previous frame, for stepping animations
+    /// (see [`crate::anim`]). Zero on the first frame, and at most a tenth of
+    /// a second after the app has been idle.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Overrides [`Input::delta`] until the next update, for tests that step
+    /// time themselves
+    pub fn set_delta(&mut self, delta: Duration) {
+        self.delta = delta;
+    }
+
+    /// Sets how `button` repeats while held, or stops it repeating
+    pub fn set_repeat(&mut self, button: Button, repeat: Option<Repeat>) {
+        self.repeat[button] = repeat;
+    }
+
+    /// See [`ButtonState::repeated`]
+    pub fn repeated(&self, button: Button) -> bool {
+        self.buttons[button].repeated()
+    }
+
+    /// When the next repeat of a held button is due, so backends can wake up
+    /// for it
+    pub fn next_repeat(&self) -> Option<Instant> {
+        self.buttons
+            .values()
+            .filter(|state| state.pressed)
+            .filter_map(|state| state.repeat_at)
+            .min()
+    }
+
+    /// Whether a repeat is due this frame
+    pub(crate) fn any_repeating(&self) -> bool {
+        self.buttons
+            .values()
+            .any(|state| state.pressed && state.repeating)
+    }
+
+    pub fn event(&mut self, button: Button, value: bool) {
+        self.buttons[button].pressed = value;
+    }
+
+    /// Holds down exactly `buttons`, letting go of the rest, for an app's
+    /// [`App::demo_input`]. Leave a button out while it's held to tap it.
+    pub fn hold_only(&mut self, buttons: &[Button]) {
+        for button in all::<Button>() {
+            self.event(button, buttons.contains(&button));
+        }
+    }
+
+    /// The input from the `index`th controller alone, counting from 0, if
+    /// it's sent anything yet
+    pub fn player(&self, index: usize) -> Option<&Input> {
+        self.players.get(index)
+    }
+
+    /// How many controllers have sent input
+    pub fn players(&self) -> usize {
+        self.players.len()
+    }
+
+    fn player_mut(&mut self, index: usize) -> &mut Input {
+        if self.players.len() <= index {
+            // Players repeat like the combined input
+            let repeat = self.repeat;
+            self.players.resize_with(index + 1, || Input {
+                repeat,
+                ..Input::default()
+            });
+        }
+        &mut self.players[index]
+    }
+
+    /// A button event from the `player`th controller, which counts for the
+    /// combined input too
+    pub fn player_event(&mut self, player: usize, button: Button, value: bool) {
+        self.event(button, value);
+        self.player_mut(player).event(button, value);
+    }
+
+    /// An axis reading from the `player`th controller, see
+    /// [`player_event`](Self::player_event)
+    pub fn player_axis_event(&mut self, player: usize, axis: Axis, raw: f32) {
+        self.axis_event(axis, raw);
+        self.player_mut(player).axis_event(axis, raw);
+    }
+
+    /// A new raw reading from an analog stick or trigger
+    pub fn axis_event(&mut self, axis: Axis, raw: f32) {
+        if self.axes[axis] != raw {
+            self.axes_moved = true;
+        }
+        self.axes[axis] = raw;
+    }
+
+    /// The reading from `axis` as the device reported it, for calibrating
+    pub fn raw_axis(&self, axis: Axis) -> f32 {
+        self.axes[axis]
+    }
+
+    /// The position of `axis` from -1 to 1, or 0 to 1 for triggers, with the
+    /// [calibration](crate::axes::calibration) applied
+    pub fn axis(&self, axis: Axis) -> f32 {
+        crate::axes::calibration().apply(axis, self.axes[axis])
+    }
+
+    pub fn pressed(&self, button: Button) -> bool {
+        self.buttons[button].pressed()
+    }
+
+    pub fn just_pressed(&self, button: Button) -> bool {
+        self.buttons[button].just_pressed()
+    }
+
+    pub fn just_released(&self, button: Button) -> bool {
+        self.buttons[button].just_released()
+    }
+
+    pub fn just_changed(&self, button: Button) -> bool {
+        self.buttons[button].just_changed()
+    }
+
+    pub fn get(&self, button: Button) -> ButtonState {
+        self.buttons[button]
+    }
+
+    /// Whether START was just pressed on its own, for apps using it. With
+    /// SELECT held it's the chord the menu leaves apps with instead.
+    pub fn start_pressed(&self) -> bool {
+        self.just_pressed(Button::MenuR) && !self.pressed(Button::MenuL)
+    }
+
+    /// Whether any button was pressed or released since the last update, the
+    /// pointer was pressed, released or dragged, or an axis moved
+    pub fn any_changed(&self) -> bool {
+        self.buttons.values().any(|state| state.just_changed())
+            || self.pointer.pressed != self.pointer.previous
+            || self.pointer.dragged
+            || self.axes_moved
+    }
+
+    /// Moves the pointer to `x`, `y` in frame coordinates
+    pub fn pointer_moved(&mut self, x: usize, y: usize) {
+        if self.pointer.position != Some((x, y)) && self.pointer.pressed {
+            self.pointer.dragged = true;
+        }
+        self.pointer.position = Some((x, y));
+    }
+
+    /// The pointer went off the screen, which also lets go of it
+    pub fn pointer_left(&mut self) {
+        self.pointer.position = None;
+        self.pointer.pressed = false;
+    }
+
+    /// A finger touched or left the screen, or the mouse button changed
+    pub fn pointer_event(&mut self, pressed: bool) {
+        if pressed && !self.pointer.pressed {
+            self.pointer.start = self.pointer.position;
+        }
+        self.pointer.pressed = pressed && self.pointer.position.is_some();
+    }
+
+    /// Where the pointer is, pressed or not
+    pub fn pointer(&self) -> Option<(usize, usize)> {
+        self.pointer.position
+    }
+
+    /// Where the screen was just touched or clicked
+    pub fn tapped(&self) -> Option<(usize, usize)> {
+        (self.pointer.pressed && !self.pointer.previous)
+            .then_some(self.pointer.start)
+            .flatten()
+    }
+
+    /// Where the pointer was just lifted
+    pub fn released(&self) -> Option<(usize, usize)> {
+        (!self.pointer.pressed && self.pointer.previous)
+            .then_some(self.pointer.position)
+            .flatten()
+    }
+
+    /// Where the current press started and where it is now
+    pub fn dragging(&self) -> Option<((usize, usize), (usize, usize))> {
+        if !self.pointer.pressed {
+            return None;
+        }
+        Some((self.pointer.start?, self.pointer.position?))
+    }
+
+    /// The same input with the pointer's coordinates mapped through `f`, for
+    /// handing to an app drawing into a different frame
+    pub(crate) fn map_pointer(&self, f: impl Fn((usize, usize)) -> Option<(usize, usize)>) -> Self {
+        let mut input = self.clone();
+        input.pointer.position = self.pointer.position.and_then(&f);
+        input.pointer.start = self.pointer.start.and_then(&f);
+        input
+    }
+}
+
+/// Loads a font, falling back to the default one if it's missing so text can
+/// still be measured. The menu shows which assets were missing.
+pub fn load_font(font: &str) -> rusttype::Font<'static> {
+    crate::assets::font(font)
+        .or_else(|_| crate::assets::font(crate::assets::DEFAULT_FONT))
+        .expect("the default font is built in")
+}
+
+pub struct Frame<'a> {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) bytespp: usize,
+    /// Bytes from the start of one row to the next, more than a row's pixels
+    /// in a [sub-frame](Frame::sub) where the rest of the parent's row is in
+    /// between
+    pub(crate) stride: usize,
+    pub data: &'a mut [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// A frame over `data`, with 4 bytes per pixel for RGBX or 2 for RGB565
+    /// like the device's framebuffer
+    pub fn new(width: usize, height: usize, bytespp: usize, data: &'a mut [u8]) -> Self {
+        assert!(bytespp == 4 || bytespp == 2, "Unknown bytespp {}", bytespp);
+        assert_eq!(data.len(), width * height * bytespp);
+        Self {
+            width,
+            height,
+            bytespp,
+            stride: width * bytespp,
+            data,
+        }
+    }
+
+    /// A frame drawing into the `width` x `height` rectangle at `x`, `y` of
+    /// this one, clipped to it, with its own coordinates starting from 0 at
+    /// that corner. Anything drawn off its edges is clipped, so an app
+    /// handed a sub-frame can't draw outside it.
+    pub fn sub(&mut self, x: usize, y: usize, width: usize, height: usize) -> Frame<'_> {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        let width = width.min(self.width - x);
+        let height = height.min(self.height - y);
+        let data = match (width, height) {
+            // Nothing to point into, and the corner can be past the end of a
+            // sub-frame's data
+            (0, _) | (_, 0) => &mut self.data[..0],
+            _ => {
+                let start = y * self.stride + x * self.bytespp;
+                let len = (height - 1) * self.stride + width * self.bytespp;
+                &mut self.data[start..start + len]
+            }
+        };
+        Frame {
+            width,
+            height,
+            bytespp: self.bytespp,
+            stride: self.stride,
+            data,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn bytespp(&self) -> usize {
+        self.bytespp
+    }
+
+    /// See [`Frame::sub`]; the whole of `data` is only this frame's pixels
+    /// when it's `width * bytespp`
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// The pixels of row `y`, for copying whole rows in and out
+    pub fn row(&self, y: usize) -> &[u8] {
+        &self.data[y * self.stride..y * self.stride + self.width * self.bytespp]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [u8] {
+        &mut self.data[y * self.stride..y * self.stride + self.width * self.bytespp]
+    }
+
+    pub fn put_pixel(&mut self, x: usize, y: usize, color: LinSrgb<u8>) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let pixel_offset = y * self.stride + x * self.bytespp;
+        let (encoded, len) = self.encode(color);
+        self.data[pixel_offset..pixel_offset + len].copy_from_slice(&encoded[..len]);
+    }
+
+    /// `color` as the bytes of one pixel, and how many of them to write. The
+    /// fourth byte of RGBX is left alone.
+    fn encode(&self, color: LinSrgb<u8>) -> ([u8; 3], usize) {
+        if self.bytespp == 4 {
+            ([color.red, color.green, color.blue], 3)
+        } else if self.bytespp == 2 {
+            // Format is 5-6-5 (probably)
+            let d = ((color.red as u16 >> 3) << 11)
+                | ((color.green as u16 >> 2) << 5)
+                | (color.blue as u16 >> 3);
+            let [low, high] = d.to_le_bytes();
+            ([low, high, 0], 2)
+        } else {
+            panic!("Unknown bytespp {}", self.bytespp);
+        }
+    }
+
+    /// The color at `x`, `y`, or black if it's off the frame
+    pub fn get_pixel(&self, x: usize, y: usize) -> LinSrgb<u8> {
+        if x >= self.width || y >= self.height {
+            return LinSrgb::new(0, 0, 0);
+        }
+        let pixel_offset = y * self.stride + x * self.bytespp;
+        if self.bytespp == 4 {
+            LinSrgb::new(
+                self.data[pixel_offset],
+                self.data[pixel_offset + 1],
+                self.data[pixel_offset + 2],
+            )
+        } else if self.bytespp == 2 {
+            let d = u16::from_le_bytes([self.data[pixel_offset], self.data[pixel_offset + 1]]);
+            let r = (d >> 11) as u8;
+            let g = ((d >> 5) & 0x3f) as u8;
+            let b = (d & 0x1f) as u8;
+            // Replicate the high bits so white stays white
+            LinSrgb::new(
+                (r << 3) | (r >> 2),
+                (g << 2) | (g >> 4),
+                (b << 3) | (b >> 2),
+            )
+        } else {
+            panic!("Unknown bytespp {}", self.bytespp);
+        }
+    }
+
+    /// Fills a rectangle, clipped to the frame. The color is converted once
+    /// and copied along each row, which matters on the device's RGB565
+    /// framebuffer where games fill a lot of thin spans, e.g. a road a
+    /// scanline at a time.
+    pub fn fill_rect(
+        &mut self,
+        startx: usize,
+        starty: usize,
+        width: usize,
+        height: usize,
+        color: LinSrgb<u8>,
+    ) {
+        let endx = startx.saturating_add(width).min(self.width);
+        let endy = starty.saturating_add(height).min(self.height);
+        if startx >= endx || starty >= endy {
+            return;
+        }
+        let (encoded, len) = self.encode(color);
+        let pixel = &encoded[..len];
+        let stride = self.stride;
+        for y in starty..endy {
+            let row = &mut self.data
+                [y * stride + startx * self.bytespp..y * stride + endx * self.bytespp];
+            for dest in row.chunks_exact_mut(self.bytespp) {
+                dest[..len].copy_from_slice(pixel);
+            }
+        }
+    }
+
+    /// Mixes `color` into the pixel at `x`, `y`, all of it at an `alpha` of 1
+    pub fn blend_pixel(&mut self, x: usize, y: usize, color: LinSrgb<u8>, alpha: f32) {
+        if x >= self.width || y >= self.height || alpha <= 0.0 {
+            return;
+        }
+        let below = self.get_pixel(x, y).into_format::<f32>();
+        let mixed = below.mix(color.into_format(), alpha.min(1.0));
+        self.put_pixel(x, y, mixed.into_format());
+    }
+
+    /// Draws a one pixel wide antialiased line between two points, which
+    /// needn't be on whole pixels, so things moving slowly glide rather than
+    /// step a pixel at a time. Pixel centers are at whole coordinates, and
+    /// anything off the frame is clipped.
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), color: LinSrgb<u8>) {
+        // Xiaolin Wu's: along the longer axis, splitting each step between
+        // the two pixels either side of the line
+        let steep = (to.1 - from.1).abs() > (to.0 - from.0).abs();
+        let (mut start, mut end) = match steep {
+            false => (from, to),
+            true => ((from.1, from.0), (to.1, to.0)),
+        };
+        if start.0 > end.0 {
+            std::mem::swap(&mut start, &mut end);
+        }
+        let run = end.0 - start.0;
+        let gradient = if run < f32::EPSILON {
+            0.0
+        } else {
+            (end.1 - start.1) / run
+        };
+        let limit = match steep {
+            false => self.width,
+            true => self.height,
+        } as f32;
+        let first = start.0.round();
+        let last = end.0.round();
+        let mut column = first.max(-1.0);
+        while column <= last.min(limit) {
+            // The end columns are only partly covered
+            let coverage = if first == last {
+                run.max(1.0)
+            } else if column == first {
+                first + 0.5 - start.0
+            } else if column == last {
+                end.0 - (last - 0.5)
+            } else {
+                1.0
+            };
+            let y = start.1 + gradient * (column - start.0);
+            let (row, fraction) = (y.floor(), y - y.floor());
+            for (row, alpha) in [(row, 1.0 - fraction), (row + 1.0, fraction)] {
+                let (x, y) = match steep {
+                    false => (column, row),
+                    true => (row, column),
+                };
+                if x >= 0.0 && y >= 0.0 {
+                    self.blend_pixel(x as usize, y as usize, color, alpha * coverage);
+                }
+            }
+            column += 1.0;
+        }
+    }
+
+    /// Lines from each of `points` to the next, and from the last back to
+    /// the first
+    pub fn polygon(&mut self, points: &[(f32, f32)], color: LinSrgb<u8>) {
+        for (i, &point) in points.iter().enumerate() {
+            self.line(point, points[(i + 1) % points.len()], color);
+        }
+    }
+
+    /// Draws `s` with the left end of its baseline at `x`, `y`. If the font
+    /// can't be loaded, an outline roughly the size of the text is drawn
+    /// instead so the mistake is visible, and the menu reports the missing
+    /// font.
+    ///
+    /// A `.fnt` font is a [bitmap font](crate::bitmap_font), which is drawn at
+    /// the size it was made and ignores `fontsize`.
+    pub fn text(
+        &mut self,
+        font: &str,
+        x: usize,
+        y: usize,
+        fontsize: f32,
+        color: LinSrgb<u8>,
+        s: &str,
+    ) {
+        if self.text_checked(font, x, y, fontsize, color, s).is_err() {
+            let width = (s.chars().count() as f32 * fontsize * 0.5) as usize;
+            let height = (fontsize * 0.75) as usize;
+            let top = y.saturating_sub(height);
+            self.fill_rect(x, top, width, 1, color);
+            self.fill_rect(x, y, width, 1, color);
+            self.fill_rect(x, top, 1, height, color);
+            self.fill_rect(x + width, top, 1, height + 1, color);
+        }
+    }
+
+    /// Like [`Frame::text`], but returns an error without drawing anything
+    /// if the font can't be loaded
+    pub fn text_checked(
+        &mut self,
+        font: &str,
+        x: usize,
+        y: usize,
+        fontsize: f32,
+        color: LinSrgb<u8>,
+        s: &str,
+    ) -> Result<(), String> {
+        if font.ends_with(".fnt") {
+            crate::assets::bitmap_font(font)?.draw(self, x, y, color, s);
+            return Ok(());
+        }
+        let font = crate::assets::font(font)?;
+        //let fontsize = fontsize;
+        //let pixel_height = fontsize.ceil() as usize;
+
+        let offset = point(x as f32, y as f32);
+        let scale = Scale {
+            x: fontsize,
+            y: fontsize,
+        };
+
+        let glyphs = font.layout(s, scale, offset).collect::<Vec<_>>();
+
+        //self.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+
+        for g in glyphs.iter() {
+            if let Some(bb) = g.pixel_bounding_box() {
+                g.draw(|x, y, v| {
+                    if v <= 0.0 {
+                        return;
+                    }
+                    let x = (x as i32 + bb.min.x) as usize;
+                    let y = (y as i32 + bb.min.y) as usize;
+                    // Blended over what's there, so text can go on panels
+                    let below = self.get_pixel(x, y).into_format::<f32>();
+                    let c = below.mix(color.into_format(), v.min(1.0));
+                    self.put_pixel(x, y, c.into_format());
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn context<'b>(&'b mut self) -> GraphicsContext<'b, 'a> {
+        GraphicsContext {
+            x: 0,
+            y: 0,
+            font: crate::assets::DEFAULT_FONT.to_string(),
+            fontsize: 18.0,
+            color: LinSrgb::new(255, 255, 255),
+            frame: self,
+        }
+    }
+}
+
+pub struct GraphicsContext<'a, 'b> {
+    x: usize,
+    y: usize,
+    font: String,
+    fontsize: f32,
+    color: LinSrgb<u8>,
+    frame: &'a mut Frame<'b>,
+}
+
+impl<'a, 'b> GraphicsContext<'a, 'b> {
+    /// Draws later text in `font`, a TTF or a `.fnt` [bitmap
+    /// font](crate::bitmap_font)
+    pub fn set_font(&mut self, font: &str) {
+        self.font = font.to_string();
+    }
+
+    pub fn set_fontsize(&mut self, fontsize: f32) {
+        self.fontsize = fontsize;
+    }
+
+    pub fn offset(&mut self, dx: i32, dy: i32) {
+        self.x = self.x.saturating_add_signed(dx as isize);
+        self.y = self.y.saturating_add_signed(dy as isize);
+    }
+
+    pub fn set_color(&mut self, color: LinSrgb<u8>) {
+        self.color = color;
+    }
+
+    pub fn text(&mut self, s: &str) {
+        self.frame
+            .text(&self.font, self.x, self.y, self.fontsize, self.color, s);
+    }
+}
+
+/// Named parameters handed to an app when it is built, e.g. from
+/// `--arg difficulty=12` on the command line.
+#[derive(Debug, Default, Clone)]
+pub struct AppArgs {
+    values: HashMap<String, String>,
+}
+
+impl AppArgs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|v| v.as_str())
+    }
+
+    /// Parses the value for `key`, returning `None` if it is missing or malformed
+    pub fn parse<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key).and_then(|v| v.parse().ok())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for AppArgs {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut args = Self::new();
+        for (k, v) in iter {
+            args.insert(k, v);
+        }
+        args
+    }
+}
+
+/// Descriptive information about an app, shown by the launcher
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Manifest {
+    pub version: &'static str,
+    pub author: &'static str,
+    pub description: &'static str,
+    /// What can be unlocked with [`crate::achievements::unlock`]
+    pub achievements: &'static [Achievement],
+    /// Whether [`App::demo_input`] plays it, for the menu's attract mode
+    pub demo: bool,
+}
+
+impl Manifest {
+    pub const fn new(version: &'static str) -> Self {
+        Self {
+            version,
+            author: "",
+            description: "",
+            achievements: &[],
+            demo: false,
+        }
+    }
+
+    pub const fn author(mut self, author: &'static str) -> Self {
+        self.author = author;
+        self
+    }
+
+    pub const fn description(mut self, description: &'static str) -> Self {
+        self.description = description;
+        self
+    }
+
+    pub const fn achievements(mut self, achievements: &'static [Achievement]) -> Self {
+        self.achievements = achievements;
+        self
+    }
+
+    /// Has a demo; see [`crate::demo`]
+    pub const fn demo(mut self) -> Self {
+        self.demo = true;
+        self
+    }
+}
+
+/// How soon an app needs its next [`App::update`], so backends can sleep
+/// instead of redrawing a screen that hasn't changed. A button being pressed or
+/// released always wakes the app, for that frame and the one after.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    /// Update every frame
+    #[default]
+    Busy,
+    /// Nothing changes before this time unless a button changes
+    Idle(Instant),
+    /// Nothing changes until a button changes
+    Waiting,
+}
+
+impl Activity {
+    /// Wakes up by `at` at the latest, e.g. for a held button to repeat
+    pub fn wake_by(self, at: Option<Instant>) -> Self {
+        match (self, at) {
+            (Self::Idle(until), Some(at)) => Self::Idle(until.min(at)),
+            (Self::Waiting, Some(at)) => Self::Idle(at),
+            (activity, _) => activity,
+        }
+    }
+}
+
+/// What an app would like the CPU governor set to while it runs
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PowerHint {
+    /// Whatever the system was using when the launcher started
+    #[default]
+    Default,
+    /// Run flat out, for games that need a steady framerate
+    Performance,
+    /// Run slowly, for tools that mostly sit still
+    Powersave,
+}
+
+/// What an app gets each update: the controls, the frame to draw, and the
+/// framework's services, so new ones can be added without changing every
+/// app. The fields can be borrowed separately, e.g. to draw to `frame` while
+/// spawning on `tasks`.
+pub struct AppContext<'a, 'f> {
+    pub input: &'a Input,
+    pub frame: &'a mut Frame<'f>,
+    /// Background work for the running app, which the menu shuts down when
+    /// the app exits
+    pub tasks: &'a mut Tasks,
+}
+
+impl<'a, 'f> AppContext<'a, 'f> {
+    pub fn new(input: &'a Input, frame: &'a mut Frame<'f>, tasks: &'a mut Tasks) -> Self {
+        Self {
+            input,
+            frame,
+            tasks,
+        }
+    }
+
+    /// The same services with different controls and somewhere else to
+    /// draw, for apps that run other apps
+    pub fn with<'b, 'g>(
+        &'b mut self,
+        input: &'b Input,
+        frame: &'b mut Frame<'g>,
+    ) -> AppContext<'b, 'g> {
+        AppContext {
+            input,
+            frame,
+            tasks: &mut *self.tasks,
+        }
+    }
+
+    /// Time since the last update; see [`Input::delta`]
+    pub fn delta(&self) -> Duration {
+        self.input.delta()
+    }
+
+    /// Runs `work` in the background; see [`crate::tasks`]
+    pub fn spawn<T: Send + 'static>(
+        &mut self,
+        work: impl FnOnce() -> T + Send + 'static,
+    ) -> Task<T> {
+        self.tasks.spawn(work)
+    }
+
+    /// Starts `sound` playing; see [`crate::audio`]
+    pub fn play(&self, sound: &Sound) -> Playing {
+        audio::play(sound)
+    }
+
+    /// The current profile's settings and records for `app`
+    pub fn store(&self, app: &str) -> Store {
+        Store::open(app)
+    }
+
+    /// `app`'s saved input macros in the current profile; see
+    /// [`crate::input_macros`]
+    pub fn input_macros(&self, app: &str) -> InputMacros {
+        InputMacros::open(app)
+    }
+
+    /// Shows `text` in a toast over the app
+    pub fn notify(&self, text: &str) {
+        events::publish(Notice(text.to_string()));
+    }
+
+    /// Hands `event` to its subscribers; see [`crate::events`]
+    pub fn publish<T: Clone + Send + 'static>(&self, event: T) {
+        events::publish(event);
+    }
+
+    pub fn battery(&self) -> Option<Battery> {
+        system::battery()
+    }
+}
+
+pub trait App {
+    fn update(&mut self, ctx: &mut AppContext);
+
+    /// Polled by the backend after every update. Returning a command hands the
+    /// screen and controls over to it until it exits.
+    fn pending_command(&mut self) -> Option<Command> {
+        None
+    }
+
+    /// A lower resolution to draw at, which the backend scales up by the
+    /// largest whole factor that fits the screen. Polled every frame, so apps
+    /// can switch between resolutions.
+    fn resolution(&self) -> Option<(usize, usize)> {
+        None
+    }
+
+    /// Polled by the backend after every update to decide how long it can
+    /// sleep before the next one
+    fn activity(&self) -> Activity {
+        Activity::Busy
+    }
+
+    /// Polled by the device after every update; see [`crate::system::GovernorControl`]
+    fn power_hint(&self) -> PowerHint {
+        PowerHint::Default
+    }
+
+    /// Polled after every update by whatever is running the app. The menu
+    /// goes back to its list, and [`sequence`] on to the next app.
+    fn is_finished(&self) -> bool {
+        false
+    }
+
+    /// Presses and releases buttons on `input` for the app to play itself in
+    /// the menu's attract mode, called before each update of a demo. The
+    /// input carries over from the last update, so buttons stay held until
+    /// they're released. Apps that have this say so in their
+    /// [`Manifest::demo`]; see [`crate::demo`].
+    fn demo_input(&mut self, _input: &mut Input) {}
+
+    /// Called when the player leaves for the menu, just before the app is
+    /// dropped. Games save their progress here; see [`crate::savestate`].
+    fn on_exit(&mut self) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_missing_font() {
+        let mut data = vec![0; 64 * 32 * 4];
+        let mut frame = Frame {
+            width: 64,
+            height: 32,
+            bytespp: 4,
+            stride: 64 * 4,
+            data: &mut data,
+        };
+        let white = LinSrgb::new(255, 255, 255);
+        assert!(frame
+            .text_checked("fonts/Missing.ttf", 4, 24, 16.0, white, "hi")
+            .is_err());
+        assert!(frame.data.iter().all(|&b| b == 0));
+
+        // The placeholder outline still shows up
+        frame.text("fonts/Missing.ttf", 4, 24, 16.0, white, "hi");
+        assert!(frame.data.iter().any(|&b| b != 0));
+    }
+
+    #[test]
+    fn test_fill_rect() {
+        let color = LinSrgb::new(200, 100, 50);
+        for bytespp in [4, 2] {
+            let mut data = vec![0; 8 * 4 * bytespp];
+            let mut frame = Frame::new(8, 4, bytespp, &mut data);
+            // Hanging off the right and bottom
+            frame.fill_rect(5, 2, 10, 10, color);
+            let mut expected = vec![0; 8 * 4 * bytespp];
+            let mut by_pixel = Frame::new(8, 4, bytespp, &mut expected);
+            for (x, y) in (5..8).flat_map(|x| (2..4).map(move |y| (x, y))) {
+                by_pixel.put_pixel(x, y, color);
+            }
+            assert_eq!(data, expected);
+        }
+    }
+
+    #[test]
+    fn test_sub_frame() {
+        let red = LinSrgb::new(255, 0, 0);
+        for bytespp in [4, 2] {
+            let mut data = vec![0; 6 * 4 * bytespp];
+            let mut frame = Frame::new(6, 4, bytespp, &mut data);
+            let mut sub = frame.sub(2, 1, 3, 10);
+            assert_eq!((sub.width(), sub.height()), (3, 3));
+            // Its own coordinates, and clipped to its edges
+            sub.fill_rect(1, 0, 10, 10, red);
+            sub.put_pixel(3, 0, red);
+            assert_eq!(sub.get_pixel(0, 0), LinSrgb::new(0, 0, 0));
+            assert_eq!(sub.row(2).len(), 3 * bytespp);
+
+            let painted: Vec<_> = (0..4)
+                .flat_map(|y| (0..6).map(move |x| (x, y)))
+                .filter(|&(x, y)| frame.get_pixel(x, y) == red)
+                .collect();
+            let expected: Vec<_> = (1..4).flat_map(|y| [(3, y), (4, y)]).collect();
+            assert_eq!(painted, expected);
+
+            // Empty at the bottom right edge of a sub-frame, whose data ends
+            // with its last row
+            let mut sub = frame.sub(1, 1, 3, 2);
+            for (x, y) in [(0, 2), (3, 0), (3, 2)] {
+                let mut empty = sub.sub(x, y, 5, 5);
+                assert_eq!(empty.width() * empty.height(), 0);
+                empty.fill_rect(0, 0, 5, 5, red);
+            }
+        }
+    }
+
+    #[test]
+    fn test_line() {
+        let mut data = vec![0; 16 * 8 * 4];
+        let mut frame = Frame::new(16, 8, 4, &mut data);
+        let white = LinSrgb::new(255, 255, 255);
+        frame.line((2.0, 3.0), (12.0, 3.0), white);
+        assert_eq!(frame.get_pixel(7, 3), white);
+        assert_eq!(frame.get_pixel(7, 4), LinSrgb::new(0, 0, 0));
+        assert_eq!(frame.get_pixel(13, 3), LinSrgb::new(0, 0, 0));
+
+        // Halfway between rows, it's spread over both
+        frame.line((2.0, 5.5), (12.0, 5.5), white);
+        let half = frame.get_pixel(7, 5).red;
+        assert!((120..=135).contains(&half));
+        assert_eq!(frame.get_pixel(7, 6).red, half);
+
+        // Running off the frame, steep or not
+        frame.line((-40.0, -3.0), (40.0, 9.0), white);
+        frame.line((8.0, -100.0), (8.0, 100.0), white);
+        assert_eq!(frame.get_pixel(8, 0).red, 255);
+    }
+
+    #[test]
+    fn test_repeat() {
+        let mut input = Input::default();
+        input.set_repeat(
+            Button::PovDown,
+            Some(Repeat {
+                delay: Duration::from_millis(30),
+                interval: Duration::from_millis(10),
+            }),
+        );
+        input.event(Button::PovDown, true);
+        assert!(input.repeated(Button::PovDown));
+
+        input.update();
+        assert!(!input.repeated(Button::PovDown));
+        let repeat_at = input.next_repeat().unwrap();
+        while Instant::now() < repeat_at {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        input.update();
+        assert!(input.repeated(Button::PovDown));
+
+        // Buttons without a repeat only fire once
+        input.event(Button::ActionA, true);
+        input.update();
+        assert!(!input.repeated(Button::ActionA));
+        input.event(Button::PovDown, false);
+        input.update();
+        assert_eq!(input.next_repeat(), None);
+    }
+
+    #[test]
+    fn test_players() {
+        let mut input = Input::default();
+        input.player_event(1, Button::ActionA, true);
+        assert!(input.pressed(Button::ActionA));
+        assert_eq!(input.players(), 2);
+        assert!(input.player(1).unwrap().just_pressed(Button::ActionA));
+        assert!(!input.player(0).unwrap().pressed(Button::ActionA));
+        input.update();
+        assert!(!input.player(1).unwrap().just_pressed(Button::ActionA));
+    }
+
+    #[test]
+    fn test_pointer() {
+        let mut input = Input::default();
+        input.pointer_moved(10, 20);
+        assert!(!input.any_changed());
+        input.pointer_event(true);
+        assert_eq!(input.tapped(), Some((10, 20)));
+        input.update();
+        assert_eq!(input.tapped(), None);
+
+        input.pointer_moved(30, 20);
+        assert!(input.any_changed());
+        assert_eq!(input.dragging(), Some(((10, 20), (30, 20))));
+        input.pointer_event(false);
+        assert_eq!(input.released(), Some((30, 20)));
+        input.update();
+        assert_eq!(input.released(), None);
+        assert_eq!(input.pointer(), Some((30, 20)));
+
+        // Scaled into a 2x frame with a 10px border
+        let scaled = input.map_pointer(|(x, y)| Some(((x - 10) / 2, (y - 10) / 2)));
+        assert_eq!(scaled.pointer(), Some((10, 5)));
+    }
+}
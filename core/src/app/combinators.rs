@@ -0,0 +1,379 @@
+use palette::LinSrgb;
+
+use super::{Activity, App, AppContext, Input, PowerHint};
+use crate::display::{update_app, Flip};
+
+/// Runs `overlay` after `app` each update, to draw over it
+pub fn with_overlay<A: App, F: FnMut(&mut AppContext)>(app: A, overlay: F) -> WithOverlay<A, F> {
+    WithOverlay { app, overlay }
+}
+
+/// See [`with_overlay`]
+pub struct WithOverlay<A, F> {
+    pub app: A,
+    overlay: F,
+}
+
+impl<A: App, F: FnMut(&mut AppContext)> App for WithOverlay<A, F> {
+    fn update(&mut self, ctx: &mut AppContext) {
+        self.app.update(ctx);
+        (self.overlay)(ctx);
+    }
+
+    fn pending_command(&mut self) -> Option<std::process::Command> {
+        self.app.pending_command()
+    }
+
+    fn resolution(&self) -> Option<(usize, usize)> {
+        self.app.resolution()
+    }
+
+    fn activity(&self) -> Activity {
+        self.app.activity()
+    }
+
+    fn power_hint(&self) -> PowerHint {
+        self.app.power_hint()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.app.is_finished()
+    }
+
+    fn on_exit(&mut self) {
+        self.app.on_exit();
+    }
+}
+
+/// Stops updating `app` while `paused` says so, showing its last frame
+/// instead. Time doesn't pass for it meanwhile, since it's the frame's
+/// [`Input::delta`] it sees when it carries on.
+pub fn paused_when<A: App, P: FnMut(&Input) -> bool>(app: A, paused: P) -> PausedWhen<A, P> {
+    PausedWhen {
+        app,
+        paused,
+        is_paused: false,
+        last_frame: vec![],
+    }
+}
+
+/// See [`paused_when`]
+pub struct PausedWhen<A, P> {
+    pub app: A,
+    paused: P,
+    is_paused: bool,
+    last_frame: Vec<u8>,
+}
+
+impl<A: App, P: FnMut(&Input) -> bool> App for PausedWhen<A, P> {
+    fn update(&mut self, ctx: &mut AppContext) {
+        self.is_paused = (self.paused)(ctx.input);
+        let frame_height = ctx.frame.height();
+        if !self.is_paused {
+            self.app.update(ctx);
+            self.last_frame.clear();
+            for y in 0..frame_height {
+                self.last_frame.extend_from_slice(ctx.frame.row(y));
+            }
+            return;
+        }
+        let row = ctx.frame.width() * ctx.frame.bytespp();
+        if self.last_frame.len() == row * frame_height {
+            for y in 0..frame_height {
+                ctx.frame
+                    .row_mut(y)
+                    .copy_from_slice(&self.last_frame[y * row..(y + 1) * row]);
+            }
+        }
+    }
+
+    fn pending_command(&mut self) -> Option<std::process::Command> {
+        self.app.pending_command()
+    }
+
+    fn resolution(&self) -> Option<(usize, usize)> {
+        self.app.resolution()
+    }
+
+    fn activity(&self) -> Activity {
+        match self.is_paused {
+            true => Activity::Waiting,
+            false => self.app.activity(),
+        }
+    }
+
+    fn power_hint(&self) -> PowerHint {
+        self.app.power_hint()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.app.is_finished()
+    }
+
+    fn on_exit(&mut self) {
+        self.app.on_exit();
+    }
+}
+
+/// Runs each app until it's [finished](App::is_finished), then the next,
+/// e.g. an intro and then the game. It's finished itself when the last one
+/// is.
+pub fn sequence(apps: Vec<Box<dyn App>>) -> AppSequence {
+    AppSequence {
+        apps,
+        current: 0,
+        switched: false,
+    }
+}
+
+/// See [`sequence`]
+pub struct AppSequence {
+    apps: Vec<Box<dyn App>>,
+    current: usize,
+    /// Whether the next app hasn't had its first update yet
+    switched: bool,
+}
+
+impl AppSequence {
+    fn current(&self) -> Option<&dyn App> {
+        self.apps.get(self.current).map(|app| &**app)
+    }
+
+    /// Which app is running, counting from 0
+    pub fn position(&self) -> usize {
+        self.current
+    }
+}
+
+impl App for AppSequence {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let last = self.current + 1 >= self.apps.len();
+        let Some(app) = self.apps.get_mut(self.current) else {
+            return;
+        };
+        app.update(ctx);
+        self.switched = app.is_finished() && !last;
+        if self.switched {
+            app.on_exit();
+            self.current += 1;
+        }
+    }
+
+    fn pending_command(&mut self) -> Option<std::process::Command> {
+        self.apps.get_mut(self.current)?.pending_command()
+    }
+
+    fn resolution(&self) -> Option<(usize, usize)> {
+        self.current()?.resolution()
+    }
+
+    fn activity(&self) -> Activity {
+        match self.current() {
+            Some(_) if self.switched => Activity::Busy,
+            Some(app) => app.activity(),
+            None => Activity::Waiting,
+        }
+    }
+
+    fn power_hint(&self) -> PowerHint {
+        self.current()
+            .map_or_else(Default::default, |app| app.power_hint())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.current().is_none_or(|app| app.is_finished())
+    }
+
+    fn on_exit(&mut self) {
+        if let Some(app) = self.apps.get_mut(self.current) {
+            app.on_exit();
+        }
+    }
+}
+
+/// Runs `left` and `right` side by side, each with its own half of the
+/// screen and its own controller: the first for `left` and the second for
+/// `right`, for local versus games. With one controller, it plays `left`.
+/// Each draws into a [sub-frame](super::Frame::sub) of the screen.
+pub fn split_screen<L: App, R: App>(left: L, right: R) -> SplitScreen<L, R> {
+    SplitScreen {
+        left,
+        right,
+        tabletop: false,
+        scratch: Default::default(),
+    }
+}
+
+/// See [`split_screen`]
+pub struct SplitScreen<L, R> {
+    pub left: L,
+    pub right: R,
+    tabletop: bool,
+    /// Each side's low resolution frame, if it asked for one
+    scratch: [Vec<u8>; 2],
+}
+
+/// Between the halves of a [`split_screen`]
+const DIVIDER: usize = 2;
+
+impl<L, R> SplitScreen<L, R> {
+    /// Stacks the halves instead, with `left` on top turned round, for two
+    /// players facing each other across the device lying flat between them
+    pub fn tabletop(mut self) -> Self {
+        self.tabletop = true;
+        self
+    }
+}
+
+impl<L: App, R: App> App for SplitScreen<L, R> {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (width, height) = (ctx.frame.width(), ctx.frame.height());
+        // Each side's x, y, width and height, and where the divider goes
+        let (areas, divider) = match self.tabletop {
+            false => {
+                let half = width.saturating_sub(DIVIDER) / 2;
+                let areas = [(0, 0, half, height), (half + DIVIDER, 0, half, height)];
+                (areas, (half, 0, width - 2 * half, height))
+            }
+            true => {
+                let half = height.saturating_sub(DIVIDER) / 2;
+                let areas = [(0, 0, width, half), (0, half + DIVIDER, width, half)];
+                (areas, (0, half, width, height - 2 * half))
+            }
+        };
+        let mut quiet = Input::default();
+        quiet.set_delta(ctx.delta());
+        let inputs = [
+            ctx.input.player(0).unwrap_or(ctx.input),
+            ctx.input.player(1).unwrap_or(&quiet),
+        ];
+        let apps: [&mut dyn App; 2] = [&mut self.left, &mut self.right];
+        for (side, app) in apps.into_iter().enumerate() {
+            let (x, y, w, h) = areas[side];
+            let turned = self.tabletop && side == 0;
+            let input = inputs[side].map_pointer(|(px, py)| {
+                let (px, py) = (px.checked_sub(x)?, py.checked_sub(y)?);
+                let inside = px < w && py < h;
+                match turned {
+                    false => inside.then_some((px, py)),
+                    true => inside.then(|| Flip::Rotate180.map_point((w, h), (px, py))),
+                }
+            });
+            let mut frame = ctx.frame.sub(x, y, w, h);
+            let mut side_ctx = AppContext::new(&input, &mut frame, ctx.tasks);
+            update_app(app, &mut side_ctx, &mut self.scratch[side]);
+            if turned {
+                let (bytespp, stride) = (frame.bytespp(), frame.stride());
+                Flip::Rotate180.apply(frame.data, w, h, bytespp, stride);
+            }
+        }
+        let (x, y, w, h) = divider;
+        ctx.frame.fill_rect(x, y, w, h, LinSrgb::new(0, 0, 0));
+    }
+
+    fn pending_command(&mut self) -> Option<std::process::Command> {
+        self.left
+            .pending_command()
+            .or_else(|| self.right.pending_command())
+    }
+
+    fn activity(&self) -> Activity {
+        match (self.left.activity(), self.right.activity()) {
+            (Activity::Busy, _) | (_, Activity::Busy) => Activity::Busy,
+            (Activity::Idle(at), other) | (other, Activity::Idle(at)) => other.wake_by(Some(at)),
+            (Activity::Waiting, Activity::Waiting) => Activity::Waiting,
+        }
+    }
+
+    fn power_hint(&self) -> PowerHint {
+        match (self.left.power_hint(), self.right.power_hint()) {
+            (PowerHint::Performance, _) | (_, PowerHint::Performance) => PowerHint::Performance,
+            (PowerHint::Powersave, PowerHint::Powersave) => PowerHint::Powersave,
+            _ => PowerHint::Default,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.left.is_finished() && self.right.is_finished()
+    }
+
+    fn on_exit(&mut self) {
+        self.left.on_exit();
+        self.right.on_exit();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::{Button, Frame};
+    use crate::tasks::Tasks;
+
+    /// Fills the frame with a shade counting its updates, and finishes on A
+    #[derive(Default)]
+    struct Counter {
+        updates: u8,
+        finished: bool,
+    }
+
+    impl App for Counter {
+        fn update(&mut self, ctx: &mut AppContext) {
+            self.updates += 1;
+            self.finished |= ctx.input.just_pressed(Button::ActionA);
+            let (width, height) = (ctx.frame.width(), ctx.frame.height());
+            let shade = LinSrgb::new(self.updates * 10, 0, 0);
+            ctx.frame.fill_rect(0, 0, width, height, shade);
+        }
+
+        fn is_finished(&self) -> bool {
+            self.finished
+        }
+    }
+
+    fn step(app: &mut dyn App, input: &mut Input, data: &mut [u8]) {
+        let mut frame = Frame::new(10, 4, 4, data);
+        app.update(&mut AppContext::new(input, &mut frame, &mut Tasks::new(1)));
+        input.update();
+    }
+
+    #[test]
+    fn test_combinators() {
+        let mut data = vec![0; 10 * 4 * 4];
+        let mut input = Input::default();
+        let red =
+            |data: &[u8], x: usize| Frame::new(10, 4, 4, &mut data.to_vec()).get_pixel(x, 0).red;
+
+        // Paused, the last frame stays up
+        let mut paused = paused_when(Counter::default(), |input| input.pressed(Button::ActionB));
+        step(&mut paused, &mut input, &mut data);
+        input.event(Button::ActionB, true);
+        data.fill(0);
+        step(&mut paused, &mut input, &mut data);
+        assert_eq!(paused.app.updates, 1);
+        assert_eq!(red(&data, 0), 10);
+        assert_eq!(paused.activity(), Activity::Waiting);
+        input.event(Button::ActionB, false);
+
+        // The next app takes over once the first finishes
+        let mut seq = sequence(vec![
+            Box::new(Counter::default()),
+            Box::new(Counter::default()),
+        ]);
+        step(&mut seq, &mut input, &mut data);
+        input.event(Button::ActionA, true);
+        step(&mut seq, &mut input, &mut data);
+        assert_eq!((seq.position(), seq.is_finished()), (1, false));
+        step(&mut seq, &mut input, &mut data);
+        assert_eq!(red(&data, 0), 10);
+
+        // Each side gets its own controller and half of the screen
+        let mut split = split_screen(Counter::default(), Counter::default());
+        let mut input = Input::default();
+        input.player_event(1, Button::ActionA, true);
+        step(&mut split, &mut input, &mut data);
+        assert!(!split.left.finished && split.right.finished);
+        assert_eq!(red(&data, 3), 10);
+        assert_eq!(red(&data, 4), 0);
+        assert_eq!(red(&data, 6), 10);
+    }
+}
@@ -0,0 +1,242 @@
+//! Fonts, images and data the apps use. Anything in the assets directory on
+//! disk takes precedence over the copy built into the binary, so fonts and
+//! word lists can be added or replaced without rebuilding.
+
+use std::{collections::HashMap, path::PathBuf, sync::Arc, sync::Mutex};
+
+use include_dir::Dir;
+use palette::LinSrgb;
+
+use crate::{app::Frame, bitmap_font::BitmapFont};
+
+#[cfg(feature = "all-fonts")]
+static EMBEDDED: Dir = include_dir::include_dir!("$CARGO_MANIFEST_DIR/assets");
+/// Just the default font, the debug overlay's bitmap font and the themes'
+/// panels, for small builds
+#[cfg(not(feature = "all-fonts"))]
+static EMBEDDED: Dir = Dir::new(
+    "",
+    &[
+        include_dir::DirEntry::File(include_dir::File::new(
+            DEFAULT_FONT,
+            include_bytes!("../assets/fonts/Ubuntu-B.ttf"),
+        )),
+        include_dir::DirEntry::File(include_dir::File::new(
+            "fonts/Ubuntu-B-12.fnt",
+            include_bytes!("../assets/fonts/Ubuntu-B-12.fnt"),
+        )),
+        include_dir::DirEntry::File(include_dir::File::new(
+            "fonts/Ubuntu-B-12.png",
+            include_bytes!("../assets/fonts/Ubuntu-B-12.png"),
+        )),
+        include_dir::DirEntry::File(include_dir::File::new(
+            "ui/panel.9.png",
+            include_bytes!("../assets/ui/panel.9.png"),
+        )),
+        include_dir::DirEntry::File(include_dir::File::new(
+            "ui/panel-light.9.png",
+            include_bytes!("../assets/ui/panel-light.9.png"),
+        )),
+    ],
+);
+
+/// The font used when the one asked for is missing, which is always built in
+pub const DEFAULT_FONT: &str = "fonts/Ubuntu-B.ttf";
+
+pub type Asset = Arc<[u8]>;
+
+struct Cache {
+    /// Overrides the default directory when set
+    dir: Option<PathBuf>,
+    /// Built in by other crates, searched after [`EMBEDDED`]
+    embedded: Vec<&'static Dir<'static>>,
+    /// Missing assets are cached too, so they're only looked for once
+    files: HashMap<String, Result<Asset, String>>,
+    fonts: HashMap<String, rusttype::Font<'static>>,
+    images: HashMap<String, Arc<image::RgbaImage>>,
+    bitmap_fonts: HashMap<String, Arc<BitmapFont>>,
+    /// Assets that weren't found since the last [`take_missing`]
+    missing: Vec<String>,
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+fn with_cache<T>(f: impl FnOnce(&mut Cache) -> T) -> T {
+    let mut cache = CACHE.lock().unwrap();
+    f(cache.get_or_insert_with(|| Cache {
+        dir: None,
+        embedded: vec![],
+        files: HashMap::new(),
+        fonts: HashMap::new(),
+        images: HashMap::new(),
+        bitmap_fonts: HashMap::new(),
+        missing: vec![],
+    }))
+}
+
+/// The directory searched before the built-in assets: `assets/` next to the
+/// launcher binary unless [`set_dir`] was called
+pub fn dir() -> PathBuf {
+    with_cache(|cache| cache.dir.clone())
+        .unwrap_or_else(|| crate::storage::base_dir().join("assets"))
+}
+
+/// Searches `dir` for assets instead, forgetting anything already loaded
+pub fn set_dir(dir: impl Into<PathBuf>) {
+    with_cache(|cache| cache.dir = Some(dir.into()));
+    clear_cache();
+}
+
+/// Builds `dir` in alongside the framework's own fonts, for crates with
+/// assets of their own. Anything the framework has takes precedence.
+pub fn add_embedded(dir: &'static Dir<'static>) {
+    with_cache(|cache| cache.embedded.push(dir));
+    clear_cache();
+}
+
+/// Forgets loaded assets, so changes on disk are picked up
+pub fn clear_cache() {
+    with_cache(|cache| {
+        cache.files.clear();
+        cache.fonts.clear();
+        cache.images.clear();
+        cache.bitmap_fonts.clear();
+    });
+}
+
+/// The contents of the asset at `path`, relative to the assets directory,
+/// e.g. `fonts/Ubuntu-B.ttf`
+pub fn load(path: &str) -> Result<Asset, String> {
+    if let Some(cached) = with_cache(|cache| cache.files.get(path).cloned()) {
+        return cached;
+    }
+
+    let on_disk = dir().join(path);
+    let result = match std::fs::read(&on_disk) {
+        Ok(bytes) => Ok(Asset::from(bytes)),
+        Err(_) => match EMBEDDED.get_file(path).or_else(|| {
+            with_cache(|cache| cache.embedded.iter().find_map(|dir| dir.get_file(path)))
+        }) {
+            Some(file) => Ok(Asset::from(file.contents())),
+            None => Err(format!(
+                "{} isn't in {} or built in",
+                path,
+                on_disk.parent().unwrap_or(&on_disk).display()
+            )),
+        },
+    };
+    if let Err(e) = &result {
+        println!("Missing asset: {}", e);
+    }
+    with_cache(|cache| {
+        if result.is_err() {
+            cache.missing.push(path.to_string());
+        }
+        cache.files.insert(path.to_string(), result.clone());
+    });
+    result
+}
+
+/// Whether there's an asset at `path`, on disk or built in, for ones that
+/// are optional. Unlike [`load`], it's not reported as missing if not.
+pub fn exists(path: &str) -> bool {
+    dir().join(path).is_file()
+        || EMBEDDED.get_file(path).is_some()
+        || with_cache(|cache| {
+            cache
+                .embedded
+                .iter()
+                .any(|dir| dir.get_file(path).is_some())
+        })
+}
+
+/// Loads and parses a font
+pub fn font(path: &str) -> Result<rusttype::Font<'static>, String> {
+    if let Some(font) = with_cache(|cache| cache.fonts.get(path).cloned()) {
+        return Ok(font);
+    }
+    let bytes = load(path)?;
+    let font = rusttype::Font::try_from_vec(bytes.to_vec())
+        .ok_or_else(|| format!("{} isn't a font", path))?;
+    with_cache(|cache| cache.fonts.insert(path.to_string(), font.clone()));
+    Ok(font)
+}
+
+/// Loads and decodes an image, e.g. a PNG
+pub fn image(path: &str) -> Result<Arc<image::RgbaImage>, String> {
+    if let Some(image) = with_cache(|cache| cache.images.get(path).cloned()) {
+        return Ok(image);
+    }
+    let bytes = load(path)?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|e| format!("{} isn't an image: {}", path, e))?
+        .into_rgba8();
+    let image = Arc::new(image);
+    with_cache(|cache| cache.images.insert(path.to_string(), image.clone()));
+    Ok(image)
+}
+
+/// Loads a BMFont `.fnt` file and the page image it names, which is looked
+/// for next to it
+pub fn bitmap_font(path: &str) -> Result<Arc<BitmapFont>, String> {
+    if let Some(font) = with_cache(|cache| cache.bitmap_fonts.get(path).cloned()) {
+        return Ok(font);
+    }
+    let bytes = load(path)?;
+    let descriptor = std::str::from_utf8(&bytes).map_err(|e| format!("{}: {}", path, e))?;
+    let font = BitmapFont::parse(descriptor, |file| {
+        image(&match path.rsplit_once('/') {
+            Some((dir, _)) => format!("{}/{}", dir, file),
+            None => file.to_string(),
+        })
+    })
+    .map_err(|e| format!("{} isn't a bitmap font: {}", path, e))?;
+    let font = Arc::new(font);
+    with_cache(|cache| cache.bitmap_fonts.insert(path.to_string(), font.clone()));
+    Ok(font)
+}
+
+/// Assets that couldn't be found since the last call, for showing an error
+pub fn take_missing() -> Vec<String> {
+    with_cache(|cache| std::mem::take(&mut cache.missing))
+}
+
+/// Replaces the frame with a list of assets that couldn't be loaded
+pub fn draw_error(frame: &mut Frame, missing: &[String]) {
+    frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(90, 0, 0));
+    let mut ctx = frame.context();
+    ctx.offset(30, 50);
+    ctx.set_fontsize(32.0);
+    ctx.text("Missing assets");
+    ctx.set_fontsize(18.0);
+    ctx.offset(0, 20);
+    for path in missing {
+        ctx.offset(0, 25);
+        ctx.text(path);
+    }
+    ctx.offset(0, 40);
+    ctx.text(&format!("Put them in {}", dir().display()));
+    ctx.offset(0, 25);
+    ctx.text("Press confirm to carry on without them");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_missing_asset() {
+        assert!(font(DEFAULT_FONT).is_ok());
+        assert!(load("fonts/Nope.ttf").is_err());
+        // Only reported the first time it's looked for
+        assert!(load("fonts/Nope.ttf").is_err());
+        let missing = take_missing();
+        assert_eq!(
+            missing
+                .iter()
+                .filter(|path| *path == "fonts/Nope.ttf")
+                .count(),
+            1
+        );
+    }
+}
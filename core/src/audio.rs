@@ -0,0 +1,359 @@
+//! Sound: a mixer apps [`play`] sounds into, and outputs that pull mixed
+//! samples from it. The outputs report how much they have queued up, so
+//! [`Playing::position`] says what's being heard right now rather than what
+//! was last mixed, which is what games need to line pictures up with sound.
+//!
+//! The SDL runner and the device (through OSS, `/dev/dsp`) have outputs. The
+//! winit simulator and the browser don't yet, so sounds there are silent and
+//! have no position, and apps should fall back to the clock.
+
+use std::sync::{Arc, Mutex};
+
+use crate::time::{Duration, Instant};
+
+/// Samples a second. Everything is mono.
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// Mono 16-bit samples at [`SAMPLE_RATE`], cheap to clone
+#[derive(Debug, Clone, Default)]
+pub struct Sound {
+    samples: Arc<[i16]>,
+}
+
+impl Sound {
+    pub fn new(samples: Vec<i16>) -> Self {
+        Self {
+            samples: samples.into(),
+        }
+    }
+
+    /// A sine wave at `frequency` for `length`, at `volume` from 0 to 1,
+    /// faded in and out over a few milliseconds so it doesn't click
+    pub fn tone(frequency: f32, length: Duration, volume: f32) -> Self {
+        let count = samples_in(length);
+        let fade = samples_in(Duration::from_millis(5)).min(count / 2).max(1);
+        let samples = (0..count)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let envelope = (i.min(count - 1 - i) as f32 / fade as f32).min(1.0);
+                let value = (t * frequency * std::f32::consts::TAU).sin();
+                (value * envelope * volume.clamp(0.0, 1.0) * i16::MAX as f32) as i16
+            })
+            .collect();
+        Self::new(samples)
+    }
+
+    /// Reads a WAV file of 8 or 16-bit PCM, mixing stereo down to mono and
+    /// resampling to [`SAMPLE_RATE`] if it needs it
+    pub fn from_wav(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err("not a WAV file".to_string());
+        }
+        let mut format = None;
+        let mut data = None;
+        let mut at = 12;
+        while at + 8 <= bytes.len() {
+            let id = &bytes[at..at + 4];
+            let size = u32::from_le_bytes(bytes[at + 4..at + 8].try_into().unwrap()) as usize;
+            let body = bytes
+                .get(at + 8..at + 8 + size)
+                .ok_or("the WAV file is cut short")?;
+            match id {
+                b"fmt " if size >= 16 => {
+                    let word = |i: usize| u16::from_le_bytes([body[i], body[i + 1]]);
+                    let rate = u32::from_le_bytes(body[4..8].try_into().unwrap());
+                    format = Some((word(0), word(2) as usize, rate, word(14)));
+                }
+                b"data" => data = Some(body),
+                _ => (),
+            }
+            // Chunks are padded to an even length
+            at += 8 + size + size % 2;
+        }
+        let (encoding, channels, rate, bits) = format.ok_or("the WAV file has no format")?;
+        let data = data.ok_or("the WAV file has no samples")?;
+        if encoding != 1 || channels == 0 || rate == 0 {
+            return Err("only uncompressed WAV files can be played".to_string());
+        }
+        let frames: Vec<i16> = match bits {
+            16 => data
+                .chunks_exact(2 * channels)
+                .map(|frame| {
+                    let sum: i32 = frame
+                        .chunks_exact(2)
+                        .map(|s| i16::from_le_bytes([s[0], s[1]]) as i32)
+                        .sum();
+                    (sum / channels as i32) as i16
+                })
+                .collect(),
+            8 => data
+                .chunks_exact(channels)
+                .map(|frame| {
+                    let sum: i32 = frame.iter().map(|&s| (s as i32 - 128) << 8).sum();
+                    (sum / channels as i32) as i16
+                })
+                .collect(),
+            _ => return Err(format!("{}-bit WAV files can't be played", bits)),
+        };
+        if rate == SAMPLE_RATE {
+            return Ok(Self::new(frames));
+        }
+        // Nearest sample, which is rough but fine for music played back on a
+        // handheld's speaker
+        let count = (frames.len() as u64 * SAMPLE_RATE as u64 / rate as u64) as usize;
+        let resampled = (0..count)
+            .map(|i| frames[(i as u64 * rate as u64 / SAMPLE_RATE as u64) as usize])
+            .collect();
+        Ok(Self::new(resampled))
+    }
+
+    /// Sounds mixed together, each starting at its time, for building up
+    /// music out of notes
+    pub fn arrange<'a>(parts: impl IntoIterator<Item = (Duration, &'a Sound)>) -> Self {
+        let mut samples: Vec<i16> = vec![];
+        for (at, sound) in parts {
+            let start = samples_in(at);
+            if samples.len() < start + sound.samples.len() {
+                samples.resize(start + sound.samples.len(), 0);
+            }
+            for (sample, &add) in samples[start..].iter_mut().zip(sound.samples.iter()) {
+                *sample = sample.saturating_add(add);
+            }
+        }
+        Self::new(samples)
+    }
+
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    pub fn duration(&self) -> Duration {
+        duration_of(self.samples.len() as u64)
+    }
+}
+
+fn samples_in(duration: Duration) -> usize {
+    (duration.as_secs_f64() * SAMPLE_RATE as f64).round() as usize
+}
+
+fn duration_of(samples: u64) -> Duration {
+    Duration::from_secs_f64(samples as f64 / SAMPLE_RATE as f64)
+}
+
+struct Voice {
+    id: u64,
+    sound: Sound,
+    /// The next sample to mix
+    next: usize,
+    /// The mixer's count of samples when it was first mixed
+    started: Option<u64>,
+}
+
+#[derive(Default)]
+struct Mixer {
+    voices: Vec<Voice>,
+    next_id: u64,
+    /// Samples mixed since the output started
+    mixed: u64,
+    /// When the output last took samples, and how many from before them
+    /// were still waiting to be heard then. `None` without an output.
+    last_mix: Option<(Instant, u64)>,
+}
+
+impl Mixer {
+    /// The sample being heard now, counted like `mixed`
+    fn heard(&self) -> Option<u64> {
+        let (at, queued) = self.last_mix?;
+        let since = (at.elapsed().as_secs_f64() * SAMPLE_RATE as f64) as u64;
+        Some((self.mixed.saturating_sub(queued) + since).min(self.mixed))
+    }
+}
+
+static MIXER: Mutex<Option<Mixer>> = Mutex::new(None);
+
+fn with_mixer<T>(f: impl FnOnce(&mut Mixer) -> T) -> T {
+    f(MIXER.lock().unwrap().get_or_insert_with(Mixer::default))
+}
+
+/// A sound started with [`play`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Playing(u64);
+
+impl Playing {
+    pub fn stop(self) {
+        with_mixer(|mixer| mixer.voices.retain(|voice| voice.id != self.0));
+    }
+
+    /// Whether it's still to be mixed, which it is until it ends or is
+    /// stopped, even without an output
+    pub fn is_playing(self) -> bool {
+        with_mixer(|mixer| mixer.voices.iter().any(|voice| voice.id == self.0))
+    }
+
+    /// How far into the sound the speaker is, allowing for what the output
+    /// has queued up. `None` without an output, or before any of it has
+    /// been heard.
+    pub fn position(self) -> Option<Duration> {
+        with_mixer(|mixer| {
+            let voice = mixer.voices.iter().find(|voice| voice.id == self.0)?;
+            let heard = mixer.heard()?;
+            let into = heard.checked_sub(voice.started?)?;
+            Some(duration_of(into))
+        })
+    }
+}
+
+/// Starts `sound` playing, on top of anything else
+pub fn play(sound: &Sound) -> Playing {
+    with_mixer(|mixer| {
+        let id = mixer.next_id;
+        mixer.next_id += 1;
+        mixer.voices.push(Voice {
+            id,
+            sound: sound.clone(),
+            next: 0,
+            started: None,
+        });
+        Playing(id)
+    })
+}
+
+/// Stops every sound, e.g. on leaving an app
+pub fn stop_all() {
+    with_mixer(|mixer| mixer.voices.clear());
+}
+
+/// Whether an output is taking samples, so sounds will be heard
+pub fn has_output() -> bool {
+    with_mixer(|mixer| mixer.last_mix.is_some())
+}
+
+/// Fills `out` with the next samples of everything playing, for an output
+/// to send on. `queued` is how many samples it's still to play from before
+/// these, as best it knows.
+pub fn mix(out: &mut [i16], queued: usize) {
+    with_mixer(|mixer| {
+        let mut sums = vec![0i32; out.len()];
+        for voice in &mut mixer.voices {
+            voice.started.get_or_insert(mixer.mixed);
+            let remaining = &voice.sound.samples[voice.next..];
+            for (sum, &sample) in sums.iter_mut().zip(remaining) {
+                *sum += sample as i32;
+            }
+            voice.next += remaining.len().min(out.len());
+        }
+        mixer
+            .voices
+            .retain(|voice| voice.next < voice.sound.samples.len());
+        for (sample, sum) in out.iter_mut().zip(sums) {
+            *sample = sum.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        }
+        mixer.last_mix = Some((Instant::now(), queued as u64));
+        mixer.mixed += out.len() as u64;
+    });
+}
+
+/// Plays through OSS's `/dev/dsp` on a thread of its own, for the device
+#[cfg(target_os = "linux")]
+pub fn start_oss() -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::fd::AsRawFd;
+
+    // From linux/soundcard.h
+    const SNDCTL_DSP_SPEED: libc::c_ulong = 0xc004_5002;
+    const SNDCTL_DSP_SETFMT: libc::c_ulong = 0xc004_5005;
+    const SNDCTL_DSP_CHANNELS: libc::c_ulong = 0xc004_5006;
+    const SNDCTL_DSP_GETODELAY: libc::c_ulong = 0x4004_5017;
+    const AFMT_S16_LE: libc::c_int = 0x10;
+
+    let mut dsp = std::fs::OpenOptions::new().write(true).open("/dev/dsp")?;
+    let fd = dsp.as_raw_fd();
+    for (request, value) in [
+        (SNDCTL_DSP_SETFMT, AFMT_S16_LE),
+        (SNDCTL_DSP_CHANNELS, 1),
+        (SNDCTL_DSP_SPEED, SAMPLE_RATE as libc::c_int),
+    ] {
+        let mut value = value;
+        // SAFETY: each request reads and writes one int
+        if unsafe { libc::ioctl(fd, request as _, &mut value) } < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    std::thread::spawn(move || {
+        // About 12ms at a time
+        let mut samples = [0i16; 512];
+        let mut bytes = [0u8; 1024];
+        loop {
+            let mut delay: libc::c_int = 0;
+            // SAFETY: reads one int. If it fails, the delay is taken as none.
+            unsafe { libc::ioctl(fd, SNDCTL_DSP_GETODELAY as _, &mut delay) };
+            mix(&mut samples, delay.max(0) as usize / 2);
+            for (pair, sample) in bytes.chunks_exact_mut(2).zip(samples) {
+                pair.copy_from_slice(&sample.to_le_bytes());
+            }
+            if let Err(e) = dsp.write_all(&bytes) {
+                println!("Stopped playing sound: {}", e);
+                with_mixer(|mixer| mixer.last_mix = None);
+                return;
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mix() {
+        let a = play(&Sound::new(vec![100; 4]));
+        let b = play(&Sound::new(vec![i16::MAX; 2]));
+        // Nothing's been heard without an output
+        assert_eq!(a.position(), None);
+
+        let mut out = [0; 3];
+        mix(&mut out, 0);
+        assert_eq!(out, [i16::MAX, i16::MAX, 100]);
+        assert!(a.is_playing() && !b.is_playing());
+        assert!(has_output());
+        // What's been mixed has all been heard by now, with nothing queued
+        assert_eq!(a.position(), Some(duration_of(3)));
+
+        mix(&mut out, 3);
+        assert_eq!(out, [100, 0, 0]);
+        assert!(!a.is_playing());
+        stop_all();
+    }
+
+    #[test]
+    fn test_wav() {
+        // Stereo 16-bit at half the rate, so each frame becomes two samples
+        let mut wav = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+        wav.extend(16u32.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(2u16.to_le_bytes());
+        wav.extend((SAMPLE_RATE / 2).to_le_bytes());
+        wav.extend((SAMPLE_RATE * 2).to_le_bytes());
+        wav.extend(4u16.to_le_bytes());
+        wav.extend(16u16.to_le_bytes());
+        wav.extend(b"data");
+        wav.extend(8u32.to_le_bytes());
+        for sample in [100i16, 300, -50, -50] {
+            wav.extend(sample.to_le_bytes());
+        }
+        let sound = Sound::from_wav(&wav).unwrap();
+        assert_eq!(sound.samples(), [200, 200, -50, -50]);
+        assert!(Sound::from_wav(b"RIFF").is_err());
+
+        let tone = Sound::tone(440.0, Duration::from_millis(10), 0.5);
+        assert_eq!(tone.samples().len(), 441);
+        let music = Sound::arrange([
+            (Duration::ZERO, &tone),
+            (Duration::from_millis(20), &sound),
+            (Duration::ZERO, &sound),
+        ]);
+        assert_eq!(music.samples().len(), 882 + 4);
+        assert_eq!(music.samples()[0], 200);
+    }
+}
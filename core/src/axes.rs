@@ -0,0 +1,231 @@
+//! Analog sticks and triggers, and the calibration that turns their raw
+//! readings into something apps can use.
+//!
+//! Calibration is kept in `axes.txt` next to the launcher binary and can be
+//! recorded from Settings > Calibration, e.g.
+//!
+//! ```text
+//! deadzone = 0.15
+//! curve = 1.5
+//! # min center max
+//! left_x = -0.92 0.03 0.95
+//! ```
+
+use std::{io, path::PathBuf, str::FromStr, sync::Mutex};
+
+use enum_iterator::{all, Sequence};
+use enum_map::{Enum, EnumMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum, Sequence)]
+pub enum Axis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    TriggerL,
+    TriggerR,
+}
+
+impl Axis {
+    /// The name used in `axes.txt`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::LeftX => "left_x",
+            Self::LeftY => "left_y",
+            Self::RightX => "right_x",
+            Self::RightY => "right_y",
+            Self::TriggerL => "trigger_l",
+            Self::TriggerR => "trigger_r",
+        }
+    }
+}
+
+impl FromStr for Axis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        all::<Axis>()
+            .find(|axis| axis.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown axis `{}`", s))
+    }
+}
+
+/// The raw readings at an axis's limits and at rest. Triggers rest at one end,
+/// so their center is the same as their minimum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub min: f32,
+    pub center: f32,
+    pub max: f32,
+}
+
+impl Default for Range {
+    fn default() -> Self {
+        Self {
+            min: -1.0,
+            center: 0.0,
+            max: 1.0,
+        }
+    }
+}
+
+impl Range {
+    /// `raw` scaled so the center is 0 and the limits are -1 and 1
+    fn normalize(&self, raw: f32) -> f32 {
+        let value = if raw >= self.center {
+            (raw - self.center) / (self.max - self.center).max(f32::EPSILON)
+        } else {
+            (raw - self.center) / (self.center - self.min).max(f32::EPSILON)
+        };
+        value.clamp(-1.0, 1.0)
+    }
+}
+
+/// How raw axis readings are turned into values for apps
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub ranges: EnumMap<Axis, Range>,
+    /// How far from the center an axis has to move before it reads as
+    /// anything but 0, as a fraction of its travel
+    pub deadzone: f32,
+    /// The response curve's exponent: 1 is linear, higher gives finer
+    /// control near the center
+    pub curve: f32,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            ranges: EnumMap::from_fn(|axis| match axis {
+                Axis::TriggerL | Axis::TriggerR => Range {
+                    min: 0.0,
+                    center: 0.0,
+                    max: 1.0,
+                },
+                _ => Range::default(),
+            }),
+            deadzone: 0.1,
+            curve: 1.0,
+        }
+    }
+}
+
+impl Calibration {
+    /// A raw reading from `axis` between -1 and 1, or 0 and 1 for triggers,
+    /// with the dead zone and response curve applied
+    pub fn apply(&self, axis: Axis, raw: f32) -> f32 {
+        let value = self.ranges[axis].normalize(raw);
+        let magnitude = value.abs();
+        if magnitude <= self.deadzone {
+            return 0.0;
+        }
+        let scaled = (magnitude - self.deadzone) / (1.0 - self.deadzone).max(f32::EPSILON);
+        scaled.powf(self.curve).copysign(value)
+    }
+
+    /// Applies the lines of `axes.txt`, logging any it doesn't understand
+    pub fn apply_config(&mut self, text: &str) {
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Err(e) = self.apply_line(line) {
+                println!("Ignoring `{}` in axes: {}", line, e);
+            }
+        }
+    }
+
+    fn apply_line(&mut self, line: &str) -> Result<(), String> {
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| "expected name = value".to_string())?;
+        let (key, value) = (key.trim(), value.trim());
+        let parse = |v: &str| v.parse::<f32>().map_err(|e| e.to_string());
+        match key {
+            "deadzone" => self.deadzone = parse(value)?.clamp(0.0, 0.9),
+            "curve" => self.curve = parse(value)?.max(0.1),
+            _ => {
+                let axis = key.parse::<Axis>()?;
+                let values = value
+                    .split_whitespace()
+                    .map(parse)
+                    .collect::<Result<Vec<_>, _>>()?;
+                let [min, center, max] = values[..] else {
+                    return Err("expected min center max".to_string());
+                };
+                if !(min <= center && center <= max && min < max) {
+                    return Err("expected min <= center <= max".to_string());
+                }
+                self.ranges[axis] = Range { min, center, max };
+            }
+        }
+        Ok(())
+    }
+
+    /// The contents of `axes.txt` for this calibration
+    pub fn to_config(&self) -> String {
+        let mut text = format!("deadzone = {}\ncurve = {}\n", self.deadzone, self.curve);
+        for (axis, range) in self.ranges.iter() {
+            text += &format!(
+                "{} = {} {} {}\n",
+                axis.name(),
+                range.min,
+                range.center,
+                range.max
+            );
+        }
+        text
+    }
+}
+
+fn config_path() -> PathBuf {
+    crate::storage::base_dir().join("axes.txt")
+}
+
+/// Loaded once, then kept up to date by [`set_calibration`]
+static CALIBRATION: Mutex<Option<Calibration>> = Mutex::new(None);
+
+/// The calibration from `axes.txt`, or the defaults
+pub fn calibration() -> Calibration {
+    CALIBRATION
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| {
+            let mut calibration = Calibration::default();
+            if let Ok(text) = std::fs::read_to_string(config_path()) {
+                calibration.apply_config(&text);
+            }
+            calibration
+        })
+        .clone()
+}
+
+/// Uses `calibration` from now on and saves it to `axes.txt`
+pub fn set_calibration(calibration: Calibration) -> io::Result<()> {
+    let text = calibration.to_config();
+    *CALIBRATION.lock().unwrap() = Some(calibration);
+    std::fs::write(config_path(), text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_calibration() {
+        let mut calibration = Calibration::default();
+        calibration.apply_config("deadzone = 0.2\nleft_x = -0.5 0.1 0.9\nleft_y = 1 0 2\n");
+        assert_eq!(calibration.apply(Axis::LeftX, 0.15), 0.0);
+        assert_eq!(calibration.apply(Axis::LeftX, 0.9), 1.0);
+        assert_eq!(calibration.apply(Axis::LeftX, -2.0), -1.0);
+        assert!((calibration.apply(Axis::LeftX, 0.5) - 0.375).abs() < 1e-5);
+        // The bad range was ignored
+        assert_eq!(calibration.ranges[Axis::LeftY], Range::default());
+        assert_eq!(calibration.apply(Axis::TriggerL, 0.0), 0.0);
+
+        let mut reloaded = Calibration::default();
+        reloaded.apply_config(&calibration.to_config());
+        assert_eq!(reloaded, calibration);
+    }
+}
@@ -0,0 +1,92 @@
+//! Traits for backends that can be driven by a plain polling loop, so a new
+//! output or input library only has to provide pixels and events.
+
+use crate::{
+    app::{Activity, App, AppContext, Frame, Input, Repeat},
+    tasks::Tasks,
+    time::{Duration, Instant},
+};
+
+/// Somewhere to show finished frames
+pub trait Display {
+    /// Width and height in pixels
+    fn size(&self) -> (usize, usize);
+
+    /// Bytes per pixel of the frames handed to [`Display::present`]
+    fn bytespp(&self) -> usize {
+        4
+    }
+
+    fn present(&mut self, data: &[u8]);
+}
+
+/// Somewhere button presses come from
+pub trait InputSource {
+    /// Applies any pending events to `input`. Returns false once the user has
+    /// asked to quit.
+    fn poll(&mut self, input: &mut Input) -> bool;
+}
+
+/// Runs `app` at roughly 60fps until the backend reports a quit. Frames are
+/// skipped while the app reports itself [idle](App::activity).
+pub fn run<B: Display + InputSource>(mut app: impl App, backend: &mut B) {
+    let (width, height) = backend.size();
+    let bytespp = backend.bytespp();
+    let mut frame_data = vec![0; width * height * bytespp];
+    let mut scaled_data = vec![];
+    let mut tasks = Tasks::default();
+    let mut input = Input::with_repeat(Repeat::standard());
+    let mut activity = Activity::Busy;
+    let mut input_changed = false;
+
+    loop {
+        let frame_start = Instant::now();
+
+        input.update();
+        if !backend.poll(&mut input) {
+            break;
+        }
+        #[cfg(feature = "remote")]
+        crate::remote::apply(&mut input);
+
+        // Buttons settling after a change always get one more frame
+        let due = input_changed
+            || input.any_changed()
+            || input.any_repeating()
+            || match activity {
+                Activity::Busy => true,
+                Activity::Idle(until) => frame_start >= until,
+                Activity::Waiting => false,
+            };
+        input_changed = input.any_changed();
+
+        if due {
+            let mut frame = Frame {
+                width,
+                height,
+                bytespp,
+                stride: width * bytespp,
+                data: &mut frame_data,
+            };
+            let mut ctx = AppContext::new(&input, &mut frame, &mut tasks);
+            crate::display::update_app(&mut app, &mut ctx, &mut scaled_data);
+            backend.present(&frame_data);
+
+            if let Some(mut command) = app.pending_command() {
+                println!("Running {:?}", command);
+                match command.status() {
+                    Ok(status) => println!("{:?} exited with {}", command, status),
+                    Err(e) => println!("Couldn't run {:?}: {}", command, e),
+                }
+                input = Input::with_repeat(Repeat::standard());
+                input_changed = true;
+            }
+            activity = app.activity().wake_by(input.next_repeat());
+        }
+
+        let frame_time = Duration::from_millis(16);
+        if let Some(remaining) = frame_time.checked_sub(frame_start.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Fonts drawn from prerendered glyphs instead of rasterized from a TTF each
+//! time, which is sharper at small sizes and much faster. Fonts are in the
+//! text format of AngelCode's BMFont, which most bitmap font tools can
+//! export: a `.fnt` file describing where each glyph is on a PNG.
+//!
+//! Pass a `.fnt` path anywhere a font is asked for, e.g. to [`Frame::text`]
+//! or [`GraphicsContext::set_font`](crate::app::GraphicsContext::set_font).
+//! The font size is ignored, since each file is one size; `Ubuntu-B-12.fnt`
+//! and `Ubuntu-B-16.fnt` are built in.
+
+use std::{collections::HashMap, sync::Arc};
+
+use image::RgbaImage;
+use palette::{LinSrgb, Mix};
+
+use crate::app::Frame;
+
+/// Where a glyph is on the page and how it's placed, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Glyph {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    /// From the pen position to the glyph's left edge
+    xoffset: i32,
+    /// From the top of the line to the glyph's top edge
+    yoffset: i32,
+    /// How far the pen moves on afterwards
+    xadvance: i32,
+}
+
+pub struct BitmapFont {
+    line_height: usize,
+    /// From the top of the line to the baseline
+    base: usize,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), i32>,
+    /// The glyphs, in white with coverage in the alpha channel
+    page: Arc<RgbaImage>,
+}
+
+/// The `key=value` pairs on one line of a `.fnt` file, where values may be
+/// quoted
+fn fields(line: &str) -> HashMap<&str, &str> {
+    let mut fields = HashMap::new();
+    let mut rest = line;
+    while let Some(equals) = rest.find('=') {
+        let key = rest[..equals].trim();
+        let after = &rest[equals + 1..];
+        let (value, next) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+            }
+            None => {
+                let end = after.find(' ').unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        fields.insert(key, value);
+        rest = next;
+    }
+    fields
+}
+
+impl BitmapFont {
+    /// Parses a BMFont text descriptor, loading its page with `load_page`.
+    /// Only single page fonts are supported.
+    pub fn parse(
+        descriptor: &str,
+        load_page: impl FnOnce(&str) -> Result<Arc<RgbaImage>, String>,
+    ) -> Result<Self, String> {
+        let mut line_height = None;
+        let mut base = 0;
+        let mut page_file = None;
+        let mut glyphs = HashMap::new();
+        let mut kerning = HashMap::new();
+
+        for (number, line) in descriptor.lines().enumerate() {
+            let (tag, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let fields = fields(rest);
+            let int = |key: &str| -> Result<i32, String> {
+                fields
+                    .get(key)
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| format!("line {}: missing or bad `{}`", number + 1, key))
+            };
+            let char = |key: &str| -> Result<char, String> {
+                char::from_u32(int(key)? as u32)
+                    .ok_or_else(|| format!("line {}: bad character", number + 1))
+            };
+            match tag {
+                "common" => {
+                    line_height = Some(int("lineHeight")? as usize);
+                    base = int("base")? as usize;
+                    if int("pages")? != 1 {
+                        return Err("only fonts with one page are supported".to_string());
+                    }
+                }
+                "page" => {
+                    page_file = fields.get("file").map(|file| file.to_string());
+                }
+                "char" => {
+                    glyphs.insert(
+                        char("id")?,
+                        Glyph {
+                            x: int("x")? as u32,
+                            y: int("y")? as u32,
+                            width: int("width")? as u32,
+                            height: int("height")? as u32,
+                            xoffset: int("xoffset")?,
+                            yoffset: int("yoffset")?,
+                            xadvance: int("xadvance")?,
+                        },
+                    );
+                }
+                "kerning" => {
+                    kerning.insert((char("first")?, char("second")?), int("amount")?);
+                }
+                _ => (),
+            }
+        }
+
+        let line_height = line_height.ok_or("no `common` line")?;
+        let page = load_page(&page_file.ok_or("no `page` line")?)?;
+        for (c, glyph) in &glyphs {
+            if glyph.x + glyph.width > page.width() || glyph.y + glyph.height > page.height() {
+                return Err(format!("glyph for {:?} is off the page", c));
+            }
+        }
+        Ok(Self {
+            line_height,
+            base,
+            glyphs,
+            kerning,
+            page,
+        })
+    }
+
+    /// How far apart lines of text should be
+    pub fn line_height(&self) -> usize {
+        self.line_height
+    }
+
+    /// From the top of a line to the baseline
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    /// The glyph for `c`, or `?` if the font doesn't have it
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?'))
+    }
+
+    /// Each glyph of `s` with the pen position it's drawn at
+    fn layout<'a>(&'a self, s: &'a str) -> impl Iterator<Item = (i32, &'a Glyph)> + 'a {
+        let mut pen = 0;
+        let mut previous = None;
+        s.chars().filter_map(move |c| {
+            let glyph = self.glyph(c)?;
+            if let Some(previous) = previous {
+                pen += self.kerning.get(&(previous, c)).copied().unwrap_or(0);
+            }
+            previous = Some(c);
+            let at = pen;
+            pen += glyph.xadvance;
+            Some((at, glyph))
+        })
+    }
+
+    /// The width of `s` and the font's line height, in pixels
+    pub fn measure(&self, s: &str) -> (usize, usize) {
+        let width = self
+            .layout(s)
+            .map(|(pen, glyph)| pen + glyph.xadvance.max(glyph.xoffset + glyph.width as i32))
+            .max()
+            .unwrap_or(0);
+        (width.max(0) as usize, self.line_height)
+    }
+
+    /// Draws `s` with the left end of its baseline at `x`, `y`
+    pub fn draw(&self, frame: &mut Frame, x: usize, y: usize, color: LinSrgb<u8>, s: &str) {
+        let top = y as i32 - self.base as i32;
+        for (pen, glyph) in self.layout(s) {
+            let left = x as i32 + pen + glyph.xoffset;
+            let glyph_top = top + glyph.yoffset;
+            for gy in 0..glyph.height {
+                let dy = glyph_top + gy as i32;
+                if dy < 0 || dy as usize >= frame.height() {
+                    continue;
+                }
+                for gx in 0..glyph.width {
+                    let dx = left + gx as i32;
+                    if dx < 0 {
+                        continue;
+                    }
+                    let alpha = self.page.get_pixel(glyph.x + gx, glyph.y + gy).0[3];
+                    let (dx, dy) = (dx as usize, dy as usize);
+                    match alpha {
+                        0 => (),
+                        255 => frame.put_pixel(dx, dy, color),
+                        _ => {
+                            let below = frame.get_pixel(dx, dy).into_format::<f32>();
+                            let blended = below.mix(color.into_format(), alpha as f32 / 255.0);
+                            frame.put_pixel(dx, dy, blended.into_format());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_parse_and_draw() {
+        let descriptor = "info face=\"Test Font\" size=4\n\
+            common lineHeight=6 base=4 scaleW=4 scaleH=2 pages=1\n\
+            page id=0 file=\"test font.png\"\n\
+            chars count=2\n\
+            char id=65 x=0 y=0 width=2 height=2 xoffset=0 yoffset=2 xadvance=3 page=0\n\
+            char id=63 x=2 y=0 width=1 height=1 xoffset=1 yoffset=0 xadvance=2 page=0\n\
+            kerning first=65 second=65 amount=-1\n";
+        let font = BitmapFont::parse(descriptor, |file| {
+            assert_eq!(file, "test font.png");
+            let mut page = RgbaImage::new(4, 2);
+            for (x, y) in [(0, 0), (1, 1), (2, 0)] {
+                page.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+            Ok(Arc::new(page))
+        })
+        .unwrap();
+        assert_eq!(font.line_height(), 6);
+        // Kerned together, then a `?` for the missing `B`
+        assert_eq!(font.measure("AAB"), (7, 6));
+
+        let mut data = vec![0; 8 * 6 * 4];
+        let mut frame = Frame {
+            width: 8,
+            height: 6,
+            bytespp: 4,
+            stride: 8 * 4,
+            data: &mut data,
+        };
+        let red = LinSrgb::new(255, 0, 0);
+        font.draw(&mut frame, 1, 4, red, "AB");
+        assert_eq!(frame.get_pixel(1, 2), red);
+        assert_eq!(frame.get_pixel(2, 3), red);
+        assert_eq!(frame.get_pixel(2, 2), LinSrgb::new(0, 0, 0));
+        assert_eq!(frame.get_pixel(5, 0), red);
+    }
+}
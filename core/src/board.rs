@@ -0,0 +1,195 @@
+//! Drawing square chequered boards for games like chess and checkers. The
+//! squares are addressed by [`Point`] as seen with the first player at the
+//! bottom, and the board can be flipped round for the other player without
+//! the game having to know.
+
+use palette::LinSrgb;
+
+use crate::app::Frame;
+use crate::grid::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Board {
+    /// Squares along each side
+    pub size: usize,
+    /// Pixels along each side of a square
+    pub square: usize,
+    pub left: usize,
+    pub top: usize,
+    /// Turned round, with the top row at the bottom
+    pub flipped: bool,
+    pub light: LinSrgb<u8>,
+    pub dark: LinSrgb<u8>,
+}
+
+impl Board {
+    /// A `size` x `size` board at the top left of the frame, in wood colors
+    pub fn new(size: usize, square: usize) -> Self {
+        Self {
+            size,
+            square,
+            left: 0,
+            top: 0,
+            flipped: false,
+            light: LinSrgb::new(238, 216, 178),
+            dark: LinSrgb::new(176, 128, 92),
+        }
+    }
+
+    pub fn at(mut self, left: usize, top: usize) -> Self {
+        self.left = left;
+        self.top = top;
+        self
+    }
+
+    pub fn colors(mut self, light: LinSrgb<u8>, dark: LinSrgb<u8>) -> Self {
+        self.light = light;
+        self.dark = dark;
+        self
+    }
+
+    /// Pixels along each side of the whole board
+    pub fn extent(&self) -> usize {
+        self.size * self.square
+    }
+
+    /// Whether `point` is a dark square. The bottom left one is, as on a
+    /// chess board.
+    pub fn is_dark(&self, point: Point) -> bool {
+        (point.0 + point.1 + self.size) % 2 == 1
+    }
+
+    /// The pixel at the top left of the square at `point`
+    pub fn origin(&self, Point(x, y): Point) -> (usize, usize) {
+        let (column, row) = match self.flipped {
+            false => (x, y),
+            true => (self.size - 1 - x, self.size - 1 - y),
+        };
+        (
+            self.left + column * self.square,
+            self.top + row * self.square,
+        )
+    }
+
+    pub fn center(&self, point: Point) -> (usize, usize) {
+        let (x, y) = self.origin(point);
+        (x + self.square / 2, y + self.square / 2)
+    }
+
+    /// The square under the pixel at `x`, `y`, for picking squares by touch
+    pub fn square_at(&self, x: usize, y: usize) -> Option<Point> {
+        let column = x.checked_sub(self.left)? / self.square;
+        let row = y.checked_sub(self.top)? / self.square;
+        if column >= self.size || row >= self.size {
+            return None;
+        }
+        Some(match self.flipped {
+            false => Point(column, row),
+            true => Point(self.size - 1 - column, self.size - 1 - row),
+        })
+    }
+
+    /// Draws the empty squares
+    pub fn draw(&self, frame: &mut Frame) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let point = Point(x, y);
+                let color = if self.is_dark(point) {
+                    self.dark
+                } else {
+                    self.light
+                };
+                self.fill(frame, point, color);
+            }
+        }
+    }
+
+    /// Fills the square at `point`, to show the last move or where a piece
+    /// can go
+    pub fn fill(&self, frame: &mut Frame, point: Point, color: LinSrgb<u8>) {
+        let (x, y) = self.origin(point);
+        frame.fill_rect(x, y, self.square, self.square, color);
+    }
+
+    /// A box `thickness` pixels wide just inside the square at `point`, for
+    /// the cursor or a selected piece
+    pub fn outline(&self, frame: &mut Frame, point: Point, thickness: usize, color: LinSrgb<u8>) {
+        let (x, y) = self.origin(point);
+        let (size, thickness) = (self.square, thickness.min(self.square / 2));
+        frame.fill_rect(x, y, size, thickness, color);
+        frame.fill_rect(x, y + size - thickness, size, thickness, color);
+        frame.fill_rect(x, y, thickness, size, color);
+        frame.fill_rect(x + size - thickness, y, thickness, size, color);
+    }
+
+    /// A filled circle in the middle of the square at `point`, for pieces or
+    /// marking moves
+    pub fn disc(&self, frame: &mut Frame, point: Point, radius: usize, color: LinSrgb<u8>) {
+        let (cx, cy) = self.center(point);
+        let r = radius as isize;
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if dx * dx + dy * dy <= r * r {
+                    let (x, y) = (cx as isize + dx, cy as isize + dy);
+                    if x >= 0 && y >= 0 {
+                        frame.put_pixel(x as usize, y as usize, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Letters along the bottom row and numbers up the left column, small in
+    /// the corners of the squares, the way chess boards name squares
+    pub fn coordinates(&self, frame: &mut Frame, color: LinSrgb<u8>) {
+        let size = self.size;
+        let fontsize = (self.square / 4).max(10) as f32;
+        for i in 0..size {
+            let column = match self.flipped {
+                false => i,
+                true => size - 1 - i,
+            };
+            let letter = ((b'a' + column as u8) as char).to_string();
+            let x = self.left + i * self.square + 2;
+            let y = self.top + self.extent() - 3;
+            frame.text("fonts/Ubuntu-B.ttf", x, y, fontsize, color, &letter);
+
+            let number = match self.flipped {
+                false => size - i,
+                true => i + 1,
+            };
+            let y = self.top + i * self.square + fontsize as usize;
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                self.left + 2,
+                y,
+                fontsize,
+                color,
+                &number.to_string(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_squares() {
+        let mut board = Board::new(8, 40).at(100, 20);
+        assert!(board.is_dark(Point(0, 7)));
+        assert!(!board.is_dark(Point(7, 7)));
+        assert_eq!(board.origin(Point(1, 0)), (140, 20));
+        assert_eq!(board.square_at(99, 30), None);
+        assert_eq!(board.square_at(100 + 8 * 40, 30), None);
+        assert_eq!(board.square_at(179, 59), Some(Point(1, 0)));
+
+        board.flipped = true;
+        assert_eq!(board.origin(Point(7, 7)), (100, 20));
+        for point in [Point(0, 0), Point(3, 5), Point(7, 2)] {
+            let (x, y) = board.center(point);
+            assert_eq!(board.square_at(x, y), Some(point));
+        }
+    }
+}
@@ -0,0 +1,647 @@
+//! Launcher-wide preferences, kept in `launcher.txt` next to the binary. The
+//! first-run wizard writes the initial copy.
+//!
+//! ```text
+//! language = en
+//! theme = classic
+//! clock_24h = true
+//! utc_offset = 2
+//! profile = Player 1
+//! ```
+//!
+//! The accessibility options make text bigger and give timed things longer:
+//!
+//! ```text
+//! theme = high_contrast
+//! ui_scale = 1.5
+//! extra_time = true
+//! color_filter = deuteranopia
+//! haptics = 25
+//! ```
+//!
+//! It also holds the PIN lock, see [`crate::lock`], and the Distance
+//! Integrator's aircraft, one per line:
+//!
+//! ```text
+//! aircraft = Cessna 172, 110, 500, 8.5
+//! current_aircraft = Cessna 172
+//! ```
+
+use std::{io, path::PathBuf, sync::Mutex};
+
+use enum_iterator::{all, Sequence};
+use palette::LinSrgb;
+
+use crate::display::{ColorCorrection, ColorFilter, Flip};
+use crate::time::{Duration, SystemTime};
+use crate::units::{AltitudeUnit, DistanceUnit, SpeedUnit, Units};
+
+/// Languages the launcher can be shown in, as `(code, name)`
+pub const LANGUAGES: [(&str, &str); 1] = [("en", "English")];
+
+/// Colors for the launcher's own screens
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum Theme {
+    /// Red and white on black
+    #[default]
+    Classic,
+    Light,
+    Ocean,
+    /// Yellow and white on black, for the hard of seeing
+    HighContrast,
+}
+
+/// What a [`Theme`] draws with
+pub struct ThemeColors {
+    pub background: LinSrgb<u8>,
+    pub text: LinSrgb<u8>,
+    pub selected: LinSrgb<u8>,
+    /// Hints and descriptions
+    pub dim: LinSrgb<u8>,
+}
+
+impl Theme {
+    /// The name used in `launcher.txt`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Light => "light",
+            Self::Ocean => "ocean",
+            Self::HighContrast => "high_contrast",
+        }
+    }
+
+    /// The name shown in Settings
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Classic => "Classic",
+            Self::Light => "Light",
+            Self::Ocean => "Ocean",
+            Self::HighContrast => "High contrast",
+        }
+    }
+
+    /// The [nine-patch](crate::ninepatch) that dialogs, toasts and
+    /// highlights are drawn on, which is always built in
+    pub fn panel(&self) -> &'static str {
+        match self {
+            Self::Light => "ui/panel-light.9.png",
+            Self::Classic | Self::Ocean | Self::HighContrast => "ui/panel.9.png",
+        }
+    }
+
+    pub fn colors(&self) -> ThemeColors {
+        match self {
+            Self::Classic => ThemeColors {
+                background: LinSrgb::new(0, 0, 0),
+                text: LinSrgb::new(255, 0, 0),
+                selected: LinSrgb::new(255, 255, 255),
+                dim: LinSrgb::new(180, 180, 180),
+            },
+            Self::Light => ThemeColors {
+                background: LinSrgb::new(235, 235, 230),
+                text: LinSrgb::new(90, 90, 90),
+                selected: LinSrgb::new(0, 0, 0),
+                dim: LinSrgb::new(120, 120, 120),
+            },
+            Self::Ocean => ThemeColors {
+                background: LinSrgb::new(0, 20, 60),
+                text: LinSrgb::new(80, 170, 255),
+                selected: LinSrgb::new(255, 255, 255),
+                dim: LinSrgb::new(150, 170, 200),
+            },
+            Self::HighContrast => ThemeColors {
+                background: LinSrgb::new(0, 0, 0),
+                text: LinSrgb::new(255, 255, 0),
+                selected: LinSrgb::new(255, 255, 255),
+                dim: LinSrgb::new(230, 230, 230),
+            },
+        }
+    }
+}
+
+/// Where the device backend reads controllers from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum InputDriver {
+    /// The `stick` crate
+    #[default]
+    Stick,
+    /// Straight from `/dev/input`, see `gamepad`
+    Evdev,
+}
+
+impl InputDriver {
+    /// The name used in `launcher.txt`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stick => "stick",
+            Self::Evdev => "evdev",
+        }
+    }
+}
+
+/// An aircraft's typical numbers, which the Distance Integrator starts from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aircraft {
+    pub name: String,
+    /// Cruise indicated airspeed, in knots
+    pub ias: u32,
+    /// Feet per minute
+    pub descent_rate: u32,
+    /// US gallons per hour
+    pub fuel_flow: f32,
+}
+
+impl Aircraft {
+    /// `name, ias, descent rate, fuel flow`. Only the numbers are split off,
+    /// so names can have commas in them.
+    fn parse(value: &str) -> Result<Self, String> {
+        let mut fields = value.rsplitn(4, ',').map(str::trim);
+        let mut next = |what: &str| fields.next().ok_or_else(|| format!("missing {}", what));
+        let fuel_flow = next("fuel flow")?;
+        let descent_rate = next("descent rate")?;
+        let ias = next("airspeed")?;
+        let name = next("name")?;
+        if name.is_empty() {
+            return Err("missing name".to_string());
+        }
+        let number = |v: &str| {
+            v.parse::<u32>()
+                .map_err(|_| format!("expected a number, not `{}`", v))
+        };
+        Ok(Self {
+            name: name.to_string(),
+            ias: number(ias)?,
+            descent_rate: number(descent_rate)?.max(1),
+            fuel_flow: fuel_flow
+                .parse::<f32>()
+                .ok()
+                .filter(|flow| flow.is_finite() && *flow >= 0.0)
+                .ok_or_else(|| format!("expected a fuel flow, not `{}`", fuel_flow))?,
+        })
+    }
+
+    fn to_text(&self) -> String {
+        format!(
+            "{}, {}, {}, {}",
+            self.name, self.ias, self.descent_rate, self.fuel_flow
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// A code from [`LANGUAGES`]
+    pub language: String,
+    pub theme: Theme,
+    pub clock_24h: bool,
+    /// Hours ahead of UTC, since the device has no time zone database
+    pub utc_offset: i32,
+    /// See [`crate::profiles`]
+    pub profile: String,
+    /// The hash of the PIN, if one's been set
+    pub pin: Option<String>,
+    /// Menu entries that need the PIN
+    pub locked: Vec<String>,
+    /// Makes apps' randomness repeatable, see [`crate::random`]
+    pub seed: Option<u64>,
+    /// The simulator window's size when it was last closed
+    pub window: Option<(usize, usize)>,
+    /// And where it was on the desktop
+    pub window_position: Option<(i32, i32)>,
+    pub input: InputDriver,
+    /// Applied to the panel on the device and in the simulator
+    pub flip: Flip,
+    /// Applied to the panel after flipping
+    pub color: ColorCorrection,
+    /// Run in the launcher's place when it quits on the device, e.g. to go
+    /// back to the stock UI
+    pub on_exit: Option<String>,
+    /// Whether the first-run wizard has been through
+    pub set_up: bool,
+    /// Extra `aircraft` lines, in order
+    pub aircraft: Vec<Aircraft>,
+    /// The name of the one the Distance Integrator last used
+    pub current_aircraft: Option<String>,
+    /// What distances, speeds and altitudes are shown in
+    pub units: Units,
+    /// How long the menu's left alone before it starts playing demos, if it
+    /// ever does; see [`crate::demo`]
+    pub attract: Option<Duration>,
+    /// How much bigger the launcher's text and spacing are drawn, in
+    /// hundredths; see [`Config::scaled`]
+    pub ui_scale: u16,
+    /// Gives timers and held buttons more time; see [`Config::timed`]
+    pub extra_time: bool,
+    /// How strongly controllers rumble for [feedback](crate::haptics), in
+    /// percent, with 0 for not at all
+    pub haptics: u8,
+}
+
+/// The smallest and largest [`Config::ui_scale`]
+pub const UI_SCALE_RANGE: (u16, u16) = (100, 200);
+
+const DEFAULT_HAPTICS: u8 = 50;
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            language: LANGUAGES[0].0.to_string(),
+            theme: Theme::default(),
+            clock_24h: true,
+            utc_offset: 0,
+            profile: crate::profiles::DEFAULT_PROFILE.to_string(),
+            pin: None,
+            locked: vec![],
+            seed: None,
+            window: None,
+            window_position: None,
+            input: InputDriver::default(),
+            flip: Flip::default(),
+            color: ColorCorrection::default(),
+            on_exit: None,
+            set_up: false,
+            aircraft: vec![],
+            current_aircraft: None,
+            units: Units::default(),
+            attract: None,
+            ui_scale: 100,
+            extra_time: false,
+            haptics: DEFAULT_HAPTICS,
+        }
+    }
+}
+
+impl Config {
+    /// Applies the lines of `launcher.txt`, logging any it doesn't understand.
+    /// A file with anything in it means setup has been done.
+    pub fn apply(&mut self, text: &str) {
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            self.set_up = true;
+            if let Err(e) = self.apply_line(line) {
+                println!("Ignoring `{}` in launcher.txt: {}", line, e);
+            }
+        }
+    }
+
+    fn apply_line(&mut self, line: &str) -> Result<(), String> {
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| "expected name = value".to_string())?;
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "language" => {
+                if !LANGUAGES.iter().any(|(code, _)| *code == value) {
+                    return Err(format!("unknown language `{}`", value));
+                }
+                self.language = value.to_string();
+            }
+            "theme" => {
+                self.theme = all::<Theme>()
+                    .find(|theme| theme.name() == value)
+                    .ok_or_else(|| format!("unknown theme `{}`", value))?;
+            }
+            "clock_24h" => {
+                self.clock_24h = value
+                    .parse()
+                    .map_err(|_| "expected true or false".to_string())?;
+            }
+            "utc_offset" => {
+                self.utc_offset = value
+                    .parse::<i32>()
+                    .map_err(|e| e.to_string())?
+                    .clamp(-12, 14);
+            }
+            "profile" => {
+                if !crate::profiles::is_valid_name(value) {
+                    return Err(format!("`{}` isn't a valid profile name", value));
+                }
+                self.profile = value.to_string();
+            }
+            "pin" => {
+                if value.len() != 64 || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err("expected a PIN hash".to_string());
+                }
+                self.pin = Some(value.to_lowercase());
+            }
+            "locked" => {
+                self.locked = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            "seed" => self.seed = Some(value.parse().map_err(|_| "expected a number")?),
+            "window" => self.window = Some(crate::display::parse_size(value)?),
+            "window_position" => {
+                let (x, y) = value
+                    .split_once(',')
+                    .ok_or_else(|| "expected X,Y".to_string())?;
+                let parse = |v: &str| v.trim().parse::<i32>().map_err(|e| e.to_string());
+                self.window_position = Some((parse(x)?, parse(y)?));
+            }
+            "input" => {
+                self.input = all::<InputDriver>()
+                    .find(|driver| driver.name() == value)
+                    .ok_or_else(|| format!("unknown input driver `{}`", value))?;
+            }
+            "flip" => {
+                self.flip = all::<Flip>()
+                    .find(|flip| flip.name() == value)
+                    .ok_or_else(|| format!("unknown flip `{}`", value))?;
+            }
+            // One value for every channel, or red, green and blue
+            "gamma" => {
+                let values = value
+                    .split(',')
+                    .map(parse_hundredths)
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.color.gamma = match values[..] {
+                    [all] => [all; 3],
+                    [red, green, blue] => [red, green, blue],
+                    _ => return Err("expected one gamma or three".to_string()),
+                };
+            }
+            "contrast" => self.color.contrast = parse_hundredths(value)?,
+            "color_filter" => self.color.filter = value.parse()?,
+            "on_exit" => self.on_exit = Some(value.to_string()).filter(|c| !c.is_empty()),
+            // Each line adds one, replacing any with the same name
+            "aircraft" => {
+                let aircraft = Aircraft::parse(value)?;
+                self.aircraft.retain(|a| a.name != aircraft.name);
+                self.aircraft.push(aircraft);
+            }
+            "distance_unit" => {
+                self.units.distance = all::<DistanceUnit>()
+                    .find(|unit| unit.name() == value)
+                    .ok_or_else(|| format!("unknown distance unit `{}`", value))?;
+            }
+            "speed_unit" => {
+                self.units.speed = all::<SpeedUnit>()
+                    .find(|unit| unit.name() == value)
+                    .ok_or_else(|| format!("unknown speed unit `{}`", value))?;
+            }
+            "altitude_unit" => {
+                self.units.altitude = all::<AltitudeUnit>()
+                    .find(|unit| unit.name() == value)
+                    .ok_or_else(|| format!("unknown altitude unit `{}`", value))?;
+            }
+            // In seconds, with 0 or off for never
+            "attract" => {
+                self.attract = match value {
+                    "off" => None,
+                    _ => value
+                        .parse::<u64>()
+                        .map_err(|_| "expected seconds or off".to_string())
+                        .map(|secs| Some(Duration::from_secs(secs)).filter(|d| !d.is_zero()))?,
+                };
+            }
+            "ui_scale" => {
+                self.ui_scale = parse_hundredths(value)?.clamp(UI_SCALE_RANGE.0, UI_SCALE_RANGE.1)
+            }
+            "extra_time" => {
+                self.extra_time = value
+                    .parse()
+                    .map_err(|_| "expected true or false".to_string())?;
+            }
+            "haptics" => {
+                self.haptics = value
+                    .parse::<u8>()
+                    .map_err(|_| "expected a percentage".to_string())?
+                    .min(100)
+            }
+            "current_aircraft" => {
+                self.current_aircraft = Some(value.to_string()).filter(|n| !n.is_empty())
+            }
+            _ => return Err("unknown setting".to_string()),
+        }
+        Ok(())
+    }
+
+    /// The contents of `launcher.txt` for this config
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "language = {}\ntheme = {}\nclock_24h = {}\nutc_offset = {}\nprofile = {}\n",
+            self.language,
+            self.theme.name(),
+            self.clock_24h,
+            self.utc_offset,
+            self.profile
+        );
+        if let Some(pin) = &self.pin {
+            text += &format!("pin = {}\n", pin);
+        }
+        if !self.locked.is_empty() {
+            text += &format!("locked = {}\n", self.locked.join(", "));
+        }
+        if let Some(seed) = self.seed {
+            text += &format!("seed = {}\n", seed);
+        }
+        if let Some((width, height)) = self.window {
+            text += &format!("window = {}x{}\n", width, height);
+        }
+        if let Some((x, y)) = self.window_position {
+            text += &format!("window_position = {},{}\n", x, y);
+        }
+        if self.input != InputDriver::default() {
+            text += &format!("input = {}\n", self.input.name());
+        }
+        if self.flip != Flip::default() {
+            text += &format!("flip = {}\n", self.flip.name());
+        }
+        if !self.color.is_filter_only() {
+            let [red, green, blue] = self.color.gamma.map(format_hundredths);
+            text += &format!(
+                "gamma = {}, {}, {}\ncontrast = {}\n",
+                red,
+                green,
+                blue,
+                format_hundredths(self.color.contrast)
+            );
+        }
+        if self.color.filter != ColorFilter::None {
+            text += &format!("color_filter = {}\n", self.color.filter.name());
+        }
+        if let Some(command) = &self.on_exit {
+            text += &format!("on_exit = {}\n", command);
+        }
+        if self.units != Units::default() {
+            text += &format!(
+                "distance_unit = {}\nspeed_unit = {}\naltitude_unit = {}\n",
+                self.units.distance.name(),
+                self.units.speed.name(),
+                self.units.altitude.name()
+            );
+        }
+        for aircraft in &self.aircraft {
+            text += &format!("aircraft = {}\n", aircraft.to_text());
+        }
+        if let Some(name) = &self.current_aircraft {
+            text += &format!("current_aircraft = {}\n", name);
+        }
+        if let Some(attract) = self.attract {
+            text += &format!("attract = {}\n", attract.as_secs());
+        }
+        if self.ui_scale != 100 {
+            text += &format!("ui_scale = {}\n", format_hundredths(self.ui_scale));
+        }
+        if self.extra_time {
+            text += "extra_time = true\n";
+        }
+        if self.haptics != DEFAULT_HAPTICS {
+            text += &format!("haptics = {}\n", self.haptics);
+        }
+        text
+    }
+
+    /// A size in pixels, e.g. a font size or the gap between rows, at the
+    /// [UI scale](Self::ui_scale)
+    pub fn scaled(&self, size: f32) -> f32 {
+        size * self.ui_scale as f32 / 100.0
+    }
+
+    /// Half as long again with [extra time](Self::extra_time) on, for
+    /// countdowns and button repeats
+    pub fn timed(&self, duration: Duration) -> Duration {
+        match self.extra_time {
+            true => duration * 3 / 2,
+            false => duration,
+        }
+    }
+
+    /// The time of day at `time` in this config's zone and format, e.g.
+    /// `14:05` or `2:05 PM`
+    pub fn clock(&self, time: SystemTime) -> String {
+        let secs = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let (_, _, _, hour, minute, _) =
+            crate::storage::civil_from_unix(secs + self.utc_offset as i64 * 3600);
+        if self.clock_24h {
+            format!("{:02}:{:02}", hour, minute)
+        } else {
+            let suffix = if hour < 12 { "AM" } else { "PM" };
+            format!("{}:{:02} {}", (hour + 11) % 12 + 1, minute, suffix)
+        }
+    }
+}
+
+/// Reads a factor like `1.25` as 125, between 0.1 and 5
+fn parse_hundredths(value: &str) -> Result<u16, String> {
+    let factor = value
+        .trim()
+        .parse::<f32>()
+        .map_err(|_| format!("expected a number, not `{}`", value.trim()))?;
+    Ok((factor.clamp(0.1, 5.0) * 100.0).round() as u16)
+}
+
+/// `125` as `1.25`
+pub fn format_hundredths(value: u16) -> String {
+    format!("{}.{:02}", value / 100, value % 100)
+}
+
+fn config_path() -> PathBuf {
+    crate::storage::base_dir().join("launcher.txt")
+}
+
+/// Loaded once, then kept up to date by [`set_config`]
+static CONFIG: Mutex<Option<Config>> = Mutex::new(None);
+
+/// The config from `launcher.txt`, or the defaults
+pub fn config() -> Config {
+    CONFIG
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| {
+            let mut config = Config::default();
+            if let Ok(text) = std::fs::read_to_string(config_path()) {
+                config.apply(&text);
+            }
+            config
+        })
+        .clone()
+}
+
+/// Uses `config` from now on and saves it to `launcher.txt`
+pub fn set_config(config: Config) -> io::Result<()> {
+    let text = config.to_text();
+    *CONFIG.lock().unwrap() = Some(config);
+    std::fs::write(config_path(), text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::display::ColorBlindness;
+
+    #[test]
+    fn test_config() {
+        let mut config = Config::default();
+        config.apply("# comment\ntheme = ocean\nclock_24h = false\nutc_offset = -5\nfoo = 1\n");
+        config.apply("profile = Player 2\nprofile = ../up\n");
+        assert_eq!(config.profile, "Player 2");
+        assert!(config.set_up);
+        assert_eq!(config.theme, Theme::Ocean);
+        config.apply("pin = 1234\nlocked = Settings, Taboo\nseed = 42\n");
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(config.pin, None);
+        assert_eq!(config.locked, ["Settings", "Taboo"]);
+        config.apply("window = 1280x960\nwindow_position = -20, 40\n");
+        assert_eq!(config.window, Some((1280, 960)));
+        assert_eq!(config.window_position, Some((-20, 40)));
+        config.apply(
+            "flip = horizontal\ninput = evdev\non_exit = /mnt/vendor/bin/dmenu.bin --start\n",
+        );
+        assert_eq!(config.input, InputDriver::Evdev);
+        assert_eq!(config.flip, Flip::Horizontal);
+        config.apply("gamma = 1.1, 1, 0.95\ncontrast = 1.2\ngamma = 1, 2\n");
+        assert_eq!(config.color.gamma, [110, 100, 95]);
+        assert_eq!(config.color.contrast, 120);
+        assert_eq!(
+            config.on_exit.as_deref(),
+            Some("/mnt/vendor/bin/dmenu.bin --start")
+        );
+        config.apply(
+            "aircraft = Piper J-3, Cub, 65, 400, 3.5\naircraft = Jet, 250\n\
+            aircraft = Cessna 172, 110, 500, 8.5\ncurrent_aircraft = Cessna 172\n",
+        );
+        assert_eq!(config.aircraft.len(), 2);
+        assert_eq!(config.aircraft[0].name, "Piper J-3, Cub");
+        assert_eq!(config.aircraft[1].fuel_flow, 8.5);
+        config.apply("distance_unit = mi\nspeed_unit = km/h\naltitude_unit = yd\n");
+        assert_eq!(config.units.distance, DistanceUnit::Miles);
+        assert_eq!(config.units.speed, SpeedUnit::KilometersPerHour);
+        assert_eq!(config.units.altitude, AltitudeUnit::Feet);
+        config.apply("attract = 90\nattract = soon\n");
+        assert_eq!(config.attract, Some(Duration::from_secs(90)));
+        config.apply("ui_scale = 1.5\nextra_time = true\ntheme = high_contrast\n");
+        config.apply("haptics = 250\nhaptics = strong\n");
+        assert_eq!(config.haptics, 100);
+        config.apply("color_filter = simulate_tritanopia\ncolor_filter = red\n");
+        assert_eq!(
+            config.color.filter,
+            ColorFilter::Simulate(ColorBlindness::Tritanopia)
+        );
+        assert_eq!(config.scaled(18.0), 27.0);
+        assert_eq!(
+            config.timed(Duration::from_secs(60)),
+            Duration::from_secs(90)
+        );
+        config.pin = Some(crate::lock::hash_pin(&[crate::app::Button::PovUp; 4]));
+
+        // 2024-05-01 17:30 UTC
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1714584600);
+        assert_eq!(config.clock(time), "12:30 PM");
+        config.clock_24h = true;
+        assert_eq!(config.clock(time), "12:30");
+
+        let mut reloaded = Config::default();
+        reloaded.apply(&config.to_text());
+        assert_eq!(reloaded, config);
+    }
+}
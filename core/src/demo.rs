@@ -0,0 +1,263 @@
+//! Apps playing themselves, for the menu's attract mode: once the menu's
+//! been left alone for a while it runs each app with a demo in turn, like an
+//! arcade cabinet, until a button is pressed. An app has a demo if it presses
+//! its own buttons in [`App::demo_input`] and says so with
+//! [`Manifest::demo`](crate::app::Manifest::demo), or if there's a replay of
+//! someone playing it at `demos/<slug>.txt` in the assets.
+//!
+//! A replay has a line for each press and release, at seconds from the
+//! start, with the buttons named as they're labelled on the device:
+//!
+//! ```text
+//! # Flat out, then round the first bend
+//! 0.5 A down
+//! 3.2 LEFT down
+//! 3.9 LEFT up
+//! ```
+//!
+//! A [`Recorder`] writes them from real play.
+//!
+//! Nothing done in a demo is kept: while one is [`playing`], achievements
+//! aren't unlocked and [`Store`](crate::profiles::Store)s and
+//! [`SaveState`](crate::savestate::SaveState)s aren't written.
+
+use std::cell::Cell;
+use std::str::FromStr;
+
+use enum_iterator::all;
+use palette::LinSrgb;
+use rusttype::Scale;
+
+use crate::actions::{button_label, parse_button};
+use crate::app::{load_font, App, AppContext, Button, Input, Repeat};
+use crate::assets::DEFAULT_FONT;
+use crate::tasks::Tasks;
+use crate::time::Duration;
+use crate::widgets::text_width;
+
+thread_local! {
+    static PLAYING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the app being updated is playing a demo, so its results
+/// shouldn't count
+pub fn playing() -> bool {
+    PLAYING.with(Cell::get)
+}
+
+/// Button presses and releases at times from the start
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Replay {
+    events: Vec<(Duration, Button, bool)>,
+    /// The first event that's not been played yet
+    next: usize,
+}
+
+impl Replay {
+    /// How long until the last event
+    pub fn length(&self) -> Duration {
+        self.events.last().map_or(Duration::ZERO, |&(at, ..)| at)
+    }
+
+    /// Whether everything's been played
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.events.len()
+    }
+
+    /// Presses and releases everything due by `elapsed` on `input`
+    pub fn play(&mut self, elapsed: Duration, input: &mut Input) {
+        while let Some(&(at, button, pressed)) = self.events.get(self.next) {
+            if at > elapsed {
+                break;
+            }
+            input.event(button, pressed);
+            self.next += 1;
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        self.events
+            .iter()
+            .map(|&(at, button, pressed)| {
+                let state = if pressed { "down" } else { "up" };
+                format!(
+                    "{:.3} {} {}\n",
+                    at.as_secs_f32(),
+                    button_label(button),
+                    state
+                )
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Replay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut events = vec![];
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |e: String| format!("line {}: {}", number + 1, e);
+            let [at, button, state] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(error(
+                    "expected a time, a button and down or up".to_string(),
+                ));
+            };
+            let at = at
+                .parse::<f32>()
+                .ok()
+                .filter(|at| at.is_finite() && *at >= 0.0)
+                .ok_or_else(|| error(format!("`{}` isn't a time in seconds", at)))?;
+            let pressed = match state {
+                "down" => true,
+                "up" => false,
+                _ => return Err(error(format!("expected down or up, not `{}`", state))),
+            };
+            events.push((
+                Duration::from_secs_f32(at),
+                parse_button(button).map_err(error)?,
+                pressed,
+            ));
+        }
+        // Stable, so a press and release at the same time stay in order
+        events.sort_by_key(|&(at, ..)| at);
+        Ok(Self { events, next: 0 })
+    }
+}
+
+/// Writes down the buttons pressed while playing, to make a [`Replay`]
+#[derive(Default)]
+pub struct Recorder {
+    elapsed: Duration,
+    events: Vec<(Duration, Button, bool)>,
+}
+
+impl Recorder {
+    /// Notes what changed in `input` this frame. Call it every update.
+    pub fn record(&mut self, input: &Input) {
+        self.elapsed += input.delta();
+        for button in all::<Button>().filter(|&button| input.just_changed(button)) {
+            self.events
+                .push((self.elapsed, button, input.pressed(button)));
+        }
+    }
+
+    pub fn finish(self) -> Replay {
+        Replay {
+            events: self.events,
+            next: 0,
+        }
+    }
+}
+
+/// An app playing itself, with input from its [`App::demo_input`] or a
+/// replay
+pub struct Demo {
+    app: Box<dyn App>,
+    replay: Option<Replay>,
+    input: Input,
+    elapsed: Duration,
+    /// Its own, so its work is dropped along with it
+    tasks: Tasks,
+}
+
+impl Demo {
+    pub fn new(app: Box<dyn App>, replay: Option<Replay>) -> Self {
+        Self {
+            app,
+            replay,
+            input: Input::with_repeat(Repeat::standard()),
+            elapsed: Duration::ZERO,
+            tasks: Tasks::default(),
+        }
+    }
+
+    /// How long it's been playing
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn app(&self) -> &dyn App {
+        &*self.app
+    }
+
+    /// Updates the app with its demo input, drawing into `ctx`'s frame with
+    /// a note that it's a demo. `ctx`'s own input is ignored.
+    pub fn update(&mut self, ctx: &mut AppContext) {
+        self.input.update();
+        self.elapsed += self.input.delta();
+        match &mut self.replay {
+            Some(replay) => replay.play(self.elapsed, &mut self.input),
+            None => self.app.demo_input(&mut self.input),
+        }
+
+        PLAYING.with(|playing| playing.set(true));
+        self.app.update(&mut AppContext::new(
+            &self.input,
+            ctx.frame,
+            &mut self.tasks,
+        ));
+        PLAYING.with(|playing| playing.set(false));
+
+        let frame = &mut *ctx.frame;
+        let (width, height) = (frame.width(), frame.height());
+        // Blinking, a second on and a second off
+        if self.elapsed.as_secs().is_multiple_of(2) {
+            frame.fill_rect(
+                0,
+                height.saturating_sub(28),
+                width,
+                28,
+                LinSrgb::new(0, 0, 0),
+            );
+            let text = "DEMO - press any button";
+            let font = load_font(DEFAULT_FONT);
+            let x = (width as f32 - text_width(&font, Scale::uniform(18.0), text)) / 2.0;
+            frame.text(
+                DEFAULT_FONT,
+                x.max(0.0) as usize,
+                height.saturating_sub(8),
+                18.0,
+                LinSrgb::new(255, 255, 255),
+                text,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let text =
+            "# Comments and blank lines are skipped\n\n1.5 LEFT down\n0.25 a down\n1.5 LEFT up\n";
+        let mut replay: Replay = text.parse().unwrap();
+        assert_eq!(replay.length(), Duration::from_millis(1500));
+        assert!("0.5 Z down".parse::<Replay>().is_err());
+        assert!("soon A down".parse::<Replay>().is_err());
+
+        let mut input = Input::default();
+        replay.play(Duration::from_millis(300), &mut input);
+        assert!(input.pressed(Button::ActionA));
+        assert!(!input.pressed(Button::PovLeft));
+        // Same time, so the press and release happen in the order written
+        replay.play(Duration::from_secs(2), &mut input);
+        assert!(!input.pressed(Button::PovLeft));
+
+        // What's recorded reads back the same
+        let mut recorder = Recorder::default();
+        let mut input = Input::default();
+        input.set_delta(Duration::from_millis(250));
+        input.event(Button::ActionB, true);
+        recorder.record(&input);
+        let recorded = recorder.finish();
+        assert_eq!(recorded.to_text(), "0.250 B down\n");
+        assert_eq!(recorded.to_text().parse::<Replay>().unwrap(), recorded);
+    }
+}
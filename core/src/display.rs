@@ -0,0 +1,719 @@
+//! Mapping between the frame apps draw into and the physical panel.
+
+use std::str::FromStr;
+
+use enum_iterator::Sequence;
+
+use crate::app::{App, AppContext, Frame};
+
+/// Clockwise rotation applied when copying a frame to a panel that is mounted
+/// sideways or upside-down
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+impl FromStr for Rotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(Self::None),
+            "90" => Ok(Self::Cw90),
+            "180" => Ok(Self::Cw180),
+            "270" => Ok(Self::Cw270),
+            _ => Err(format!("rotation must be 0, 90, 180 or 270, not `{}`", s)),
+        }
+    }
+}
+
+/// Mirroring applied to the panel after [`Rotation`], for a device mounted
+/// upside-down in a grip or a mirrored capture. Unlike the rotation it can be
+/// changed while running, from Settings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum Flip {
+    #[default]
+    None,
+    /// Both ways, which is the same as turning the panel around
+    Rotate180,
+    Horizontal,
+    Vertical,
+}
+
+impl Flip {
+    /// The name used in `launcher.txt`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Rotate180 => "180",
+            Self::Horizontal => "horizontal",
+            Self::Vertical => "vertical",
+        }
+    }
+
+    /// For Settings
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::None => "Normal",
+            Self::Rotate180 => "Upside down",
+            Self::Horizontal => "Mirrored",
+            Self::Vertical => "Flipped vertically",
+        }
+    }
+
+    /// Whether it flips left to right and top to bottom
+    fn axes(&self) -> (bool, bool) {
+        match self {
+            Self::None => (false, false),
+            Self::Rotate180 => (true, true),
+            Self::Horizontal => (true, false),
+            Self::Vertical => (false, true),
+        }
+    }
+
+    /// Where a point on a panel of the given size was before flipping, which
+    /// is where it goes after, since flips undo themselves
+    pub fn map_point(
+        &self,
+        (width, height): (usize, usize),
+        (x, y): (usize, usize),
+    ) -> (usize, usize) {
+        let (horizontal, vertical) = self.axes();
+        (
+            if horizontal { width - 1 - x } else { x },
+            if vertical { height - 1 - y } else { y },
+        )
+    }
+
+    /// Flips a `width` x `height` image with rows `stride` bytes apart in
+    /// place
+    pub fn apply(
+        &self,
+        data: &mut [u8],
+        width: usize,
+        height: usize,
+        bytespp: usize,
+        stride: usize,
+    ) {
+        let (horizontal, vertical) = self.axes();
+        let row = width * bytespp;
+        if vertical {
+            for y in 0..height / 2 {
+                let (top, bottom) = data.split_at_mut((height - 1 - y) * stride);
+                top[y * stride..y * stride + row].swap_with_slice(&mut bottom[..row]);
+            }
+        }
+        if horizontal {
+            for y in 0..height {
+                let row = &mut data[y * stride..y * stride + row];
+                for x in 0..width / 2 {
+                    let (left, right) = row.split_at_mut((width - 1 - x) * bytespp);
+                    left[x * bytespp..(x + 1) * bytespp].swap_with_slice(&mut right[..bytespp]);
+                }
+            }
+        }
+    }
+}
+
+/// A kind of color blindness, missing one of the three kinds of cone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum ColorBlindness {
+    /// Red-green, without green cones
+    Deuteranopia,
+    /// Red-green, without red cones
+    Protanopia,
+    /// Blue-yellow
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Deuteranopia => "deuteranopia",
+            Self::Protanopia => "protanopia",
+            Self::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// How colors look with it, as a matrix on linear red, green and blue,
+    /// from Machado, Oliveira and Fernandes (2009)
+    fn simulation(&self) -> [[f32; 3]; 3] {
+        match self {
+            Self::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Self::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Self::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    /// Where the difference it can't see is moved to, so it shows up in
+    /// channels it can
+    fn shift(&self) -> [[f32; 3]; 3] {
+        match self {
+            Self::Deuteranopia | Self::Protanopia => {
+                [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]]
+            }
+            Self::Tritanopia => [[1.0, 0.0, 0.7], [0.0, 1.0, 0.7], [0.0, 0.0, 0.0]],
+        }
+    }
+}
+
+/// A filter over the whole screen for color-blind players, so things told
+/// apart only by color, like Snake's food and body, stay distinguishable
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum ColorFilter {
+    #[default]
+    None,
+    /// Moves the colors that can't be told apart towards ones that can
+    Compensate(ColorBlindness),
+    /// Shows the screen as it looks with the color blindness, for checking
+    /// an app's colors
+    Simulate(ColorBlindness),
+}
+
+impl ColorFilter {
+    /// The name used in `launcher.txt`, e.g. `deuteranopia` or
+    /// `simulate_deuteranopia`
+    pub fn name(&self) -> String {
+        match self {
+            Self::None => "none".to_string(),
+            Self::Compensate(kind) => kind.name().to_string(),
+            Self::Simulate(kind) => format!("simulate_{}", kind.name()),
+        }
+    }
+
+    /// For Settings
+    pub fn label(&self) -> String {
+        let name = self.name().replace('_', " ");
+        match self {
+            Self::None => "Off".to_string(),
+            _ => name[..1].to_uppercase() + &name[1..],
+        }
+    }
+
+    /// The matrix it applies to linear red, green and blue, by rows
+    fn matrix(&self) -> Option<[[f32; 3]; 3]> {
+        match self {
+            Self::None => None,
+            Self::Simulate(kind) => Some(kind.simulation()),
+            // Adds the shifted difference between how the color looks and
+            // how it should: (I + shift * (I - simulation)) * color
+            Self::Compensate(kind) => {
+                let (simulation, shift) = (kind.simulation(), kind.shift());
+                let identity = |i: usize, j: usize| if i == j { 1.0 } else { 0.0 };
+                Some(std::array::from_fn(|i| {
+                    std::array::from_fn(|j| {
+                        identity(i, j)
+                            + (0..3)
+                                .map(|k| shift[i][k] * (identity(k, j) - simulation[k][j]))
+                                .sum::<f32>()
+                    })
+                }))
+            }
+        }
+    }
+}
+
+impl FromStr for ColorFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        enum_iterator::all::<Self>()
+            .find(|filter| filter.name() == s)
+            .ok_or_else(|| format!("unknown color filter `{}`", s))
+    }
+}
+
+/// Gamma for each of red, green and blue, and a contrast, applied to the
+/// panel through a lookup table so the device's LCD can be matched to a
+/// desktop screen. Kept in hundredths, so 100 changes nothing. A
+/// [`ColorFilter`] goes on first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorCorrection {
+    /// Above 100 brightens a channel's midtones, below darkens them
+    pub gamma: [u16; 3],
+    /// Stretches colors away from mid-gray, or squashes them towards it
+    pub contrast: u16,
+    pub filter: ColorFilter,
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        Self {
+            gamma: [100; 3],
+            contrast: 100,
+            filter: ColorFilter::None,
+        }
+    }
+}
+
+/// Steps in the linear values a [`ColorFilter`] mixes, as fixed point
+const LINEAR_STEPS: i32 = 4095;
+
+/// A [`ColorFilter`]'s matrix as lookup tables: what each 8-bit value of
+/// each input channel adds to each output channel in linear fixed point, and
+/// the 8-bit value for each linear one
+struct FilterTables {
+    contributions: [[[i32; 256]; 3]; 3],
+    from_linear: Vec<u8>,
+}
+
+impl FilterTables {
+    fn new(matrix: [[f32; 3]; 3]) -> Self {
+        let to_linear = |value: usize| {
+            let value = value as f32 / 255.0;
+            match value <= 0.04045 {
+                true => value / 12.92,
+                false => ((value + 0.055) / 1.055).powf(2.4),
+            }
+        };
+        let from_linear = (0..=LINEAR_STEPS)
+            .map(|step| {
+                let value = step as f32 / LINEAR_STEPS as f32;
+                let value = match value <= 0.0031308 {
+                    true => value * 12.92,
+                    false => 1.055 * value.powf(1.0 / 2.4) - 0.055,
+                };
+                (value * 255.0).round() as u8
+            })
+            .collect();
+        let mut contributions = [[[0; 256]; 3]; 3];
+        for (out, row) in contributions.iter_mut().enumerate() {
+            for (channel, table) in row.iter_mut().enumerate() {
+                for (value, entry) in table.iter_mut().enumerate() {
+                    let linear = matrix[out][channel] * to_linear(value);
+                    *entry = (linear * LINEAR_STEPS as f32).round() as i32;
+                }
+            }
+        }
+        Self {
+            contributions,
+            from_linear,
+        }
+    }
+
+    fn apply(&self, rgb: [u8; 3]) -> [u8; 3] {
+        std::array::from_fn(|out| {
+            let [r, g, b] = &self.contributions[out];
+            let linear = r[rgb[0] as usize] + g[rgb[1] as usize] + b[rgb[2] as usize];
+            self.from_linear[linear.clamp(0, LINEAR_STEPS) as usize]
+        })
+    }
+}
+
+impl ColorCorrection {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether only the [`ColorFilter`] is changed
+    pub fn is_filter_only(&self) -> bool {
+        Self {
+            filter: ColorFilter::None,
+            ..*self
+        }
+        .is_identity()
+    }
+
+    /// What each value of red, green and blue becomes
+    pub fn tables(&self) -> [[u8; 256]; 3] {
+        let contrast = self.contrast as f32 / 100.0;
+        let mut tables = [[0; 256]; 3];
+        for (table, gamma) in tables.iter_mut().zip(self.gamma) {
+            let exponent = 100.0 / gamma.max(1) as f32;
+            for (value, entry) in table.iter_mut().enumerate() {
+                let contrasted = ((value as f32 / 255.0 - 0.5) * contrast + 0.5).clamp(0.0, 1.0);
+                *entry = (contrasted.powf(exponent) * 255.0).round() as u8;
+            }
+        }
+        tables
+    }
+}
+
+/// Applies a [`ColorCorrection`], keeping its tables until it changes
+#[derive(Default)]
+pub struct ColorLut {
+    tables: Option<(ColorCorrection, [[u8; 256]; 3], Option<FilterTables>)>,
+}
+
+impl ColorLut {
+    /// Corrects a `width` x `height` image with rows `stride` bytes apart in
+    /// place, either RGBX or RGB565
+    pub fn apply(
+        &mut self,
+        correction: ColorCorrection,
+        data: &mut [u8],
+        width: usize,
+        height: usize,
+        bytespp: usize,
+        stride: usize,
+    ) {
+        if correction.is_identity() {
+            return;
+        }
+        if self
+            .tables
+            .as_ref()
+            .is_none_or(|(built, ..)| *built != correction)
+        {
+            let filter = correction.filter.matrix().map(FilterTables::new);
+            self.tables = Some((correction, correction.tables(), filter));
+        }
+        let Some((_, [red, green, blue], filter)) = &self.tables else {
+            return;
+        };
+        let correct = |rgb: [u8; 3]| {
+            let [r, g, b] = filter.as_ref().map_or(rgb, |filter| filter.apply(rgb));
+            [red[r as usize], green[g as usize], blue[b as usize]]
+        };
+        for y in 0..height {
+            let row = &mut data[y * stride..y * stride + width * bytespp];
+            if bytespp == 4 {
+                for pixel in row.chunks_exact_mut(4) {
+                    let rgb = correct([pixel[0], pixel[1], pixel[2]]);
+                    pixel[..3].copy_from_slice(&rgb);
+                }
+            } else {
+                for pixel in row.chunks_exact_mut(2) {
+                    let d = u16::from_le_bytes([pixel[0], pixel[1]]);
+                    // Widened to 8 bits so the same tables work
+                    let (r, g, b) = ((d >> 11) & 31, (d >> 5) & 63, d & 31);
+                    let [r, g, b] = correct([
+                        ((r << 3) | (r >> 2)) as u8,
+                        ((g << 2) | (g >> 4)) as u8,
+                        ((b << 3) | (b >> 2)) as u8,
+                    ])
+                    .map(u16::from);
+                    let (r, g, b) = (r >> 3, g >> 2, b >> 3);
+                    pixel.copy_from_slice(&((r << 11) | (g << 5) | b).to_le_bytes());
+                }
+            }
+        }
+    }
+}
+
+/// Which window system the simulator opens its window with on Linux
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WindowSystem {
+    /// Wayland if a compositor is running, otherwise X11
+    #[default]
+    Auto,
+    Wayland,
+    X11,
+}
+
+impl FromStr for WindowSystem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "wayland" => Ok(Self::Wayland),
+            "x11" => Ok(Self::X11),
+            _ => Err(format!("display must be wayland, x11 or auto, not `{}`", s)),
+        }
+    }
+}
+
+impl Rotation {
+    /// The size apps see for a panel of the given physical size
+    pub fn logical_size(&self, physical: (usize, usize)) -> (usize, usize) {
+        match self {
+            Self::None | Self::Cw180 => physical,
+            Self::Cw90 | Self::Cw270 => (physical.1, physical.0),
+        }
+    }
+
+    /// Where a point on a panel of the given physical size is in the frame,
+    /// for touches
+    pub fn to_logical(&self, physical: (usize, usize), (px, py): (usize, usize)) -> (usize, usize) {
+        let (width, height) = self.logical_size(physical);
+        match self {
+            Self::None => (px, py),
+            Self::Cw90 => (py, height - 1 - px),
+            Self::Cw180 => (width - 1 - px, height - 1 - py),
+            Self::Cw270 => (width - 1 - py, px),
+        }
+    }
+
+    /// Copies a `width` x `height` logical frame into `dst`, a physical buffer
+    /// with rows `dst_stride` bytes apart
+    pub fn apply(
+        &self,
+        src: &[u8],
+        width: usize,
+        height: usize,
+        bytespp: usize,
+        dst: &mut [u8],
+        dst_stride: usize,
+    ) {
+        let (physical_width, _) = self.logical_size((width, height));
+        if *self == Self::None {
+            let row = width * bytespp;
+            for y in 0..height {
+                dst[y * dst_stride..y * dst_stride + row]
+                    .copy_from_slice(&src[y * row..(y + 1) * row]);
+            }
+            return;
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                let (px, py) = match self {
+                    Self::None => (x, y),
+                    Self::Cw90 => (physical_width - 1 - y, x),
+                    Self::Cw180 => (width - 1 - x, height - 1 - y),
+                    Self::Cw270 => (y, width - 1 - x),
+                };
+                let s = (y * width + x) * bytespp;
+                let d = py * dst_stride + px * bytespp;
+                dst[d..d + bytespp].copy_from_slice(&src[s..s + bytespp]);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DisplayOptions {
+    pub rotation: Rotation,
+
+    /// Physical resolution to simulate. The device always uses the resolution
+    /// the framebuffer reports.
+    pub size: Option<(usize, usize)>,
+
+    /// The simulator window's size in logical pixels, instead of the last one
+    /// used. The panel is scaled to fit and letterboxed.
+    pub window: Option<(usize, usize)>,
+
+    /// Runs the simulator borderless on the whole screen
+    pub fullscreen: bool,
+
+    pub window_system: WindowSystem,
+}
+
+/// Parses a `WIDTHxHEIGHT` resolution like `720x720`
+pub fn parse_size(s: &str) -> Result<(usize, usize), String> {
+    let (w, h) = s
+        .split_once('x')
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got `{}`", s))?;
+    let parse = |v: &str| v.parse::<usize>().map_err(|e| e.to_string());
+    Ok((parse(w)?, parse(h)?))
+}
+
+/// Updates `app`, scaling its output up if it asked for a lower
+/// [`resolution`](App::resolution). `scratch` holds the low resolution frame
+/// between calls.
+pub fn update_app<A: App + ?Sized>(app: &mut A, ctx: &mut AppContext, scratch: &mut Vec<u8>) {
+    let frame = &*ctx.frame;
+    let Some((width, height)) = app
+        .resolution()
+        .filter(|&(w, h)| w > 0 && h > 0 && w <= frame.width && h <= frame.height)
+    else {
+        app.update(ctx);
+        return;
+    };
+
+    scratch.resize(width * height * frame.bytespp, 0);
+    let mut small = Frame {
+        width,
+        height,
+        bytespp: frame.bytespp,
+        stride: width * frame.bytespp,
+        data: scratch,
+    };
+    // Taps land where the scaled frame was drawn
+    let factor = (frame.width / width).min(frame.height / height);
+    let left = (frame.width - width * factor) / 2;
+    let top = (frame.height - height * factor) / 2;
+    let input = ctx.input.map_pointer(|(x, y)| {
+        let (x, y) = (x.checked_sub(left)? / factor, y.checked_sub(top)? / factor);
+        (x < width && y < height).then_some((x, y))
+    });
+    app.update(&mut ctx.with(&input, &mut small));
+    scale_nearest(&small, ctx.frame);
+}
+
+/// Scales `src` up by the largest whole factor that fits in `dst`, centered
+fn scale_nearest(src: &Frame, dst: &mut Frame) {
+    let factor = (dst.width / src.width).min(dst.height / src.height);
+    let bytespp = dst.bytespp;
+    let left = (dst.width - src.width * factor) / 2;
+    let top = (dst.height - src.height * factor) / 2;
+    let dst_stride = dst.stride;
+    let scaled_row = src.width * factor * bytespp;
+
+    // Borders outside the scaled image
+    if src.width * factor != dst.width || src.height * factor != dst.height {
+        for y in 0..dst.height {
+            dst.row_mut(y).fill(0);
+        }
+    }
+
+    for y in 0..src.height {
+        let first = (top + y * factor) * dst_stride + left * bytespp;
+        let src_row = src.row(y);
+        let row = &mut dst.data[first..first + scaled_row];
+        for (x, pixel) in src_row.chunks_exact(bytespp).enumerate() {
+            for i in 0..factor {
+                let offset = (x * factor + i) * bytespp;
+                row[offset..offset + bytespp].copy_from_slice(pixel);
+            }
+        }
+        // The remaining rows are copies of the first
+        for i in 1..factor {
+            dst.data
+                .copy_within(first..first + scaled_row, first + i * dst_stride);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        // 3x2 frame, one byte per pixel:
+        // 0 1 2
+        // 3 4 5
+        let src = [0, 1, 2, 3, 4, 5];
+        let expected = [
+            (Rotation::None, 3, vec![0, 1, 2, 3, 4, 5]),
+            (Rotation::Cw90, 2, vec![3, 0, 4, 1, 5, 2]),
+            (Rotation::Cw180, 3, vec![5, 4, 3, 2, 1, 0]),
+            (Rotation::Cw270, 2, vec![2, 5, 1, 4, 0, 3]),
+        ];
+        for (rotation, stride, expected) in expected {
+            let mut dst = vec![0; 6];
+            rotation.apply(&src, 3, 2, 1, &mut dst, stride);
+            assert_eq!(dst, expected, "{:?}", rotation);
+            // Each physical pixel maps back to the logical one drawn there
+            let physical = (stride, 6 / stride);
+            for (i, &value) in dst.iter().enumerate() {
+                let (x, y) = rotation.to_logical(physical, (i % stride, i / stride));
+                assert_eq!(src[y * 3 + x], value, "{:?}", rotation);
+            }
+        }
+    }
+
+    #[test]
+    fn test_flip() {
+        // 3x2 panel, two bytes per pixel, with a padding byte after each row
+        let src = [0, 0, 1, 1, 2, 2, 9, 3, 3, 4, 4, 5, 5, 9];
+        let expected = [
+            (Flip::None, [0, 1, 2, 3, 4, 5]),
+            (Flip::Rotate180, [5, 4, 3, 2, 1, 0]),
+            (Flip::Horizontal, [2, 1, 0, 5, 4, 3]),
+            (Flip::Vertical, [3, 4, 5, 0, 1, 2]),
+        ];
+        for (flip, expected) in expected {
+            let mut data = src;
+            flip.apply(&mut data, 3, 2, 2, 7);
+            for (i, &value) in expected.iter().enumerate() {
+                let (x, y) = (i % 3, i / 3);
+                assert_eq!(data[y * 7 + x * 2], value, "{:?}", flip);
+                assert_eq!(data[y * 7 + x * 2 + 1], value, "{:?}", flip);
+                let (sx, sy) = flip.map_point((3, 2), (x, y));
+                assert_eq!(src[sy * 7 + sx * 2], value, "{:?}", flip);
+            }
+            assert_eq!((data[6], data[13]), (9, 9));
+        }
+    }
+
+    #[test]
+    fn test_color_correction() {
+        let correction = ColorCorrection {
+            gamma: [100, 200, 50],
+            contrast: 100,
+            ..Default::default()
+        };
+        let [red, green, blue] = correction.tables();
+        assert_eq!((red[0], red[128], red[255]), (0, 128, 255));
+        assert_eq!((green[0], green[64], green[255]), (0, 128, 255));
+        assert_eq!(blue[128], 64);
+        let high_contrast = ColorCorrection {
+            contrast: 200,
+            ..Default::default()
+        };
+        assert_eq!(high_contrast.tables()[0][60], 0);
+
+        // The padding byte and the padding after the row stay as they were
+        let mut data = [10, 64, 128, 7, 9];
+        ColorLut::default().apply(correction, &mut data, 1, 1, 4, 5);
+        assert_eq!(data, [10, 128, 64, 7, 9]);
+        // Full white in RGB565 stays white
+        let mut data = [0xff, 0xff];
+        ColorLut::default().apply(correction, &mut data, 1, 1, 2, 2);
+        assert_eq!(data, [0xff, 0xff]);
+
+        // Grays stay gray through a filter, while red and green, which look
+        // alike without red cones, get blue to tell them apart
+        let filtered = |filter, rgb: [u8; 3]| {
+            let mut data = [rgb[0], rgb[1], rgb[2], 0];
+            let correction = ColorCorrection {
+                filter,
+                ..Default::default()
+            };
+            ColorLut::default().apply(correction, &mut data, 1, 1, 4, 4);
+            [data[0], data[1], data[2]]
+        };
+        for filter in enum_iterator::all::<ColorFilter>() {
+            let white = filtered(filter, [255; 3]);
+            assert!(white.iter().all(|&v| v >= 253), "{:?}", filter);
+            assert_eq!(filter.name().parse::<ColorFilter>(), Ok(filter));
+        }
+        let seen = filtered(
+            ColorFilter::Simulate(ColorBlindness::Protanopia),
+            [255, 0, 0],
+        );
+        assert!(seen[0] < 128 && seen[1] > seen[2]);
+        let compensated = filtered(
+            ColorFilter::Compensate(ColorBlindness::Protanopia),
+            [255, 0, 0],
+        );
+        assert!(compensated[2] > 128);
+    }
+
+    #[test]
+    fn test_scale_nearest() {
+        let mut src_data = vec![1, 2, 3, 4];
+        let src = Frame {
+            width: 2,
+            height: 2,
+            bytespp: 1,
+            stride: 2,
+            data: &mut src_data,
+        };
+        let mut dst_data = vec![9; 5 * 4];
+        let mut dst = Frame {
+            width: 5,
+            height: 4,
+            bytespp: 1,
+            stride: 5,
+            data: &mut dst_data,
+        };
+        scale_nearest(&src, &mut dst);
+        #[rustfmt::skip]
+        assert_eq!(dst_data, vec![
+            1, 1, 2, 2, 0,
+            1, 1, 2, 2, 0,
+            3, 3, 4, 4, 0,
+            3, 3, 4, 4, 0,
+        ]);
+    }
+}
@@ -0,0 +1,38 @@
+//! What can stop a backend from starting or keep it from running
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The framebuffer device couldn't be opened or configured
+    Framebuffer { path: String, message: String },
+    /// The simulator couldn't start its event loop or open a window
+    Window(String),
+    /// A finished frame couldn't be shown
+    Present(String),
+    /// SDL2 couldn't be initialised
+    Sdl(String),
+}
+
+#[cfg(all(feature = "device", target_os = "linux"))]
+impl Error {
+    pub(crate) fn framebuffer(path: &str, error: impl fmt::Debug) -> Self {
+        Self::Framebuffer {
+            path: path.to_string(),
+            message: format!("{:?}", error),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Framebuffer { path, message } => write!(f, "couldn't use {}: {}", path, message),
+            Error::Window(message) => write!(f, "couldn't open a window: {}", message),
+            Error::Present(message) => write!(f, "couldn't show a frame: {}", message),
+            Error::Sdl(message) => write!(f, "couldn't start SDL: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,167 @@
+//! A typed event bus, so the framework's parts, the menu and apps can tell
+//! each other things without knowing about each other. Anything can
+//! [`publish`] a value of any type, and every [`Subscription`] to that type
+//! gets a copy to take when it's ready, from whichever thread.
+//!
+//! ```ignore
+//! use rg35xx::events::{self, ControllerDisconnected};
+//!
+//! let disconnects = events::subscribe::<ControllerDisconnected>();
+//! events::publish(ControllerDisconnected { player: 1 });
+//! assert_eq!(disconnects.take(), [ControllerDisconnected { player: 1 }]);
+//! ```
+//!
+//! The framework publishes the types below. Apps can publish and subscribe
+//! to their own types just the same.
+
+use std::any::{Any, TypeId};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, Weak};
+
+/// Events a subscriber that's never taken from holds on to, after which
+/// the oldest are dropped
+const MAX_QUEUED: usize = 64;
+
+type Queue<T> = Mutex<VecDeque<T>>;
+
+/// Every subscription's queue, by the type it's for. Dropped subscriptions
+/// are cleared out as events are published.
+static SUBSCRIBERS: Mutex<Vec<(TypeId, Weak<dyn Any + Send + Sync>)>> = Mutex::new(vec![]);
+
+/// Events of one type published since it was made
+pub struct Subscription<T> {
+    queue: Arc<Queue<T>>,
+}
+
+impl<T: Clone + Send + 'static> Subscription<T> {
+    /// Takes the next event, oldest first
+    pub fn next(&self) -> Option<T> {
+        self.queue.lock().unwrap().pop_front()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().unwrap().is_empty()
+    }
+
+    /// Takes every waiting event, oldest first
+    pub fn take(&self) -> Vec<T> {
+        self.queue.lock().unwrap().drain(..).collect()
+    }
+}
+
+impl<T: Clone + Send + 'static> Default for Subscription<T> {
+    fn default() -> Self {
+        subscribe()
+    }
+}
+
+/// Starts collecting the events of type `T` published from now on
+pub fn subscribe<T: Clone + Send + 'static>() -> Subscription<T> {
+    let queue: Arc<Queue<T>> = Arc::default();
+    let erased: Arc<dyn Any + Send + Sync> = queue.clone();
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .push((TypeId::of::<T>(), Arc::downgrade(&erased)));
+    Subscription { queue }
+}
+
+/// Hands `event` to every subscription to its type
+pub fn publish<T: Clone + Send + 'static>(event: T) {
+    let queues: Vec<Arc<Queue<T>>> = {
+        let mut subscribers = SUBSCRIBERS.lock().unwrap();
+        subscribers.retain(|(_, queue)| queue.strong_count() > 0);
+        subscribers
+            .iter()
+            .filter(|(id, _)| *id == TypeId::of::<T>())
+            .filter_map(|(_, queue)| queue.upgrade()?.downcast().ok())
+            .collect()
+    };
+    for queue in queues {
+        let mut queue = queue.lock().unwrap();
+        if queue.len() >= MAX_QUEUED {
+            queue.pop_front();
+        }
+        queue.push_back(event.clone());
+    }
+}
+
+/// A message for the player, which the menu shows as a toast
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notice(pub String);
+
+/// Asks the running game to pause, because the player may not be able to
+/// carry on: a controller went away, or the battery is about to run out.
+/// Games with a pause screen should go to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoPause;
+
+/// Published by [`crate::achievements::unlock`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AchievementUnlocked {
+    pub id: String,
+}
+
+/// A controller was found. Players are numbered from 0, the same as
+/// [`crate::app::Input::player`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControllerConnected {
+    pub player: usize,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControllerDisconnected {
+    pub player: usize,
+}
+
+/// The battery fell below a warning level while not charging; see
+/// [`crate::system::Watcher`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryLow {
+    pub percent: u8,
+}
+
+/// The SoC is getting close to its thermal limit; see
+/// [`crate::system::ThermalMonitor`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Overheating {
+    pub celsius: f32,
+}
+
+/// The device got or lost a network connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkChanged {
+    pub online: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bus() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Ping(u32);
+
+        let first = subscribe::<Ping>();
+        publish(Ping(1));
+        let second = subscribe::<Ping>();
+        let other = subscribe::<Notice>();
+        publish(Ping(2));
+        assert_eq!(first.take(), [Ping(1), Ping(2)]);
+        assert_eq!(second.next(), Some(Ping(2)));
+        assert_eq!(second.next(), None);
+        assert!(other.take().is_empty());
+
+        // Dropped subscriptions stop being published to, and ones that
+        // aren't taken from only keep the newest
+        drop(second);
+        for i in 0..100 {
+            publish(Ping(i));
+        }
+        let waiting = first.take();
+        assert_eq!(waiting.len(), MAX_QUEUED);
+        assert_eq!(waiting.last(), Some(&Ping(99)));
+    }
+}
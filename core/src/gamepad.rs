@@ -0,0 +1,317 @@
+//! Controllers read straight from evdev, an alternative to `stick` for the
+//! device backend, picked with `input = evdev` in `launcher.txt`.
+//!
+//! Every event the kernel reports is passed on, so presses shorter than a
+//! frame aren't lost, and if the kernel's buffer overflows the device's
+//! current state is read back. Events come out as the [`stick::Event`]s stick
+//! would send, so the device backend turns both into buttons with one table.
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    os::fd::AsRawFd,
+    path::PathBuf,
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+use stick::Event;
+
+use crate::events::{publish, ControllerConnected, ControllerDisconnected};
+use crate::touch::{
+    axis_info, find_devices, has_capability, parse_event, EVENT_SIZE, EV_ABS, EV_KEY, EV_SYN,
+    SYN_REPORT,
+};
+
+const SYN_DROPPED: u16 = 0x03;
+const BTN_SOUTH: u16 = 0x130;
+const BTN_EAST: u16 = 0x131;
+const BTN_NORTH: u16 = 0x133;
+const BTN_WEST: u16 = 0x134;
+const BTN_TL: u16 = 0x136;
+const BTN_TR: u16 = 0x137;
+const BTN_SELECT: u16 = 0x13a;
+const BTN_START: u16 = 0x13b;
+const BTN_MODE: u16 = 0x13c;
+const BTN_DPAD_UP: u16 = 0x220;
+const BTN_DPAD_DOWN: u16 = 0x221;
+const BTN_DPAD_LEFT: u16 = 0x222;
+const BTN_DPAD_RIGHT: u16 = 0x223;
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_Z: u16 = 0x02;
+const ABS_RX: u16 = 0x03;
+const ABS_RY: u16 = 0x04;
+const ABS_RZ: u16 = 0x05;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT0Y: u16 = 0x11;
+/// The highest key code, which sizes `EVIOCGKEY`'s bitmap
+const KEY_MAX: usize = 0x2ff;
+
+/// Makes a stick event from a button's state or an axis's position
+type MakeEvent<T> = fn(T) -> Event;
+
+/// The stick event each key is sent as. Face buttons go by position, the
+/// same as in the SDL backend.
+const KEYS: [(u16, MakeEvent<bool>); 13] = [
+    (BTN_EAST, Event::ActionA),
+    (BTN_SOUTH, Event::ActionB),
+    (BTN_WEST, Event::ActionH),
+    (BTN_NORTH, Event::ActionV),
+    (BTN_TL, Event::BumperL),
+    (BTN_TR, Event::BumperR),
+    (BTN_SELECT, Event::MenuL),
+    (BTN_START, Event::MenuR),
+    (BTN_MODE, Event::Exit),
+    (BTN_DPAD_UP, Event::PovUp),
+    (BTN_DPAD_DOWN, Event::PovDown),
+    (BTN_DPAD_LEFT, Event::PovLeft),
+    (BTN_DPAD_RIGHT, Event::PovRight),
+];
+
+/// The stick event each stick and trigger is sent as, scaled to -1..1
+const AXES: [(u16, MakeEvent<f64>); 6] = [
+    (ABS_X, Event::JoyX),
+    (ABS_Y, Event::JoyY),
+    (ABS_RX, Event::CamX),
+    (ABS_RY, Event::CamY),
+    // The triggers are reported as joysticks, like stick does
+    (ABS_Z, Event::JoyZ),
+    (ABS_RZ, Event::CamZ),
+];
+
+/// How often to look for newly plugged in controllers
+const RESCAN: Duration = Duration::from_secs(2);
+
+/// Whether an event device with these capabilities is a controller
+fn is_gamepad(keys: &str, _abs: &str) -> bool {
+    [BTN_SOUTH, BTN_START, BTN_DPAD_UP]
+        .into_iter()
+        .any(|key| has_capability(keys, key))
+}
+
+/// The event for a key's `value`, which is 0 when released, 1 when pressed
+/// and 2 for the kernel's autorepeat. Autorepeats are dropped, since `Input`
+/// repeats held buttons itself.
+fn key_event(code: u16, value: i32) -> Option<Event> {
+    if value == 2 {
+        return None;
+    }
+    KEYS.iter()
+        .find(|&&(key, _)| key == code)
+        .map(|&(_, event)| event(value != 0))
+}
+
+/// The events for a change on an absolute axis, given the range of each of
+/// [`AXES`] the device has. A hat moving releases the opposite direction.
+fn abs_events(ranges: &[(u16, (i32, i32))], code: u16, value: i32) -> Vec<Event> {
+    match code {
+        ABS_HAT0X => vec![Event::PovLeft(value < 0), Event::PovRight(value > 0)],
+        ABS_HAT0Y => vec![Event::PovUp(value < 0), Event::PovDown(value > 0)],
+        _ => {
+            let Some(&(_, (min, max))) = ranges.iter().find(|&&(axis, _)| axis == code) else {
+                return vec![];
+            };
+            let Some(&(_, event)) = AXES.iter().find(|&&(axis, _)| axis == code) else {
+                return vec![];
+            };
+            // A driver reporting an empty or backwards range can't be scaled
+            if min >= max {
+                return vec![];
+            }
+            let scaled = (value.clamp(min, max) - min) as f64 / (max - min) as f64;
+            vec![event(scaled * 2.0 - 1.0)]
+        }
+    }
+}
+
+struct Gamepad {
+    path: PathBuf,
+    file: File,
+    /// The range of each of [`AXES`] the device has
+    ranges: Vec<(u16, (i32, i32))>,
+    /// Events were lost, so everything until the next report is stale
+    dropped: bool,
+}
+
+impl Gamepad {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        let file = File::open(&path)?;
+        let ranges = AXES
+            .iter()
+            .filter_map(|&(axis, _)| axis_info(&file, axis).map(|(_, min, max)| (axis, (min, max))))
+            .collect();
+        Ok(Self {
+            path,
+            file,
+            ranges,
+            dropped: false,
+        })
+    }
+
+    /// The stick events for one evdev event
+    fn translate(&mut self, kind: u16, code: u16, value: i32) -> Vec<Event> {
+        match (kind, code) {
+            (EV_SYN, SYN_DROPPED) => {
+                self.dropped = true;
+                vec![]
+            }
+            (EV_SYN, SYN_REPORT) if self.dropped => {
+                self.dropped = false;
+                self.current_state()
+            }
+            _ if self.dropped => vec![],
+            (EV_KEY, _) => key_event(code, value).into_iter().collect(),
+            (EV_ABS, _) => abs_events(&self.ranges, code, value),
+            _ => vec![],
+        }
+    }
+
+    /// Every key and axis as it is now, to catch up after events were lost
+    fn current_state(&self) -> Vec<Event> {
+        let mut keys = [0u8; KEY_MAX / 8 + 1];
+        let request = (2 << 30) | ((keys.len() as u64) << 16) | ((b'E' as u64) << 8) | 0x18;
+        // SAFETY: EVIOCGKEY fills in at most `keys.len()` bytes
+        let result = unsafe { libc::ioctl(self.file.as_raw_fd(), request as _, keys.as_mut_ptr()) };
+        let mut events = vec![];
+        if result >= 0 {
+            events.extend(
+                KEYS.iter()
+                    .map(|&(code, event)| event(keys[code as usize / 8] & (1 << (code % 8)) != 0)),
+            );
+        }
+        for code in AXES
+            .iter()
+            .map(|&(axis, _)| axis)
+            .chain([ABS_HAT0X, ABS_HAT0Y])
+        {
+            if let Some((value, _, _)) = axis_info(&self.file, code) {
+                events.extend(abs_events(&self.ranges, code, value));
+            }
+        }
+        events
+    }
+}
+
+/// Reads every controller, including ones plugged in later, from one thread
+/// until the receiver goes away. Controllers are numbered in the order
+/// they're found, so the built-in one is 0. A player keeps their number when
+/// another controller's unplugged, and a new one takes the first free number.
+pub fn read_devices<T: From<(usize, Event)>>(sender: Sender<T>) {
+    let mut gamepads: Vec<Option<Gamepad>> = vec![];
+    let mut last_scan: Option<Instant> = None;
+    let mut buffer = [0; EVENT_SIZE * 64];
+    loop {
+        if last_scan.is_none_or(|scan| scan.elapsed() >= RESCAN) {
+            for path in find_devices(is_gamepad) {
+                if gamepads
+                    .iter()
+                    .flatten()
+                    .any(|gamepad| gamepad.path == path)
+                {
+                    continue;
+                }
+                match Gamepad::open(path.clone()) {
+                    Ok(gamepad) => {
+                        let player = gamepads
+                            .iter()
+                            .position(Option::is_none)
+                            .unwrap_or(gamepads.len());
+                        println!("Connected p{}: {}", player + 1, path.display());
+                        publish(ControllerConnected {
+                            player,
+                            name: path.display().to_string(),
+                        });
+                        match gamepads.get_mut(player) {
+                            Some(slot) => *slot = Some(gamepad),
+                            None => gamepads.push(Some(gamepad)),
+                        }
+                    }
+                    Err(e) => println!("Couldn't open controller {}: {}", path.display(), e),
+                }
+            }
+            last_scan = Some(Instant::now());
+        }
+
+        let (players, mut fds): (Vec<usize>, Vec<libc::pollfd>) = gamepads
+            .iter()
+            .enumerate()
+            .filter_map(|(player, gamepad)| {
+                let fd = libc::pollfd {
+                    fd: gamepad.as_ref()?.file.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                };
+                Some((player, fd))
+            })
+            .unzip();
+        // SAFETY: `fds` holds `fds.len()` initialised pollfds
+        let ready = unsafe {
+            libc::poll(
+                fds.as_mut_ptr(),
+                fds.len() as _,
+                RESCAN.as_millis() as libc::c_int,
+            )
+        };
+        if ready <= 0 {
+            continue;
+        }
+
+        let mut gone = vec![];
+        for (&player, fd) in players.iter().zip(&fds) {
+            if fd.revents == 0 {
+                continue;
+            }
+            let Some(gamepad) = gamepads[player].as_mut() else {
+                continue;
+            };
+            let events = match gamepad.file.read(&mut buffer) {
+                Ok(len) if len > 0 => buffer[..len]
+                    .chunks_exact(EVENT_SIZE)
+                    .flat_map(|event| {
+                        let (kind, code, value) = parse_event(event);
+                        gamepad.translate(kind, code, value)
+                    })
+                    .collect(),
+                _ => {
+                    println!("p{} went away: {}", player + 1, gamepad.path.display());
+                    publish(ControllerDisconnected { player });
+                    gone.push(player);
+                    vec![Event::Disconnect]
+                }
+            };
+            for event in events {
+                if sender.send((player, event).into()).is_err() {
+                    return;
+                }
+            }
+        }
+        for player in gone {
+            gamepads[player] = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(key_event(BTN_EAST, 1), Some(Event::ActionA(true)));
+        assert_eq!(key_event(BTN_EAST, 0), Some(Event::ActionA(false)));
+        assert_eq!(key_event(BTN_EAST, 2), None);
+        assert_eq!(key_event(0x1f, 1), None);
+
+        let ranges = [(ABS_X, (0, 255)), (ABS_Z, (0, 1023))];
+        assert_eq!(abs_events(&ranges, ABS_X, 255), [Event::JoyX(1.0)]);
+        assert_eq!(abs_events(&ranges, ABS_Z, 0), [Event::JoyZ(-1.0)]);
+        assert_eq!(abs_events(&ranges, ABS_Y, 10), []);
+        assert_eq!(abs_events(&[(ABS_X, (5, 5))], ABS_X, 5), []);
+        assert_eq!(abs_events(&[(ABS_X, (9, 0))], ABS_X, 5), []);
+        assert_eq!(
+            abs_events(&ranges, ABS_HAT0Y, -1),
+            [Event::PovUp(true), Event::PovDown(false)]
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Position and groundspeed from a GPS receiver, either a USB serial device
+//! speaking NMEA or a local gpsd.
+
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpStream},
+    path::PathBuf,
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+};
+
+use crate::time::{Duration, Instant};
+
+const KNOTS_TO_MPS: f64 = 1852.0 / 3600.0;
+/// Fixes older than this are treated as lost
+const FIX_TIMEOUT: Duration = Duration::from_secs(3);
+/// How long a read waits for the receiver before the reader checks whether
+/// it should stop
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long to wait before opening the source again after it went away
+const RETRY_TIME: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fix {
+    /// Degrees, north positive
+    pub latitude: f64,
+    /// Degrees, east positive
+    pub longitude: f64,
+    /// Groundspeed in meters/second
+    pub speed: f64,
+    /// Degrees true, if moving fast enough for the receiver to tell
+    pub track: Option<f64>,
+    /// Meters above mean sea level
+    pub altitude: Option<f64>,
+    pub received: Instant,
+}
+
+impl Fix {
+    pub fn is_fresh(&self) -> bool {
+        self.received.elapsed() < FIX_TIMEOUT
+    }
+}
+
+/// Checks the `*XX` checksum and returns the comma-separated fields
+fn nmea_fields(line: &str) -> Option<Vec<&str>> {
+    let (body, checksum) = line.trim().strip_prefix('$')?.split_once('*')?;
+    let expected = u8::from_str_radix(checksum.get(..2)?, 16).ok()?;
+    if body.bytes().fold(0, |sum, b| sum ^ b) != expected {
+        return None;
+    }
+    Some(body.split(',').collect())
+}
+
+/// Parses `ddmm.mmmm` (or `dddmm.mmmm`) and a hemisphere into degrees
+fn nmea_coordinate(value: &str, hemisphere: &str) -> Option<f64> {
+    let dot = value.find('.')?;
+    let (degrees, minutes) = value.split_at(dot.checked_sub(2)?);
+    let degrees = degrees.parse::<f64>().ok()? + minutes.parse::<f64>().ok()? / 60.0;
+    match hemisphere {
+        "N" | "E" => Some(degrees),
+        "S" | "W" => Some(-degrees),
+        _ => None,
+    }
+}
+
+/// Applies one NMEA sentence to `fix`. RMC sentences carry the position and
+/// velocity, GGA the altitude; anything else is ignored.
+fn parse_sentence(line: &str, fix: &mut Option<Fix>) {
+    let Some(fields) = nmea_fields(line) else {
+        return;
+    };
+    // The talker ID (GP, GN, GL...) depends on which constellations are used
+    let kind = fields[0].get(2..).unwrap_or("");
+    match kind {
+        "RMC" if fields.len() >= 9 && fields[2] == "A" => {
+            let (Some(latitude), Some(longitude), Ok(knots)) = (
+                nmea_coordinate(fields[3], fields[4]),
+                nmea_coordinate(fields[5], fields[6]),
+                fields[7].parse::<f64>(),
+            ) else {
+                return;
+            };
+            *fix = Some(Fix {
+                latitude,
+                longitude,
+                speed: knots * KNOTS_TO_MPS,
+                track: fields[8].parse().ok(),
+                altitude: fix.and_then(|fix| fix.altitude),
+                received: Instant::now(),
+            });
+        }
+        "GGA" if fields.len() >= 10 => {
+            if let Some(fix) = fix.as_mut() {
+                fix.altitude = fields[9].parse().ok();
+            }
+        }
+        _ => (),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpsSource {
+    /// A serial device such as `/dev/ttyACM0`, and its baud rate
+    Serial(PathBuf, u32),
+    Gpsd(SocketAddr),
+}
+
+impl GpsSource {
+    /// The first USB serial device, or gpsd's default port
+    pub fn detect() -> Self {
+        for path in ["/dev/ttyACM0", "/dev/ttyUSB0"] {
+            if std::path::Path::new(path).exists() {
+                return Self::Serial(path.into(), 9600);
+            }
+        }
+        Self::Gpsd(([127, 0, 0, 1], 2947).into())
+    }
+
+    fn open(&self) -> std::io::Result<Box<dyn Read + Send>> {
+        match self {
+            Self::Serial(path, baud) => {
+                // Raw mode at the right speed; USB CDC receivers ignore the speed.
+                // Reads give up after a second without data.
+                let _ = Command::new("stty")
+                    .arg("-F")
+                    .arg(path)
+                    .arg(baud.to_string())
+                    .arg("raw")
+                    .args(["min", "0", "time", "10"])
+                    .status();
+                Ok(Box::new(std::fs::File::open(path)?))
+            }
+            Self::Gpsd(addr) => {
+                let mut stream = TcpStream::connect_timeout(addr, Duration::from_secs(2))?;
+                stream.set_read_timeout(Some(READ_TIMEOUT))?;
+                stream.write_all(b"?WATCH={\"enable\":true,\"nmea\":true};\n")?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+
+    /// Applies sentences from `reader` to `fix` until the source goes away
+    /// or `stop` is set
+    fn read(&self, reader: Box<dyn Read + Send>, fix: &Mutex<Option<Fix>>, stop: &AtomicBool) {
+        let mut reader = BufReader::new(reader);
+        let mut line = String::new();
+        while !stop.load(Ordering::Relaxed) {
+            match reader.read_line(&mut line) {
+                // A serial device's read timing out, or gpsd closing
+                Ok(0) if matches!(self, Self::Serial(..)) => continue,
+                Ok(0) => return,
+                Ok(_) if line.ends_with('\n') => {
+                    parse_sentence(&line, &mut fix.lock().unwrap());
+                    line.clear();
+                }
+                // The rest of the line's still to come
+                Ok(_) => (),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+/// Reads fixes on a background thread, which is stopped when this is dropped
+pub struct Gps {
+    source: GpsSource,
+    fix: Arc<Mutex<Option<Fix>>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Gps {
+    pub fn start(source: GpsSource) -> Self {
+        let fix = Arc::new(Mutex::new(None));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_source = source.clone();
+        let thread_fix = fix.clone();
+        let thread_stop = stop.clone();
+        let thread = std::thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                match thread_source.open() {
+                    Ok(reader) => thread_source.read(reader, &thread_fix, &thread_stop),
+                    Err(e) => println!("Couldn't open GPS {:?}: {}", thread_source, e),
+                }
+                // Unplugged or not there yet; try again shortly, unless
+                // woken to stop
+                std::thread::park_timeout(RETRY_TIME);
+            }
+        });
+        Self {
+            source,
+            fix,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn source(&self) -> &GpsSource {
+        &self.source
+    }
+
+    /// The latest fix, if it's recent
+    pub fn fix(&self) -> Option<Fix> {
+        self.fix.lock().unwrap().filter(|fix| fix.is_fresh())
+    }
+}
+
+impl Drop for Gps {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_sentence() {
+        let mut fix = None;
+        parse_sentence(
+            "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A",
+            &mut fix,
+        );
+        let rmc = fix.unwrap();
+        assert!((rmc.latitude - 48.1173).abs() < 1e-4);
+        assert!((rmc.longitude - 11.5166).abs() < 1e-4);
+        assert!((rmc.speed - 22.4 * KNOTS_TO_MPS).abs() < 1e-9);
+        assert_eq!(rmc.track, Some(84.4));
+
+        parse_sentence(
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47",
+            &mut fix,
+        );
+        assert_eq!(fix.unwrap().altitude, Some(545.4));
+
+        // Bad checksums are ignored
+        parse_sentence(
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.5,M,46.9,M,,*47",
+            &mut fix,
+        );
+        assert_eq!(fix.unwrap().altitude, Some(545.4));
+    }
+}
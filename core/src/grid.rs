@@ -0,0 +1,265 @@
+//! 2D boards for grid games like Snake, Minesweeper or Sokoban: cells
+//! addressed by [`Point`], moved between by [`Direction`], either wrapping
+//! at the edges or stopping at them.
+
+use std::ops::{Index, IndexMut};
+
+use enum_iterator::Sequence;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Sequence, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    pub fn is_opposite(&self, other: Self) -> bool {
+        self.opposite() == other
+    }
+
+    /// How far one step goes in x and y, with y growing downwards
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            Self::Up => (0, -1),
+            Self::Down => (0, 1),
+            Self::Left => (-1, 0),
+            Self::Right => (1, 0),
+        }
+    }
+}
+
+/// A cell on a grid, as x then y from the top left
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Point(pub usize, pub usize);
+
+impl Point {
+    /// Moved by `(dx, dy)`, coming back in on the other side of a `width` x
+    /// `height` grid if it goes off one
+    pub fn wrapped_offset(&self, (dx, dy): (isize, isize), width: usize, height: usize) -> Self {
+        let wrap = |value: usize, delta: isize, size: usize| {
+            (value as isize + delta).rem_euclid(size as isize) as usize
+        };
+        Self(wrap(self.0, dx, width), wrap(self.1, dy, height))
+    }
+
+    /// One step in `direction`, wrapping at the edges of a `width` x `height`
+    /// grid
+    pub fn wrapped(&self, direction: Direction, width: usize, height: usize) -> Self {
+        self.wrapped_offset(direction.offset(), width, height)
+    }
+
+    /// One step in `direction`, or `None` off the edge of a `width` x
+    /// `height` grid
+    pub fn stepped(&self, direction: Direction, width: usize, height: usize) -> Option<Self> {
+        self.offset(direction.offset(), width, height)
+    }
+
+    /// One step in `direction`, staying put at the edges of a `width` x
+    /// `height` grid
+    pub fn clamped(&self, direction: Direction, width: usize, height: usize) -> Self {
+        self.stepped(direction, width, height).unwrap_or(*self)
+    }
+
+    /// Moved by `(dx, dy)`, or `None` off the edge of a `width` x `height`
+    /// grid
+    pub fn offset(&self, (dx, dy): (isize, isize), width: usize, height: usize) -> Option<Self> {
+        let x = self.0.checked_add_signed(dx).filter(|&x| x < width)?;
+        let y = self.1.checked_add_signed(dy).filter(|&y| y < height)?;
+        Some(Self(x, y))
+    }
+}
+
+/// The eight cells around one, clockwise from the top left
+const SURROUNDING: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+];
+
+/// A `width` x `height` board with a `T` in every cell, stored row by row
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// A grid with every cell set to `value`
+    pub fn new(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    /// A grid with each cell set to what `f` returns for it
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Point) -> T) -> Self {
+        Self {
+            width,
+            height,
+            cells: (0..width * height)
+                .map(|i| f(Point(i % width, i / width)))
+                .collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        point.0 < self.width && point.1 < self.height
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.contains(point)
+            .then(|| &self.cells[point.1 * self.width + point.0])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.contains(point)
+            .then(|| &mut self.cells[point.1 * self.width + point.0])
+    }
+
+    /// Sets every cell to `value`
+    pub fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        self.cells.fill(value);
+    }
+
+    /// Every cell's point, row by row
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| Point(i % width, i / width))
+    }
+
+    /// Every cell with its point, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(&self.cells)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Point, &mut T)> {
+        self.points().zip(&mut self.cells)
+    }
+
+    /// The cells above, below, left and right of `point` that are on the grid
+    pub fn neighbors(&self, point: Point) -> impl Iterator<Item = Point> {
+        let (width, height) = (self.width, self.height);
+        enum_iterator::all::<Direction>()
+            .filter_map(move |direction| point.stepped(direction, width, height))
+    }
+
+    /// All eight cells around `point` that are on the grid, including
+    /// diagonals, for counting mines or live cells
+    pub fn surrounding(&self, point: Point) -> impl Iterator<Item = Point> {
+        let (width, height) = (self.width, self.height);
+        SURROUNDING
+            .into_iter()
+            .filter_map(move |offset| point.offset(offset, width, height))
+    }
+
+    /// All eight cells around `point`, wrapping at the edges
+    pub fn wrapped_surrounding(&self, point: Point) -> impl Iterator<Item = Point> {
+        let (width, height) = (self.width, self.height);
+        SURROUNDING
+            .into_iter()
+            .map(move |offset| point.wrapped_offset(offset, width, height))
+    }
+
+    /// One step from `point` in `direction`, wrapping at the edges
+    pub fn wrapped(&self, point: Point, direction: Direction) -> Point {
+        point.wrapped(direction, self.width, self.height)
+    }
+
+    /// One step from `point` in `direction`, staying put at the edges
+    pub fn clamped(&self, point: Point, direction: Direction) -> Point {
+        point.clamped(direction, self.width, self.height)
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    /// Panics if `point` is off the grid
+    fn index(&self, point: Point) -> &T {
+        self.get(point).expect("point off the grid")
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut T {
+        self.get_mut(point).expect("point off the grid")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grid() {
+        let mut grid = Grid::from_fn(3, 2, |Point(x, y)| x + 10 * y);
+        assert_eq!(grid[Point(2, 1)], 12);
+        assert_eq!(grid.get(Point(3, 0)), None);
+        grid[Point(0, 1)] = 99;
+        assert_eq!(
+            grid.iter().map(|(_, &v)| v).collect::<Vec<_>>(),
+            [0, 1, 2, 99, 11, 12]
+        );
+
+        let corner = Point(0, 0);
+        assert_eq!(
+            grid.neighbors(corner).collect::<Vec<_>>(),
+            [Point(0, 1), Point(1, 0)]
+        );
+        assert_eq!(grid.surrounding(Point(1, 0)).count(), 5);
+        assert_eq!(grid.wrapped_surrounding(corner).count(), 8);
+        assert_eq!(grid.wrapped(corner, Direction::Left), Point(2, 0));
+        assert_eq!(grid.wrapped(corner, Direction::Up), Point(0, 1));
+        assert_eq!(grid.clamped(corner, Direction::Up), corner);
+        assert_eq!(grid.clamped(corner, Direction::Right), Point(1, 0));
+        assert!(Direction::Up.is_opposite(Direction::Down));
+        assert!(!Direction::Up.is_opposite(Direction::Left));
+    }
+
+    #[test]
+    fn test_wrapping() {
+        for (width, height) in [(1, 1), (1, 5), (4, 3), (7, 7)] {
+            for point in Grid::new(width, height, ()).points() {
+                for direction in enum_iterator::all::<Direction>() {
+                    let moved = point.wrapped(direction, width, height);
+                    assert!(moved.0 < width && moved.1 < height);
+                    assert_eq!(moved.wrapped(direction.opposite(), width, height), point);
+                }
+            }
+        }
+    }
+}
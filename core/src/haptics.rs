@@ -0,0 +1,230 @@
+//! Rumble for feedback: short [`Pattern`]s of pulses, each with its own
+//! envelope, played on every controller that can rumble. Widgets tick as
+//! the focus moves and buzz on confirming, and apps can [`play`] the named
+//! patterns or their own. How strong it all is comes from
+//! [`Config::haptics`](crate::config::Config::haptics), set in Settings.
+//!
+//! On the device the strength is sent to the controllers as it changes; the
+//! simulator and the browser don't rumble.
+
+use std::borrow::Cow;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll, Waker};
+
+use crate::config::config;
+use crate::time::{Duration, Instant};
+
+/// One rise and fall in strength, starting `at` from the start of its
+/// pattern
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pulse {
+    pub at: Duration,
+    /// Rising from nothing to full
+    pub attack: Duration,
+    /// At full
+    pub hold: Duration,
+    /// Falling back to nothing
+    pub release: Duration,
+    /// Full, from 0 to 1
+    pub strength: f32,
+}
+
+impl Pulse {
+    pub const fn new(at_millis: u64, hold_millis: u64, strength: f32) -> Self {
+        Self {
+            at: Duration::from_millis(at_millis),
+            attack: Duration::ZERO,
+            hold: Duration::from_millis(hold_millis),
+            release: Duration::ZERO,
+            strength,
+        }
+    }
+
+    /// Rises over `attack_millis` and falls over `release_millis` around the
+    /// hold, rather than starting and stopping sharply
+    pub const fn with_envelope(mut self, attack_millis: u64, release_millis: u64) -> Self {
+        self.attack = Duration::from_millis(attack_millis);
+        self.release = Duration::from_millis(release_millis);
+        self
+    }
+
+    fn end(&self) -> Duration {
+        self.at + self.attack + self.hold + self.release
+    }
+
+    /// How strong it is at `elapsed` from the start of its pattern
+    fn strength_at(&self, elapsed: Duration) -> f32 {
+        let Some(t) = elapsed.checked_sub(self.at) else {
+            return 0.0;
+        };
+        let fraction = |t: Duration, of: Duration| t.as_secs_f32() / of.as_secs_f32();
+        let envelope = if t < self.attack {
+            fraction(t, self.attack)
+        } else if t < self.attack + self.hold {
+            1.0
+        } else if t < self.end() - self.at {
+            1.0 - fraction(t - self.attack - self.hold, self.release)
+        } else {
+            0.0
+        };
+        envelope * self.strength
+    }
+}
+
+/// Pulses played together, strongest winning where they overlap
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pulses: Cow<'static, [Pulse]>,
+}
+
+impl Pattern {
+    /// A short, light tap, for moving between things
+    pub const TICK: Self = Self::new(&[Pulse::new(0, 15, 0.4)]);
+    /// Two firm buzzes, for confirming
+    pub const DOUBLE_BUZZ: Self = Self::new(&[Pulse::new(0, 40, 0.8), Pulse::new(90, 40, 0.8)]);
+    /// A strong beat and a weaker one, like a pulse
+    pub const HEARTBEAT: Self = Self::new(&[
+        Pulse::new(0, 60, 0.9).with_envelope(20, 60),
+        Pulse::new(220, 40, 0.5).with_envelope(20, 80),
+    ]);
+    /// A long buzz fading out, for mistakes and losing
+    pub const FAILURE: Self = Self::new(&[Pulse::new(0, 250, 1.0).with_envelope(0, 300)]);
+
+    pub const fn new(pulses: &'static [Pulse]) -> Self {
+        Self {
+            pulses: Cow::Borrowed(pulses),
+        }
+    }
+
+    /// A pattern made while running, e.g. one pulse as long as an explosion
+    pub fn from_pulses(pulses: Vec<Pulse>) -> Self {
+        Self {
+            pulses: Cow::Owned(pulses),
+        }
+    }
+
+    /// How long until the last pulse has died away
+    pub fn length(&self) -> Duration {
+        self.pulses.iter().map(Pulse::end).max().unwrap_or_default()
+    }
+
+    /// How strong it is at `elapsed` from its start, from 0 to 1
+    pub fn strength_at(&self, elapsed: Duration) -> f32 {
+        self.pulses
+            .iter()
+            .map(|pulse| pulse.strength_at(elapsed))
+            .fold(0.0, f32::max)
+    }
+}
+
+struct Playback {
+    playing: Vec<(Pattern, Instant)>,
+    /// The backend's [`Strength`], waiting for a change
+    waker: Option<Waker>,
+}
+
+impl Playback {
+    fn forget_finished(&mut self, now: Instant) {
+        self.playing
+            .retain(|(pattern, start)| now.saturating_duration_since(*start) < pattern.length());
+    }
+}
+
+static PLAYBACK: Mutex<Playback> = Mutex::new(Playback {
+    playing: vec![],
+    waker: None,
+});
+
+/// Starts `pattern`, on top of anything already playing. Nothing plays
+/// during [demos](crate::demo), so the menu's attract mode stays quiet.
+pub fn play(pattern: &Pattern) {
+    if config().haptics == 0 || crate::demo::playing() {
+        return;
+    }
+    let now = Instant::now();
+    let mut playback = PLAYBACK.lock().unwrap();
+    // Without a backend asking for the strength, nothing else forgets them
+    playback.forget_finished(now);
+    playback.playing.push((pattern.clone(), now));
+    if let Some(waker) = playback.waker.take() {
+        waker.wake();
+    }
+}
+
+/// Whether a pattern's still going, so the backend keeps updating the
+/// strength
+pub fn is_playing() -> bool {
+    !PLAYBACK.lock().unwrap().playing.is_empty()
+}
+
+/// How strongly the controllers should rumble now, from 0 to 1, at the
+/// configured strength. Forgets patterns that have finished.
+pub fn strength() -> f32 {
+    let now = Instant::now();
+    let mut playback = PLAYBACK.lock().unwrap();
+    playback.forget_finished(now);
+    let strength = playback
+        .playing
+        .iter()
+        .map(|(pattern, start)| pattern.strength_at(now.saturating_duration_since(*start)))
+        .fold(0.0, f32::max);
+    strength * config().haptics as f32 / 100.0
+}
+
+/// Lets the backend's [`Strength`] see how things have moved on. Call it
+/// every frame while a pattern [`is_playing`].
+pub fn tick() {
+    if let Some(waker) = PLAYBACK.lock().unwrap().waker.take() {
+        waker.wake();
+    }
+}
+
+/// Resolves with the [`strength`] each time it changes, for a backend to
+/// pass on to its controllers
+#[derive(Default)]
+pub struct Strength {
+    last: f32,
+}
+
+impl Future for Strength {
+    type Output = f32;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<f32> {
+        let strength = strength();
+        if strength != self.last {
+            self.last = strength;
+            return Poll::Ready(strength);
+        }
+        PLAYBACK.lock().unwrap().waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_patterns() {
+        let ms = Duration::from_millis;
+        let tick = &Pattern::TICK;
+        assert_eq!(tick.length(), ms(15));
+        assert_eq!(tick.strength_at(ms(5)), 0.4);
+        assert_eq!(tick.strength_at(ms(20)), 0.0);
+
+        let buzz = &Pattern::DOUBLE_BUZZ;
+        assert_eq!(buzz.strength_at(ms(60)), 0.0);
+        assert_eq!(buzz.strength_at(ms(100)), 0.8);
+
+        // Rising and falling with the envelope
+        let pulse = Pulse::new(10, 10, 1.0).with_envelope(10, 20);
+        let pattern = Pattern::from_pulses(vec![pulse]);
+        assert_eq!(pattern.length(), ms(50));
+        assert_eq!(pattern.strength_at(ms(15)), 0.5);
+        assert_eq!(pattern.strength_at(ms(25)), 1.0);
+        assert_eq!(pattern.strength_at(ms(35)), 0.75);
+        assert!(Pattern::HEARTBEAT.strength_at(ms(240)) > 0.0);
+    }
+}
@@ -0,0 +1,44 @@
+//! Blocking HTTP(S) requests with timeouts. Call these from a worker thread,
+//! since a slow server would otherwise freeze the screen.
+
+use std::{io::Read, time::Duration};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Bodies are cut off after this many bytes, so a bad URL can't use up the
+/// device's memory
+const MAX_BODY: u64 = 16 * 1024 * 1024;
+
+fn agent(timeout: Duration) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout(timeout)
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .build()
+}
+
+pub fn get_bytes_with_timeout(url: &str, timeout: Duration) -> Result<Vec<u8>, String> {
+    if !crate::net::is_online() {
+        return Err("No network connection".to_string());
+    }
+    let response = agent(timeout).get(url).call().map_err(|e| e.to_string())?;
+    let mut body = vec![];
+    response
+        .into_reader()
+        .take(MAX_BODY)
+        .read_to_end(&mut body)
+        .map_err(|e| e.to_string())?;
+    Ok(body)
+}
+
+pub fn get_bytes(url: &str) -> Result<Vec<u8>, String> {
+    get_bytes_with_timeout(url, DEFAULT_TIMEOUT)
+}
+
+/// Fetches `url` as text, replacing any invalid UTF-8
+pub fn get(url: &str) -> Result<String, String> {
+    get_bytes(url).map(|body| String::from_utf8_lossy(&body).into_owned())
+}
@@ -0,0 +1,190 @@
+//! Named recordings of an app's input that the app can play back into
+//! itself, e.g. a puzzle's solution or a practised opening. They're kept in
+//! the current profile at `profiles/<profile>/<app>.macros/<name>.txt`, in
+//! the same format as the menu's [demo replays](crate::demo).
+//!
+//! Apps keep an [`InputMacros`] from
+//! [`AppContext::input_macros`](crate::app::AppContext::input_macros) and
+//! pass each update's input through [`InputMacros::input`], using what it
+//! gives back. While recording that's the real input, noted down; while a
+//! macro plays it's the macro's, until it runs out.
+
+use std::{io, path::PathBuf};
+
+use crate::app::{Input, Repeat};
+use crate::demo::{Recorder, Replay};
+use crate::profiles::is_valid_name;
+use crate::time::Duration;
+
+/// A macro being played back, with the input it's pressing
+struct Playback {
+    name: String,
+    replay: Replay,
+    input: Input,
+    elapsed: Duration,
+}
+
+/// One app's macros, recording or playing at most one at a time
+pub struct InputMacros {
+    dir: PathBuf,
+    recording: Option<(String, Recorder)>,
+    playing: Option<Playback>,
+}
+
+impl InputMacros {
+    /// The macros for `app`, e.g. `sokoban`
+    pub fn open(app: &str) -> Self {
+        Self::at(crate::profiles::dir().join(format!("{}.macros", app)))
+    }
+
+    fn at(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            recording: None,
+            playing: None,
+        }
+    }
+
+    fn path(&self, name: &str) -> io::Result<PathBuf> {
+        match is_valid_name(name) {
+            true => Ok(self.dir.join(format!("{}.txt", name))),
+            false => Err(io::Error::other(format!("`{}` isn't a valid name", name))),
+        }
+    }
+
+    /// The names of the saved macros, sorted
+    pub fn list(&self) -> Vec<String> {
+        let mut names = std::fs::read_dir(&self.dir)
+            .map(|read| {
+                read.flatten()
+                    .filter_map(|entry| {
+                        let name = entry.file_name().to_string_lossy().into_owned();
+                        Some(name.strip_suffix(".txt")?.to_string())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// Starts noting down the input as `name`, replacing any macro by that
+    /// name once [`stop`](Self::stop)ped. Stops anything playing.
+    pub fn record(&mut self, name: &str) -> io::Result<()> {
+        self.path(name)?;
+        self.playing = None;
+        self.recording = Some((name.to_string(), Recorder::default()));
+        Ok(())
+    }
+
+    /// Plays the macro saved as `name` from the start, stopping any
+    /// recording without saving it
+    pub fn play(&mut self, name: &str) -> io::Result<()> {
+        let replay = std::fs::read_to_string(self.path(name)?)?
+            .parse::<Replay>()
+            .map_err(io::Error::other)?;
+        self.recording = None;
+        self.playing = Some(Playback {
+            name: name.to_string(),
+            replay,
+            input: Input::with_repeat(Repeat::standard()),
+            elapsed: Duration::ZERO,
+        });
+        Ok(())
+    }
+
+    /// Stops playing, or stops recording and saves the macro, unless a
+    /// [demo](crate::demo) is playing
+    pub fn stop(&mut self) -> io::Result<()> {
+        self.playing = None;
+        let Some((name, recorder)) = self.recording.take() else {
+            return Ok(());
+        };
+        if crate::demo::playing() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.path(&name)?, recorder.finish().to_text())
+    }
+
+    pub fn remove(&self, name: &str) -> io::Result<()> {
+        std::fs::remove_file(self.path(name)?)
+    }
+
+    /// The name of the macro being recorded
+    pub fn recording(&self) -> Option<&str> {
+        self.recording.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    /// The name of the macro being played
+    pub fn playing(&self) -> Option<&str> {
+        self.playing.as_ref().map(|playback| playback.name.as_str())
+    }
+
+    /// The input for the app to use this update instead of `input`. Call it
+    /// once each update. Playback ends by itself after the macro's last
+    /// press or release.
+    pub fn input<'a>(&'a mut self, input: &'a Input) -> &'a Input {
+        if let Some((_, recorder)) = self.recording.as_mut() {
+            recorder.record(input);
+        }
+        if self
+            .playing
+            .as_ref()
+            .is_some_and(|playback| playback.replay.is_finished())
+        {
+            self.playing = None;
+        }
+        let Some(playback) = self.playing.as_mut() else {
+            return input;
+        };
+        playback.input.update();
+        playback.input.set_delta(input.delta());
+        playback.elapsed += input.delta();
+        playback.replay.play(playback.elapsed, &mut playback.input);
+        &playback.input
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::Button;
+
+    #[test]
+    fn test_input_macros() {
+        let dir = std::env::temp_dir().join(format!("rg35xx-macros-{}", std::process::id()));
+        let mut macros = InputMacros::at(dir.clone());
+        assert!(macros.record("../up").is_err());
+
+        // Up for a tenth of a second
+        macros.record("up").unwrap();
+        let mut input = Input::default();
+        input.set_delta(Duration::from_millis(50));
+        input.event(Button::PovUp, true);
+        macros.input(&input);
+        input.update();
+        input.set_delta(Duration::from_millis(100));
+        input.event(Button::PovUp, false);
+        macros.input(&input);
+        macros.stop().unwrap();
+        assert_eq!(macros.list(), ["up"]);
+
+        macros.play("up").unwrap();
+        let quiet = Input::default();
+        let mut step = |millis| {
+            let mut real = quiet.clone();
+            real.set_delta(Duration::from_millis(millis));
+            macros.input(&real).pressed(Button::PovUp)
+        };
+        assert!(!step(10));
+        assert!(step(60));
+        assert!(!step(100));
+        // It's run out, so it's the real input again
+        step(10);
+        assert_eq!(macros.playing(), None);
+
+        macros.remove("up").unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
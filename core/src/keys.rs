@@ -0,0 +1,436 @@
+//! Which keyboard keys stand in for the device's buttons in the simulator.
+//!
+//! The defaults can be changed in `keys.txt` next to the launcher binary,
+//! either by hand or with the simulator's remap mode (F2), e.g.
+//!
+//! ```text
+//! A = Numpad6, ArrowRight
+//! START = Enter
+//! ```
+//!
+//! With `players = 2`, a second cluster (IJKL, with U, O, P, 8, 9, 0, N and M)
+//! is player 2's controller, bound with lines like `P2 A = KeyO`.
+//!
+//! F1 shows the current bindings over the running app.
+
+use std::{io, path::PathBuf};
+
+use enum_iterator::{all, cardinality};
+use enum_map::EnumMap;
+use palette::LinSrgb;
+use winit::keyboard::KeyCode;
+
+use crate::actions::{button_label, parse_button};
+use crate::app::{Button, Frame};
+
+/// Keys that can be bound, by the names used in `keys.txt`
+const KEYS: &[KeyCode] = &[
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadEnter,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+];
+
+/// The name of a key in `keys.txt`, e.g. `KeyA` or `Numpad6`
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(s: &str) -> Result<KeyCode, String> {
+    KEYS.iter()
+        .copied()
+        .find(|&key| key_name(key).eq_ignore_ascii_case(s))
+        .ok_or_else(|| format!("unknown key `{}`", s))
+}
+
+fn path() -> PathBuf {
+    crate::storage::base_dir().join("keys.txt")
+}
+
+/// How many players' keys can be set up, see [`KeyMap::players`]
+pub const MAX_PLAYERS: usize = 2;
+
+/// The keys bound to each button, for each player
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap {
+    bindings: [EnumMap<Button, Vec<KeyCode>>; MAX_PLAYERS],
+    /// Player 2's keys only do anything after `players = 2`
+    players: usize,
+}
+
+impl Default for KeyMap {
+    /// WASD for the d-pad and the numpad or arrow keys for the face buttons,
+    /// and IJKL for player 2's d-pad
+    fn default() -> Self {
+        Self {
+            bindings: [
+                EnumMap::from_fn(|button| match button {
+                    Button::PovLeft => vec![KeyCode::KeyA],
+                    Button::PovDown => vec![KeyCode::KeyS],
+                    Button::PovRight => vec![KeyCode::KeyD],
+                    Button::PovUp => vec![KeyCode::KeyW],
+                    Button::ActionV => vec![KeyCode::Numpad4, KeyCode::ArrowUp],
+                    Button::ActionB => vec![KeyCode::Numpad2, KeyCode::ArrowDown],
+                    Button::ActionA => vec![KeyCode::Numpad6, KeyCode::ArrowRight],
+                    Button::ActionH => vec![KeyCode::Numpad8, KeyCode::ArrowLeft],
+                    // Q and E for keyboards without a numpad
+                    Button::BumperL => vec![KeyCode::Space, KeyCode::KeyQ],
+                    Button::BumperR => vec![KeyCode::Numpad0, KeyCode::KeyE],
+                    Button::MenuR => vec![KeyCode::Period],
+                    Button::MenuL => vec![KeyCode::Comma],
+                }),
+                EnumMap::from_fn(|button| {
+                    vec![match button {
+                        Button::PovLeft => KeyCode::KeyJ,
+                        Button::PovDown => KeyCode::KeyK,
+                        Button::PovRight => KeyCode::KeyL,
+                        Button::PovUp => KeyCode::KeyI,
+                        Button::ActionV => KeyCode::Digit9,
+                        Button::ActionB => KeyCode::KeyP,
+                        Button::ActionA => KeyCode::KeyO,
+                        Button::ActionH => KeyCode::Digit8,
+                        Button::BumperL => KeyCode::KeyU,
+                        Button::BumperR => KeyCode::Digit0,
+                        Button::MenuR => KeyCode::KeyM,
+                        Button::MenuL => KeyCode::KeyN,
+                    }]
+                }),
+            ],
+            players: 1,
+        }
+    }
+}
+
+impl KeyMap {
+    /// The defaults with `keys.txt` applied
+    pub fn load() -> Self {
+        let mut map = Self::default();
+        if let Ok(text) = std::fs::read_to_string(path()) {
+            map.apply(&text);
+        }
+        map
+    }
+
+    /// Applies the lines of a key file, logging any it doesn't understand
+    pub fn apply(&mut self, text: &str) {
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Err(e) = self.apply_line(line) {
+                println!("Ignoring `{}` in keys.txt: {}", line, e);
+            }
+        }
+    }
+
+    fn apply_line(&mut self, line: &str) -> Result<(), String> {
+        let (name, keys) = line
+            .split_once('=')
+            .ok_or_else(|| "expected BUTTON = keys".to_string())?;
+        let (name, keys) = (name.trim(), keys.trim());
+        if name == "players" {
+            self.players = keys
+                .parse::<usize>()
+                .ok()
+                .filter(|players| (1..=MAX_PLAYERS).contains(players))
+                .ok_or_else(|| format!("expected 1 to {}", MAX_PLAYERS))?;
+            return Ok(());
+        }
+        // Player 2's buttons are written `P2 A`
+        let (player, button) = match name.split_once(' ') {
+            Some((prefix, button)) => {
+                let player = prefix
+                    .strip_prefix('P')
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .filter(|n| (1..=MAX_PLAYERS).contains(n))
+                    .ok_or_else(|| format!("unknown player `{}`", prefix))?;
+                (player - 1, button.trim())
+            }
+            None => (0, name),
+        };
+        let button = parse_button(button)?;
+        self.bindings[player][button] = keys
+            .split(',')
+            .map(|key| parse_key(key.trim()))
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("players = {}\n", self.players);
+        for player in 0..MAX_PLAYERS {
+            for button in all::<Button>() {
+                text += &format!(
+                    "{} = {}\n",
+                    Self::button_name(player, button),
+                    self.describe(player, button)
+                );
+            }
+        }
+        text
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        std::fs::write(path(), self.to_text())
+    }
+
+    /// How many players have keys, from 1 to [`MAX_PLAYERS`]
+    pub fn players(&self) -> usize {
+        self.players
+    }
+
+    pub fn set_players(&mut self, players: usize) {
+        self.players = players.clamp(1, MAX_PLAYERS);
+    }
+
+    /// Player numbers count from 0
+    pub fn bind(&mut self, player: usize, button: Button, keys: &[KeyCode]) {
+        self.bindings[player][button] = keys.to_vec();
+    }
+
+    pub fn keys(&self, player: usize, button: Button) -> &[KeyCode] {
+        &self.bindings[player][button]
+    }
+
+    /// The player and button `key` stands in for, if any
+    pub fn button(&self, key: KeyCode) -> Option<(usize, Button)> {
+        (0..self.players).find_map(|player| {
+            all::<Button>()
+                .find(|&button| self.bindings[player][button].contains(&key))
+                .map(|button| (player, button))
+        })
+    }
+
+    /// `A` for player 1, `P2 A` for player 2
+    fn button_name(player: usize, button: Button) -> String {
+        match player {
+            0 => button_label(button).to_string(),
+            _ => format!("P{} {}", player + 1, button_label(button)),
+        }
+    }
+
+    /// The keys for `button` as a list, e.g. `Numpad6, ArrowRight`
+    fn describe(&self, player: usize, button: Button) -> String {
+        self.bindings[player][button]
+            .iter()
+            .map(|&key| key_name(key))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The F1 help and F2 remap screens drawn over the simulated panel
+#[derive(Default)]
+pub struct KeyHelp {
+    pub visible: bool,
+    /// The bindings so far while remapping, and how many buttons have been
+    /// done, counting through each player's in turn
+    remapping: Option<(KeyMap, usize)>,
+    /// Shown at the bottom of the help, e.g. after saving
+    message: Option<String>,
+}
+
+impl KeyHelp {
+    /// Whether keys should go to the help instead of the app
+    pub fn active(&self) -> bool {
+        self.visible || self.remapping.is_some()
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.remapping = None;
+        self.message = None;
+    }
+
+    /// Starts asking for a key for each button in turn
+    pub fn start_remap(&mut self, keys: &KeyMap) {
+        self.visible = true;
+        self.remapping = Some((keys.clone(), 0));
+        self.message = None;
+    }
+
+    /// The player and button remap step `index` asks for
+    fn step(index: usize) -> (usize, Button) {
+        let buttons = cardinality::<Button>();
+        (
+            index / buttons,
+            all::<Button>().nth(index % buttons).unwrap(),
+        )
+    }
+
+    /// Handles a key press while [`active`](Self::active). Finishing a
+    /// remap replaces `keys` and saves them to `keys.txt`.
+    pub fn key_pressed(&mut self, key: KeyCode, keys: &mut KeyMap) {
+        let Some((remapped, index)) = self.remapping.as_mut() else {
+            if key == KeyCode::Escape {
+                self.visible = false;
+            }
+            return;
+        };
+        if key == KeyCode::Escape {
+            self.remapping = None;
+            self.message = Some("Remapping cancelled".to_string());
+            return;
+        }
+        if !KEYS.contains(&key) {
+            self.message = Some(format!("{} can't be bound", key_name(key)));
+            return;
+        }
+        let (player, button) = Self::step(*index);
+        remapped.bind(player, button, &[key]);
+        *index += 1;
+        self.message = None;
+        if *index == remapped.players() * cardinality::<Button>() {
+            *keys = remapped.clone();
+            self.remapping = None;
+            self.message = Some(match keys.save() {
+                Ok(()) => format!("Saved to {}", path().display()),
+                Err(e) => format!("Couldn't save {}: {}", path().display(), e),
+            });
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame, keys: &KeyMap) {
+        if !self.active() {
+            return;
+        }
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 40));
+        let column = (frame.width() - 30) / keys.players();
+        let (title, shown, done) = match &self.remapping {
+            Some((remapped, index)) => {
+                let (player, button) = Self::step(*index);
+                (
+                    format!("Press a key for {}", KeyMap::button_name(player, button)),
+                    remapped,
+                    *index,
+                )
+            }
+            None => ("Keys".to_string(), keys, usize::MAX),
+        };
+        {
+            let mut ctx = frame.context();
+            ctx.offset(30, 40);
+            ctx.set_fontsize(28.0);
+            ctx.text(&title);
+        }
+
+        let buttons = cardinality::<Button>();
+        for player in 0..keys.players() {
+            let mut ctx = frame.context();
+            ctx.offset((30 + player * column) as i32, 50);
+            ctx.set_fontsize(16.0);
+            for (i, button) in all::<Button>().enumerate() {
+                if player * buttons + i >= done {
+                    break;
+                }
+                ctx.offset(0, 22);
+                ctx.text(&format!(
+                    "{}: {}",
+                    KeyMap::button_name(player, button),
+                    shown.describe(player, button)
+                ));
+            }
+        }
+
+        let mut ctx = frame.context();
+        ctx.offset(30, 50 + 22 * buttons as i32);
+        ctx.set_fontsize(16.0);
+        if self.remapping.is_none() {
+            ctx.offset(0, 30);
+            ctx.text("Enter confirms and Escape goes back in any app");
+            ctx.offset(0, 22);
+            ctx.text("F1 closes this, F2 remaps every button");
+        }
+        if let Some(message) = &self.message {
+            ctx.offset(0, 30);
+            ctx.text(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keys_round_trip() {
+        let mut keys = KeyMap::default();
+        keys.bind(0, Button::ActionA, &[KeyCode::KeyX, KeyCode::Enter]);
+        let mut loaded = KeyMap::default();
+        loaded.apply(&keys.to_text());
+        assert_eq!(loaded, keys);
+        assert_eq!(loaded.button(KeyCode::KeyX), Some((0, Button::ActionA)));
+        assert_eq!(loaded.button(KeyCode::Numpad6), None);
+        // Player 2's keys are off until asked for
+        assert_eq!(loaded.button(KeyCode::KeyO), None);
+        loaded.apply("players = 2\nP2 B = KeyZ\n");
+        assert_eq!(loaded.button(KeyCode::KeyO), Some((1, Button::ActionA)));
+        assert_eq!(loaded.button(KeyCode::KeyZ), Some((1, Button::ActionB)));
+    }
+}
@@ -0,0 +1,145 @@
+use palette::LinSrgb;
+use rusttype::{point, Scale};
+
+use crate::app::Frame;
+
+/// Text that is rasterized once and then copied to the frame every time it's
+/// drawn. Changing the text, size, or color re-rasterizes it.
+pub struct Label {
+    font: String,
+    fontsize: f32,
+    color: LinSrgb<u8>,
+    text: String,
+    /// Offset of the top-left of `pixels` from the text's origin
+    left: i32,
+    top: i32,
+    width: usize,
+    height: usize,
+    /// `None` for pixels not covered by any glyph's bounding box
+    pixels: Vec<Option<LinSrgb<u8>>>,
+}
+
+impl Label {
+    pub fn new(font: &str, fontsize: f32, color: LinSrgb<u8>) -> Self {
+        Self {
+            font: font.to_string(),
+            fontsize,
+            color,
+            text: String::new(),
+            left: 0,
+            top: 0,
+            width: 0,
+            height: 0,
+            pixels: vec![],
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        if text != self.text {
+            self.text = text.to_string();
+            self.rasterize();
+        }
+    }
+
+    pub fn set_color(&mut self, color: LinSrgb<u8>) {
+        if color != self.color {
+            self.color = color;
+            self.rasterize();
+        }
+    }
+
+    pub fn set_fontsize(&mut self, fontsize: f32) {
+        if fontsize != self.fontsize {
+            self.fontsize = fontsize;
+            self.rasterize();
+        }
+    }
+
+    /// Size of the rasterized text in pixels
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn rasterize(&mut self) {
+        let Ok(font) = crate::assets::font(&self.font) else {
+            self.rasterize_placeholder();
+            return;
+        };
+        let scale = Scale {
+            x: self.fontsize,
+            y: self.fontsize,
+        };
+        let glyphs = font
+            .layout(&self.text, scale, point(0.0, 0.0))
+            .filter_map(|g| g.pixel_bounding_box().map(|bb| (g, bb)))
+            .collect::<Vec<_>>();
+
+        let Some(left) = glyphs.iter().map(|(_, bb)| bb.min.x).min() else {
+            self.width = 0;
+            self.height = 0;
+            self.pixels.clear();
+            return;
+        };
+        let top = glyphs.iter().map(|(_, bb)| bb.min.y).min().unwrap();
+        let right = glyphs.iter().map(|(_, bb)| bb.max.x).max().unwrap();
+        let bottom = glyphs.iter().map(|(_, bb)| bb.max.y).max().unwrap();
+        self.left = left;
+        self.top = top;
+        self.width = (right - left) as usize;
+        self.height = (bottom - top) as usize;
+        self.pixels.clear();
+        self.pixels.resize(self.width * self.height, None);
+
+        let (r, g, b) = self.color.into_format::<f32>().into_components();
+        for (glyph, bb) in glyphs.iter() {
+            glyph.draw(|x, y, v| {
+                let x = (x as i32 + bb.min.x - left) as usize;
+                let y = (y as i32 + bb.min.y - top) as usize;
+                self.pixels[y * self.width + x] =
+                    Some(LinSrgb::new(r * v, g * v, b * v).into_format());
+            });
+        }
+    }
+
+    /// An outline roughly the size of the text, like [`Frame::text`] draws
+    /// when the font is missing
+    fn rasterize_placeholder(&mut self) {
+        self.width = (self.text.chars().count() as f32 * self.fontsize * 0.5) as usize + 1;
+        self.height = (self.fontsize * 0.75) as usize + 1;
+        self.left = 0;
+        self.top = -(self.height as i32) + 1;
+        self.pixels.clear();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let edge = x == 0 || y == 0 || x == self.width - 1 || y == self.height - 1;
+                self.pixels.push(edge.then_some(self.color));
+            }
+        }
+    }
+
+    /// Draws the text with its origin (the left end of the baseline) at `x`, `y`,
+    /// matching [`Frame::text`]
+    pub fn draw(&self, frame: &mut Frame, x: usize, y: usize) {
+        let left = x as i32 + self.left;
+        let top = y as i32 + self.top;
+        for row in 0..self.height {
+            let py = top + row as i32;
+            if py < 0 {
+                continue;
+            }
+            for col in 0..self.width {
+                let px = left + col as i32;
+                if px < 0 {
+                    continue;
+                }
+                if let Some(color) = self.pixels[row * self.width + col] {
+                    frame.put_pixel(px as usize, py as usize, color);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! The framework the launcher and its apps are built on: the [`app::App`]
+//! trait, backends for the device, the simulator and the browser, drawing,
+//! input and storage. The bundled apps live in the `rg35xx-apps` crate at
+//! the root of the workspace.
+
+pub mod achievements;
+pub mod actions;
+pub mod anim;
+pub mod app;
+pub mod assets;
+pub mod audio;
+pub mod axes;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod backend;
+pub mod bitmap_font;
+pub mod board;
+pub mod config;
+pub mod demo;
+pub mod display;
+pub mod error;
+pub mod events;
+#[cfg(all(feature = "device", target_os = "linux"))]
+pub mod gamepad;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gps;
+pub mod grid;
+pub mod haptics;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub mod http;
+pub mod input_macros;
+#[cfg(feature = "sim")]
+pub mod keys;
+pub mod label;
+pub mod lobby;
+pub mod lock;
+pub mod memory;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod net;
+pub mod ninepatch;
+pub mod overlay;
+pub mod particles;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod profiles;
+pub mod qr;
+pub mod random;
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+pub mod remote;
+#[cfg(all(feature = "device", target_os = "linux"))]
+pub mod rg35xx;
+pub mod savestate;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(feature = "sdl2")]
+pub mod sdl;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod storage;
+pub mod surface;
+pub mod system;
+pub mod tasks;
+pub mod testing;
+pub mod text_block;
+pub mod time;
+#[cfg(target_os = "linux")]
+pub mod touch;
+pub mod transition;
+pub mod units;
+pub mod url;
+#[cfg(all(feature = "sim", target_arch = "wasm32"))]
+pub mod web;
+pub mod widgets;
+
+pub use error::Error;
@@ -0,0 +1,264 @@
+//! Finding and talking to another device on the local network, for party games.
+//!
+//! One side [hosts](Host), announcing itself with UDP broadcasts, and the other
+//! [browses](Browser) for announcements and connects. The two then exchange
+//! newline-delimited text messages over a TCP [`Session`]. Everything is
+//! non-blocking so it can be polled from an app's update.
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream, UdpSocket},
+};
+
+use crate::time::{Duration, Instant};
+
+pub const DISCOVERY_PORT: u16 = 35735;
+
+/// Start of every announcement, with the protocol version
+const MAGIC: &str = "rg35xx-lobby 1";
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+/// Hosts that haven't announced themselves for this long are forgotten
+const ADVERT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What this device calls itself in the lobby
+pub fn device_name() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_string())
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "rg35xx".to_string())
+}
+
+/// Waits for a player to join a game
+pub struct Host {
+    game: String,
+    name: String,
+    listener: TcpListener,
+    broadcast: UdpSocket,
+    last_announce: Option<Instant>,
+    /// Whether the last announcement failed, so a failure's logged once
+    announce_failed: bool,
+}
+
+impl Host {
+    pub fn start(game: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        listener.set_nonblocking(true)?;
+        let broadcast = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        broadcast.set_broadcast(true)?;
+        Ok(Self {
+            game: game.to_string(),
+            name: device_name(),
+            listener,
+            broadcast,
+            last_announce: None,
+            announce_failed: false,
+        })
+    }
+
+    fn announce(&self) -> io::Result<()> {
+        let port = self.listener.local_addr()?.port();
+        let message = format!("{} {} {} {}", MAGIC, self.game, port, self.name);
+        self.broadcast
+            .send_to(message.as_bytes(), (Ipv4Addr::BROADCAST, DISCOVERY_PORT))?;
+        Ok(())
+    }
+
+    /// Announces the game now and then, and returns the session once someone
+    /// joins. Announcing can fail without a broadcast route, e.g. over USB
+    /// networking alone, while a player can still connect, so only a
+    /// failure to accept is an error.
+    pub fn poll(&mut self) -> io::Result<Option<Session>> {
+        if self
+            .last_announce
+            .is_none_or(|last| last.elapsed() >= ANNOUNCE_INTERVAL)
+        {
+            self.last_announce = Some(Instant::now());
+            match self.announce() {
+                Ok(()) => self.announce_failed = false,
+                Err(e) if !self.announce_failed => {
+                    println!("Couldn't announce {}: {}", self.game, e);
+                    self.announce_failed = true;
+                }
+                Err(_) => (),
+            }
+        }
+
+        match self.listener.accept() {
+            Ok((stream, _)) => Session::new(stream).map(Some),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// A game that's waiting for players
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Advert {
+    /// The host's device name
+    pub name: String,
+    pub addr: SocketAddr,
+}
+
+/// Listens for hosts announcing a game
+pub struct Browser {
+    game: String,
+    socket: UdpSocket,
+    adverts: Vec<(Advert, Instant)>,
+}
+
+impl Browser {
+    pub fn start(game: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            game: game.to_string(),
+            socket,
+            adverts: vec![],
+        })
+    }
+
+    /// Hosts currently offering this game
+    pub fn poll(&mut self) -> Vec<Advert> {
+        let mut buf = [0; 512];
+        while let Ok((len, from)) = self.socket.recv_from(&mut buf) {
+            let Some(advert) = self.parse(&buf[..len], from) else {
+                continue;
+            };
+            self.adverts.retain(|(a, _)| a.addr != advert.addr);
+            self.adverts.push((advert, Instant::now()));
+        }
+        self.adverts
+            .retain(|(_, seen)| seen.elapsed() < ADVERT_TIMEOUT);
+        self.adverts.iter().map(|(a, _)| a.clone()).collect()
+    }
+
+    fn parse(&self, message: &[u8], from: SocketAddr) -> Option<Advert> {
+        let message = std::str::from_utf8(message).ok()?;
+        let mut parts = message.strip_prefix(MAGIC)?.trim().splitn(3, ' ');
+        if parts.next()? != self.game {
+            return None;
+        }
+        let port = parts.next()?.parse().ok()?;
+        Some(Advert {
+            name: parts.next().unwrap_or("").to_string(),
+            addr: SocketAddr::new(from.ip(), port),
+        })
+    }
+}
+
+/// A connection to the other device
+pub struct Session {
+    stream: TcpStream,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    connected: bool,
+}
+
+impl Session {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            incoming: vec![],
+            outgoing: vec![],
+            connected: true,
+        })
+    }
+
+    pub fn connect(advert: &Advert) -> io::Result<Self> {
+        Self::new(TcpStream::connect_timeout(
+            &advert.addr,
+            Duration::from_secs(3),
+        )?)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Queues a message, which mustn't contain newlines. It's sent by the next
+    /// [`Session::poll`].
+    pub fn send(&mut self, message: &str) {
+        debug_assert!(!message.contains('\n'));
+        self.outgoing.extend_from_slice(message.as_bytes());
+        self.outgoing.push(b'\n');
+    }
+
+    /// Sends anything queued and returns the messages that have arrived
+    pub fn poll(&mut self) -> Vec<String> {
+        if !self.connected {
+            return vec![];
+        }
+
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => self.connected = false,
+                Ok(n) => {
+                    self.outgoing.drain(..n);
+                    continue;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => (),
+                Err(_) => self.connected = false,
+            }
+            break;
+        }
+
+        let mut buf = [0; 1024];
+        loop {
+            match self.stream.read(&mut buf) {
+                Ok(0) => {
+                    self.connected = false;
+                    break;
+                }
+                Ok(n) => self.incoming.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => {
+                    self.connected = false;
+                    break;
+                }
+            }
+        }
+
+        let mut messages = vec![];
+        while let Some(end) = self.incoming.iter().position(|&b| b == b'\n') {
+            let line = self.incoming.drain(..=end).collect::<Vec<_>>();
+            messages.push(String::from_utf8_lossy(&line[..end]).into_owned());
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_session_messages() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let advert = Advert {
+            name: "test".to_string(),
+            addr: listener.local_addr().unwrap(),
+        };
+        let mut client = Session::connect(&advert).unwrap();
+        let mut server = Session::new(listener.accept().unwrap().0).unwrap();
+
+        client.send("hello");
+        client.send("world");
+        client.poll();
+        let mut received = vec![];
+        let start = Instant::now();
+        while received.len() < 2 && start.elapsed() < Duration::from_secs(5) {
+            received.extend(server.poll());
+        }
+        assert_eq!(received, vec!["hello", "world"]);
+
+        drop(client);
+        let start = Instant::now();
+        while server.is_connected() && start.elapsed() < Duration::from_secs(5) {
+            server.poll();
+        }
+        assert!(!server.is_connected());
+    }
+}
@@ -0,0 +1,169 @@
+//! A PIN entered on the D-pad that guards chosen menu entries, so the device
+//! can be handed round without anyone wandering into Settings. Only a hash of
+//! the PIN is kept, in `launcher.txt`, along with the names of the locked
+//! entries:
+//!
+//! ```text
+//! pin = 3b1c...
+//! locked = Settings, RetroArch
+//! ```
+
+use sha2::{Digest, Sha256};
+
+use crate::actions::{button_label, Action, ActionMap};
+use crate::app::{Button, Frame, Input};
+use crate::config::config;
+use crate::haptics::{self, Pattern};
+
+/// PINs are between this many and [`MAX_LENGTH`] presses
+pub const MIN_LENGTH: usize = 4;
+pub const MAX_LENGTH: usize = 8;
+
+const DIRECTIONS: [Button; 4] = [
+    Button::PovUp,
+    Button::PovDown,
+    Button::PovLeft,
+    Button::PovRight,
+];
+
+/// The presses in `pin` written out, e.g. `UP,UP,LEFT,DOWN`
+pub(crate) fn pin_text(pin: &[Button]) -> String {
+    pin.iter()
+        .map(|&button| button_label(button))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// What goes in `launcher.txt` for `pin`, as lowercase hex
+pub fn hash_pin(pin: &[Button]) -> String {
+    Sha256::digest(format!("rg35xx pin {}", pin_text(pin)))
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Whether `pin` is the one that's been set. Always false if there isn't one.
+pub fn check(pin: &[Button]) -> bool {
+    config().pin.is_some_and(|hash| hash == hash_pin(pin))
+}
+
+/// Whether the menu entry called `name` needs the PIN to open
+pub fn is_locked(name: &str) -> bool {
+    let config = config();
+    config.pin.is_some() && config.locked.iter().any(|locked| locked == name)
+}
+
+/// What the player did on a [`PinPad`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinEvent {
+    Entered(Vec<Button>),
+    Cancelled,
+}
+
+/// Asks for a PIN. The D-pad adds to it, [`Action::Confirm`] finishes, and
+/// [`Action::Back`] takes back the last press or cancels if there's nothing
+/// to take back.
+pub struct PinPad {
+    title: String,
+    entered: Vec<Button>,
+    /// Shown under the PIN, e.g. after a wrong one
+    message: String,
+}
+
+impl PinPad {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            entered: vec![],
+            message: String::new(),
+        }
+    }
+
+    /// Clears what's been entered and says why, with a failed buzz
+    pub fn reject(&mut self, message: &str) {
+        self.entered.clear();
+        self.message = message.to_string();
+        haptics::play(&Pattern::FAILURE);
+    }
+
+    pub fn update(
+        &mut self,
+        input: &Input,
+        actions: &ActionMap,
+        frame: &mut Frame,
+    ) -> Option<PinEvent> {
+        let mut event = None;
+        for button in DIRECTIONS {
+            if input.just_pressed(button) && self.entered.len() < MAX_LENGTH {
+                self.entered.push(button);
+                self.message.clear();
+            }
+        }
+        if actions.just_pressed(input, Action::Confirm) {
+            if self.entered.len() >= MIN_LENGTH {
+                event = Some(PinEvent::Entered(std::mem::take(&mut self.entered)));
+            } else {
+                self.message = format!("At least {} presses", MIN_LENGTH);
+            }
+        } else if actions.just_pressed(input, Action::Back) && self.entered.pop().is_none() {
+            event = Some(PinEvent::Cancelled);
+        }
+
+        self.draw(actions, frame);
+        event
+    }
+
+    fn draw(&self, actions: &ActionMap, frame: &mut Frame) {
+        let colors = config().theme.colors();
+        frame.fill_rect(0, 0, frame.width(), frame.height(), colors.background);
+        let mut ctx = frame.context();
+        ctx.offset(50, 60);
+        ctx.set_color(colors.selected);
+        ctx.set_fontsize(36.0);
+        ctx.text(&self.title);
+        // The presses themselves stay hidden from anyone looking over a shoulder
+        ctx.offset(0, 70);
+        ctx.set_fontsize(48.0);
+        let dots = (0..MAX_LENGTH)
+            .map(|i| if i < self.entered.len() { "*" } else { "_" })
+            .collect::<Vec<_>>()
+            .join(" ");
+        ctx.text(&dots);
+        ctx.offset(0, 45);
+        ctx.set_color(colors.text);
+        ctx.set_fontsize(18.0);
+        ctx.text(&self.message);
+
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            frame.height() - 20,
+            18.0,
+            colors.dim,
+            &format!(
+                "D-pad to enter, {} done, {} delete",
+                actions.label(Action::Confirm),
+                actions.label(Action::Back)
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hash_pin() {
+        let pin = [
+            Button::PovUp,
+            Button::PovUp,
+            Button::PovLeft,
+            Button::PovDown,
+        ];
+        assert_eq!(hash_pin(&pin), hash_pin(&pin));
+        assert_eq!(hash_pin(&pin).len(), 64);
+        assert_ne!(hash_pin(&pin), hash_pin(&pin[..3]));
+        assert_ne!(hash_pin(&pin), hash_pin(&[Button::PovUp; 4]));
+    }
+}
@@ -0,0 +1,141 @@
+//! How much heap the launcher's using, so an app holding on to more than it
+//! needs shows up before it runs the device out of memory. With the
+//! `alloc-tracking` feature every allocation goes through a counting wrapper
+//! around the system allocator; without it nothing's counted and
+//! [`current`] and [`peak`] are `None`.
+//!
+//! The menu notes each app's [`Span`] from launch to exit and logs it, and
+//! the debug overlay shows the usage as it goes.
+
+#[cfg(feature = "alloc-tracking")]
+mod tracking {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    pub static CURRENT: AtomicUsize = AtomicUsize::new(0);
+    pub static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counting;
+
+    fn allocated(size: usize) {
+        let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(current, Ordering::Relaxed);
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                allocated(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                allocated(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new = System.realloc(ptr, layout, new_size);
+            if !new.is_null() {
+                CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+                allocated(new_size);
+            }
+            new
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+}
+
+/// Bytes allocated and not yet freed
+pub fn current() -> Option<usize> {
+    #[cfg(feature = "alloc-tracking")]
+    return Some(tracking::CURRENT.load(std::sync::atomic::Ordering::Relaxed));
+    #[cfg(not(feature = "alloc-tracking"))]
+    None
+}
+
+/// The most [`current`] has been since the last [`reset_peak`]
+pub fn peak() -> Option<usize> {
+    #[cfg(feature = "alloc-tracking")]
+    return Some(tracking::PEAK.load(std::sync::atomic::Ordering::Relaxed));
+    #[cfg(not(feature = "alloc-tracking"))]
+    None
+}
+
+/// Starts the [`peak`] again from what's allocated now
+pub fn reset_peak() {
+    #[cfg(feature = "alloc-tracking")]
+    {
+        use std::sync::atomic::Ordering;
+        let current = tracking::CURRENT.load(Ordering::Relaxed);
+        tracking::PEAK.store(current, Ordering::Relaxed);
+    }
+}
+
+/// A number of bytes in KB or MB, whichever reads better
+pub fn format_bytes(bytes: usize) -> String {
+    const MB: usize = 1024 * 1024;
+    if bytes < MB {
+        format!("{:.1} KB", bytes as f32 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f32 / MB as f32)
+    }
+}
+
+/// The heap used over a stretch of time, e.g. while an app runs. Starting
+/// one [resets the peak](reset_peak), so only one should be running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// What was allocated when it started
+    start: Option<usize>,
+}
+
+impl Span {
+    pub fn start() -> Self {
+        reset_peak();
+        Self { start: current() }
+    }
+
+    /// A line for the log: the most used above what was allocated at the
+    /// start, and how much of it is still held. `None` without tracking.
+    pub fn report(&self, name: &str) -> Option<String> {
+        let (start, current, peak) = (self.start?, current()?, peak()?);
+        Some(format!(
+            "{} used {} at most, {} of it still held",
+            name,
+            format_bytes(peak.saturating_sub(start)),
+            format_bytes(current.saturating_sub(start)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_memory() {
+        assert_eq!(format_bytes(512), "0.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 / 2), "1.5 MB");
+
+        let span = Span::start();
+        let held = vec![0u8; 1 << 20];
+        let report = span.report("Test");
+        assert_eq!(report.is_some(), cfg!(feature = "alloc-tracking"));
+        if let Some(peak) = peak() {
+            assert!(peak >= held.len());
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Network status and plain blocking sockets, for devices with a USB Wi-Fi or
+//! ethernet adapter plugged in.
+
+use std::{
+    io,
+    net::{IpAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    time::Duration,
+};
+
+const NET_DIR: &str = "/sys/class/net";
+/// Where wpa_supplicant's networks are configured on the firmwares the device
+/// runs, the first that exists is read
+const WPA_SUPPLICANT_CONFIGS: [&str; 3] = [
+    "/etc/wpa_supplicant/wpa_supplicant.conf",
+    "/etc/wpa_supplicant.conf",
+    "/mnt/mmc/wpa_supplicant.conf",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interface {
+    pub name: String,
+    /// Whether the link is up
+    pub up: bool,
+    pub wireless: bool,
+    pub addresses: Vec<IpAddr>,
+}
+
+impl Interface {
+    pub fn is_loopback(&self) -> bool {
+        self.name == "lo"
+    }
+}
+
+/// Every network interface, sorted by name
+pub fn interfaces() -> Vec<Interface> {
+    let addresses = if_addrs::get_if_addrs().unwrap_or_default();
+    let Ok(entries) = std::fs::read_dir(NET_DIR) else {
+        return vec![];
+    };
+    let mut interfaces = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let dir = entry.path();
+            let up = std::fs::read_to_string(dir.join("operstate"))
+                .is_ok_and(|state| matches!(state.trim(), "up" | "unknown"));
+            Interface {
+                wireless: dir.join("wireless").exists(),
+                up,
+                addresses: addresses
+                    .iter()
+                    .filter(|a| a.name == name)
+                    .map(|a| a.ip())
+                    .collect(),
+                name,
+            }
+        })
+        .collect::<Vec<_>>();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    interfaces
+}
+
+/// Whether any interface other than loopback is up with an address
+pub fn is_online() -> bool {
+    interfaces()
+        .iter()
+        .any(|i| !i.is_loopback() && i.up && !i.addresses.is_empty())
+}
+
+/// Connects to the first of `addr`'s addresses that answers within `timeout`.
+/// Reads and writes on the stream also time out after `timeout`.
+pub fn connect<A: ToSocketAddrs>(addr: A, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "no addresses to connect to")
+    }))
+}
+
+/// A UDP socket on `port` (0 for any) whose reads give up after `timeout`
+pub fn udp_socket(port: u16, timeout: Duration) -> io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(("0.0.0.0", port))?;
+    socket.set_read_timeout(Some(timeout))?;
+    Ok(socket)
+}
+
+/// A Wi-Fi network the device is set up to join
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    /// `None` for an open network
+    pub password: Option<String>,
+}
+
+/// The networks in wpa_supplicant's configuration, in the order they're
+/// listed. Networks saved with a hashed key rather than the password are left
+/// out, as there's no password to give.
+pub fn wifi_networks() -> Vec<WifiNetwork> {
+    WPA_SUPPLICANT_CONFIGS
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|text| parse_wpa_supplicant(&text))
+        .unwrap_or_default()
+}
+
+fn parse_wpa_supplicant(text: &str) -> Vec<WifiNetwork> {
+    let quoted = |value: &str| {
+        let value = value.strip_prefix('"')?.strip_suffix('"')?;
+        Some(value.to_string())
+    };
+    let mut networks = vec![];
+    // The ssid, psk and whether it's open, of the network block being read
+    let mut block: Option<(Option<String>, Option<String>, bool)> = None;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("network=") {
+            block = Some((None, None, false));
+            continue;
+        }
+        let Some((ssid, psk, open)) = &mut block else {
+            continue;
+        };
+        match line.split_once('=') {
+            Some(("ssid", value)) => *ssid = quoted(value),
+            Some(("psk", value)) => *psk = Some(value.to_string()),
+            Some(("key_mgmt", value)) => *open = value == "NONE",
+            _ if line == "}" => {
+                let (ssid, psk, open) = block.take().unwrap();
+                let password = psk.as_deref().and_then(quoted);
+                if let Some(ssid) = ssid.filter(|_| open || password.is_some()) {
+                    networks.push(WifiNetwork {
+                        ssid,
+                        password: password.filter(|_| !open),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+    networks
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_wpa_supplicant() {
+        let config = r#"
+ctrl_interface=/var/run/wpa_supplicant
+update_config=1
+
+network={
+    ssid="Home"
+    psk="correct horse"
+}
+network={
+    ssid="Hashed"
+    psk=4f8a1e0d9c3b2a1f4f8a1e0d9c3b2a1f4f8a1e0d9c3b2a1f4f8a1e0d9c3b2a1f
+}
+network={
+    ssid="Cafe"
+    key_mgmt=NONE
+}
+"#;
+        assert_eq!(
+            parse_wpa_supplicant(config),
+            [
+                WifiNetwork {
+                    ssid: "Home".to_string(),
+                    password: Some("correct horse".to_string()),
+                },
+                WifiNetwork {
+                    ssid: "Cafe".to_string(),
+                    password: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Panels drawn from a small image at any size, by keeping its corners as
+//! they are and stretching its edges and middle: nine-slice scaling.
+//!
+//! Images ending in `.9.png` follow Android's convention of a one pixel
+//! border around the image, where black pixels along the top and left mark
+//! the columns and rows to stretch. Replacing a [theme's
+//! panel](crate::config::Theme::panel) in the assets directory changes how
+//! every dialog and toast looks.
+
+use std::sync::Arc;
+
+use image::RgbaImage;
+use palette::{LinSrgb, Mix};
+
+use crate::{app::Frame, config::config};
+
+pub struct NinePatch {
+    image: Arc<RgbaImage>,
+    /// The top left and size of the part of `image` that's drawn, leaving
+    /// out the markers of a `.9.png`
+    content: (u32, u32, u32, u32),
+    /// The columns that stretch, from the left of the content
+    stretch_x: (u32, u32),
+    /// The rows that stretch, from the top of the content
+    stretch_y: (u32, u32),
+}
+
+impl NinePatch {
+    /// Loads the panel at `path` from the assets, reading its stretchable
+    /// area from the markers if it's a `.9.png` and taking the middle third
+    /// otherwise
+    pub fn load(path: &str) -> Result<Self, String> {
+        let image = crate::assets::image(path)?;
+        if path.ends_with(".9.png") {
+            Self::from_markers(image)
+                .ok_or_else(|| format!("{} is too small for a nine-patch", path))
+        } else {
+            let (width, height) = image.dimensions();
+            let border = (width / 3, height / 3, width / 3, height / 3);
+            Ok(Self::new(image, border))
+        }
+    }
+
+    /// A panel from a plain image, where `border` is how wide the fixed left,
+    /// top, right and bottom edges are
+    pub fn new(image: Arc<RgbaImage>, border: (u32, u32, u32, u32)) -> Self {
+        let (width, height) = image.dimensions();
+        let (left, top, right, bottom) = border;
+        Self {
+            content: (0, 0, width, height),
+            stretch_x: stretch_range(left, width.saturating_sub(right), width),
+            stretch_y: stretch_range(top, height.saturating_sub(bottom), height),
+            image,
+        }
+    }
+
+    /// A panel from an image with `.9.png` markers around it
+    pub fn from_markers(image: Arc<RgbaImage>) -> Option<Self> {
+        let (width, height) = image.dimensions();
+        if width < 3 || height < 3 {
+            return None;
+        }
+        let is_marker = |x, y| image.get_pixel(x, y).0 == [0, 0, 0, 255];
+        let marked = |positions: &mut dyn Iterator<Item = (u32, u32, u32)>, size| {
+            let marked = positions
+                .filter(|&(x, y, _)| is_marker(x, y))
+                .map(|(_, _, i)| i)
+                .collect::<Vec<_>>();
+            match (marked.first(), marked.last()) {
+                (Some(&start), Some(&end)) => stretch_range(start, end + 1, size),
+                _ => (0, size),
+            }
+        };
+        let (inner_width, inner_height) = (width - 2, height - 2);
+        let stretch_x = marked(&mut (1..width - 1).map(|x| (x, 0, x - 1)), inner_width);
+        let stretch_y = marked(&mut (1..height - 1).map(|y| (0, y, y - 1)), inner_height);
+        Some(Self {
+            content: (1, 1, inner_width, inner_height),
+            stretch_x,
+            stretch_y,
+            image,
+        })
+    }
+
+    /// The smallest size the panel can be drawn at without squashing its
+    /// corners
+    pub fn min_size(&self) -> (usize, usize) {
+        let (_, _, width, height) = self.content;
+        let fixed = |(start, end): (u32, u32), size: u32| (start + size - end) as usize;
+        (fixed(self.stretch_x, width), fixed(self.stretch_y, height))
+    }
+
+    /// Draws the panel `width` x `height` with its top left at `x`, `y`,
+    /// which may be partly off screen
+    pub fn draw(&self, frame: &mut Frame, x: i32, y: i32, width: usize, height: usize) {
+        self.draw_tinted(frame, x, y, width, height, LinSrgb::new(255, 255, 255));
+    }
+
+    /// Draws the panel with every pixel multiplied by `tint`, so a grey
+    /// panel can take on a theme's colors
+    pub fn draw_tinted(
+        &self,
+        frame: &mut Frame,
+        x: i32,
+        y: i32,
+        width: usize,
+        height: usize,
+        tint: LinSrgb<u8>,
+    ) {
+        let (left, top, content_width, content_height) = self.content;
+        let x0 = x.max(0) as usize;
+        let y0 = y.max(0) as usize;
+        let x1 = ((x + width as i32).max(0) as usize).min(frame.width());
+        let y1 = ((y + height as i32).max(0) as usize).min(frame.height());
+        let multiply = |value: u8, tint: u8| ((value as u16 * tint as u16 + 127) / 255) as u8;
+
+        for dy in y0..y1 {
+            let sy = top
+                + source(
+                    (dy as i32 - y) as usize,
+                    height,
+                    content_height,
+                    self.stretch_y,
+                );
+            for dx in x0..x1 {
+                let sx = left
+                    + source(
+                        (dx as i32 - x) as usize,
+                        width,
+                        content_width,
+                        self.stretch_x,
+                    );
+                let [r, g, b, a] = self.image.get_pixel(sx, sy).0;
+                if a == 0 {
+                    continue;
+                }
+                let color = LinSrgb::new(
+                    multiply(r, tint.red),
+                    multiply(g, tint.green),
+                    multiply(b, tint.blue),
+                );
+                let color = if a < 255 {
+                    let below = frame.get_pixel(dx, dy).into_format::<f32>();
+                    below
+                        .mix(color.into_format(), a as f32 / 255.0)
+                        .into_format()
+                } else {
+                    color
+                };
+                frame.put_pixel(dx, dy, color);
+            }
+        }
+    }
+}
+
+/// `start..end` clamped to `0..size`, or all of it if that leaves nothing to
+/// stretch
+fn stretch_range(start: u32, end: u32, size: u32) -> (u32, u32) {
+    let end = end.min(size);
+    if start < end {
+        (start, end)
+    } else {
+        (0, size)
+    }
+}
+
+/// Which of `content` source pixels to draw at `offset` along a panel `size`
+/// pixels long, where `stretch` is the part of the source that stretches
+fn source(offset: usize, size: usize, content: u32, (start, end): (u32, u32)) -> u32 {
+    let (before, after) = (start as usize, (content - end) as usize);
+    if before + after >= size {
+        // Too small for the corners, so they're squashed together
+        let scaled = offset * (before + after) / size.max(1);
+        return if scaled < before {
+            scaled as u32
+        } else {
+            end + (scaled - before) as u32
+        };
+    }
+    if offset < before {
+        offset as u32
+    } else if offset >= size - after {
+        content - (size - offset) as u32
+    } else {
+        let stretched = (size - before - after) as u32;
+        start + (offset - before) as u32 * (end - start) / stretched
+    }
+}
+
+/// Draws the current theme's panel, or a plain dark box if it can't be
+/// loaded
+pub fn draw_panel(frame: &mut Frame, x: usize, y: usize, width: usize, height: usize) {
+    match NinePatch::load(config().theme.panel()) {
+        Ok(panel) => panel.draw(frame, x as i32, y as i32, width, height),
+        Err(_) => frame.fill_rect(x, y, width, height, LinSrgb::new(40, 40, 40)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_source() {
+        // Two fixed pixels either side of a two pixel middle
+        let stretch = (2, 4);
+        let drawn = (0..10)
+            .map(|offset| source(offset, 10, 6, stretch))
+            .collect::<Vec<_>>();
+        assert_eq!(drawn, [0, 1, 2, 2, 2, 3, 3, 3, 4, 5]);
+        // Narrower than the corners
+        let drawn = (0..2)
+            .map(|offset| source(offset, 2, 6, stretch))
+            .collect::<Vec<_>>();
+        assert_eq!(drawn, [0, 4]);
+    }
+
+    #[test]
+    fn test_markers() {
+        // A 3x3 panel, red in the middle and white around it, with the middle
+        // column and row marked
+        let mut image = RgbaImage::new(5, 5);
+        for y in 1..4 {
+            for x in 1..4 {
+                let middle = x == 2 && y == 2;
+                let color = if middle {
+                    [255, 0, 0, 255]
+                } else {
+                    [255, 255, 255, 255]
+                };
+                image.put_pixel(x, y, Rgba(color));
+            }
+        }
+        image.put_pixel(2, 0, Rgba([0, 0, 0, 255]));
+        image.put_pixel(0, 2, Rgba([0, 0, 0, 255]));
+        let panel = NinePatch::from_markers(Arc::new(image)).unwrap();
+        assert_eq!(panel.min_size(), (2, 2));
+
+        let mut data = vec![0; 6 * 4 * 4];
+        let mut frame = Frame {
+            width: 6,
+            height: 4,
+            bytespp: 4,
+            stride: 6 * 4,
+            data: &mut data,
+        };
+        panel.draw(&mut frame, 1, 0, 4, 4);
+        let white = LinSrgb::new(255, 255, 255);
+        let red = LinSrgb::new(255, 0, 0);
+        assert_eq!(frame.get_pixel(0, 0), LinSrgb::new(0, 0, 0));
+        assert_eq!(frame.get_pixel(1, 0), white);
+        assert_eq!(frame.get_pixel(2, 1), red);
+        assert_eq!(frame.get_pixel(3, 2), red);
+        assert_eq!(frame.get_pixel(4, 3), white);
+        assert_eq!(frame.get_pixel(3, 3), white);
+    }
+}
@@ -0,0 +1,271 @@
+//! Things drawn over the running app: the debug overlay, toggled with
+//! SELECT+R1, toast notifications and the device's quit prompt.
+
+use palette::LinSrgb;
+
+use crate::{
+    actions::{Action, ActionMap},
+    app::{Frame, Input},
+    config::config,
+    events::{Notice, Subscription},
+    label::Label,
+    ninepatch::draw_panel,
+    system::CpuUsage,
+    time::{Duration, Instant},
+    widgets::Plot,
+};
+
+/// How often the figures are refreshed, so they're readable
+const REFRESH: Duration = Duration::from_millis(500);
+
+/// Small and quick to draw every frame
+const DEBUG_FONT: &str = "fonts/Ubuntu-B-12.fnt";
+
+/// How many frames the frame time plot covers
+const PLOT_FRAMES: usize = 120;
+
+fn frame_time_plot() -> Plot {
+    Plot::new(240, 90)
+        .with_series("frame", LinSrgb::new(0, 255, 0))
+        .with_series("update", LinSrgb::new(255, 255, 0))
+        .with_capacity(PLOT_FRAMES)
+        .with_zero(true)
+        .with_ticks(3)
+        .with_font(DEBUG_FONT, 12.0)
+}
+
+pub struct DebugOverlay {
+    pub visible: bool,
+    frames: u32,
+    window_start: Instant,
+    /// The slowest update in the current window
+    update_time: Duration,
+    cpu: CpuUsage,
+    lines: Vec<String>,
+    /// How long this frame's update took
+    last_update: Duration,
+    last_frame: Instant,
+    /// Milliseconds between frames and spent updating, for the last
+    /// [`PLOT_FRAMES`] frames
+    frame_times: Plot,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            frames: 0,
+            window_start: Instant::now(),
+            update_time: Duration::ZERO,
+            cpu: CpuUsage::default(),
+            lines: vec![],
+            last_update: Duration::ZERO,
+            last_frame: Instant::now(),
+            frame_times: frame_time_plot(),
+        }
+    }
+}
+
+impl DebugOverlay {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.frames = 0;
+        self.window_start = Instant::now();
+        self.update_time = Duration::ZERO;
+        self.lines.clear();
+        self.last_frame = Instant::now();
+        self.frame_times.clear();
+    }
+
+    /// Records how long the app's update took this frame
+    pub fn record_update(&mut self, time: Duration) {
+        self.update_time = self.update_time.max(time);
+        self.last_update = time;
+    }
+
+    /// Call once per frame, after everything else has been drawn
+    pub fn draw(&mut self, frame: &mut Frame) {
+        if !self.visible {
+            return;
+        }
+
+        self.frames += 1;
+        let millis = |time: Duration| time.as_secs_f32() * 1000.0;
+        self.frame_times.push(0, millis(self.last_frame.elapsed()));
+        self.frame_times.push(1, millis(self.last_update));
+        self.last_frame = Instant::now();
+        self.last_update = Duration::ZERO;
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= REFRESH || self.lines.is_empty() {
+            let fps = self.frames as f32 / elapsed.as_secs_f32().max(f32::EPSILON);
+            self.frames = 0;
+            self.window_start = Instant::now();
+            self.refresh(fps);
+            self.update_time = Duration::ZERO;
+        }
+
+        let line_height = 14;
+        let font = crate::assets::bitmap_font(DEBUG_FONT).ok();
+        let width = self
+            .lines
+            .iter()
+            .map(|line| font.as_ref().map_or(0, |font| font.measure(line).0))
+            .max()
+            .unwrap_or(0);
+        frame.fill_rect(
+            0,
+            0,
+            (width + 8).min(frame.width()),
+            (self.lines.len() * line_height + 6).min(frame.height()),
+            LinSrgb::new(0, 0, 0),
+        );
+        for (i, line) in self.lines.iter().enumerate() {
+            frame.text(
+                DEBUG_FONT,
+                4,
+                (i + 1) * line_height,
+                12.0,
+                LinSrgb::new(0, 255, 0),
+                line,
+            );
+        }
+
+        // Frame times in milliseconds underneath
+        let top = self.lines.len() * line_height + 6;
+        frame.fill_rect(0, top, 248, 94, LinSrgb::new(0, 0, 0));
+        self.frame_times.draw(frame, 4, top);
+    }
+
+    fn refresh(&mut self, fps: f32) {
+        let mut text = vec![format!(
+            "{:.1} fps, update {:.1} ms",
+            fps,
+            self.update_time.as_secs_f32() * 1000.0
+        )];
+        let khz = crate::system::cpu_frequency();
+        let governor = crate::system::governor();
+        if khz.is_some() || governor.is_some() {
+            text.push(format!(
+                "CPU {} MHz ({})",
+                khz.map_or("?".to_string(), |khz| (khz / 1000).to_string()),
+                governor.as_deref().unwrap_or("?"),
+            ));
+        }
+        if let Some(usage) = self.cpu.sample() {
+            text.push(format!("CPU usage {:.0}%", usage * 100.0));
+        }
+        if let Some(temperature) = crate::system::temperature() {
+            text.push(format!("SoC {:.1} °C", temperature));
+        }
+        if let (Some(current), Some(peak)) = (crate::memory::current(), crate::memory::peak()) {
+            text.push(format!(
+                "Heap {}, peak {}",
+                crate::memory::format_bytes(current),
+                crate::memory::format_bytes(peak)
+            ));
+        }
+        self.lines = text;
+    }
+}
+
+/// A short message shown along the bottom of the screen for a few seconds,
+/// from [`Toast::show`] or a [`Notice`] published anywhere
+#[derive(Default)]
+pub struct Toast {
+    current: Option<(Label, Instant)>,
+    notices: Subscription<Notice>,
+}
+
+impl Toast {
+    const DURATION: Duration = Duration::from_secs(3);
+
+    /// Replaces any message already showing
+    pub fn show(&mut self, text: &str) {
+        let fontsize = config().scaled(18.0);
+        let mut label = Label::new("fonts/Ubuntu-B.ttf", fontsize, LinSrgb::new(255, 255, 255));
+        label.set_text(text);
+        self.current = Some((label, Instant::now()));
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.current.is_some() || !self.notices.is_empty()
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame) {
+        if let Some(Notice(text)) = self.notices.take().pop() {
+            self.show(&text);
+        }
+        let Some((label, shown)) = self.current.as_ref() else {
+            return;
+        };
+        if shown.elapsed() >= config().timed(Self::DURATION) {
+            self.current = None;
+            return;
+        }
+
+        let (width, height) = label.size();
+        let x = frame.width().saturating_sub(width) / 2;
+        let y = frame.height().saturating_sub(height + 20);
+        let (left, top) = (x.saturating_sub(8), y.saturating_sub(6));
+        draw_panel(frame, left, top, width + 16, height + 12);
+        label.draw(frame, x, y + height);
+    }
+}
+
+/// Asks before the launcher quits on the device's exit button
+#[derive(Default)]
+pub struct ExitPrompt {
+    /// The question and the buttons that answer it, while it's showing
+    current: Option<(ActionMap, [Label; 2])>,
+}
+
+impl ExitPrompt {
+    pub fn show(&mut self) {
+        let actions = ActionMap::global();
+        let mut question = Label::new("fonts/Ubuntu-B.ttf", 24.0, LinSrgb::new(255, 255, 255));
+        question.set_text("Quit the launcher?");
+        let mut hint = Label::new("fonts/Ubuntu-B.ttf", 16.0, LinSrgb::new(180, 180, 180));
+        hint.set_text(&format!(
+            "{}: quit    {}: keep playing",
+            actions.label(Action::Confirm),
+            actions.label(Action::Back)
+        ));
+        self.current = Some((actions, [question, hint]));
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Whether the player chose to quit. Going back hides the prompt.
+    pub fn update(&mut self, input: &Input) -> bool {
+        let Some((actions, _)) = &self.current else {
+            return false;
+        };
+        if actions.just_pressed(input, Action::Confirm) {
+            return true;
+        }
+        if actions.just_pressed(input, Action::Back) {
+            self.current = None;
+        }
+        false
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let Some((_, lines)) = &self.current else {
+            return;
+        };
+        let width = lines.iter().map(|l| l.size().0).max().unwrap_or(0) + 40;
+        let height = lines.iter().map(|l| l.size().1 + 12).sum::<usize>() + 28;
+        let left = frame.width().saturating_sub(width) / 2;
+        let top = frame.height().saturating_sub(height) / 2;
+        draw_panel(frame, left, top, width, height);
+        let mut y = top + 14;
+        for line in lines {
+            let (line_width, line_height) = line.size();
+            y += line_height;
+            line.draw(frame, frame.width().saturating_sub(line_width) / 2, y);
+            y += 12;
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Little bursts and streams of pixels, for sparks when food is eaten,
+//! debris from broken bricks or confetti when someone wins.
+//!
+//! An [`Emitter`] is configured once, then either emits continuously from
+//! its position or [bursts](Emitter::burst) on demand. Step it each frame
+//! with [`Input::delta`](crate::app::Input::delta) and draw it after the rest
+//! of the scene, and report [`Activity::Busy`](crate::app::Activity::Busy)
+//! while it [is active](Emitter::is_active).
+
+use std::f32::consts::PI;
+
+use palette::LinSrgb;
+use rand::{rngs::StdRng, Rng};
+
+use crate::{anim::Lerp, app::Frame, time::Duration};
+
+struct Particle {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    /// Seconds since it was spawned
+    age: f32,
+    /// Seconds it lives for
+    lifetime: f32,
+    color: LinSrgb<u8>,
+}
+
+pub struct Emitter {
+    /// Where continuous emission comes from, in frame coordinates
+    pub position: (f32, f32),
+    /// Particles a second from `position`, or 0 to only burst
+    pub rate: f32,
+    /// Shortest and longest life in seconds
+    lifetime: (f32, f32),
+    /// Slowest and fastest starting speed in pixels a second
+    speed: (f32, f32),
+    /// The direction particles head off in, in radians clockwise from the
+    /// right, and how far either side of it they can stray
+    direction: (f32, f32),
+    /// Downwards acceleration in pixels a second squared
+    gravity: f32,
+    /// Each particle picks one of these
+    colors: Vec<LinSrgb<u8>>,
+    /// What particles fade to by the end of their lives, if anything
+    fade_to: Option<LinSrgb<u8>>,
+    /// Width and height of each particle in pixels
+    size: usize,
+    /// The most particles alive at once. New ones are dropped beyond this.
+    max: usize,
+    particles: Vec<Particle>,
+    /// Part of a particle left over from the last update's emission
+    pending: f32,
+    rng: StdRng,
+}
+
+impl Emitter {
+    /// A single-pixel white emitter for at most `max` particles, spraying
+    /// every way at 20-60px/s for half a second to a second. Seeded from
+    /// [`crate::random::rng`] with `name`, so seeded runs repeat.
+    pub fn new(name: &str, max: usize) -> Self {
+        Self {
+            position: (0.0, 0.0),
+            rate: 0.0,
+            lifetime: (0.5, 1.0),
+            speed: (20.0, 60.0),
+            direction: (0.0, PI),
+            gravity: 0.0,
+            colors: vec![LinSrgb::new(255, 255, 255)],
+            fade_to: None,
+            size: 1,
+            max,
+            particles: Vec::with_capacity(max),
+            pending: 0.0,
+            rng: crate::random::rng(name),
+        }
+    }
+
+    /// Emits `rate` particles a second from [`Emitter::position`]
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_lifetime(mut self, min: Duration, max: Duration) -> Self {
+        self.lifetime = (min.as_secs_f32(), max.as_secs_f32().max(min.as_secs_f32()));
+        self
+    }
+
+    /// Starting speeds in pixels a second
+    pub fn with_speed(mut self, min: f32, max: f32) -> Self {
+        self.speed = (min, max.max(min));
+        self
+    }
+
+    /// Heads particles off at `angle` radians clockwise from the right, give
+    /// or take `spread`. `-PI / 2.0` is straight up.
+    pub fn with_direction(mut self, angle: f32, spread: f32) -> Self {
+        self.direction = (angle, spread.abs());
+        self
+    }
+
+    /// Pulls particles down by `gravity` pixels a second squared
+    pub fn with_gravity(mut self, gravity: f32) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Gives each particle one of `colors`
+    pub fn with_colors(mut self, colors: &[LinSrgb<u8>]) -> Self {
+        if !colors.is_empty() {
+            self.colors = colors.to_vec();
+        }
+        self
+    }
+
+    /// Fades particles to `color` over their lives, usually the background
+    pub fn with_fade_to(mut self, color: LinSrgb<u8>) -> Self {
+        self.fade_to = Some(color);
+        self
+    }
+
+    /// Draws particles as `size` pixel squares
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size.max(1);
+        self
+    }
+
+    /// Spawns `count` particles at once from `x`, `y`
+    pub fn burst(&mut self, count: usize, x: f32, y: f32) {
+        for _ in 0..count {
+            self.spawn((x, y));
+        }
+    }
+
+    fn spawn(&mut self, position: (f32, f32)) {
+        if self.particles.len() >= self.max {
+            return;
+        }
+        let range = |rng: &mut StdRng, (min, max): (f32, f32)| {
+            if max > min {
+                rng.gen_range(min..max)
+            } else {
+                min
+            }
+        };
+        let (angle, spread) = self.direction;
+        let angle = range(&mut self.rng, (angle - spread, angle + spread));
+        let speed = range(&mut self.rng, self.speed);
+        let lifetime = range(&mut self.rng, self.lifetime);
+        let color = self.colors[self.rng.gen_range(0..self.colors.len())];
+        self.particles.push(Particle {
+            position,
+            velocity: (angle.cos() * speed, angle.sin() * speed),
+            age: 0.0,
+            lifetime,
+            color,
+        });
+    }
+
+    /// Moves every particle on by `delta`, the time since the last frame,
+    /// removes the ones that have died and emits any new ones due
+    pub fn update(&mut self, delta: Duration) {
+        let delta = delta.as_secs_f32();
+        self.particles.retain_mut(|particle| {
+            particle.age += delta;
+            particle.velocity.1 += self.gravity * delta;
+            particle.position.0 += particle.velocity.0 * delta;
+            particle.position.1 += particle.velocity.1 * delta;
+            particle.age < particle.lifetime
+        });
+
+        self.pending += self.rate * delta;
+        while self.pending >= 1.0 {
+            self.pending -= 1.0;
+            self.spawn(self.position);
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        for particle in &self.particles {
+            let (x, y) = particle.position;
+            if x < 0.0 || y < 0.0 {
+                continue;
+            }
+            let color = match self.fade_to {
+                Some(to) => particle.color.lerp(to, particle.age / particle.lifetime),
+                None => particle.color,
+            };
+            if self.size == 1 {
+                frame.put_pixel(x as usize, y as usize, color);
+            } else {
+                frame.fill_rect(x as usize, y as usize, self.size, self.size, color);
+            }
+        }
+    }
+
+    /// How many particles are alive
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Whether anything will change on the next update
+    pub fn is_active(&self) -> bool {
+        !self.particles.is_empty() || self.rate > 0.0
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.pending = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_emitter() {
+        let second = Duration::from_secs(1);
+        let mut emitter = Emitter::new("test", 10)
+            .with_lifetime(second, second)
+            .with_speed(10.0, 10.0)
+            .with_direction(0.0, 0.0)
+            .with_gravity(4.0);
+        emitter.burst(20, 5.0, 5.0);
+        assert_eq!(emitter.len(), 10);
+
+        emitter.update(second / 2);
+        let particle = &emitter.particles[0];
+        assert_eq!(particle.position, (10.0, 6.0));
+        emitter.update(second);
+        assert!(!emitter.is_active());
+
+        emitter.rate = 4.0;
+        emitter.update(second);
+        assert_eq!(emitter.len(), 4);
+    }
+}
@@ -0,0 +1,598 @@
+//! Loading third-party apps from shared objects at runtime.
+//!
+//! A plugin is a `cdylib` exporting the symbols below, most easily generated
+//! with [`export_plugin!`](crate::export_plugin). Everything crossing the
+//! boundary is `#[repr(C)]`, so plugins don't need to be built with the same
+//! compiler version as the launcher, only the same [`ABI_VERSION`].
+//!
+//! - `rg35xx_plugin_abi_version() -> u32`
+//! - `rg35xx_plugin_name() -> *const c_char`
+//! - `rg35xx_plugin_create() -> *mut c_void`
+//! - `rg35xx_plugin_update(*mut c_void, *const FfiInput, *mut FfiFrame)`
+//! - `rg35xx_plugin_destroy(*mut c_void)`
+//!
+//! Plugins exported with `export_plugin!("Name", App, serde)` additionally
+//! export the following, letting [`HotReload`] carry their state across a
+//! rebuild:
+//!
+//! - `rg35xx_plugin_save_state(*mut c_void, *mut usize) -> *mut u8`
+//! - `rg35xx_plugin_free_state(*mut u8, usize)`
+//! - `rg35xx_plugin_restore_state(*const u8, usize) -> *mut c_void`
+
+use std::{
+    ffi::{c_char, c_void, CStr},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use enum_iterator::all;
+use enum_map::Enum;
+use libloading::{Library, Symbol};
+
+use crate::app::{App, AppContext, Button, ButtonState, Frame, Input};
+use crate::axes::Axis;
+
+// Used by `export_plugin!(.., serde)`
+#[doc(hidden)]
+pub use serde_json;
+
+/// Bumped whenever the layout of [`FfiInput`]/[`FfiFrame`] or the exported
+/// symbols change
+pub const ABI_VERSION: u32 = 3;
+
+/// The touchscreen or mouse, see [`Input::pointer`]
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FfiPointer {
+    /// Whether it's on the screen, at `x`, `y`
+    pub on_screen: bool,
+    pub x: u32,
+    pub y: u32,
+    pub pressed: bool,
+    pub previous: bool,
+    pub dragged: bool,
+    /// Whether a press started, at `start_x`, `start_y`
+    pub started: bool,
+    pub start_x: u32,
+    pub start_y: u32,
+}
+
+/// The combined input for a frame. Buttons are bitmasks indexed by the
+/// `Button` enum's declaration order, and axes by the `Axis` enum's. Each
+/// player's own input isn't carried.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FfiInput {
+    pub pressed: u32,
+    pub previous: u32,
+    /// Buttons whose repeat is due this frame, see [`Input::repeated`]
+    pub repeating: u32,
+    /// See [`Input::delta`]
+    pub delta_ms: f32,
+    /// Uncalibrated readings
+    pub axes: [f32; <Axis as Enum>::LENGTH],
+    pub axes_moved: bool,
+    pub pointer: FfiPointer,
+}
+
+impl FfiInput {
+    pub fn from_input(input: &Input) -> Self {
+        let mut ffi = Self {
+            delta_ms: input.delta.as_secs_f32() * 1000.0,
+            axes_moved: input.axes_moved,
+            ..Self::default()
+        };
+        for button in all::<Button>() {
+            let state = input.get(button);
+            let bit = 1 << button.into_usize();
+            if state.pressed {
+                ffi.pressed |= bit;
+            }
+            if state.previous {
+                ffi.previous |= bit;
+            }
+            if state.repeating {
+                ffi.repeating |= bit;
+            }
+        }
+        for axis in all::<Axis>() {
+            ffi.axes[axis.into_usize()] = input.axes[axis];
+        }
+        let pointer = &input.pointer;
+        let (x, y) = pointer.position.unwrap_or_default();
+        let (start_x, start_y) = pointer.start.unwrap_or_default();
+        ffi.pointer = FfiPointer {
+            on_screen: pointer.position.is_some(),
+            x: x as u32,
+            y: y as u32,
+            pressed: pointer.pressed,
+            previous: pointer.previous,
+            dragged: pointer.dragged,
+            started: pointer.start.is_some(),
+            start_x: start_x as u32,
+            start_y: start_y as u32,
+        };
+        ffi
+    }
+
+    pub fn to_input(&self) -> Input {
+        let mut input = Input::default();
+        for button in all::<Button>() {
+            let bit = 1 << button.into_usize();
+            input.buttons[button] = ButtonState {
+                pressed: self.pressed & bit != 0,
+                previous: self.previous & bit != 0,
+                repeat_at: None,
+                repeating: self.repeating & bit != 0,
+            };
+        }
+        for axis in all::<Axis>() {
+            input.axes[axis] = self.axes[axis.into_usize()];
+        }
+        input.axes_moved = self.axes_moved;
+        input.delta = Duration::from_micros((self.delta_ms.max(0.0) * 1000.0).round() as u64);
+        let pointer = &mut input.pointer;
+        let ffi = &self.pointer;
+        pointer.position = ffi.on_screen.then_some((ffi.x as usize, ffi.y as usize));
+        pointer.pressed = ffi.pressed;
+        pointer.previous = ffi.previous;
+        pointer.dragged = ffi.dragged;
+        pointer.start = ffi
+            .started
+            .then_some((ffi.start_x as usize, ffi.start_y as usize));
+        input
+    }
+}
+
+#[repr(C)]
+pub struct FfiFrame {
+    pub width: u32,
+    pub height: u32,
+    pub bytespp: u32,
+    /// Bytes from one row to the next; see [`Frame::stride`]
+    pub stride: usize,
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl FfiFrame {
+    pub fn from_frame(frame: &mut Frame) -> Self {
+        Self {
+            width: frame.width as u32,
+            height: frame.height as u32,
+            bytespp: frame.bytespp as u32,
+            stride: frame.stride,
+            data: frame.data.as_mut_ptr(),
+            len: frame.data.len(),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `data` must point to `len` writable bytes that outlive the returned frame
+    pub unsafe fn as_frame(&mut self) -> Frame<'_> {
+        Frame {
+            width: self.width as usize,
+            height: self.height as usize,
+            bytespp: self.bytespp as usize,
+            stride: self.stride,
+            data: std::slice::from_raw_parts_mut(self.data, self.len),
+        }
+    }
+}
+
+type CreateFn = unsafe extern "C" fn() -> *mut c_void;
+type UpdateFn = unsafe extern "C" fn(*mut c_void, *const FfiInput, *mut FfiFrame);
+type DestroyFn = unsafe extern "C" fn(*mut c_void);
+type SaveStateFn = unsafe extern "C" fn(*mut c_void, *mut usize) -> *mut u8;
+type FreeStateFn = unsafe extern "C" fn(*mut u8, usize);
+type RestoreStateFn = unsafe extern "C" fn(*const u8, usize) -> *mut c_void;
+
+/// A shared object that has been checked to export a compatible app
+pub struct Plugin {
+    name: String,
+    path: PathBuf,
+    library: Arc<Library>,
+}
+
+impl Plugin {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        // Safety: loading a library runs its initializers, which we have to trust
+        let library = unsafe { Library::new(path) }.map_err(|e| e.to_string())?;
+        let name = unsafe {
+            let version: Symbol<unsafe extern "C" fn() -> u32> = library
+                .get(b"rg35xx_plugin_abi_version")
+                .map_err(|e| e.to_string())?;
+            let version = version();
+            if version != ABI_VERSION {
+                return Err(format!(
+                    "ABI version {} doesn't match launcher's {}",
+                    version, ABI_VERSION
+                ));
+            }
+            for symbol in [
+                &b"rg35xx_plugin_create"[..],
+                b"rg35xx_plugin_update",
+                b"rg35xx_plugin_destroy",
+            ] {
+                library
+                    .get::<*const c_void>(symbol)
+                    .map_err(|e| e.to_string())?;
+            }
+            let name: Symbol<unsafe extern "C" fn() -> *const c_char> = library
+                .get(b"rg35xx_plugin_name")
+                .map_err(|e| e.to_string())?;
+            CStr::from_ptr(name()).to_string_lossy().into_owned()
+        };
+        Ok(Self {
+            name,
+            path: path.to_owned(),
+            library: Arc::new(library),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn instantiate(&self) -> PluginApp {
+        let handle = unsafe {
+            let create: Symbol<CreateFn> = self.library.get(b"rg35xx_plugin_create").unwrap();
+            create()
+        };
+        self.wrap(handle)
+    }
+
+    /// Rebuilds an app from [`PluginApp::save_state`] output, which may have
+    /// come from an older build of the same plugin
+    pub fn restore(&self, state: &[u8]) -> Option<PluginApp> {
+        let handle = unsafe {
+            let restore: Symbol<RestoreStateFn> =
+                self.library.get(b"rg35xx_plugin_restore_state").ok()?;
+            restore(state.as_ptr(), state.len())
+        };
+        (!handle.is_null()).then(|| self.wrap(handle))
+    }
+
+    fn wrap(&self, handle: *mut c_void) -> PluginApp {
+        let library = self.library.clone();
+        unsafe {
+            let update: Symbol<UpdateFn> = library.get(b"rg35xx_plugin_update").unwrap();
+            let destroy: Symbol<DestroyFn> = library.get(b"rg35xx_plugin_destroy").unwrap();
+            let save_state = library
+                .get::<SaveStateFn>(b"rg35xx_plugin_save_state")
+                .ok()
+                .map(|f| *f);
+            let free_state = library
+                .get::<FreeStateFn>(b"rg35xx_plugin_free_state")
+                .ok()
+                .map(|f| *f);
+            PluginApp {
+                handle,
+                update: *update,
+                destroy: *destroy,
+                save_state: save_state.zip(free_state),
+                _library: library.clone(),
+            }
+        }
+    }
+}
+
+/// A running instance of a plugin's app
+pub struct PluginApp {
+    handle: *mut c_void,
+    update: UpdateFn,
+    destroy: DestroyFn,
+    save_state: Option<(SaveStateFn, FreeStateFn)>,
+    // Keeps the function pointers above valid
+    _library: Arc<Library>,
+}
+
+impl PluginApp {
+    /// Serializes the app's state, if the plugin supports it
+    pub fn save_state(&self) -> Option<Vec<u8>> {
+        let (save, free) = self.save_state?;
+        unsafe {
+            let mut len = 0;
+            let data = save(self.handle, &mut len);
+            if data.is_null() {
+                return None;
+            }
+            let state = std::slice::from_raw_parts(data, len).to_vec();
+            free(data, len);
+            Some(state)
+        }
+    }
+}
+
+impl App for PluginApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let input = FfiInput::from_input(ctx.input);
+        let mut ffi_frame = FfiFrame::from_frame(ctx.frame);
+        unsafe { (self.update)(self.handle, &input, &mut ffi_frame) };
+    }
+}
+
+impl Drop for PluginApp {
+    fn drop(&mut self) {
+        unsafe { (self.destroy)(self.handle) };
+    }
+}
+
+/// Runs a plugin, reloading it whenever the shared object changes on disk.
+/// State is carried over for plugins exported with the `serde` option, so
+/// game logic can be iterated on without replaying to the same point.
+pub struct HotReload {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+    generation: usize,
+    // Dropped before `plugin` so the library outlives the app
+    app: Option<PluginApp>,
+    plugin: Option<Plugin>,
+    error: Option<String>,
+}
+
+impl HotReload {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        let mut hot = Self {
+            path: path.into(),
+            modified: None,
+            last_check: Instant::now(),
+            generation: 0,
+            app: None,
+            plugin: None,
+            error: None,
+        };
+        hot.reload();
+        hot
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    fn reload(&mut self) {
+        self.modified = self.modified();
+
+        // dlopen() hands back the already-loaded library for a path it has
+        // seen before, so every generation gets its own copy
+        self.generation += 1;
+        let copy = std::env::temp_dir().join(format!(
+            "rg35xx-hot-{}-{}.{}",
+            std::process::id(),
+            self.generation,
+            std::env::consts::DLL_EXTENSION
+        ));
+        let plugin = std::fs::copy(&self.path, &copy)
+            .map_err(|e| e.to_string())
+            .and_then(|_| Plugin::load(&copy));
+        let _ = std::fs::remove_file(&copy);
+        let plugin = match plugin {
+            Ok(plugin) => plugin,
+            Err(e) => {
+                // Keep running the previous build
+                println!("Reloading {} failed: {}", self.path.display(), e);
+                self.error = Some(e);
+                return;
+            }
+        };
+
+        let state = self.app.as_ref().and_then(PluginApp::save_state);
+        self.app = None;
+        let app = state
+            .and_then(|state| plugin.restore(&state))
+            .unwrap_or_else(|| plugin.instantiate());
+        println!("Loaded {} (generation {})", plugin.name(), self.generation);
+        self.app = Some(app);
+        self.plugin = Some(plugin);
+        self.error = None;
+    }
+}
+
+impl App for HotReload {
+    fn update(&mut self, ctx: &mut AppContext) {
+        if self.last_check.elapsed() > Duration::from_millis(500) {
+            self.last_check = Instant::now();
+            let modified = self.modified();
+            if modified.is_some() && modified != self.modified {
+                self.reload();
+            }
+        }
+
+        if let Some(app) = self.app.as_mut() {
+            app.update(ctx);
+        }
+        if let Some(error) = self.error.as_ref() {
+            let frame = &mut *ctx.frame;
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                10,
+                frame.height() - 10,
+                18.0,
+                palette::LinSrgb::new(255, 0, 0),
+                error,
+            );
+        }
+    }
+}
+
+/// The `apps/` directory next to the launcher binary
+pub fn default_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("apps")))
+        .unwrap_or_else(|| PathBuf::from("apps"))
+}
+
+/// Loads every shared object in `dir`, skipping (and logging) any that fail
+pub fn discover(dir: &Path) -> Vec<Plugin> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        // .so, .dylib or .dll
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths
+        .iter()
+        .filter_map(|path| match Plugin::load(path) {
+            Ok(plugin) => Some(plugin),
+            Err(e) => {
+                println!("Skipping plugin {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Exports the plugin ABI for an `App + Default` type from a `cdylib`:
+///
+/// ```ignore
+/// rg35xx::export_plugin!("My App", MyApp);
+/// ```
+///
+/// Adding `serde` also exports state saving for hot reloads, which requires
+/// the app to implement `Serialize` and `DeserializeOwned`:
+///
+/// ```ignore
+/// rg35xx::export_plugin!("My App", MyApp, serde);
+/// ```
+#[macro_export]
+macro_rules! export_plugin {
+    ($name:literal, $app:ty, serde) => {
+        $crate::export_plugin!($name, $app);
+
+        /// # Safety
+        ///
+        /// `app` must be a live handle and `len` must be writable
+        #[no_mangle]
+        pub unsafe extern "C" fn rg35xx_plugin_save_state(
+            app: *mut ::std::ffi::c_void,
+            len: *mut usize,
+        ) -> *mut u8 {
+            let app = &*(app as *const $app);
+            match $crate::plugin::serde_json::to_vec(app) {
+                Ok(state) => {
+                    let state = state.into_boxed_slice();
+                    *len = state.len();
+                    Box::into_raw(state) as *mut u8
+                }
+                Err(_) => ::std::ptr::null_mut(),
+            }
+        }
+
+        /// # Safety
+        ///
+        /// Must be given a pointer and length from `rg35xx_plugin_save_state`
+        #[no_mangle]
+        pub unsafe extern "C" fn rg35xx_plugin_free_state(state: *mut u8, len: usize) {
+            drop(Box::from_raw(::std::ptr::slice_from_raw_parts_mut(
+                state, len,
+            )));
+        }
+
+        /// # Safety
+        ///
+        /// `state` must point to `len` readable bytes
+        #[no_mangle]
+        pub unsafe extern "C" fn rg35xx_plugin_restore_state(
+            state: *const u8,
+            len: usize,
+        ) -> *mut ::std::ffi::c_void {
+            let state = ::std::slice::from_raw_parts(state, len);
+            match $crate::plugin::serde_json::from_slice::<$app>(state) {
+                Ok(app) => Box::into_raw(Box::new(app)) as *mut ::std::ffi::c_void,
+                Err(_) => ::std::ptr::null_mut(),
+            }
+        }
+    };
+    ($name:literal, $app:ty) => {
+        #[no_mangle]
+        pub extern "C" fn rg35xx_plugin_abi_version() -> u32 {
+            $crate::plugin::ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn rg35xx_plugin_name() -> *const ::std::ffi::c_char {
+            concat!($name, "\0").as_ptr() as *const ::std::ffi::c_char
+        }
+
+        #[no_mangle]
+        pub extern "C" fn rg35xx_plugin_create() -> *mut ::std::ffi::c_void {
+            Box::into_raw(Box::new(<$app as Default>::default())) as *mut ::std::ffi::c_void
+        }
+
+        /// # Safety
+        ///
+        /// Must only be called by the launcher with a handle from `rg35xx_plugin_create`
+        #[no_mangle]
+        pub unsafe extern "C" fn rg35xx_plugin_update(
+            app: *mut ::std::ffi::c_void,
+            input: *const $crate::plugin::FfiInput,
+            frame: *mut $crate::plugin::FfiFrame,
+        ) {
+            let app = &mut *(app as *mut $app);
+            let input = (*input).to_input();
+            let mut frame = (*frame).as_frame();
+            // The launcher's pool can't be shared across the library
+            // boundary, so each plugin keeps one of its own
+            ::std::thread_local! {
+                static TASKS: ::std::cell::RefCell<$crate::tasks::Tasks> =
+                    ::std::cell::RefCell::new(::std::default::Default::default());
+            }
+            TASKS.with(|tasks| {
+                let mut tasks = tasks.borrow_mut();
+                let mut ctx = $crate::app::AppContext::new(&input, &mut frame, &mut tasks);
+                $crate::app::App::update(app, &mut ctx);
+            });
+        }
+
+        /// # Safety
+        ///
+        /// Must only be called once per handle from `rg35xx_plugin_create`
+        #[no_mangle]
+        pub unsafe extern "C" fn rg35xx_plugin_destroy(app: *mut ::std::ffi::c_void) {
+            drop(Box::from_raw(app as *mut $app));
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ffi_input() {
+        let mut input = Input::default();
+        input.event(Button::ActionA, true);
+        input.axis_event(Axis::LeftX, 0.5);
+        input.pointer_moved(10, 20);
+        input.pointer_event(true);
+        input.update();
+        input.event(Button::ActionB, true);
+        input.pointer_moved(30, 40);
+        input.set_delta(Duration::from_millis(16));
+
+        let carried = FfiInput::from_input(&input).to_input();
+        assert!(carried.pressed(Button::ActionA) && !carried.just_pressed(Button::ActionA));
+        assert!(carried.just_pressed(Button::ActionB) && carried.repeated(Button::ActionB));
+        assert_eq!(carried.raw_axis(Axis::LeftX), 0.5);
+        assert_eq!(carried.dragging(), Some(((10, 20), (30, 40))));
+        assert!(carried.any_changed());
+        assert_eq!(carried.delta(), Duration::from_millis(16));
+    }
+}
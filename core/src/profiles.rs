@@ -0,0 +1,165 @@
+//! Separate save data for each person using the device. Each profile is a
+//! directory under `profiles/` next to the binary, and the one in use is
+//! remembered in `launcher.txt`.
+
+use std::{collections::BTreeMap, fmt::Display, io, path::PathBuf, str::FromStr};
+
+use crate::config::{config, set_config};
+
+/// The profile used until another is made
+pub const DEFAULT_PROFILE: &str = "Player 1";
+
+fn profiles_dir() -> PathBuf {
+    crate::storage::base_dir().join("profiles")
+}
+
+/// Whether `name` can be used as a profile's directory
+pub fn is_valid_name(name: &str) -> bool {
+    !name.trim().is_empty() && !name.contains(['/', '\\', '=']) && !name.starts_with('.')
+}
+
+/// The profile in use
+pub fn current() -> String {
+    config().profile
+}
+
+/// Where the current profile's data goes
+pub fn dir() -> PathBuf {
+    profiles_dir().join(current())
+}
+
+/// Every profile, sorted, including the current one even if it has no data
+/// yet
+pub fn list() -> Vec<String> {
+    let mut profiles = std::fs::read_dir(profiles_dir())
+        .map(|read| {
+            read.flatten()
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| is_valid_name(name))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let current = current();
+    if !profiles.contains(&current) {
+        profiles.push(current);
+    }
+    profiles.sort();
+    profiles
+}
+
+/// Makes `name` the current profile, creating it if needed
+pub fn switch(name: &str) -> io::Result<()> {
+    if !is_valid_name(name) {
+        return Err(io::Error::other(format!("`{}` isn't a valid name", name)));
+    }
+    std::fs::create_dir_all(profiles_dir().join(name))?;
+    let mut config = config();
+    config.profile = name.to_string();
+    set_config(config)
+}
+
+/// Makes a new profile called `Player N`, returning its name
+pub fn create() -> io::Result<String> {
+    let existing = list();
+    let name = (1..)
+        .map(|n| format!("Player {}", n))
+        .find(|name| !existing.contains(name))
+        .unwrap();
+    std::fs::create_dir_all(profiles_dir().join(&name))?;
+    Ok(name)
+}
+
+/// Deletes a profile and everything saved in it. The current profile can't be
+/// removed.
+pub fn remove(name: &str) -> io::Result<()> {
+    if name == current() {
+        return Err(io::Error::other("Switch to another profile first"));
+    }
+    if !is_valid_name(name) {
+        return Err(io::Error::other(format!("`{}` isn't a valid name", name)));
+    }
+    std::fs::remove_dir_all(profiles_dir().join(name))
+}
+
+/// One app's saved values in the current profile, kept in
+/// `profiles/<profile>/<app>.txt` as `key = value` lines
+pub struct Store {
+    path: PathBuf,
+    values: BTreeMap<String, String>,
+}
+
+impl Store {
+    /// The store for `app`, e.g. `snake`, empty if nothing's been saved
+    pub fn open(app: &str) -> Self {
+        Self::at(dir().join(format!("{}.txt", app)))
+    }
+
+    fn at(path: PathBuf) -> Self {
+        let values = std::fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Self { path, values }
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.values.get(key)?.parse().ok()
+    }
+
+    /// Changes a value, which is kept once [`save`](Self::save) is called
+    pub fn set(&mut self, key: &str, value: impl Display) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    /// Adds `amount` to a count, starting from 0
+    pub fn add(&mut self, key: &str, amount: u64) {
+        let total = self.get::<u64>(key).unwrap_or(0) + amount;
+        self.set(key, total);
+    }
+
+    /// Writes the values out, unless a [demo](crate::demo) is playing
+    pub fn save(&self) -> io::Result<()> {
+        if crate::demo::playing() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = self
+            .values
+            .iter()
+            .map(|(key, value)| format!("{} = {}\n", key, value))
+            .collect::<String>();
+        std::fs::write(&self.path, text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_store() {
+        let path = std::env::temp_dir()
+            .join(format!("rg35xx-profile-{}", std::process::id()))
+            .join("test.txt");
+        let mut store = Store::at(path.clone());
+        assert_eq!(store.get::<u64>("high_score"), None);
+        store.set("high_score", 12);
+        store.add("games", 1);
+        store.add("games", 2);
+        store.save().unwrap();
+
+        let store = Store::at(path.clone());
+        assert_eq!(store.get::<u64>("high_score"), Some(12));
+        assert_eq!(store.get::<u64>("games"), Some(3));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert!(is_valid_name("Player 2"));
+        assert!(!is_valid_name("../x"));
+        assert!(!is_valid_name(" "));
+    }
+}
@@ -0,0 +1,118 @@
+//! QR codes on the screen, for handing a link, a Wi-Fi network or a note to
+//! a phone's camera instead of typing it out.
+//!
+//! ```ignore
+//! let qr = Qr::new(&qr::wifi("Home", Some("hunter22")))?;
+//! qr.draw(frame, 100, 40, 400);
+//! ```
+
+use palette::LinSrgb;
+use qrcode::{Color, QrCode};
+
+use crate::app::Frame;
+
+/// The white border scanners need around a code, in modules
+const QUIET_ZONE: usize = 4;
+
+/// A QR code ready to draw
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Qr {
+    /// Modules on a side
+    width: usize,
+    /// Whether each module is dark, row by row
+    dark: Vec<bool>,
+}
+
+impl Qr {
+    /// Encodes `data`, failing if it's more than a QR code holds (a little
+    /// under 3 KB)
+    pub fn new(data: &str) -> Result<Self, String> {
+        let code = QrCode::new(data.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(Self {
+            width: code.width(),
+            dark: code
+                .to_colors()
+                .into_iter()
+                .map(|color| color == Color::Dark)
+                .collect(),
+        })
+    }
+
+    /// Modules on a side, not counting the quiet zone
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.dark[y * self.width + x]
+    }
+
+    /// The side in pixels it's drawn at to fit in `size`, whole pixels per
+    /// module so it stays sharp
+    pub fn drawn_size(&self, size: usize) -> usize {
+        let modules = self.width + QUIET_ZONE * 2;
+        (size / modules).max(1) * modules
+    }
+
+    /// Draws the code, quiet zone and all, as large as fits in a `size`
+    /// square with its top left at `x`, `y`. Returns the side it took up.
+    pub fn draw(&self, frame: &mut Frame, x: usize, y: usize, size: usize) -> usize {
+        let side = self.drawn_size(size);
+        let scale = side / (self.width + QUIET_ZONE * 2);
+        frame.fill_rect(x, y, side, side, LinSrgb::new(255, 255, 255));
+        let left = x + QUIET_ZONE * scale;
+        let top = y + QUIET_ZONE * scale;
+        for my in 0..self.width {
+            for mx in 0..self.width {
+                if self.is_dark(mx, my) {
+                    let (px, py) = (left + mx * scale, top + my * scale);
+                    frame.fill_rect(px, py, scale, scale, LinSrgb::new(0, 0, 0));
+                }
+            }
+        }
+        side
+    }
+}
+
+/// Backslashes the characters special in a Wi-Fi QR code
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// What to encode for a phone to join a Wi-Fi network, WPA with a password or
+/// open without one
+pub fn wifi(ssid: &str, password: Option<&str>) -> String {
+    match password {
+        Some(password) => format!("WIFI:T:WPA;S:{};P:{};;", escape(ssid), escape(password)),
+        None => format!("WIFI:T:nopass;S:{};;", escape(ssid)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_qr() {
+        let qr = Qr::new("https://example.com").unwrap();
+        // Version 2, the smallest that holds it at the default error correction
+        assert_eq!(qr.width(), 25);
+        // Finder patterns in three corners
+        assert!(qr.is_dark(0, 0) && qr.is_dark(24, 0) && qr.is_dark(0, 24));
+        assert_eq!(qr.drawn_size(400), 33 * 12);
+        assert!(Qr::new(&"x".repeat(5000)).is_err());
+
+        assert_eq!(
+            wifi("Cafe; 2", Some("a:b")),
+            "WIFI:T:WPA;S:Cafe\\; 2;P:a\\:b;;"
+        );
+        assert_eq!(wifi("Open", None), "WIFI:T:nopass;S:Open;;");
+    }
+}
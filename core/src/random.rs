@@ -0,0 +1,65 @@
+//! Randomness for apps. Built-in apps get their generator from [`rng`]
+//! instead of `rand::thread_rng`, so a run can be repeated exactly by giving
+//! the same seed with `--seed` or `seed = ` in `launcher.txt`.
+
+use std::sync::Mutex;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Set from the command line, which wins over `launcher.txt`
+static SEED: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Seeds every generator handed out from now on
+pub fn set_seed(seed: u64) {
+    *SEED.lock().unwrap() = Some(seed);
+}
+
+/// The seed in use, if runs are repeatable
+pub fn seed() -> Option<u64> {
+    SEED.lock().unwrap().or(crate::config::config().seed)
+}
+
+/// FNV-1a, so each name gets its own stream whatever order they're asked for in
+fn hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// A generator for `name`, usually the app's, which gives the same numbers
+/// each run when there's a [`seed`] and different ones otherwise
+pub fn rng(name: &str) -> StdRng {
+    match seed() {
+        Some(seed) => StdRng::seed_from_u64(seed ^ hash(name)),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// A generator for `name` that's the same for everyone on a given day,
+/// whatever the [`seed`], e.g. for a daily puzzle that two devices should
+/// agree on. Days count from 1970 as in [`crate::storage::today`].
+pub fn daily(name: &str, day: i64) -> StdRng {
+    StdRng::seed_from_u64(hash(&format!(
+        "{}/{}",
+        name,
+        crate::storage::date_of_day(day)
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_seeded() {
+        set_seed(7);
+        let numbers = |name| rng(name).gen::<[u32; 4]>();
+        assert_eq!(numbers("snake"), numbers("snake"));
+        assert_ne!(numbers("snake"), numbers("taboo"));
+
+        let daily = |day| daily("sudoku", day).gen::<[u32; 4]>();
+        assert_eq!(daily(20_000), daily(20_000));
+        assert_ne!(daily(20_000), daily(20_001));
+    }
+}
@@ -0,0 +1,240 @@
+//! A virtual controller in a phone's browser. [`start`] serves a page that
+//! shows whatever the running app [publishes](publish) along with buttons,
+//! and taps on those buttons arrive in the app's [`Input`] like real presses.
+//!
+//! The page polls for changes rather than holding a WebSocket open, which
+//! keeps the server to plain HTTP.
+//!
+//! Everything is served under a token made up each time the server starts,
+//! so only someone shown a link can press buttons or download: the page is
+//! at `/<token>` ([`page_url`]), taps go to `/tap/<token>/<Button>`, and
+//! files in the exports directory are at `/exports/<token>/<name>`
+//! ([`export_url`]). Anything else is a 404.
+
+use std::sync::Mutex;
+
+use enum_iterator::all;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::app::{Button, Input};
+use crate::storage::exports_dir;
+use crate::time::Duration;
+use crate::url::{percent_decode, percent_encode};
+
+/// What the phone shows
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub title: String,
+    pub lines: Vec<String>,
+    /// Labelled buttons, each standing in for a controller button
+    pub buttons: Vec<(String, Button)>,
+}
+
+struct Shared {
+    running: bool,
+    port: u16,
+    /// Needed in every link
+    token: String,
+    page: Page,
+    /// Bumped whenever the page changes, so the phone can skip redrawing
+    version: u64,
+    /// Presses and releases waiting for the next frame
+    events: Vec<(Button, bool)>,
+}
+
+static SHARED: Mutex<Shared> = Mutex::new(Shared {
+    running: false,
+    port: 0,
+    token: String::new(),
+    page: Page {
+        title: String::new(),
+        lines: vec![],
+        buttons: vec![],
+    },
+    version: 0,
+    events: vec![],
+});
+
+/// How often apps should update while the server is running, so taps aren't
+/// left waiting on an idle app
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const INDEX: &str = include_str!("remote.html");
+
+/// Starts serving the controller page on `port` in the background
+pub fn start(port: u16) -> std::io::Result<()> {
+    let server =
+        Server::http(("0.0.0.0", port)).map_err(|e| std::io::Error::other(e.to_string()))?;
+    // From the system rather than crate::random, which --seed makes repeat
+    let token = format!("{:032x}", StdRng::from_entropy().gen::<u128>());
+    let page = format!("/{}", token);
+    let state = format!("/state/{}", token);
+    let tap = format!("/tap/{}/", token);
+    let exports = format!("/exports/{}/", token);
+    let mut shared = SHARED.lock().unwrap();
+    shared.running = true;
+    shared.port = port;
+    shared.token = token;
+    drop(shared);
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let url = request.url().to_string();
+            let response = match (request.method(), url.as_str()) {
+                (Method::Get, url) if url.trim_end_matches('/') == page => {
+                    Response::from_string(INDEX).with_header(
+                        Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap(),
+                    )
+                }
+                (Method::Get, url) if url == state => Response::from_string(state_json())
+                    .with_header(Header::from_bytes("Content-Type", "application/json").unwrap()),
+                (Method::Get, url) if url.starts_with(&exports) => {
+                    let name = percent_decode(&url[exports.len()..]);
+                    // Only files directly in the exports directory
+                    let file = if name.contains(['/', '\\']) || name == ".." {
+                        None
+                    } else {
+                        std::fs::read(exports_dir().join(&name)).ok()
+                    };
+                    match file {
+                        Some(bytes) => Response::from_data(bytes).with_header(
+                            Header::from_bytes("Content-Type", content_type(&name)).unwrap(),
+                        ),
+                        None => Response::from_string("not found").with_status_code(404),
+                    }
+                }
+                (Method::Post, url) if url.starts_with(&tap) => {
+                    let name = &url[tap.len()..];
+                    match all::<Button>().find(|b| format!("{:?}", b) == name) {
+                        Some(button) => {
+                            let mut shared = SHARED.lock().unwrap();
+                            shared.events.push((button, true));
+                            shared.events.push((button, false));
+                            Response::from_string("")
+                        }
+                        None => Response::from_string("unknown button").with_status_code(404),
+                    }
+                }
+                _ => Response::from_string("not found").with_status_code(404),
+            };
+            let _ = request.respond(response);
+        }
+    });
+    Ok(())
+}
+
+pub fn is_running() -> bool {
+    SHARED.lock().unwrap().running
+}
+
+/// Where a phone on the same network reaches the server, and the token, if
+/// the server's running and the device has an address
+fn server() -> Option<(String, String)> {
+    let (port, token) = {
+        let shared = SHARED.lock().unwrap();
+        shared
+            .running
+            .then(|| (shared.port, shared.token.clone()))?
+    };
+    let address = crate::net::interfaces()
+        .into_iter()
+        .filter(|interface| !interface.is_loopback() && interface.up)
+        .flat_map(|interface| interface.addresses)
+        .find(|address| address.is_ipv4())?;
+    Some((format!("http://{}:{}", address, port), token))
+}
+
+/// The link to the controller page, if the server's running and the device
+/// has an address
+pub fn page_url() -> Option<String> {
+    let (server, token) = server()?;
+    Some(format!("{}/{}", server, token))
+}
+
+/// The link a phone can download an export from, see [`page_url`]
+pub fn export_url(name: &str) -> Option<String> {
+    let (server, token) = server()?;
+    Some(format!(
+        "{}/exports/{}/{}",
+        server,
+        token,
+        percent_encode(name)
+    ))
+}
+
+fn content_type(name: &str) -> &'static str {
+    match name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("csv" | "txt") => "text/plain; charset=utf-8",
+        Some("gpx") => "application/gpx+xml",
+        Some("json") => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Replaces what the phone shows. Cheap to call every frame with the same page.
+pub fn publish(page: Page) {
+    let mut shared = SHARED.lock().unwrap();
+    if shared.page != page {
+        shared.page = page;
+        shared.version += 1;
+    }
+}
+
+/// Applies taps from the phone to `input`. Called by the backends once per
+/// frame; a button changes at most once per frame so every tap is seen.
+pub fn apply(input: &mut Input) {
+    let mut shared = SHARED.lock().unwrap();
+    let mut changed = vec![];
+    shared.events.retain(|&(button, pressed)| {
+        if changed.contains(&button) {
+            return true;
+        }
+        changed.push(button);
+        input.event(button, pressed);
+        false
+    });
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            c if (c as u32) < 0x20 => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn state_json() -> String {
+    let shared = SHARED.lock().unwrap();
+    let page = &shared.page;
+    let lines = page
+        .lines
+        .iter()
+        .map(|line| json_string(line))
+        .collect::<Vec<_>>()
+        .join(",");
+    let buttons = page
+        .buttons
+        .iter()
+        .map(|(label, button)| {
+            format!(
+                "{{\"label\":{},\"button\":\"{:?}\"}}",
+                json_string(label),
+                button
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"version\":{},\"title\":{},\"lines\":[{}],\"buttons\":[{}]}}",
+        shared.version,
+        json_string(&page.title),
+        lines,
+        buttons
+    )
+}
@@ -0,0 +1,387 @@
+use std::{
+    os::unix::process::CommandExt,
+    process::Command,
+    sync::mpsc::{channel, RecvTimeoutError, Sender},
+    time::{Duration, Instant},
+};
+
+use framebuffer::{Framebuffer, VarScreeninfo};
+use pasts::Loop;
+use std::task::Poll::{self, Pending};
+use stick::{Controller, Event, Listener};
+
+use crate::app::{Activity, App, AppContext, Button, Frame, Input, PowerHint, Repeat};
+use crate::axes::Axis;
+use crate::config::{config, InputDriver};
+use crate::display::{ColorLut, DisplayOptions, Flip, Rotation};
+use crate::error::Error;
+use crate::events::{publish, ControllerConnected, ControllerDisconnected};
+use crate::gamepad;
+use crate::haptics;
+use crate::overlay::ExitPrompt;
+use crate::system::GovernorControl;
+use crate::tasks::Tasks;
+use crate::touch::{self, TouchEvent};
+
+type Exit = usize;
+
+const FRAMEBUFFER: &str = "/dev/fb0";
+
+/// Input for the main loop from one of the input threads
+enum DeviceEvent {
+    /// From the controller with the given index, the built-in one being 0
+    Controller(usize, Event),
+    Touch(TouchEvent),
+}
+
+impl From<(usize, Event)> for DeviceEvent {
+    fn from((player, event): (usize, Event)) -> Self {
+        Self::Controller(player, event)
+    }
+}
+
+impl From<TouchEvent> for DeviceEvent {
+    fn from(event: TouchEvent) -> Self {
+        Self::Touch(event)
+    }
+}
+
+struct State {
+    listener: Listener,
+    controllers: Vec<Controller>,
+    haptics: haptics::Strength,
+    channel: Sender<DeviceEvent>,
+}
+
+impl State {
+    fn connect(&mut self, controller: Controller) -> Poll<Exit> {
+        println!(
+            "Connected p{}, id: {:016X}, name: {}",
+            self.controllers.len() + 1,
+            controller.id(),
+            controller.name(),
+        );
+        publish(ControllerConnected {
+            player: self.controllers.len(),
+            name: controller.name().to_string(),
+        });
+        self.controllers.push(controller);
+        Pending
+    }
+
+    fn rumble(&mut self, strength: f32) -> Poll<Exit> {
+        for controller in &mut self.controllers {
+            controller.rumble(strength);
+        }
+        Pending
+    }
+
+    fn event(&mut self, id: usize, event: Event) -> Poll<Exit> {
+        let player = id + 1;
+        println!("p{}: {}", player, event);
+        self.channel
+            .send(DeviceEvent::Controller(id, event))
+            .unwrap();
+        if let Event::Disconnect = event {
+            publish(ControllerDisconnected { player: id });
+            self.controllers.swap_remove(id);
+        }
+        Pending
+    }
+}
+
+/// Puts the framebuffer back the way the launcher found it and clears it when
+/// dropped, including when a panic unwinds out of [`run_app`]. Nothing else
+/// happens here, so an unwinding panic still gets reported; the `on_exit`
+/// command is [`run_on_exit`]'s job.
+struct Restore {
+    original: VarScreeninfo,
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        match Framebuffer::new(FRAMEBUFFER) {
+            Ok(mut framebuffer) => {
+                // The original yoffset shows the first buffer, which is cleared
+                if let Err(e) = Framebuffer::put_var_screeninfo(&framebuffer.device, &self.original)
+                {
+                    println!("Couldn't restore {}: {:?}", FRAMEBUFFER, e);
+                }
+                let line_length = framebuffer.fix_screen_info.line_length as usize;
+                let rows = self.original.yres_virtual as usize;
+                framebuffer.write_frame(&vec![0; line_length * rows]);
+            }
+            Err(e) => println!("Couldn't restore {}: {:?}", FRAMEBUFFER, e),
+        }
+    }
+}
+
+/// Replaces the launcher with the `on_exit` command from `launcher.txt`, if
+/// there is one, e.g. to go back to the stock UI. Call it once [`run_app`]
+/// has returned `Ok`; after an error or a panic the launcher should exit
+/// and say what went wrong instead.
+pub fn run_on_exit() {
+    if let Some(command) = config().on_exit {
+        println!("Running {}", command);
+        // Only returns if the command couldn't be started
+        let e = Command::new("sh").arg("-c").arg(&command).exec();
+        println!("Couldn't run {}: {}", command, e);
+    }
+}
+
+async fn event_loop(sender: Sender<DeviceEvent>) {
+    let mut state = State {
+        listener: Listener::default(),
+        controllers: Vec::new(),
+        haptics: haptics::Strength::default(),
+        channel: sender,
+    };
+
+    let player_id = Loop::new(&mut state)
+        .when(|s| &mut s.listener, State::connect)
+        .when(|s| &mut s.haptics, State::rumble)
+        .poll(|s| &mut s.controllers, State::event)
+        .await;
+
+    println!("p{} ended the session", player_id);
+}
+
+pub fn run_app(mut app: impl App, options: DisplayOptions) -> Result<(), Error> {
+    let mut framebuffer =
+        Framebuffer::new(FRAMEBUFFER).map_err(|e| Error::framebuffer(FRAMEBUFFER, e))?;
+    let original_var_info = framebuffer.var_screen_info.clone();
+    let _restore = Restore {
+        original: original_var_info.clone(),
+    };
+
+    let physical = (
+        framebuffer.var_screen_info.xres as usize,
+        framebuffer.var_screen_info.yres as usize,
+    );
+    let (width, height) = options.rotation.logical_size(physical);
+    let line_length = framebuffer.fix_screen_info.line_length as usize;
+    let bytespp = framebuffer.var_screen_info.bits_per_pixel as usize / 8;
+
+    println!("{:#?}", framebuffer.var_screen_info);
+    println!("{:#?}", framebuffer.fix_screen_info);
+
+    let is_double_buffered =
+        framebuffer.var_screen_info.yres_virtual != framebuffer.var_screen_info.yres;
+
+    let (button_tx, button_rx) = channel();
+    if let Some(path) = touch::find_device() {
+        let sender = button_tx.clone();
+        std::thread::spawn(move || touch::read_device(&path, physical, sender));
+    }
+    match config().input {
+        InputDriver::Stick => {
+            std::thread::spawn(|| {
+                pasts::block_on(event_loop(button_tx));
+            });
+        }
+        InputDriver::Evdev => {
+            std::thread::spawn(|| gamepad::read_devices(button_tx));
+        }
+    }
+
+    if let Err(e) = crate::audio::start_oss() {
+        println!("No sound: {}", e);
+    }
+
+    let mut input_state = Input::with_repeat(Repeat::standard());
+    let is_high_frame = false;
+    let mut frame_data = vec![0; width * height * bytespp];
+    let mut scaled_data = vec![];
+    let mut tasks = Tasks::default();
+    // Frames can be written out as-is unless they need rotating, flipping or
+    // color correcting, or the panel's rows are padded
+    let unrotated = options.rotation == Rotation::None && line_length == width * bytespp;
+    let mut panel_data = vec![];
+    let mut color_lut = ColorLut::default();
+    let mut frame = Frame {
+        width,
+        height,
+        bytespp,
+        stride: width * bytespp,
+        data: &mut frame_data,
+    };
+    let mut governor = GovernorControl::new();
+    let mut exit_prompt = ExitPrompt::default();
+    // What the app sees while the exit prompt takes the buttons
+    let no_input = Input::default();
+    // An event that woke the loop early, still to be handled
+    let mut woken_by = None;
+    loop {
+        // Settings can change these at any time
+        let settings = config();
+        let (flip, color) = (settings.flip, settings.color);
+
+        // Handle the input buttons
+        input_state.update();
+        let mut exit_set = false;
+        while let Some(event) = woken_by.take().or_else(|| button_rx.try_recv().ok()) {
+            let (player, event) = match event {
+                DeviceEvent::Controller(player, event) => (player, event),
+                DeviceEvent::Touch(TouchEvent::Moved(x, y)) => {
+                    let (x, y) = flip.map_point(physical, (x, y));
+                    let (x, y) = options.rotation.to_logical(physical, (x, y));
+                    input_state.pointer_moved(x, y);
+                    continue;
+                }
+                DeviceEvent::Touch(TouchEvent::Pressed(pressed)) => {
+                    input_state.pointer_event(pressed);
+                    continue;
+                }
+            };
+            if let Event::Exit(true) = event {
+                // Pressing exit again while asked confirms
+                if exit_prompt.is_active() {
+                    exit_set = true;
+                    break;
+                }
+                exit_prompt.show();
+                continue;
+            }
+            if let Some((button, value)) = match event {
+                Event::BumperL(v) => Some((Button::BumperL, v)),
+                Event::BumperR(v) => Some((Button::BumperR, v)),
+                Event::PovDown(v) => Some((Button::PovDown, v)),
+                Event::PovUp(v) => Some((Button::PovUp, v)),
+                Event::PovLeft(v) => Some((Button::PovLeft, v)),
+                Event::PovRight(v) => Some((Button::PovRight, v)),
+                Event::MenuL(v) => Some((Button::MenuL, v)),
+                Event::MenuR(v) => Some((Button::MenuR, v)),
+                Event::ActionH(v) => Some((Button::ActionV, v)),
+                Event::ActionV(v) => Some((Button::ActionH, v)),
+                Event::ActionB(v) => Some((Button::ActionB, v)),
+                Event::ActionA(v) => Some((Button::ActionA, v)),
+                _ => None,
+            } {
+                input_state.player_event(player, button, value);
+            }
+            if let Some((axis, value)) = match event {
+                Event::JoyX(v) => Some((Axis::LeftX, v)),
+                Event::JoyY(v) => Some((Axis::LeftY, v)),
+                Event::CamX(v) => Some((Axis::RightX, v)),
+                Event::CamY(v) => Some((Axis::RightY, v)),
+                // The triggers are reported as joysticks
+                Event::JoyZ(v) => Some((Axis::TriggerL, v)),
+                Event::CamZ(v) => Some((Axis::TriggerR, v)),
+                _ => None,
+            } {
+                input_state.player_axis_event(player, axis, value as f32);
+            }
+        }
+        if exit_set {
+            return Ok(());
+        }
+        #[cfg(feature = "remote")]
+        crate::remote::apply(&mut input_state);
+
+        if exit_prompt.update(&input_state) {
+            return Ok(());
+        }
+
+        // Update the active app
+        let mut input_changed = input_state.any_changed();
+        let app_input = if exit_prompt.is_active() {
+            &no_input
+        } else {
+            &input_state
+        };
+        let mut ctx = AppContext::new(app_input, &mut frame, &mut tasks);
+        crate::display::update_app(&mut app, &mut ctx, &mut scaled_data);
+        exit_prompt.draw(&mut frame);
+        governor.apply(app.power_hint());
+
+        // Write out the frame to the inactive buffer
+        let yoffset = if is_high_frame && is_double_buffered {
+            physical.1
+        } else {
+            0
+        };
+        if unrotated && flip == Flip::None && color.is_identity() {
+            framebuffer.write_frame_offset(frame.data, line_length * yoffset);
+        } else {
+            panel_data.resize(line_length * physical.1, 0);
+            options.rotation.apply(
+                frame.data,
+                width,
+                height,
+                bytespp,
+                &mut panel_data,
+                line_length,
+            );
+            flip.apply(
+                &mut panel_data,
+                physical.0,
+                physical.1,
+                bytespp,
+                line_length,
+            );
+            color_lut.apply(
+                color,
+                &mut panel_data,
+                physical.0,
+                physical.1,
+                bytespp,
+                line_length,
+            );
+            framebuffer.write_frame_offset(&panel_data, line_length * yoffset);
+        }
+
+        if is_double_buffered {
+            // Flip the active buffers
+            let mut var_info = Framebuffer::get_var_screeninfo(&framebuffer.device)
+                .map_err(|e| Error::framebuffer(FRAMEBUFFER, e))?;
+            var_info.yoffset = yoffset as u32;
+            Framebuffer::put_var_screeninfo(&framebuffer.device, &var_info)
+                .map_err(|e| Error::framebuffer(FRAMEBUFFER, e))?;
+        }
+
+        if let Some(mut command) = app.pending_command() {
+            // Give up the framebuffer while the command owns the screen
+            drop(framebuffer);
+            governor.apply(PowerHint::Default);
+            println!("Running {:?}", command);
+            match command.status() {
+                Ok(status) => println!("{:?} exited with {}", command, status),
+                Err(e) => println!("Couldn't run {:?}: {}", command, e),
+            }
+
+            // The command may have changed the mode or panned the display
+            framebuffer =
+                Framebuffer::new(FRAMEBUFFER).map_err(|e| Error::framebuffer(FRAMEBUFFER, e))?;
+            Framebuffer::put_var_screeninfo(&framebuffer.device, &original_var_info)
+                .map_err(|e| Error::framebuffer(FRAMEBUFFER, e))?;
+            framebuffer.var_screen_info = original_var_info.clone();
+
+            // Presses meant for the command shouldn't leak into the launcher
+            while button_rx.try_recv().is_ok() {}
+            input_state = Input::with_repeat(Repeat::standard());
+            // Redraw over whatever the command left on screen
+            input_changed = true;
+        }
+
+        // Sleep until the app next needs updating, waking early for input
+        let min_sleep = Duration::from_millis(10);
+        haptics::tick();
+        let timeout = match app.activity().wake_by(input_state.next_repeat()) {
+            // Let the app see the buttons settle after a change
+            _ if input_changed => min_sleep,
+            // Keep the rumble following its patterns' envelopes
+            _ if haptics::is_playing() => min_sleep,
+            Activity::Busy => min_sleep,
+            Activity::Idle(until) => until
+                .saturating_duration_since(Instant::now())
+                .max(min_sleep),
+            Activity::Waiting => Duration::MAX,
+        };
+        match button_rx.recv_timeout(timeout) {
+            Ok(event) => woken_by = Some(event),
+            Err(RecvTimeoutError::Timeout) => (),
+            // The input thread is gone, so there's nothing to wait for
+            Err(RecvTimeoutError::Disconnected) => std::thread::sleep(min_sleep),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Games in progress, kept in the current profile so they can be picked up
+//! again after leaving for the menu. Games save from [`App::on_exit`] and
+//! offer to continue on their start screen.
+//!
+//! [`App::on_exit`]: crate::app::App::on_exit
+
+use std::{io, path::PathBuf};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+fn path(name: &str) -> PathBuf {
+    crate::profiles::dir().join(format!("{}.save.json", name))
+}
+
+/// A game's state that can be saved as JSON
+pub trait SaveState: Serialize + DeserializeOwned {
+    /// Names the save file, e.g. `snake`
+    const NAME: &'static str;
+
+    /// Writes the game out, unless a [demo](crate::demo) is playing
+    fn save(&self) -> io::Result<()> {
+        if crate::demo::playing() {
+            return Ok(());
+        }
+        let path = path(Self::NAME);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec(self)?)
+    }
+
+    /// The saved game, if there is one that can still be read
+    fn load() -> Option<Self> {
+        let bytes = std::fs::read(path(Self::NAME)).ok()?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| println!("Couldn't read the saved {} game: {}", Self::NAME, e))
+            .ok()
+    }
+
+    /// Forgets the saved game, e.g. once it's been continued
+    fn clear() {
+        if crate::demo::playing() {
+            return;
+        }
+        let _ = std::fs::remove_file(path(Self::NAME));
+    }
+}
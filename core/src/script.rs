@@ -0,0 +1,215 @@
+//! Lua-scripted apps, for writing small tools on-device without a
+//! cross-compiler.
+//!
+//! A script defines global `update()` and/or `draw()` functions, which are
+//! called once per frame in that order. While they run the script can use:
+//!
+//! - `gfx.width()`, `gfx.height()`
+//! - `gfx.fill_rect(x, y, w, h, r, g, b)`
+//! - `gfx.text(x, y, size, r, g, b, text)`
+//! - `input.pressed(name)`, `input.just_pressed(name)`, `input.just_released(name)`,
+//!   where `name` is a `Button` variant such as `"ActionA"`
+//! - `storage.get(key)`, `storage.set(key, value)`, persisted next to the script
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use enum_iterator::all;
+use mlua::{Function, Lua};
+use palette::LinSrgb;
+
+use crate::app::{App, AppContext, Button, Frame, Input};
+
+fn parse_button(name: &str) -> mlua::Result<Button> {
+    all::<Button>()
+        .find(|button| format!("{:?}", button) == name)
+        .ok_or_else(|| mlua::Error::RuntimeError(format!("unknown button `{}`", name)))
+}
+
+/// String key/value pairs stored one per line as `key=value`
+struct Storage {
+    path: PathBuf,
+    values: HashMap<String, String>,
+}
+
+impl Storage {
+    fn open(path: PathBuf) -> Self {
+        let values = std::fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Self { path, values }
+    }
+
+    fn set(&mut self, key: String, value: String) {
+        self.values.insert(key, value);
+        let contents = self
+            .values
+            .iter()
+            .map(|(k, v)| format!("{}={}\n", k, v))
+            .collect::<String>();
+        if let Err(e) = std::fs::write(&self.path, contents) {
+            println!("Couldn't save {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+pub struct ScriptApp {
+    lua: Lua,
+    error: Option<String>,
+}
+
+impl ScriptApp {
+    pub fn load(path: &Path) -> Self {
+        let lua = Lua::new();
+        let error = Self::init(&lua, path).err().map(|e| e.to_string());
+        Self { lua, error }
+    }
+
+    fn init(lua: &Lua, path: &Path) -> mlua::Result<()> {
+        let storage = Rc::new(RefCell::new(Storage::open(path.with_extension("kv"))));
+        let table = lua.create_table()?;
+        let get_storage = storage.clone();
+        table.set(
+            "get",
+            lua.create_function(move |_, key: String| {
+                Ok(get_storage.borrow().values.get(&key).cloned())
+            })?,
+        )?;
+        table.set(
+            "set",
+            lua.create_function(move |_, (key, value): (String, String)| {
+                storage.borrow_mut().set(key, value);
+                Ok(())
+            })?,
+        )?;
+        lua.globals().set("storage", table)?;
+
+        let source = std::fs::read_to_string(path)?;
+        lua.load(&source)
+            .set_name(path.display().to_string())
+            .exec()
+    }
+
+    fn run_frame(&self, input: &Input, frame: &mut Frame) -> mlua::Result<()> {
+        let lua = &self.lua;
+        let frame = RefCell::new(frame);
+        lua.scope(|scope| {
+            let gfx = lua.create_table()?;
+            gfx.set(
+                "width",
+                scope.create_function(|_, ()| Ok(frame.borrow().width()))?,
+            )?;
+            gfx.set(
+                "height",
+                scope.create_function(|_, ()| Ok(frame.borrow().height()))?,
+            )?;
+            gfx.set(
+                "fill_rect",
+                scope.create_function(
+                    |_, (x, y, w, h, r, g, b): (usize, usize, usize, usize, u8, u8, u8)| {
+                        frame
+                            .borrow_mut()
+                            .fill_rect(x, y, w, h, LinSrgb::new(r, g, b));
+                        Ok(())
+                    },
+                )?,
+            )?;
+            gfx.set(
+                "text",
+                scope.create_function(
+                    |_, (x, y, size, r, g, b, s): (usize, usize, f32, u8, u8, u8, String)| {
+                        frame.borrow_mut().text(
+                            "fonts/Ubuntu-B.ttf",
+                            x,
+                            y,
+                            size,
+                            LinSrgb::new(r, g, b),
+                            &s,
+                        );
+                        Ok(())
+                    },
+                )?,
+            )?;
+            lua.globals().set("gfx", gfx)?;
+
+            let input_table = lua.create_table()?;
+            input_table.set(
+                "pressed",
+                scope.create_function(|_, name: String| Ok(input.pressed(parse_button(&name)?)))?,
+            )?;
+            input_table.set(
+                "just_pressed",
+                scope.create_function(|_, name: String| {
+                    Ok(input.just_pressed(parse_button(&name)?))
+                })?,
+            )?;
+            input_table.set(
+                "just_released",
+                scope.create_function(|_, name: String| {
+                    Ok(input.just_released(parse_button(&name)?))
+                })?,
+            )?;
+            lua.globals().set("input", input_table)?;
+
+            for callback in ["update", "draw"] {
+                if let Some(f) = lua.globals().get::<_, Option<Function>>(callback)? {
+                    f.call::<_, ()>(())?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+impl App for ScriptApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        if self.error.is_none() {
+            if let Err(e) = self.run_frame(input, frame) {
+                println!("Script error: {}", e);
+                self.error = Some(e.to_string());
+            }
+        }
+
+        if let Some(error) = self.error.as_ref() {
+            frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+            let mut ctx = frame.context();
+            ctx.offset(20, 40);
+            ctx.set_color(LinSrgb::new(255, 0, 0));
+            ctx.text("Script error:");
+            ctx.set_color(LinSrgb::new(255, 255, 255));
+            for line in error.lines() {
+                ctx.offset(0, 20);
+                ctx.text(line);
+            }
+        }
+    }
+}
+
+/// The `scripts/` directory next to the launcher binary
+pub fn default_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("scripts")))
+        .unwrap_or_else(|| PathBuf::from("scripts"))
+}
+
+/// Every `.lua` file in `dir`, sorted by name
+pub fn discover(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lua"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
@@ -0,0 +1,282 @@
+//! SDL2 backend, for CFWs that only expose SDL and for desktops where the
+//! winit simulator doesn't work.
+
+use sdl2::{
+    audio::{AudioCallback, AudioDevice, AudioSpecDesired},
+    controller::{Axis as PadAxis, Button as PadButton, GameController},
+    event::Event,
+    keyboard::Scancode,
+    mouse::MouseButton,
+    pixels::PixelFormatEnum,
+    render::{Canvas, Texture},
+    video::Window,
+    EventPump, GameControllerSubsystem,
+};
+
+use crate::{
+    actions::{Action, ActionMap},
+    app::{App, Button, Input},
+    axes::Axis,
+    backend::{Display, InputSource},
+    display::{DisplayOptions, Rotation},
+    events::{publish, ControllerConnected, ControllerDisconnected},
+};
+
+pub struct SdlBackend {
+    canvas: Canvas<Window>,
+    texture: Texture,
+    /// The window's size, the panel's before rotation
+    physical: (usize, usize),
+    rotation: Rotation,
+    /// A rotated frame, on its way to the texture
+    rotated: Vec<u8>,
+    events: EventPump,
+    game_controllers: GameControllerSubsystem,
+    controllers: Vec<GameController>,
+    /// Kept open so sound keeps playing, if there's anywhere to play it
+    _audio: Option<AudioDevice<Mixer>>,
+}
+
+/// Hands SDL's audio callback samples from [`crate::audio`]
+struct Mixer;
+
+impl AudioCallback for Mixer {
+    type Channel = i16;
+
+    fn callback(&mut self, out: &mut [i16]) {
+        // The buffer before this one is about to finish playing
+        crate::audio::mix(out, out.len());
+    }
+}
+
+impl SdlBackend {
+    /// A window the panel's [size](DisplayOptions::size), showing frames
+    /// turned by its [rotation](DisplayOptions::rotation)
+    pub fn new(options: DisplayOptions) -> Result<Self, String> {
+        let physical = options.size.unwrap_or((640, 480));
+        let (width, height) = (physical.0 as u32, physical.1 as u32);
+        let sdl = sdl2::init()?;
+        let video = sdl.video()?;
+        let window = video
+            .window("RG35XX", width, height)
+            .position_centered()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        // BGR888 is laid out as R, G, B, padding in memory on little-endian,
+        // which matches a 4-byte Frame
+        let texture = canvas
+            .texture_creator()
+            .create_texture_streaming(PixelFormatEnum::BGR888, width, height)
+            .map_err(|e| e.to_string())?;
+        let events = sdl.event_pump()?;
+        let game_controllers = sdl.game_controller()?;
+        let spec = AudioSpecDesired {
+            freq: Some(crate::audio::SAMPLE_RATE as i32),
+            channels: Some(1),
+            samples: Some(512),
+        };
+        let audio = sdl
+            .audio()
+            .and_then(|audio| audio.open_playback(None, &spec, |_| Mixer))
+            .inspect(|device| device.resume())
+            .inspect_err(|e| println!("No sound: {}", e))
+            .ok();
+
+        Ok(Self {
+            canvas,
+            texture,
+            physical,
+            rotation: options.rotation,
+            rotated: vec![],
+            events,
+            game_controllers,
+            controllers: vec![],
+            _audio: audio,
+        })
+    }
+}
+
+/// Which player the controller with SDL instance id `which` is, in the order
+/// they were connected. The keyboard is player 0 too.
+fn player(controllers: &[GameController], which: u32) -> usize {
+    controllers
+        .iter()
+        .position(|controller| controller.instance_id() == which)
+        .unwrap_or(0)
+}
+
+fn map_scancode(scancode: Scancode) -> Option<Button> {
+    // Same layout as the winit simulator
+    match scancode {
+        Scancode::A => Some(Button::PovLeft),
+        Scancode::S => Some(Button::PovDown),
+        Scancode::D => Some(Button::PovRight),
+        Scancode::W => Some(Button::PovUp),
+        Scancode::Kp4 | Scancode::Left => Some(Button::ActionV),
+        Scancode::Kp2 | Scancode::Down => Some(Button::ActionB),
+        Scancode::Kp6 | Scancode::Right => Some(Button::ActionA),
+        Scancode::Kp8 | Scancode::Up => Some(Button::ActionH),
+        Scancode::Space => Some(Button::BumperL),
+        Scancode::Kp0 => Some(Button::BumperR),
+        Scancode::Period => Some(Button::MenuR),
+        Scancode::Comma => Some(Button::MenuL),
+        Scancode::Return => ActionMap::global()
+            .buttons(Action::Confirm)
+            .first()
+            .copied(),
+        Scancode::Escape | Scancode::Backspace => {
+            ActionMap::global().buttons(Action::Back).first().copied()
+        }
+        _ => None,
+    }
+}
+
+fn map_pad_button(button: PadButton) -> Option<Button> {
+    // SDL names face buttons by position on an Xbox pad
+    match button {
+        PadButton::DPadUp => Some(Button::PovUp),
+        PadButton::DPadDown => Some(Button::PovDown),
+        PadButton::DPadLeft => Some(Button::PovLeft),
+        PadButton::DPadRight => Some(Button::PovRight),
+        PadButton::B => Some(Button::ActionA),
+        PadButton::A => Some(Button::ActionB),
+        PadButton::X => Some(Button::ActionV),
+        PadButton::Y => Some(Button::ActionH),
+        PadButton::LeftShoulder => Some(Button::BumperL),
+        PadButton::RightShoulder => Some(Button::BumperR),
+        PadButton::Back => Some(Button::MenuL),
+        PadButton::Start => Some(Button::MenuR),
+        _ => None,
+    }
+}
+
+impl Display for SdlBackend {
+    fn size(&self) -> (usize, usize) {
+        self.rotation.logical_size(self.physical)
+    }
+
+    fn present(&mut self, data: &[u8]) {
+        let pitch = self.physical.0 * 4;
+        let data = if self.rotation == Rotation::None {
+            data
+        } else {
+            let (width, height) = self.size();
+            self.rotated.resize(data.len(), 0);
+            self.rotation
+                .apply(data, width, height, 4, &mut self.rotated, pitch);
+            &self.rotated
+        };
+        if let Err(e) = self.texture.update(None, data, pitch) {
+            println!("Couldn't update texture: {}", e);
+            return;
+        }
+        self.canvas.clear();
+        if let Err(e) = self.canvas.copy(&self.texture, None, None) {
+            println!("Couldn't draw texture: {}", e);
+        }
+        self.canvas.present();
+    }
+}
+
+impl InputSource for SdlBackend {
+    fn poll(&mut self, input: &mut Input) -> bool {
+        for event in self.events.poll_iter() {
+            match event {
+                Event::Quit { .. } => return false,
+                Event::KeyDown {
+                    scancode: Some(scancode),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(button) = map_scancode(scancode) {
+                        input.player_event(0, button, true);
+                    }
+                }
+                Event::KeyUp {
+                    scancode: Some(scancode),
+                    ..
+                } => {
+                    if let Some(button) = map_scancode(scancode) {
+                        input.player_event(0, button, false);
+                    }
+                }
+                // The mouse stands in for a touchscreen
+                Event::MouseMotion { x, y, .. } => {
+                    let (width, height) = self.physical;
+                    if (0..width as i32).contains(&x) && (0..height as i32).contains(&y) {
+                        let (x, y) = self
+                            .rotation
+                            .to_logical(self.physical, (x as usize, y as usize));
+                        input.pointer_moved(x, y);
+                    } else {
+                        input.pointer_left();
+                    }
+                }
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    ..
+                } => input.pointer_event(true),
+                Event::MouseButtonUp {
+                    mouse_btn: MouseButton::Left,
+                    ..
+                } => input.pointer_event(false),
+                Event::ControllerDeviceAdded { which, .. } => {
+                    match self.game_controllers.open(which) {
+                        Ok(controller) => {
+                            println!("Connected {}", controller.name());
+                            publish(ControllerConnected {
+                                player: self.controllers.len(),
+                                name: controller.name(),
+                            });
+                            self.controllers.push(controller);
+                        }
+                        Err(e) => println!("Couldn't open controller {}: {}", which, e),
+                    }
+                }
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    publish(ControllerDisconnected {
+                        player: player(&self.controllers, which),
+                    });
+                    self.controllers
+                        .retain(|controller| controller.instance_id() != which);
+                }
+                Event::ControllerAxisMotion {
+                    which, axis, value, ..
+                } => {
+                    let axis = match axis {
+                        PadAxis::LeftX => Axis::LeftX,
+                        PadAxis::LeftY => Axis::LeftY,
+                        PadAxis::RightX => Axis::RightX,
+                        PadAxis::RightY => Axis::RightY,
+                        PadAxis::TriggerLeft => Axis::TriggerL,
+                        PadAxis::TriggerRight => Axis::TriggerR,
+                    };
+                    input.player_axis_event(
+                        player(&self.controllers, which),
+                        axis,
+                        value as f32 / i16::MAX as f32,
+                    );
+                }
+                Event::ControllerButtonDown { which, button, .. } => {
+                    if let Some(button) = map_pad_button(button) {
+                        input.player_event(player(&self.controllers, which), button, true);
+                    }
+                }
+                Event::ControllerButtonUp { which, button, .. } => {
+                    if let Some(button) = map_pad_button(button) {
+                        input.player_event(player(&self.controllers, which), button, false);
+                    }
+                }
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+pub fn run_app(app: impl App, options: DisplayOptions) -> Result<(), crate::Error> {
+    let mut backend = SdlBackend::new(options).map_err(crate::Error::Sdl)?;
+    crate::backend::run(app, &mut backend);
+    Ok(())
+}
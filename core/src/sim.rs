@@ -0,0 +1,459 @@
+use std::num::NonZeroU32;
+use std::rc::Rc;
+#[cfg(target_os = "linux")]
+use winit::event_loop::EventLoopBuilder;
+#[cfg(target_os = "linux")]
+use winit::platform::{wayland::EventLoopBuilderExtWayland, x11::EventLoopBuilderExtX11};
+use winit::window::{Fullscreen, Window, WindowId};
+use winit::{application::ApplicationHandler, keyboard::PhysicalKey};
+use winit::{event::WindowEvent, keyboard::KeyCode};
+use winit::{
+    event::{ElementState, MouseButton, StartCause},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+};
+
+use crate::actions::{Action, ActionMap};
+use crate::app::{Activity, AppContext, Frame, Input, Repeat};
+use crate::display::{ColorLut, DisplayOptions, WindowSystem};
+use crate::error::Error;
+use crate::keys::{KeyHelp, KeyMap};
+use crate::tasks::Tasks;
+
+pub(crate) struct App<A> {
+    app: A,
+    options: DisplayOptions,
+    /// The simulated panel's size
+    physical: (usize, usize),
+    frame_data: Vec<u8>,
+    scaled_data: Vec<u8>,
+    tasks: Tasks,
+    panel_data: Vec<u8>,
+    color_lut: ColorLut,
+    input: Input,
+    window: Option<Rc<Window>>,
+    surface: Option<softbuffer::Surface<Rc<Window>, Rc<Window>>>,
+    /// What stopped the event loop early, returned from `run_app`
+    error: Option<Error>,
+    keys: KeyMap,
+    key_help: KeyHelp,
+    /// Where the panel was last drawn in the window, as left, top, width
+    /// and height
+    letterbox: (usize, usize, usize, usize),
+}
+
+impl<A> App<A> {
+    pub(crate) fn new(app: A, options: DisplayOptions) -> Self {
+        let physical = options.size.unwrap_or((640, 480));
+        Self {
+            app,
+            options,
+            physical,
+            frame_data: vec![0; physical.0 * physical.1 * 4],
+            scaled_data: vec![],
+            tasks: Tasks::default(),
+            panel_data: vec![0; physical.0 * physical.1 * 4],
+            color_lut: ColorLut::default(),
+            input: Input::with_repeat(Repeat::standard()),
+            window: None,
+            surface: None,
+            error: None,
+            keys: KeyMap::load(),
+            key_help: KeyHelp::default(),
+            letterbox: (0, 0, physical.0, physical.1),
+        }
+    }
+
+    /// Stops the event loop, which then returns `error` from `run_app`
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: Error) {
+        self.error = Some(error);
+        event_loop.exit();
+    }
+
+    fn open_window(&mut self, event_loop: &ActiveEventLoop) -> Result<(), Error> {
+        let attributes = Window::default_attributes();
+        #[cfg(not(target_arch = "wasm32"))]
+        let attributes = {
+            let config = crate::config::config();
+            let (width, height) = self
+                .options
+                .window
+                .or(config.window)
+                .unwrap_or(self.physical);
+            // Logical pixels, so the window isn't tiny on high DPI screens
+            let attributes = attributes
+                .with_title("RG35XX")
+                .with_inner_size(winit::dpi::LogicalSize::new(width as u32, height as u32));
+            let attributes = match config.window_position {
+                Some((x, y)) => attributes.with_position(winit::dpi::PhysicalPosition::new(x, y)),
+                None => attributes,
+            };
+            if self.options.fullscreen {
+                attributes.with_fullscreen(Some(Fullscreen::Borderless(None)))
+            } else {
+                attributes
+            }
+        };
+        #[cfg(target_arch = "wasm32")]
+        let attributes = {
+            use winit::platform::web::WindowAttributesExtWebSys;
+            // The canvas starts out zero-sized unless we give it the device's size
+            attributes
+                .with_append(true)
+                .with_inner_size(winit::dpi::PhysicalSize::new(
+                    self.physical.0 as u32,
+                    self.physical.1 as u32,
+                ))
+        };
+        let window = event_loop
+            .create_window(attributes)
+            .map_err(|e| Error::Window(e.to_string()))?;
+        let window = Rc::new(window);
+        let context =
+            softbuffer::Context::new(window.clone()).map_err(|e| Error::Window(e.to_string()))?;
+        let surface = softbuffer::Surface::new(&context, window.clone())
+            .map_err(|e| Error::Window(e.to_string()))?;
+
+        self.window = Some(window);
+        self.surface = Some(surface);
+        Ok(())
+    }
+
+    /// Remembers the window's size and position for next time. Skipped
+    /// before setup, which a saved `launcher.txt` would count as done.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_geometry(&self) {
+        let Some(window) = self.window.as_ref() else {
+            return;
+        };
+        let mut config = crate::config::config();
+        if !config.set_up || window.fullscreen().is_some() {
+            return;
+        }
+        let size = window.inner_size().to_logical::<f64>(window.scale_factor());
+        config.window = Some((size.width as usize, size.height as usize));
+        // Wayland doesn't say where windows are
+        if let Ok(position) = window.outer_position() {
+            config.window_position = Some((position.x, position.y));
+        }
+        if let Err(e) = crate::config::set_config(config) {
+            println!("Couldn't save the window size: {}", e);
+        }
+    }
+
+    /// Maps a point in the window to the panel, if it's on the panel
+    fn to_panel(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        let (left, top, width, height) = self.letterbox;
+        if x < 0.0 || y < 0.0 || width == 0 || height == 0 {
+            return None;
+        }
+        let (x, y) = (
+            (x as usize).checked_sub(left)?,
+            (y as usize).checked_sub(top)?,
+        );
+        (x < width && y < height)
+            .then(|| (x * self.physical.0 / width, y * self.physical.1 / height))
+    }
+
+    /// Scales the panel to fit the window, keeping its shape, and fills the
+    /// rest of the window with black
+    fn present(&mut self) -> Result<(), Error> {
+        let (panel_width, panel_height) = (self.physical.0 as u32, self.physical.1 as u32);
+        let (Some(window), Some(surface)) = (self.window.as_ref(), self.surface.as_mut()) else {
+            return Ok(());
+        };
+        let size = window.inner_size();
+        let (Some(width), Some(height)) =
+            (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+        else {
+            // Minimised, so there's nothing to draw into
+            return Ok(());
+        };
+        surface
+            .resize(width, height)
+            .map_err(|e| Error::Present(e.to_string()))?;
+
+        let (width, height) = (width.get() as usize, height.get() as usize);
+        let (panel_width, panel_height) = (panel_width as usize, panel_height as usize);
+        let scale = (width as f32 / panel_width as f32).min(height as f32 / panel_height as f32);
+        let scaled = (
+            ((panel_width as f32 * scale) as usize).clamp(1, width),
+            ((panel_height as f32 * scale) as usize).clamp(1, height),
+        );
+        let (left, top) = ((width - scaled.0) / 2, (height - scaled.1) / 2);
+        self.letterbox = (left, top, scaled.0, scaled.1);
+        // The panel column each window column shows
+        let columns = (0..scaled.0)
+            .map(|x| x * panel_width / scaled.0)
+            .collect::<Vec<_>>();
+
+        let mut buffer = surface
+            .buffer_mut()
+            .map_err(|e| Error::Present(e.to_string()))?;
+        buffer.fill(0);
+        for y in 0..scaled.1 {
+            let row = &self.panel_data[y * panel_height / scaled.1 * panel_width * 4..];
+            let start = (top + y) * width + left;
+            for (pixel, &column) in buffer[start..start + scaled.0].iter_mut().zip(&columns) {
+                let offset = column * 4;
+                let red = row[offset] as u32;
+                let green = row[offset + 1] as u32;
+                let blue = row[offset + 2] as u32;
+                *pixel = blue | (green << 8) | (red << 16);
+            }
+        }
+
+        buffer.present().map_err(|e| Error::Present(e.to_string()))
+    }
+}
+
+impl<A: crate::app::App> ApplicationHandler for App<A> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if let Err(e) = self.open_window(event_loop) {
+            self.fail(event_loop, e);
+        }
+    }
+
+    fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            if let Some(window) = self.window.as_ref() {
+                window.request_redraw();
+            }
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => {
+                println!("The close button was pressed; stopping");
+                #[cfg(not(target_arch = "wasm32"))]
+                self.save_geometry();
+                event_loop.exit();
+            }
+            WindowEvent::RedrawRequested => {
+                // Redraw the application.
+                //
+                // It's preferable for applications that do not render continuously to render in
+                // this event rather than in AboutToWait, since rendering in here allows
+                // the program to gracefully handle redraws requested by the OS.
+
+                // Update app
+                #[cfg(feature = "remote")]
+                crate::remote::apply(&mut self.input);
+                let (logical_width, logical_height) =
+                    self.options.rotation.logical_size(self.physical);
+                let mut frame = Frame {
+                    width: logical_width,
+                    height: logical_height,
+                    bytespp: 4,
+                    stride: logical_width * 4,
+                    data: &mut self.frame_data,
+                };
+                let mut ctx = AppContext::new(&self.input, &mut frame, &mut self.tasks);
+                crate::display::update_app(&mut self.app, &mut ctx, &mut self.scaled_data);
+                self.key_help.draw(&mut frame, &self.keys);
+                let input_changed = self.input.any_changed();
+                self.input.update();
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(mut command) = self.app.pending_command() {
+                    // Blocks the window until the command exits, like the device does
+                    println!("Running {:?}", command);
+                    match command.status() {
+                        Ok(status) => println!("{:?} exited with {}", command, status),
+                        Err(e) => println!("Couldn't run {:?}: {}", command, e),
+                    }
+                    self.input = Input::with_repeat(Repeat::standard());
+                }
+
+                // Show what the rotated panel would
+                self.options.rotation.apply(
+                    &self.frame_data,
+                    logical_width,
+                    logical_height,
+                    4,
+                    &mut self.panel_data,
+                    self.physical.0 * 4,
+                );
+                let settings = crate::config::config();
+                settings.flip.apply(
+                    &mut self.panel_data,
+                    self.physical.0,
+                    self.physical.1,
+                    4,
+                    self.physical.0 * 4,
+                );
+                self.color_lut.apply(
+                    settings.color,
+                    &mut self.panel_data,
+                    self.physical.0,
+                    self.physical.1,
+                    4,
+                    self.physical.0 * 4,
+                );
+
+                if let Err(e) = self.present() {
+                    self.fail(event_loop, e);
+                    return;
+                }
+                let Some(window) = self.window.as_ref() else {
+                    return;
+                };
+
+                // Queue the next RedrawRequested event, unless the app is idle. Idle apps are
+                // woken by new_events once their deadline passes, or by a key changing.
+                event_loop.set_control_flow(ControlFlow::Wait);
+                match self.app.activity().wake_by(self.input.next_repeat()) {
+                    // Let the app see the buttons settle after a change, or a
+                    // held button repeat
+                    _ if input_changed || self.input.any_repeating() => window.request_redraw(),
+                    Activity::Busy => window.request_redraw(),
+                    Activity::Idle(until) => {
+                        event_loop.set_control_flow(ControlFlow::WaitUntil(until))
+                    }
+                    Activity::Waiting => (),
+                }
+            }
+            // The mouse stands in for a touchscreen
+            // Redraw at the new scale
+            WindowEvent::Resized(_) => {
+                self.window.as_ref().unwrap().request_redraw();
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if let Some((x, y)) = self.to_panel(position.x, position.y) {
+                    let (x, y) = crate::config::config()
+                        .flip
+                        .map_point(self.physical, (x, y));
+                    let (x, y) = self.options.rotation.to_logical(self.physical, (x, y));
+                    self.input.pointer_moved(x, y);
+                } else {
+                    self.input.pointer_left();
+                }
+                if self.input.any_changed() {
+                    self.window.as_ref().unwrap().request_redraw();
+                }
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.input.pointer_left();
+                self.window.as_ref().unwrap().request_redraw();
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.input.pointer_event(state == ElementState::Pressed);
+                self.window.as_ref().unwrap().request_redraw();
+            }
+            WindowEvent::KeyboardInput { event, .. } => {
+                let PhysicalKey::Code(key) = event.physical_key else {
+                    return;
+                };
+                let pressed = event.state == ElementState::Pressed;
+                let window = self.window.as_ref().unwrap();
+                if pressed && !event.repeat && matches!(key, KeyCode::F1 | KeyCode::F2) {
+                    if key == KeyCode::F1 {
+                        self.key_help.toggle();
+                    } else {
+                        self.key_help.start_remap(&self.keys);
+                    }
+                    // Held keys would otherwise stay pressed behind the help
+                    self.input = Input::with_repeat(Repeat::standard());
+                    window.request_redraw();
+                    return;
+                }
+                if self.key_help.active() {
+                    if pressed && !event.repeat {
+                        self.key_help.key_pressed(key, &mut self.keys);
+                        window.request_redraw();
+                    }
+                    return;
+                }
+                let button = self.keys.button(key).or_else(|| {
+                    // Whichever buttons confirm and go back on this setup, for
+                    // player 1
+                    let action = match key {
+                        KeyCode::Enter => Action::Confirm,
+                        KeyCode::Escape | KeyCode::Backspace => Action::Back,
+                        _ => return None,
+                    };
+                    let button = ActionMap::global().buttons(action).first().copied()?;
+                    Some((0, button))
+                });
+                if let Some((player, button)) = button {
+                    self.input.player_event(player, button, pressed);
+                    window.request_redraw();
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// An event loop on the window system asked for. winit only allows one per
+/// process, so there's no falling back to another if it fails.
+#[cfg(not(target_arch = "wasm32"))]
+fn event_loop(system: WindowSystem) -> Result<EventLoop<()>, Error> {
+    #[cfg(target_os = "linux")]
+    let event_loop = {
+        let mut builder = EventLoopBuilder::default();
+        match system {
+            // winit looks for $WAYLAND_DISPLAY, then $DISPLAY
+            WindowSystem::Auto => &mut builder,
+            WindowSystem::Wayland => builder.with_wayland(),
+            WindowSystem::X11 => builder.with_x11(),
+        }
+        .build()
+    };
+    #[cfg(not(target_os = "linux"))]
+    let event_loop = {
+        if system != WindowSystem::Auto {
+            println!(
+                "Ignoring --display {:?}, which only applies on Linux",
+                system
+            );
+        }
+        EventLoop::new()
+    };
+    event_loop.map_err(|e| match system {
+        WindowSystem::Auto => Error::Window(e.to_string()),
+        _ => Error::Window(format!("{:?}: {}", system, e)),
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run_app(app: impl crate::app::App, options: DisplayOptions) -> Result<(), Error> {
+    let event_loop = event_loop(options.window_system)?;
+
+    // ControlFlow::Poll continuously runs the event loop, even if the OS hasn't
+    // dispatched any events. This is ideal for games and similar applications.
+    event_loop.set_control_flow(ControlFlow::Poll);
+
+    // ControlFlow::Wait pauses the event loop if no events are available to process.
+    // This is ideal for non-game applications that only update in response to user
+    // input, and uses significantly less power/CPU time than ControlFlow::Poll.
+    event_loop.set_control_flow(ControlFlow::Wait);
+
+    let mut app = App::new(app, options);
+    event_loop
+        .run_app(&mut app)
+        .map_err(|e| Error::Window(e.to_string()))?;
+    app.error.map_or(Ok(()), Err)
+}
+
+/// Run this app using `Wayland`, and allows running the event loop on a separate thread during simulation
+#[cfg(target_os = "linux")]
+pub fn run_app_wayland(app: impl crate::app::App, options: DisplayOptions) -> Result<(), Error> {
+    let mut builder = EventLoopBuilder::default();
+    EventLoopBuilderExtWayland::with_any_thread(builder.with_wayland(), true);
+    let event_loop = builder.build().map_err(|e| Error::Window(e.to_string()))?;
+
+    // ControlFlow::Poll continuously runs the event loop, even if the OS hasn't
+    // dispatched any events. This is ideal for games and similar applications.
+    event_loop.set_control_flow(ControlFlow::Poll);
+
+    let mut app = App::new(app, options);
+    event_loop
+        .run_app(&mut app)
+        .map_err(|e| Error::Window(e.to_string()))?;
+    app.error.map_or(Ok(()), Err)
+}
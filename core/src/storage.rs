@@ -0,0 +1,429 @@
+//! Files the launcher keeps next to its binary, and exports of app logs that
+//! can be copied off the SD card.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "encryption")]
+pub mod encrypted;
+
+/// The directory holding the launcher binary and its data
+pub fn base_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+        .unwrap_or_default()
+}
+
+/// Where [`CsvWriter`] and [`GpxWriter`] put their files
+pub fn exports_dir() -> PathBuf {
+    base_dir().join("exports")
+}
+
+/// `(year, month, day, hour, minute, second)` in UTC
+fn civil_time(time: SystemTime) -> (i64, u32, u32, u32, u32, u32) {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    civil_from_unix(secs)
+}
+
+/// [`civil_time`] for a count of seconds since 1970, which can be shifted for
+/// a time zone first
+pub(crate) fn civil_from_unix(secs: i64) -> (i64, u32, u32, u32, u32, u32) {
+    let (days, secs) = (secs.div_euclid(86400), secs.rem_euclid(86400) as u32);
+    // Howard Hinnant's days_from_civil, backwards
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day, secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// `2024-05-01T12:30:00Z`
+pub fn iso8601(time: SystemTime) -> String {
+    let (year, month, day, hour, minute, second) = civil_time(time);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// Creates a new file in the exports directory named after `name` and the
+/// current time
+fn create_export(name: &str, extension: &str) -> io::Result<(PathBuf, BufWriter<File>)> {
+    let dir = exports_dir();
+    std::fs::create_dir_all(&dir)?;
+    let (year, month, day, hour, minute, second) = civil_time(SystemTime::now());
+    let path = dir.join(format!(
+        "{}-{:04}{:02}{:02}-{:02}{:02}{:02}.{}",
+        name, year, month, day, hour, minute, second, extension
+    ));
+    let file = File::create(&path)?;
+    Ok((path, BufWriter::new(file)))
+}
+
+/// Writes `text` to a new file in the exports directory named after `name`
+/// and the current time, e.g. a report at the end of a game
+pub fn write_text_export(name: &str, text: &str) -> io::Result<PathBuf> {
+    let (path, mut out) = create_export(name, "txt")?;
+    out.write_all(text.as_bytes())?;
+    out.flush()?;
+    Ok(path)
+}
+
+/// Writes a table one row at a time
+pub struct CsvWriter {
+    path: PathBuf,
+    out: BufWriter<File>,
+}
+
+impl CsvWriter {
+    /// Starts a new export with a header row
+    pub fn create(name: &str, columns: &[&str]) -> io::Result<Self> {
+        let (path, out) = create_export(name, "csv")?;
+        let mut writer = Self { path, out };
+        writer.row(columns)?;
+        Ok(writer)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn row<S: AsRef<str>>(&mut self, values: &[S]) -> io::Result<()> {
+        let row = values
+            .iter()
+            .map(|value| csv_field(value.as_ref()))
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(self.out, "{}", row)?;
+        // Rows should survive the battery running out
+        self.out.flush()
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// `text` escaped to go between XML tags or in an attribute
+fn xml_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes a GPS track. The file is closed off when the writer is dropped.
+pub struct GpxWriter {
+    path: PathBuf,
+    out: BufWriter<File>,
+}
+
+impl GpxWriter {
+    pub fn create(name: &str) -> io::Result<Self> {
+        let (path, mut out) = create_export(name, "gpx")?;
+        write!(
+            out,
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<gpx version=\"1.1\" creator=\"rg35xx\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+                "<trk><name>{}</name><trkseg>\n"
+            ),
+            xml_text(name)
+        )?;
+        Ok(Self { path, out })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Adds a point, with `elevation` in meters
+    pub fn point(
+        &mut self,
+        latitude: f64,
+        longitude: f64,
+        elevation: Option<f64>,
+        time: SystemTime,
+    ) -> io::Result<()> {
+        write!(
+            self.out,
+            "<trkpt lat=\"{:.7}\" lon=\"{:.7}\">",
+            latitude, longitude
+        )?;
+        if let Some(elevation) = elevation {
+            write!(self.out, "<ele>{:.1}</ele>", elevation)?;
+        }
+        writeln!(self.out, "<time>{}</time></trkpt>", iso8601(time))
+    }
+}
+
+impl Drop for GpxWriter {
+    fn drop(&mut self) {
+        let _ = self.out.write_all(b"</trkseg></trk>\n</gpx>\n");
+        let _ = self.out.flush();
+    }
+}
+
+/// A file in the exports directory
+#[derive(Debug, Clone)]
+pub struct ExportFile {
+    pub path: PathBuf,
+    pub name: String,
+    /// In bytes
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// Everything in the exports directory, newest first
+pub fn exports() -> Vec<ExportFile> {
+    let Ok(entries) = std::fs::read_dir(exports_dir()) else {
+        return vec![];
+    };
+    let mut files = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| ExportFile {
+                path: entry.path(),
+                name: entry.file_name().to_string_lossy().into_owned(),
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(UNIX_EPOCH),
+            })
+        })
+        .collect::<Vec<_>>();
+    files.sort_by_key(|file| std::cmp::Reverse(file.modified));
+    files
+}
+
+/// How much an app was used
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AppUsage {
+    pub launches: u64,
+    pub seconds: u64,
+}
+
+/// How much each app has been used each day, kept in `usage.txt` next to the
+/// binary as `date launches seconds app` lines, e.g. `2024-05-01 3 620 Snake`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Usage {
+    /// By date, then by app name
+    days: BTreeMap<String, BTreeMap<String, AppUsage>>,
+}
+
+fn usage_path() -> PathBuf {
+    base_dir().join("usage.txt")
+}
+
+impl Usage {
+    pub fn load() -> Self {
+        Self::parse(&std::fs::read_to_string(usage_path()).unwrap_or_default())
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut usage = Self::default();
+        for line in text.lines() {
+            let mut parts = line.trim().splitn(4, ' ');
+            let (Some(date), Some(launches), Some(seconds), Some(app)) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            let (Ok(launches), Ok(seconds)) = (launches.parse(), seconds.parse()) else {
+                println!("Ignoring `{}` in usage.txt", line);
+                continue;
+            };
+            usage.add(date, app, AppUsage { launches, seconds });
+        }
+        usage
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (date, apps) in &self.days {
+            for (app, usage) in apps {
+                text += &format!("{} {} {} {}\n", date, usage.launches, usage.seconds, app);
+            }
+        }
+        text
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        std::fs::write(usage_path(), self.to_text())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.days.is_empty()
+    }
+
+    /// Adds to what `app` has used on `date`, a `YYYY-MM-DD` string
+    pub fn add(&mut self, date: &str, app: &str, usage: AppUsage) {
+        let entry = self
+            .days
+            .entry(date.to_string())
+            .or_default()
+            .entry(app.to_string())
+            .or_default();
+        entry.launches += usage.launches;
+        entry.seconds += usage.seconds;
+    }
+
+    /// Each app's use over every day, longest first
+    pub fn totals(&self) -> Vec<(String, AppUsage)> {
+        let mut totals = BTreeMap::<String, AppUsage>::new();
+        for apps in self.days.values() {
+            for (app, usage) in apps {
+                let total = totals.entry(app.clone()).or_default();
+                total.launches += usage.launches;
+                total.seconds += usage.seconds;
+            }
+        }
+        let mut totals = totals.into_iter().collect::<Vec<_>>();
+        totals.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.seconds));
+        totals
+    }
+
+    /// Seconds spent in all apps on each of the `count` days ending with
+    /// `today`, oldest first
+    pub fn daily(&self, today: i64, count: usize) -> Vec<(String, u64)> {
+        self.daily_where(today, count, |_| true)
+    }
+
+    /// Like [`Usage::daily`], but only counting `app`
+    pub fn daily_app(&self, app: &str, today: i64, count: usize) -> Vec<(String, u64)> {
+        self.daily_where(today, count, |name| name == app)
+    }
+
+    fn daily_where(
+        &self,
+        today: i64,
+        count: usize,
+        include: impl Fn(&str) -> bool,
+    ) -> Vec<(String, u64)> {
+        (0..count as i64)
+            .rev()
+            .map(|ago| {
+                let date = date_of_day(today - ago);
+                let seconds = self.days.get(&date).map_or(0, |apps| {
+                    apps.iter()
+                        .filter(|(app, _)| include(app))
+                        .map(|(_, usage)| usage.seconds)
+                        .sum()
+                });
+                (date, seconds)
+            })
+            .collect()
+    }
+}
+
+/// `YYYY-MM-DD` for a count of days since 1970
+pub fn date_of_day(day: i64) -> String {
+    let (year, month, day, ..) = civil_from_unix(day * 86400);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Days since 1970 in the time zone from [`crate::config`]
+pub fn today() -> i64 {
+    let secs = crate::time::SystemTime::now()
+        .duration_since(crate::time::SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    (secs + crate::config::config().utc_offset as i64 * 3600).div_euclid(86400)
+}
+
+/// Adds to today's use of `app` in `usage.txt`
+pub fn record_usage(app: &str, usage: AppUsage) {
+    let mut all = Usage::load();
+    all.add(&date_of_day(today()), app, usage);
+    if let Err(e) = all.save() {
+        println!("Couldn't save usage.txt: {}", e);
+    }
+}
+
+/// Forgets all recorded use
+pub fn reset_usage() -> io::Result<()> {
+    match std::fs::remove_file(usage_path()) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_usage() {
+        let mut usage =
+            Usage::parse("2024-05-01 2 600 Snake\n2024-05-02 1 60 Distance Integrator\nbad\n");
+        usage.add(
+            "2024-05-02",
+            "Snake",
+            AppUsage {
+                launches: 1,
+                seconds: 30,
+            },
+        );
+        assert_eq!(Usage::parse(&usage.to_text()), usage);
+
+        let totals = usage.totals();
+        assert_eq!(
+            totals[0],
+            (
+                "Snake".to_string(),
+                AppUsage {
+                    launches: 3,
+                    seconds: 630
+                }
+            )
+        );
+        assert_eq!(totals[1].0, "Distance Integrator");
+
+        // 2024-05-02
+        let today = 19845;
+        assert_eq!(date_of_day(today), "2024-05-02");
+        assert_eq!(
+            usage.daily(today, 3),
+            [
+                ("2024-04-30".to_string(), 0),
+                ("2024-05-01".to_string(), 600),
+                ("2024-05-02".to_string(), 90),
+            ]
+        );
+        assert_eq!(usage.daily_app("Snake", today, 1)[0].1, 30);
+    }
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(iso8601(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            iso8601(UNIX_EPOCH + Duration::from_secs(1709210096)),
+            "2024-02-29T12:34:56Z"
+        );
+    }
+
+    #[test]
+    fn test_xml_text() {
+        assert_eq!(xml_text("Walk"), "Walk");
+        assert_eq!(
+            xml_text("<Tom & \"Jerry\">"),
+            "&lt;Tom &amp; &quot;Jerry&quot;&gt;"
+        );
+    }
+}
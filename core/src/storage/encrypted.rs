@@ -0,0 +1,222 @@
+//! Files encrypted with a key made from a PIN, for apps holding things like
+//! login secrets. Each file is ChaCha20-Poly1305 with a key stretched from the
+//! PIN by PBKDF2, so a wrong PIN or a damaged file fails to open rather than
+//! reading as garbage.
+//!
+//! PINs are entered on the D-pad, so there aren't many of them. This keeps a
+//! file on a lost SD card from being read at a glance, not from someone
+//! willing to try every PIN.
+//!
+//! Apps ask for the PIN on start with an [`UnlockPrompt`], keep the [`Key`]
+//! it gives back and [`write`] with it when saving.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use sha2::Sha256;
+
+use crate::actions::ActionMap;
+use crate::app::{Button, Frame, Input};
+use crate::lock::{pin_text, PinEvent, PinPad};
+
+/// At the start of every encrypted file, then the version
+const MAGIC: &[u8] = b"rg35xx-encrypted";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+/// PBKDF2 rounds, a fraction of a second on the device
+const ROUNDS: u32 = 50_000;
+
+/// What a file is encrypted with: a PIN stretched with the file's salt
+#[derive(Clone)]
+pub struct Key {
+    key: [u8; 32],
+    salt: [u8; SALT_LEN],
+}
+
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Key(..)")
+    }
+}
+
+impl Key {
+    /// A key for a new file, with fresh salt
+    pub fn new(pin: &[Button]) -> Self {
+        Self::derive(pin, rand::random())
+    }
+
+    fn derive(pin: &[Button], salt: [u8; SALT_LEN]) -> Self {
+        let mut key = [0; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(pin_text(pin).as_bytes(), &salt, ROUNDS, &mut key);
+        Self { key, salt }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// `bytes` encrypted with `key`, header and all
+pub fn encrypt(key: &Key, bytes: &[u8]) -> Vec<u8> {
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let cipher = ChaCha20Poly1305::new(&key.key.into());
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), bytes)
+        .expect("ChaCha20-Poly1305 takes messages of any size that fits in memory");
+    [MAGIC, &[VERSION], &key.salt, &nonce, &sealed].concat()
+}
+
+/// The bytes in `data` and the key they were encrypted with, if `pin` is the
+/// right one. A wrong PIN is [`io::ErrorKind::PermissionDenied`].
+pub fn decrypt(pin: &[Button], data: &[u8]) -> io::Result<(Key, Vec<u8>)> {
+    if !is_encrypted(data) {
+        return Err(invalid("Not an encrypted file"));
+    }
+    if data.len() < HEADER_LEN || data[MAGIC.len()] != VERSION {
+        return Err(invalid("Unknown encrypted file version"));
+    }
+    let (salt, rest) = data[MAGIC.len() + 1..].split_at(SALT_LEN);
+    let (nonce, sealed) = rest.split_at(NONCE_LEN);
+    let key = Key::derive(pin, salt.try_into().unwrap());
+    let cipher = ChaCha20Poly1305::new(&key.key.into());
+    let bytes = cipher
+        .decrypt(Nonce::from_slice(nonce), sealed)
+        .map_err(|_| io::Error::new(io::ErrorKind::PermissionDenied, "Wrong PIN"))?;
+    Ok((key, bytes))
+}
+
+/// Whether `data` starts like an encrypted file
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypts `bytes` into `path`, replacing it only once it's all written
+pub fn write(path: &Path, key: &Key, bytes: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, encrypt(key, bytes))?;
+    std::fs::rename(partial, path)
+}
+
+/// The decrypted contents of `path` and its key
+pub fn read(path: &Path, pin: &[Button]) -> io::Result<(Key, Vec<u8>)> {
+    decrypt(pin, &std::fs::read(path)?)
+}
+
+/// What came of an [`UnlockPrompt`]
+#[derive(Debug)]
+pub enum UnlockEvent {
+    /// The key, and the file's contents if there was one yet
+    Unlocked(Key, Option<Vec<u8>>),
+    Cancelled,
+}
+
+enum Step {
+    Unlock,
+    /// There's no file yet, so a PIN is chosen for it
+    New,
+    Repeat(Vec<Button>),
+}
+
+/// Asks for the PIN to open an encrypted file, or for a new one twice over
+/// if the file doesn't exist yet
+///
+/// ```ignore
+/// match self.prompt.update(input, frame) {
+///     Some(UnlockEvent::Unlocked(key, bytes)) => ...,
+///     Some(UnlockEvent::Cancelled) => ...,
+///     None => return,
+/// }
+/// ```
+pub struct UnlockPrompt {
+    path: PathBuf,
+    step: Step,
+    pad: PinPad,
+    actions: ActionMap,
+}
+
+impl UnlockPrompt {
+    /// `what` names what's locked, e.g. `the vault`
+    pub fn new(path: &Path, what: &str) -> Self {
+        let step = if path.exists() {
+            Step::Unlock
+        } else {
+            Step::New
+        };
+        let title = match step {
+            Step::Unlock => format!("PIN for {}", what),
+            _ => format!("New PIN for {}", what),
+        };
+        Self {
+            path: path.to_path_buf(),
+            step,
+            pad: PinPad::new(&title),
+            actions: ActionMap::global(),
+        }
+    }
+
+    /// Draws the prompt, returning what came of it once the PIN's entered or
+    /// it's cancelled
+    pub fn update(&mut self, input: &Input, frame: &mut Frame) -> Option<UnlockEvent> {
+        let pin = match self.pad.update(input, &self.actions, frame)? {
+            PinEvent::Entered(pin) => pin,
+            PinEvent::Cancelled => return Some(UnlockEvent::Cancelled),
+        };
+        match &self.step {
+            Step::Unlock => match read(&self.path, &pin) {
+                Ok((key, bytes)) => return Some(UnlockEvent::Unlocked(key, Some(bytes))),
+                Err(e) => self.pad.reject(&e.to_string()),
+            },
+            Step::New => {
+                self.pad = PinPad::new("The same PIN again");
+                self.step = Step::Repeat(pin);
+            }
+            Step::Repeat(first) if *first == pin => {
+                return Some(UnlockEvent::Unlocked(Key::new(&pin), None));
+            }
+            Step::Repeat(_) => {
+                self.pad = PinPad::new("New PIN");
+                self.pad.reject("The PINs didn't match");
+                self.step = Step::New;
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encrypt() {
+        let pin = [
+            Button::PovUp,
+            Button::PovLeft,
+            Button::PovLeft,
+            Button::PovDown,
+        ];
+        let key = Key::new(&pin);
+        let data = encrypt(&key, b"secret");
+        assert!(is_encrypted(&data));
+        assert!(!data.windows(6).any(|w| w == b"secret"));
+        let (reopened, bytes) = decrypt(&pin, &data).unwrap();
+        assert_eq!(bytes, b"secret");
+        assert_eq!(reopened.key, key.key);
+
+        let wrong = decrypt(&pin[..3], &data).unwrap_err();
+        assert_eq!(wrong.kind(), io::ErrorKind::PermissionDenied);
+        let mut damaged = data.clone();
+        *damaged.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&pin, &damaged).is_err());
+        assert!(decrypt(&pin, b"{}").is_err());
+    }
+}
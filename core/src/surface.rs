@@ -0,0 +1,395 @@
+use std::path::Path;
+
+use image::codecs::gif::GifDecoder;
+use image::{
+    imageops::FilterType, AnimationDecoder, DynamicImage, ImageDecoder, ImageFormat, ImageReader,
+    RgbaImage,
+};
+use palette::{LinSrgb, Mix};
+
+use crate::app::Frame;
+use crate::time::Duration;
+
+/// GIF frames with less delay than this are shown for [`DEFAULT_FRAME_DELAY`]
+/// instead, as browsers do, since plenty of GIFs are saved with none
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+/// How many bytes of frames an [`Animation`] keeps before leaving the rest
+/// off, since the device only has 256 MB
+const MAX_ANIMATION_BYTES: usize = 64 << 20;
+
+/// The largest size with the shape of `width` x `height` that fits in
+/// `max_width` x `max_height`, at least a pixel each way
+fn fit(width: u32, height: u32, max_width: usize, max_height: usize) -> (u32, u32) {
+    let scale =
+        (max_width as f32 / width.max(1) as f32).min(max_height as f32 / height.max(1) as f32);
+    (
+        ((width as f32 * scale).round() as u32).max(1),
+        ((height as f32 * scale).round() as u32).max(1),
+    )
+}
+
+/// An offscreen image that can be drawn into with the usual [`Frame`] calls
+/// and then copied onto the screen, possibly scaled or blended
+pub struct Surface {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+impl Surface {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; width * height * 4],
+        }
+    }
+
+    /// A copy of `image`, with any transparency over black
+    pub fn from_image(image: &RgbaImage) -> Self {
+        let mut surface = Self::new(image.width() as usize, image.height() as usize);
+        let mut frame = surface.frame();
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let [r, g, b, a] = pixel.0;
+            let over_black = |c: u8| (c as u16 * a as u16 / 255) as u8;
+            frame.put_pixel(
+                x as usize,
+                y as usize,
+                LinSrgb::new(over_black(r), over_black(g), over_black(b)),
+            );
+        }
+        surface
+    }
+
+    /// Loads an image file, e.g. a photo off a camera, turned upright if it
+    /// has an EXIF orientation and scaled to fit `max_width` x `max_height`
+    /// keeping its shape
+    pub fn load(path: &Path, max_width: usize, max_height: usize) -> Result<Self, String> {
+        let mut decoder = ImageReader::open(path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(|e| e.to_string())?
+            .into_decoder()
+            .map_err(|e| e.to_string())?;
+        let orientation = decoder.orientation().map_err(|e| e.to_string())?;
+        let mut image = DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+        image.apply_orientation(orientation);
+        Ok(Self::fitted(&image, max_width, max_height))
+    }
+
+    /// `image` scaled to fit `max_width` x `max_height`, keeping its shape
+    fn fitted(image: &DynamicImage, max_width: usize, max_height: usize) -> Self {
+        let (width, height) = fit(image.width(), image.height(), max_width, max_height);
+        let image = image
+            .resize_exact(width, height, FilterType::Triangle)
+            .into_rgba8();
+        Self::from_image(&image)
+    }
+
+    /// Cuts a sprite sheet into `width` x `height` tiles, a row at a time
+    /// from the top left. Part tiles at the right and bottom are left out.
+    pub fn sheet(image: &RgbaImage, width: u32, height: u32) -> Vec<Self> {
+        if width == 0 || height == 0 {
+            return Vec::new();
+        }
+        let (columns, rows) = (image.width() / width, image.height() / height);
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let tile =
+                    image::imageops::crop_imm(image, column * width, row * height, width, height);
+                Self::from_image(&tile.to_image())
+            })
+            .collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// A frame that draws into this surface
+    pub fn frame(&mut self) -> Frame<'_> {
+        Frame {
+            width: self.width,
+            height: self.height,
+            bytespp: 4,
+            stride: self.width * 4,
+            data: &mut self.data,
+        }
+    }
+
+    /// Copies `frame` into the surface, e.g. to capture what's on screen.
+    /// Both must be the same size.
+    pub fn copy_from(&mut self, frame: &Frame) {
+        assert_eq!((self.width, self.height), (frame.width(), frame.height()));
+        if frame.bytespp() == 4 {
+            let row = self.width * 4;
+            for y in 0..frame.height() {
+                self.data[y * row..(y + 1) * row].copy_from_slice(frame.row(y));
+            }
+            return;
+        }
+        let mut dst = self.frame();
+        for y in 0..frame.height() {
+            for x in 0..frame.width() {
+                dst.put_pixel(x, y, frame.get_pixel(x, y));
+            }
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> LinSrgb<u8> {
+        let offset = (y * self.width + x) * 4;
+        LinSrgb::new(
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+        )
+    }
+
+    /// Draws the surface with its top-left at `x`, `y`, which may be partly
+    /// off-screen
+    pub fn draw(&self, frame: &mut Frame, x: i32, y: i32) {
+        self.draw_scaled_alpha(frame, x, y, self.width, self.height, 1.0);
+    }
+
+    /// Draws the surface stretched to `width` x `height` using nearest-neighbor sampling
+    pub fn draw_scaled(&self, frame: &mut Frame, x: i32, y: i32, width: usize, height: usize) {
+        self.draw_scaled_alpha(frame, x, y, width, height, 1.0);
+    }
+
+    /// Blends the surface over the frame, where an `alpha` of 1.0 is opaque
+    pub fn draw_alpha(&self, frame: &mut Frame, x: i32, y: i32, alpha: f32) {
+        self.draw_scaled_alpha(frame, x, y, self.width, self.height, alpha);
+    }
+
+    pub fn draw_scaled_alpha(
+        &self,
+        frame: &mut Frame,
+        x: i32,
+        y: i32,
+        width: usize,
+        height: usize,
+        alpha: f32,
+    ) {
+        if alpha <= 0.0 || width == 0 || height == 0 || self.width == 0 || self.height == 0 {
+            return;
+        }
+        let alpha = alpha.min(1.0);
+
+        // Only the part of the destination that's on screen
+        let x0 = x.max(0) as usize;
+        let y0 = y.max(0) as usize;
+        let x1 = ((x + width as i32).max(0) as usize).min(frame.width());
+        let y1 = ((y + height as i32).max(0) as usize).min(frame.height());
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        // Fast path: unscaled, opaque, same pixel format
+        if alpha >= 1.0 && width == self.width && height == self.height && frame.bytespp() == 4 {
+            let sx = (x0 as i32 - x) as usize;
+            let row_bytes = (x1 - x0) * 4;
+            for dy in y0..y1 {
+                let sy = (dy as i32 - y) as usize;
+                let src = (sy * self.width + sx) * 4;
+                let dst = dy * frame.stride() + x0 * 4;
+                frame.data[dst..dst + row_bytes].copy_from_slice(&self.data[src..src + row_bytes]);
+            }
+            return;
+        }
+
+        for dy in y0..y1 {
+            let sy = (dy as i32 - y) as usize * self.height / height;
+            for dx in x0..x1 {
+                let sx = (dx as i32 - x) as usize * self.width / width;
+                let color = self.get_pixel(sx, sy);
+                let color = if alpha < 1.0 {
+                    let below = frame.get_pixel(dx, dy).into_format::<f32>();
+                    below.mix(color.into_format(), alpha).into_format()
+                } else {
+                    color
+                };
+                frame.put_pixel(dx, dy, color);
+            }
+        }
+    }
+}
+
+/// Frames that play one after another on a loop, each for its own time, e.g.
+/// an animated GIF. A still image is an animation of one frame, so either can
+/// be shown the same way.
+///
+/// ```ignore
+/// let animation = Animation::load(path, frame.width(), frame.height())?;
+/// animation.frame_at(started.elapsed()).draw(frame, 0, 0);
+/// ```
+pub struct Animation {
+    frames: Vec<(Surface, Duration)>,
+    length: Duration,
+}
+
+impl Animation {
+    pub fn still(surface: Surface) -> Self {
+        Self::from_frames(vec![(surface, Duration::ZERO)])
+    }
+
+    fn from_frames(frames: Vec<(Surface, Duration)>) -> Self {
+        let length = frames.iter().map(|(_, delay)| *delay).sum();
+        Self { frames, length }
+    }
+
+    /// Loads a GIF's frames, or any other image as a still, scaled to fit
+    /// `max_width` x `max_height` like [`Surface::load`]. Very long GIFs
+    /// are cut short rather than filling memory.
+    pub fn load(path: &Path, max_width: usize, max_height: usize) -> Result<Self, String> {
+        let reader = ImageReader::open(path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(|e| e.to_string())?;
+        if reader.format() != Some(ImageFormat::Gif) {
+            return Surface::load(path, max_width, max_height).map(Self::still);
+        }
+        let decoder = GifDecoder::new(reader.into_inner()).map_err(|e| e.to_string())?;
+        let mut frames = Vec::new();
+        let mut bytes = 0;
+        for frame in decoder.into_frames() {
+            let frame = frame.map_err(|e| e.to_string())?;
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay = Duration::from_micros(numer as u64 * 1000 / denom.max(1) as u64);
+            let delay = if delay < MIN_FRAME_DELAY {
+                DEFAULT_FRAME_DELAY
+            } else {
+                delay
+            };
+            let image = DynamicImage::ImageRgba8(frame.into_buffer());
+            let surface = Surface::fitted(&image, max_width, max_height);
+            bytes += surface.data.len();
+            frames.push((surface, delay));
+            if bytes > MAX_ANIMATION_BYTES {
+                break;
+            }
+        }
+        if frames.is_empty() {
+            return Err("The GIF has no frames".to_string());
+        }
+        Ok(Self::from_frames(frames))
+    }
+
+    /// Whether there's more than one frame
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    pub fn width(&self) -> usize {
+        self.frames[0].0.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.frames[0].0.height()
+    }
+
+    fn index_at(&self, elapsed: Duration) -> (usize, Duration) {
+        if !self.is_animated() {
+            return (0, Duration::MAX);
+        }
+        let mut into = Duration::from_nanos((elapsed.as_nanos() % self.length.as_nanos()) as u64);
+        for (i, (_, delay)) in self.frames.iter().enumerate() {
+            if into < *delay {
+                return (i, *delay - into);
+            }
+            into -= *delay;
+        }
+        (0, self.frames[0].1)
+    }
+
+    /// The frame showing `elapsed` after the animation started
+    pub fn frame_at(&self, elapsed: Duration) -> &Surface {
+        &self.frames[self.index_at(elapsed).0].0
+    }
+
+    /// How long after `elapsed` the next frame comes, or `None` for a still
+    pub fn next_frame_in(&self, elapsed: Duration) -> Option<Duration> {
+        self.is_animated().then(|| self.index_at(elapsed).1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_draw_clipped() {
+        let mut surface = Surface::new(2, 2);
+        surface
+            .frame()
+            .fill_rect(0, 0, 1, 2, LinSrgb::new(255, 0, 0));
+        surface
+            .frame()
+            .fill_rect(1, 0, 1, 2, LinSrgb::new(0, 255, 0));
+
+        let mut data = vec![0; 3 * 2 * 2];
+        let mut frame = Frame {
+            width: 3,
+            height: 2,
+            bytespp: 2,
+            stride: 3 * 2,
+            data: &mut data,
+        };
+        // Only the right (green) column lands on the frame
+        surface.draw(&mut frame, -1, 0);
+        assert_eq!(frame.get_pixel(0, 0), LinSrgb::new(0, 255, 0));
+        assert_eq!(frame.get_pixel(0, 1), LinSrgb::new(0, 255, 0));
+        assert_eq!(frame.get_pixel(1, 0), LinSrgb::new(0, 0, 0));
+    }
+
+    #[test]
+    fn test_sheet() {
+        let image = RgbaImage::from_fn(5, 4, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+        let tiles = Surface::sheet(&image, 2, 2);
+        assert_eq!(tiles.len(), 4);
+        assert_eq!((tiles[1].width(), tiles[1].height()), (2, 2));
+        assert_eq!(tiles[1].get_pixel(1, 0), LinSrgb::new(3, 0, 0));
+        assert_eq!(tiles[2].get_pixel(0, 1), LinSrgb::new(0, 3, 0));
+    }
+
+    #[test]
+    fn test_fit() {
+        // A portrait photo on a landscape screen
+        assert_eq!(fit(3000, 4000, 640, 480), (360, 480));
+        assert_eq!(fit(100, 50, 640, 480), (640, 320));
+        assert_eq!(fit(10_000, 1, 640, 480), (640, 1));
+    }
+
+    #[test]
+    fn test_animation() {
+        let frame = |shade| {
+            let mut surface = Surface::new(1, 1);
+            surface.frame().put_pixel(0, 0, LinSrgb::new(shade, 0, 0));
+            surface
+        };
+        let animation = Animation::from_frames(vec![
+            (frame(1), Duration::from_millis(100)),
+            (frame(2), Duration::from_millis(300)),
+        ]);
+        let shade = |ms| {
+            animation
+                .frame_at(Duration::from_millis(ms))
+                .get_pixel(0, 0)
+                .red
+        };
+        assert_eq!([shade(0), shade(99), shade(100), shade(399)], [1, 1, 2, 2]);
+        // Round again
+        assert_eq!(shade(450), 1);
+        assert_eq!(
+            animation.next_frame_in(Duration::from_millis(250)),
+            Some(Duration::from_millis(150))
+        );
+        assert_eq!(
+            Animation::still(frame(3)).next_frame_in(Duration::ZERO),
+            None
+        );
+    }
+}
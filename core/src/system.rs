@@ -0,0 +1,352 @@
+//! Access to the device's hardware settings and sensors through sysfs and
+//! procfs. Everything here fails gracefully on machines without the relevant
+//! files, such as the sim.
+
+use std::{io, path::PathBuf};
+
+use crate::{
+    app::PowerHint,
+    events::{publish, BatteryLow, NetworkChanged, Overheating},
+    time::{Duration, Instant},
+};
+
+const CPU_DIR: &str = "/sys/devices/system/cpu";
+const THERMAL_DIR: &str = "/sys/class/thermal";
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// The `cpufreq` directory of every CPU core
+fn cpufreq_dirs() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(CPU_DIR) else {
+        return vec![];
+    };
+    let mut dirs = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.strip_prefix("cpu")
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .map(|entry| entry.path().join("cpufreq"))
+        .filter(|dir| dir.is_dir())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs
+}
+
+fn read_trimmed(path: PathBuf) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// The scaling governor of the first CPU, e.g. `"ondemand"`
+pub fn governor() -> Option<String> {
+    read_trimmed(cpufreq_dirs().first()?.join("scaling_governor"))
+}
+
+/// Governors the kernel offers
+pub fn available_governors() -> Vec<String> {
+    cpufreq_dirs()
+        .first()
+        .and_then(|dir| read_trimmed(dir.join("scaling_available_governors")))
+        .map(|s| s.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Switches every CPU to `governor`. Needs write access to sysfs, which the
+/// launcher has when it runs as root on the device.
+pub fn set_governor(governor: &str) -> io::Result<()> {
+    let dirs = cpufreq_dirs();
+    if dirs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no cpufreq support",
+        ));
+    }
+    for dir in dirs {
+        std::fs::write(dir.join("scaling_governor"), governor)?;
+    }
+    Ok(())
+}
+
+/// The current frequency of the first CPU, in kHz
+pub fn cpu_frequency() -> Option<u32> {
+    read_trimmed(cpufreq_dirs().first()?.join("scaling_cur_freq"))?
+        .parse()
+        .ok()
+}
+
+/// Applies apps' [`PowerHint`]s as they change, putting the original governor
+/// back for [`PowerHint::Default`] and when dropped
+pub struct GovernorControl {
+    original: Option<String>,
+    current: PowerHint,
+}
+
+impl Default for GovernorControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GovernorControl {
+    pub fn new() -> Self {
+        Self {
+            original: governor(),
+            current: PowerHint::Default,
+        }
+    }
+
+    pub fn apply(&mut self, hint: PowerHint) {
+        if hint == self.current {
+            return;
+        }
+        self.current = hint;
+        let governor = match hint {
+            PowerHint::Default => match self.original.as_deref() {
+                Some(original) => original,
+                None => return,
+            },
+            PowerHint::Performance => "performance",
+            PowerHint::Powersave => "powersave",
+        };
+        if let Err(e) = set_governor(governor) {
+            println!("Couldn't set the CPU governor to {}: {}", governor, e);
+        }
+    }
+}
+
+impl Drop for GovernorControl {
+    fn drop(&mut self) {
+        self.apply(PowerHint::Default);
+    }
+}
+
+/// Every `thermal_zone*` directory
+fn thermal_zones() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(THERMAL_DIR) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+/// Sysfs reports temperatures in thousandths of a degree
+fn read_millidegrees(path: PathBuf) -> Option<f32> {
+    Some(read_trimmed(path)?.parse::<i32>().ok()? as f32 / 1000.0)
+}
+
+/// The temperature of the hottest thermal zone, in °C
+pub fn temperature() -> Option<f32> {
+    thermal_zones()
+        .into_iter()
+        .filter_map(|zone| read_millidegrees(zone.join("temp")))
+        .reduce(f32::max)
+}
+
+/// The lowest temperature at which the kernel starts throttling or shutting
+/// down, in °C
+pub fn thermal_limit() -> Option<f32> {
+    thermal_zones()
+        .into_iter()
+        .flat_map(|zone| (0..8).map(move |i| zone.join(format!("trip_point_{}_temp", i))))
+        .filter_map(read_millidegrees)
+        .filter(|&t| t > 0.0)
+        .reduce(f32::min)
+}
+
+/// Samples the fraction of time all CPUs spent busy, from `/proc/stat`
+#[derive(Default)]
+pub struct CpuUsage {
+    /// Busy and total jiffies at the last sample
+    last: Option<(u64, u64)>,
+}
+
+impl CpuUsage {
+    /// Usage between 0.0 and 1.0 since the previous call, or `None` the first
+    /// time
+    pub fn sample(&mut self) -> Option<f32> {
+        let stat = std::fs::read_to_string("/proc/stat").ok()?;
+        let times = stat
+            .lines()
+            .next()?
+            .strip_prefix("cpu ")?
+            .split_whitespace()
+            .filter_map(|n| n.parse::<u64>().ok())
+            .collect::<Vec<_>>();
+        // user nice system idle iowait ...
+        let total = times.iter().sum::<u64>();
+        let idle = times.get(3)? + times.get(4).unwrap_or(&0);
+        let busy = total - idle;
+
+        let (last_busy, last_total) = self.last.replace((busy, total))?;
+        let elapsed = total.saturating_sub(last_total);
+        if elapsed == 0 {
+            return Some(0.0);
+        }
+        Some(busy.saturating_sub(last_busy) as f32 / elapsed as f32)
+    }
+}
+
+/// Watches for the SoC getting close to its thermal limit
+pub struct ThermalMonitor {
+    warn_at: f32,
+    warned: bool,
+    last_check: Option<Instant>,
+}
+
+impl Default for ThermalMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ThermalMonitor {
+    /// Degrees below the limit at which to start warning
+    const MARGIN: f32 = 10.0;
+    const INTERVAL: Duration = Duration::from_secs(2);
+
+    pub fn new() -> Self {
+        Self {
+            warn_at: thermal_limit().unwrap_or(85.0) - Self::MARGIN,
+            warned: false,
+            last_check: None,
+        }
+    }
+
+    /// Reads the temperature at most every couple of seconds. Returns it when
+    /// it has just crossed into the warning range.
+    pub fn check(&mut self) -> Option<f32> {
+        if self
+            .last_check
+            .is_some_and(|last| last.elapsed() < Self::INTERVAL)
+        {
+            return None;
+        }
+        self.last_check = Some(Instant::now());
+
+        let temperature = temperature()?;
+        if temperature >= self.warn_at {
+            if !self.warned {
+                self.warned = true;
+                return Some(temperature);
+            }
+        } else if temperature < self.warn_at - 5.0 {
+            // Cooled down enough to warn again next time
+            self.warned = false;
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Battery {
+    pub percent: u8,
+    /// Plugged in, whether charging or already full
+    pub charging: bool,
+}
+
+/// The charge of the first power supply that's a battery
+pub fn battery() -> Option<Battery> {
+    let entries = std::fs::read_dir(POWER_SUPPLY_DIR).ok()?;
+    let dir = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|dir| read_trimmed(dir.join("type")).as_deref() == Some("Battery"))?;
+    let percent = read_trimmed(dir.join("capacity"))?.parse::<u8>().ok()?;
+    let status = read_trimmed(dir.join("status")).unwrap_or_default();
+    Some(Battery {
+        percent: percent.min(100),
+        charging: matches!(status.as_str(), "Charging" | "Full"),
+    })
+}
+
+/// Keeps an eye on the battery, the temperature and the network, publishing
+/// [`BatteryLow`], [`Overheating`] and [`NetworkChanged`] as they change.
+/// The menu polls one every update.
+#[derive(Default)]
+pub struct Watcher {
+    thermal: ThermalMonitor,
+    /// The lowest of [`Self::BATTERY_LEVELS`] warned about since the battery
+    /// was last charging
+    battery_warned: Option<u8>,
+    online: Option<bool>,
+    last_check: Option<Instant>,
+}
+
+impl Watcher {
+    /// Percentages to warn at on the way down
+    const BATTERY_LEVELS: [u8; 2] = [15, 5];
+    const INTERVAL: Duration = Duration::from_secs(2);
+
+    pub fn poll(&mut self) {
+        if let Some(celsius) = self.thermal.check() {
+            publish(Overheating { celsius });
+        }
+        if self
+            .last_check
+            .is_some_and(|last| last.elapsed() < Self::INTERVAL)
+        {
+            return;
+        }
+        self.last_check = Some(Instant::now());
+
+        if let Some(percent) = battery().and_then(|battery| self.battery_warning(battery)) {
+            publish(BatteryLow { percent });
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let online = crate::net::is_online();
+            // Nothing to say about how things were at startup
+            if self.online.is_some_and(|was| was != online) {
+                publish(NetworkChanged { online });
+            }
+            self.online = Some(online);
+        }
+    }
+
+    /// The charge to warn about, if it has just fallen past a level
+    fn battery_warning(&mut self, battery: Battery) -> Option<u8> {
+        if battery.charging {
+            self.battery_warned = None;
+            return None;
+        }
+        let level = Self::BATTERY_LEVELS
+            .into_iter()
+            .filter(|&level| battery.percent <= level)
+            .min()?;
+        if self.battery_warned.is_some_and(|warned| warned <= level) {
+            return None;
+        }
+        self.battery_warned = Some(level);
+        Some(battery.percent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_battery_warning() {
+        let mut watcher = Watcher::default();
+        let mut at = |percent, charging| watcher.battery_warning(Battery { percent, charging });
+        assert_eq!(at(50, false), None);
+        assert_eq!(at(15, false), Some(15));
+        assert_eq!(at(12, false), None);
+        assert_eq!(at(4, false), Some(4));
+        assert_eq!(at(3, false), None);
+        // Charging for a bit starts the warnings over
+        assert_eq!(at(6, true), None);
+        assert_eq!(at(5, false), Some(5));
+    }
+}
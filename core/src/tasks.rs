@@ -0,0 +1,220 @@
+//! Slow work, like downloads, decoding sounds and generating puzzles, run off
+//! the thread that calls `update` so the app keeps drawing meanwhile. An app
+//! spawns work on the pool in its [`AppContext`](crate::app::AppContext), or
+//! on a [`Tasks`] of its own, and polls the [`Task`]s it gets back each
+//! update.
+//!
+//! Dropping a `Tasks`, or the menu shutting the app's down when it exits,
+//! throws away work that hadn't started. Work that had carries on to the end
+//! with nobody waiting for it, since a download can't be stopped halfway, but
+//! long loops can check [`cancelled`] and give up early.
+//!
+//! The browser has no threads, so there work runs straight away in
+//! [`Tasks::spawn`].
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<VecDeque<Job>>,
+    ready: Condvar,
+    cancelled: Arc<AtomicBool>,
+}
+
+thread_local! {
+    /// The flag of the pool the current thread works for
+    static CANCELLED: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Whether the pool running the current task has been shut down, for long
+/// tasks to check now and then. Always false outside a task.
+pub fn cancelled() -> bool {
+    CANCELLED.with(|flag| {
+        flag.borrow()
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    })
+}
+
+/// A pool of worker threads, started as work comes in
+pub struct Tasks {
+    shared: Arc<Shared>,
+    threads: usize,
+    started: usize,
+}
+
+impl Tasks {
+    /// A pool running up to `threads` tasks at once
+    pub fn new(threads: usize) -> Self {
+        Self {
+            shared: Arc::default(),
+            threads: threads.max(1),
+            started: 0,
+        }
+    }
+
+    /// Runs `work` on the pool, with its result to be had from the [`Task`]
+    pub fn spawn<T: Send + 'static>(
+        &mut self,
+        work: impl FnOnce() -> T + Send + 'static,
+    ) -> Task<T> {
+        let (tx, rx) = channel();
+        let job = move || {
+            let _ = tx.send(work());
+        };
+        if cfg!(target_arch = "wasm32") {
+            job();
+        } else {
+            self.shared.queue.lock().unwrap().push_back(Box::new(job));
+            self.shared.ready.notify_one();
+            if self.started < self.threads {
+                self.start_worker();
+            }
+        }
+        Task { rx, done: false }
+    }
+
+    fn start_worker(&mut self) {
+        let shared = self.shared.clone();
+        let spawned = std::thread::Builder::new()
+            .name("task".to_string())
+            .spawn(move || {
+                CANCELLED.with(|flag| *flag.borrow_mut() = Some(shared.cancelled.clone()));
+                loop {
+                    let mut queue = shared.queue.lock().unwrap();
+                    let job = loop {
+                        if shared.cancelled.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        match queue.pop_front() {
+                            Some(job) => break job,
+                            None => queue = shared.ready.wait(queue).unwrap(),
+                        }
+                    };
+                    drop(queue);
+                    // A panic loses that task, whose result is then never
+                    // sent, but not the thread
+                    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+                }
+            });
+        match spawned {
+            Ok(_) => self.started += 1,
+            Err(e) => println!("Couldn't start a task thread: {}", e),
+        }
+    }
+
+    /// Throws away the work that hasn't started and lets the threads finish.
+    /// Nothing more can be spawned afterwards.
+    pub fn shutdown(&mut self) {
+        self.shared.cancelled.store(true, Ordering::Relaxed);
+        self.shared.queue.lock().unwrap().clear();
+        self.shared.ready.notify_all();
+    }
+}
+
+impl Default for Tasks {
+    /// Two threads, enough for a download or two alongside some decoding
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Work spawned on [`Tasks`]
+pub struct Task<T> {
+    rx: Receiver<T>,
+    done: bool,
+}
+
+impl<T> Task<T> {
+    /// The result, the first time it's polled after the work finishes
+    pub fn poll(&mut self) -> Option<T> {
+        if self.done {
+            return None;
+        }
+        match self.rx.try_recv() {
+            Ok(result) => {
+                self.done = true;
+                Some(result)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.done = true;
+                None
+            }
+        }
+    }
+
+    /// Whether the work hadn't finished when last polled. It's false once
+    /// polled after a task panicked or was thrown away too, which never give
+    /// a result.
+    pub fn is_pending(&self) -> bool {
+        !self.done
+    }
+
+    /// Blocks until the result is ready, or `None` if it never will be
+    pub fn wait(mut self) -> Option<T> {
+        if self.done {
+            return None;
+        }
+        self.done = true;
+        self.rx.recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tasks() {
+        let mut tasks = Tasks::new(2);
+        let mut sum = tasks.spawn(|| (1..=10).sum::<u32>());
+        let failed = tasks.spawn(|| -> u32 { panic!("a task failed") });
+        assert!(!cancelled());
+        assert_eq!(failed.wait(), None);
+        let mut result = None;
+        while result.is_none() {
+            assert!(sum.is_pending());
+            result = sum.poll();
+        }
+        assert_eq!(result, Some(55));
+        assert!(!sum.is_pending());
+        assert_eq!(sum.poll(), None);
+
+        // Once shut down, a task that's running finishes and sees it was
+        // cancelled, and one still queued behind it is dropped
+        let (started_tx, started) = channel();
+        let (go_tx, go) = channel::<()>();
+        let running = tasks.spawn(move || {
+            started_tx.send(()).unwrap();
+            go.recv().unwrap();
+            cancelled()
+        });
+        let (hold_tx, hold) = channel::<()>();
+        let busy = tasks.spawn(move || {
+            let _ = hold.recv();
+        });
+        let queued = tasks.spawn(|| 1);
+        started.recv().unwrap();
+        tasks.shutdown();
+        go_tx.send(()).unwrap();
+        assert_eq!(running.wait(), Some(true));
+        assert_eq!(queued.wait(), None);
+        // Whether or not it had started, the task holding the other thread
+        // up can end now
+        drop(hold_tx);
+        drop(busy);
+    }
+}
@@ -0,0 +1,180 @@
+//! Running apps without a screen, for tests. A [`Harness`] drives an app with
+//! scripted input into an offscreen [`Surface`], and [`assert_golden`] checks
+//! the result against a reference PNG.
+//!
+//! Set `UPDATE_GOLDEN=1` to write the references instead of checking them,
+//! after a change that's meant to look different.
+
+use std::path::Path;
+
+use image::{Rgb, RgbImage};
+
+use crate::app::{App, AppContext, Button, Input};
+use crate::surface::Surface;
+use crate::tasks::Tasks;
+use crate::time::Duration;
+
+/// How much time passes each step, as at 60fps
+const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// An app, its input and the surface it draws to
+pub struct Harness<A> {
+    pub app: A,
+    pub input: Input,
+    surface: Surface,
+    tasks: Tasks,
+}
+
+impl<A: App> Harness<A> {
+    pub fn new(app: A, width: usize, height: usize) -> Self {
+        Self {
+            app,
+            input: Input::default(),
+            surface: Surface::new(width, height),
+            tasks: Tasks::default(),
+        }
+    }
+
+    /// Updates the app once, like a backend does each frame. Animations see
+    /// a steady 60fps however fast the test runs.
+    pub fn step(&mut self) -> &mut Self {
+        let mut frame = self.surface.frame();
+        self.app.update(&mut AppContext::new(
+            &self.input,
+            &mut frame,
+            &mut self.tasks,
+        ));
+        self.input.update();
+        self.input.set_delta(FRAME_TIME);
+        self
+    }
+
+    pub fn run(&mut self, frames: usize) -> &mut Self {
+        for _ in 0..frames {
+            self.step();
+        }
+        self
+    }
+
+    /// Presses and releases `button`, a frame each
+    pub fn press(&mut self, button: Button) -> &mut Self {
+        self.input.event(button, true);
+        self.step();
+        self.input.event(button, false);
+        self.step()
+    }
+
+    pub fn surface(&self) -> &Surface {
+        &self.surface
+    }
+}
+
+fn to_image(surface: &Surface) -> RgbImage {
+    RgbImage::from_fn(surface.width() as u32, surface.height() as u32, |x, y| {
+        let color = surface.get_pixel(x as usize, y as usize);
+        Rgb([color.red, color.green, color.blue])
+    })
+}
+
+/// Pixels differing by more than `tolerance` in any channel, as an image
+/// with those pixels in red over a faded copy of `actual`
+fn diff(actual: &RgbImage, expected: &RgbImage, tolerance: u8) -> (usize, RgbImage) {
+    let mut count = 0;
+    let image = RgbImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let (a, e) = (actual.get_pixel(x, y), expected.get_pixel(x, y));
+        if a.0.iter().zip(e.0).any(|(a, e)| a.abs_diff(e) > tolerance) {
+            count += 1;
+            Rgb([255, 0, 0])
+        } else {
+            Rgb(a.0.map(|channel| channel / 4))
+        }
+    });
+    (count, image)
+}
+
+/// Panics unless `surface` matches the PNG at `reference` to within
+/// `tolerance` per channel. On a mismatch the actual image and a diff are
+/// written next to it as `<name>.actual.png` and `<name>.diff.png`.
+///
+/// Crates keep their references in their own `tests/golden/`, so pass e.g.
+/// `concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/menu.png")`.
+pub fn assert_golden(surface: &Surface, reference: impl AsRef<Path>, tolerance: u8) {
+    let reference = reference.as_ref();
+    let actual = to_image(surface);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(reference.parent().unwrap()).unwrap();
+        actual.save(reference).unwrap();
+        return;
+    }
+
+    let expected = match image::open(reference) {
+        Ok(image) => image.to_rgb8(),
+        Err(e) => panic!(
+            "Couldn't load {}: {}. Run with UPDATE_GOLDEN=1 to create it.",
+            reference.display(),
+            e
+        ),
+    };
+    assert_eq!(
+        actual.dimensions(),
+        expected.dimensions(),
+        "{} is a different size",
+        reference.display()
+    );
+    let (count, diff) = diff(&actual, &expected, tolerance);
+    if count > 0 {
+        let actual_path = reference.with_extension("actual.png");
+        let diff_path = reference.with_extension("diff.png");
+        actual.save(&actual_path).unwrap();
+        diff.save(&diff_path).unwrap();
+        panic!(
+            "{} pixels differ from {}, see {} and {}",
+            count,
+            reference.display(),
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use palette::LinSrgb;
+
+    /// Draws the lock screen's PIN pad
+    struct PinPadApp(crate::lock::PinPad);
+
+    impl App for PinPadApp {
+        fn update(&mut self, ctx: &mut AppContext) {
+            let actions = crate::actions::ActionMap::default();
+            self.0.update(ctx.input, &actions, ctx.frame);
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut surface = Surface::new(4, 4);
+        let expected = to_image(&surface);
+        surface.frame().fill_rect(0, 0, 1, 1, LinSrgb::new(3, 0, 0));
+        surface
+            .frame()
+            .fill_rect(1, 0, 1, 1, LinSrgb::new(100, 0, 0));
+        let (count, _) = diff(&to_image(&surface), &expected, 4);
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_golden_pin_pad() {
+        let mut harness = Harness::new(PinPadApp(crate::lock::PinPad::new("PIN")), 320, 240);
+        harness
+            .press(Button::PovUp)
+            .press(Button::PovLeft)
+            .press(Button::ActionA);
+        assert_golden(
+            harness.surface(),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/pin_pad.png"),
+            8,
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! Paragraphs of text that are laid out once and then drawn as often as
+//! needed: wrapped to a width, aligned, spaced out and, for Hebrew and
+//! Arabic, running right to left.
+//!
+//! Right-to-left lines are reordered for display by reversing them, keeping
+//! runs of Latin letters and numbers the right way round. There's no shaping,
+//! so Arabic letters are drawn in whatever form the font gives them on their
+//! own.
+
+use std::ops::Range;
+
+use palette::LinSrgb;
+use rusttype::Scale;
+
+use crate::{app::Frame, widgets::text_width};
+
+/// Where each line sits across the block. `Start` is the left for
+/// left-to-right lines and the right for right-to-left ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextDirection {
+    /// Each paragraph goes whichever way its first letter does
+    #[default]
+    Auto,
+    Ltr,
+    Rtl,
+}
+
+/// Whether `c` is from a right-to-left script
+fn is_rtl(c: char) -> bool {
+    matches!(c,
+        '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+}
+
+/// Whether `c` is from a left-to-right script or a digit, which keep their
+/// order inside right-to-left lines
+fn is_ltr(c: char) -> bool {
+    c.is_alphanumeric() && !is_rtl(c)
+}
+
+impl TextDirection {
+    /// Whether `paragraph` runs right to left
+    fn is_rtl(&self, paragraph: &str) -> bool {
+        match self {
+            Self::Auto => paragraph
+                .chars()
+                .find(|&c| is_rtl(c) || is_ltr(c))
+                .is_some_and(is_rtl),
+            Self::Ltr => false,
+            Self::Rtl => true,
+        }
+    }
+}
+
+/// A right-to-left `line` in the order it's drawn from left to right
+fn visual_order(line: &str) -> String {
+    let chars = line.chars().collect::<Vec<_>>();
+    // Spaces and punctuation between two left-to-right letters or digits go
+    // with them, and otherwise with the right-to-left text
+    let strong = |c: char| is_ltr(c) || is_rtl(c);
+    let ltr = |i: usize| {
+        let before = chars[..i].iter().rev().find(|&&c| strong(c));
+        let after = chars[i..].iter().find(|&&c| strong(c));
+        matches!((before, after), (Some(&b), Some(&a)) if is_ltr(b) && is_ltr(a))
+    };
+
+    let mut runs: Vec<(bool, String)> = vec![];
+    for (i, &c) in chars.iter().enumerate() {
+        let ltr = is_ltr(c) || (!is_rtl(c) && ltr(i));
+        match runs.last_mut() {
+            Some((run_ltr, run)) if *run_ltr == ltr => run.push(c),
+            _ => runs.push((ltr, c.to_string())),
+        }
+    }
+    runs.iter()
+        .rev()
+        .flat_map(|(ltr, run)| -> Box<dyn Iterator<Item = char>> {
+            if *ltr {
+                Box::new(run.chars())
+            } else {
+                Box::new(run.chars().rev())
+            }
+        })
+        .collect()
+}
+
+/// Splits `paragraph` into lines that `fits`. Words too long for a line of
+/// their own are split wherever they overflow.
+fn wrap_paragraph(paragraph: &str, fits: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in paragraph.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", line, word)
+        };
+        if fits(&candidate) {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        // Break up words that don't fit on a line by themselves
+        for c in word.chars() {
+            line.push(c);
+            if !fits(&line) && line.chars().count() > 1 {
+                line.pop();
+                lines.push(std::mem::replace(&mut line, c.to_string()));
+            }
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+struct Line {
+    /// In the order it's drawn, left to right
+    text: String,
+    width: usize,
+    rtl: bool,
+}
+
+/// Text wrapped into lines once, when it or anything about its layout
+/// changes, rather than every time it's drawn. Fonts can be TTFs or
+/// [bitmap fonts](crate::bitmap_font).
+///
+/// ```ignore
+/// let card = TextBlock::new("fonts/Ubuntu-B.ttf", 36.0, red)
+///     .with_max_width(400)
+///     .with_align(Align::Center)
+///     .with_text("Taboo words");
+/// card.draw(frame, 120, 100);
+/// ```
+pub struct TextBlock {
+    font: String,
+    fontsize: f32,
+    color: LinSrgb<u8>,
+    text: String,
+    max_width: Option<usize>,
+    align: Align,
+    line_spacing: f32,
+    direction: TextDirection,
+    lines: Vec<Line>,
+    /// Distance between baselines
+    line_height: usize,
+    /// From the top of a line to its baseline
+    ascent: usize,
+}
+
+impl TextBlock {
+    /// An empty block that doesn't wrap, with lines 1.25 times the font size
+    /// apart
+    pub fn new(font: &str, fontsize: f32, color: LinSrgb<u8>) -> Self {
+        let mut block = Self {
+            font: font.to_string(),
+            fontsize,
+            color,
+            text: String::new(),
+            max_width: None,
+            align: Align::Start,
+            line_spacing: 1.25,
+            direction: TextDirection::Auto,
+            lines: vec![],
+            line_height: 0,
+            ascent: 0,
+        };
+        block.layout();
+        block
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.set_text(text);
+        self
+    }
+
+    /// Wraps lines to at most `width` pixels
+    pub fn with_max_width(mut self, width: usize) -> Self {
+        self.set_max_width(Some(width));
+        self
+    }
+
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Spaces lines `spacing` times the font's line height apart
+    pub fn with_line_spacing(mut self, spacing: f32) -> Self {
+        self.line_spacing = spacing;
+        self.layout();
+        self
+    }
+
+    pub fn with_direction(mut self, direction: TextDirection) -> Self {
+        self.direction = direction;
+        self.layout();
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text, laying it out again if it's changed. Newlines
+    /// always start a new paragraph.
+    pub fn set_text(&mut self, text: &str) {
+        if text != self.text {
+            self.text = text.to_string();
+            self.layout();
+        }
+    }
+
+    pub fn set_color(&mut self, color: LinSrgb<u8>) {
+        self.color = color;
+    }
+
+    /// Wraps lines to at most `width` pixels, or not at all if it's `None`
+    pub fn set_max_width(&mut self, width: Option<usize>) {
+        if width != self.max_width {
+            self.max_width = width;
+            self.layout();
+        }
+    }
+
+    fn layout(&mut self) {
+        type Measure = Box<dyn Fn(&str) -> usize>;
+        let metrics: Result<(f32, usize, Measure), String> = if self.font.ends_with(".fnt") {
+            crate::assets::bitmap_font(&self.font).map(|font| {
+                let metrics = (font.line_height() as f32, font.base());
+                (
+                    metrics.0,
+                    metrics.1,
+                    Box::new(move |s: &str| font.measure(s).0) as Measure,
+                )
+            })
+        } else {
+            crate::assets::font(&self.font).map(|font| {
+                let scale = Scale::uniform(self.fontsize);
+                let ascent = font.v_metrics(scale).ascent.ceil() as usize;
+                let measure = move |s: &str| text_width(&font, scale, s).ceil() as usize;
+                (self.fontsize, ascent, Box::new(measure) as Measure)
+            })
+        };
+        // Matching the outline `Frame::text` draws when the font is missing
+        let fontsize = self.fontsize;
+        let (line_height, ascent, measure) = metrics.unwrap_or_else(|_| {
+            let measure = move |s: &str| (s.chars().count() as f32 * fontsize * 0.5) as usize;
+            (fontsize, (fontsize * 0.75) as usize, Box::new(measure))
+        });
+        self.ascent = ascent;
+        self.line_height = (line_height * self.line_spacing).ceil() as usize;
+
+        let max_width = self.max_width;
+        let fits = |s: &str| max_width.is_none_or(|max| measure(s) <= max);
+        self.lines.clear();
+        for paragraph in self.text.lines() {
+            let rtl = self.direction.is_rtl(paragraph);
+            for line in wrap_paragraph(paragraph, fits) {
+                self.lines.push(Line {
+                    width: measure(&line),
+                    text: if rtl { visual_order(&line) } else { line },
+                    rtl,
+                });
+            }
+        }
+    }
+
+    /// Each line as it's drawn, left to right
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(|line| line.text.as_str())
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Distance between one line's baseline and the next
+    pub fn line_height(&self) -> usize {
+        self.line_height
+    }
+
+    /// The maximum width if there is one, or else the widest line's
+    pub fn width(&self) -> usize {
+        self.max_width
+            .unwrap_or_else(|| self.lines.iter().map(|line| line.width).max().unwrap_or(0))
+    }
+
+    /// Width and height of the block in pixels
+    pub fn size(&self) -> (usize, usize) {
+        (self.width(), self.lines.len() * self.line_height)
+    }
+
+    /// Draws the whole block with its top left at `x`, `y`
+    pub fn draw(&self, frame: &mut Frame, x: usize, y: usize) {
+        self.draw_lines(frame, x, y, 0..self.lines.len());
+    }
+
+    /// Draws just `lines`, with the first of them at the top, for scrolling
+    /// through text too long to show at once
+    pub fn draw_lines(&self, frame: &mut Frame, x: usize, y: usize, lines: Range<usize>) {
+        let width = self.width();
+        let lines = lines.start.min(self.lines.len())..lines.end.min(self.lines.len());
+        for (i, line) in self.lines[lines].iter().enumerate() {
+            let space = width.saturating_sub(line.width);
+            let offset = match (self.align, line.rtl) {
+                (Align::Center, _) => space / 2,
+                (Align::Start, false) | (Align::End, true) => 0,
+                (Align::Start, true) | (Align::End, false) => space,
+            };
+            frame.text(
+                &self.font,
+                x + offset,
+                y + self.ascent + i * self.line_height,
+                self.fontsize,
+                self.color,
+                &line.text,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(visual_order("שלום abc 12!"), "!abc 12 םולש");
+        assert!(TextDirection::Auto.is_rtl("(שלום) abc"));
+        assert!(!TextDirection::Auto.is_rtl("abc שלום"));
+
+        // Ten pixels a character with the placeholder for a missing font
+        let block = TextBlock::new("fonts/missing.ttf", 20.0, LinSrgb::new(255, 255, 255))
+            .with_max_width(50)
+            .with_line_spacing(1.5)
+            .with_align(Align::Center)
+            .with_text("one two three\n\nלא כן");
+        assert_eq!(
+            block.lines().collect::<Vec<_>>(),
+            ["one", "two", "three", "", "ןכ אל"]
+        );
+        assert_eq!(block.size(), (50, 5 * 30));
+    }
+}
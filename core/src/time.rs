@@ -0,0 +1,8 @@
+//! `std::time` doesn't work in the browser, so apps should take their clock
+//! types from here instead.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(target_arch = "wasm32")]
+pub use web_time::{Duration, Instant, SystemTime};
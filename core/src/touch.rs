@@ -0,0 +1,179 @@
+//! Touchscreens on the variants that have one, read straight from evdev.
+
+use std::{
+    fs::File,
+    io::Read,
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+pub(crate) const EV_SYN: u16 = 0x00;
+pub(crate) const EV_KEY: u16 = 0x01;
+pub(crate) const EV_ABS: u16 = 0x03;
+pub(crate) const SYN_REPORT: u16 = 0x00;
+const BTN_TOUCH: u16 = 0x14a;
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_MT_POSITION_X: u16 = 0x35;
+const ABS_MT_POSITION_Y: u16 = 0x36;
+
+/// `struct input_event`: a `timeval` followed by type, code and value
+pub(crate) const EVENT_SIZE: usize = 2 * std::mem::size_of::<usize>() + 8;
+
+/// A change on the touchscreen, in physical panel pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchEvent {
+    Moved(usize, usize),
+    /// A finger touched the screen or left it
+    Pressed(bool),
+}
+
+/// Whether bit `bit` is set in a sysfs capability bitmap, which is
+/// space-separated hex words with the highest first
+pub(crate) fn has_capability(bitmap: &str, bit: u16) -> bool {
+    let word_bits = usize::BITS as usize;
+    let bit = bit as usize;
+    bitmap
+        .split_whitespace()
+        .rev()
+        .nth(bit / word_bits)
+        .and_then(|word| usize::from_str_radix(word, 16).ok())
+        .is_some_and(|word| word & (1 << (bit % word_bits)) != 0)
+}
+
+/// Event devices whose `key` and `abs` capability bitmaps pass `matches`, in
+/// the order the kernel numbered them
+pub(crate) fn find_devices(matches: impl Fn(&str, &str) -> bool) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/input") else {
+        return vec![];
+    };
+    let mut devices = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("event"))
+        .collect::<Vec<_>>();
+    // event10 after event9
+    devices.sort_by_key(|name| (name.len(), name.clone()));
+    devices
+        .into_iter()
+        .filter(|name| {
+            let capabilities = Path::new("/sys/class/input")
+                .join(name)
+                .join("device/capabilities");
+            let read = |kind| std::fs::read_to_string(capabilities.join(kind));
+            matches!((read("key"), read("abs")), (Ok(keys), Ok(abs)) if matches(&keys, &abs))
+        })
+        .map(|name| Path::new("/dev/input").join(name))
+        .collect()
+}
+
+/// The first event device that reports touches and positions
+pub fn find_device() -> Option<PathBuf> {
+    find_devices(|keys, abs| has_capability(keys, BTN_TOUCH) && has_capability(abs, ABS_X))
+        .into_iter()
+        .next()
+}
+
+/// The current, minimum and maximum values of an absolute axis, via
+/// `EVIOCGABS`
+pub(crate) fn axis_info(file: &File, axis: u16) -> Option<(i32, i32, i32)> {
+    // value, minimum, maximum, fuzz, flat, resolution
+    let mut info = [0i32; 6];
+    let size = std::mem::size_of_val(&info) as u64;
+    let request = (2 << 30) | (size << 16) | ((b'E' as u64) << 8) | (0x40 + axis as u64);
+    // SAFETY: EVIOCGABS fills in exactly one `struct input_absinfo`
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), request as _, info.as_mut_ptr()) };
+    (result >= 0 && info[2] > info[1]).then_some((info[0], info[1], info[2]))
+}
+
+/// The minimum and maximum values of an absolute axis
+fn axis_range(file: &File, axis: u16) -> Option<(i32, i32)> {
+    axis_info(file, axis).map(|(_, min, max)| (min, max))
+}
+
+/// The type, code and value of a `struct input_event`
+pub(crate) fn parse_event(buffer: &[u8]) -> (u16, u16, i32) {
+    let kind = u16::from_ne_bytes([buffer[EVENT_SIZE - 8], buffer[EVENT_SIZE - 7]]);
+    let code = u16::from_ne_bytes([buffer[EVENT_SIZE - 6], buffer[EVENT_SIZE - 5]]);
+    let value = i32::from_ne_bytes(buffer[EVENT_SIZE - 4..EVENT_SIZE].try_into().unwrap());
+    (kind, code, value)
+}
+
+/// Scales `value` from `range` to `0..size`
+fn scale(value: i32, (min, max): (i32, i32), size: usize) -> usize {
+    let value = value.clamp(min, max) - min;
+    (value as i64 * (size as i64 - 1) / (max - min) as i64) as usize
+}
+
+/// Reads touches from `path` until it goes away, sending them scaled to a
+/// panel of `physical` size. A report's position is sent before its press, so
+/// a touch starts where the finger landed.
+pub fn read_device<T: From<TouchEvent>>(path: &Path, physical: (usize, usize), sender: Sender<T>) {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            println!("Couldn't open touchscreen {}: {}", path.display(), e);
+            return;
+        }
+    };
+    let ranges = (
+        axis_range(&file, ABS_X).unwrap_or((0, physical.0 as i32 - 1)),
+        axis_range(&file, ABS_Y).unwrap_or((0, physical.1 as i32 - 1)),
+    );
+    println!("Touchscreen {} with range {:?}", path.display(), ranges);
+
+    let (mut x, mut y, mut pressed) = (0, 0, None);
+    let mut moved = false;
+    let mut buffer = [0; EVENT_SIZE];
+    while file.read_exact(&mut buffer).is_ok() {
+        let (kind, code, value) = parse_event(&buffer);
+        match (kind, code) {
+            (EV_ABS, ABS_X | ABS_MT_POSITION_X) => {
+                x = scale(value, ranges.0, physical.0);
+                moved = true;
+            }
+            (EV_ABS, ABS_Y | ABS_MT_POSITION_Y) => {
+                y = scale(value, ranges.1, physical.1);
+                moved = true;
+            }
+            (EV_KEY, BTN_TOUCH) => pressed = Some(value != 0),
+            (EV_SYN, SYN_REPORT) => {
+                let mut events = vec![];
+                if moved {
+                    events.push(TouchEvent::Moved(x, y));
+                }
+                events.extend(pressed.take().map(TouchEvent::Pressed));
+                moved = false;
+                for event in events {
+                    if sender.send(event.into()).is_err() {
+                        return;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    println!("Touchscreen {} went away", path.display());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        // BTN_TOUCH is bit 330, bit 10 of the sixth 64-bit word from the end
+        let keys = if usize::BITS == 64 {
+            "400 0 0 0 0 0"
+        } else {
+            "400 0 0 0 0 0 0 0 0 0 0"
+        };
+        assert!(has_capability(keys, BTN_TOUCH));
+        assert!(!has_capability("400", BTN_TOUCH));
+        assert!(has_capability("3", ABS_Y));
+        assert!(!has_capability("3", ABS_MT_POSITION_X));
+        assert_eq!(scale(4095, (0, 4095), 640), 639);
+        assert_eq!(scale(-5, (0, 4095), 640), 0);
+    }
+}
@@ -0,0 +1,139 @@
+use palette::LinSrgb;
+
+use crate::{
+    anim::Easing,
+    app::Frame,
+    surface::Surface,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    /// Fades the old screen out to black, then the new one in
+    Fade,
+    /// Blends directly from the old screen to the new one
+    Crossfade,
+    /// Pushes the old screen off to the left as the new one comes in from the right
+    Slide,
+}
+
+struct Active {
+    kind: TransitionKind,
+    start: Instant,
+    from: Surface,
+    /// Scratch copy of the new screen, only used when sliding
+    to: Option<Surface>,
+}
+
+/// Animates between whatever was on screen when [`Transitions::start`] was
+/// called and whatever has been drawn since.
+///
+/// Call `start` before drawing the first frame of the new screen, then
+/// [`Transitions::draw`] after drawing each frame.
+pub struct Transitions {
+    pub kind: TransitionKind,
+    pub duration: Duration,
+    /// How the transition speeds up and slows down
+    pub easing: Easing,
+    active: Option<Active>,
+}
+
+impl Default for Transitions {
+    fn default() -> Self {
+        Self::new(TransitionKind::Fade, Duration::from_millis(300))
+    }
+}
+
+impl Transitions {
+    pub fn new(kind: TransitionKind, duration: Duration) -> Self {
+        Self {
+            kind,
+            duration,
+            easing: Easing::QuadInOut,
+            active: None,
+        }
+    }
+
+    /// Starts a transition of the default kind away from what's currently in `frame`
+    pub fn start(&mut self, frame: &Frame) {
+        self.start_with(self.kind, frame);
+    }
+
+    pub fn start_with(&mut self, kind: TransitionKind, frame: &Frame) {
+        if self.duration.is_zero() {
+            return;
+        }
+        let mut from = Surface::new(frame.width(), frame.height());
+        from.copy_from(frame);
+        self.active = Some(Active {
+            kind,
+            start: Instant::now(),
+            from,
+            to: None,
+        });
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Composites the transition over the newly drawn `frame`
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let Some(active) = self.active.as_mut() else {
+            return;
+        };
+        let t = active.start.elapsed().as_secs_f32() / self.duration.as_secs_f32();
+        if t >= 1.0 {
+            self.active = None;
+            return;
+        }
+        let t = self.easing.apply(t);
+
+        match active.kind {
+            TransitionKind::Fade => {
+                if t < 0.5 {
+                    active.from.draw(frame, 0, 0);
+                    dim(frame, 1.0 - t * 2.0);
+                } else {
+                    dim(frame, t * 2.0 - 1.0);
+                }
+            }
+            TransitionKind::Crossfade => {
+                active.from.draw_alpha(frame, 0, 0, 1.0 - t);
+            }
+            TransitionKind::Slide => {
+                let to = active
+                    .to
+                    .get_or_insert_with(|| Surface::new(frame.width(), frame.height()));
+                to.copy_from(frame);
+                let offset = (t * frame.width() as f32) as i32;
+                active.from.draw(frame, -offset, 0);
+                to.draw(frame, frame.width() as i32 - offset, 0);
+            }
+        }
+    }
+}
+
+/// Scales every pixel towards black, where a `brightness` of 0.0 is fully black
+fn dim(frame: &mut Frame, brightness: f32) {
+    let scale = (brightness.clamp(0.0, 1.0) * 256.0) as u16;
+    if frame.bytespp() == 4 {
+        for y in 0..frame.height() {
+            for byte in frame.row_mut(y) {
+                *byte = ((*byte as u16 * scale) >> 8) as u8;
+            }
+        }
+        return;
+    }
+    for y in 0..frame.height() {
+        for x in 0..frame.width() {
+            let c = frame.get_pixel(x, y);
+            let c = LinSrgb::new(
+                ((c.red as u16 * scale) >> 8) as u8,
+                ((c.green as u16 * scale) >> 8) as u8,
+                ((c.blue as u16 * scale) >> 8) as u8,
+            );
+            frame.put_pixel(x, y, c);
+        }
+    }
+}
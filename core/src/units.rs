@@ -0,0 +1,166 @@
+//! Units for showing distances, speeds and altitudes, each chosen on its own
+//! and kept in [`crate::config`]. Values are kept in meters and meters per
+//! second and only converted to be shown or entered.
+//!
+//! ```text
+//! distance_unit = nm
+//! speed_unit = kt
+//! altitude_unit = ft
+//! ```
+
+use enum_iterator::Sequence;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum DistanceUnit {
+    #[default]
+    NauticalMiles,
+    Kilometers,
+    Miles,
+}
+
+impl DistanceUnit {
+    /// The name used in `launcher.txt` and shown after values
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NauticalMiles => "nm",
+            Self::Kilometers => "km",
+            Self::Miles => "mi",
+        }
+    }
+
+    /// How many meters one is
+    pub fn meters(&self) -> f64 {
+        match self {
+            Self::NauticalMiles => 1852.0,
+            Self::Kilometers => 1000.0,
+            Self::Miles => 1609.344,
+        }
+    }
+
+    pub fn from_meters(&self, meters: f64) -> f64 {
+        meters / self.meters()
+    }
+
+    pub fn to_meters(&self, value: f64) -> f64 {
+        value * self.meters()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum SpeedUnit {
+    #[default]
+    Knots,
+    MilesPerHour,
+    KilometersPerHour,
+}
+
+impl SpeedUnit {
+    /// The name used in `launcher.txt` and shown after values
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Knots => "kt",
+            Self::MilesPerHour => "mph",
+            Self::KilometersPerHour => "km/h",
+        }
+    }
+
+    /// How many meters per second one is
+    pub fn meters_per_second(&self) -> f64 {
+        let distance = match self {
+            Self::Knots => DistanceUnit::NauticalMiles,
+            Self::MilesPerHour => DistanceUnit::Miles,
+            Self::KilometersPerHour => DistanceUnit::Kilometers,
+        };
+        distance.meters() / 3600.0
+    }
+
+    pub fn from_mps(&self, mps: f64) -> f64 {
+        mps / self.meters_per_second()
+    }
+
+    pub fn to_mps(&self, value: f64) -> f64 {
+        value * self.meters_per_second()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum AltitudeUnit {
+    #[default]
+    Feet,
+    Meters,
+}
+
+impl AltitudeUnit {
+    /// The name used in `launcher.txt` and shown after values
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Feet => "ft",
+            Self::Meters => "m",
+        }
+    }
+
+    /// How many meters one is
+    pub fn meters(&self) -> f64 {
+        match self {
+            Self::Feet => 0.3048,
+            Self::Meters => 1.0,
+        }
+    }
+
+    pub fn from_meters(&self, meters: f64) -> f64 {
+        meters / self.meters()
+    }
+
+    pub fn to_meters(&self, value: f64) -> f64 {
+        value * self.meters()
+    }
+}
+
+/// A unit for each quantity
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Units {
+    pub distance: DistanceUnit,
+    pub speed: SpeedUnit,
+    pub altitude: AltitudeUnit,
+}
+
+impl Units {
+    /// Nautical miles, knots and feet, as flown
+    pub const AVIATION: Self = Self {
+        distance: DistanceUnit::NauticalMiles,
+        speed: SpeedUnit::Knots,
+        altitude: AltitudeUnit::Feet,
+    };
+
+    pub const METRIC: Self = Self {
+        distance: DistanceUnit::Kilometers,
+        speed: SpeedUnit::KilometersPerHour,
+        altitude: AltitudeUnit::Meters,
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn test_units() {
+        assert!(close(
+            DistanceUnit::NauticalMiles.from_meters(18520.0),
+            10.0
+        ));
+        assert!(close(DistanceUnit::Miles.to_meters(1.0), 1609.344));
+        assert!(close(SpeedUnit::Knots.from_mps(100.0), 194.384449));
+        assert!(close(SpeedUnit::KilometersPerHour.to_mps(36.0), 10.0));
+        assert!(close(
+            SpeedUnit::MilesPerHour.from_mps(SpeedUnit::Knots.to_mps(100.0)),
+            115.077945
+        ));
+        assert!(close(AltitudeUnit::Feet.from_meters(3048.0), 10_000.0));
+        assert_eq!(Units::default(), Units::AVIATION);
+    }
+}
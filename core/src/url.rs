@@ -0,0 +1,59 @@
+//! Escaping text for links, for the remote server's download links and
+//! apps reading and writing URIs like `otpauth://`.
+
+/// `text` with anything but letters, digits and `-._~` escaped as `%XX`, so
+/// it's safe anywhere in a link
+pub fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// `%20` and the like turned back into what they stand for. A `%` without
+/// two hex digits after it is left as it is.
+pub fn percent_decode(text: &str) -> String {
+    let mut bytes = vec![];
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        // Checked first, since from_str_radix would take a sign
+        let hex = tail
+            .get(..2)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (byte, hex) {
+            (b'%', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// A value from a query string, where `+` is a space too
+pub fn query_decode(text: &str) -> String {
+    percent_decode(&text.replace('+', " "))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent_encode("log 1/2:ä.csv"), "log%201%2F2%3A%C3%A4.csv");
+        assert_eq!(percent_decode("log%201%2F2%3A%C3%A4.csv"), "log 1/2:ä.csv");
+        assert_eq!(percent_decode("100%+a%2"), "100%+a%2");
+        assert_eq!(query_decode("a+b%2Bc"), "a b+c");
+    }
+}
@@ -0,0 +1,16 @@
+//! Browser backend, sharing the simulator's window handling. Build with
+//! `wasm-pack build --target web` and serve `web/index.html`.
+
+use winit::{
+    event_loop::{ControlFlow, EventLoop},
+    platform::web::EventLoopExtWebSys,
+};
+
+/// Runs the app in a canvas appended to the page. Returns immediately; the
+/// browser drives the event loop from then on.
+pub fn run_app(app: impl crate::app::App + 'static) -> Result<(), crate::Error> {
+    let event_loop = EventLoop::new().map_err(|e| crate::Error::Window(e.to_string()))?;
+    event_loop.set_control_flow(ControlFlow::Poll);
+    event_loop.spawn_app(crate::sim::App::new(app, Default::default()));
+    Ok(())
+}
@@ -0,0 +1,30 @@
+//! Reusable pieces of UI for apps to build on.
+
+use rusttype::{point, Font, Scale};
+
+mod file_browser;
+mod focus;
+mod keyboard;
+mod meters;
+mod plot;
+mod reader;
+mod tree;
+
+pub use file_browser::{BrowserAction, Entry, FileBrowser};
+pub use focus::{FocusRing, Region, Strategy};
+pub use keyboard::{Keyboard, KeyboardEvent, BASE32_KEYS, TEXT_KEYS};
+pub use meters::{progress_bar, slider, Gauge};
+pub use plot::{Plot, Series};
+pub use reader::{wrap, Reader};
+pub use tree::{Ui, UiEvent, Widget};
+
+const FONT: &str = "fonts/Ubuntu-B.ttf";
+
+/// Width in pixels of `s` laid out in a single line
+pub fn text_width(font: &Font, scale: Scale, s: &str) -> f32 {
+    font.layout(s, scale, point(0.0, 0.0))
+        .last()
+        .map_or(0.0, |g| {
+            g.position().x + g.unpositioned().h_metrics().advance_width
+        })
+}
@@ -0,0 +1,351 @@
+use std::path::{Path, PathBuf};
+
+use palette::LinSrgb;
+use rusttype::Scale;
+
+use super::{text_width, FONT};
+use crate::actions::{Action, ActionMap};
+use crate::app::{load_font, Button, Frame, Input};
+use crate::time::{Duration, Instant};
+
+/// How often a long name scrolls by a character
+const MARQUEE_STEP: Duration = Duration::from_millis(200);
+/// Steps a long name waits at each end before scrolling again
+const MARQUEE_PAUSE: usize = 5;
+
+/// A file or directory in a [`FileBrowser`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// In bytes, 0 for directories
+    pub size: u64,
+}
+
+/// What the user did in a [`FileBrowser`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrowserAction {
+    /// A file was picked
+    Open(PathBuf),
+    /// Back was pressed in the top directory
+    Leave,
+}
+
+/// A list of the files under a directory, for picking one. Navigation can't
+/// leave `root`.
+pub struct FileBrowser {
+    root: PathBuf,
+    dir: PathBuf,
+    /// Lowercase extensions of the files to show, or empty for all files
+    extensions: Vec<String>,
+    entries: Vec<Entry>,
+    selected: usize,
+    /// When the selection last changed, which long names scroll from
+    selected_at: Instant,
+    error: Option<String>,
+    fontsize: f32,
+    /// How many rows fit in the last drawn area
+    visible_rows: usize,
+    /// The entry in the top row when last drawn
+    first_row: usize,
+    /// Left, top, width and row height of the rows when last drawn, for taps
+    rows_area: (usize, usize, usize, usize),
+    /// Whether the selected name was too long for the last drawn area
+    marquee: bool,
+    /// Which buttons confirm and go back, loaded when it's made
+    actions: ActionMap,
+}
+
+impl FileBrowser {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        let mut browser = Self {
+            dir: root.clone(),
+            root,
+            extensions: vec![],
+            entries: vec![],
+            selected: 0,
+            selected_at: Instant::now(),
+            error: None,
+            fontsize: 20.0,
+            visible_rows: 1,
+            first_row: 0,
+            rows_area: (0, 0, 0, 1),
+            marquee: false,
+            actions: ActionMap::global(),
+        };
+        browser.refresh();
+        browser
+    }
+
+    /// Only shows files with one of these extensions, e.g. `["csv", "txt"]`
+    pub fn with_extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = extensions.iter().map(|e| e.to_lowercase()).collect();
+        self.refresh();
+        self
+    }
+
+    pub fn with_fontsize(mut self, fontsize: f32) -> Self {
+        self.fontsize = fontsize;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The directory being shown
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        self.entries.get(self.selected)
+    }
+
+    /// Whether a long name is scrolling, so the app should keep updating
+    pub fn is_animating(&self) -> bool {
+        self.marquee
+    }
+
+    fn shows(&self, path: &Path) -> bool {
+        self.extensions.is_empty()
+            || path.extension().is_some_and(|e| {
+                self.extensions
+                    .contains(&e.to_string_lossy().to_lowercase())
+            })
+    }
+
+    /// Re-reads the directory, keeping the selection on the same name if it's
+    /// still there
+    pub fn refresh(&mut self) {
+        let previous = self.selected().map(|entry| entry.name.clone());
+        self.entries.clear();
+        self.error = None;
+        match std::fs::read_dir(&self.dir) {
+            Ok(read) => {
+                for entry in read.flatten() {
+                    let Ok(metadata) = entry.metadata() else {
+                        continue;
+                    };
+                    let path = entry.path();
+                    if !metadata.is_dir() && !self.shows(&path) {
+                        continue;
+                    }
+                    self.entries.push(Entry {
+                        name: entry.file_name().to_string_lossy().into_owned(),
+                        path,
+                        is_dir: metadata.is_dir(),
+                        size: if metadata.is_dir() { 0 } else { metadata.len() },
+                    });
+                }
+            }
+            Err(e) => self.error = Some(format!("Couldn't read {}: {}", self.dir.display(), e)),
+        }
+        // Directories first, then by name
+        self.entries.sort_by(|a, b| {
+            b.is_dir
+                .cmp(&a.is_dir)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        match previous {
+            Some(name) => self.select_name(&name),
+            None => self.select(0),
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        let index = index.min(self.entries.len().saturating_sub(1));
+        if index != self.selected {
+            self.selected_at = Instant::now();
+        }
+        self.selected = index;
+    }
+
+    /// Moves the selection to the entry called `name`, if there is one
+    pub fn select_name(&mut self, name: &str) {
+        let index = self.entries.iter().position(|entry| entry.name == name);
+        self.select(index.unwrap_or(self.selected));
+    }
+
+    /// The index of the entry drawn at `x`, `y` in the last [`draw`](Self::draw)
+    fn entry_at(&self, (x, y): (usize, usize)) -> Option<usize> {
+        let (left, top, width, row_height) = self.rows_area;
+        if x < left || x >= left + width || y < top {
+            return None;
+        }
+        let row = (y - top) / row_height;
+        let index = self.first_row + row;
+        (row < self.visible_rows && index < self.entries.len()).then_some(index)
+    }
+
+    fn enter(&mut self, dir: PathBuf) {
+        self.dir = dir;
+        self.selected = 0;
+        self.selected_at = Instant::now();
+        self.refresh();
+    }
+
+    /// Up and down move by one, left and right by a page. Confirm opens a
+    /// directory or picks a file, and back goes up a directory. Tapping an
+    /// entry selects it and tapping it again is the same as confirm.
+    pub fn handle_input(&mut self, input: &Input) -> Option<BrowserAction> {
+        let page = self.visible_rows.max(1);
+        if input.repeated(Button::PovUp) {
+            self.select(self.selected.saturating_sub(1));
+        }
+        if input.repeated(Button::PovDown) {
+            self.select(self.selected + 1);
+        }
+        if input.repeated(Button::PovLeft) {
+            self.select(self.selected.saturating_sub(page));
+        }
+        if input.repeated(Button::PovRight) {
+            self.select(self.selected + page);
+        }
+
+        let mut tapped_selected = false;
+        if let Some(index) = input.tapped().and_then(|point| self.entry_at(point)) {
+            tapped_selected = index == self.selected;
+            self.select(index);
+        }
+
+        if self.actions.just_pressed(input, Action::Confirm) || tapped_selected {
+            let entry = self.selected()?.clone();
+            if entry.is_dir {
+                self.enter(entry.path);
+            } else {
+                return Some(BrowserAction::Open(entry.path));
+            }
+        } else if self.actions.just_pressed(input, Action::Back) {
+            if self.dir == self.root {
+                return Some(BrowserAction::Leave);
+            }
+            let child = self
+                .dir
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned());
+            let parent = self
+                .dir
+                .parent()
+                .map_or(self.root.clone(), Path::to_path_buf);
+            self.enter(parent);
+            if let Some(child) = child {
+                self.select_name(&child);
+            }
+        }
+        None
+    }
+
+    /// Draws the current directory and as many entries as fit in the area
+    pub fn draw(&mut self, frame: &mut Frame, x: usize, y: usize, width: usize, height: usize) {
+        let font = load_font(FONT);
+        let scale = Scale::uniform(self.fontsize);
+        let fits = |s: &str| text_width(&font, scale, s) <= width as f32;
+        let line_height = (self.fontsize * 1.5).ceil() as usize;
+
+        let title = match self.dir.strip_prefix(&self.root) {
+            Ok(relative) if relative.as_os_str().is_empty() => "/".to_string(),
+            Ok(relative) => format!("/{}", relative.display()),
+            Err(_) => self.dir.display().to_string(),
+        };
+        frame.text(
+            FONT,
+            x,
+            y + self.fontsize as usize,
+            self.fontsize,
+            LinSrgb::new(180, 180, 180),
+            &title,
+        );
+        let y = y + line_height;
+        let height = height.saturating_sub(line_height);
+
+        if let Some(error) = &self.error {
+            frame.text(
+                FONT,
+                x,
+                y + self.fontsize as usize,
+                self.fontsize,
+                LinSrgb::new(255, 255, 255),
+                error,
+            );
+        } else if self.entries.is_empty() {
+            frame.text(
+                FONT,
+                x,
+                y + self.fontsize as usize,
+                self.fontsize,
+                LinSrgb::new(180, 180, 180),
+                "Empty",
+            );
+        }
+
+        self.visible_rows = (height / line_height).max(1);
+        let first = self
+            .selected
+            .saturating_sub(self.visible_rows - 1)
+            .min(self.entries.len().saturating_sub(self.visible_rows));
+        self.first_row = first;
+        self.rows_area = (x, y, width, line_height);
+        self.marquee = false;
+        for (row, (i, entry)) in self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(self.visible_rows)
+            .enumerate()
+        {
+            let name = if entry.is_dir {
+                format!("{}/", entry.name)
+            } else {
+                entry.name.clone()
+            };
+            let chars = name.chars().collect::<Vec<_>>();
+            // The longest start of `chars` that fits, with `suffix` on the end
+            let fitting = |chars: &[char], suffix: &str| {
+                (0..=chars.len())
+                    .rev()
+                    .map(|end| format!("{}{}", chars[..end].iter().collect::<String>(), suffix))
+                    .find(|s| fits(s))
+                    .unwrap_or_default()
+            };
+            let shown = if fits(&name) {
+                name
+            } else if i == self.selected {
+                // Scroll long names back and forth, pausing at each end
+                self.marquee = true;
+                let max_shift = (1..chars.len())
+                    .find(|&n| fits(&chars[n..].iter().collect::<String>()))
+                    .unwrap_or(chars.len() - 1);
+                let step =
+                    (self.selected_at.elapsed().as_millis() / MARQUEE_STEP.as_millis()) as usize;
+                let shift = (step % (max_shift + 2 * MARQUEE_PAUSE))
+                    .saturating_sub(MARQUEE_PAUSE)
+                    .min(max_shift);
+                fitting(&chars[shift..], "")
+            } else {
+                fitting(&chars, "...")
+            };
+            frame.text(
+                FONT,
+                x,
+                y + self.fontsize as usize + row * line_height,
+                self.fontsize,
+                if i == self.selected {
+                    LinSrgb::new(255, 255, 255)
+                } else if entry.is_dir {
+                    LinSrgb::new(255, 160, 0)
+                } else {
+                    LinSrgb::new(255, 0, 0)
+                },
+                &shown,
+            );
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::actions::{Action, ActionMap};
+use crate::app::{Button, Input};
+use crate::grid::Direction;
+use crate::haptics::{self, Pattern};
+
+/// How a [`FocusRing`] picks the next region for a D-pad press
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Up and left go to the previous region, down and right to the next
+    #[default]
+    Ordered,
+    /// To the nearest region in the direction pressed, by where the regions
+    /// are on screen
+    Spatial,
+}
+
+/// Where a focusable thing is on screen: left, top, width and height
+pub type Region = (usize, usize, usize, usize);
+
+/// Which of a set of focusable regions is focused, moved between with the
+/// D-pad. Set the regions as they're laid out, then call
+/// [`FocusRing::update`] each frame and ask [`FocusRing::is_focused`] while
+/// drawing. Confirm presses the focused region, see
+/// [`FocusRing::activated`].
+#[derive(Debug, Clone)]
+pub struct FocusRing {
+    strategy: Strategy,
+    /// Whether ordered focus goes round from the last region to the first
+    wrap: bool,
+    regions: Vec<(String, Region)>,
+    focused: Option<String>,
+    /// The region confirm was pressed on this frame
+    activated: Option<String>,
+    /// Which button confirms, loaded when it's made
+    actions: ActionMap,
+}
+
+impl Default for FocusRing {
+    fn default() -> Self {
+        Self {
+            strategy: Strategy::default(),
+            wrap: false,
+            regions: vec![],
+            focused: None,
+            activated: None,
+            actions: ActionMap::global(),
+        }
+    }
+}
+
+/// The middle of `region`, doubled to stay in whole numbers
+fn center((x, y, width, height): Region) -> (isize, isize) {
+    ((x * 2 + width) as isize, (y * 2 + height) as isize)
+}
+
+impl FocusRing {
+    pub fn new(strategy: Strategy) -> Self {
+        Self {
+            strategy,
+            ..Self::default()
+        }
+    }
+
+    /// Goes round from the last region to the first and back, with
+    /// [`Strategy::Ordered`]
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Replaces the regions. Focus stays where it is if its id is still
+    /// there, and otherwise goes to the first region.
+    pub fn set_regions<S: Into<String>>(&mut self, regions: impl IntoIterator<Item = (S, Region)>) {
+        self.regions = regions
+            .into_iter()
+            .map(|(id, region)| (id.into(), region))
+            .collect();
+        let still_there = self
+            .focused
+            .as_ref()
+            .is_some_and(|focused| self.index(focused).is_some());
+        if !still_there {
+            self.focused = self.regions.first().map(|(id, _)| id.clone());
+        }
+    }
+
+    /// Regions in order that don't need positions, for
+    /// [`Strategy::Ordered`]
+    pub fn set_ids<S: Into<String>>(&mut self, ids: impl IntoIterator<Item = S>) {
+        self.set_regions(ids.into_iter().map(|id| (id, (0, 0, 0, 0))));
+    }
+
+    fn index(&self, id: &str) -> Option<usize> {
+        self.regions.iter().position(|(region, _)| region == id)
+    }
+
+    pub fn focused(&self) -> Option<&str> {
+        self.focused.as_deref()
+    }
+
+    pub fn is_focused(&self, id: &str) -> bool {
+        self.focused.as_deref() == Some(id)
+    }
+
+    /// Focuses `id`, if it's one of the regions
+    pub fn focus(&mut self, id: &str) {
+        if self.index(id).is_some() {
+            self.focused = Some(id.to_string());
+        }
+    }
+
+    /// Whether confirm was pressed on `id` this frame
+    pub fn activated(&self, id: &str) -> bool {
+        self.activated.as_deref() == Some(id)
+    }
+
+    /// Moves focus for a D-pad press and notes whether confirm was pressed,
+    /// returning whether focus moved. Either gives a little
+    /// [rumble](crate::haptics).
+    pub fn update(&mut self, input: &Input) -> bool {
+        self.activated = None;
+        if self.actions.just_pressed(input, Action::Confirm) {
+            self.activated = self.focused.clone();
+            if self.activated.is_some() {
+                haptics::play(&Pattern::DOUBLE_BUZZ);
+            }
+        }
+        let direction = [
+            (Button::PovUp, Direction::Up),
+            (Button::PovDown, Direction::Down),
+            (Button::PovLeft, Direction::Left),
+            (Button::PovRight, Direction::Right),
+        ]
+        .into_iter()
+        .find(|&(button, _)| input.repeated(button));
+        let moved = direction.is_some_and(|(_, direction)| self.step(direction));
+        if moved {
+            haptics::play(&Pattern::TICK);
+        }
+        moved
+    }
+
+    /// The controls it was made with, for widgets built on it
+    pub(super) fn actions(&self) -> &ActionMap {
+        &self.actions
+    }
+
+    /// Moves focus one step in `direction`, returning whether it moved
+    pub fn step(&mut self, direction: Direction) -> bool {
+        let Some(current) = self.focused.as_deref().and_then(|id| self.index(id)) else {
+            self.focused = self.regions.first().map(|(id, _)| id.clone());
+            return self.focused.is_some();
+        };
+        let next = match self.strategy {
+            Strategy::Ordered => self.ordered(current, direction),
+            Strategy::Spatial => self.spatial(current, direction),
+        };
+        match next {
+            Some(next) if next != current => {
+                self.focused = Some(self.regions[next].0.clone());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn ordered(&self, current: usize, direction: Direction) -> Option<usize> {
+        let count = self.regions.len();
+        let back = matches!(direction, Direction::Up | Direction::Left);
+        match (back, self.wrap) {
+            (true, true) => Some((current + count - 1) % count),
+            (false, true) => Some((current + 1) % count),
+            (true, false) => current.checked_sub(1),
+            (false, false) => Some(current + 1).filter(|&next| next < count),
+        }
+    }
+
+    /// The nearest region whose middle is past the current one's far edge
+    /// in `direction`, counting the gap across twice as much as the distance
+    /// along so focus keeps to rows and columns. Ties go to the earlier
+    /// region.
+    fn spatial(&self, current: usize, direction: Direction) -> Option<usize> {
+        let from = self.regions[current].1;
+        // Its edges, doubled like the middles
+        let (left, top) = (from.0 as isize * 2, from.1 as isize * 2);
+        let (right, bottom) = (left + from.2 as isize * 2, top + from.3 as isize * 2);
+        self.regions
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != current)
+            .filter_map(|(i, &(_, region))| {
+                let (x, y) = center(region);
+                let (along, across) = match direction {
+                    Direction::Up => (top - y, gap((from.0, from.2), (region.0, region.2))),
+                    Direction::Down => (y - bottom, gap((from.0, from.2), (region.0, region.2))),
+                    Direction::Left => (left - x, gap((from.1, from.3), (region.1, region.3))),
+                    Direction::Right => (x - right, gap((from.1, from.3), (region.1, region.3))),
+                };
+                (along > 0).then_some((along + across * 4, i))
+            })
+            .min()
+            .map(|(_, i)| i)
+    }
+}
+
+/// How far apart two spans given as start and length are, or 0 if they
+/// overlap
+fn gap((a, a_length): (usize, usize), (b, b_length): (usize, usize)) -> isize {
+    let gap = if a < b {
+        b.saturating_sub(a + a_length)
+    } else {
+        a.saturating_sub(b + b_length)
+    };
+    gap as isize
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_focus_ring() {
+        let mut ordered = FocusRing::new(Strategy::Ordered);
+        ordered.set_ids(["a", "b", "c"]);
+        assert!(ordered.is_focused("a"));
+        assert!(!ordered.step(Direction::Up));
+        assert!(ordered.step(Direction::Right));
+        assert!(ordered.is_focused("b"));
+        let mut wrapping = ordered.clone().with_wrap(true);
+        wrapping.focus("c");
+        assert!(wrapping.step(Direction::Down));
+        assert!(wrapping.is_focused("a"));
+
+        // A field above two buttons side by side
+        let mut spatial = FocusRing::new(Strategy::Spatial);
+        spatial.set_regions([
+            ("name", (0, 0, 200, 20)),
+            ("ok", (0, 40, 60, 20)),
+            ("cancel", (100, 40, 60, 20)),
+        ]);
+        assert!(spatial.step(Direction::Down));
+        assert!(spatial.is_focused("ok"));
+        assert!(spatial.step(Direction::Right));
+        assert!(spatial.is_focused("cancel"));
+        assert!(!spatial.step(Direction::Right));
+        assert!(spatial.step(Direction::Up));
+        assert!(spatial.is_focused("name"));
+        spatial.set_regions([("ok", (0, 0, 10, 10))]);
+        assert!(spatial.is_focused("ok"));
+
+        // B presses with confirm swapped, and A doesn't
+        spatial.actions.swap_confirm();
+        let mut input = Input::default();
+        input.event(Button::ActionA, true);
+        spatial.update(&input);
+        assert!(!spatial.activated("ok"));
+        input.update();
+        input.event(Button::ActionB, true);
+        spatial.update(&input);
+        assert!(spatial.activated("ok"));
+    }
+}
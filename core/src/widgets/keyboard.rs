@@ -0,0 +1,258 @@
+use super::FONT;
+use crate::actions::{Action, ActionMap};
+use crate::anim::Lerp;
+use crate::app::{Button, Frame, Input};
+use crate::config::config;
+use crate::grid::Direction;
+
+/// Letters, digits and a little punctuation, for names
+pub const TEXT_KEYS: [&str; 4] = ["1234567890", "QWERTYUIOP", "ASDFGHJKL-", "ZXCVBNM .@"];
+/// The letters and digits of base32, for secrets and codes
+pub const BASE32_KEYS: [&str; 4] = ["ABCDEFGH", "IJKLMNOP", "QRSTUVWX", "YZ234567"];
+
+const KEY_SIZE: usize = 40;
+const KEY_GAP: usize = 4;
+const FIELD_HEIGHT: usize = 40;
+
+/// What a [`Keyboard`] was closed with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyboardEvent {
+    Done(String),
+    Cancelled,
+}
+
+/// Typing with the D-pad: a grid of keys to move around, over the text typed
+/// so far. Confirm types the key, X deletes the last character, Y switches
+/// between upper and lower case, START is done and back cancels.
+///
+/// ```ignore
+/// let mut keyboard = Keyboard::new(&TEXT_KEYS).with_text("Tea");
+/// match keyboard.update(input) {
+///     Some(KeyboardEvent::Done(text)) => self.name = text,
+///     Some(KeyboardEvent::Cancelled) => (),
+///     None => keyboard.draw(frame, 100, 200),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyboard {
+    rows: Vec<Vec<char>>,
+    row: usize,
+    column: usize,
+    text: String,
+    max_len: usize,
+    lowercase: bool,
+    /// Which buttons confirm and go back, loaded when it's made
+    actions: ActionMap,
+}
+
+impl Keyboard {
+    /// A keyboard with a row of keys for each string in `rows`
+    pub fn new(rows: &[&str]) -> Self {
+        Self {
+            rows: rows
+                .iter()
+                .map(|row| row.chars().collect())
+                .filter(|row: &Vec<char>| !row.is_empty())
+                .collect(),
+            row: 0,
+            column: 0,
+            text: String::new(),
+            max_len: 32,
+            lowercase: false,
+            actions: ActionMap::global(),
+        }
+    }
+
+    /// Starts with `text` already typed
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = text.chars().take(self.max_len).collect();
+        self
+    }
+
+    /// How many characters can be typed, 32 unless set
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self.text = self.text.chars().take(max_len).collect();
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The key under the cursor, in the case it would be typed
+    pub fn key(&self) -> Option<char> {
+        let key = *self.rows.get(self.row)?.get(self.column)?;
+        Some(self.cased(key))
+    }
+
+    fn cased(&self, key: char) -> char {
+        if self.lowercase {
+            key.to_ascii_lowercase()
+        } else {
+            key
+        }
+    }
+
+    /// Moves the cursor a key over, wrapping round at the edges. Going up or
+    /// down onto a shorter row lands on its last key.
+    pub fn step(&mut self, direction: Direction) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let rows = self.rows.len();
+        match direction {
+            Direction::Up => self.row = (self.row + rows - 1) % rows,
+            Direction::Down => self.row = (self.row + 1) % rows,
+            Direction::Left | Direction::Right => {
+                let columns = self.rows[self.row].len();
+                self.column = if direction == Direction::Left {
+                    (self.column.min(columns - 1) + columns - 1) % columns
+                } else {
+                    (self.column + 1) % columns
+                };
+            }
+        }
+        self.column = self.column.min(self.rows[self.row].len() - 1);
+    }
+
+    /// Types the key under the cursor, if there's room
+    pub fn type_key(&mut self) {
+        if self.text.chars().count() < self.max_len {
+            if let Some(key) = self.key() {
+                self.text.push(key);
+            }
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.text.pop();
+    }
+
+    /// Moves around and types, returning what the keyboard was closed with
+    pub fn update(&mut self, input: &Input) -> Option<KeyboardEvent> {
+        for (button, direction) in [
+            (Button::PovUp, Direction::Up),
+            (Button::PovDown, Direction::Down),
+            (Button::PovLeft, Direction::Left),
+            (Button::PovRight, Direction::Right),
+        ] {
+            if input.repeated(button) {
+                self.step(direction);
+            }
+        }
+        if self.actions.repeated(input, Action::Confirm) {
+            self.type_key();
+        }
+        if input.repeated(Button::ActionV) {
+            self.backspace();
+        }
+        if input.just_pressed(Button::ActionH) {
+            self.lowercase = !self.lowercase;
+        }
+        if self.actions.just_pressed(input, Action::Back) {
+            return Some(KeyboardEvent::Cancelled);
+        }
+        if input.start_pressed() {
+            return Some(KeyboardEvent::Done(self.text.clone()));
+        }
+        None
+    }
+
+    /// The width and height it takes up when drawn, including the hints
+    pub fn size(&self) -> (usize, usize) {
+        let columns = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let width = (columns * (KEY_SIZE + KEY_GAP)).saturating_sub(KEY_GAP);
+        let height = FIELD_HEIGHT + KEY_GAP * 2 + self.rows.len() * (KEY_SIZE + KEY_GAP) + 20;
+        (width, height)
+    }
+
+    /// Draws the text typed so far with the keys under it, the top left at
+    /// `x`, `y`
+    pub fn draw(&self, frame: &mut Frame, x: usize, y: usize) {
+        let colors = config().theme.colors();
+        let key_color = colors.dim.lerp(colors.background, 0.7);
+        let (width, height) = self.size();
+
+        frame.fill_rect(x, y, width, FIELD_HEIGHT, key_color);
+        let cursor = if self.text.chars().count() < self.max_len {
+            "_"
+        } else {
+            ""
+        };
+        frame.text(
+            FONT,
+            x + 8,
+            y + 29,
+            24.0,
+            colors.text,
+            &format!("{}{}", self.text, cursor),
+        );
+
+        let top = y + FIELD_HEIGHT + KEY_GAP * 2;
+        for (r, row) in self.rows.iter().enumerate() {
+            for (c, &key) in row.iter().enumerate() {
+                let kx = x + c * (KEY_SIZE + KEY_GAP);
+                let ky = top + r * (KEY_SIZE + KEY_GAP);
+                let focused = (r, c) == (self.row, self.column);
+                let (fill, color) = if focused {
+                    (colors.selected, colors.background)
+                } else {
+                    (key_color, colors.text)
+                };
+                frame.fill_rect(kx, ky, KEY_SIZE, KEY_SIZE, fill);
+                if key == ' ' {
+                    frame.text(FONT, kx + 8, ky + 25, 12.0, color, "spc");
+                } else {
+                    let key = self.cased(key).to_string();
+                    frame.text(FONT, kx + 12, ky + 29, 22.0, color, &key);
+                }
+            }
+        }
+
+        frame.text(
+            FONT,
+            x,
+            y + height - 4,
+            14.0,
+            colors.dim,
+            &format!(
+                "{} type  X delete  Y case  START done  {} cancel",
+                self.actions.label(Action::Confirm),
+                self.actions.label(Action::Back),
+            ),
+        );
+    }
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new(&TEXT_KEYS)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keyboard() {
+        let mut keyboard = Keyboard::new(&["ABC", "DE"]).with_max_len(3);
+        keyboard.step(Direction::Left);
+        assert_eq!(keyboard.key(), Some('C'));
+        // Down onto the shorter row lands on its last key
+        keyboard.step(Direction::Down);
+        assert_eq!(keyboard.key(), Some('E'));
+        keyboard.type_key();
+        keyboard.step(Direction::Down);
+        keyboard.step(Direction::Left);
+        assert_eq!(keyboard.key(), Some('A'));
+        keyboard.lowercase = true;
+        keyboard.type_key();
+        keyboard.type_key();
+        keyboard.type_key();
+        assert_eq!(keyboard.text(), "Eaa");
+        keyboard.backspace();
+        assert_eq!(keyboard.text(), "Ea");
+    }
+}
@@ -0,0 +1,193 @@
+use std::f32::consts::PI;
+
+use palette::LinSrgb;
+use rusttype::Scale;
+
+use super::{text_width, FONT};
+use crate::anim::Lerp;
+use crate::app::{load_font, Frame};
+use crate::config::{config, ThemeColors};
+
+/// The empty part of a bar or dial: the theme's hint color, most of the way
+/// to the background
+fn track_color(colors: &ThemeColors) -> LinSrgb<u8> {
+    colors.dim.lerp(colors.background, 0.7)
+}
+
+/// Draws a bar `progress` of the way full, from 0 to 1, in the theme's
+/// colors
+pub fn progress_bar(
+    frame: &mut Frame,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    progress: f32,
+) {
+    let colors = config().theme.colors();
+    let filled = (width as f32 * progress.clamp(0.0, 1.0)).round() as usize;
+    frame.fill_rect(x, y, width, height, track_color(&colors));
+    frame.fill_rect(x, y, filled, height, colors.text);
+}
+
+/// Draws a slider's track with its knob `fraction` of the way along, from 0
+/// to 1. The knob is highlighted when `focused`, as left and right move it.
+pub fn slider(frame: &mut Frame, x: usize, y: usize, width: usize, fraction: f32, focused: bool) {
+    let colors = config().theme.colors();
+    const KNOB: usize = 12;
+    let travel = width.saturating_sub(KNOB);
+    let knob = (travel as f32 * fraction.clamp(0.0, 1.0)).round() as usize;
+    frame.fill_rect(x, y + KNOB / 2 - 2, width, 4, track_color(&colors));
+    frame.fill_rect(x, y + KNOB / 2 - 2, knob, 4, colors.text);
+    let knob_color = if focused {
+        colors.selected
+    } else {
+        colors.text
+    };
+    frame.fill_rect(x + knob, y, KNOB, KNOB, knob_color);
+}
+
+/// A round dial that fills clockwise from the bottom left to the bottom
+/// right as its value goes from `min` to `max`, with the value written in
+/// the middle
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gauge {
+    pub min: f32,
+    pub max: f32,
+    /// Under the value, e.g. the unit
+    pub label: String,
+    /// Digits after the decimal point
+    pub decimals: usize,
+    /// Marks around the outside, including both ends, or 0 for none
+    pub ticks: usize,
+}
+
+/// How far round the dial goes, in radians
+const SWEEP: f32 = 1.5 * PI;
+/// Where the dial starts, in radians clockwise from the right
+const START: f32 = 0.75 * PI;
+
+impl Gauge {
+    pub fn new(min: f32, max: f32) -> Self {
+        Self {
+            min,
+            max,
+            label: String::new(),
+            decimals: 0,
+            ticks: 0,
+        }
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = label.to_string();
+        self
+    }
+
+    pub fn with_decimals(mut self, decimals: usize) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    pub fn with_ticks(mut self, ticks: usize) -> Self {
+        self.ticks = ticks;
+        self
+    }
+
+    /// How far round `value` is, from 0 to 1
+    fn fraction(&self, value: f32) -> f32 {
+        if self.max > self.min {
+            ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Draws the dial showing `value`, centered on `x`, `y`
+    pub fn draw(&self, frame: &mut Frame, x: usize, y: usize, radius: usize, value: f32) {
+        let colors = config().theme.colors();
+        let track = track_color(&colors);
+        let filled = self.fraction(value);
+        let outer = radius as f32;
+        let inner = outer - (outer / 6.0).max(3.0);
+
+        for py in y.saturating_sub(radius)..y + radius {
+            for px in x.saturating_sub(radius)..x + radius {
+                // From the middle of the pixel
+                let dx = px as f32 + 0.5 - x as f32;
+                let dy = py as f32 + 0.5 - y as f32;
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance < inner || distance > outer {
+                    continue;
+                }
+                let along = (dy.atan2(dx) - START).rem_euclid(2.0 * PI) / SWEEP;
+                if along <= 1.0 {
+                    let color = if along <= filled { colors.text } else { track };
+                    frame.put_pixel(px, py, color);
+                }
+            }
+        }
+
+        // Ticks just outside the dial
+        for tick in 0..self.ticks {
+            let along = tick as f32 / (self.ticks - 1).max(1) as f32;
+            let (sin, cos) = (START + along * SWEEP).sin_cos();
+            for step in 0..(outer / 8.0).max(2.0) as usize {
+                let distance = outer + 2.0 + step as f32;
+                let px = x as f32 + cos * distance;
+                let py = y as f32 + sin * distance;
+                if px >= 0.0 && py >= 0.0 {
+                    frame.put_pixel(px as usize, py as usize, colors.dim);
+                }
+            }
+        }
+
+        let font = load_font(FONT);
+        let mut centered = |text: &str, fontsize: f32, baseline: usize, color| {
+            let width = text_width(&font, Scale::uniform(fontsize), text) as usize;
+            frame.text(
+                FONT,
+                x.saturating_sub(width / 2),
+                baseline,
+                fontsize,
+                color,
+                text,
+            );
+        };
+        let fontsize = (outer / 2.2).max(8.0);
+        let text = format!("{:.*}", self.decimals, value);
+        centered(
+            &text,
+            fontsize,
+            y + (fontsize / 3.0) as usize,
+            colors.selected,
+        );
+        if !self.label.is_empty() {
+            let small = (fontsize / 2.0).max(8.0);
+            let baseline = y + (outer * 0.75) as usize;
+            centered(&self.label, small, baseline, colors.dim);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::surface::Surface;
+
+    #[test]
+    fn test_gauge() {
+        let gauge = Gauge::new(0.0, 200.0);
+        assert_eq!(gauge.fraction(50.0), 0.25);
+        assert_eq!(gauge.fraction(-10.0), 0.0);
+        assert_eq!(Gauge::new(1.0, 1.0).fraction(1.0), 0.0);
+
+        let colors = config().theme.colors();
+        let mut surface = Surface::new(40, 40);
+        gauge.draw(&mut surface.frame(), 20, 20, 20, 100.0);
+        // Half way round, filled to the top but not past it
+        assert_eq!(surface.get_pixel(18, 1), colors.text);
+        assert_eq!(surface.get_pixel(22, 1), track_color(&colors));
+        // Nothing in the gap at the bottom
+        assert_eq!(surface.get_pixel(20, 38), LinSrgb::new(0, 0, 0));
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::VecDeque;
+
+use palette::LinSrgb;
+
+use super::FONT;
+use crate::anim::Lerp;
+use crate::app::Frame;
+use crate::config::config;
+use crate::text_block::TextBlock;
+
+/// One line on a [`Plot`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub color: LinSrgb<u8>,
+    /// Evenly spaced along the bottom, oldest first
+    pub values: VecDeque<f32>,
+}
+
+impl Series {
+    /// Drops the oldest values beyond `capacity`
+    fn trim(&mut self, capacity: Option<usize>) {
+        if let Some(capacity) = capacity {
+            let excess = self.values.len().saturating_sub(capacity);
+            self.values.drain(..excess);
+        }
+    }
+}
+
+/// A line plot of one or more series of values against time, with the
+/// vertical axis fitted to the values unless it's fixed with
+/// [`Plot::with_range`]. Push values as they come in, or set them all at
+/// once, and draw it each frame.
+///
+/// ```ignore
+/// let mut plot = Plot::new(300, 120)
+///     .with_series("update", red)
+///     .with_capacity(120);
+/// plot.push(0, millis);
+/// plot.draw(frame, 20, 20);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    width: usize,
+    height: usize,
+    series: Vec<Series>,
+    /// How many values each series keeps, and how many fit across
+    capacity: Option<usize>,
+    range: Option<(f32, f32)>,
+    /// Whether the fitted range always includes 0
+    zero: bool,
+    ticks: usize,
+    /// Under the first and last values, e.g. `-2m` and `now`
+    x_labels: Option<(String, String)>,
+    font: String,
+    fontsize: f32,
+}
+
+/// A round step between `ticks` marks spanning about `span`: 1, 2 or 5
+/// times a power of ten
+fn nice_step(span: f32, ticks: usize) -> f32 {
+    let rough = span / ticks.saturating_sub(1).max(1) as f32;
+    let magnitude = 10f32.powf(rough.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= rough)
+        .unwrap_or(10.0 * magnitude);
+    step.max(f32::MIN_POSITIVE)
+}
+
+/// `min` and `max` pushed out to whole steps, and the step
+fn fit(min: f32, max: f32, ticks: usize) -> (f32, f32, f32) {
+    let (min, max) = if max > min {
+        (min, max)
+    } else {
+        (min - 1.0, max + 1.0)
+    };
+    let step = nice_step(max - min, ticks);
+    (
+        (min / step).floor() * step,
+        (max / step).ceil() * step,
+        step,
+    )
+}
+
+/// Draws a one pixel line from one point to another
+fn line(frame: &mut Frame, (x0, y0): (isize, isize), (x1, y1): (isize, isize), color: LinSrgb<u8>) {
+    let steps = (x1 - x0).abs().max((y1 - y0).abs()).max(1);
+    for i in 0..=steps {
+        let x = x0 + (x1 - x0) * i / steps;
+        let y = y0 + (y1 - y0) * i / steps;
+        if x >= 0 && y >= 0 {
+            frame.put_pixel(x as usize, y as usize, color);
+        }
+    }
+}
+
+impl Plot {
+    /// An empty plot `width` by `height` pixels, including its labels,
+    /// with 5 ticks up the side
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            series: vec![],
+            capacity: None,
+            range: None,
+            zero: false,
+            ticks: 5,
+            x_labels: None,
+            font: FONT.to_string(),
+            fontsize: 12.0,
+        }
+    }
+
+    /// Adds a series, which is numbered in the order they're added
+    pub fn with_series(mut self, name: &str, color: LinSrgb<u8>) -> Self {
+        self.series.push(Series {
+            name: name.to_string(),
+            color,
+            values: VecDeque::new(),
+        });
+        self
+    }
+
+    /// Keeps the last `capacity` values of each series, which fill the plot
+    /// from the right as they're pushed
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Fixes the vertical axis instead of fitting it to the values
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.range = Some((min, max));
+        self
+    }
+
+    /// Keeps 0 on the vertical axis when fitting it to the values
+    pub fn with_zero(mut self, zero: bool) -> Self {
+        self.zero = zero;
+        self
+    }
+
+    /// About how many marks and labels go up the side, including both ends,
+    /// or 0 for none
+    pub fn with_ticks(mut self, ticks: usize) -> Self {
+        self.ticks = ticks;
+        self
+    }
+
+    /// Labels under the oldest and newest ends of the plot
+    pub fn with_x_labels(mut self, first: &str, last: &str) -> Self {
+        self.x_labels = Some((first.to_string(), last.to_string()));
+        self
+    }
+
+    /// The labels' font, which can be a [bitmap font](crate::bitmap_font)
+    pub fn with_font(mut self, font: &str, fontsize: f32) -> Self {
+        self.font = font.to_string();
+        self.fontsize = fontsize;
+        self
+    }
+
+    pub fn series(&self) -> &[Series] {
+        &self.series
+    }
+
+    /// Adds a value to the end of `series`, dropping the oldest if it's full
+    pub fn push(&mut self, series: usize, value: f32) {
+        let capacity = self.capacity;
+        if let Some(series) = self.series.get_mut(series) {
+            series.values.push_back(value);
+            series.trim(capacity);
+        }
+    }
+
+    /// Replaces all of the values of `series`
+    pub fn set_values(&mut self, series: usize, values: impl IntoIterator<Item = f32>) {
+        let capacity = self.capacity;
+        if let Some(series) = self.series.get_mut(series) {
+            series.values = values.into_iter().collect();
+            series.trim(capacity);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for series in &mut self.series {
+            series.values.clear();
+        }
+    }
+
+    /// The bottom and top of the vertical axis and the step between ticks
+    pub fn range(&self) -> (f32, f32, f32) {
+        if let Some((min, max)) = self.range {
+            let span = if max > min { max - min } else { 1.0 };
+            return (min, max, span / self.ticks.saturating_sub(1).max(1) as f32);
+        }
+        let values = self
+            .series
+            .iter()
+            .flat_map(|series| series.values.iter().copied())
+            .filter(|value| value.is_finite());
+        let (mut min, mut max) = values
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+        if min > max {
+            (min, max) = (0.0, 1.0);
+        }
+        if self.zero {
+            (min, max) = (min.min(0.0), max.max(0.0));
+        }
+        fit(min, max, self.ticks)
+    }
+
+    /// Draws the plot with its top left at `x`, `y`: tick labels up the
+    /// left, the lines, and a legend in the top right when there's more
+    /// than one series
+    pub fn draw(&self, frame: &mut Frame, x: usize, y: usize) {
+        let colors = config().theme.colors();
+        let (min, max, step) = self.range();
+        let decimals = (-step.log10().floor()).max(0.0) as usize;
+        let label = |text: &str, color| {
+            TextBlock::new(&self.font, self.fontsize, color)
+                .with_line_spacing(1.0)
+                .with_text(text)
+        };
+
+        // Fitting to round steps can take a tick more or less than asked for
+        let ticks = match self.ticks {
+            0 => 0,
+            _ => ((max - min) / step).round() as usize + 1,
+        };
+        let tick_labels = (0..ticks)
+            .map(|i| {
+                let value = min + step * i as f32;
+                label(&format!("{:.*}", decimals, value), colors.dim)
+            })
+            .collect::<Vec<_>>();
+        let x_labels = self
+            .x_labels
+            .as_ref()
+            .map(|(first, last)| (label(first, colors.dim), label(last, colors.dim)));
+        let label_width = tick_labels
+            .iter()
+            .map(|label| label.width())
+            .max()
+            .unwrap_or(0);
+        let label_height = tick_labels
+            .first()
+            .or(x_labels.as_ref().map(|(first, _)| first))
+            .map_or(0, |label| label.line_height());
+
+        // The area the lines are drawn in
+        let left = x + label_width + 4;
+        let top = y + label_height / 2;
+        let bottom_space = if x_labels.is_some() {
+            label_height + 2
+        } else {
+            label_height / 2
+        };
+        let width = (x + self.width).saturating_sub(left + 1);
+        let height = (y + self.height).saturating_sub(top + bottom_space + 1);
+        let bottom = top + height;
+        let to_y = |value: f32| {
+            let along = if max > min {
+                (value - min) / (max - min)
+            } else {
+                0.0
+            };
+            bottom as isize - (along.clamp(0.0, 1.0) * height as f32).round() as isize
+        };
+
+        let grid = colors.dim.lerp(colors.background, 0.8);
+        for (i, tick) in tick_labels.iter().enumerate() {
+            let ty = to_y(min + step * i as f32);
+            frame.fill_rect(left, ty as usize, width, 1, grid);
+            let (tick_width, tick_height) = tick.size();
+            tick.draw(
+                frame,
+                left - 4 - tick_width,
+                (ty as usize).saturating_sub(tick_height / 2),
+            );
+        }
+        frame.fill_rect(left, top, 1, height + 1, colors.dim);
+        frame.fill_rect(left, bottom, width, 1, colors.dim);
+        if let Some((first, last)) = x_labels {
+            first.draw(frame, left, bottom + 2);
+            last.draw(
+                frame,
+                (left + width).saturating_sub(last.width()),
+                bottom + 2,
+            );
+        }
+
+        let slots = self.capacity.unwrap_or_else(|| {
+            self.series
+                .iter()
+                .map(|s| s.values.len())
+                .max()
+                .unwrap_or(0)
+        });
+        let to_x = |i: usize| {
+            (left + 1) as isize
+                + (i * width.saturating_sub(1) / slots.saturating_sub(1).max(1)) as isize
+        };
+        for series in &self.series {
+            // Right aligned, so the newest value is always at the right
+            let first = slots.saturating_sub(series.values.len());
+            let points = series
+                .values
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| value.is_finite())
+                .map(|(i, &value)| (to_x(first + i), to_y(value)))
+                .collect::<Vec<_>>();
+            for pair in points.windows(2) {
+                line(frame, pair[0], pair[1], series.color);
+            }
+            if let [point] = points[..] {
+                line(frame, point, point, series.color);
+            }
+        }
+
+        if self.series.len() > 1 {
+            let mut right = left + width;
+            for series in self.series.iter().rev() {
+                let name = label(&series.name, series.color);
+                right = right.saturating_sub(name.width());
+                name.draw(frame, right, top + 2);
+                right = right.saturating_sub(8);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_plot() {
+        assert_eq!(fit(3.0, 47.0, 5), (0.0, 60.0, 20.0));
+        assert_eq!(fit(120.0, 310.0, 3), (100.0, 400.0, 100.0));
+        // A flat line gets some room around it
+        assert_eq!(fit(2.0, 2.0, 3), (1.0, 3.0, 1.0));
+
+        let mut plot = Plot::new(100, 50)
+            .with_series("a", LinSrgb::new(255, 0, 0))
+            .with_series("b", LinSrgb::new(0, 255, 0))
+            .with_capacity(3);
+        for value in [1.0, 2.0, 3.0, 4.0] {
+            plot.push(0, value);
+        }
+        plot.push(1, -6.0);
+        plot.push(2, 100.0);
+        assert_eq!(plot.series()[0].values, [2.0, 3.0, 4.0]);
+        assert_eq!(plot.range(), (-10.0, 5.0, 5.0));
+        plot.clear();
+        assert_eq!(plot.with_zero(true).range().0, 0.0);
+    }
+}
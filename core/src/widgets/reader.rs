@@ -0,0 +1,104 @@
+use palette::LinSrgb;
+
+use super::FONT;
+use crate::app::{Button, Frame, Input};
+use crate::text_block::{TextBlock, TextDirection};
+
+/// Splits `text` into lines no wider than `width` pixels. Newlines in `text`
+/// always start a new line, and words too long for a line of their own are
+/// split wherever they overflow.
+pub fn wrap(text: &str, fontsize: f32, width: usize) -> Vec<String> {
+    TextBlock::new(FONT, fontsize, LinSrgb::new(255, 255, 255))
+        .with_direction(TextDirection::Ltr)
+        .with_max_width(width)
+        .with_text(text)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// A scrollable block of word-wrapped text, for reading articles and other
+/// long text a screen at a time. Paragraphs in Hebrew or Arabic run right to
+/// left.
+pub struct Reader {
+    /// Wrapped to the width it was last drawn at
+    text: TextBlock,
+    /// Index of the first line on screen
+    scroll: usize,
+    /// How many lines fit in the last drawn area
+    visible_lines: usize,
+}
+
+impl Reader {
+    pub fn new(fontsize: f32) -> Self {
+        Self {
+            text: TextBlock::new(FONT, fontsize, LinSrgb::new(255, 255, 255)),
+            scroll: 0,
+            visible_lines: 1,
+        }
+    }
+
+    pub fn set_color(&mut self, color: LinSrgb<u8>) {
+        self.text.set_color(color);
+    }
+
+    /// Replaces the text and scrolls back to the top
+    pub fn set_text(&mut self, text: &str) {
+        self.text.set_text(text);
+        self.scroll = 0;
+    }
+
+    /// The first line on screen, for coming back to the same place later
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Scrolls to `line`, or as far as the text goes once it's drawn
+    pub fn set_scroll(&mut self, line: usize) {
+        self.scroll = line;
+    }
+
+    /// Scrolls by `lines`, which may be negative, stopping at either end
+    pub fn scroll_by(&mut self, lines: isize) {
+        let max = self.text.line_count().saturating_sub(self.visible_lines);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max);
+    }
+
+    /// Up and down scroll by a line, left and right by a page
+    pub fn handle_input(&mut self, input: &Input) {
+        let page = self.visible_lines.saturating_sub(1).max(1) as isize;
+        for (button, lines) in [
+            (Button::PovUp, -1),
+            (Button::PovDown, 1),
+            (Button::PovLeft, -page),
+            (Button::PovRight, page),
+        ] {
+            if input.repeated(button) {
+                self.scroll_by(lines);
+            }
+        }
+    }
+
+    /// Draws as many lines as fit in the given area
+    pub fn draw(&mut self, frame: &mut Frame, x: usize, y: usize, width: usize, height: usize) {
+        self.text.set_max_width(Some(width));
+        self.visible_lines = (height / self.text.line_height().max(1)).max(1);
+        self.scroll_by(0);
+        self.text
+            .draw_lines(frame, x, y, self.scroll..self.scroll + self.visible_lines);
+
+        // Scrollbar, if there's more than fits
+        let lines = self.text.line_count();
+        if lines > self.visible_lines && height > 0 {
+            let bar = (height * self.visible_lines / lines).max(4);
+            let offset = (height - bar) * self.scroll / (lines - self.visible_lines).max(1);
+            frame.fill_rect(
+                x + width + 4,
+                y + offset,
+                3,
+                bar,
+                LinSrgb::new(120, 120, 120),
+            );
+        }
+    }
+}
@@ -0,0 +1,649 @@
+//! A retained tree of widgets, for tool-like apps that are mostly forms and
+//! lists. Build a [`Ui`] once, change its widgets by id as the app's state
+//! changes, and call [`Ui::update`] each frame to draw it and find out what
+//! the user did. It draws to the frame like anything else, so immediate
+//! drawing calls can go before or after it.
+
+use palette::LinSrgb;
+
+use super::focus::{FocusRing, Region, Strategy};
+use super::meters::{self, Gauge};
+use super::FONT;
+use crate::actions::Action;
+use crate::app::{Button, Frame, Input};
+use crate::config::config;
+use crate::grid::Direction;
+use crate::haptics::{self, Pattern};
+use crate::ninepatch::draw_panel;
+use crate::text_block::TextBlock;
+
+/// Space around a button's text inside its panel
+const BUTTON_PADDING: usize = 8;
+/// How much a slider's track adds under its text
+const SLIDER_HEIGHT: usize = 16;
+const PROGRESS_HEIGHT: usize = 8;
+
+/// What the user did to a [`Ui`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UiEvent {
+    /// Confirm was pressed on a button or list
+    Activated(String),
+    /// Left or right was pressed on a value or slider, as -1 or 1
+    Stepped(String, isize),
+    /// A list's selection moved to this index
+    Selected(String, usize),
+}
+
+enum Kind {
+    Column(Vec<Widget>),
+    Row(Vec<Widget>),
+    Label(TextBlock),
+    Button(TextBlock),
+    /// Drawn as `name: < value >`
+    Value {
+        name: String,
+        value: String,
+        text: TextBlock,
+    },
+    List {
+        items: Vec<String>,
+        selected: usize,
+        /// How many items show at once
+        rows: usize,
+        /// The first item showing
+        scroll: usize,
+        fontsize: f32,
+    },
+    /// Drawn as `name: value` over a track
+    Slider {
+        name: String,
+        text: TextBlock,
+        /// From 0 to 1
+        fraction: f32,
+        width: usize,
+    },
+    Progress {
+        /// From 0 to 1
+        progress: f32,
+        width: usize,
+    },
+    Gauge {
+        gauge: Gauge,
+        radius: usize,
+        value: f32,
+    },
+    Space(usize),
+}
+
+/// A node in a [`Ui`]. Buttons, values, sliders and lists can be focused,
+/// and need ids to tell which one an event is for.
+pub struct Widget {
+    id: Option<String>,
+    /// Overrides the theme's color
+    color: Option<LinSrgb<u8>>,
+    /// Between a container's children
+    spacing: usize,
+    kind: Kind,
+    /// Width and height when last measured, see [`Widget::measure`]
+    size: (usize, usize),
+}
+
+/// Sizes are given as they'd be at the default [UI
+/// scale](crate::config::Config::ui_scale), and scaled when the widget's made
+fn scaled(size: usize) -> usize {
+    config().scaled(size as f32).round() as usize
+}
+
+fn text_block(fontsize: f32, text: &str) -> TextBlock {
+    TextBlock::new(FONT, config().scaled(fontsize), LinSrgb::new(255, 255, 255)).with_text(text)
+}
+
+impl Widget {
+    fn new(kind: Kind) -> Self {
+        Self {
+            id: None,
+            color: None,
+            spacing: scaled(8),
+            kind,
+            size: (0, 0),
+        }
+    }
+
+    /// `children` stacked top to bottom
+    pub fn column(children: Vec<Widget>) -> Self {
+        Self::new(Kind::Column(children))
+    }
+
+    /// `children` side by side, lined up along their tops
+    pub fn row(children: Vec<Widget>) -> Self {
+        Self::new(Kind::Row(children))
+    }
+
+    /// Text in the theme's color for hints and descriptions
+    pub fn label(text: &str) -> Self {
+        Self::new(Kind::Label(text_block(18.0, text)))
+    }
+
+    /// A large heading
+    pub fn title(text: &str) -> Self {
+        Self::new(Kind::Label(text_block(36.0, text))).with_color(config().theme.colors().selected)
+    }
+
+    pub fn button(id: &str, text: &str) -> Self {
+        Self::new(Kind::Button(text_block(18.0, text))).with_id(id)
+    }
+
+    /// A setting changed with left and right, which send
+    /// [`UiEvent::Stepped`]. Change what it shows with [`Widget::set_text`].
+    pub fn value(id: &str, name: &str, value: &str) -> Self {
+        Self::new(Kind::Value {
+            name: name.to_string(),
+            value: value.to_string(),
+            text: text_block(18.0, &format!("{}: < {} >", name, value)),
+        })
+        .with_id(id)
+    }
+
+    /// A setting shown as `name: value` over a `width` pixel track, which
+    /// left and right step like [`Widget::value`]. Move the knob with
+    /// [`Widget::set_value`] and change the text with [`Widget::set_text`].
+    pub fn slider(id: &str, name: &str, width: usize) -> Self {
+        Self::new(Kind::Slider {
+            name: name.to_string(),
+            text: text_block(18.0, name),
+            fraction: 0.0,
+            width,
+        })
+        .with_id(id)
+    }
+
+    /// A bar `width` pixels wide, filled with [`Widget::set_value`]
+    pub fn progress(width: usize) -> Self {
+        Self::new(Kind::Progress {
+            progress: 0.0,
+            width,
+        })
+    }
+
+    /// A dial `radius` pixels in radius, set with [`Widget::set_value`]
+    pub fn gauge(gauge: Gauge, radius: usize) -> Self {
+        let value = gauge.min;
+        Self::new(Kind::Gauge {
+            gauge,
+            radius,
+            value,
+        })
+    }
+
+    /// A list of `items` to pick from, `rows` of them showing at a time
+    pub fn list(id: &str, items: Vec<String>, rows: usize) -> Self {
+        Self::new(Kind::List {
+            items,
+            selected: 0,
+            rows: rows.max(1),
+            scroll: 0,
+            fontsize: config().scaled(18.0),
+        })
+        .with_id(id)
+    }
+
+    /// Empty space `height` pixels tall, or wide in a row
+    pub fn space(height: usize) -> Self {
+        Self::new(Kind::Space(scaled(height)))
+    }
+
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    pub fn with_color(mut self, color: LinSrgb<u8>) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Pixels between a container's children
+    pub fn with_spacing(mut self, spacing: usize) -> Self {
+        self.spacing = scaled(spacing);
+        self
+    }
+
+    pub fn with_fontsize(mut self, fontsize: f32) -> Self {
+        match &mut self.kind {
+            Kind::Label(text)
+            | Kind::Button(text)
+            | Kind::Value { text, .. }
+            | Kind::Slider { text, .. } => {
+                *text = text_block(fontsize, text.text());
+            }
+            Kind::List { fontsize: size, .. } => *size = config().scaled(fontsize),
+            _ => (),
+        }
+        self
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Changes a label or button's text, or what a value shows
+    pub fn set_text(&mut self, new: &str) {
+        match &mut self.kind {
+            Kind::Label(text) | Kind::Button(text) => text.set_text(new),
+            Kind::Value { name, value, text } => {
+                *value = new.to_string();
+                text.set_text(&format!("{}: < {} >", name, value));
+            }
+            Kind::Slider { name, text, .. } => text.set_text(&format!("{}: {}", name, new)),
+            _ => (),
+        }
+    }
+
+    /// Moves a slider's knob or fills a progress bar to `value`, from 0 to
+    /// 1, or sets what a gauge shows
+    pub fn set_value(&mut self, value: f32) {
+        match &mut self.kind {
+            Kind::Slider { fraction, .. } => *fraction = value.clamp(0.0, 1.0),
+            Kind::Progress { progress, .. } => *progress = value.clamp(0.0, 1.0),
+            Kind::Gauge { value: shown, .. } => *shown = value,
+            _ => (),
+        }
+    }
+
+    /// Replaces a list's items, keeping the selection where it can
+    pub fn set_items(&mut self, new: Vec<String>) {
+        if let Kind::List {
+            items, selected, ..
+        } = &mut self.kind
+        {
+            *items = new;
+            *selected = (*selected).min(items.len().saturating_sub(1));
+        }
+    }
+
+    /// The selected item of a list
+    pub fn selected(&self) -> usize {
+        match self.kind {
+            Kind::List { selected, .. } => selected,
+            _ => 0,
+        }
+    }
+
+    pub fn set_selected(&mut self, index: usize) {
+        if let Kind::List {
+            items, selected, ..
+        } = &mut self.kind
+        {
+            *selected = index.min(items.len().saturating_sub(1));
+        }
+    }
+
+    fn is_focusable(&self) -> bool {
+        matches!(
+            self.kind,
+            Kind::Button(_) | Kind::Value { .. } | Kind::Slider { .. } | Kind::List { .. }
+        ) && self.id.is_some()
+    }
+
+    fn children(&self) -> &[Widget] {
+        match &self.kind {
+            Kind::Column(children) | Kind::Row(children) => children,
+            _ => &[],
+        }
+    }
+
+    /// Where every focusable widget in the tree is when it's drawn at `x`,
+    /// `y`, in order
+    fn layout(&self, x: usize, y: usize, regions: &mut Vec<(String, Region)>) {
+        if let Some(id) = self.id().filter(|_| self.is_focusable()) {
+            let (width, height) = self.size;
+            regions.push((id.to_string(), (x, y, width, height)));
+        }
+        let (mut x, mut y) = (x, y);
+        for child in self.children() {
+            child.layout(x, y, regions);
+            let (width, height) = child.size;
+            match self.kind {
+                Kind::Row(_) => x += width + self.spacing,
+                _ => y += height + self.spacing,
+            }
+        }
+    }
+
+    fn find(&self, id: &str) -> Option<&Widget> {
+        if self.id() == Some(id) {
+            return Some(self);
+        }
+        self.children().iter().find_map(|child| child.find(id))
+    }
+
+    fn find_mut(&mut self, id: &str) -> Option<&mut Widget> {
+        if self.id() == Some(id) {
+            return Some(self);
+        }
+        match &mut self.kind {
+            Kind::Column(children) | Kind::Row(children) => {
+                children.iter_mut().find_map(|child| child.find_mut(id))
+            }
+            _ => None,
+        }
+    }
+
+    fn line_height(fontsize: f32) -> usize {
+        (fontsize * 1.25).ceil() as usize
+    }
+
+    /// Width and height in pixels, as of the last time the [`Ui`] was laid
+    /// out
+    pub fn size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    /// Works out the size of every widget in the tree, children first, and
+    /// keeps them for laying out and drawing. Done once a layout, so text is
+    /// only measured once a frame.
+    fn measure(&mut self) -> (usize, usize) {
+        let spacing = self.spacing * self.children().len().saturating_sub(1);
+        self.size = match &mut self.kind {
+            Kind::Column(children) => {
+                let sizes = children.iter_mut().map(Widget::measure);
+                let (width, height) = sizes.fold((0, 0), |(w, h), (cw, ch)| (w.max(cw), h + ch));
+                (width, height + spacing)
+            }
+            Kind::Row(children) => {
+                let sizes = children.iter_mut().map(Widget::measure);
+                let (width, height) = sizes.fold((0, 0), |(w, h), (cw, ch)| (w + cw, h.max(ch)));
+                (width + spacing, height)
+            }
+            Kind::Label(text) | Kind::Value { text, .. } => text.size(),
+            Kind::Button(text) => {
+                let (width, height) = text.size();
+                (width + BUTTON_PADDING * 2, height + BUTTON_PADDING * 2)
+            }
+            Kind::List {
+                items,
+                rows,
+                fontsize,
+                ..
+            } => {
+                let font = crate::app::load_font(FONT);
+                let scale = rusttype::Scale::uniform(*fontsize);
+                let width = items
+                    .iter()
+                    .map(|item| super::text_width(&font, scale, item).ceil() as usize)
+                    .max()
+                    .unwrap_or(0);
+                (width, *rows * Self::line_height(*fontsize))
+            }
+            Kind::Slider { text, width, .. } => {
+                let (text_width, height) = text.size();
+                ((*width).max(text_width), height + SLIDER_HEIGHT)
+            }
+            Kind::Progress { width, .. } => (*width, PROGRESS_HEIGHT),
+            Kind::Gauge { radius, .. } => (*radius * 2, *radius * 2),
+            Kind::Space(size) => (*size, *size),
+        };
+        self.size
+    }
+
+    /// Draws the widget with its top left at `x`, `y`
+    fn draw(&mut self, frame: &mut Frame, x: usize, y: usize, focused: Option<&str>) {
+        let colors = config().theme.colors();
+        let is_focused = self.id.is_some() && self.id.as_deref() == focused;
+        let color = self.color.unwrap_or(match self.kind {
+            Kind::Label(_) => colors.dim,
+            _ if is_focused => colors.selected,
+            _ => colors.text,
+        });
+        let spacing = self.spacing;
+        match &mut self.kind {
+            Kind::Column(children) => {
+                let mut y = y;
+                for child in children {
+                    child.draw(frame, x, y, focused);
+                    y += child.size.1 + spacing;
+                }
+            }
+            Kind::Row(children) => {
+                let mut x = x;
+                for child in children {
+                    child.draw(frame, x, y, focused);
+                    x += child.size.0 + spacing;
+                }
+            }
+            Kind::Label(text) | Kind::Value { text, .. } => {
+                text.set_color(color);
+                text.draw(frame, x, y);
+            }
+            Kind::Button(text) => {
+                let (width, height) = text.size();
+                draw_panel(
+                    frame,
+                    x,
+                    y,
+                    width + BUTTON_PADDING * 2,
+                    height + BUTTON_PADDING * 2,
+                );
+                text.set_color(color);
+                text.draw(frame, x + BUTTON_PADDING, y + BUTTON_PADDING);
+            }
+            Kind::List {
+                items,
+                selected,
+                rows,
+                scroll,
+                fontsize,
+            } => {
+                // Keep the selection on screen
+                *scroll = (*scroll)
+                    .min(*selected)
+                    .max((*selected + 1).saturating_sub(*rows));
+                let line_height = Self::line_height(*fontsize);
+                for (i, item) in items.iter().enumerate().skip(*scroll).take(*rows) {
+                    let item_color = if i == *selected && is_focused {
+                        colors.selected
+                    } else if i == *selected {
+                        colors.dim
+                    } else {
+                        color
+                    };
+                    let baseline = y + (i - *scroll) * line_height + *fontsize as usize;
+                    frame.text(FONT, x, baseline, *fontsize, item_color, item);
+                }
+            }
+            Kind::Slider {
+                text,
+                fraction,
+                width,
+                ..
+            } => {
+                text.set_color(color);
+                text.draw(frame, x, y);
+                let below = y + text.size().1;
+                meters::slider(frame, x, below, *width, *fraction, is_focused);
+            }
+            Kind::Progress { progress, width } => {
+                meters::progress_bar(frame, x, y, *width, PROGRESS_HEIGHT, *progress);
+            }
+            Kind::Gauge {
+                gauge,
+                radius,
+                value,
+            } => gauge.draw(frame, x + *radius, y + *radius, *radius, *value),
+            Kind::Space(_) => (),
+        }
+    }
+}
+
+/// A tree of [`Widget`]s with one of them focused, moved between with the
+/// D-pad to the nearest one in the direction pressed. Up and down move
+/// through a list before leaving it, and left and right change values.
+pub struct Ui {
+    root: Widget,
+    focus: FocusRing,
+}
+
+impl Ui {
+    /// Focuses the first focusable widget in `root`
+    pub fn new(root: Widget) -> Self {
+        let mut ui = Self {
+            root,
+            focus: FocusRing::new(Strategy::Spatial),
+        };
+        // Where things are relative to each other is all that matters until
+        // it's drawn somewhere
+        ui.layout(0, 0);
+        ui
+    }
+
+    fn layout(&mut self, x: usize, y: usize) {
+        self.root.measure();
+        let mut regions = vec![];
+        self.root.layout(x, y, &mut regions);
+        self.focus.set_regions(regions);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Widget> {
+        self.root.find(id)
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Widget> {
+        self.root.find_mut(id)
+    }
+
+    /// Sets the text of the widget with `id`, if there is one
+    pub fn set_text(&mut self, id: &str, text: &str) {
+        if let Some(widget) = self.get_mut(id) {
+            widget.set_text(text);
+        }
+    }
+
+    pub fn focused(&self) -> Option<&str> {
+        self.focus.focused()
+    }
+
+    /// Moves focus to `id`, if it can be focused
+    pub fn focus(&mut self, id: &str) {
+        self.focus.focus(id);
+    }
+
+    /// Width and height in pixels, measured again in case widgets changed
+    pub fn size(&mut self) -> (usize, usize) {
+        self.root.measure()
+    }
+
+    /// Handles the D-pad and confirm, then draws the tree with its top left at
+    /// `x`, `y`
+    pub fn update(
+        &mut self,
+        input: &Input,
+        frame: &mut Frame,
+        x: usize,
+        y: usize,
+    ) -> Option<UiEvent> {
+        let event = self.handle_input(input);
+        self.draw(frame, x, y);
+        event
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame, x: usize, y: usize) {
+        self.layout(x, y);
+        let focused = self.focus.focused().map(str::to_string);
+        self.root.draw(frame, x, y, focused.as_deref());
+    }
+
+    /// Moves focus or changes the focused widget, without drawing, for
+    /// apps that only sometimes show the tree
+    pub fn handle_input(&mut self, input: &Input) -> Option<UiEvent> {
+        let id = self.focus.focused()?.to_string();
+        let widget = self.root.find_mut(&id)?;
+        let direction = [
+            (Button::PovUp, Direction::Up),
+            (Button::PovDown, Direction::Down),
+            (Button::PovLeft, Direction::Left),
+            (Button::PovRight, Direction::Right),
+        ]
+        .into_iter()
+        .find(|&(button, _)| input.repeated(button))
+        .map(|(_, direction)| direction);
+
+        match (&mut widget.kind, direction) {
+            (
+                Kind::List {
+                    items, selected, ..
+                },
+                Some(direction @ (Direction::Up | Direction::Down)),
+            ) => {
+                let moved = selected.saturating_add_signed(direction.offset().1);
+                if moved < items.len() && moved != *selected {
+                    *selected = moved;
+                    haptics::play(&Pattern::TICK);
+                    return Some(UiEvent::Selected(id, moved));
+                }
+            }
+            (
+                Kind::Value { .. } | Kind::Slider { .. },
+                Some(direction @ (Direction::Left | Direction::Right)),
+            ) => {
+                haptics::play(&Pattern::TICK);
+                return Some(UiEvent::Stepped(id, direction.offset().0));
+            }
+            (Kind::Button(_) | Kind::List { .. }, None)
+                if self.focus.actions().just_pressed(input, Action::Confirm) =>
+            {
+                haptics::play(&Pattern::DOUBLE_BUZZ);
+                return Some(UiEvent::Activated(id));
+            }
+            _ => (),
+        }
+        if direction.is_some_and(|direction| self.focus.step(direction)) {
+            haptics::play(&Pattern::TICK);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::surface::Surface;
+
+    #[test]
+    fn test_focus() {
+        let mut ui = Ui::new(Widget::column(vec![
+            Widget::title("Form"),
+            Widget::value("volume", "Volume", "5"),
+            Widget::list("fruit", vec!["Apple".into(), "Pear".into()], 2),
+            Widget::row(vec![
+                Widget::button("ok", "OK"),
+                Widget::button("cancel", "Cancel"),
+            ]),
+        ]));
+        assert_eq!(ui.focused(), Some("volume"));
+
+        let mut surface = Surface::new(320, 240);
+        let mut input = Input::default();
+        let mut press = |ui: &mut Ui, button| {
+            input.event(button, true);
+            let event = ui.update(&input, &mut surface.frame(), 0, 0);
+            input.update();
+            input.event(button, false);
+            input.update();
+            event
+        };
+        let stepped = Some(UiEvent::Stepped("volume".into(), 1));
+        assert_eq!(press(&mut ui, Button::PovRight), stepped);
+        assert_eq!(press(&mut ui, Button::PovDown), None);
+        assert_eq!(ui.focused(), Some("fruit"));
+        let selected = Some(UiEvent::Selected("fruit".into(), 1));
+        assert_eq!(press(&mut ui, Button::PovDown), selected);
+        assert_eq!(ui.get("fruit").unwrap().selected(), 1);
+        // Off the end of the list, then along the row of buttons
+        assert_eq!(press(&mut ui, Button::PovDown), None);
+        assert_eq!(press(&mut ui, Button::PovRight), None);
+        let activated = Some(UiEvent::Activated("cancel".into()));
+        assert_eq!(press(&mut ui, Button::ActionA), activated);
+        assert_eq!(press(&mut ui, Button::PovDown), None);
+        assert_eq!(ui.focused(), Some("cancel"));
+    }
+}
@@ -0,0 +1,893 @@
+//! Bomber: four fighters in an arena of walls and crates, dropping bombs to
+//! clear a way through and to catch each other in the blast. Crates hide
+//! power-ups for more bombs, longer blasts and faster feet. The last one
+//! standing wins the round.
+//!
+//! With a second controller connected the second fighter is a person too,
+//! otherwise everyone but the first is played by the computer. The game runs
+//! in fixed ticks, so it plays the same however fast it's drawn.
+
+use std::collections::VecDeque;
+
+use enum_iterator::{all, Sequence};
+use palette::LinSrgb;
+use rand::{prelude::*, rngs::StdRng};
+
+use rg35xx::anim::FixedStep;
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::grid::{Direction, Grid, Point};
+use rg35xx::particles::Emitter;
+use rg35xx::time::{Duration, Instant};
+
+pub const MANIFEST: Manifest =
+    Manifest::new("0.1.0").description("Blow up crates and each other, for one or two players");
+
+const WIDTH: usize = 15;
+const HEIGHT: usize = 11;
+const TILE_SIZE: usize = 40;
+/// Where the arena starts, under the scores
+const ARENA_TOP: usize = 36;
+const ARENA_LEFT: usize = 20;
+const TICK: Duration = Duration::from_millis(50);
+/// Ticks from a bomb being dropped to it going off
+const FUSE: u32 = 50;
+/// Ticks the flames of a blast last
+const FLAME: u32 = 8;
+/// Ticks between steps at the slowest, and the fewest with every speed-up
+const SLOWEST: u32 = 5;
+const FASTEST: u32 = 2;
+const MAX_BOMBS: usize = 8;
+const MAX_RANGE: usize = 8;
+const CRATE_CHANCE: f64 = 0.75;
+const POWER_UP_CHANCE: f64 = 0.3;
+/// How long the result shows before another round can start
+const RESULT_TIME: Duration = Duration::from_secs(2);
+
+const COLORS: [LinSrgb<u8>; 4] = [
+    LinSrgb::new(240, 240, 240),
+    LinSrgb::new(230, 60, 60),
+    LinSrgb::new(60, 120, 240),
+    LinSrgb::new(240, 200, 40),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Floor,
+    Wall,
+    Crate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
+enum PowerUp {
+    Bomb,
+    Range,
+    Speed,
+}
+
+impl PowerUp {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Bomb => "B",
+            Self::Range => "R",
+            Self::Speed => "S",
+        }
+    }
+
+    fn color(self) -> LinSrgb<u8> {
+        match self {
+            Self::Bomb => LinSrgb::new(90, 90, 220),
+            Self::Range => LinSrgb::new(220, 90, 40),
+            Self::Speed => LinSrgb::new(60, 190, 90),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Control {
+    /// A person on the `n`th controller
+    Player(usize),
+    Computer,
+}
+
+#[derive(Debug, Clone)]
+struct Fighter {
+    control: Control,
+    at: Point,
+    /// Where the last step came from, to draw the move between them
+    from: Point,
+    /// Ticks since the last step
+    since_step: u32,
+    alive: bool,
+    /// How many bombs can be out at once
+    bombs: usize,
+    /// How far blasts reach
+    range: usize,
+    /// Speed-ups picked up
+    speed: u32,
+}
+
+impl Fighter {
+    fn new(control: Control, at: Point) -> Self {
+        Self {
+            control,
+            at,
+            from: at,
+            since_step: SLOWEST,
+            alive: true,
+            bombs: 1,
+            range: 2,
+            speed: 0,
+        }
+    }
+
+    /// Ticks each step takes
+    fn step_ticks(&self) -> u32 {
+        SLOWEST.saturating_sub(self.speed).max(FASTEST)
+    }
+
+    fn can_step(&self) -> bool {
+        self.since_step >= self.step_ticks()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Bomb {
+    at: Point,
+    /// The fighter who dropped it
+    owner: usize,
+    /// Ticks until it goes off
+    fuse: u32,
+    range: usize,
+}
+
+/// What a fighter's doing this tick
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Command {
+    direction: Option<Direction>,
+    bomb: bool,
+}
+
+struct Arena {
+    cells: Grid<Cell>,
+    /// Hidden under crates until they're blown up
+    power_ups: Grid<Option<PowerUp>>,
+    /// Ticks left burning
+    flames: Grid<u32>,
+    bombs: Vec<Bomb>,
+    fighters: Vec<Fighter>,
+}
+
+impl Arena {
+    /// The corners, where the fighters start
+    fn starts() -> [Point; 4] {
+        [
+            Point(1, 1),
+            Point(WIDTH - 2, HEIGHT - 2),
+            Point(WIDTH - 2, 1),
+            Point(1, HEIGHT - 2),
+        ]
+    }
+
+    fn new(controls: [Control; 4], rng: &mut StdRng) -> Self {
+        let starts = Self::starts();
+        let cells = Grid::from_fn(WIDTH, HEIGHT, |Point(x, y)| {
+            let edge = x == 0 || y == 0 || x == WIDTH - 1 || y == HEIGHT - 1;
+            // Room to get clear of the first bomb
+            let near_start = starts
+                .iter()
+                .any(|start| start.0.abs_diff(x) + start.1.abs_diff(y) <= 1);
+            if edge || (x.is_multiple_of(2) && y.is_multiple_of(2)) {
+                Cell::Wall
+            } else if !near_start && rng.gen_bool(CRATE_CHANCE) {
+                Cell::Crate
+            } else {
+                Cell::Floor
+            }
+        });
+        let power_ups = Grid::from_fn(WIDTH, HEIGHT, |point| {
+            let hidden = cells[point] == Cell::Crate && rng.gen_bool(POWER_UP_CHANCE);
+            hidden.then(|| all::<PowerUp>().choose(rng).unwrap())
+        });
+        Self {
+            cells,
+            power_ups,
+            flames: Grid::new(WIDTH, HEIGHT, 0),
+            bombs: Vec::new(),
+            fighters: controls
+                .into_iter()
+                .zip(starts)
+                .map(|(control, at)| Fighter::new(control, at))
+                .collect(),
+        }
+    }
+
+    fn bomb_at(&self, point: Point) -> bool {
+        self.bombs.iter().any(|bomb| bomb.at == point)
+    }
+
+    /// Whether a fighter could step onto `point`
+    fn is_open(&self, point: Point) -> bool {
+        self.cells[point] == Cell::Floor && !self.bomb_at(point)
+    }
+
+    /// The cells a bomb at `center` would set alight. Blasts stop at walls,
+    /// and at the first crate or bomb in each direction.
+    fn blast(&self, center: Point, range: usize) -> Vec<Point> {
+        let mut points = vec![center];
+        for direction in all::<Direction>() {
+            let mut point = center;
+            for _ in 0..range {
+                match point.stepped(direction, WIDTH, HEIGHT) {
+                    Some(next) if self.cells[next] != Cell::Wall => point = next,
+                    _ => break,
+                }
+                points.push(point);
+                if self.cells[point] == Cell::Crate || self.bomb_at(point) {
+                    break;
+                }
+            }
+        }
+        points
+    }
+
+    /// Cells that are alight or will be when the bombs out go off
+    fn danger(&self) -> Grid<bool> {
+        let mut danger = Grid::from_fn(WIDTH, HEIGHT, |point| self.flames[point] > 0);
+        for bomb in &self.bombs {
+            for point in self.blast(bomb.at, bomb.range) {
+                danger[point] = true;
+            }
+        }
+        danger
+    }
+
+    fn drop_bomb(&mut self, index: usize) {
+        let fighter = &self.fighters[index];
+        let out = self.bombs.iter().filter(|bomb| bomb.owner == index).count();
+        if fighter.alive && out < fighter.bombs && !self.bomb_at(fighter.at) {
+            self.bombs.push(Bomb {
+                at: fighter.at,
+                owner: index,
+                fuse: FUSE,
+                range: fighter.range,
+            });
+        }
+    }
+
+    /// Sets off every bomb whose fuse has run out, and any others caught in
+    /// their blasts, returning the cells set alight
+    fn explode(&mut self) -> Vec<Point> {
+        let mut burnt = Vec::new();
+        while let Some(index) = self.bombs.iter().position(|bomb| bomb.fuse == 0) {
+            let bomb = self.bombs.remove(index);
+            for point in self.blast(bomb.at, bomb.range) {
+                self.flames[point] = FLAME;
+                if self.cells[point] == Cell::Crate {
+                    // Showing what was inside, if anything
+                    self.cells[point] = Cell::Floor;
+                } else {
+                    self.power_ups[point] = None;
+                }
+                for other in &mut self.bombs {
+                    if other.at == point {
+                        other.fuse = 0;
+                    }
+                }
+                burnt.push(point);
+            }
+        }
+        burnt
+    }
+
+    /// Runs a tick with each fighter doing as they're told, returning the
+    /// cells set alight
+    fn tick(&mut self, commands: &[Command]) -> Vec<Point> {
+        for (_, flame) in self.flames.iter_mut() {
+            *flame = flame.saturating_sub(1);
+        }
+        for bomb in &mut self.bombs {
+            bomb.fuse = bomb.fuse.saturating_sub(1);
+        }
+
+        for (index, command) in commands.iter().enumerate() {
+            if !self.fighters[index].alive {
+                continue;
+            }
+            if command.bomb {
+                self.drop_bomb(index);
+            }
+            let fighter = &self.fighters[index];
+            let next = command
+                .direction
+                .filter(|_| fighter.can_step())
+                .and_then(|direction| fighter.at.stepped(direction, WIDTH, HEIGHT))
+                .filter(|&next| self.is_open(next));
+            let fighter = &mut self.fighters[index];
+            fighter.since_step = fighter.since_step.saturating_add(1);
+            let Some(next) = next else {
+                continue;
+            };
+            fighter.from = fighter.at;
+            fighter.at = next;
+            fighter.since_step = 0;
+            match self.power_ups[next].take() {
+                Some(PowerUp::Bomb) => fighter.bombs = (fighter.bombs + 1).min(MAX_BOMBS),
+                Some(PowerUp::Range) => fighter.range = (fighter.range + 1).min(MAX_RANGE),
+                Some(PowerUp::Speed) => fighter.speed += 1,
+                None => (),
+            }
+        }
+
+        let burnt = self.explode();
+        for fighter in &mut self.fighters {
+            if self.flames[fighter.at] > 0 {
+                fighter.alive = false;
+            }
+        }
+        burnt
+    }
+
+    /// The first step on the shortest way from `from` to a cell that's
+    /// `wanted`, never through flames and, if `safe`, never through anywhere
+    /// a bomb will reach. `None` if there's no way, or it's there already.
+    fn route(
+        &self,
+        from: Point,
+        danger: &Grid<bool>,
+        safe: bool,
+        wanted: impl Fn(Point) -> bool,
+    ) -> Option<Direction> {
+        let mut first = Grid::new(WIDTH, HEIGHT, None);
+        let mut seen = Grid::new(WIDTH, HEIGHT, false);
+        let mut queue = VecDeque::from([from]);
+        seen[from] = true;
+        while let Some(point) = queue.pop_front() {
+            if wanted(point) {
+                return first[point];
+            }
+            for direction in all::<Direction>() {
+                let Some(next) = point.stepped(direction, WIDTH, HEIGHT) else {
+                    continue;
+                };
+                let passable =
+                    self.is_open(next) && self.flames[next] == 0 && !(safe && danger[next]);
+                if passable && !seen[next] {
+                    seen[next] = true;
+                    first[next] = first[point].or(Some(direction));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Whether a bomb dropped at `point` by the `index`th fighter would
+    /// catch a crate or someone else
+    fn worth_bombing(&self, index: usize, point: Point) -> bool {
+        self.blast(point, self.fighters[index].range)
+            .into_iter()
+            .any(|hit| {
+                self.cells[hit] == Cell::Crate
+                    || self.fighters.iter().enumerate().any(|(other, fighter)| {
+                        other != index && fighter.alive && fighter.at == hit
+                    })
+            })
+    }
+
+    /// What the computer does with the `index`th fighter: get out of the way
+    /// of blasts first, then drop a bomb if it'd hit something and there's
+    /// somewhere to hide from it, then head for power-ups and things to bomb
+    fn think(&self, index: usize, rng: &mut StdRng) -> Command {
+        let me = &self.fighters[index];
+        let danger = self.danger();
+        if danger[me.at] {
+            return Command {
+                direction: self.route(me.at, &danger, false, |point| !danger[point]),
+                bomb: false,
+            };
+        }
+
+        let out = self.bombs.iter().filter(|bomb| bomb.owner == index).count();
+        if out < me.bombs && self.worth_bombing(index, me.at) {
+            let mut after = danger.clone();
+            for point in self.blast(me.at, me.range) {
+                after[point] = true;
+            }
+            if self
+                .route(me.at, &after, false, |point| !after[point])
+                .is_some()
+            {
+                return Command {
+                    direction: None,
+                    bomb: true,
+                };
+            }
+        }
+
+        let target = self.route(me.at, &danger, true, |point| {
+            self.power_ups[point].is_some() && self.cells[point] == Cell::Floor
+                || (out < me.bombs && self.worth_bombing(index, point))
+        });
+        // Wandering a little when there's nothing to do, or it's waiting on
+        // its bombs
+        let direction = target.or_else(|| {
+            let open = all::<Direction>()
+                .filter(|&direction| {
+                    me.at
+                        .stepped(direction, WIDTH, HEIGHT)
+                        .is_some_and(|next| self.is_open(next) && !danger[next])
+                })
+                .collect::<Vec<_>>();
+            open.choose(rng).copied().filter(|_| rng.gen_bool(0.2))
+        });
+        Command {
+            direction,
+            bomb: false,
+        }
+    }
+
+    fn alive(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.fighters.len()).filter(|&index| self.fighters[index].alive)
+    }
+}
+
+enum Phase {
+    Title,
+    Playing,
+    /// The winner, if anyone was left, and when the round ended
+    Over(Option<usize>, Instant),
+}
+
+pub struct BomberApp {
+    arena: Arena,
+    phase: Phase,
+    ticks: FixedStep,
+    /// Moves and bombs asked for since the last tick, so presses between
+    /// ticks aren't lost
+    pending: [Command; 4],
+    /// Rounds won by each fighter this session
+    wins: [usize; 4],
+    explosions: Emitter,
+    rng: StdRng,
+}
+
+impl Default for BomberApp {
+    fn default() -> Self {
+        let mut rng = rg35xx::random::rng("bomber");
+        Self {
+            arena: Arena::new(
+                [
+                    Control::Player(0),
+                    Control::Computer,
+                    Control::Computer,
+                    Control::Computer,
+                ],
+                &mut rng,
+            ),
+            phase: Phase::Title,
+            ticks: FixedStep::new(TICK),
+            pending: [Command::default(); 4],
+            wins: [0; 4],
+            explosions: Emitter::new("bomber", 1500)
+                .with_lifetime(Duration::from_millis(200), Duration::from_millis(600))
+                .with_speed(30.0, 140.0)
+                .with_colors(&[
+                    LinSrgb::new(255, 220, 80),
+                    LinSrgb::new(255, 140, 30),
+                    LinSrgb::new(240, 60, 20),
+                ])
+                .with_fade_to(LinSrgb::new(40, 40, 40))
+                .with_size(3),
+            rng,
+        }
+    }
+}
+
+impl BomberApp {
+    /// Starts a round, with a second person playing if there's a second
+    /// controller
+    fn start(&mut self, input: &Input) {
+        let second = if input.players() > 1 {
+            Control::Player(1)
+        } else {
+            Control::Computer
+        };
+        let controls = [
+            Control::Player(0),
+            second,
+            Control::Computer,
+            Control::Computer,
+        ];
+        self.arena = Arena::new(controls, &mut self.rng);
+        self.pending = [Command::default(); 4];
+        self.ticks.reset();
+        self.explosions.clear();
+        self.phase = Phase::Playing;
+    }
+
+    /// The input a person's fighter follows. With one controller that's all
+    /// input, so a keyboard works too.
+    fn controller(input: &Input, player: usize) -> Option<&Input> {
+        if input.players() > 1 {
+            input.player(player)
+        } else {
+            (player == 0).then_some(input)
+        }
+    }
+
+    fn handle_input(&mut self, input: &Input) {
+        for (index, fighter) in self.arena.fighters.iter().enumerate() {
+            let Control::Player(player) = fighter.control else {
+                continue;
+            };
+            let Some(input) = Self::controller(input, player) else {
+                continue;
+            };
+            let pending = &mut self.pending[index];
+            pending.bomb |=
+                input.just_pressed(Button::ActionA) || input.just_pressed(Button::ActionB);
+            let directions = [
+                (Button::PovUp, Direction::Up),
+                (Button::PovDown, Direction::Down),
+                (Button::PovLeft, Direction::Left),
+                (Button::PovRight, Direction::Right),
+            ];
+            // A tap counts even if it's let go before the next tick
+            if let Some(&(_, direction)) = directions
+                .iter()
+                .find(|(button, _)| input.just_pressed(*button))
+            {
+                pending.direction = Some(direction);
+            } else if let Some(&(_, direction)) =
+                directions.iter().find(|(button, _)| input.pressed(*button))
+            {
+                pending.direction.get_or_insert(direction);
+            }
+        }
+    }
+
+    fn tick(&mut self) {
+        let commands = (0..self.arena.fighters.len())
+            .map(|index| match self.arena.fighters[index].control {
+                Control::Player(_) => self.pending[index],
+                Control::Computer => self.arena.think(index, &mut self.rng),
+            })
+            .collect::<Vec<_>>();
+        // Held directions are read again each tick, taps are kept until
+        // they've had a chance to move
+        for (pending, fighter) in self.pending.iter_mut().zip(&self.arena.fighters) {
+            pending.bomb = false;
+            if fighter.can_step() {
+                pending.direction = None;
+            }
+        }
+
+        for point in self.arena.tick(&commands) {
+            let (x, y) = Self::cell_position(point);
+            let middle = TILE_SIZE as f32 / 2.0;
+            self.explosions
+                .burst(4, x as f32 + middle, y as f32 + middle);
+        }
+
+        let alive = self.arena.alive().collect::<Vec<_>>();
+        let people_left = alive
+            .iter()
+            .any(|&index| matches!(self.arena.fighters[index].control, Control::Player(_)));
+        if alive.len() <= 1 || !people_left {
+            let winner = (alive.len() == 1).then(|| alive[0]);
+            if let Some(winner) = winner {
+                self.wins[winner] += 1;
+            }
+            self.phase = Phase::Over(winner, Instant::now());
+        }
+    }
+
+    fn cell_position(Point(x, y): Point) -> (usize, usize) {
+        (ARENA_LEFT + x * TILE_SIZE, ARENA_TOP + y * TILE_SIZE)
+    }
+
+    fn draw_arena(&self, frame: &mut Frame) {
+        let arena = &self.arena;
+        for (point, cell) in arena.cells.iter() {
+            let (x, y) = Self::cell_position(point);
+            match cell {
+                Cell::Floor => {
+                    frame.fill_rect(x, y, TILE_SIZE, TILE_SIZE, LinSrgb::new(30, 90, 40));
+                    if let Some(power_up) = arena.power_ups[point] {
+                        frame.fill_rect(
+                            x + 6,
+                            y + 6,
+                            TILE_SIZE - 12,
+                            TILE_SIZE - 12,
+                            power_up.color(),
+                        );
+                        frame.text(
+                            "fonts/Ubuntu-B.ttf",
+                            x + 14,
+                            y + 28,
+                            20.0,
+                            LinSrgb::new(255, 255, 255),
+                            power_up.label(),
+                        );
+                    }
+                }
+                Cell::Wall => {
+                    frame.fill_rect(x, y, TILE_SIZE, TILE_SIZE, LinSrgb::new(110, 110, 120));
+                    frame.fill_rect(
+                        x + 3,
+                        y + 3,
+                        TILE_SIZE - 6,
+                        TILE_SIZE - 6,
+                        LinSrgb::new(150, 150, 160),
+                    );
+                }
+                Cell::Crate => {
+                    frame.fill_rect(x, y, TILE_SIZE, TILE_SIZE, LinSrgb::new(120, 70, 30));
+                    frame.fill_rect(
+                        x + 4,
+                        y + 4,
+                        TILE_SIZE - 8,
+                        TILE_SIZE - 8,
+                        LinSrgb::new(170, 110, 50),
+                    );
+                    frame.fill_rect(
+                        x + 4,
+                        y + TILE_SIZE / 2 - 2,
+                        TILE_SIZE - 8,
+                        4,
+                        LinSrgb::new(120, 70, 30),
+                    );
+                }
+            }
+            let flame = arena.flames[point];
+            if flame > 0 {
+                // Shrinking as it dies down
+                let inset = (FLAME - flame) as usize * 2;
+                frame.fill_rect(
+                    x + inset / 2,
+                    y + inset / 2,
+                    TILE_SIZE - inset,
+                    TILE_SIZE - inset,
+                    LinSrgb::new(255, 140, 30),
+                );
+                frame.fill_rect(
+                    x + inset / 2 + 8,
+                    y + inset / 2 + 8,
+                    (TILE_SIZE - inset).saturating_sub(16),
+                    (TILE_SIZE - inset).saturating_sub(16),
+                    LinSrgb::new(255, 230, 120),
+                );
+            }
+        }
+
+        for bomb in &arena.bombs {
+            let (x, y) = Self::cell_position(bomb.at);
+            // Pulsing faster as the fuse burns down
+            let pulse = if bomb.fuse < FUSE / 3 { 2 } else { 4 };
+            let size = if (bomb.fuse / pulse) % 2 == 1 { 26 } else { 22 };
+            let inset = (TILE_SIZE - size) / 2;
+            frame.fill_rect(x + inset, y + inset, size, size, LinSrgb::new(20, 20, 20));
+            frame.fill_rect(
+                x + inset + 4,
+                y + inset + 4,
+                5,
+                5,
+                LinSrgb::new(120, 120, 120),
+            );
+        }
+
+        let along = self.ticks.alpha();
+        for (index, fighter) in arena.fighters.iter().enumerate() {
+            if !fighter.alive {
+                continue;
+            }
+            // Part way from the last cell to this one
+            let moved =
+                ((fighter.since_step as f32 + along) / fighter.step_ticks() as f32).min(1.0);
+            let (from_x, from_y) = Self::cell_position(fighter.from);
+            let (to_x, to_y) = Self::cell_position(fighter.at);
+            let x = from_x as f32 + (to_x as f32 - from_x as f32) * moved;
+            let y = from_y as f32 + (to_y as f32 - from_y as f32) * moved;
+            let (x, y) = (x as usize, y as usize);
+            frame.fill_rect(
+                x + 7,
+                y + 5,
+                TILE_SIZE - 14,
+                TILE_SIZE - 10,
+                LinSrgb::new(0, 0, 0),
+            );
+            frame.fill_rect(x + 9, y + 7, TILE_SIZE - 18, TILE_SIZE - 14, COLORS[index]);
+            frame.fill_rect(x + 13, y + 13, 4, 6, LinSrgb::new(0, 0, 0));
+            frame.fill_rect(x + TILE_SIZE - 17, y + 13, 4, 6, LinSrgb::new(0, 0, 0));
+        }
+    }
+
+    fn draw_status(&self, frame: &mut Frame) {
+        frame.fill_rect(0, 0, frame.width(), ARENA_TOP, LinSrgb::new(0, 0, 0));
+        for (index, fighter) in self.arena.fighters.iter().enumerate() {
+            let x = 20 + index * 155;
+            let name = match fighter.control {
+                Control::Player(player) => format!("P{}", player + 1),
+                Control::Computer => "CPU".to_string(),
+            };
+            let color = if fighter.alive || matches!(self.phase, Phase::Title) {
+                COLORS[index]
+            } else {
+                LinSrgb::new(90, 90, 90)
+            };
+            frame.fill_rect(x, 10, 16, 16, color);
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                x + 24,
+                25,
+                18.0,
+                color,
+                &format!(
+                    "{} {}  {}/{}",
+                    name, self.wins[index], fighter.bombs, fighter.range
+                ),
+            );
+        }
+    }
+
+    fn draw_banner(frame: &mut Frame, lines: &[&str]) {
+        let height = 30 + lines.len() * 28;
+        let top = (frame.height() - height) / 2;
+        frame.fill_rect(60, top, frame.width() - 120, height, LinSrgb::new(0, 0, 0));
+        for (i, line) in lines.iter().enumerate() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                80,
+                top + 38 + i * 28,
+                20.0,
+                LinSrgb::new(255, 255, 255),
+                line,
+            );
+        }
+    }
+}
+
+impl App for BomberApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        let start = input.start_pressed();
+        match self.phase {
+            Phase::Title => {
+                if start || input.just_pressed(Button::ActionA) {
+                    self.start(input);
+                }
+            }
+            Phase::Playing => {
+                self.handle_input(input);
+                for _ in 0..self.ticks.update(input.delta()) {
+                    self.tick();
+                    if !matches!(self.phase, Phase::Playing) {
+                        break;
+                    }
+                }
+            }
+            Phase::Over(_, at) => {
+                if at.elapsed() >= RESULT_TIME && (start || input.just_pressed(Button::ActionA)) {
+                    self.start(input);
+                }
+            }
+        }
+        self.explosions.update(input.delta());
+
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+        self.draw_arena(frame);
+        self.explosions.draw(frame);
+        self.draw_status(frame);
+
+        match self.phase {
+            Phase::Title => {
+                let players = match input.players() {
+                    0 | 1 => "1 player against the computer".to_string(),
+                    n => format!("{} controllers: 2 players", n),
+                };
+                Self::draw_banner(
+                    frame,
+                    &[
+                        &players,
+                        "D-pad to move, A to drop a bomb",
+                        "B more bombs, R longer blasts, S speed",
+                        "A or START to play",
+                    ],
+                );
+            }
+            Phase::Playing => (),
+            Phase::Over(winner, at) => {
+                let result = match winner {
+                    Some(index) => match self.arena.fighters[index].control {
+                        Control::Player(player) => format!("Player {} wins!", player + 1),
+                        Control::Computer => "The computer wins".to_string(),
+                    },
+                    None => "Nobody wins".to_string(),
+                };
+                let again = if at.elapsed() >= RESULT_TIME {
+                    "A or START for another round"
+                } else {
+                    ""
+                };
+                Self::draw_banner(frame, &[&result, again]);
+            }
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        match self.phase {
+            Phase::Playing => Activity::Busy,
+            _ if self.explosions.is_active() => Activity::Busy,
+            Phase::Over(_, at) if at.elapsed() < RESULT_TIME => Activity::Idle(at + RESULT_TIME),
+            Phase::Title | Phase::Over(..) => Activity::Waiting,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_blast() {
+        let mut rng = rg35xx::random::rng("bomber-test");
+        let mut arena = Arena::new([Control::Computer; 4], &mut rng);
+        arena.cells.fill(Cell::Floor);
+        arena.power_ups.fill(None);
+        arena.cells[Point(5, 1)] = Cell::Crate;
+        arena.cells[Point(6, 1)] = Cell::Crate;
+        arena.cells[Point(3, 2)] = Cell::Wall;
+        arena.power_ups[Point(5, 1)] = Some(PowerUp::Range);
+        arena.fighters[1].at = Point(3, 4);
+        arena.fighters[2].at = Point(1, 3);
+
+        // The first bomb sets off the second, which catches the fighter
+        // behind it, but the wall shelters the one below the first
+        arena.fighters[0].at = Point(3, 1);
+        arena.drop_bomb(0);
+        arena.fighters[0].at = Point(1, 1);
+        arena.fighters[0].bombs = 2;
+        arena.drop_bomb(0);
+        arena.bombs[0].fuse = 1;
+        let burnt = arena.tick(&[Command::default(); 4]);
+        assert!(arena.bombs.is_empty());
+        assert!(burnt.contains(&Point(5, 1)) && !burnt.contains(&Point(6, 1)));
+        assert!(!burnt.contains(&Point(3, 2)) && !burnt.contains(&Point(3, 3)));
+        assert_eq!(arena.cells[Point(5, 1)], Cell::Floor);
+        assert_eq!(arena.cells[Point(6, 1)], Cell::Crate);
+        assert_eq!(arena.power_ups[Point(5, 1)], Some(PowerUp::Range));
+        assert_eq!(arena.alive().collect::<Vec<_>>(), [1, 3]);
+
+        // Flames die down, then the power-up can be picked up
+        for _ in 0..FLAME {
+            arena.tick(&[Command::default(); 4]);
+        }
+        let mut commands = [Command::default(); 4];
+        commands[1].direction = Some(Direction::Up);
+        arena.fighters[1].at = Point(5, 2);
+        arena.tick(&commands);
+        assert_eq!(arena.fighters[1].at, Point(5, 1));
+        assert_eq!(arena.fighters[1].range, 3);
+    }
+
+    #[test]
+    fn test_think() {
+        // A computer fighter next to a crate bombs it, then runs for cover
+        let mut rng = rg35xx::random::rng("bomber-test");
+        let mut arena = Arena::new([Control::Computer; 4], &mut rng);
+        let me = arena.fighters[0].at;
+        arena.cells[Point(me.0 + 2, me.1)] = Cell::Crate;
+        arena.cells[Point(me.0, me.1 + 2)] = Cell::Floor;
+        arena.cells[Point(me.0, me.1 + 3)] = Cell::Floor;
+        let mut commands = [Command::default(); 4];
+        commands[0] = arena.think(0, &mut rng);
+        assert!(commands[0].bomb);
+        arena.tick(&commands);
+        for _ in 0..FUSE {
+            commands[0] = arena.think(0, &mut rng);
+            arena.tick(&commands);
+        }
+        assert!(arena.fighters[0].alive);
+        assert_eq!(arena.cells[Point(me.0 + 2, me.1)], Cell::Floor);
+    }
+}
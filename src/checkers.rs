@@ -0,0 +1,869 @@
+//! Checkers, or English draughts, against the computer or a second player
+//! passing the device back and forth. Captures are forced, as are the
+//! jumps after them, and a man reaching the far side is crowned and stops
+//! there. A side left without a move loses, and forty moves each with only
+//! kings moving and nothing taken is a draw.
+//!
+//! The D-pad moves the cursor, A picks a piece up and then puts it down on
+//! each square it lands on in turn, and B puts it back.
+
+use enum_iterator::{all, Sequence};
+use palette::LinSrgb;
+use rand::{rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+
+use rg35xx::achievements::{unlock, Achievement};
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::board::Board;
+use rg35xx::grid::{Grid, Point};
+use rg35xx::profiles::Store;
+use rg35xx::savestate::SaveState;
+use rg35xx::time::{Duration, Instant};
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0")
+    .description("Draughts against the computer or a friend")
+    .achievements(&[
+        Achievement::new("checkers_win", "Crowned", "Beat the computer at Checkers"),
+        Achievement::new(
+            "checkers_hard",
+            "Draughtsman",
+            "Beat the computer at Checkers on Hard",
+        ),
+    ]);
+
+const SIZE: usize = 8;
+const SQUARE: usize = 56;
+const BOARD_LEFT: usize = 16;
+const BOARD_TOP: usize = 16;
+/// Where the turn, counts and help go, right of the board
+const PANEL_LEFT: usize = BOARD_LEFT + SQUARE * SIZE + 20;
+/// Plies with only kings moving and nothing taken before it's a draw
+const QUIET_LIMIT: u32 = 80;
+/// How long the computer waits before moving, so the last move can be seen
+const THINKING_TIME: Duration = Duration::from_millis(500);
+
+const MAN: i32 = 100;
+const KING: i32 = 160;
+/// More than any difference in material, for a side that can't move
+const LOST: i32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Side {
+    /// Moves first, from the bottom of the board
+    Dark,
+    Light,
+}
+
+impl Side {
+    fn other(self) -> Self {
+        match self {
+            Self::Dark => Self::Light,
+            Self::Light => Self::Dark,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Dark => "Red",
+            Self::Light => "White",
+        }
+    }
+
+    /// The row its men are crowned on
+    fn crowning_row(self) -> usize {
+        match self {
+            Self::Dark => 0,
+            Self::Light => SIZE - 1,
+        }
+    }
+
+    fn color(self) -> LinSrgb<u8> {
+        match self {
+            Self::Dark => LinSrgb::new(190, 40, 40),
+            Self::Light => LinSrgb::new(240, 236, 224),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Piece {
+    side: Side,
+    king: bool,
+}
+
+impl Piece {
+    /// The diagonals it can move along
+    fn directions(self) -> &'static [(isize, isize)] {
+        match (self.king, self.side) {
+            (true, _) => &[(-1, -1), (1, -1), (-1, 1), (1, 1)],
+            (false, Side::Dark) => &[(-1, -1), (1, -1)],
+            (false, Side::Light) => &[(-1, 1), (1, 1)],
+        }
+    }
+}
+
+/// A piece's path from where it starts to where it ends up, landing on each
+/// square in between when it jumps more than once
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Move {
+    path: Vec<Point>,
+    captured: Vec<Point>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Position {
+    squares: Grid<Option<Piece>>,
+    turn: Side,
+    /// Plies since a capture or a man moved
+    quiet: u32,
+}
+
+enum Outcome {
+    Won(Side),
+    Drawn,
+}
+
+impl Position {
+    fn new() -> Self {
+        let squares = Grid::from_fn(SIZE, SIZE, |Point(x, y)| {
+            let side = match y {
+                _ if (x + y) % 2 == 0 => None,
+                0..=2 => Some(Side::Light),
+                5.. => Some(Side::Dark),
+                _ => None,
+            };
+            side.map(|side| Piece { side, king: false })
+        });
+        Self {
+            squares,
+            turn: Side::Dark,
+            quiet: 0,
+        }
+    }
+
+    fn pieces(&self, side: Side) -> impl Iterator<Item = (Point, Piece)> + '_ {
+        self.squares
+            .iter()
+            .filter_map(move |(point, piece)| Some((point, (*piece)?)))
+            .filter(move |(_, piece)| piece.side == side)
+    }
+
+    /// Every way `piece`, having got to the end of `path` so far, can carry
+    /// on jumping, added to `moves`
+    fn jumps(
+        &self,
+        piece: Piece,
+        path: &mut Vec<Point>,
+        captured: &mut Vec<Point>,
+        moves: &mut Vec<Move>,
+    ) {
+        let at = *path.last().unwrap();
+        let mut ended = true;
+        for &(dx, dy) in piece.directions() {
+            let Some(over) = at.offset((dx, dy), SIZE, SIZE) else {
+                continue;
+            };
+            let Some(landing) = at.offset((dx * 2, dy * 2), SIZE, SIZE) else {
+                continue;
+            };
+            let jumpable = matches!(self.squares[over], Some(other) if other.side != piece.side)
+                && !captured.contains(&over);
+            // The square it set off from is empty by now
+            let free = self.squares[landing].is_none() || landing == path[0];
+            if !jumpable || !free {
+                continue;
+            }
+            ended = false;
+            path.push(landing);
+            captured.push(over);
+            if !piece.king && landing.1 == piece.side.crowning_row() {
+                moves.push(Move {
+                    path: path.clone(),
+                    captured: captured.clone(),
+                });
+            } else {
+                self.jumps(piece, path, captured, moves);
+            }
+            path.pop();
+            captured.pop();
+        }
+        if ended && path.len() > 1 {
+            moves.push(Move {
+                path: path.clone(),
+                captured: captured.clone(),
+            });
+        }
+    }
+
+    /// The moves the side to play can make, which are only captures if
+    /// there are any
+    fn moves(&self) -> Vec<Move> {
+        let mut moves = vec![];
+        for (from, piece) in self.pieces(self.turn) {
+            self.jumps(piece, &mut vec![from], &mut vec![], &mut moves);
+        }
+        if !moves.is_empty() {
+            return moves;
+        }
+        for (from, piece) in self.pieces(self.turn) {
+            for &offset in piece.directions() {
+                if let Some(to) = from.offset(offset, SIZE, SIZE) {
+                    if self.squares[to].is_none() {
+                        moves.push(Move {
+                            path: vec![from, to],
+                            captured: vec![],
+                        });
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    fn play(&mut self, mv: &Move) {
+        let Some(mut piece) = self.squares[mv.path[0]].take() else {
+            return;
+        };
+        for &point in &mv.captured {
+            self.squares[point] = None;
+        }
+        let to = *mv.path.last().unwrap();
+        let quiet = piece.king && mv.captured.is_empty();
+        piece.king |= to.1 == piece.side.crowning_row();
+        self.squares[to] = Some(piece);
+        self.quiet = if quiet { self.quiet + 1 } else { 0 };
+        self.turn = self.turn.other();
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        if self.moves().is_empty() {
+            Some(Outcome::Won(self.turn.other()))
+        } else if self.quiet >= QUIET_LIMIT {
+            Some(Outcome::Drawn)
+        } else {
+            None
+        }
+    }
+
+    /// How good the position looks for the side to play: material, with men
+    /// worth a little more the closer they are to being crowned
+    fn evaluate(&self) -> i32 {
+        self.squares
+            .iter()
+            .filter_map(|(Point(_, y), piece)| {
+                let piece = (*piece)?;
+                let worth = match piece.king {
+                    true => KING,
+                    false => MAN + 3 * y.abs_diff(SIZE - 1 - piece.side.crowning_row()) as i32,
+                };
+                Some(if piece.side == self.turn {
+                    worth
+                } else {
+                    -worth
+                })
+            })
+            .sum()
+    }
+
+    /// Negamax with alpha-beta pruning. Captures are searched past `depth`
+    /// until things settle down, so a piece about to be taken isn't counted.
+    fn search(&self, depth: u32, mut alpha: i32, beta: i32) -> i32 {
+        let moves = self.moves();
+        if moves.is_empty() {
+            // Losing later is better
+            return -LOST - depth as i32;
+        }
+        if self.quiet >= QUIET_LIMIT {
+            return 0;
+        }
+        if depth == 0 && moves[0].captured.is_empty() {
+            return self.evaluate();
+        }
+        for mv in moves {
+            let mut next = self.clone();
+            next.play(&mv);
+            let score = -next.search(depth.saturating_sub(1), -beta, -alpha);
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Sequence)]
+enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Medium => "Medium",
+            Self::Hard => "Hard",
+        }
+    }
+
+    /// Plies looked ahead, and how much worse than the best a move can look
+    /// and still be picked, to make mistakes and vary its play
+    fn search(self) -> (u32, i32) {
+        match self {
+            Self::Easy => (2, 90),
+            Self::Medium => (4, 15),
+            Self::Hard => (7, 0),
+        }
+    }
+}
+
+/// The computer's move, picked at random among those that look good enough
+fn choose(position: &Position, difficulty: Difficulty, rng: &mut StdRng) -> Option<Move> {
+    let (depth, slack) = difficulty.search();
+    let mut scored = vec![];
+    let mut best = -LOST * 2;
+    for mv in position.moves() {
+        let mut next = position.clone();
+        next.play(&mv);
+        // Anything worse than the slack allows won't be picked, so doesn't
+        // need an exact score
+        let score = -next.search(depth - 1, -LOST * 2, -(best - slack));
+        best = best.max(score);
+        scored.push((mv, score));
+    }
+    let good: Vec<_> = scored
+        .into_iter()
+        .filter(|(_, score)| *score >= best - slack)
+        .map(|(mv, _)| mv)
+        .collect();
+    good.choose(rng).cloned()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Opponent {
+    Computer(Difficulty),
+    /// Another player taking turns on the same device
+    Player,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Game {
+    position: Position,
+    opponent: Opponent,
+    /// The squares the last move went through
+    last: Vec<Point>,
+}
+
+impl SaveState for Game {
+    const NAME: &'static str = "checkers";
+}
+
+impl Game {
+    fn new(opponent: Opponent) -> Self {
+        Self {
+            position: Position::new(),
+            opponent,
+            last: vec![],
+        }
+    }
+
+    fn computer_to_play(&self) -> Option<Difficulty> {
+        match self.opponent {
+            Opponent::Computer(difficulty) if self.position.turn == Side::Light => Some(difficulty),
+            _ => None,
+        }
+    }
+}
+
+/// What can be started from the title screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Continue,
+    New(Opponent),
+}
+
+enum Phase {
+    Title,
+    Playing,
+    Over(Outcome),
+}
+
+pub struct CheckersApp {
+    game: Option<Game>,
+    phase: Phase,
+    /// The highlighted choice on the title screen
+    choice: usize,
+    cursor: Point,
+    /// The squares the piece being moved has been put down on so far,
+    /// starting where it was picked up
+    picked: Vec<Point>,
+    /// When the computer's turn started
+    thinking: Instant,
+    /// Wins and losses against each difficulty
+    stats: Store,
+    rng: StdRng,
+}
+
+impl Default for CheckersApp {
+    fn default() -> Self {
+        Self {
+            game: Game::load(),
+            phase: Phase::Title,
+            choice: 0,
+            cursor: Point(3, 5),
+            picked: vec![],
+            thinking: Instant::now(),
+            stats: Store::open("checkers"),
+            rng: rg35xx::random::rng("checkers"),
+        }
+    }
+}
+
+impl CheckersApp {
+    fn choices(&self) -> Vec<Choice> {
+        self.game
+            .is_some()
+            .then_some(Choice::Continue)
+            .into_iter()
+            .chain(all::<Difficulty>().map(|d| Choice::New(Opponent::Computer(d))))
+            .chain([Choice::New(Opponent::Player)])
+            .collect()
+    }
+
+    fn board(&self) -> Board {
+        let mut board = Board::new(SIZE, SQUARE).at(BOARD_LEFT, BOARD_TOP);
+        // Each player sees their own pieces at the bottom when taking turns
+        board.flipped = matches!(
+            &self.game,
+            Some(game) if game.opponent == Opponent::Player && game.position.turn == Side::Light
+        );
+        board
+    }
+
+    /// The moves that carry on from what's been picked so far
+    fn candidates(&self, game: &Game) -> Vec<Move> {
+        game.position
+            .moves()
+            .into_iter()
+            .filter(|mv| mv.path.starts_with(&self.picked))
+            .collect()
+    }
+
+    fn start(&mut self, choice: Choice) {
+        if let Choice::New(opponent) = choice {
+            self.game = Some(Game::new(opponent));
+            self.cursor = Point(3, 5);
+        }
+        self.picked.clear();
+        self.thinking = Instant::now();
+        self.phase = Phase::Playing;
+    }
+
+    fn play(&mut self, mv: Move) {
+        let Some(game) = &mut self.game else {
+            return;
+        };
+        game.position.play(&mv);
+        game.last = mv.path;
+        self.picked.clear();
+        self.thinking = Instant::now();
+        if let Some(outcome) = game.position.outcome() {
+            self.finished(outcome);
+        }
+    }
+
+    fn finished(&mut self, outcome: Outcome) {
+        Game::clear();
+        if let Some(Game {
+            opponent: Opponent::Computer(difficulty),
+            ..
+        }) = &self.game
+        {
+            let key = match outcome {
+                Outcome::Won(Side::Dark) => {
+                    unlock("checkers_win");
+                    if *difficulty == Difficulty::Hard {
+                        unlock("checkers_hard");
+                    }
+                    "won"
+                }
+                Outcome::Won(Side::Light) => "lost",
+                Outcome::Drawn => "drawn",
+            };
+            self.stats
+                .add(&format!("{}_{}", key, difficulty.name().to_lowercase()), 1);
+            if let Err(e) = self.stats.save() {
+                println!("Couldn't save the Checkers results: {}", e);
+            }
+        }
+        self.phase = Phase::Over(outcome);
+    }
+
+    /// Puts the piece being moved down on `point`, or picks one up there
+    fn select(&mut self, point: Point) {
+        let Some(game) = &self.game else {
+            return;
+        };
+        let turn = game.position.turn;
+        let own = matches!(game.position.squares[point], Some(piece) if piece.side == turn);
+        if own && self.picked.len() <= 1 {
+            self.picked = vec![point];
+            if self.candidates(game).is_empty() {
+                self.picked.clear();
+            }
+            return;
+        }
+        if self.picked.is_empty() {
+            return;
+        }
+        self.picked.push(point);
+        let candidates = self.candidates(game);
+        if candidates.is_empty() {
+            self.picked.pop();
+        } else if let Some(mv) = candidates.into_iter().find(|mv| mv.path == self.picked) {
+            self.play(mv);
+        }
+    }
+
+    fn handle_playing(&mut self, input: &Input, start: bool) {
+        if start {
+            self.picked.clear();
+            self.phase = Phase::Title;
+            self.choice = 0;
+            return;
+        }
+        let Some(game) = &self.game else {
+            return;
+        };
+        if let Some(difficulty) = game.computer_to_play() {
+            if self.thinking.elapsed() >= THINKING_TIME {
+                if let Some(mv) = choose(&game.position, difficulty, &mut self.rng) {
+                    self.play(mv);
+                }
+            }
+            return;
+        }
+
+        // Up the screen is down the board when it's flipped
+        let flip = if self.board().flipped { -1 } else { 1 };
+        let moves = [
+            (Button::PovUp, (0, -flip)),
+            (Button::PovDown, (0, flip)),
+            (Button::PovLeft, (-flip, 0)),
+            (Button::PovRight, (flip, 0)),
+        ];
+        for (button, offset) in moves {
+            if input.repeated(button) {
+                self.cursor = self
+                    .cursor
+                    .offset(offset, SIZE, SIZE)
+                    .unwrap_or(self.cursor);
+            }
+        }
+        if let Some(point) = input
+            .tapped()
+            .and_then(|(x, y)| self.board().square_at(x, y))
+        {
+            self.cursor = point;
+            self.select(point);
+        }
+        if input.just_pressed(Button::ActionA) {
+            self.select(self.cursor);
+        }
+        if input.just_pressed(Button::ActionB) {
+            self.picked.clear();
+        }
+    }
+
+    fn draw_board(&self, frame: &mut Frame, game: &Game) {
+        let board = self.board();
+        board.draw(frame);
+        for &point in &game.last {
+            board.fill(frame, point, LinSrgb::new(150, 120, 60));
+        }
+        let candidates = self.candidates(game);
+        for mv in &candidates {
+            if let Some(&next) = mv.path.get(self.picked.len()) {
+                board.disc(frame, next, 8, LinSrgb::new(90, 200, 90));
+            }
+        }
+
+        for (point, piece) in game.position.squares.iter() {
+            let Some(piece) = piece else {
+                continue;
+            };
+            let lifted = self.picked.first() == Some(&point);
+            let edge = match lifted {
+                true => LinSrgb::new(90, 200, 90),
+                false => LinSrgb::new(30, 30, 30),
+            };
+            board.disc(frame, point, SQUARE / 2 - 5, edge);
+            board.disc(frame, point, SQUARE / 2 - 8, piece.side.color());
+            if piece.king {
+                board.disc(frame, point, SQUARE / 6, LinSrgb::new(230, 180, 30));
+            }
+        }
+        // Where the piece has been put down along the way
+        for &point in self.picked.iter().skip(1) {
+            board.outline(frame, point, 3, LinSrgb::new(90, 200, 90));
+        }
+        if matches!(self.phase, Phase::Playing) && game.computer_to_play().is_none() {
+            board.outline(frame, self.cursor, 4, LinSrgb::new(255, 210, 60));
+        }
+    }
+
+    fn draw_panel(&self, frame: &mut Frame, game: &Game) {
+        let white = LinSrgb::new(255, 255, 255);
+        let grey = LinSrgb::new(150, 150, 150);
+        let title = match game.opponent {
+            Opponent::Computer(difficulty) => difficulty.name(),
+            Opponent::Player => "Two players",
+        };
+        frame.text("fonts/Ubuntu-B.ttf", PANEL_LEFT, 44, 20.0, white, title);
+
+        let turn = game.position.turn;
+        let status = match (&self.phase, game.computer_to_play()) {
+            (Phase::Over(Outcome::Won(side)), _) => format!("{} wins", side.name()),
+            (Phase::Over(Outcome::Drawn), _) => "Drawn".to_string(),
+            (_, Some(_)) => "Thinking...".to_string(),
+            _ => format!("{} to play", turn.name()),
+        };
+        frame.fill_rect(PANEL_LEFT, 62, 20, 20, turn.color());
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            PANEL_LEFT + 28,
+            80,
+            18.0,
+            white,
+            &status,
+        );
+
+        // Pieces left on each side
+        for (i, side) in [Side::Dark, Side::Light].into_iter().enumerate() {
+            let pieces = game.position.pieces(side).count();
+            let kings = game.position.pieces(side).filter(|(_, p)| p.king).count();
+            let text = match kings {
+                0 => format!("{} {}", side.name(), pieces),
+                _ => format!(
+                    "{} {} ({} king{})",
+                    side.name(),
+                    pieces,
+                    kings,
+                    if kings == 1 { "" } else { "s" }
+                ),
+            };
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                PANEL_LEFT,
+                130 + i * 26,
+                18.0,
+                white,
+                &text,
+            );
+        }
+
+        let help = ["A pick up/put down", "B put back", "START menu"];
+        for (i, line) in help.iter().enumerate() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                PANEL_LEFT,
+                380 + i * 22,
+                15.0,
+                grey,
+                line,
+            );
+        }
+    }
+
+    fn draw_title(&self, frame: &mut Frame) {
+        let white = LinSrgb::new(255, 255, 255);
+        frame.text("fonts/Ubuntu-B.ttf", 60, 80, 40.0, white, "Checkers");
+        for (i, choice) in self.choices().into_iter().enumerate() {
+            let y = 140 + i * 44;
+            if i == self.choice {
+                frame.fill_rect(50, y - 30, 540, 40, LinSrgb::new(110, 50, 40));
+            }
+            let (label, detail) = match choice {
+                Choice::Continue => ("Continue".to_string(), None),
+                Choice::New(Opponent::Computer(difficulty)) => {
+                    let name = difficulty.name().to_lowercase();
+                    let count = |key: &str| {
+                        self.stats
+                            .get::<u64>(&format!("{}_{}", key, name))
+                            .unwrap_or(0)
+                    };
+                    let record = (count("won"), count("lost"), count("drawn"));
+                    (
+                        format!("Computer, {}", name),
+                        (record != (0, 0, 0)).then(|| {
+                            format!("won {}, lost {}, drawn {}", record.0, record.1, record.2)
+                        }),
+                    )
+                }
+                Choice::New(Opponent::Player) => ("Two players".to_string(), None),
+            };
+            frame.text("fonts/Ubuntu-B.ttf", 70, y, 26.0, white, &label);
+            if let Some(detail) = detail {
+                let grey = LinSrgb::new(170, 170, 170);
+                frame.text("fonts/Ubuntu-B.ttf", 330, y, 20.0, grey, &detail);
+            }
+        }
+    }
+}
+
+impl App for CheckersApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        let start = input.start_pressed();
+        match self.phase {
+            Phase::Title => {
+                let choices = self.choices();
+                if input.repeated(Button::PovUp) {
+                    self.choice = self.choice.saturating_sub(1);
+                }
+                if input.repeated(Button::PovDown) {
+                    self.choice += 1;
+                }
+                self.choice = self.choice.min(choices.len() - 1);
+                if start || input.just_pressed(Button::ActionA) {
+                    self.start(choices[self.choice]);
+                }
+            }
+            Phase::Playing => self.handle_playing(input, start),
+            Phase::Over(_) => {
+                if start || input.just_pressed(Button::ActionA) {
+                    self.game = None;
+                    self.phase = Phase::Title;
+                    self.choice = 0;
+                }
+            }
+        }
+
+        frame.fill_rect(
+            0,
+            0,
+            frame.width(),
+            frame.height(),
+            LinSrgb::new(44, 34, 30),
+        );
+        match (&self.phase, &self.game) {
+            (Phase::Title, _) | (_, None) => self.draw_title(frame),
+            (_, Some(game)) => {
+                self.draw_board(frame, game);
+                self.draw_panel(frame, game);
+            }
+        }
+        if let Phase::Over(_) = self.phase {
+            let grey = LinSrgb::new(170, 170, 170);
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                PANEL_LEFT,
+                250,
+                16.0,
+                grey,
+                "A to carry on",
+            );
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        match (
+            &self.phase,
+            self.game.as_ref().and_then(Game::computer_to_play),
+        ) {
+            (Phase::Playing, Some(_)) => Activity::Idle(self.thinking + THINKING_TIME),
+            _ => Activity::Waiting,
+        }
+    }
+
+    fn on_exit(&mut self) {
+        if matches!(self.phase, Phase::Over(_)) {
+            return;
+        }
+        if let Some(game) = &self.game {
+            if let Err(e) = game.save() {
+                println!("Couldn't save the Checkers game: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn setup(pieces: &[(Point, Side, bool)], turn: Side) -> Position {
+        let mut position = Position {
+            squares: Grid::new(SIZE, SIZE, None),
+            turn,
+            quiet: 0,
+        };
+        for &(point, side, king) in pieces {
+            position.squares[point] = Some(Piece { side, king });
+        }
+        position
+    }
+
+    #[test]
+    fn test_moves() {
+        let start = Position::new();
+        assert_eq!(start.pieces(Side::Dark).count(), 12);
+        assert_eq!(start.moves().len(), 7);
+
+        // A capture has to be taken, and carries on as far as it can
+        let mut position = setup(
+            &[
+                (Point(0, 7), Side::Dark, false),
+                (Point(6, 7), Side::Dark, false),
+                (Point(1, 6), Side::Light, false),
+                (Point(3, 4), Side::Light, false),
+            ],
+            Side::Dark,
+        );
+        let moves = position.moves();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].path, [Point(0, 7), Point(2, 5), Point(4, 3)]);
+        position.play(&moves[0]);
+        assert_eq!(position.pieces(Side::Light).count(), 0);
+        assert!(matches!(position.outcome(), Some(Outcome::Won(Side::Dark))));
+
+        // Crowning ends the move, even with another jump for a king
+        let mut position = setup(
+            &[
+                (Point(5, 2), Side::Dark, false),
+                (Point(4, 1), Side::Light, false),
+                (Point(2, 1), Side::Light, false),
+                (Point(7, 0), Side::Light, false),
+            ],
+            Side::Dark,
+        );
+        let moves = position.moves();
+        assert_eq!(moves.len(), 1);
+        position.play(&moves[0]);
+        let king = Piece {
+            side: Side::Dark,
+            king: true,
+        };
+        assert_eq!(position.squares[Point(3, 0)], Some(king));
+        assert!(position.squares[Point(2, 1)].is_some());
+    }
+
+    #[test]
+    fn test_choose() {
+        // Moving to the right gets the man taken
+        let position = setup(
+            &[
+                (Point(2, 3), Side::Light, false),
+                (Point(4, 5), Side::Dark, false),
+            ],
+            Side::Light,
+        );
+        let mut rng = rg35xx::random::rng("checkers-test");
+        for difficulty in [Difficulty::Medium, Difficulty::Hard] {
+            let mv = choose(&position, difficulty, &mut rng).unwrap();
+            assert_eq!(mv.path, [Point(2, 3), Point(1, 4)]);
+        }
+    }
+}
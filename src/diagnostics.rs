@@ -0,0 +1,260 @@
+//! Checks the controls: the device drawn with each button lighting up while
+//! it's held, how often each has been pressed, the combinations held
+//! together, the sticks and triggers against the calibrated dead zone, and
+//! rumble patterns to feel. SELECT with A, B, X or Y plays the patterns.
+
+use enum_iterator::all;
+use enum_map::EnumMap;
+use palette::LinSrgb;
+
+use rg35xx::actions::button_label;
+use rg35xx::app::{App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::axes::{calibration, Axis};
+use rg35xx::haptics::{self, Pattern};
+use rg35xx::widgets::progress_bar;
+
+pub const MANIFEST: Manifest =
+    Manifest::new("0.1.0").description("Test buttons, sticks and rumble");
+
+const FONT: &str = "fonts/Ubuntu-B.ttf";
+/// How many recent chords are listed
+const CHORDS_SHOWN: usize = 4;
+/// Each stick's box, with -1 to 1 across it
+const STICK_SIZE: usize = 100;
+
+const BODY: LinSrgb<u8> = LinSrgb::new(70, 70, 80);
+const SCREEN: LinSrgb<u8> = LinSrgb::new(15, 15, 20);
+const RELEASED: LinSrgb<u8> = LinSrgb::new(40, 40, 45);
+const HELD: LinSrgb<u8> = LinSrgb::new(255, 200, 0);
+const TEXT: LinSrgb<u8> = LinSrgb::new(255, 255, 255);
+const DIM: LinSrgb<u8> = LinSrgb::new(150, 150, 150);
+const DEADZONE: LinSrgb<u8> = LinSrgb::new(200, 60, 60);
+
+/// Launcher-wide chords, named when they're held
+const KNOWN_CHORDS: [(&[Button], &str); 2] = [
+    (&[Button::MenuL, Button::MenuR], "leaves the app"),
+    (&[Button::MenuL, Button::BumperR], "debug overlay"),
+];
+
+/// Played while SELECT is held
+const RUMBLE_TESTS: [(Button, &str, Pattern); 4] = [
+    (Button::ActionA, "tick", Pattern::TICK),
+    (Button::ActionB, "double buzz", Pattern::DOUBLE_BUZZ),
+    (Button::ActionV, "heartbeat", Pattern::HEARTBEAT),
+    (Button::ActionH, "failure", Pattern::FAILURE),
+];
+
+/// A button on the drawing of the device
+enum Shape {
+    /// Left, top, width and height
+    Rect(usize, usize, usize, usize),
+    /// Center and radius
+    Round(usize, usize, usize),
+}
+
+/// Where each button is on the drawing, like the device with its screen
+/// between the D-pad and the face buttons, and where its label goes
+fn shape(button: Button) -> (Shape, (usize, usize)) {
+    match button {
+        Button::BumperL => (Shape::Rect(60, 42, 100, 16), (60, 74)),
+        Button::BumperR => (Shape::Rect(480, 42, 100, 16), (480, 74)),
+        Button::PovUp => (Shape::Rect(105, 140, 30, 35), (100, 134)),
+        Button::PovDown => (Shape::Rect(105, 205, 30, 35), (100, 254)),
+        Button::PovLeft => (Shape::Rect(70, 175, 35, 30), (50, 222)),
+        Button::PovRight => (Shape::Rect(135, 175, 35, 30), (140, 222)),
+        Button::MenuL => (Shape::Rect(250, 285, 50, 14), (250, 312)),
+        Button::MenuR => (Shape::Rect(340, 285, 50, 14), (340, 312)),
+        Button::ActionV => (Shape::Round(520, 150, 18), (502, 181)),
+        Button::ActionB => (Shape::Round(520, 230, 18), (502, 261)),
+        Button::ActionH => (Shape::Round(480, 190, 18), (462, 221)),
+        Button::ActionA => (Shape::Round(560, 190, 18), (542, 221)),
+    }
+}
+
+fn fill_disc(frame: &mut Frame, cx: usize, cy: usize, radius: usize, color: LinSrgb<u8>) {
+    let r = radius as isize;
+    for dy in -r..=r {
+        let half = ((r * r - dy * dy) as f32).sqrt() as usize;
+        let y = cy.saturating_add_signed(dy);
+        frame.fill_rect(cx.saturating_sub(half), y, half * 2 + 1, 1, color);
+    }
+}
+
+fn circle(frame: &mut Frame, (cx, cy): (f32, f32), radius: f32, color: LinSrgb<u8>) {
+    let points = (0..32)
+        .map(|i| {
+            let angle = i as f32 / 32.0 * std::f32::consts::TAU;
+            (cx + radius * angle.cos(), cy + radius * angle.sin())
+        })
+        .collect::<Vec<_>>();
+    frame.polygon(&points, color);
+}
+
+/// The buttons held together, as labelled on the device
+fn chord_name(buttons: &[Button]) -> String {
+    buttons
+        .iter()
+        .map(|&button| button_label(button))
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+#[derive(Default)]
+pub struct DiagnosticsApp {
+    presses: EnumMap<Button, u32>,
+    /// Buttons held together, in the order of [`Button`], the latest first
+    recent_chords: Vec<Vec<Button>>,
+}
+
+impl DiagnosticsApp {
+    fn handle_input(&mut self, input: &Input) {
+        for button in all::<Button>().filter(|&button| input.just_pressed(button)) {
+            self.presses[button] += 1;
+        }
+        let held = all::<Button>()
+            .filter(|&button| input.pressed(button))
+            .collect::<Vec<_>>();
+        let pressed_any = all::<Button>().any(|button| input.just_pressed(button));
+        if pressed_any && held.len() >= 2 && self.recent_chords.first() != Some(&held) {
+            self.recent_chords.retain(|chord| *chord != held);
+            self.recent_chords.insert(0, held);
+            self.recent_chords.truncate(CHORDS_SHOWN);
+        }
+
+        if input.pressed(Button::MenuL) {
+            for (button, _, pattern) in &RUMBLE_TESTS {
+                if input.just_pressed(*button) {
+                    haptics::play(pattern);
+                }
+            }
+        }
+    }
+
+    fn draw_device(&self, input: &Input, frame: &mut Frame) {
+        frame.fill_rect(40, 60, 560, 260, BODY);
+        frame.fill_rect(200, 80, 240, 180, SCREEN);
+        // The middle of the D-pad, between its arms
+        frame.fill_rect(105, 175, 30, 30, RELEASED);
+        for button in all::<Button>() {
+            let color = if input.pressed(button) {
+                HELD
+            } else {
+                RELEASED
+            };
+            let (shape, (label_x, label_y)) = shape(button);
+            match shape {
+                Shape::Rect(x, y, width, height) => frame.fill_rect(x, y, width, height, color),
+                Shape::Round(x, y, radius) => fill_disc(frame, x, y, radius, color),
+            }
+            let text = format!("{} {}", button_label(button), self.presses[button]);
+            frame.text(FONT, label_x, label_y, 12.0, TEXT, &text);
+        }
+    }
+
+    /// On the drawing's screen: how many controllers, what's held together
+    /// and the recent chords
+    fn draw_screen(&self, input: &Input, frame: &mut Frame) {
+        let controllers = match input.players() {
+            0 | 1 => "1 controller".to_string(),
+            players => format!("{} controllers", players),
+        };
+        frame.text(FONT, 210, 100, 14.0, DIM, &controllers);
+        frame.text(FONT, 210, 122, 16.0, TEXT, "Chords");
+        for (i, chord) in self.recent_chords.iter().enumerate() {
+            let mut text = chord_name(chord);
+            if let Some((_, meaning)) = KNOWN_CHORDS.iter().find(|(buttons, _)| buttons == chord) {
+                text += &format!(" ({})", meaning);
+            }
+            let color = if i == 0 { HELD } else { DIM };
+            frame.text(FONT, 210, 144 + i * 20, 14.0, color, &text);
+        }
+        if self.recent_chords.is_empty() {
+            frame.text(FONT, 210, 144, 14.0, DIM, "Hold two buttons at once");
+        }
+    }
+
+    /// A stick's box: the raw reading in grey and the calibrated one in
+    /// yellow, with the dead zone in the middle
+    fn draw_stick(&self, input: &Input, frame: &mut Frame, x: usize, (ax, ay): (Axis, Axis)) {
+        let y = 350;
+        frame.fill_rect(x, y, STICK_SIZE, STICK_SIZE, SCREEN);
+        let half = STICK_SIZE as f32 / 2.0;
+        let center = (x as f32 + half, y as f32 + half);
+        circle(frame, center, half - 1.0, DIM);
+        circle(frame, center, calibration().deadzone * half, DEADZONE);
+        let point = |(vx, vy): (f32, f32)| {
+            (
+                (center.0 + vx.clamp(-1.0, 1.0) * (half - 4.0)) as usize,
+                (center.1 + vy.clamp(-1.0, 1.0) * (half - 4.0)) as usize,
+            )
+        };
+        let (rx, ry) = point((input.raw_axis(ax), input.raw_axis(ay)));
+        fill_disc(frame, rx, ry, 3, DIM);
+        let (cx, cy) = point((input.axis(ax), input.axis(ay)));
+        fill_disc(frame, cx, cy, 3, HELD);
+        let text = format!("{:+.2} {:+.2}", input.axis(ax), input.axis(ay));
+        frame.text(FONT, x, y + STICK_SIZE + 16, 12.0, TEXT, &text);
+    }
+}
+
+impl App for DiagnosticsApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        self.handle_input(input);
+
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+        frame.text(FONT, 20, 30, 20.0, TEXT, "Controller test");
+        self.draw_device(input, frame);
+        self.draw_screen(input, frame);
+
+        frame.text(FONT, 40, 340, 14.0, DIM, "Sticks");
+        self.draw_stick(input, frame, 40, (Axis::LeftX, Axis::LeftY));
+        self.draw_stick(input, frame, 160, (Axis::RightX, Axis::RightY));
+        frame.text(FONT, 290, 340, 14.0, DIM, "Triggers");
+        for (i, axis) in [Axis::TriggerL, Axis::TriggerR].into_iter().enumerate() {
+            let y = 355 + i * 30;
+            progress_bar(frame, 290, y, 120, 12, input.axis(axis));
+            let text = format!("{} {:.2}", axis.name(), input.raw_axis(axis));
+            frame.text(FONT, 420, y + 11, 12.0, TEXT, &text);
+        }
+
+        frame.text(FONT, 290, 430, 14.0, DIM, "Rumble: hold SELECT and press");
+        let tests = RUMBLE_TESTS
+            .iter()
+            .map(|(button, name, _)| format!("{} {}", button_label(*button), name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        frame.text(FONT, 290, 450, 12.0, DIM, &tests);
+        frame.text(FONT, 290, 470, 12.0, DIM, "SELECT+START to leave");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics() {
+        let mut app = DiagnosticsApp::default();
+        let mut input = Input::default();
+        let mut press = |app: &mut DiagnosticsApp, button, pressed| {
+            input.event(button, pressed);
+            app.handle_input(&input);
+            input.update();
+        };
+        press(&mut app, Button::ActionA, true);
+        press(&mut app, Button::ActionA, false);
+        press(&mut app, Button::ActionA, true);
+        assert_eq!(app.presses[Button::ActionA], 2);
+        assert!(app.recent_chords.is_empty());
+
+        // A then B held together, and again the other way round
+        press(&mut app, Button::ActionB, true);
+        press(&mut app, Button::ActionA, false);
+        press(&mut app, Button::ActionA, true);
+        assert_eq!(app.recent_chords, [vec![Button::ActionB, Button::ActionA]]);
+        press(&mut app, Button::BumperL, true);
+        assert_eq!(chord_name(&app.recent_chords[0]), "L1 + B + A");
+        assert_eq!(app.recent_chords.len(), 2);
+    }
+}
@@ -0,0 +1,325 @@
+//! Test patterns for checking the panel: solid colors, gradients to show
+//! banding from the RGB565 conversion, a grid for geometry and rotation,
+//! a cycle of colors for spotting dead or stuck pixels, greys against a
+//! dither for the gamma setting, and moving bars for tearing and dropped
+//! frames from the double-buffer flip.
+//!
+//! LEFT and RIGHT change pattern and UP and DOWN the solid color. The
+//! pattern's name shows for a moment after any press.
+
+use enum_iterator::{first, last, Sequence};
+use palette::LinSrgb;
+
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest, PowerHint};
+use rg35xx::time::{Duration, Instant};
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0").description("Test patterns for the screen");
+
+const FONT: &str = "fonts/Ubuntu-B.ttf";
+/// How long a pattern's name shows after changing to it
+const LABEL_TIME: Duration = Duration::from_secs(2);
+/// How long each color shows in the dead pixel cycle
+const CYCLE_TIME: Duration = Duration::from_secs(1);
+/// How long the motion test's bar takes to cross the screen
+const SWEEP_TIME: Duration = Duration::from_secs(2);
+
+const BLACK: LinSrgb<u8> = LinSrgb::new(0, 0, 0);
+const WHITE: LinSrgb<u8> = LinSrgb::new(255, 255, 255);
+
+/// The solid colors, and the dead pixel cycle's
+const COLORS: [(&str, LinSrgb<u8>); 8] = [
+    ("White", WHITE),
+    ("Black", BLACK),
+    ("Red", LinSrgb::new(255, 0, 0)),
+    ("Green", LinSrgb::new(0, 255, 0)),
+    ("Blue", LinSrgb::new(0, 0, 255)),
+    ("Cyan", LinSrgb::new(0, 255, 255)),
+    ("Magenta", LinSrgb::new(255, 0, 255)),
+    ("Yellow", LinSrgb::new(255, 255, 0)),
+];
+
+/// Display gammas the gamma pattern has a grey for
+const GAMMAS: [f32; 5] = [1.8, 2.0, 2.2, 2.4, 2.6];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
+enum TestPattern {
+    Solid,
+    Gradients,
+    Grid,
+    DeadPixels,
+    Gamma,
+    Motion,
+}
+
+impl TestPattern {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Solid => "Solid color",
+            Self::Gradients => "Gradients",
+            Self::Grid => "Grid",
+            Self::DeadPixels => "Dead pixels",
+            Self::Gamma => "Gamma",
+            Self::Motion => "Motion",
+        }
+    }
+}
+
+/// The grey that looks as bright as a dither of black and white on a
+/// display with `gamma`
+fn gamma_grey(gamma: f32) -> u8 {
+    (255.0 * 0.5f32.powf(1.0 / gamma)).round() as u8
+}
+
+pub struct DisplayTestApp {
+    pattern: TestPattern,
+    /// Which of [`COLORS`] the solid pattern shows
+    color: usize,
+    /// How long the pattern's been showing
+    elapsed: Duration,
+    /// Updates since the pattern changed, for the motion test
+    frames: u64,
+    /// When to stop showing the pattern's name
+    label_until: Option<Instant>,
+}
+
+impl Default for DisplayTestApp {
+    fn default() -> Self {
+        Self {
+            pattern: TestPattern::Solid,
+            color: 0,
+            elapsed: Duration::ZERO,
+            frames: 0,
+            label_until: Some(Instant::now() + LABEL_TIME),
+        }
+    }
+}
+
+impl DisplayTestApp {
+    fn handle_input(&mut self, input: &Input) {
+        let pattern = self.pattern;
+        if input.repeated(Button::PovRight) {
+            self.pattern = pattern.next().unwrap_or(first::<TestPattern>().unwrap());
+        }
+        if input.repeated(Button::PovLeft) {
+            self.pattern = pattern.previous().unwrap_or(last::<TestPattern>().unwrap());
+        }
+        if self.pattern == TestPattern::Solid {
+            if input.repeated(Button::PovDown) {
+                self.color = (self.color + 1) % COLORS.len();
+            }
+            if input.repeated(Button::PovUp) {
+                self.color = (self.color + COLORS.len() - 1) % COLORS.len();
+            }
+        }
+        if self.pattern != pattern {
+            self.elapsed = Duration::ZERO;
+            self.frames = 0;
+        }
+        if self.pattern != pattern || input.any_changed() {
+            self.label_until = Some(Instant::now() + LABEL_TIME);
+        }
+    }
+
+    fn label(&self) -> String {
+        match self.pattern {
+            TestPattern::Solid => format!("{}: {}", self.pattern.name(), COLORS[self.color].0),
+            pattern => pattern.name().to_string(),
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let (width, height) = (frame.width(), frame.height());
+        match self.pattern {
+            TestPattern::Solid => frame.fill_rect(0, 0, width, height, COLORS[self.color].1),
+            TestPattern::Gradients => draw_gradients(frame),
+            TestPattern::Grid => draw_grid(frame),
+            TestPattern::DeadPixels => {
+                let step = self.elapsed.as_millis() / CYCLE_TIME.as_millis();
+                let (_, color) = COLORS[step as usize % COLORS.len()];
+                frame.fill_rect(0, 0, width, height, color);
+            }
+            TestPattern::Gamma => draw_gamma(frame),
+            TestPattern::Motion => self.draw_motion(frame),
+        }
+    }
+
+    /// A bar sweeping across, which breaks up where a frame tears, and a
+    /// square changing color every frame, which flickers evenly unless
+    /// frames are dropped or shown twice
+    fn draw_motion(&self, frame: &mut Frame) {
+        let (width, height) = (frame.width(), frame.height());
+        frame.fill_rect(0, 0, width, height, BLACK);
+        let sweep = self.elapsed.as_secs_f32() / SWEEP_TIME.as_secs_f32();
+        let x = (sweep.fract() * width as f32) as usize;
+        frame.fill_rect(x, 0, 16, height, WHITE);
+        // Stripes moving a pixel a frame, for judging smoothness
+        let offset = self.frames as usize % 32;
+        frame.fill_rect(0, height - 40, offset.saturating_sub(16), 40, COLORS[2].1);
+        for x in (offset..width).step_by(32) {
+            frame.fill_rect(x, height - 40, 16, 40, COLORS[2].1);
+        }
+        let flicker = match self.frames % 2 {
+            0 => WHITE,
+            _ => BLACK,
+        };
+        // Below the pattern's name
+        frame.fill_rect(20, 60, 40, 40, LinSrgb::new(80, 80, 80));
+        frame.fill_rect(24, 64, 32, 32, flicker);
+        frame.text(FONT, 70, 88, 16.0, WHITE, &format!("frame {}", self.frames));
+    }
+}
+
+/// Red, green, blue and grey ramps from black, in bands down the screen
+fn draw_gradients(frame: &mut Frame) {
+    let (width, height) = (frame.width(), frame.height());
+    let band = height / 4;
+    for x in 0..width {
+        let level = (x * 255 / width.saturating_sub(1).max(1)) as u8;
+        let ramps = [
+            LinSrgb::new(level, 0, 0),
+            LinSrgb::new(0, level, 0),
+            LinSrgb::new(0, 0, level),
+            LinSrgb::new(level, level, level),
+        ];
+        for (i, color) in ramps.into_iter().enumerate() {
+            frame.fill_rect(x, i * band, 1, band, color);
+        }
+    }
+}
+
+/// Lines every 32 pixels, with the border and middle marked, for checking
+/// the picture's the right size, the right way round and not stretched
+fn draw_grid(frame: &mut Frame) {
+    let (width, height) = (frame.width(), frame.height());
+    frame.fill_rect(0, 0, width, height, BLACK);
+    let line = LinSrgb::new(120, 120, 120);
+    for x in (0..width).step_by(32) {
+        frame.fill_rect(x, 0, 1, height, line);
+    }
+    for y in (0..height).step_by(32) {
+        frame.fill_rect(0, y, width, 1, line);
+    }
+    let edge = COLORS[2].1;
+    frame.fill_rect(0, 0, width, 1, edge);
+    frame.fill_rect(0, height - 1, width, 1, edge);
+    frame.fill_rect(0, 0, 1, height, edge);
+    frame.fill_rect(width - 1, 0, 1, height, edge);
+    frame.fill_rect(width / 2, 0, 1, height, WHITE);
+    frame.fill_rect(0, height / 2, width, 1, WHITE);
+    // The top left's marked, so a flip or rotation shows
+    frame.text(FONT, 6, 24, 18.0, WHITE, "TOP LEFT");
+}
+
+/// A dither of black and white, with greys across it that match it at
+/// different gammas. The one that blends in is the panel's.
+fn draw_gamma(frame: &mut Frame) {
+    let (width, height) = (frame.width(), frame.height());
+    for y in 0..height {
+        for x in 0..width {
+            let color = if (x + y).is_multiple_of(2) {
+                WHITE
+            } else {
+                BLACK
+            };
+            frame.put_pixel(x, y, color);
+        }
+    }
+    let patch = width / (GAMMAS.len() * 2);
+    for (i, gamma) in GAMMAS.into_iter().enumerate() {
+        let grey = gamma_grey(gamma);
+        let x = patch / 2 + i * patch * 2;
+        frame.fill_rect(
+            x,
+            height / 3,
+            patch,
+            height / 3,
+            LinSrgb::new(grey, grey, grey),
+        );
+        frame.fill_rect(x, height * 2 / 3 + 10, patch, 28, BLACK);
+        frame.text(
+            FONT,
+            x + 4,
+            height * 2 / 3 + 30,
+            16.0,
+            WHITE,
+            &format!("{:.1}", gamma),
+        );
+    }
+}
+
+impl App for DisplayTestApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        self.handle_input(input);
+        self.elapsed += input.delta();
+        self.frames += 1;
+
+        self.draw(frame);
+        if self.label_until.is_some_and(|until| Instant::now() < until) {
+            frame.fill_rect(0, 0, frame.width(), 40, BLACK);
+            frame.text(FONT, 12, 28, 20.0, WHITE, &self.label());
+            let hint = "LEFT/RIGHT: pattern";
+            frame.text(
+                FONT,
+                frame.width().saturating_sub(170),
+                28,
+                14.0,
+                WHITE,
+                hint,
+            );
+        } else {
+            self.label_until = None;
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        let label = self.label_until.map_or(Activity::Waiting, Activity::Idle);
+        match self.pattern {
+            TestPattern::Motion => Activity::Busy,
+            TestPattern::DeadPixels => {
+                let step =
+                    CYCLE_TIME.as_millis() - self.elapsed.as_millis() % CYCLE_TIME.as_millis();
+                let next = Instant::now() + Duration::from_millis(step as u64);
+                label.wake_by(Some(next))
+            }
+            _ => label,
+        }
+    }
+
+    fn power_hint(&self) -> PowerHint {
+        match self.pattern {
+            TestPattern::Motion => PowerHint::Performance,
+            _ => PowerHint::Default,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use enum_iterator::all;
+
+    #[test]
+    fn test_display_test() {
+        assert_eq!(gamma_grey(2.2), 186);
+        assert_eq!(gamma_grey(1.0), 128);
+        assert_eq!(all::<TestPattern>().count(), 6);
+
+        // Round the patterns both ways
+        let mut app = DisplayTestApp::default();
+        let mut input = Input::default();
+        input.event(Button::PovLeft, true);
+        app.handle_input(&input);
+        assert_eq!(app.pattern, TestPattern::Motion);
+        input.update();
+        input.event(Button::PovLeft, false);
+        input.event(Button::PovRight, true);
+        app.handle_input(&input);
+        assert_eq!(app.pattern, TestPattern::Solid);
+        input.update();
+        input.event(Button::PovRight, false);
+        input.event(Button::PovUp, true);
+        app.handle_input(&input);
+        assert_eq!(app.label(), "Solid color: Yellow");
+    }
+}
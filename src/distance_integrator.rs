@@ -1,9 +1,22 @@
-use std::{collections::HashMap, ops::Div, time::Instant};
+use std::{collections::HashMap, ops::Div};
 
+use enum_iterator::{all, next_cycle};
 use palette::LinSrgb;
 use rand::prelude::*;
 
-use crate::app::{App, Button};
+use rg35xx::app::{App, AppContext, Button, Manifest, PowerHint};
+use rg35xx::config::{config, set_config, Aircraft};
+#[cfg(not(target_arch = "wasm32"))]
+use rg35xx::gps::{Gps, GpsSource};
+use rg35xx::label::Label;
+#[cfg(not(target_arch = "wasm32"))]
+use rg35xx::storage::{CsvWriter, GpxWriter};
+use rg35xx::time::{Duration, Instant};
+use rg35xx::units::{AltitudeUnit, SpeedUnit, Units};
+use rg35xx::widgets::{Gauge, Plot};
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0")
+    .description("Integrates groundspeed over time, with wind and altitude correction");
 
 struct ButtonHoldIncrementer {
     last_update: Instant,
@@ -30,6 +43,11 @@ impl ButtonHoldIncrementer {
     fn update(&mut self, pressed: bool, positive: bool, value: &mut u32) {
         let elapsed = self.last_update.elapsed().as_secs_f64();
         self.last_update = Instant::now();
+        self.advance(elapsed, pressed, positive, value);
+    }
+
+    /// [`update`](Self::update) with `elapsed` seconds since the last one
+    fn advance(&mut self, elapsed: f64, pressed: bool, positive: bool, value: &mut u32) {
         if pressed {
             self.pressed_time += elapsed;
             if !self.previously_pressed {
@@ -74,68 +92,85 @@ impl ButtonHoldIncrementer {
     }
 }
 
-enum Unit {
-    Imperial,
-    Metric,
+/// How often a row is added to the log
+const LOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The top of the groundspeed gauge, in m/s
+const GAUGE_SPEED: f64 = 250.0;
+/// The top of the altitude gauge, in meters
+const GAUGE_ALTITUDE: f64 = 13_716.0;
+/// Where the gauges are drawn, down the right of the screen, and how big
+const GAUGE_X: usize = 582;
+const GAUGE_RADIUS: usize = 52;
+
+/// How often groundspeed is added to the history plot, and how many
+/// samples it shows
+const HISTORY_INTERVAL: Duration = Duration::from_secs(1);
+const HISTORY_SAMPLES: usize = 120;
+
+fn history_plot() -> Plot {
+    Plot::new(150, 160)
+        .with_series("gs", LinSrgb::new(255, 0, 0))
+        .with_capacity(HISTORY_SAMPLES)
+        .with_zero(true)
+        .with_ticks(4)
+        .with_x_labels("-2m", "now")
 }
 
-impl Unit {
-    fn distance_conversion_factor(&self) -> f64 {
-        match self {
-            Self::Imperial => 0.000539957,
-            Self::Metric => 0.001,
-        }
-    }
-
-    fn distance_to_display(&self, raw: f64) -> f64 {
-        raw * self.distance_conversion_factor()
-    }
-
-    fn display_to_distance(&self, display: f64) -> f64 {
-        display / self.distance_conversion_factor()
-    }
-
-    fn distance_unit(&self) -> &'static str {
-        match self {
-            Self::Imperial => "nm",
-            Self::Metric => "km",
-        }
-    }
-
-    /// Converting from m/s to <dist>/hr
-    fn speed_conversion_factor(&self) -> f64 {
-        self.distance_conversion_factor() * 3600.0
-    }
-
-    fn speed_to_display(&self, raw: f64) -> f64 {
-        raw * self.speed_conversion_factor()
+/// Used when `launcher.txt` has no `aircraft` lines: name, knots, feet per
+/// minute and gallons per hour
+const BUILT_IN_AIRCRAFT: [(&str, u32, u32, f32); 3] = [
+    ("Cessna 172", 110, 500, 8.5),
+    ("King Air 350", 300, 1500, 100.0),
+    ("Boeing 737-800", 450, 2000, 850.0),
+];
+
+/// The aircraft from the config, or the built-in ones if there aren't any
+fn aircraft_profiles() -> Vec<Aircraft> {
+    let aircraft = config().aircraft;
+    if !aircraft.is_empty() {
+        return aircraft;
     }
+    BUILT_IN_AIRCRAFT
+        .iter()
+        .map(|&(name, ias, descent_rate, fuel_flow)| Aircraft {
+            name: name.to_string(),
+            ias,
+            descent_rate,
+            fuel_flow,
+        })
+        .collect()
+}
 
-    fn speed_from_display(&self, display: f64) -> f64 {
-        display / self.speed_conversion_factor()
-    }
+/// `degrees` rounded to a whole compass heading, from 0 to 359
+fn compass(degrees: f64) -> u32 {
+    (degrees.round() as i64).rem_euclid(360) as u32
+}
 
-    fn speed_unit(&self) -> &'static str {
-        match self {
-            Self::Imperial => "mph",
-            Self::Metric => "km/h",
-        }
+/// `13E`, `4W` or `0`
+fn format_variation(variation: f64) -> String {
+    let degrees = variation.abs().round();
+    match variation.round() {
+        v if v > 0.0 => format!("{:.0}E", degrees),
+        v if v < 0.0 => format!("{:.0}W", degrees),
+        _ => "0".to_string(),
     }
+}
 
-    /// Converting an altitude value to meters
-    fn altitude_conversion_factor(&self) -> f64 {
-        match self {
-            Self::Imperial => 0.3048,
-            Self::Metric => 1.0,
-        }
-    }
+/// The true and magnetic headings to fly `course` with `crab` degrees of
+/// wind correction, right positive, and `variation` east positive
+fn headings(course: f64, crab: f64, variation: f64) -> (u32, u32) {
+    let heading = course + crab;
+    // East is least
+    (compass(heading), compass(heading - variation))
+}
 
-    fn altitude_unit(&self) -> &'static str {
-        match self {
-            Self::Imperial => "ft",
-            Self::Metric => "m",
-        }
-    }
+/// A CSV of the run, and a GPX track of it once there's a GPS fix
+#[cfg(not(target_arch = "wasm32"))]
+struct Log {
+    csv: CsvWriter,
+    gpx: Option<GpxWriter>,
+    last_row: Instant,
 }
 
 pub struct DistanceIntegrator {
@@ -151,11 +186,48 @@ pub struct DistanceIntegrator {
     altitude: u32,
 
     /// The struct's internal values are meters, seconds, and meters/second, but
-    /// this sets how it's displayed
-    unit: Unit,
+    /// these set how they're displayed
+    units: Units,
 
     speed_incrementer: ButtonHoldIncrementer,
     speed_decrementer: ButtonHoldIncrementer,
+
+    /// When set, groundspeed comes from the receiver instead of the manual
+    /// speed, wind and altitude, as long as it has a fix
+    #[cfg(not(target_arch = "wasm32"))]
+    gps: Option<Gps>,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    log: Option<Log>,
+
+    /// One per line of the display, since most lines don't change every frame
+    labels: Vec<Label>,
+
+    /// Groundspeed over the last couple of minutes, in display units
+    history: Plot,
+    last_sample: Instant,
+
+    /// Holding, so time and distance don't add up
+    paused: bool,
+    /// Seconds added to or taken off the time after the fact, for a timer
+    /// started late or early
+    slip: f64,
+    /// R1 was used with another button since it was pressed, so letting go
+    /// of it doesn't reset
+    r1_chorded: bool,
+    /// And L1, so letting go of it doesn't switch the GPS
+    l1_chorded: bool,
+    /// And SELECT, so letting go of it doesn't switch units
+    select_chorded: bool,
+
+    /// The true course to make good, in degrees
+    course: f64,
+    /// Magnetic variation in degrees, east positive
+    variation: f64,
+
+    aircraft: Vec<Aircraft>,
+    /// Which of `aircraft` is being flown
+    current_aircraft: usize,
 }
 
 impl Default for DistanceIntegrator {
@@ -169,15 +241,238 @@ impl Default for DistanceIntegrator {
             crosswind: 0.0,
             crosswind_bearing: 0.0,
             altitude: 0,
-            unit: Unit::Imperial,
+            units: config().units,
             speed_incrementer: Default::default(),
             speed_decrementer: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            gps: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            log: None,
+            labels: (0..6)
+                .map(|_| Label::new("fonts/Ubuntu-B.ttf", 60.0, LinSrgb::new(255, 255, 255)))
+                .collect(),
+            history: history_plot(),
+            last_sample: Instant::now(),
+            paused: false,
+            slip: 0.0,
+            r1_chorded: false,
+            l1_chorded: false,
+            select_chorded: false,
+            course: 0.0,
+            variation: 0.0,
+            aircraft: vec![],
+            current_aircraft: 0,
+        }
+        .with_aircraft(aircraft_profiles(), config().current_aircraft.as_deref())
+    }
+}
+
+impl DistanceIntegrator {
+    /// Starts out flying the aircraft called `current`, or the first
+    fn with_aircraft(mut self, aircraft: Vec<Aircraft>, current: Option<&str>) -> Self {
+        self.aircraft = aircraft;
+        let current = self
+            .aircraft
+            .iter()
+            .position(|a| Some(a.name.as_str()) == current);
+        self.select_aircraft(current.unwrap_or(0));
+        self
+    }
+
+    /// Switches to `aircraft[index]`, setting the speed to its airspeed
+    fn select_aircraft(&mut self, index: usize) {
+        let Some(aircraft) = self.aircraft.get(index) else {
+            return;
+        };
+        self.current_aircraft = index;
+        self.speed = SpeedUnit::Knots.to_mps(aircraft.ias as f64);
+        self.display_speed = self.units.speed.from_mps(self.speed).round() as u32;
+    }
+
+    /// The altitude in thousands of the altitude unit
+    fn kilo_altitude(&self) -> f64 {
+        let meters = AltitudeUnit::Feet.to_meters(self.altitude as f64 * 1000.0);
+        self.units.altitude.from_meters(meters) / 1000.0
+    }
+
+    /// Shows values in `units` from now on and remembers them in the config
+    fn set_units(&mut self, units: Units) {
+        if units == self.units {
+            return;
+        }
+        let old = self.units.speed;
+        self.units = units;
+        self.display_speed = units.speed.from_mps(self.speed).round() as u32;
+        let history = self.history.series()[0].values.clone();
+        self.history.set_values(
+            0,
+            history
+                .into_iter()
+                .map(|v| units.speed.from_mps(old.to_mps(v as f64)) as f32),
+        );
+        let mut config = config();
+        config.units = units;
+        if let Err(e) = set_config(config) {
+            println!("Couldn't save the units: {}", e);
+        }
+    }
+
+    /// Goes to the next or previous aircraft and remembers it in the config
+    fn step_aircraft(&mut self, forward: bool) {
+        let count = self.aircraft.len();
+        if count == 0 {
+            return;
+        }
+        let index = if forward {
+            (self.current_aircraft + 1) % count
+        } else {
+            (self.current_aircraft + count - 1) % count
+        };
+        self.select_aircraft(index);
+        let mut config = config();
+        config.current_aircraft = Some(self.aircraft[index].name.clone());
+        if let Err(e) = set_config(config) {
+            println!("Couldn't save the aircraft: {}", e);
+        }
+    }
+
+    /// Groundspeed (m/s) and track (degrees) from the GPS, if it's enabled and
+    /// has a fix
+    fn gps_velocity(&self) -> Option<(f64, Option<f64>)> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(fix) = self.gps.as_ref().and_then(|gps| gps.fix()) {
+            return Some((fix.speed, fix.track));
+        }
+        None
+    }
+
+    fn gps_enabled(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.gps.is_some();
+        #[cfg(target_arch = "wasm32")]
+        false
+    }
+
+    fn is_logging(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.log.is_some();
+        #[cfg(target_arch = "wasm32")]
+        false
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn toggle_logging(&mut self) {
+        if self.log.take().is_some() {
+            return;
+        }
+        match CsvWriter::create(
+            "distance",
+            &[
+                "time_s",
+                "distance_m",
+                "groundspeed_mps",
+                "source",
+                "paused",
+                "slip_s",
+            ],
+        ) {
+            Ok(csv) => {
+                self.log = Some(Log {
+                    csv,
+                    gpx: None,
+                    // Write the first row straight away
+                    last_row: Instant::now() - LOG_INTERVAL,
+                })
+            }
+            Err(e) => println!("Couldn't start a log: {}", e),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn toggle_logging(&mut self) {}
+
+    /// Adds a row to the log if one's due
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_log(&mut self, groundspeed: f64) {
+        let Some(log) = self.log.as_mut() else {
+            return;
+        };
+        if log.last_row.elapsed() < LOG_INTERVAL {
+            return;
+        }
+        log.last_row = Instant::now();
+
+        let fix = self.gps.as_ref().and_then(|gps| gps.fix());
+        let mut result = log.csv.row(&[
+            format!("{:.3}", self.time),
+            format!("{:.1}", self.distance),
+            format!("{:.2}", groundspeed),
+            if fix.is_some() { "gps" } else { "manual" }.to_string(),
+            u8::from(self.paused).to_string(),
+            format!("{:.0}", self.slip),
+        ]);
+        if let Some(fix) = fix {
+            if log.gpx.is_none() {
+                log.gpx = GpxWriter::create("distance")
+                    .map_err(|e| println!("{}", e))
+                    .ok();
+            }
+            if let Some(gpx) = log.gpx.as_mut() {
+                result = result.and(gpx.point(
+                    fix.latitude,
+                    fix.longitude,
+                    fix.altitude,
+                    std::time::SystemTime::now(),
+                ));
+            }
+        }
+        if let Err(e) = result {
+            println!("Stopped logging: {}", e);
+            self.log = None;
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write_log(&mut self, _groundspeed: f64) {}
+
+    /// Writes a row on the next frame rather than waiting for the interval,
+    /// so a change shows up in the log where it happened
+    fn log_soon(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(log) = self.log.as_mut() {
+            log.last_row = Instant::now() - LOG_INTERVAL;
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.log_soon();
+    }
+
+    /// Adds `seconds` to the time, or takes them off if it's negative, with
+    /// the distance that would have been covered at `groundspeed`
+    fn slip_time(&mut self, seconds: f64, groundspeed: f64) {
+        let seconds = seconds.max(-self.time);
+        self.time += seconds;
+        self.slip += seconds;
+        self.distance = (self.distance + groundspeed * seconds).max(0.0);
+        self.log_soon();
+    }
+
+    fn toggle_gps(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.gps = match self.gps {
+                Some(_) => None,
+                None => Some(Gps::start(GpsSource::detect())),
+            };
         }
     }
 }
 
 impl App for DistanceIntegrator {
-    fn update(&mut self, input: &crate::app::Input, frame: &mut crate::app::Frame) {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
         frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
 
         let elapsed = self.last_update.elapsed();
@@ -185,7 +480,7 @@ impl App for DistanceIntegrator {
         let elapsed = elapsed.as_secs_f64();
 
         // Compute air density, see https://en.wikipedia.org/wiki/Density_of_air
-        let h = Unit::Imperial.altitude_conversion_factor() * self.altitude as f64 * 1000.0;
+        let h = AltitudeUnit::Feet.to_meters(self.altitude as f64 * 1000.0);
         let temp_lapse_rate = 0.0065;
         let est_oat = 288.15 - h * temp_lapse_rate;
         let pressure =
@@ -211,109 +506,168 @@ impl App for DistanceIntegrator {
         } else {
             0.0
         };
-        let groundspeed = crab.to_radians().cos() * tas - headwind_component;
+        let gps_velocity = self.gps_velocity();
+        let groundspeed = match gps_velocity {
+            Some((speed, _)) => speed,
+            None => crab.to_radians().cos() * tas - headwind_component,
+        };
 
-        self.time += elapsed;
-        self.distance += groundspeed * elapsed;
+        if !self.paused {
+            self.time += elapsed;
+            self.distance += groundspeed * elapsed;
+        }
 
-        frame.text(
-            "fonts/Ubuntu-B.ttf",
-            20,
-            70,
-            72.0,
-            LinSrgb::new(255, 255, 255),
-            &format!(
-                "{:.1} {} ({:.1}gs)",
-                self.unit.distance_to_display(self.distance),
-                self.unit.distance_unit(),
-                // Distance in grid squares
-                self.distance / 10_000.0,
-            ),
-        );
+        self.labels[0].set_text(&format!(
+            "{:.1} {} ({:.1}gs)",
+            self.units.distance.from_meters(self.distance),
+            self.units.distance.name(),
+            // Distance in grid squares
+            self.distance / 10_000.0,
+        ));
+        self.labels[0].draw(frame, 20, 70);
         let time_per_distance = if groundspeed > 1.0 {
-            let reference_distance = self.unit.display_to_distance(10.0);
+            let reference_distance = self.units.distance.to_meters(10.0);
             reference_distance / groundspeed
         } else {
             0.0
         };
-        frame.text(
-            "fonts/Ubuntu-B.ttf",
-            20,
-            150,
-            72.0,
-            LinSrgb::new(255, 255, 255),
-            &format!(
-                "{:.0} {} ({}:{:02.0}/10)",
-                self.unit.speed_to_display(self.speed),
-                self.unit.speed_unit(),
-                time_per_distance.div_euclid(60.0),
-                time_per_distance.rem_euclid(60.0),
-            ),
-        );
+        let (speed, source) = match gps_velocity {
+            Some((speed, _)) => (speed, "GPS "),
+            None => (self.speed, ""),
+        };
+        self.labels[1].set_text(&format!(
+            "{}{:.0} {} ({}:{:02.0}/10)",
+            source,
+            self.units.speed.from_mps(speed),
+            self.units.speed.name(),
+            time_per_distance.div_euclid(60.0),
+            time_per_distance.rem_euclid(60.0),
+        ));
+        self.labels[1].draw(frame, 20, 150);
         let minutes = self.time.div_euclid(60.0) as u32;
         let seconds = self.time.rem_euclid(60.0) as u32;
         let milliseconds = (self.time.rem_euclid(1.0) * 1000.0) as u32;
-        frame.text(
-            "fonts/Ubuntu-B.ttf",
-            20,
-            230,
-            72.0,
-            LinSrgb::new(255, 255, 255),
-            &format!(
-                "{:.1} k{} {:02}:{:02}.{:03}",
-                self.altitude as f64 * 0.3048 / self.unit.altitude_conversion_factor(),
-                self.unit.altitude_unit(),
-                minutes,
-                seconds,
-                milliseconds
-            ),
-        );
+        let altitude = self.kilo_altitude();
+        self.labels[2].set_text(&format!(
+            "{:.1} k{} {:02}:{:02}.{:03}",
+            altitude,
+            self.units.altitude.name(),
+            minutes,
+            seconds,
+            milliseconds
+        ));
+        self.labels[2].draw(frame, 20, 230);
 
         // Crosswind computer
+        self.labels[3].set_text(&format!(
+            "{:.1}m/s @ {:.0}deg",
+            self.crosswind, self.crosswind_bearing,
+        ));
+        self.labels[3].draw(frame, 20, 310);
+        match gps_velocity {
+            Some((_, Some(track))) => self.labels[4].set_text(&format!("{:.0}deg track", track)),
+            Some((_, None)) => self.labels[4].set_text("GPS, no track"),
+            // Manual entry carries on while the receiver has no fix
+            None if self.gps_enabled() => self.labels[4].set_text("No GPS fix"),
+            None => {
+                let (true_heading, magnetic) = headings(self.course, crab, self.variation);
+                self.labels[4].set_text(&format!("hdg {:03}T {:03}M", true_heading, magnetic))
+            }
+        }
+        self.labels[4].draw(frame, 20, 390);
         frame.text(
             "fonts/Ubuntu-B.ttf",
             20,
-            310,
-            72.0,
-            LinSrgb::new(255, 255, 255),
+            414,
+            18.0,
+            LinSrgb::new(180, 180, 180),
             &format!(
-                "{:.1}m/s @ {:.0}deg",
-                self.crosswind, self.crosswind_bearing,
+                "{:.1}deg crab, course {:03}T, variation {}",
+                crab,
+                compass(self.course),
+                format_variation(self.variation)
             ),
         );
-        frame.text(
-            "fonts/Ubuntu-B.ttf",
-            20,
-            390,
-            72.0,
-            LinSrgb::new(255, 255, 255),
-            &format!("{:.1}deg crab", crab),
-        );
 
         // Show how many distance we go during descent
-        let secs_to_descend_1km = match self.unit {
-            // 1kfpm standard imperial descent
-            Unit::Imperial => 60.0 / 1.0,
-
-            // 400m per minute standard metric descent
-            Unit::Metric => 60.0 / 0.4,
-        };
-        let descent_distance = secs_to_descend_1km * groundspeed;
-        frame.text(
-            "fonts/Ubuntu-B.ttf",
-            20,
-            470,
-            72.0,
-            LinSrgb::new(255, 255, 255),
-            &format!(
-                "{:.1} {} per 1k{}",
-                self.unit.distance_to_display(descent_distance),
-                self.unit.distance_unit(),
-                self.unit.altitude_unit(),
-            ),
-        );
+        // At the aircraft's descent rate, or 1kfpm without one
+        let aircraft = self.aircraft.get(self.current_aircraft);
+        let feet_per_minute = aircraft.map_or(1000, |a| a.descent_rate) as f64;
+        let secs_to_descend_1k = self.units.altitude.to_meters(1000.0)
+            / (AltitudeUnit::Feet.to_meters(feet_per_minute) / 60.0);
+        let descent_distance = secs_to_descend_1k * groundspeed;
+        self.labels[5].set_text(&format!(
+            "{:.1} {} per 1k{}",
+            self.units.distance.from_meters(descent_distance),
+            self.units.distance.name(),
+            self.units.altitude.name(),
+        ));
+        self.labels[5].draw(frame, 20, 470);
+
+        Gauge::new(0.0, self.units.speed.from_mps(GAUGE_SPEED) as f32)
+            .with_label(&format!("gs {}", self.units.speed.name()))
+            .with_ticks(6)
+            .draw(
+                frame,
+                GAUGE_X,
+                296,
+                GAUGE_RADIUS,
+                self.units.speed.from_mps(groundspeed) as f32,
+            );
+        let gauge_altitude = self.units.altitude.from_meters(GAUGE_ALTITUDE) / 1000.0;
+        Gauge::new(0.0, gauge_altitude as f32)
+            .with_label(&format!("k{}", self.units.altitude.name()))
+            .with_decimals(1)
+            .with_ticks(6)
+            .draw(
+                frame,
+                GAUGE_X,
+                416,
+                GAUGE_RADIUS,
+                self.kilo_altitude() as f32,
+            );
+
+        if self.last_sample.elapsed() >= HISTORY_INTERVAL {
+            self.last_sample = Instant::now();
+            self.history
+                .push(0, self.units.speed.from_mps(groundspeed) as f32);
+        }
+        self.history.draw(frame, 486, 50);
+
+        self.write_log(groundspeed);
+        let mut status = vec![];
+        if let Some(aircraft) = self.aircraft.get(self.current_aircraft) {
+            // Burnt so far at the aircraft's fuel flow
+            let fuel = aircraft.fuel_flow as f64 * self.time / 3600.0;
+            status.push(format!("{}, {:.1} gal", aircraft.name, fuel));
+        }
+        if self.paused {
+            status.push("HOLD".to_string());
+        }
+        if self.slip != 0.0 {
+            status.push(format!("time slip {:+.0} s", self.slip));
+        }
+        if !status.is_empty() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                20,
+                20,
+                18.0,
+                LinSrgb::new(255, 255, 0),
+                &status.join("   "),
+            );
+        }
+        if self.is_logging() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                frame.width() - 90,
+                40,
+                32.0,
+                LinSrgb::new(255, 0, 0),
+                "REC",
+            );
+        }
 
-        //let mut speed = self.unit.speed_to_display(self.speed) as u32;
         self.speed_incrementer.update(
             input.pressed(Button::ActionH),
             true,
@@ -324,46 +678,192 @@ impl App for DistanceIntegrator {
             false,
             &mut self.display_speed,
         );
-        self.speed = self.unit.speed_from_display(self.display_speed as f64);
+        self.speed = self.units.speed.to_mps(self.display_speed as f64);
 
+        // R1 with A holds, with left and right slips the time, with up and
+        // down changes aircraft, and on its own resets
+        let chord = input.pressed(Button::BumperR);
         if input.just_pressed(Button::BumperR) {
+            self.r1_chorded = false;
+        }
+        if chord && input.just_pressed(Button::ActionA) {
+            self.r1_chorded = true;
+            self.toggle_pause();
+        }
+        // SELECT+R1 is the debug overlay
+        if chord && input.pressed(Button::MenuL) {
+            self.r1_chorded = true;
+        }
+        for (button, seconds) in [(Button::PovLeft, -1.0), (Button::PovRight, 1.0)] {
+            if chord && input.repeated(button) {
+                self.r1_chorded = true;
+                self.slip_time(seconds, groundspeed);
+            }
+        }
+        for (button, forward) in [(Button::PovUp, false), (Button::PovDown, true)] {
+            if chord && input.just_pressed(button) {
+                self.r1_chorded = true;
+                self.step_aircraft(forward);
+            }
+        }
+        if input.just_released(Button::BumperR) && !self.r1_chorded {
             self.distance = 0.0;
             self.time = 0.0;
+            self.slip = 0.0;
+            self.paused = false;
+            self.history.clear();
+            self.log_soon();
+        }
+
+        // L1 with left and right sets the course, with up and down the
+        // variation, and on its own switches the GPS
+        let l1_chord = input.pressed(Button::BumperL);
+        if input.just_pressed(Button::BumperL) {
+            self.l1_chorded = false;
         }
+        for (button, degrees) in [(Button::PovLeft, -1.0), (Button::PovRight, 1.0)] {
+            if l1_chord && input.repeated(button) {
+                self.l1_chorded = true;
+                self.course = (self.course + degrees).rem_euclid(360.0);
+            }
+        }
+        for (button, degrees) in [(Button::PovDown, -1.0), (Button::PovUp, 1.0)] {
+            if l1_chord && input.repeated(button) {
+                self.l1_chorded = true;
+                self.variation = (self.variation + degrees).clamp(-180.0, 180.0);
+            }
+        }
+        if input.just_released(Button::BumperL) && !self.l1_chorded {
+            self.toggle_gps();
+        }
+        // The D-pad sets the wind when neither bumper is held
+        let chord = chord || l1_chord;
+        if input.start_pressed() {
+            self.toggle_logging();
+        }
+
+        // SELECT with up, right or down changes the distance, speed or
+        // altitude unit, and on its own switches between aviation and metric
+        let select_chord = input.pressed(Button::MenuL);
         if input.just_pressed(Button::MenuL) {
-            self.unit = match self.unit {
-                Unit::Imperial => Unit::Metric,
-                Unit::Metric => Unit::Imperial,
+            self.select_chorded = false;
+        }
+        let mut units = self.units;
+        if select_chord && input.just_pressed(Button::PovUp) {
+            units.distance = next_cycle(&units.distance);
+        }
+        if select_chord && input.just_pressed(Button::PovRight) {
+            units.speed = next_cycle(&units.speed);
+        }
+        if select_chord && input.just_pressed(Button::PovDown) {
+            units.altitude = next_cycle(&units.altitude);
+        }
+        let other = all::<Button>().any(|b| b != Button::MenuL && input.just_pressed(b));
+        if select_chord && other {
+            self.select_chorded = true;
+        }
+        if units == self.units && input.just_released(Button::MenuL) && !self.select_chorded {
+            units = if units == Units::METRIC {
+                Units::AVIATION
+            } else {
+                Units::METRIC
             };
-            self.display_speed = self.unit.speed_to_display(self.speed) as u32;
         }
+        self.set_units(units);
+        let chord = chord || select_chord;
 
-        if input.pressed(Button::PovUp) {
+        if input.pressed(Button::PovUp) && !chord {
             self.crosswind += elapsed * 2.0;
         }
-        if input.pressed(Button::PovDown) {
+        if input.pressed(Button::PovDown) && !chord {
             self.crosswind -= elapsed * 2.0;
             if self.crosswind < 0.0 {
                 self.crosswind = 0.0;
             }
         }
-        if input.pressed(Button::PovLeft) {
+        if input.pressed(Button::PovLeft) && !chord {
             self.crosswind_bearing -= elapsed * 30.0;
             if self.crosswind_bearing < -180.0 {
                 self.crosswind_bearing += 360.0;
             }
         }
-        if input.pressed(Button::PovRight) {
+        if input.pressed(Button::PovRight) && !chord {
             self.crosswind_bearing += elapsed * 30.0;
             if self.crosswind_bearing > 180.0 {
                 self.crosswind_bearing -= 360.0;
             }
         }
-        if input.just_pressed(Button::ActionA) {
+        if input.just_pressed(Button::ActionA) && !chord {
             self.altitude += 1;
         }
         if input.just_pressed(Button::ActionV) {
             self.altitude = self.altitude.saturating_sub(1);
         }
     }
+
+    fn power_hint(&self) -> PowerHint {
+        PowerHint::Powersave
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_headings() {
+        assert_eq!(headings(90.0, 2.4, 13.0), (92, 79));
+        assert_eq!(headings(359.0, 3.0, -4.0), (2, 6));
+        assert_eq!(compass(359.6), 0);
+        assert_eq!(format_variation(-4.2), "4W");
+        assert_eq!(format_variation(0.3), "0");
+    }
+
+    #[test]
+    fn test_slip_time() {
+        let mut integrator = DistanceIntegrator {
+            time: 10.0,
+            distance: 1000.0,
+            ..Default::default()
+        };
+        integrator.slip_time(5.0, 100.0);
+        assert_eq!((integrator.time, integrator.distance), (15.0, 1500.0));
+        // Not back past the start
+        integrator.slip_time(-20.0, 100.0);
+        assert_eq!((integrator.time, integrator.distance), (0.0, 0.0));
+        assert_eq!(integrator.slip, -10.0);
+    }
+
+    proptest! {
+        #[test]
+        fn test_hold_incrementer(
+            start in 0u32..1000,
+            positive: bool,
+            steps in prop::collection::vec((any::<bool>(), 0.0f64..1.0), 1..200),
+        ) {
+            let mut incrementer = ButtonHoldIncrementer::default();
+            let mut value = start;
+            let mut previously_pressed = false;
+            for (pressed, elapsed) in steps {
+                let before = value;
+                incrementer.advance(elapsed, pressed, positive, &mut value);
+                if positive {
+                    prop_assert!(value >= before);
+                } else {
+                    prop_assert!(value <= before);
+                }
+                if !pressed {
+                    prop_assert_eq!(value, before);
+                } else if !previously_pressed {
+                    // The first press moves by exactly one
+                    prop_assert_eq!(value.abs_diff(before), u32::from(before > 0 || positive));
+                } else if value != before {
+                    // Repeats land on round numbers
+                    prop_assert_eq!(value % 5, 0);
+                }
+                previously_pressed = pressed;
+            }
+        }
+    }
 }
@@ -0,0 +1,932 @@
+//! A small turn-based roguelike: down through generated dungeon levels,
+//! fighting what's there and picking up what's lying around. Only what's in
+//! sight is shown, with what's been seen before remembered dimmed.
+//!
+//! Death is final. The run is written up in a morgue file in the exports
+//! directory and the save is deleted; otherwise it's saved on leaving for the
+//! menu.
+
+use std::collections::VecDeque;
+
+use enum_iterator::{all, Sequence};
+use palette::LinSrgb;
+use rand::{prelude::*, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::grid::{Direction, Grid, Point};
+use rg35xx::profiles::Store;
+use rg35xx::savestate::SaveState;
+use rg35xx::storage::{iso8601, write_text_export};
+use rg35xx::time::SystemTime;
+
+pub const MANIFEST: Manifest =
+    Manifest::new("0.1.0").description("Turn-based dungeon crawl with permadeath");
+
+const MAP_WIDTH: usize = 40;
+const MAP_HEIGHT: usize = 25;
+const TILE_SIZE: usize = 16;
+/// Where the map starts, under the status line
+const MAP_TOP: usize = 24;
+/// How far the player can see, in tiles
+const SIGHT: usize = 7;
+const ROOM_ATTEMPTS: usize = 40;
+const MAX_ROOMS: usize = 9;
+/// Lines of messages shown under the map
+const MESSAGES: usize = 3;
+/// Turns between each point of health coming back
+const REGENERATION: u64 = 10;
+const POTION_HEALING: i32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Tile {
+    Wall,
+    Floor,
+    Stairs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Sequence)]
+enum Kind {
+    Rat,
+    Kobold,
+    Goblin,
+    Orc,
+    Ogre,
+    Troll,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Rat => "rat",
+            Self::Kobold => "kobold",
+            Self::Goblin => "goblin",
+            Self::Orc => "orc",
+            Self::Ogre => "ogre",
+            Self::Troll => "troll",
+        }
+    }
+
+    fn glyph(self) -> &'static str {
+        match self {
+            Self::Rat => "r",
+            Self::Kobold => "k",
+            Self::Goblin => "g",
+            Self::Orc => "o",
+            Self::Ogre => "O",
+            Self::Troll => "T",
+        }
+    }
+
+    fn color(self) -> LinSrgb<u8> {
+        match self {
+            Self::Rat => LinSrgb::new(170, 140, 110),
+            Self::Kobold => LinSrgb::new(230, 120, 60),
+            Self::Goblin => LinSrgb::new(90, 220, 90),
+            Self::Orc => LinSrgb::new(60, 160, 60),
+            Self::Ogre => LinSrgb::new(220, 180, 80),
+            Self::Troll => LinSrgb::new(80, 200, 200),
+        }
+    }
+
+    /// Health, attack and defense
+    fn stats(self) -> (i32, i32, i32) {
+        match self {
+            Self::Rat => (3, 2, 0),
+            Self::Kobold => (5, 3, 0),
+            Self::Goblin => (7, 4, 1),
+            Self::Orc => (11, 5, 2),
+            Self::Ogre => (18, 7, 2),
+            Self::Troll => (24, 8, 3),
+        }
+    }
+
+    /// The shallowest level it turns up on
+    fn depth(self) -> u32 {
+        match self {
+            Self::Rat => 1,
+            Self::Kobold => 1,
+            Self::Goblin => 2,
+            Self::Orc => 3,
+            Self::Ogre => 5,
+            Self::Troll => 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Monster {
+    kind: Kind,
+    at: Point,
+    hp: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Item {
+    Potion,
+    Sword,
+    Armor,
+    /// Shows the whole level
+    Map,
+    Gold(u32),
+}
+
+impl Item {
+    fn glyph(self) -> &'static str {
+        match self {
+            Self::Potion => "!",
+            Self::Sword => "/",
+            Self::Armor => "[",
+            Self::Map => "?",
+            Self::Gold(_) => "$",
+        }
+    }
+
+    fn color(self) -> LinSrgb<u8> {
+        match self {
+            Self::Potion => LinSrgb::new(240, 80, 200),
+            Self::Sword | Self::Armor => LinSrgb::new(200, 200, 220),
+            Self::Map => LinSrgb::new(240, 240, 200),
+            Self::Gold(_) => LinSrgb::new(255, 210, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Room {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Room {
+    fn center(&self) -> Point {
+        Point(self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    /// Whether the rooms overlap or touch, leaving no wall between them
+    fn overlaps(&self, other: &Room) -> bool {
+        self.x <= other.x + other.width
+            && other.x <= self.x + self.width
+            && self.y <= other.y + other.height
+            && other.y <= self.y + self.height
+    }
+
+    fn random_point(&self, rng: &mut StdRng) -> Point {
+        Point(
+            rng.gen_range(self.x..self.x + self.width),
+            rng.gen_range(self.y..self.y + self.height),
+        )
+    }
+}
+
+/// The cells on a line from `from` to `to`, both included
+fn line(from: Point, to: Point) -> Vec<Point> {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let (x1, y1) = (to.0 as isize, to.1 as isize);
+    let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+    let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+    let mut error = dx + dy;
+    let mut points = vec![from];
+    while (x, y) != (x1, y1) {
+        let doubled = error * 2;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+        points.push(Point(x as usize, y as usize));
+    }
+    points
+}
+
+fn distance(a: Point, b: Point) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Level {
+    tiles: Grid<Tile>,
+    /// Tiles that have been in sight, remembered on the map
+    seen: Grid<bool>,
+    monsters: Vec<Monster>,
+    loot: Vec<(Point, Item)>,
+}
+
+impl Level {
+    /// Rooms joined by corridors, each to the one before, with monsters and
+    /// loot in all but the first. Returns where the player starts.
+    fn generate(depth: u32, rng: &mut StdRng) -> (Self, Point) {
+        let mut tiles = Grid::new(MAP_WIDTH, MAP_HEIGHT, Tile::Wall);
+        let mut rooms: Vec<Room> = vec![];
+        for _ in 0..ROOM_ATTEMPTS {
+            if rooms.len() == MAX_ROOMS {
+                break;
+            }
+            let (width, height) = (rng.gen_range(4..=9), rng.gen_range(3..=6));
+            let room = Room {
+                x: rng.gen_range(1..MAP_WIDTH - width - 1),
+                y: rng.gen_range(1..MAP_HEIGHT - height - 1),
+                width,
+                height,
+            };
+            if rooms.iter().any(|other| other.overlaps(&room)) {
+                continue;
+            }
+            for y in room.y..room.y + room.height {
+                for x in room.x..room.x + room.width {
+                    tiles[Point(x, y)] = Tile::Floor;
+                }
+            }
+            if let Some(previous) = rooms.last() {
+                let (Point(x0, y0), Point(x1, y1)) = (previous.center(), room.center());
+                // Along then down, or down then along
+                let corner = if rng.gen() {
+                    Point(x1, y0)
+                } else {
+                    Point(x0, y1)
+                };
+                for point in line(Point(x0, y0), corner)
+                    .into_iter()
+                    .chain(line(corner, Point(x1, y1)))
+                {
+                    tiles[point] = Tile::Floor;
+                }
+            }
+            rooms.push(room);
+        }
+
+        let start = rooms[0].center();
+        let last = rooms[rooms.len() - 1];
+        let stairs = if last.center() == start {
+            Point(last.x, last.y)
+        } else {
+            last.center()
+        };
+        tiles[stairs] = Tile::Stairs;
+
+        let kinds = all::<Kind>()
+            .filter(|kind| kind.depth() <= depth)
+            .collect::<Vec<_>>();
+        // The toughest three that can turn up this deep
+        let kinds = &kinds[kinds.len().saturating_sub(3)..];
+        let mut monsters: Vec<Monster> = vec![];
+        let mut loot = vec![];
+        for room in &rooms[1..] {
+            for _ in 0..rng.gen_range(0..=1 + depth as usize / 3) {
+                let at = room.random_point(rng);
+                if at != stairs && monsters.iter().all(|monster| monster.at != at) {
+                    let kind = *kinds.choose(rng).unwrap();
+                    monsters.push(Monster {
+                        kind,
+                        at,
+                        hp: kind.stats().0,
+                    });
+                }
+            }
+            if rng.gen_bool(0.5) {
+                let item = match rng.gen_range(0..10) {
+                    0..=3 => Item::Potion,
+                    4..=6 => Item::Gold(rng.gen_range(5..=20) * depth),
+                    7 => Item::Sword,
+                    8 => Item::Armor,
+                    _ => Item::Map,
+                };
+                loot.push((room.random_point(rng), item));
+            }
+        }
+
+        let level = Self {
+            tiles,
+            seen: Grid::new(MAP_WIDTH, MAP_HEIGHT, false),
+            monsters,
+            loot,
+        };
+        (level, start)
+    }
+
+    fn is_open(&self, point: Point) -> bool {
+        self.tiles[point] != Tile::Wall
+    }
+
+    /// What can be seen from `from`: everything in range with no wall in the
+    /// way, including the walls themselves
+    fn sight(&self, from: Point) -> Grid<bool> {
+        let mut visible = Grid::new(MAP_WIDTH, MAP_HEIGHT, false);
+        for point in self.tiles.points() {
+            let (dx, dy) = (point.0.abs_diff(from.0), point.1.abs_diff(from.1));
+            if dx * dx + dy * dy > SIGHT * SIGHT {
+                continue;
+            }
+            let path = line(from, point);
+            if path[1..path.len().max(2) - 1]
+                .iter()
+                .all(|&between| self.is_open(between))
+            {
+                visible[point] = true;
+            }
+        }
+        visible
+    }
+
+    fn monster_at(&self, point: Point) -> Option<usize> {
+        self.monsters.iter().position(|monster| monster.at == point)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Player {
+    at: Point,
+    hp: i32,
+    max_hp: i32,
+    attack: i32,
+    defense: i32,
+    potions: u32,
+    gold: u32,
+}
+
+/// A game from the first level to death
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Run {
+    depth: u32,
+    turns: u64,
+    player: Player,
+    level: Level,
+    /// Everything killed, in order
+    kills: Vec<Kind>,
+    messages: VecDeque<String>,
+    /// What killed the player, once something has
+    killed_by: Option<String>,
+    /// What's in sight now. Not saved, it's worked out again after loading.
+    #[serde(skip)]
+    visible: Grid<bool>,
+}
+
+impl SaveState for Run {
+    const NAME: &'static str = "dungeon";
+}
+
+/// `attack` plus up to one more, less `defense`, but always at least 1
+fn damage(attack: i32, defense: i32, rng: &mut StdRng) -> i32 {
+    (attack + rng.gen_range(0..=1) - defense).max(1)
+}
+
+impl Run {
+    fn new(rng: &mut StdRng) -> Self {
+        let (level, start) = Level::generate(1, rng);
+        let mut run = Self {
+            depth: 1,
+            turns: 0,
+            player: Player {
+                at: start,
+                hp: 20,
+                max_hp: 20,
+                attack: 3,
+                defense: 0,
+                potions: 1,
+                gold: 0,
+            },
+            level,
+            kills: vec![],
+            messages: VecDeque::new(),
+            killed_by: None,
+            visible: Grid::default(),
+        };
+        run.log("You enter the dungeon.".to_string());
+        run.look();
+        run
+    }
+
+    fn log(&mut self, message: String) {
+        self.messages.push_back(message);
+        if self.messages.len() > MESSAGES * 4 {
+            self.messages.pop_front();
+        }
+    }
+
+    /// Works out what's in sight and remembers it
+    fn look(&mut self) {
+        self.visible = self.level.sight(self.player.at);
+        for (point, &visible) in self.visible.iter() {
+            if visible {
+                self.level.seen[point] = true;
+            }
+        }
+    }
+
+    fn is_dead(&self) -> bool {
+        self.killed_by.is_some()
+    }
+
+    /// Moves or attacks in `direction`, then lets the monsters act
+    fn step(&mut self, direction: Direction, rng: &mut StdRng) {
+        let Some(target) = self.player.at.stepped(direction, MAP_WIDTH, MAP_HEIGHT) else {
+            return;
+        };
+        if let Some(index) = self.level.monster_at(target) {
+            self.attack(index, rng);
+        } else if self.level.is_open(target) {
+            self.player.at = target;
+            self.pick_up();
+        } else {
+            return;
+        }
+        self.end_turn(rng);
+    }
+
+    fn attack(&mut self, index: usize, rng: &mut StdRng) {
+        let kind = self.level.monsters[index].kind;
+        let hit = damage(self.player.attack, kind.stats().2, rng);
+        let monster = &mut self.level.monsters[index];
+        monster.hp -= hit;
+        if monster.hp <= 0 {
+            self.level.monsters.remove(index);
+            self.kills.push(kind);
+            self.log(format!("You kill the {}.", kind.name()));
+        } else {
+            self.log(format!("You hit the {} for {}.", kind.name(), hit));
+        }
+    }
+
+    fn pick_up(&mut self) {
+        let Some(index) = self
+            .level
+            .loot
+            .iter()
+            .position(|(at, _)| *at == self.player.at)
+        else {
+            return;
+        };
+        let (_, item) = self.level.loot.remove(index);
+        let message = match item {
+            Item::Potion => {
+                self.player.potions += 1;
+                "You pick up a healing potion.".to_string()
+            }
+            Item::Sword => {
+                self.player.attack += 1;
+                "You find a better sword.".to_string()
+            }
+            Item::Armor => {
+                self.player.defense += 1;
+                "You find better armor.".to_string()
+            }
+            Item::Map => {
+                for point in self.level.tiles.points() {
+                    let near_floor = self
+                        .level
+                        .tiles
+                        .surrounding(point)
+                        .chain([point])
+                        .any(|p| self.level.is_open(p));
+                    if near_floor {
+                        self.level.seen[point] = true;
+                    }
+                }
+                "You read a map of the level.".to_string()
+            }
+            Item::Gold(amount) => {
+                self.player.gold += amount;
+                format!("You pick up {} gold.", amount)
+            }
+        };
+        self.log(message);
+    }
+
+    fn drink(&mut self, rng: &mut StdRng) {
+        if self.player.potions == 0 {
+            self.log("You have no potions.".to_string());
+            return;
+        }
+        self.player.potions -= 1;
+        self.player.hp = (self.player.hp + POTION_HEALING).min(self.player.max_hp);
+        self.log("You feel better.".to_string());
+        self.end_turn(rng);
+    }
+
+    /// Goes down the stairs, if the player's on them
+    fn descend(&mut self, rng: &mut StdRng) {
+        if self.level.tiles[self.player.at] != Tile::Stairs {
+            self.log("There are no stairs here.".to_string());
+            return;
+        }
+        self.depth += 1;
+        let (level, start) = Level::generate(self.depth, rng);
+        self.level = level;
+        self.player.at = start;
+        self.player.max_hp += 2;
+        self.log(format!("You go down to depth {}.", self.depth));
+        self.look();
+    }
+
+    fn end_turn(&mut self, rng: &mut StdRng) {
+        self.turns += 1;
+        if self.turns.is_multiple_of(REGENERATION) {
+            self.player.hp = (self.player.hp + 1).min(self.player.max_hp);
+        }
+        self.look();
+        self.monsters_act(rng);
+    }
+
+    /// Monsters in sight come for the player, and attack once next to them
+    fn monsters_act(&mut self, rng: &mut StdRng) {
+        for index in 0..self.level.monsters.len() {
+            if self.is_dead() {
+                return;
+            }
+            let Monster { kind, at, .. } = self.level.monsters[index];
+            if !self.visible[at] {
+                continue;
+            }
+            let player = self.player.at;
+            if distance(at, player) == 1 {
+                let hit = damage(kind.stats().1, self.player.defense, rng);
+                self.player.hp -= hit;
+                self.log(format!("The {} hits you for {}.", kind.name(), hit));
+                if self.player.hp <= 0 {
+                    self.killed_by = Some(format!(
+                        "Killed by {} {} on depth {}",
+                        if matches!(kind, Kind::Orc | Kind::Ogre) {
+                            "an"
+                        } else {
+                            "a"
+                        },
+                        kind.name(),
+                        self.depth
+                    ));
+                    self.log("You die...".to_string());
+                }
+                continue;
+            }
+            let closer = all::<Direction>()
+                .filter_map(|direction| at.stepped(direction, MAP_WIDTH, MAP_HEIGHT))
+                .filter(|&next| {
+                    self.level.is_open(next)
+                        && next != player
+                        && self.level.monster_at(next).is_none()
+                })
+                .min_by_key(|&next| distance(next, player))
+                .filter(|&next| distance(next, player) < distance(at, player));
+            if let Some(next) = closer {
+                self.level.monsters[index].at = next;
+            }
+        }
+    }
+
+    /// The level as a character map, as much as has been seen
+    fn map_text(&self) -> String {
+        let mut text = String::new();
+        for y in 0..MAP_HEIGHT {
+            for x in 0..MAP_WIDTH {
+                let point = Point(x, y);
+                let glyph = if point == self.player.at {
+                    "@"
+                } else if let Some(monster) = self
+                    .level
+                    .monsters
+                    .iter()
+                    .find(|monster| monster.at == point && self.visible[point])
+                {
+                    monster.kind.glyph()
+                } else if !self.level.seen[point] {
+                    " "
+                } else {
+                    match self.level.tiles[point] {
+                        Tile::Wall => "#",
+                        Tile::Floor => ".",
+                        Tile::Stairs => ">",
+                    }
+                };
+                text += glyph;
+            }
+            text = text.trim_end().to_string();
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The write-up of a finished run
+    fn morgue(&self) -> String {
+        let mut kills = all::<Kind>()
+            .map(|kind| (kind, self.kills.iter().filter(|&&k| k == kind).count()))
+            .filter(|(_, count)| *count > 0)
+            .map(|(kind, count)| format!("  {} {}\n", count, kind.name()))
+            .collect::<String>();
+        if kills.is_empty() {
+            kills = "  nothing\n".to_string();
+        }
+        let messages = self
+            .messages
+            .iter()
+            .map(|message| format!("  {}\n", message))
+            .collect::<String>();
+        format!(
+            "Dungeon morgue file, {}\n\n\
+             {}: {} after {} turns.\n\
+             Health {}/{}, attack {}, defense {}, gold {}, potions {}.\n\n\
+             Kills:\n{}\n\
+             Last messages:\n{}\n\
+             The level as last seen:\n\n{}",
+            iso8601(SystemTime::now()),
+            rg35xx::profiles::current(),
+            self.killed_by.as_deref().unwrap_or("Still alive"),
+            self.turns,
+            self.player.hp.max(0),
+            self.player.max_hp,
+            self.player.attack,
+            self.player.defense,
+            self.player.gold,
+            self.player.potions,
+            kills,
+            messages,
+            self.map_text(),
+        )
+    }
+}
+
+pub struct DungeonApp {
+    run: Option<Run>,
+    /// The depth of a saved run, offered on the title screen
+    saved: Option<u32>,
+    rng: StdRng,
+    /// Holds the current profile's deepest depth
+    store: Store,
+    /// Where the last morgue file went, or why it couldn't be written
+    morgue: Option<String>,
+}
+
+impl Default for DungeonApp {
+    fn default() -> Self {
+        Self {
+            run: None,
+            saved: Run::load().map(|run| run.depth),
+            rng: rg35xx::random::rng("dungeon"),
+            store: Store::open("dungeon"),
+            morgue: None,
+        }
+    }
+}
+
+impl DungeonApp {
+    /// Writes the morgue file and the deepest depth, and deletes the save
+    fn finish(&mut self) {
+        let Some(run) = &self.run else {
+            return;
+        };
+        Run::clear();
+        self.morgue = Some(match write_text_export("morgue", &run.morgue()) {
+            Ok(path) => format!("Morgue file saved to {}", path.display()),
+            Err(e) => format!("Couldn't write the morgue file: {}", e),
+        });
+        if run.depth > self.store.get("deepest").unwrap_or(0) {
+            self.store.set("deepest", run.depth);
+            if let Err(e) = self.store.save() {
+                println!("Couldn't save the deepest depth: {}", e);
+            }
+        }
+    }
+
+    fn title(&mut self, input: &Input, frame: &mut Frame) {
+        let mut ctx = frame.context();
+        ctx.offset(50, 60);
+        ctx.set_fontsize(36.0);
+        ctx.text("Dungeon");
+        ctx.set_fontsize(18.0);
+        ctx.offset(0, 40);
+        ctx.text("START for a new run");
+        if let Some(depth) = self.saved {
+            ctx.offset(0, 25);
+            ctx.text(&format!("A to continue (depth {})", depth));
+        }
+        ctx.offset(0, 25);
+        ctx.text(&format!(
+            "Deepest for {}: {}",
+            rg35xx::profiles::current(),
+            self.store.get::<u32>("deepest").unwrap_or(0)
+        ));
+        ctx.set_color(LinSrgb::new(180, 180, 180));
+        for line in [
+            "D-pad moves, and attacks by moving into a monster",
+            "A drinks a potion, X waits a turn, Y goes down stairs (>)",
+        ] {
+            ctx.offset(0, 25);
+            ctx.text(line);
+        }
+        if let Some(morgue) = &self.morgue {
+            ctx.offset(0, 40);
+            ctx.text(morgue);
+        }
+
+        if input.start_pressed() {
+            Run::clear();
+            self.saved = None;
+            self.run = Some(Run::new(&mut self.rng));
+        } else if self.saved.is_some() && input.just_pressed(Button::ActionA) {
+            self.run = Run::load().map(|mut run| {
+                run.look();
+                run
+            });
+            Run::clear();
+            self.saved = None;
+        }
+    }
+
+    fn play(&mut self, input: &Input) {
+        let Some(run) = &mut self.run else {
+            return;
+        };
+        if run.is_dead() {
+            if input.just_pressed(Button::ActionA) {
+                self.run = None;
+            }
+            return;
+        }
+        for (button, direction) in [
+            (Button::PovUp, Direction::Up),
+            (Button::PovDown, Direction::Down),
+            (Button::PovLeft, Direction::Left),
+            (Button::PovRight, Direction::Right),
+        ] {
+            if input.repeated(button) {
+                run.step(direction, &mut self.rng);
+            }
+        }
+        if input.just_pressed(Button::ActionA) {
+            run.drink(&mut self.rng);
+        }
+        if input.repeated(Button::ActionV) {
+            run.end_turn(&mut self.rng);
+        }
+        if input.just_pressed(Button::ActionH) {
+            run.descend(&mut self.rng);
+        }
+        if run.is_dead() {
+            self.finish();
+        }
+    }
+
+    fn draw_run(run: &Run, frame: &mut Frame) {
+        let text = |frame: &mut Frame, x, y, size, color, s: &str| {
+            frame.text("fonts/Ubuntu-B.ttf", x, y, size, color, s);
+        };
+        let player = &run.player;
+        text(
+            frame,
+            8,
+            18,
+            16.0,
+            LinSrgb::new(255, 255, 255),
+            &format!(
+                "Depth {}   HP {}/{}   Attack {}   Defense {}   Potions {}   Gold {}",
+                run.depth,
+                player.hp.max(0),
+                player.max_hp,
+                player.attack,
+                player.defense,
+                player.potions,
+                player.gold
+            ),
+        );
+
+        for (point, &tile) in run.level.tiles.iter() {
+            let visible = run.visible[point];
+            if !visible && !run.level.seen[point] {
+                continue;
+            }
+            let color = match (tile, visible) {
+                (Tile::Wall, true) => LinSrgb::new(120, 100, 80),
+                (Tile::Wall, false) => LinSrgb::new(50, 42, 35),
+                (_, true) => LinSrgb::new(40, 40, 55),
+                (_, false) => LinSrgb::new(18, 18, 24),
+            };
+            let (x, y) = (point.0 * TILE_SIZE, MAP_TOP + point.1 * TILE_SIZE);
+            frame.fill_rect(x, y, TILE_SIZE, TILE_SIZE, color);
+            if tile == Tile::Stairs {
+                text(frame, x + 4, y + 13, 16.0, LinSrgb::new(255, 255, 0), ">");
+            }
+        }
+        let glyphs = run
+            .level
+            .loot
+            .iter()
+            .map(|(at, item)| (*at, item.glyph(), item.color()))
+            .chain(
+                run.level
+                    .monsters
+                    .iter()
+                    .map(|monster| (monster.at, monster.kind.glyph(), monster.kind.color())),
+            )
+            .filter(|(at, _, _)| run.visible[*at])
+            .chain([(player.at, "@", LinSrgb::new(255, 255, 255))]);
+        for (at, glyph, color) in glyphs {
+            let (x, y) = (at.0 * TILE_SIZE, MAP_TOP + at.1 * TILE_SIZE);
+            text(frame, x + 3, y + 13, 16.0, color, glyph);
+        }
+
+        let top = MAP_TOP + MAP_HEIGHT * TILE_SIZE;
+        let recent = run.messages.iter().rev().take(MESSAGES).rev();
+        for (i, message) in recent.enumerate() {
+            text(
+                frame,
+                8,
+                top + 16 + i * 18,
+                14.0,
+                LinSrgb::new(200, 200, 200),
+                message,
+            );
+        }
+        if let Some(killed_by) = &run.killed_by {
+            frame.fill_rect(120, 180, 400, 80, LinSrgb::new(0, 0, 0));
+            text(frame, 140, 215, 22.0, LinSrgb::new(255, 0, 0), killed_by);
+            text(
+                frame,
+                140,
+                245,
+                16.0,
+                LinSrgb::new(200, 200, 200),
+                "A to go back",
+            );
+        }
+    }
+}
+
+impl App for DungeonApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+        self.play(input);
+        match &self.run {
+            Some(run) => Self::draw_run(run, frame),
+            None => self.title(input, frame),
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        // Turn-based, so only input changes anything
+        Activity::Waiting
+    }
+
+    fn on_exit(&mut self) {
+        if let Some(run) = self.run.as_ref().filter(|run| !run.is_dead()) {
+            if let Err(e) = run.save() {
+                println!("Couldn't save the run: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let mut rng = rg35xx::random::rng("dungeon-test");
+        for depth in 1..=8 {
+            let (level, start) = Level::generate(depth, &mut rng);
+            // The stairs can always be reached from the start
+            let mut reached = Grid::new(MAP_WIDTH, MAP_HEIGHT, false);
+            let mut queue = VecDeque::from([start]);
+            reached[start] = true;
+            while let Some(point) = queue.pop_front() {
+                for next in level.tiles.neighbors(point) {
+                    if level.is_open(next) && !reached[next] {
+                        reached[next] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+            let stairs = level
+                .tiles
+                .points()
+                .find(|&p| level.tiles[p] == Tile::Stairs);
+            assert!(reached[stairs.unwrap()]);
+            assert!(level
+                .monsters
+                .iter()
+                .all(|monster| monster.kind.depth() <= depth && level.is_open(monster.at)));
+        }
+    }
+
+    #[test]
+    fn test_sight() {
+        let mut tiles = Grid::new(MAP_WIDTH, MAP_HEIGHT, Tile::Floor);
+        tiles[Point(5, 3)] = Tile::Wall;
+        let level = Level {
+            tiles,
+            seen: Grid::new(MAP_WIDTH, MAP_HEIGHT, false),
+            monsters: vec![],
+            loot: vec![],
+        };
+        let visible = level.sight(Point(3, 3));
+        // The wall's seen, but not what's behind it
+        assert!(visible[Point(5, 3)]);
+        assert!(!visible[Point(7, 3)]);
+        assert!(visible[Point(3, 8)]);
+        assert!(!visible[Point(3, 3 + SIGHT + 1)]);
+    }
+}
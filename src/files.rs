@@ -0,0 +1,443 @@
+//! A file manager for the launcher's directory: exports, scripts, decks and
+//! so on can be copied, renamed and deleted without taking the SD card out.
+//! Text files open in a reader and pictures in an image viewer, and L1 shows
+//! a QR code to get a note or an export onto a phone.
+
+mod viewer;
+
+use std::path::{Path, PathBuf};
+
+use palette::LinSrgb;
+use rusttype::Scale;
+
+use rg35xx::app::{load_font, Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::qr::Qr;
+use rg35xx::storage::base_dir;
+use rg35xx::time::{Duration, Instant};
+use rg35xx::widgets::{text_width, BrowserAction, FileBrowser, Reader};
+
+use viewer::{is_image, ImageViewer};
+
+pub const MANIFEST: Manifest =
+    Manifest::new("0.4.0").description("Browse, copy, rename and delete files");
+
+/// Files that open in the text viewer
+const TEXT_EXTENSIONS: [&str; 7] = ["txt", "csv", "gpx", "md", "log", "lua", "json"];
+/// Characters a name can be edited to, in the order up and down cycle through
+const NAME_CHARS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-_. ";
+/// Long names scroll, so keep updating while one's selected
+const ANIMATION_INTERVAL: Duration = Duration::from_millis(200);
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// `name` if nothing in `dir` has it, otherwise `stem (2).ext` and so on
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    (2..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// Spells out a new name a character at a time, since there's no keyboard
+struct NameEditor {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl NameEditor {
+    fn new(name: &str) -> Self {
+        Self {
+            chars: name.chars().collect(),
+            cursor: 0,
+        }
+    }
+
+    fn name(&self) -> String {
+        self.chars.iter().collect::<String>().trim().to_string()
+    }
+
+    /// Left and right move the cursor, past the end to add a character; up and
+    /// down change the character under it, and X deletes it
+    fn handle_input(&mut self, input: &Input) {
+        if input.repeated(Button::PovLeft) {
+            self.cursor = self.cursor.saturating_sub(1);
+        }
+        if input.repeated(Button::PovRight) {
+            self.cursor = (self.cursor + 1).min(self.chars.len());
+        }
+        if self.cursor == self.chars.len() {
+            self.chars.push(' ');
+        }
+        for (button, delta) in [(Button::PovUp, NAME_CHARS.len() - 1), (Button::PovDown, 1)] {
+            if input.repeated(button) {
+                let current = NAME_CHARS
+                    .chars()
+                    .position(|c| c == self.chars[self.cursor])
+                    .unwrap_or(0);
+                let next = (current + delta) % NAME_CHARS.len();
+                self.chars[self.cursor] = NAME_CHARS.chars().nth(next).unwrap();
+            }
+        }
+        if input.just_pressed(Button::ActionV) && self.chars.len() > 1 {
+            self.chars.remove(self.cursor);
+            self.cursor = self.cursor.min(self.chars.len() - 1);
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, x: usize, y: usize) {
+        let fontsize = 28.0;
+        let text = self.chars.iter().collect::<String>();
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            x,
+            y,
+            fontsize,
+            LinSrgb::new(255, 255, 255),
+            &text,
+        );
+        // Underline the character being edited
+        let font = load_font("fonts/Ubuntu-B.ttf");
+        let scale = Scale::uniform(fontsize);
+        let before = self.chars[..self.cursor].iter().collect::<String>();
+        let through = self.chars[..=self.cursor].iter().collect::<String>();
+        let start = text_width(&font, scale, &before) as usize;
+        let end = (text_width(&font, scale, &through) as usize).max(start + 10);
+        frame.fill_rect(x + start, y + 6, end - start, 3, LinSrgb::new(255, 0, 0));
+    }
+}
+
+enum Mode {
+    Browsing,
+    /// Delete was pressed once and needs pressing again
+    ConfirmDelete,
+    Renaming(NameEditor),
+    Viewing(Reader),
+    Pictures(Box<ImageViewer>),
+    /// A QR code for the selected file, and what it holds
+    Sharing(Qr, String),
+}
+
+pub struct FilesApp {
+    browser: FileBrowser,
+    mode: Mode,
+    /// A file copied with X, waiting to be pasted with START
+    clipboard: Option<PathBuf>,
+    status: String,
+}
+
+impl Default for FilesApp {
+    fn default() -> Self {
+        Self {
+            browser: FileBrowser::new(base_dir()),
+            mode: Mode::Browsing,
+            clipboard: None,
+            status: String::new(),
+        }
+    }
+}
+
+impl FilesApp {
+    fn open(&mut self, path: &Path) {
+        if is_image(path) {
+            self.mode = Mode::Pictures(Box::new(ImageViewer::open(path)));
+            return;
+        }
+        let is_text = path.extension().is_some_and(|e| {
+            TEXT_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str())
+        });
+        if !is_text {
+            self.status = "Can't open that kind of file".to_string();
+            return;
+        }
+        match std::fs::read(path) {
+            Ok(bytes) => {
+                let mut reader = Reader::new(18.0);
+                reader.set_text(&String::from_utf8_lossy(&bytes));
+                self.mode = Mode::Viewing(reader);
+            }
+            Err(e) => self.status = format!("Couldn't open {}: {}", path.display(), e),
+        }
+    }
+
+    /// A QR code with a link to download the selected export from the remote
+    /// controller's server, or the text of a text file
+    fn share_selected(&mut self) {
+        let Some(entry) = self.browser.selected().filter(|entry| !entry.is_dir) else {
+            return;
+        };
+        #[cfg(feature = "remote")]
+        if entry.path.parent() == Some(rg35xx::storage::exports_dir().as_path()) {
+            if let Some(url) = rg35xx::remote::export_url(&entry.name) {
+                self.show_qr(&url, url.clone());
+                return;
+            }
+        }
+        let is_text = entry.path.extension().is_some_and(|e| {
+            TEXT_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str())
+        });
+        if !is_text {
+            self.status = "Only text files and exports (with --remote) can be shared".to_string();
+            return;
+        }
+        let name = entry.name.clone();
+        match std::fs::read_to_string(&entry.path) {
+            Ok(text) => self.show_qr(&text, name),
+            Err(e) => self.status = format!("Couldn't read {}: {}", name, e),
+        }
+    }
+
+    fn show_qr(&mut self, data: &str, caption: String) {
+        match Qr::new(data) {
+            Ok(qr) => self.mode = Mode::Sharing(qr, caption),
+            Err(e) => self.status = format!("Too much for a QR code: {}", e),
+        }
+    }
+
+    fn delete_selected(&mut self) {
+        let Some(entry) = self.browser.selected().cloned() else {
+            return;
+        };
+        // Only empty directories, so a slip can't take a whole tree with it
+        let result = if entry.is_dir {
+            std::fs::remove_dir(&entry.path)
+        } else {
+            std::fs::remove_file(&entry.path)
+        };
+        self.status = match result {
+            Ok(()) => format!("Deleted {}", entry.name),
+            Err(e) => format!("Couldn't delete {}: {}", entry.name, e),
+        };
+        self.browser.refresh();
+    }
+
+    fn paste(&mut self) {
+        let Some(source) = self.clipboard.clone() else {
+            self.status = "Copy a file with X first".to_string();
+            return;
+        };
+        let name = source
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let target = unique_path(self.browser.dir(), &name);
+        self.status = match std::fs::copy(&source, &target) {
+            Ok(_) => format!("Copied {}", name),
+            Err(e) => format!("Couldn't copy {}: {}", name, e),
+        };
+        self.browser.refresh();
+        if let Some(name) = target.file_name() {
+            self.browser.select_name(&name.to_string_lossy());
+        }
+    }
+
+    fn rename_selected(&mut self, name: &str) {
+        let Some(entry) = self.browser.selected().cloned() else {
+            return;
+        };
+        if name.is_empty() || name.contains('/') {
+            self.status = "That's not a valid name".to_string();
+            return;
+        }
+        let target = self.browser.dir().join(name);
+        if target.exists() {
+            self.status = format!("{} already exists", name);
+            return;
+        }
+        self.status = match std::fs::rename(&entry.path, &target) {
+            Ok(()) => format!("Renamed to {}", name),
+            Err(e) => format!("Couldn't rename {}: {}", entry.name, e),
+        };
+        self.browser.refresh();
+        self.browser.select_name(name);
+    }
+
+    fn browse(&mut self, input: &Input, frame: &mut Frame) {
+        match self.browser.handle_input(input) {
+            Some(BrowserAction::Open(path)) => self.open(&path),
+            // The menu's exit chord is the way out
+            Some(BrowserAction::Leave) | None => (),
+        }
+        if input.just_pressed(Button::ActionH) {
+            self.mode = Mode::ConfirmDelete;
+        }
+        if input.just_pressed(Button::ActionV) {
+            if let Some(entry) = self.browser.selected().filter(|entry| !entry.is_dir) {
+                self.status = format!("Copied {}, START to paste", entry.name);
+                self.clipboard = Some(entry.path.clone());
+            }
+        }
+        if input.start_pressed() {
+            self.paste();
+        }
+        if input.just_pressed(Button::BumperL) {
+            self.share_selected();
+        }
+        if input.just_pressed(Button::BumperR) {
+            if let Some(entry) = self.browser.selected() {
+                self.mode = Mode::Renaming(NameEditor::new(&entry.name));
+            }
+        }
+
+        self.browser
+            .draw(frame, 30, 20, frame.width() - 60, frame.height() - 80);
+        let details = match self.browser.selected() {
+            Some(entry) if !entry.is_dir => format_size(entry.size),
+            _ => String::new(),
+        };
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            30,
+            frame.height() - 40,
+            18.0,
+            LinSrgb::new(180, 180, 180),
+            &details,
+        );
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            30,
+            frame.height() - 15,
+            18.0,
+            LinSrgb::new(180, 180, 180),
+            if self.status.is_empty() {
+                "X copy, START paste, Y delete, R1 rename, L1 share"
+            } else {
+                &self.status
+            },
+        );
+    }
+}
+
+impl App for FilesApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+
+        match &mut self.mode {
+            Mode::Browsing => self.browse(input, frame),
+            Mode::ConfirmDelete => {
+                let name = self
+                    .browser
+                    .selected()
+                    .map_or(String::new(), |entry| entry.name.clone());
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    30,
+                    80,
+                    28.0,
+                    LinSrgb::new(255, 255, 255),
+                    &format!("Delete {}?", name),
+                );
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    30,
+                    120,
+                    18.0,
+                    LinSrgb::new(180, 180, 180),
+                    "Y to delete, B to cancel",
+                );
+                if input.just_pressed(Button::ActionH) {
+                    self.delete_selected();
+                    self.mode = Mode::Browsing;
+                } else if input.just_pressed(Button::ActionB) {
+                    self.mode = Mode::Browsing;
+                }
+            }
+            Mode::Renaming(editor) => {
+                editor.handle_input(input);
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    30,
+                    50,
+                    18.0,
+                    LinSrgb::new(180, 180, 180),
+                    "Rename",
+                );
+                editor.draw(frame, 30, 100);
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    30,
+                    frame.height() - 15,
+                    18.0,
+                    LinSrgb::new(180, 180, 180),
+                    "Up/down change, X delete, A done, B cancel",
+                );
+                if input.just_pressed(Button::ActionA) {
+                    let name = editor.name();
+                    self.mode = Mode::Browsing;
+                    self.rename_selected(&name);
+                } else if input.just_pressed(Button::ActionB) {
+                    self.mode = Mode::Browsing;
+                }
+            }
+            Mode::Viewing(reader) => {
+                reader.handle_input(input);
+                reader.draw(frame, 30, 20, frame.width() - 70, frame.height() - 40);
+                if input.just_pressed(Button::ActionB) {
+                    self.mode = Mode::Browsing;
+                }
+            }
+            Mode::Pictures(viewer) => {
+                if !viewer.update(input, frame) {
+                    self.mode = Mode::Browsing;
+                }
+            }
+            Mode::Sharing(qr, caption) => {
+                let size = frame.height() - 80;
+                let side = qr.drawn_size(size);
+                qr.draw(frame, (frame.width() - side) / 2, 20, size);
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    30,
+                    frame.height() - 15,
+                    18.0,
+                    LinSrgb::new(180, 180, 180),
+                    &format!("{}  (B to go back)", caption),
+                );
+                if input.just_pressed(Button::ActionB) {
+                    self.mode = Mode::Browsing;
+                }
+            }
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        if let Mode::Pictures(viewer) = &self.mode {
+            return viewer.activity();
+        }
+        if matches!(self.mode, Mode::Browsing) && self.browser.is_animating() {
+            Activity::Idle(Instant::now() + ANIMATION_INTERVAL)
+        } else {
+            Activity::Waiting
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unique_path() {
+        let dir = std::env::temp_dir().join(format!("rg35xx-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("log.csv"), "").unwrap();
+        std::fs::write(dir.join("log (2).csv"), "").unwrap();
+        assert_eq!(unique_path(&dir, "new.csv"), dir.join("new.csv"));
+        assert_eq!(unique_path(&dir, "log.csv"), dir.join("log (3).csv"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,311 @@
+//! Pictures opened from the file manager, with the others in the same
+//! directory a press away and a slideshow through them all. Animated GIFs
+//! play on a loop.
+
+use std::path::{Path, PathBuf};
+
+use palette::LinSrgb;
+use rand::{rngs::StdRng, seq::SliceRandom};
+
+use rg35xx::app::{Activity, Button, Frame, Input};
+use rg35xx::profiles::Store;
+use rg35xx::surface::{Animation, Surface};
+use rg35xx::time::{Duration, Instant};
+
+/// Files that open in the image viewer
+pub const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "bmp", "webp"];
+/// Seconds each picture stays up in a slideshow, picked from with up and down
+const INTERVALS: [u64; 6] = [3, 5, 10, 20, 30, 60];
+const DEFAULT_INTERVAL: u64 = 5;
+const FADE: Duration = Duration::from_millis(600);
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+}
+
+struct Slideshow {
+    next_at: Instant,
+}
+
+pub struct ImageViewer {
+    /// Every picture in the directory, in name order
+    paths: Vec<PathBuf>,
+    /// The order they're shown in, shuffled or not
+    order: Vec<usize>,
+    /// Where in `order` the one showing is
+    position: usize,
+    current: Result<Animation, String>,
+    /// When the current picture was loaded, for playing animations from
+    shown_at: Instant,
+    /// The last one, as it was on screen, and when it started fading out
+    previous: Option<(Surface, Instant)>,
+    slideshow: Option<Slideshow>,
+    /// Seconds between pictures in a slideshow
+    interval: u64,
+    shuffle: bool,
+    rng: StdRng,
+    /// The interval and shuffle, for the current profile
+    settings: Store,
+    /// The screen size, known once it's first drawn
+    size: (usize, usize),
+}
+
+impl ImageViewer {
+    /// Shows `path`, with the other pictures next to it to move through
+    pub fn open(path: &Path) -> Self {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut paths = std::fs::read_dir(dir)
+            .map(|read| {
+                read.flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_file() && is_image(path))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        paths.sort();
+        if !paths.iter().any(|p| p == path) {
+            paths.push(path.to_path_buf());
+        }
+        let settings = Store::open("gallery");
+        let mut viewer = Self {
+            order: (0..paths.len()).collect(),
+            position: paths.iter().position(|p| p == path).unwrap_or(0),
+            paths,
+            current: Err(String::new()),
+            shown_at: Instant::now(),
+            previous: None,
+            slideshow: None,
+            interval: settings
+                .get("interval")
+                .filter(|interval| INTERVALS.contains(interval))
+                .unwrap_or(DEFAULT_INTERVAL),
+            shuffle: false,
+            rng: rg35xx::random::rng("gallery"),
+            settings,
+            size: (0, 0),
+        };
+        if viewer.settings.get("shuffle") == Some(true) {
+            viewer.set_shuffle(true);
+        }
+        viewer
+    }
+
+    fn path(&self) -> &Path {
+        &self.paths[self.order[self.position]]
+    }
+
+    fn load(&mut self) {
+        let (width, height) = self.size;
+        self.current = Animation::load(self.path(), width, height);
+        self.shown_at = Instant::now();
+    }
+
+    /// The current picture's frame centered on a screen-sized surface, so it
+    /// can be faded to and from whatever the shape of the other
+    fn snapshot(&self) -> Option<Surface> {
+        let animation = self.current.as_ref().ok()?;
+        let (width, height) = self.size;
+        let mut screen = Surface::new(width, height);
+        let x = (width - animation.width()) / 2;
+        let y = (height - animation.height()) / 2;
+        let image = animation.frame_at(self.shown_at.elapsed());
+        image.draw(&mut screen.frame(), x as i32, y as i32);
+        Some(screen)
+    }
+
+    /// Moves `steps` along, fading from the picture that was showing
+    fn step(&mut self, steps: isize) {
+        let count = self.order.len() as isize;
+        self.position = (self.position as isize + steps).rem_euclid(count) as usize;
+        self.previous = self.snapshot().map(|screen| (screen, Instant::now()));
+        self.load();
+    }
+
+    /// Shuffles the rest of the pictures after the one showing, or puts them
+    /// back in name order
+    fn set_shuffle(&mut self, shuffle: bool) {
+        let showing = self.order[self.position];
+        self.shuffle = shuffle;
+        self.order = (0..self.paths.len()).collect();
+        if shuffle {
+            self.order.retain(|&i| i != showing);
+            self.order.shuffle(&mut self.rng);
+            self.order.insert(0, showing);
+        }
+        self.position = self.order.iter().position(|&i| i == showing).unwrap_or(0);
+    }
+
+    fn save_settings(&mut self) {
+        self.settings.set("interval", self.interval);
+        self.settings.set("shuffle", self.shuffle);
+        if let Err(e) = self.settings.save() {
+            println!("Couldn't save the slideshow settings: {}", e);
+        }
+    }
+
+    fn handle_input(&mut self, input: &Input) {
+        if input.repeated(Button::PovLeft) {
+            self.step(-1);
+        }
+        if input.repeated(Button::PovRight) {
+            self.step(1);
+        }
+        if input.just_pressed(Button::ActionA) {
+            self.slideshow = match self.slideshow {
+                Some(_) => None,
+                None => Some(Slideshow {
+                    next_at: Instant::now() + Duration::from_secs(self.interval),
+                }),
+            };
+        }
+        for (button, delta) in [(Button::PovUp, 1), (Button::PovDown, -1)] {
+            if input.just_pressed(button) {
+                let current = INTERVALS.iter().position(|&i| i == self.interval);
+                let next = current.map_or(0, |i| i as isize + delta);
+                self.interval = INTERVALS[next.clamp(0, INTERVALS.len() as isize - 1) as usize];
+                self.save_settings();
+            }
+        }
+        if input.just_pressed(Button::ActionH) {
+            self.set_shuffle(!self.shuffle);
+            self.save_settings();
+        }
+    }
+
+    /// Draws the picture, returning false once B's been pressed to go back
+    pub fn update(&mut self, input: &Input, frame: &mut Frame) -> bool {
+        if self.size != (frame.width(), frame.height()) {
+            self.size = (frame.width(), frame.height());
+            self.load();
+        }
+        self.handle_input(input);
+        if let Some(slideshow) = &mut self.slideshow {
+            if Instant::now() >= slideshow.next_at {
+                slideshow.next_at = Instant::now() + Duration::from_secs(self.interval);
+                self.step(1);
+            }
+        }
+
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+        let fade = self.previous.as_ref().map(|(previous, started)| {
+            let along = started.elapsed().as_secs_f32() / FADE.as_secs_f32();
+            (previous, along)
+        });
+        match (&self.current, fade) {
+            (Ok(_), Some((previous, along))) if along < 1.0 => {
+                previous.draw(frame, 0, 0);
+                if let Some(current) = self.snapshot() {
+                    current.draw_alpha(frame, 0, 0, along);
+                }
+            }
+            (Ok(animation), _) => {
+                let x = (frame.width() - animation.width()) / 2;
+                let y = (frame.height() - animation.height()) / 2;
+                let image = animation.frame_at(self.shown_at.elapsed());
+                image.draw(frame, x as i32, y as i32);
+            }
+            (Err(e), _) => {
+                let text = format!("Couldn't show {}: {}", self.path().display(), e);
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    30,
+                    60,
+                    18.0,
+                    LinSrgb::new(255, 255, 255),
+                    &text,
+                );
+            }
+        }
+        if self
+            .previous
+            .as_ref()
+            .is_some_and(|(_, started)| started.elapsed() >= FADE)
+        {
+            self.previous = None;
+        }
+
+        // Out of the way during a slideshow
+        if self.slideshow.is_none() {
+            let name = self
+                .path()
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let status = format!(
+                "{}  {}/{}  A slideshow ({}s, up/down)  Y shuffle {}",
+                name,
+                self.position + 1,
+                self.order.len(),
+                self.interval,
+                if self.shuffle { "on" } else { "off" },
+            );
+            frame.fill_rect(
+                0,
+                frame.height() - 30,
+                frame.width(),
+                30,
+                LinSrgb::new(0, 0, 0),
+            );
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                10,
+                frame.height() - 10,
+                16.0,
+                LinSrgb::new(180, 180, 180),
+                &status,
+            );
+        }
+        !input.just_pressed(Button::ActionB)
+    }
+
+    pub fn activity(&self) -> Activity {
+        if self.previous.is_some() {
+            return Activity::Busy;
+        }
+        let next_frame = self.current.as_ref().ok().and_then(|animation| {
+            let elapsed = self.shown_at.elapsed();
+            animation
+                .next_frame_in(elapsed)
+                .map(|wait| Instant::now() + wait)
+        });
+        let next_slide = self.slideshow.as_ref().map(|slideshow| slideshow.next_at);
+        match next_frame.into_iter().chain(next_slide).min() {
+            Some(at) => Activity::Idle(at),
+            None => Activity::Waiting,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shuffle() {
+        let paths = (0..5).map(|i| PathBuf::from(format!("{}.png", i)));
+        let mut viewer = ImageViewer {
+            paths: paths.collect(),
+            order: (0..5).collect(),
+            position: 2,
+            current: Err(String::new()),
+            shown_at: Instant::now(),
+            previous: None,
+            slideshow: None,
+            interval: DEFAULT_INTERVAL,
+            shuffle: false,
+            rng: rg35xx::random::rng("gallery"),
+            settings: Store::open("gallery-test"),
+            size: (0, 0),
+        };
+        // The picture showing stays put and comes first
+        viewer.set_shuffle(true);
+        assert_eq!((viewer.position, viewer.order[0]), (0, 2));
+        let mut sorted = viewer.order.clone();
+        sorted.sort();
+        assert_eq!(sorted, [0, 1, 2, 3, 4]);
+        viewer.set_shuffle(false);
+        assert_eq!(viewer.order, [0, 1, 2, 3, 4]);
+        assert_eq!(viewer.path(), Path::new("2.png"));
+    }
+}
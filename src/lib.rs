@@ -1,16 +1,172 @@
-pub mod app;
-pub mod rg35xx;
-pub mod sim;
+//! The apps bundled with the launcher, on top of the framework in `core/`
+//! (the `rg35xx` crate).
 
+#[cfg(feature = "app-bomber")]
+mod bomber;
+#[cfg(feature = "app-checkers")]
+mod checkers;
+#[cfg(feature = "app-diagnostics")]
+mod diagnostics;
+#[cfg(feature = "app-display-test")]
+mod display_test;
+#[cfg(feature = "app-distance-integrator")]
 mod distance_integrator;
+#[cfg(feature = "app-dungeon")]
+mod dungeon;
+#[cfg(all(feature = "app-files", not(target_arch = "wasm32")))]
+mod files;
 mod menu;
+#[cfg(feature = "app-meteors")]
+mod meteors;
+#[cfg(all(feature = "news", not(target_arch = "wasm32")))]
+mod news;
+#[cfg(feature = "app-racer")]
+mod racer;
+#[cfg(feature = "app-reaction")]
+mod reaction;
+#[cfg(feature = "app-rhythm")]
+mod rhythm;
+mod settings;
+mod setup;
+#[cfg(feature = "app-simon")]
+mod simon;
+#[cfg(feature = "app-snake")]
 mod snake;
+#[cfg(feature = "app-solitaire")]
+mod solitaire;
+#[cfg(feature = "app-stats")]
+mod stats;
+#[cfg(feature = "app-sudoku")]
+mod sudoku;
+#[cfg(feature = "app-taboo")]
 mod taboo;
+#[cfg(feature = "app-timers")]
+mod timers;
+#[cfg(feature = "app-totp")]
+mod totp;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod updater;
+#[cfg(all(feature = "weather", not(target_arch = "wasm32")))]
+mod weather;
+#[cfg(feature = "app-words")]
+mod words;
 
-pub fn make_menu() -> impl crate::app::App {
+pub use menu::MenuApp;
+
+/// Taboo's default deck, which Words guesses from too, Solitaire's cards,
+/// Rhythm's beat maps and Racer's demo replay. Fonts are built into the
+/// framework.
+#[cfg(any(
+    feature = "app-taboo",
+    feature = "app-solitaire",
+    feature = "app-words",
+    feature = "app-rhythm",
+    feature = "app-racer"
+))]
+static ASSETS: include_dir::Dir = include_dir::include_dir!("$CARGO_MANIFEST_DIR/assets");
+
+pub fn make_menu() -> MenuApp {
+    #[cfg(any(
+        feature = "app-taboo",
+        feature = "app-solitaire",
+        feature = "app-words",
+        feature = "app-rhythm",
+        feature = "app-racer"
+    ))]
+    rg35xx::assets::add_embedded(&ASSETS);
     let mut menu = crate::menu::MenuApp::default();
-    menu.register_app::<crate::snake::SnakeApp, _>("Snake");
-    menu.register_app::<crate::taboo::TabooApp, _>("Taboo");
-    menu.register_app::<crate::distance_integrator::DistanceIntegrator, _>("Distance Integrator");
+    #[cfg(feature = "app-snake")]
+    menu.register_app_with("Snake", crate::snake::SnakeApp::from_args)
+        .with_manifest(crate::snake::MANIFEST);
+    #[cfg(feature = "app-taboo")]
+    menu.register_app::<crate::taboo::TabooApp, _>("Taboo")
+        .with_manifest(crate::taboo::MANIFEST);
+    #[cfg(feature = "app-simon")]
+    menu.register_app::<crate::simon::SimonApp, _>("Simon")
+        .with_manifest(crate::simon::MANIFEST);
+    #[cfg(feature = "app-solitaire")]
+    menu.register_app::<crate::solitaire::SolitaireApp, _>("Solitaire")
+        .with_manifest(crate::solitaire::MANIFEST);
+    #[cfg(feature = "app-sudoku")]
+    menu.register_app::<crate::sudoku::SudokuApp, _>("Sudoku")
+        .with_manifest(crate::sudoku::MANIFEST);
+    #[cfg(feature = "app-words")]
+    menu.register_app::<crate::words::WordsApp, _>("Words")
+        .with_manifest(crate::words::MANIFEST);
+    #[cfg(feature = "app-checkers")]
+    menu.register_app::<crate::checkers::CheckersApp, _>("Checkers")
+        .with_manifest(crate::checkers::MANIFEST);
+    #[cfg(feature = "app-meteors")]
+    menu.register_app::<crate::meteors::MeteorsApp, _>("Meteors")
+        .with_manifest(crate::meteors::MANIFEST);
+    #[cfg(feature = "app-racer")]
+    menu.register_app::<crate::racer::RacerApp, _>("Racer")
+        .with_manifest(crate::racer::MANIFEST);
+    #[cfg(feature = "app-rhythm")]
+    menu.register_app::<crate::rhythm::RhythmApp, _>("Rhythm")
+        .with_manifest(crate::rhythm::MANIFEST);
+    #[cfg(feature = "app-dungeon")]
+    menu.register_app::<crate::dungeon::DungeonApp, _>("Dungeon")
+        .with_manifest(crate::dungeon::MANIFEST);
+    #[cfg(feature = "app-bomber")]
+    menu.register_app::<crate::bomber::BomberApp, _>("Bomber")
+        .with_manifest(crate::bomber::MANIFEST);
+    #[cfg(feature = "app-distance-integrator")]
+    menu.register_app::<crate::distance_integrator::DistanceIntegrator, _>("Distance Integrator")
+        .with_manifest(crate::distance_integrator::MANIFEST);
+    menu.register_app::<crate::settings::SettingsApp, _>("Settings")
+        .with_manifest(crate::settings::MANIFEST);
+    #[cfg(feature = "app-stats")]
+    menu.register_app::<crate::stats::StatsApp, _>("Stats")
+        .with_manifest(crate::stats::MANIFEST);
+    #[cfg(feature = "app-reaction")]
+    menu.register_app::<crate::reaction::ReactionApp, _>("Reaction")
+        .with_manifest(crate::reaction::MANIFEST);
+    #[cfg(feature = "app-diagnostics")]
+    menu.register_app::<crate::diagnostics::DiagnosticsApp, _>("Controller Test")
+        .with_manifest(crate::diagnostics::MANIFEST);
+    #[cfg(feature = "app-display-test")]
+    menu.register_app::<crate::display_test::DisplayTestApp, _>("Display Test")
+        .with_manifest(crate::display_test::MANIFEST);
+    #[cfg(feature = "app-timers")]
+    menu.register_app::<crate::timers::TimersApp, _>("Timers")
+        .with_manifest(crate::timers::MANIFEST);
+    #[cfg(feature = "app-totp")]
+    menu.register_app::<crate::totp::TotpApp, _>("Authenticator")
+        .with_manifest(crate::totp::MANIFEST);
+    #[cfg(all(feature = "app-files", not(target_arch = "wasm32")))]
+    menu.register_app::<crate::files::FilesApp, _>("Files")
+        .with_manifest(crate::files::MANIFEST);
+    #[cfg(all(feature = "app-updater", not(target_arch = "wasm32")))]
+    menu.register_app::<crate::updater::UpdaterApp, _>("Updater")
+        .with_manifest(crate::updater::MANIFEST);
+    #[cfg(all(feature = "news", not(target_arch = "wasm32")))]
+    menu.register_app::<crate::news::NewsApp, _>("News")
+        .with_manifest(crate::news::MANIFEST);
+    #[cfg(all(feature = "weather", not(target_arch = "wasm32")))]
+    menu.register_app::<crate::weather::WeatherApp, _>("Weather")
+        .with_manifest(crate::weather::MANIFEST);
+    if let Some(retroarch) = find_in_path("retroarch") {
+        menu.register_command("RetroArch", retroarch, ["--menu"])
+            .with_manifest(rg35xx::app::Manifest::default().description("Emulator frontend"));
+    }
+    #[cfg(feature = "scripting")]
+    menu.register_scripts(&rg35xx::script::default_dir());
+    #[cfg(feature = "plugins")]
+    menu.register_plugins(&rg35xx::plugin::default_dir());
     menu
 }
+
+/// Looks up an executable on `$PATH`, like `which`
+fn find_in_path(program: &str) -> Option<std::path::PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(all(feature = "sim", target_arch = "wasm32"))]
+#[wasm_bindgen::prelude::wasm_bindgen(start)]
+pub fn start() -> Result<(), wasm_bindgen::JsValue> {
+    rg35xx::web::run_app(make_menu()).map_err(|e| e.to_string().into())
+}
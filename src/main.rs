@@ -1,38 +1,231 @@
 use clap::Parser;
-use enum_iterator::all;
-use palette::LinSrgb;
 
-use rg35xx::app::{App, Button, Frame, Input};
-
-struct InputTestApp;
-
-impl App for InputTestApp {
-    fn update(&mut self, input: &Input, frame: &mut Frame) {
-        frame.fill_rect(32, 32, 32, 32, LinSrgb::new(255, 255, 0));
-        for (i, button) in all::<Button>().enumerate() {
-            if input.just_pressed(button) {
-                frame.fill_rect(0, i * 32, 32, 32, LinSrgb::new(255, 0, 0));
-            }
-            if input.just_released(button) {
-                frame.fill_rect(0, i * 32, 32, 32, LinSrgb::new(0, 0, 0));
-            }
-        }
-    }
-}
+use rg35xx::app::AppArgs;
+#[cfg(feature = "sim")]
+use rg35xx::display::WindowSystem;
+use rg35xx::display::{parse_size, DisplayOptions, Rotation};
+#[cfg(any(
+    feature = "sim",
+    feature = "sdl2",
+    all(feature = "device", target_os = "linux")
+))]
+use rg35xx::Error;
 
 #[derive(Parser)]
 struct Args {
+    /// Run in a window instead of on the device
+    #[cfg(feature = "sim")]
     #[arg(long)]
     sim: bool,
+
+    /// Run with the SDL2 backend instead of the framebuffer
+    #[cfg(feature = "sdl2")]
+    #[arg(long)]
+    sdl: bool,
+
+    /// Rotate the output clockwise by 0, 90, 180 or 270 degrees, for panels
+    /// mounted sideways or upside-down
+    #[arg(long, default_value = "0")]
+    rotate: Rotation,
+
+    /// Simulated panel resolution, e.g. 480x320 or 720x720
+    #[arg(long, value_parser = parse_size)]
+    resolution: Option<(usize, usize)>,
+
+    /// Size of the simulator window, e.g. 1280x960. The panel is scaled to
+    /// fit. Defaults to the size it had when last closed.
+    #[cfg(feature = "sim")]
+    #[arg(long, value_name = "WxH", value_parser = parse_size)]
+    window: Option<(usize, usize)>,
+
+    /// Run the simulator borderless on the whole screen
+    #[cfg(feature = "sim")]
+    #[arg(long)]
+    fullscreen: bool,
+
+    /// Open the simulator with wayland, x11 or whichever is running (auto)
+    #[cfg(feature = "sim")]
+    #[arg(long = "display", value_name = "SYSTEM", default_value = "auto")]
+    window_system: WindowSystem,
+
+    /// Look for fonts and other assets here before the built-in ones, instead
+    /// of `assets/` next to the binary
+    #[arg(long, value_name = "DIR")]
+    assets: Option<std::path::PathBuf>,
+
+    /// Launch directly into the named app instead of the menu
+    #[arg(long)]
+    app: Option<String>,
+
+    /// Arguments passed to the app given by --app, as key=value
+    #[arg(long = "arg", value_parser = parse_key_value)]
+    args: Vec<(String, String)>,
+
+    /// Seed apps' random numbers, so a run can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Serve a controller page for phones on this port
+    #[cfg(feature = "remote")]
+    #[arg(long, value_name = "PORT")]
+    remote: Option<u16>,
+
+    /// Run a plugin in the simulator, reloading it whenever it's rebuilt
+    #[cfg(all(feature = "plugins", feature = "sim"))]
+    #[arg(long, value_name = "PLUGIN.so")]
+    hot_reload: Option<std::path::PathBuf>,
+}
+
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got `{s}`"))?;
+    Ok((key.to_string(), value.to_string()))
 }
 
 fn main() {
+    // Before anything else, so an update never runs alongside the old binary
+    rg35xx_apps::updater::apply_staged();
     let args = Args::parse();
+    #[cfg_attr(not(feature = "sim"), allow(unused_mut))]
+    let mut display = DisplayOptions {
+        rotation: args.rotate,
+        size: args.resolution,
+        ..Default::default()
+    };
+    #[cfg(feature = "sim")]
+    {
+        display.window = args.window;
+        display.fullscreen = args.fullscreen;
+        display.window_system = args.window_system;
+    }
 
-    let app = rg35xx::make_menu();
+    if let Some(dir) = args.assets {
+        rg35xx::assets::set_dir(dir);
+    }
+    if let Some(seed) = args.seed {
+        rg35xx::random::set_seed(seed);
+    }
+
+    #[cfg(all(feature = "plugins", feature = "sim"))]
+    if let Some(path) = args.hot_reload {
+        exit_on_error(rg35xx::sim::run_app(
+            rg35xx::plugin::HotReload::new(path),
+            display,
+        ));
+        return;
+    }
+
+    #[cfg(feature = "remote")]
+    if let Some(port) = args.remote {
+        match rg35xx::remote::start(port) {
+            Ok(()) => match rg35xx::remote::page_url() {
+                Some(url) => println!("Remote controller at {}", url),
+                None => println!("Remote controller on port {}, with no address yet", port),
+            },
+            Err(e) => eprintln!("Couldn't start the remote controller: {}", e),
+        }
+    }
+
+    let app_args = args.args.into_iter().collect::<AppArgs>();
+    // May be needed twice, if the device can't be used and the simulator
+    // takes over
+    let make_app = || {
+        let mut app = rg35xx_apps::make_menu();
+        if let Some(name) = args.app.as_deref() {
+            if !app.launch(name, &app_args) {
+                eprintln!(
+                    "Unknown app `{}`, expected one of: {}",
+                    name,
+                    app.app_names().collect::<Vec<_>>().join(", ")
+                );
+                std::process::exit(1);
+            }
+        }
+        app
+    };
+    #[cfg(feature = "sdl2")]
+    if args.sdl {
+        exit_on_error(rg35xx::sdl::run_app(make_app(), display));
+        return;
+    }
+    #[cfg(feature = "sim")]
     if args.sim {
-        rg35xx::sim::run_app(app);
-    } else {
-        rg35xx::rg35xx::run_app(app);
+        exit_on_error(rg35xx::sim::run_app(make_app(), display));
+        return;
+    }
+    #[cfg(all(feature = "device", target_os = "linux"))]
+    {
+        let result = rg35xx::rg35xx::run_app(make_app(), display);
+        // On a desktop, most likely run without --sim by mistake
+        #[cfg(feature = "sim")]
+        if let Err(e @ Error::Framebuffer { .. }) = &result {
+            if std::env::var_os("DISPLAY").is_some()
+                || std::env::var_os("WAYLAND_DISPLAY").is_some()
+            {
+                eprintln!("{}", explain(e));
+                eprintln!("Running in the simulator instead");
+                exit_on_error(rg35xx::sim::run_app(make_app(), display));
+                return;
+            }
+        }
+        exit_on_error(result);
+        // Only reached after a clean quit, so a crash is never hidden by it
+        rg35xx::rg35xx::run_on_exit();
+    }
+    // Without the device backend, e.g. on macOS and Windows, the simulator
+    // is all there is
+    #[cfg(all(not(all(feature = "device", target_os = "linux")), feature = "sim"))]
+    exit_on_error(rg35xx::sim::run_app(make_app(), display));
+    #[cfg(not(any(all(feature = "device", target_os = "linux"), feature = "sim")))]
+    {
+        let _ = (make_app, display);
+        eprintln!("Built without the device backend or the simulator");
+        std::process::exit(1);
+    }
+}
+
+/// A backend's error as a sentence, with what to try instead
+#[cfg(any(
+    feature = "sim",
+    feature = "sdl2",
+    all(feature = "device", target_os = "linux")
+))]
+fn explain(error: &Error) -> String {
+    match error {
+        Error::Framebuffer { path, message } => {
+            let mut text = format!(
+                "Couldn't open {} — are you running on the device? ({})",
+                path, message
+            );
+            if cfg!(feature = "sim") {
+                text.push_str("\nPass --sim to run in a window instead.");
+            }
+            text
+        }
+        Error::Window(_) => {
+            let mut text = format!("{} — is a display available?", error);
+            if cfg!(target_os = "linux") {
+                text.push_str("\nPass --display x11 or --display wayland to pick one.");
+            }
+            if cfg!(feature = "sdl2") {
+                text.push_str("\nPass --sdl to try the SDL2 backend instead.");
+            }
+            text
+        }
+        Error::Present(_) => error.to_string(),
+        Error::Sdl(_) => format!("{} — is SDL2 installed?", error),
+    }
+}
+
+#[cfg(any(
+    feature = "sim",
+    feature = "sdl2",
+    all(feature = "device", target_os = "linux")
+))]
+fn exit_on_error(result: Result<(), Error>) {
+    if let Err(e) = result {
+        eprintln!("{}", explain(&e));
+        std::process::exit(1);
     }
 }
@@ -1,10 +1,90 @@
-use palette::LinSrgb;
+use std::{path::PathBuf, process::Command};
 
-use crate::app::{App, Button};
+use crate::setup::Setup;
+use rg35xx::actions::{Action, ActionMap};
+use rg35xx::anim::{Easing, Tween};
+use rg35xx::app::{Activity, App, AppArgs, AppContext, Button, Manifest, PowerHint};
+use rg35xx::config::{config, set_config, ThemeColors};
+use rg35xx::demo::{Demo, Replay};
+use rg35xx::events::{
+    publish, AchievementUnlocked, AutoPause, BatteryLow, ControllerConnected,
+    ControllerDisconnected, NetworkChanged, Overheating, Subscription,
+};
+use rg35xx::lock::{PinEvent, PinPad};
+use rg35xx::memory::Span;
+use rg35xx::ninepatch::draw_panel;
+use rg35xx::overlay::{DebugOverlay, Toast};
+use rg35xx::storage::{record_usage, AppUsage};
+use rg35xx::system::Watcher;
+use rg35xx::tasks::Tasks;
+use rg35xx::time::{Duration, Instant, SystemTime};
+use rg35xx::transition::{TransitionKind, Transitions};
 
-struct MenuEntry {
+type AppBuilder = Box<dyn Fn(&AppArgs) -> Box<dyn App>>;
+
+/// How long the highlight takes to slide to a newly selected entry
+const CURSOR_TIME: Duration = Duration::from_millis(150);
+
+/// How long each app's demo plays in attract mode before the next
+const DEMO_TIME: Duration = Duration::from_secs(30);
+
+enum Launch {
+    App(AppBuilder),
+    /// An external program, run by the backend in place of the launcher
+    Command {
+        program: PathBuf,
+        args: Vec<String>,
+    },
+}
+
+/// What happens once the PIN's been entered
+enum AfterPin {
+    /// Start the entry at this index
+    Launch(usize),
+    /// Stop the entry at this index needing the PIN
+    Unlock(usize),
+}
+
+pub struct MenuEntry {
     name: String,
-    builder: Box<dyn Fn() -> Box<dyn App>>,
+    launch: Launch,
+    manifest: Manifest,
+}
+
+impl MenuEntry {
+    fn new(name: String, launch: Launch) -> Self {
+        Self {
+            name,
+            launch,
+            manifest: Manifest::default(),
+        }
+    }
+
+    pub fn with_manifest(&mut self, manifest: Manifest) -> &mut Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Lowercase, dash-separated form of the name used for deep links, e.g.
+    /// "Distance Integrator" becomes "distance-integrator"
+    fn slug(&self) -> String {
+        self.name
+            .split_whitespace()
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+}
+
+/// The framework's events the menu tells the player about
+#[derive(Default)]
+struct MenuEvents {
+    achievements: Subscription<AchievementUnlocked>,
+    battery: Subscription<BatteryLow>,
+    heat: Subscription<Overheating>,
+    connected: Subscription<ControllerConnected>,
+    disconnected: Subscription<ControllerDisconnected>,
+    network: Subscription<NetworkChanged>,
 }
 
 #[derive(Default)]
@@ -12,49 +92,791 @@ pub struct MenuApp {
     apps: Vec<MenuEntry>,
     current_app: usize,
     app: Option<Box<dyn App>>,
+    /// The running app's background work, given a fresh pool each launch so
+    /// leaving an app shuts down whatever it left behind
+    app_tasks: Tasks,
+    pending_command: Option<Command>,
+    showing_about: bool,
+    /// The achievements gallery, scrolled down this many lines
+    showing_achievements: Option<usize>,
+    transitions: Transitions,
+    debug_overlay: DebugOverlay,
+    toast: Toast,
+    watcher: Watcher,
+    events: MenuEvents,
+    /// Assets an app asked for that couldn't be found, shown until dismissed
+    missing_assets: Vec<String>,
+    /// Loaded when first needed, and again after leaving an app in case the
+    /// controls were changed
+    actions: Option<ActionMap>,
+    /// The splash and first-run wizard shown before the menu
+    setup: Setup,
+    /// Asking for the PIN before doing something to a locked entry
+    pin_prompt: Option<(AfterPin, PinPad)>,
+    /// When the running app was started, for the usage stats
+    app_started: Option<Instant>,
+    /// The heap the running app's used, logged when it exits
+    app_memory: Option<Span>,
+    /// An app playing itself in attract mode, and its index
+    demo: Option<(usize, Demo)>,
+    /// When the menu was last touched, for starting demos once it's been
+    /// left alone for [`Config::attract`](rg35xx::config::Config::attract)
+    last_input: Option<Instant>,
+    /// The baseline of the row the highlight is behind, sliding between
+    /// rows. Made when the menu's first drawn.
+    cursor: Option<Tween<f32>>,
+    /// The first entry on screen, moved so the selected one stays in view
+    scroll: usize,
 }
 
 impl MenuApp {
-    pub fn register_app<A: 'static + Default + App, S: Into<String>>(&mut self, name: S) {
-        self.apps.push(MenuEntry {
-            name: name.into(),
-            builder: Box::new(|| Box::<A>::default()),
-        });
+    pub fn register_app<A: 'static + Default + App, S: Into<String>>(
+        &mut self,
+        name: S,
+    ) -> &mut MenuEntry {
+        self.push_entry(MenuEntry::new(
+            name.into(),
+            Launch::App(Box::new(|_| Box::<A>::default())),
+        ))
     }
-}
 
-impl App for MenuApp {
-    fn update(&mut self, input: &crate::app::Input, frame: &mut crate::app::Frame) {
-        if let Some(app) = self.app.as_mut() {
-            app.update(input, frame);
+    /// Registers an app whose builder receives the arguments it was launched with
+    pub fn register_app_with<S, F, A>(&mut self, name: S, builder: F) -> &mut MenuEntry
+    where
+        S: Into<String>,
+        F: 'static + Fn(&AppArgs) -> A,
+        A: 'static + App,
+    {
+        self.push_entry(MenuEntry::new(
+            name.into(),
+            Launch::App(Box::new(move |args| Box::new(builder(args)))),
+        ))
+    }
+
+    /// Registers an entry that runs an external program (e.g. RetroArch with
+    /// a ROM) in place of the launcher, returning to the menu when it exits
+    pub fn register_command<S, P, I, A>(&mut self, name: S, program: P, args: I) -> &mut MenuEntry
+    where
+        S: Into<String>,
+        P: Into<PathBuf>,
+        I: IntoIterator<Item = A>,
+        A: Into<String>,
+    {
+        self.push_entry(MenuEntry::new(
+            name.into(),
+            Launch::Command {
+                program: program.into(),
+                args: args.into_iter().map(Into::into).collect(),
+            },
+        ))
+    }
+
+    /// Adds every loadable plugin found in `dir` after the built-in apps
+    #[cfg(feature = "plugins")]
+    pub fn register_plugins(&mut self, dir: &std::path::Path) {
+        for plugin in rg35xx::plugin::discover(dir) {
+            let name = plugin.name().to_string();
+            println!("Loaded plugin {} from {}", name, plugin.path().display());
+            self.push_entry(MenuEntry::new(
+                name,
+                Launch::App(Box::new(move |_| Box::new(plugin.instantiate()))),
+            ))
+            .with_manifest(Manifest::default().description("Plugin"));
+        }
+    }
+
+    /// Adds an entry for every Lua script in `dir`, named after the file
+    #[cfg(feature = "scripting")]
+    pub fn register_scripts(&mut self, dir: &std::path::Path) {
+        for path in rg35xx::script::discover(dir) {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            // Scripts are re-read on every launch, so edits show up without a restart
+            self.push_entry(MenuEntry::new(
+                name,
+                Launch::App(Box::new(move |_| {
+                    Box::new(rg35xx::script::ScriptApp::load(&path))
+                })),
+            ))
+            .with_manifest(Manifest::default().description("Lua script"));
+        }
+    }
+
+    fn push_entry(&mut self, entry: MenuEntry) -> &mut MenuEntry {
+        self.apps.push(entry);
+        self.apps.last_mut().unwrap()
+    }
+
+    /// Starts the app registered under `name` (either its display name or its
+    /// slug) directly, skipping the menu. Returns false if no such app exists.
+    pub fn launch(&mut self, name: &str, args: &AppArgs) -> bool {
+        let Some(index) = self
+            .apps
+            .iter()
+            .position(|app| app.name.eq_ignore_ascii_case(name) || app.slug() == name)
+        else {
+            return false;
+        };
+        self.current_app = index;
+        self.setup.finish();
+        self.start(index, args);
+        true
+    }
+
+    fn start(&mut self, index: usize, args: &AppArgs) {
+        let launch = AppUsage {
+            launches: 1,
+            seconds: 0,
+        };
+        record_usage(&self.apps[index].name, launch);
+        match &self.apps[index].launch {
+            Launch::App(builder) => {
+                // Before it's built, so what it loads up front counts too
+                self.app_memory = Some(Span::start());
+                self.app = Some(builder(args));
+                self.app_tasks = Tasks::default();
+                self.app_started = Some(Instant::now());
+            }
+            Launch::Command { program, args } => {
+                let mut command = Command::new(program);
+                command.args(args);
+                self.pending_command = Some(command);
+            }
+        }
+    }
+
+    /// Plays the demo of the first entry after `after` that has one, going
+    /// round to the start. Entries behind the PIN are skipped. Returns
+    /// whether there was one to play.
+    fn start_demo(&mut self, after: usize) -> bool {
+        let count = self.apps.len();
+        for index in (1..=count).map(|i| (after + i) % count) {
+            let entry = &self.apps[index];
+            let Launch::App(builder) = &entry.launch else {
+                continue;
+            };
+            if rg35xx::lock::is_locked(&entry.name) {
+                continue;
+            }
+            let path = format!("demos/{}.txt", entry.slug());
+            let replay = match rg35xx::assets::exists(&path) {
+                false => None,
+                true => match rg35xx::assets::load(&path)
+                    .and_then(|bytes| String::from_utf8_lossy(&bytes).parse::<Replay>())
+                {
+                    Ok(replay) => Some(replay),
+                    Err(e) => {
+                        println!("Couldn't read {}: {}", path, e);
+                        continue;
+                    }
+                },
+            };
+            if replay.is_some() || entry.manifest.demo {
+                self.demo = Some((index, Demo::new(builder(&AppArgs::new()), replay)));
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether the menu's been left alone long enough to play demos
+    fn attract_due(&self) -> Option<Instant> {
+        let after = config().attract?;
+        let modal = self.showing_about
+            || self.showing_achievements.is_some()
+            || self.pin_prompt.is_some()
+            || !self.missing_assets.is_empty();
+        match modal {
+            true => None,
+            false => Some(self.last_input? + after),
+        }
+    }
+
+    fn update_demo(&mut self, input: &rg35xx::app::Input, frame: &mut rg35xx::app::Frame) {
+        let Some((index, demo)) = self.demo.as_mut() else {
             return;
+        };
+        // Any button stops it, without doing anything else
+        if input.any_changed() {
+            self.demo = None;
+            self.last_input = Some(Instant::now());
+            self.transitions.start(frame);
+            return;
+        }
+        if demo.elapsed() >= DEMO_TIME {
+            let index = *index;
+            if !self.start_demo(index) {
+                self.demo = None;
+                self.last_input = Some(Instant::now());
+            }
+            self.transitions.start(frame);
+        }
+        if let Some((_, demo)) = self.demo.as_mut() {
+            demo.update(&mut AppContext::new(input, frame, &mut self.app_tasks));
         }
+    }
+
+    /// Sets the animation used when entering and leaving apps. A zero duration
+    /// disables it.
+    pub fn set_transition(&mut self, kind: TransitionKind, duration: Duration) {
+        self.transitions = Transitions::new(kind, duration);
+    }
+
+    pub fn app_names(&self) -> impl Iterator<Item = &str> {
+        self.apps.iter().map(|app| app.name.as_str())
+    }
+
+    fn draw_about(
+        &self,
+        frame: &mut rg35xx::app::Frame,
+        actions: &ActionMap,
+        colors: &ThemeColors,
+    ) {
+        let mut ctx = frame.context();
+        ctx.set_color(colors.selected);
+        ctx.offset(50, 50);
+        ctx.set_fontsize(36.0);
+        ctx.text("About");
 
-        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+        ctx.set_fontsize(18.0);
+        ctx.offset(0, 30);
+        ctx.text(&format!(
+            "{} v{}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ));
+        ctx.offset(0, 20);
+        ctx.text(&format!(
+            "Built for {}-{} ({})",
+            std::env::consts::ARCH,
+            std::env::consts::OS,
+            if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            },
+        ));
 
-        for (i, app) in self.apps.iter().enumerate() {
+        ctx.offset(0, 40);
+        ctx.text("Registered apps:");
+        ctx.set_color(colors.dim);
+        for entry in self.apps.iter() {
+            ctx.offset(0, 20);
+            let mut line = entry.name.clone();
+            if !entry.manifest.version.is_empty() {
+                line += &format!(" v{}", entry.manifest.version);
+            }
+            if !entry.manifest.author.is_empty() {
+                line += &format!(" by {}", entry.manifest.author);
+            }
+            ctx.text(&line);
+        }
+
+        let hint = format!(
+            "{} or {} to close",
+            actions.label(Action::Info),
+            actions.label(Action::Back)
+        );
+        ctx.set_color(colors.selected);
+        ctx.offset(0, 40);
+        ctx.text(&hint);
+    }
+
+    /// Asks for the PIN if one's needed, then does `after`
+    fn with_pin(&mut self, after: AfterPin, frame: &mut rg35xx::app::Frame) {
+        let (AfterPin::Launch(index) | AfterPin::Unlock(index)) = after;
+        if rg35xx::lock::is_locked(&self.apps[index].name) {
+            let title = format!("PIN for {}", self.apps[index].name);
+            self.pin_prompt = Some((after, PinPad::new(&title)));
+        } else {
+            self.after_pin(after, frame);
+        }
+    }
+
+    fn after_pin(&mut self, after: AfterPin, frame: &mut rg35xx::app::Frame) {
+        match after {
+            AfterPin::Launch(index) => {
+                self.start(index, &AppArgs::new());
+                if self.app.is_some() {
+                    self.transitions.start(frame);
+                }
+            }
+            AfterPin::Unlock(index) => {
+                let name = &self.apps[index].name;
+                let mut config = config();
+                config.locked.retain(|locked| locked != name);
+                match set_config(config) {
+                    Ok(()) => self.toast.show(&format!("{} unlocked", name)),
+                    Err(e) => self.toast.show(&format!("Couldn't save: {}", e)),
+                }
+            }
+        }
+    }
+
+    /// X locks the highlighted entry, or unlocks it with the PIN
+    fn toggle_lock(&mut self, frame: &mut rg35xx::app::Frame) {
+        let mut config = config();
+        let name = self.apps[self.current_app].name.clone();
+        if config.pin.is_none() {
+            self.toast.show("Set a PIN in Settings first");
+        } else if rg35xx::lock::is_locked(&name) {
+            self.with_pin(AfterPin::Unlock(self.current_app), frame);
+        } else {
+            config.locked.push(name.clone());
+            match set_config(config) {
+                Ok(()) => self.toast.show(&format!("{} locked", name)),
+                Err(e) => self.toast.show(&format!("Couldn't save: {}", e)),
+            }
+        }
+    }
+
+    /// Every app's achievements, with the ones unlocked in the current profile
+    /// highlighted
+    fn draw_achievements(
+        &mut self,
+        input: &rg35xx::app::Input,
+        frame: &mut rg35xx::app::Frame,
+        colors: &ThemeColors,
+    ) {
+        let mut lines = vec![];
+        let (mut unlocked, mut total) = (0, 0);
+        for entry in self
+            .apps
+            .iter()
+            .filter(|entry| !entry.manifest.achievements.is_empty())
+        {
+            lines.push((entry.name.clone(), colors.selected));
+            for achievement in entry.manifest.achievements {
+                total += 1;
+                let line = match rg35xx::achievements::unlocked_on(achievement.id) {
+                    Some(date) => {
+                        unlocked += 1;
+                        (
+                            format!("  {} ({})", achievement.title, date),
+                            colors.selected,
+                        )
+                    }
+                    None => (
+                        format!("  {}: {}", achievement.title, achievement.description),
+                        colors.dim,
+                    ),
+                };
+                lines.push(line);
+            }
+        }
+
+        // Up and down scroll, keeping the last line on screen
+        let rows = (frame.height().saturating_sub(120) / 28).max(1);
+        let scroll = self.showing_achievements.get_or_insert(0);
+        if input.repeated(Button::PovUp) {
+            *scroll = scroll.saturating_sub(1);
+        }
+        if input.repeated(Button::PovDown) {
+            *scroll += 1;
+        }
+        *scroll = (*scroll).min(lines.len().saturating_sub(rows));
+
+        let mut ctx = frame.context();
+        ctx.offset(50, 50);
+        ctx.set_color(colors.selected);
+        ctx.set_fontsize(36.0);
+        ctx.text(&format!("Achievements {}/{}", unlocked, total));
+        ctx.set_fontsize(20.0);
+        ctx.offset(0, 10);
+        for (line, color) in lines.iter().skip(*scroll).take(rows) {
+            ctx.offset(0, 28);
+            ctx.set_color(*color);
+            ctx.text(line);
+        }
+    }
+
+    fn actions(&mut self) -> &ActionMap {
+        self.actions.get_or_insert_with(ActionMap::global)
+    }
+
+    fn update_menu(&mut self, input: &rg35xx::app::Input, frame: &mut rg35xx::app::Frame) {
+        let config = config();
+        let colors = config.theme.colors();
+        frame.fill_rect(0, 0, frame.width(), frame.height(), colors.background);
+
+        let actions = self.actions().clone();
+        if self.showing_about {
+            self.draw_about(frame, &actions, &colors);
+            if actions.just_pressed(input, Action::Info)
+                || actions.just_pressed(input, Action::Back)
+                || input.tapped().is_some()
+            {
+                self.showing_about = false;
+            }
+            return;
+        }
+
+        if self.showing_achievements.is_some() {
+            self.draw_achievements(input, frame, &colors);
+            if input.just_pressed(Button::ActionH) || actions.just_pressed(input, Action::Back) {
+                self.showing_achievements = None;
+            }
+            return;
+        }
+
+        if let Some((after, mut pad)) = self.pin_prompt.take() {
+            match pad.update(input, &actions, frame) {
+                Some(PinEvent::Entered(pin)) if rg35xx::lock::check(&pin) => {
+                    self.after_pin(after, frame)
+                }
+                Some(PinEvent::Entered(_)) => {
+                    pad.reject("Wrong PIN");
+                    self.pin_prompt = Some((after, pad));
+                }
+                Some(PinEvent::Cancelled) => (),
+                None => self.pin_prompt = Some((after, pad)),
+            }
+            return;
+        }
+
+        // Each row is 40px tall at the default UI scale, with the text's
+        // baseline 10px from the bottom
+        let scaled = |size: f32| config.scaled(size).round() as usize;
+        let (row_height, ascent) = (scaled(40.0), scaled(30.0));
+        let baseline = |i: usize| 20 + ascent + i * row_height;
+        // The rows that fit between the header and the footer
+        let visible_rows = (frame.height().saturating_sub(60) / row_height).max(1);
+        if self.current_app < self.scroll {
+            self.scroll = self.current_app;
+        } else if self.current_app >= self.scroll + visible_rows {
+            self.scroll = self.current_app + 1 - visible_rows;
+        }
+        self.scroll = self
+            .scroll
+            .min(self.apps.len().saturating_sub(visible_rows));
+
+        // The highlight behind the selected entry, which slides between rows
+        let row_y = baseline(self.current_app - self.scroll) as f32;
+        let cursor = self
+            .cursor
+            .get_or_insert_with(|| Tween::new(row_y, CURSOR_TIME, Easing::CubicOut));
+        cursor.to(row_y);
+        cursor.update(input.delta());
+        draw_panel(
+            frame,
+            186,
+            cursor.value() as usize - scaled(33.0),
+            frame.width().saturating_sub(186 + 40),
+            scaled(42.0),
+        );
+
+        let shown = self.apps.iter().enumerate().skip(self.scroll);
+        for (i, app) in shown.take(visible_rows) {
+            if config.pin.is_some() && config.locked.contains(&app.name) {
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    150,
+                    baseline(i - self.scroll),
+                    config.scaled(18.0),
+                    colors.dim,
+                    "PIN",
+                );
+            }
             frame.text(
                 "fonts/Ubuntu-B.ttf",
                 200,
-                50 + i * 40,
-                36.0,
+                baseline(i - self.scroll),
+                config.scaled(36.0),
                 if i == self.current_app {
-                    LinSrgb::new(255, 255, 255)
+                    colors.selected
                 } else {
-                    LinSrgb::new(255, 0, 0)
+                    colors.text
                 },
                 &app.name,
             );
         }
-        if input.just_pressed(Button::PovUp) {
+
+        // The profile picker, switched with L1 and R1
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            20,
+            30,
+            config.scaled(20.0),
+            colors.dim,
+            &format!("L1 {} R1", config.profile),
+        );
+        let step = i32::from(input.just_pressed(Button::BumperR))
+            - i32::from(input.just_pressed(Button::BumperL));
+        // SELECT+R1 is the debug overlay instead
+        if step != 0 && !input.pressed(Button::MenuL) {
+            let profiles = rg35xx::profiles::list();
+            let current = profiles
+                .iter()
+                .position(|name| *name == config.profile)
+                .unwrap_or(0) as i32;
+            let next = &profiles[(current + step).rem_euclid(profiles.len() as i32) as usize];
+            match rg35xx::profiles::switch(next) {
+                Ok(()) => self.toast.show(&format!("Playing as {}", next)),
+                Err(e) => self.toast.show(&format!("Couldn't switch profile: {}", e)),
+            }
+        }
+
+        let clock = config.clock(SystemTime::now());
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            frame.width().saturating_sub(scaled(90.0)),
+            30,
+            config.scaled(20.0),
+            colors.dim,
+            &clock,
+        );
+
+        // Footer with the highlighted app's description
+        if let Some(entry) = self.apps.get(self.current_app) {
+            let footer_y = frame.height() as i32 - 20;
+            let mut ctx = frame.context();
+            ctx.offset(20, footer_y);
+            ctx.set_color(colors.dim);
+            ctx.set_fontsize(config.scaled(18.0));
+            if entry.manifest.version.is_empty() {
+                ctx.text(entry.manifest.description);
+            } else {
+                ctx.text(&format!(
+                    "v{} - {}",
+                    entry.manifest.version, entry.manifest.description
+                ));
+            }
+        }
+
+        if actions.just_pressed(input, Action::Info) {
+            self.showing_about = true;
+        }
+        if actions.repeated(input, Action::Up) {
             self.current_app = self.current_app.saturating_sub(1);
         }
-        if input.just_pressed(Button::PovDown) {
+        if actions.repeated(input, Action::Down) {
             self.current_app = (self.current_app + 1).min(self.apps.len() - 1);
         }
-        if input.just_pressed(Button::ActionA) {
-            // Start the app...
-            self.app = Some((self.apps[self.current_app].builder)());
+        // Tapping an app selects it, and tapping it again starts it
+        let mut tapped_current = false;
+        if let Some((_, y)) = input.tapped() {
+            let row = y
+                .checked_sub(20)
+                .map(|y| y / row_height)
+                .filter(|&row| row < visible_rows)
+                .map(|row| self.scroll + row);
+            if let Some(row) = row.filter(|&row| row < self.apps.len()) {
+                tapped_current = row == self.current_app;
+                self.current_app = row;
+            }
+        }
+        if input.just_pressed(Button::ActionV) {
+            self.toggle_lock(frame);
+        }
+        if input.just_pressed(Button::ActionH) {
+            self.showing_achievements = Some(0);
+        }
+
+        if actions.just_pressed(input, Action::Confirm) || tapped_current {
+            // Start the app, once the PIN's been given if it needs one
+            self.with_pin(AfterPin::Launch(self.current_app), frame);
+        }
+    }
+
+    /// Shows the framework's events as toasts, and pauses the running game
+    /// when the player might not be able to carry on
+    fn announce_events(&mut self) {
+        for AchievementUnlocked { id } in self.events.achievements.take() {
+            let achievement = self
+                .apps
+                .iter()
+                .flat_map(|entry| entry.manifest.achievements)
+                .find(|achievement| achievement.id == id);
+            match achievement {
+                Some(achievement) => self
+                    .toast
+                    .show(&format!("Achievement unlocked: {}", achievement.title)),
+                None => println!("Unlocked achievement {} isn't in any manifest", id),
+            }
+        }
+        for Overheating { celsius } in self.events.heat.take() {
+            println!("Warning: the SoC is at {:.1} °C", celsius);
+            self.toast.show(&format!("Running hot ({:.0} °C)", celsius));
+        }
+        for ControllerConnected { player, name } in self.events.connected.take() {
+            // The built-in controls are always there
+            if player > 0 {
+                self.toast
+                    .show(&format!("Player {} connected: {}", player + 1, name));
+            }
+        }
+        let mut pause = false;
+        for ControllerDisconnected { player } in self.events.disconnected.take() {
+            self.toast
+                .show(&format!("Player {} disconnected", player + 1));
+            pause = true;
+        }
+        for BatteryLow { percent } in self.events.battery.take() {
+            self.toast.show(&format!("Battery low ({}%)", percent));
+            pause = true;
+        }
+        for NetworkChanged { online } in self.events.network.take() {
+            self.toast.show(match online {
+                true => "Connected to the network",
+                false => "Lost the network connection",
+            });
+        }
+        if pause && self.app.is_some() {
+            publish(AutoPause);
+        }
+    }
+}
+
+impl App for MenuApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        // Got this far, so an update that was just installed works
+        #[cfg(not(target_arch = "wasm32"))]
+        crate::updater::confirm_update();
+        // SELECT+R1 toggles the debug overlay, in apps or the menu
+        if input.pressed(Button::MenuL) && input.just_pressed(Button::BumperR) {
+            self.debug_overlay.toggle();
+        }
+
+        if !self.setup.is_finished() && self.app.is_none() {
+            if self.setup.update(input, frame) {
+                // The wizard may have swapped confirm and back
+                self.actions = None;
+                self.transitions.start(frame);
+            }
+            return;
+        }
+
+        if let Some(app) = self.app.as_mut() {
+            // SELECT+START leaves the app, or it can finish by itself
+            let chord = input.pressed(Button::MenuL) && input.pressed(Button::MenuR);
+            let chorded =
+                chord && (input.just_pressed(Button::MenuL) || input.just_pressed(Button::MenuR));
+            if chorded || app.is_finished() {
+                if let Some(started) = self.app_started.take() {
+                    let played = AppUsage {
+                        launches: 0,
+                        seconds: started.elapsed().as_secs(),
+                    };
+                    record_usage(&self.apps[self.current_app].name, played);
+                }
+                app.on_exit();
+                self.app = None;
+                self.app_tasks.shutdown();
+                // After it's dropped, so what's still held was leaked or
+                // kept somewhere global
+                let name = &self.apps[self.current_app].name;
+                if let Some(report) = self.app_memory.take().and_then(|span| span.report(name)) {
+                    println!("{}", report);
+                }
+                self.last_input = None;
+                self.actions = None;
+                self.transitions.start(frame);
+                return;
+            }
+            let start = Instant::now();
+            app.update(&mut AppContext::new(input, frame, &mut self.app_tasks));
+            self.debug_overlay.record_update(start.elapsed());
+        } else if self.demo.is_some() {
+            self.update_demo(input, frame);
+        } else {
+            if input.any_changed() || self.last_input.is_none() {
+                self.last_input = Some(Instant::now());
+            }
+            self.update_menu(input, frame);
+            #[cfg(feature = "remote")]
+            rg35xx::remote::publish(Default::default());
+            if self.attract_due().is_some_and(|due| Instant::now() >= due) {
+                match self.start_demo(self.current_app) {
+                    true => self.transitions.start(frame),
+                    // Nothing has a demo, so wait as long again before
+                    // looking, rather than looking every frame
+                    false => self.last_input = Some(Instant::now()),
+                }
+            }
+        }
+
+        self.watcher.poll();
+        self.announce_events();
+
+        self.missing_assets.extend(rg35xx::assets::take_missing());
+        if !self.missing_assets.is_empty() {
+            rg35xx::assets::draw_error(frame, &self.missing_assets);
+            if self.actions().just_pressed(input, Action::Confirm) || input.tapped().is_some() {
+                self.missing_assets.clear();
+            }
+        }
+        self.transitions.draw(frame);
+        self.debug_overlay.draw(frame);
+        self.toast.draw(frame);
+    }
+
+    fn resolution(&self) -> Option<(usize, usize)> {
+        if let Some(app) = self.app.as_ref() {
+            return app.resolution();
+        }
+        self.demo
+            .as_ref()
+            .and_then(|(_, demo)| demo.app().resolution())
+    }
+
+    fn pending_command(&mut self) -> Option<Command> {
+        if let Some(app) = self.app.as_mut() {
+            return app.pending_command();
+        }
+        self.pending_command.take()
+    }
+
+    fn activity(&self) -> Activity {
+        if self.transitions.is_active() || self.debug_overlay.visible || self.toast.is_active() {
+            return Activity::Busy;
+        }
+        if self.setup.is_busy() {
+            return Activity::Busy;
+        }
+        let sliding = self
+            .cursor
+            .as_ref()
+            .is_some_and(|cursor| cursor.is_active());
+        if self.app.is_none() && sliding {
+            return Activity::Busy;
+        }
+        let activity = match self.app.as_ref() {
+            Some(app) => app.activity(),
+            // Demos press their own buttons, which needs updates to do
+            None if self.demo.is_some() => Activity::Busy,
+            // The menu only changes when a button does, or the clock, or
+            // when it's time for the demos
+            None => {
+                let secs = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                Activity::Idle(Instant::now() + Duration::from_secs(60 - secs % 60))
+                    .wake_by(self.attract_due())
+            }
+        };
+
+        // Taps from the phone only arrive when the backend polls for input
+        #[cfg(feature = "remote")]
+        if rg35xx::remote::is_running() {
+            let poll = Instant::now() + rg35xx::remote::POLL_INTERVAL;
+            return match activity {
+                Activity::Idle(until) if until < poll => activity,
+                Activity::Busy => activity,
+                _ => Activity::Idle(poll),
+            };
+        }
+        activity
+    }
+
+    fn power_hint(&self) -> PowerHint {
+        if let Some((_, demo)) = self.demo.as_ref() {
+            return demo.app().power_hint();
         }
+        self.app
+            .as_ref()
+            .map_or(PowerHint::Default, |app| app.power_hint())
     }
 }
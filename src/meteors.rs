@@ -0,0 +1,762 @@
+//! Meteors, a shooter in the style of the old vector arcade games: turn and
+//! thrust a ship around space that wraps at the edges, and shoot the rocks
+//! drifting through it, which break into smaller ones. Everything moves in
+//! fractions of a pixel and is drawn with antialiased lines, so it glides.
+//!
+//! The stick or the D-pad turns, up or B thrusts, A shoots and Y jumps to
+//! hyperspace, somewhere at random.
+
+use std::f32::consts::TAU;
+
+use palette::LinSrgb;
+use rand::{rngs::StdRng, Rng};
+use rusttype::Scale;
+
+use rg35xx::achievements::{unlock, Achievement};
+use rg35xx::anim::FixedStep;
+use rg35xx::app::{load_font, Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::axes::Axis;
+use rg35xx::events::{AutoPause, Subscription};
+use rg35xx::haptics::{self, Pattern};
+use rg35xx::particles::Emitter;
+use rg35xx::profiles::Store;
+use rg35xx::time::Duration;
+use rg35xx::widgets::text_width;
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0")
+    .description("Shoot the rocks before they hit you")
+    .achievements(&[Achievement::new(
+        "meteors_10000",
+        "Rock Breaker",
+        "Score 10,000 in Meteors",
+    )])
+    .demo();
+
+const WIDTH: f32 = 640.0;
+const HEIGHT: f32 = 480.0;
+const STEP: Duration = Duration::from_millis(10);
+
+/// Radians a second at full turn
+const TURN_SPEED: f32 = 4.5;
+/// Pixels a second, each second, at full thrust
+const THRUST: f32 = 260.0;
+/// The fraction of its speed the ship loses each second
+const DRAG: f32 = 0.4;
+const MAX_SPEED: f32 = 380.0;
+const SHIP_RADIUS: f32 = 11.0;
+/// Seconds safe from rocks after coming back
+const INVULNERABLE: f32 = 2.5;
+/// Seconds before a new ship, once the middle is clear
+const RESPAWN: f32 = 1.5;
+/// Seconds out of space when jumping to hyperspace
+const HYPERSPACE: f32 = 0.6;
+const SHOT_SPEED: f32 = 480.0;
+/// Seconds before a shot fades out
+const SHOT_LIFE: f32 = 0.85;
+const MAX_SHOTS: usize = 5;
+/// Seconds between one wave being cleared and the next
+const WAVE_GAP: f32 = 2.0;
+const LIVES: u32 = 3;
+/// Points for each extra ship
+const EXTRA_LIFE: u32 = 10_000;
+
+type Vector = (f32, f32);
+
+/// The ship's outline, pointing up
+const HULL: [Vector; 4] = [(0.0, -15.0), (10.0, 11.0), (0.0, 6.0), (-10.0, 11.0)];
+
+/// Keeps `position` in space, coming back in on the opposite side
+fn wrap((x, y): Vector) -> Vector {
+    (x.rem_euclid(WIDTH), y.rem_euclid(HEIGHT))
+}
+
+/// How far `a` is from `b` the short way, which may be across an edge
+fn distance(a: Vector, b: Vector) -> f32 {
+    let dx = (a.0 - b.0 + WIDTH / 2.0).rem_euclid(WIDTH) - WIDTH / 2.0;
+    let dy = (a.1 - b.1 + HEIGHT / 2.0).rem_euclid(HEIGHT) - HEIGHT / 2.0;
+    dx.hypot(dy)
+}
+
+/// The unit vector for `angle`, with 0 pointing up the screen
+fn heading(angle: f32) -> Vector {
+    (angle.sin(), -angle.cos())
+}
+
+/// `point` turned by `angle` and moved to `center`
+fn place((x, y): Vector, angle: f32, center: Vector) -> Vector {
+    let (sin, cos) = angle.sin_cos();
+    (center.0 + x * cos - y * sin, center.1 + x * sin + y * cos)
+}
+
+/// Where to draw something `radius` across at `position` so it shows on
+/// both sides of an edge it's crossing
+fn copies(position: Vector, radius: f32) -> impl Iterator<Item = Vector> {
+    [-WIDTH, 0.0, WIDTH]
+        .into_iter()
+        .flat_map(|dx| [-HEIGHT, 0.0, HEIGHT].map(|dy| (dx, dy)))
+        .map(move |(dx, dy)| (position.0 + dx, position.1 + dy))
+        .filter(move |&(x, y)| {
+            x + radius >= 0.0 && x - radius <= WIDTH && y + radius >= 0.0 && y - radius <= HEIGHT
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Size {
+    Large,
+    Medium,
+    Small,
+}
+
+impl Size {
+    fn radius(self) -> f32 {
+        match self {
+            Self::Large => 38.0,
+            Self::Medium => 20.0,
+            Self::Small => 10.0,
+        }
+    }
+
+    fn points(self) -> u32 {
+        match self {
+            Self::Large => 20,
+            Self::Medium => 50,
+            Self::Small => 100,
+        }
+    }
+
+    /// Pixels a second
+    fn speeds(self) -> (f32, f32) {
+        match self {
+            Self::Large => (25.0, 55.0),
+            Self::Medium => (50.0, 90.0),
+            Self::Small => (80.0, 130.0),
+        }
+    }
+
+    /// What it breaks into when shot
+    fn smaller(self) -> Option<Self> {
+        match self {
+            Self::Large => Some(Self::Medium),
+            Self::Medium => Some(Self::Small),
+            Self::Small => None,
+        }
+    }
+}
+
+struct Rock {
+    position: Vector,
+    velocity: Vector,
+    angle: f32,
+    /// Radians a second
+    spin: f32,
+    size: Size,
+    /// How far out each corner is, as a fraction of the radius, for a
+    /// lumpy outline
+    shape: Vec<f32>,
+}
+
+impl Rock {
+    /// A rock at `position`, heading off in a random direction a little
+    /// faster in later waves
+    fn new(size: Size, position: Vector, drift: Vector, wave: u32, rng: &mut StdRng) -> Self {
+        let (min, max) = size.speeds();
+        let speed = rng.gen_range(min..max) * (1.0 + wave.min(10) as f32 * 0.05);
+        let direction = heading(rng.gen_range(0.0..TAU));
+        Self {
+            position,
+            velocity: (drift.0 + direction.0 * speed, drift.1 + direction.1 * speed),
+            angle: rng.gen_range(0.0..TAU),
+            spin: rng.gen_range(-1.0..1.0),
+            size,
+            shape: (0..11).map(|_| rng.gen_range(0.72..1.12)).collect(),
+        }
+    }
+
+    fn outline(&self, center: Vector) -> Vec<Vector> {
+        let radius = self.size.radius();
+        let corners = self.shape.len() as f32;
+        self.shape
+            .iter()
+            .enumerate()
+            .map(|(i, scale)| {
+                let (x, y) = heading(i as f32 * TAU / corners);
+                place((x * radius * scale, y * radius * scale), self.angle, center)
+            })
+            .collect()
+    }
+}
+
+struct Shot {
+    position: Vector,
+    velocity: Vector,
+    /// Seconds since it was fired
+    age: f32,
+}
+
+#[derive(Default)]
+struct Ship {
+    position: Vector,
+    velocity: Vector,
+    angle: f32,
+    /// How hard it's thrusting, from 0 to 1, for drawing the flame
+    thrust: f32,
+    /// Seconds left safe from rocks
+    invulnerable: f32,
+    /// Seconds left out of space, after blowing up or in hyperspace
+    gone: f32,
+    /// Blown up rather than in hyperspace
+    wrecked: bool,
+}
+
+impl Ship {
+    fn present(&self) -> bool {
+        self.gone <= 0.0
+    }
+
+    /// The corners of its outline, and of its flame when thrusting
+    fn outline(&self, center: Vector) -> (Vec<Vector>, Vec<Vector>) {
+        let hull = HULL.map(|point| place(point, self.angle, center)).to_vec();
+        let flame = [(-5.0, 9.0), (0.0, 9.0 + 10.0 * self.thrust), (5.0, 9.0)]
+            .map(|point| place(point, self.angle, center))
+            .to_vec();
+        (hull, flame)
+    }
+}
+
+/// What the player's asking the ship to do this step
+#[derive(Debug, Default, Clone, Copy)]
+struct Controls {
+    /// -1 to turn left fully, 1 right
+    turn: f32,
+    /// 0 to 1
+    thrust: f32,
+    fire: bool,
+    hyperspace: bool,
+}
+
+/// What's going on in space
+struct Space {
+    ship: Ship,
+    rocks: Vec<Rock>,
+    shots: Vec<Shot>,
+    score: u32,
+    lives: u32,
+    /// Counting from 1
+    wave: u32,
+    /// Seconds until the next wave comes in, once one's cleared
+    calm: f32,
+}
+
+impl Space {
+    fn new(rng: &mut StdRng) -> Self {
+        let mut space = Self {
+            ship: Ship {
+                position: (WIDTH / 2.0, HEIGHT / 2.0),
+                invulnerable: INVULNERABLE,
+                ..Default::default()
+            },
+            rocks: vec![],
+            shots: vec![],
+            score: 0,
+            lives: LIVES,
+            wave: 0,
+            calm: 0.0,
+        };
+        space.next_wave(rng);
+        space
+    }
+
+    fn is_over(&self) -> bool {
+        self.lives == 0
+    }
+
+    /// Large rocks coming in from the edges, away from the ship, one more
+    /// each wave
+    fn next_wave(&mut self, rng: &mut StdRng) {
+        self.wave += 1;
+        let count = (3 + self.wave).min(11);
+        for _ in 0..count {
+            let position = loop {
+                let position = (rng.gen_range(0.0..WIDTH), rng.gen_range(0.0..HEIGHT));
+                if distance(position, self.ship.position) > 160.0 {
+                    break position;
+                }
+            };
+            let rock = Rock::new(Size::Large, position, (0.0, 0.0), self.wave, rng);
+            self.rocks.push(rock);
+        }
+    }
+
+    fn add_score(&mut self, points: u32) {
+        let lives_before = self.score / EXTRA_LIFE;
+        self.score += points;
+        if self.score / EXTRA_LIFE > lives_before {
+            self.lives += 1;
+        }
+    }
+
+    /// Breaks the rock at `index` in two, or into nothing if it's small
+    fn shatter(&mut self, index: usize, rng: &mut StdRng, debris: &mut Emitter) {
+        let rock = self.rocks.swap_remove(index);
+        self.add_score(rock.size.points());
+        debris.burst(
+            (rock.size.radius() / 2.0) as usize + 4,
+            rock.position.0,
+            rock.position.1,
+        );
+        if let Some(size) = rock.size.smaller() {
+            for _ in 0..2 {
+                let drift = (rock.velocity.0 * 0.5, rock.velocity.1 * 0.5);
+                self.rocks
+                    .push(Rock::new(size, rock.position, drift, self.wave, rng));
+            }
+        }
+    }
+
+    fn update_ship(&mut self, controls: Controls, dt: f32, rng: &mut StdRng) {
+        let ship = &mut self.ship;
+        if !ship.present() {
+            ship.gone -= dt;
+            if ship.present() && ship.wrecked {
+                // Back in the middle, but not on top of a rock
+                let center = (WIDTH / 2.0, HEIGHT / 2.0);
+                let clear = self
+                    .rocks
+                    .iter()
+                    .all(|rock| distance(rock.position, center) > rock.size.radius() + 60.0);
+                if self.lives == 0 || !clear {
+                    ship.gone = dt;
+                    return;
+                }
+                *ship = Ship {
+                    position: center,
+                    invulnerable: INVULNERABLE,
+                    ..Default::default()
+                };
+            }
+            return;
+        }
+
+        ship.invulnerable = (ship.invulnerable - dt).max(0.0);
+        ship.angle = (ship.angle + controls.turn * TURN_SPEED * dt).rem_euclid(TAU);
+        ship.thrust = controls.thrust;
+        let (dx, dy) = heading(ship.angle);
+        let push = THRUST * controls.thrust * dt;
+        let drag = 1.0 - DRAG * dt;
+        ship.velocity = (
+            (ship.velocity.0 + dx * push) * drag,
+            (ship.velocity.1 + dy * push) * drag,
+        );
+        let speed = ship.velocity.0.hypot(ship.velocity.1);
+        if speed > MAX_SPEED {
+            ship.velocity.0 *= MAX_SPEED / speed;
+            ship.velocity.1 *= MAX_SPEED / speed;
+        }
+        ship.position = wrap((
+            ship.position.0 + ship.velocity.0 * dt,
+            ship.position.1 + ship.velocity.1 * dt,
+        ));
+
+        if controls.fire && self.shots.len() < MAX_SHOTS {
+            let nose = place((0.0, -15.0), ship.angle, ship.position);
+            self.shots.push(Shot {
+                position: nose,
+                velocity: (
+                    ship.velocity.0 + dx * SHOT_SPEED,
+                    ship.velocity.1 + dy * SHOT_SPEED,
+                ),
+                age: 0.0,
+            });
+        }
+        if controls.hyperspace {
+            // Somewhere at random, which could be right next to a rock
+            ship.gone = HYPERSPACE;
+            ship.wrecked = false;
+            ship.position = (rng.gen_range(0.0..WIDTH), rng.gen_range(0.0..HEIGHT));
+            ship.velocity = (0.0, 0.0);
+        }
+    }
+
+    /// Moves everything on by `dt` seconds
+    fn step(&mut self, controls: Controls, dt: f32, rng: &mut StdRng, debris: &mut Emitter) {
+        self.update_ship(controls, dt, rng);
+
+        for shot in &mut self.shots {
+            shot.position = wrap((
+                shot.position.0 + shot.velocity.0 * dt,
+                shot.position.1 + shot.velocity.1 * dt,
+            ));
+            shot.age += dt;
+        }
+        self.shots.retain(|shot| shot.age < SHOT_LIFE);
+        for rock in &mut self.rocks {
+            rock.position = wrap((
+                rock.position.0 + rock.velocity.0 * dt,
+                rock.position.1 + rock.velocity.1 * dt,
+            ));
+            rock.angle += rock.spin * dt;
+        }
+
+        let mut shot = 0;
+        while shot < self.shots.len() {
+            let position = self.shots[shot].position;
+            match self
+                .rocks
+                .iter()
+                .position(|rock| distance(rock.position, position) < rock.size.radius())
+            {
+                Some(rock) => {
+                    self.shots.swap_remove(shot);
+                    self.shatter(rock, rng, debris);
+                }
+                None => shot += 1,
+            }
+        }
+
+        let ship = &self.ship;
+        if ship.present() && ship.invulnerable <= 0.0 {
+            let hit = self.rocks.iter().position(|rock| {
+                distance(rock.position, ship.position) < rock.size.radius() * 0.85 + SHIP_RADIUS
+            });
+            if let Some(rock) = hit {
+                debris.burst(40, ship.position.0, ship.position.1);
+                self.ship.gone = RESPAWN;
+                self.ship.wrecked = true;
+                self.lives -= 1;
+                haptics::play(&Pattern::FAILURE);
+                self.shatter(rock, rng, debris);
+            }
+        }
+
+        if self.rocks.is_empty() {
+            if self.calm <= 0.0 {
+                self.calm = WAVE_GAP;
+            }
+            self.calm -= dt;
+            if self.calm <= 0.0 {
+                self.next_wave(rng);
+            }
+        }
+    }
+}
+
+enum Phase {
+    Title,
+    Playing,
+    Paused,
+    Over { best: bool },
+}
+
+pub struct MeteorsApp {
+    space: Space,
+    phase: Phase,
+    steps: FixedStep,
+    /// Shots and jumps asked for since the last step, so a quick tap
+    /// between steps isn't lost
+    pending: Controls,
+    high_score: u32,
+    stats: Store,
+    debris: Emitter,
+    rng: StdRng,
+    auto_pause: Subscription<AutoPause>,
+}
+
+impl Default for MeteorsApp {
+    fn default() -> Self {
+        let mut rng = rg35xx::random::rng("meteors");
+        let stats = Store::open("meteors");
+        Self {
+            space: Space::new(&mut rng),
+            phase: Phase::Title,
+            steps: FixedStep::new(STEP),
+            pending: Controls::default(),
+            high_score: stats.get("high_score").unwrap_or(0),
+            stats,
+            debris: Emitter::new("meteors", 600)
+                .with_lifetime(Duration::from_millis(300), Duration::from_millis(900))
+                .with_speed(20.0, 120.0)
+                .with_colors(&[LinSrgb::new(255, 255, 255), LinSrgb::new(200, 200, 210)])
+                .with_fade_to(LinSrgb::new(0, 0, 0))
+                .with_size(2),
+            rng,
+            auto_pause: Subscription::default(),
+        }
+    }
+}
+
+impl MeteorsApp {
+    fn start(&mut self) {
+        self.space = Space::new(&mut self.rng);
+        self.steps.reset();
+        self.pending = Controls::default();
+        self.debris.clear();
+        self.phase = Phase::Playing;
+    }
+
+    /// Keeps the score if it's the best yet, saying whether it was
+    fn save_high_score(&mut self) -> bool {
+        if self.space.score <= self.high_score {
+            return false;
+        }
+        self.high_score = self.space.score;
+        self.stats.set("high_score", self.high_score);
+        if let Err(e) = self.stats.save() {
+            println!("Couldn't save the Meteors high score: {}", e);
+        }
+        true
+    }
+
+    fn controls(&mut self, input: &Input) -> Controls {
+        let mut turn = input.axis(Axis::LeftX);
+        if input.pressed(Button::PovLeft) {
+            turn = -1.0;
+        }
+        if input.pressed(Button::PovRight) {
+            turn = 1.0;
+        }
+        let mut thrust = (-input.axis(Axis::LeftY)).max(0.0);
+        if input.pressed(Button::PovUp) || input.pressed(Button::ActionB) {
+            thrust = 1.0;
+        }
+        self.pending.fire |= input.just_pressed(Button::ActionA);
+        self.pending.hyperspace |=
+            input.just_pressed(Button::ActionH) || input.just_pressed(Button::PovDown);
+        Controls {
+            turn,
+            thrust,
+            ..self.pending
+        }
+    }
+
+    fn draw_space(&self, frame: &mut Frame) {
+        let white = LinSrgb::new(255, 255, 255);
+        let grey = LinSrgb::new(190, 190, 200);
+        for rock in &self.space.rocks {
+            for center in copies(rock.position, rock.size.radius() * 1.2) {
+                frame.polygon(&rock.outline(center), grey);
+            }
+        }
+        for shot in &self.space.shots {
+            let length = 4.0 / SHOT_SPEED;
+            let tail = (
+                shot.position.0 - shot.velocity.0 * length,
+                shot.position.1 - shot.velocity.1 * length,
+            );
+            frame.line(tail, shot.position, white);
+        }
+
+        let ship = &self.space.ship;
+        // Blinking while it's safe
+        let blink = (ship.invulnerable * 8.0) as u32 % 2 == 1;
+        if ship.present() && !blink && !matches!(self.phase, Phase::Title) {
+            for center in copies(ship.position, 16.0) {
+                let (hull, flame) = ship.outline(center);
+                frame.polygon(&hull, white);
+                if ship.thrust > 0.0 {
+                    frame.polygon(&flame, LinSrgb::new(255, 170, 60));
+                }
+            }
+        }
+    }
+
+    fn draw_status(&self, frame: &mut Frame) {
+        let white = LinSrgb::new(255, 255, 255);
+        let grey = LinSrgb::new(150, 150, 160);
+        let space = &self.space;
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            16,
+            32,
+            24.0,
+            white,
+            &space.score.to_string(),
+        );
+        let high = format!("High {}", self.high_score.max(space.score));
+        frame.text("fonts/Ubuntu-B.ttf", 270, 28, 18.0, grey, &high);
+        let wave = format!("Wave {}", space.wave);
+        frame.text("fonts/Ubuntu-B.ttf", 560, 28, 18.0, grey, &wave);
+        // A little ship for each one to come
+        for i in 0..space.lives.saturating_sub(1).min(8) {
+            let x = 24.0 + i as f32 * 18.0;
+            frame.polygon(&HULL.map(|(dx, dy)| (x + dx * 0.6, 56.0 + dy * 0.6)), white);
+        }
+    }
+
+    fn draw_banner(frame: &mut Frame, lines: &[&str]) {
+        let white = LinSrgb::new(255, 255, 255);
+        for (i, line) in lines.iter().enumerate() {
+            let (size, y) = match i {
+                0 => (44.0, 200),
+                _ => (20.0, 220 + i * 30),
+            };
+            let width = text_width(&load_font("fonts/Ubuntu-B.ttf"), Scale::uniform(size), line);
+            let x = (WIDTH - width) as usize / 2;
+            frame.text("fonts/Ubuntu-B.ttf", x, y, size, white, line);
+        }
+    }
+}
+
+impl App for MeteorsApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        let start = input.start_pressed();
+        let auto_pause = !self.auto_pause.take().is_empty();
+        match self.phase {
+            Phase::Title | Phase::Over { .. } => {
+                if start || input.just_pressed(Button::ActionA) {
+                    self.start();
+                }
+            }
+            Phase::Paused => {
+                if start || input.just_pressed(Button::ActionA) {
+                    self.steps.reset();
+                    self.phase = Phase::Playing;
+                }
+            }
+            Phase::Playing if start || auto_pause => self.phase = Phase::Paused,
+            Phase::Playing => {
+                let mut controls = self.controls(input);
+                let dt = STEP.as_secs_f32();
+                for _ in 0..self.steps.update(input.delta()) {
+                    self.space
+                        .step(controls, dt, &mut self.rng, &mut self.debris);
+                    // A tap only fires once
+                    self.pending = Controls::default();
+                    controls.fire = false;
+                    controls.hyperspace = false;
+                }
+                if self.space.score >= 10_000 {
+                    unlock("meteors_10000");
+                }
+                if self.space.is_over() && self.space.ship.gone <= 0.0 {
+                    let best = self.save_high_score();
+                    self.phase = Phase::Over { best };
+                }
+            }
+        }
+        self.debris.update(input.delta());
+
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+        self.draw_space(frame);
+        self.debris.draw(frame);
+        self.draw_status(frame);
+        match self.phase {
+            Phase::Title => Self::draw_banner(
+                frame,
+                &[
+                    "Meteors",
+                    "Stick or D-pad to turn, up or B to thrust",
+                    "A to shoot, Y for hyperspace",
+                    "A or START to play",
+                ],
+            ),
+            Phase::Paused => Self::draw_banner(frame, &["Paused", "A or START to carry on"]),
+            Phase::Over { best } => {
+                let score = match best {
+                    true => format!("A new high score: {}", self.space.score),
+                    false => format!("Scored {}", self.space.score),
+                };
+                Self::draw_banner(frame, &["Game over", &score, "A or START to play again"]);
+            }
+            Phase::Playing => (),
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        match self.phase {
+            Phase::Playing => Activity::Busy,
+            _ if self.debris.is_active() => Activity::Busy,
+            _ => Activity::Waiting,
+        }
+    }
+
+    fn demo_input(&mut self, input: &mut Input) {
+        let mut held = vec![];
+        let fire = !input.pressed(Button::ActionA);
+        let ship = &self.space.ship;
+        let nearest = self.space.rocks.iter().min_by(|a, b| {
+            distance(a.position, ship.position).total_cmp(&distance(b.position, ship.position))
+        });
+        match (&self.phase, nearest) {
+            (Phase::Playing, Some(rock)) => {
+                // The short way to it, across an edge if need be
+                let dx = (rock.position.0 - ship.position.0 + WIDTH / 2.0).rem_euclid(WIDTH)
+                    - WIDTH / 2.0;
+                let dy = (rock.position.1 - ship.position.1 + HEIGHT / 2.0).rem_euclid(HEIGHT)
+                    - HEIGHT / 2.0;
+                let (hx, hy) = heading(ship.angle);
+                // How far it is round from the nose, positive to the right
+                let off = (hx * dy - hy * dx).atan2(hx * dx + hy * dy);
+                if off > 0.1 {
+                    held.push(Button::PovRight);
+                } else if off < -0.1 {
+                    held.push(Button::PovLeft);
+                }
+                if off.abs() < 0.3 && fire {
+                    held.push(Button::ActionA);
+                }
+                // Closing in on far ones, carefully
+                if off.abs() < 0.5 && dx.hypot(dy) > 220.0 {
+                    held.push(Button::ActionB);
+                }
+            }
+            // Between waves
+            (Phase::Playing, None) => (),
+            _ if fire => held.push(Button::ActionA),
+            _ => (),
+        }
+        input.hold_only(&held);
+    }
+
+    fn on_exit(&mut self) {
+        self.save_high_score();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wrapping() {
+        assert_eq!(wrap((-1.0, HEIGHT + 2.0)), (WIDTH - 1.0, 2.0));
+        assert!((distance((2.0, 5.0), (WIDTH - 2.0, 5.0)) - 4.0).abs() < 0.001);
+        assert!((distance((0.0, 0.0), (3.0, 4.0)) - 5.0).abs() < 0.001);
+        // Close to a corner shows up in all four
+        assert_eq!(copies((2.0, 2.0), 10.0).count(), 4);
+        assert_eq!(copies((320.0, 240.0), 10.0).count(), 1);
+    }
+
+    #[test]
+    fn test_shatter() {
+        let mut rng = rg35xx::random::rng("meteors-test");
+        let mut debris = Emitter::new("meteors-test", 100);
+        let mut space = Space::new(&mut rng);
+        assert_eq!(space.rocks.len(), 4);
+
+        // A shot right on a large rock breaks it in two
+        space.rocks.truncate(1);
+        space.rocks[0].velocity = (0.0, 0.0);
+        space.shots.push(Shot {
+            position: space.rocks[0].position,
+            velocity: (0.0, 0.0),
+            age: 0.0,
+        });
+        let controls = Controls::default();
+        space.step(controls, 0.01, &mut rng, &mut debris);
+        assert!(space.shots.is_empty());
+        assert_eq!(space.rocks.len(), 2);
+        assert!(space.rocks.iter().all(|rock| rock.size == Size::Medium));
+        assert_eq!(space.score, 20);
+
+        // A rock hitting the ship costs a life, once it's no longer safe
+        space.ship.invulnerable = 0.0;
+        space.rocks[0].position = space.ship.position;
+        space.step(controls, 0.01, &mut rng, &mut debris);
+        assert_eq!(space.lives, LIVES - 1);
+        assert!(!space.ship.present());
+
+        space.add_score(EXTRA_LIFE);
+        assert_eq!(space.lives, LIVES);
+    }
+}
@@ -0,0 +1,402 @@
+//! Headlines from RSS and Atom feeds. Feed URLs go one per line in `news.txt`
+//! next to the launcher binary, and the last copy of each feed is kept in
+//! `news/` so there's something to read offline.
+
+use std::path::PathBuf;
+
+use palette::LinSrgb;
+
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::profiles::Store;
+use rg35xx::storage::base_dir;
+use rg35xx::tasks::{Task, Tasks};
+use rg35xx::time::{Duration, Instant};
+use rg35xx::widgets::Reader;
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0").description("Headlines from RSS feeds");
+
+struct Article {
+    feed: String,
+    title: String,
+    body: String,
+    link: String,
+}
+
+/// FNV-1a, which stays the same between builds unlike `DefaultHasher`
+fn hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn cache_path(url: &str) -> PathBuf {
+    base_dir()
+        .join("news")
+        .join(format!("{:016x}.xml", hash(url)))
+}
+
+impl Article {
+    /// What the article's reading position is saved under
+    fn key(&self) -> String {
+        let id = if self.link.is_empty() {
+            &self.title
+        } else {
+            &self.link
+        };
+        format!("{:016x}", hash(id))
+    }
+}
+
+/// Turns an HTML snippet into plain text, keeping paragraph breaks
+fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text += &rest[..start];
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim_start_matches('/');
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if matches!(
+            name.as_str(),
+            "br" | "p" | "div" | "li" | "h1" | "h2" | "h3"
+        ) {
+            text.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text += rest;
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    // Collapse the blank lines left behind by nested block tags
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses an RSS 2.0 or Atom document
+fn parse_feed(xml: &str) -> Result<Vec<Article>, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    let root = doc.root_element();
+    let child_text = |node: roxmltree::Node, name: &str| {
+        node.children()
+            .find(|c| c.tag_name().name() == name)
+            .and_then(|c| c.text())
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+
+    let (channel, item_name) = match root.tag_name().name() {
+        "rss" => (
+            root.children()
+                .find(|c| c.has_tag_name("channel"))
+                .ok_or("RSS feed has no channel")?,
+            "item",
+        ),
+        "feed" => (root, "entry"),
+        other => return Err(format!("`{}` isn't an RSS or Atom feed", other)),
+    };
+    let feed = child_text(channel, "title");
+
+    Ok(channel
+        .children()
+        .filter(|c| c.tag_name().name() == item_name)
+        .map(|item| {
+            let mut body = child_text(item, "description");
+            for name in ["summary", "content"] {
+                if body.is_empty() {
+                    body = child_text(item, name);
+                }
+            }
+            let mut link = child_text(item, "link");
+            if link.is_empty() {
+                // Atom puts the URL in an attribute
+                link = item
+                    .children()
+                    .find(|c| c.tag_name().name() == "link")
+                    .and_then(|c| c.attribute("href"))
+                    .unwrap_or("")
+                    .to_string();
+            }
+            Article {
+                feed: feed.clone(),
+                title: strip_html(&child_text(item, "title")),
+                body: strip_html(&body),
+                link,
+            }
+        })
+        .collect())
+}
+
+pub struct NewsApp {
+    feeds: Vec<String>,
+    /// The latest copy of each feed, in the same order as `feeds`
+    documents: Vec<Option<String>>,
+    articles: Vec<Article>,
+    errors: Vec<String>,
+    /// Downloads in progress, which report back with their index in `feeds`
+    fetching: Vec<Task<(usize, Result<String, String>)>>,
+    tasks: Tasks,
+    selected: usize,
+    reading: bool,
+    reader: Reader,
+    /// Where each article was left, for the current profile
+    positions: Store,
+}
+
+impl Default for NewsApp {
+    fn default() -> Self {
+        let feeds = std::fs::read_to_string(base_dir().join("news.txt"))
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let documents = feeds
+            .iter()
+            .map(|url| std::fs::read_to_string(cache_path(url)).ok())
+            .collect();
+
+        let mut app = Self {
+            feeds,
+            documents,
+            articles: vec![],
+            errors: vec![],
+            fetching: vec![],
+            tasks: Tasks::new(4),
+            selected: 0,
+            reading: false,
+            reader: Reader::new(18.0),
+            positions: Store::open("news"),
+        };
+        app.parse_documents();
+        app.refresh();
+        app
+    }
+}
+
+impl NewsApp {
+    /// Starts downloading every feed in the background
+    fn refresh(&mut self) {
+        if !self.fetching.is_empty() || self.feeds.is_empty() {
+            return;
+        }
+        for (i, url) in self.feeds.iter().enumerate() {
+            let url = url.clone();
+            let download = self.tasks.spawn(move || (i, rg35xx::http::get(&url)));
+            self.fetching.push(download);
+        }
+        self.errors.clear();
+    }
+
+    fn receive(&mut self) {
+        let mut finished = vec![];
+        self.fetching.retain_mut(|download| {
+            finished.extend(download.poll());
+            download.is_pending()
+        });
+        let mut changed = false;
+        for (i, result) in finished {
+            let url = &self.feeds[i];
+            match result.and_then(|xml| parse_feed(&xml).map(|_| xml)) {
+                Ok(xml) => {
+                    let path = cache_path(url);
+                    let saved = path
+                        .parent()
+                        .map_or(Ok(()), std::fs::create_dir_all)
+                        .and_then(|_| std::fs::write(&path, &xml));
+                    if let Err(e) = saved {
+                        println!("Couldn't cache {}: {}", url, e);
+                    }
+                    self.documents[i] = Some(xml);
+                    changed = true;
+                }
+                Err(e) => self.errors.push(format!("{}: {}", url, e)),
+            }
+        }
+        if changed {
+            self.parse_documents();
+        }
+    }
+
+    fn parse_documents(&mut self) {
+        self.articles = self
+            .documents
+            .iter()
+            .flatten()
+            .filter_map(|xml| parse_feed(xml).ok())
+            .flatten()
+            .collect();
+        self.selected = self.selected.min(self.articles.len().saturating_sub(1));
+    }
+
+    fn draw_headlines(&mut self, input: &Input, frame: &mut Frame) {
+        if self.feeds.is_empty() {
+            let mut ctx = frame.context();
+            ctx.offset(20, 40);
+            ctx.text("No feeds yet. Add RSS feed URLs to news.txt,");
+            ctx.offset(0, 20);
+            ctx.text("one per line, next to the launcher.");
+            return;
+        }
+
+        let line_height = 44;
+        let visible = (frame.height().saturating_sub(40) / line_height).max(1);
+        let first = self.selected.saturating_sub(visible - 1);
+        for (i, article) in self.articles.iter().enumerate().skip(first).take(visible) {
+            let y = 30 + (i - first) * line_height;
+            let color = if i == self.selected {
+                LinSrgb::new(255, 255, 255)
+            } else {
+                LinSrgb::new(180, 180, 180)
+            };
+            frame.text("fonts/Ubuntu-B.ttf", 20, y, 20.0, color, &article.title);
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                20,
+                y + 18,
+                14.0,
+                LinSrgb::new(255, 0, 0),
+                &article.feed,
+            );
+        }
+
+        let status = if !self.fetching.is_empty() {
+            format!("Updating {} feed(s)...", self.fetching.len())
+        } else if let Some(error) = self.errors.first() {
+            error.clone()
+        } else {
+            "A to read, START to refresh".to_string()
+        };
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            20,
+            frame.height() - 10,
+            14.0,
+            LinSrgb::new(180, 180, 180),
+            &status,
+        );
+
+        if input.repeated(Button::PovUp) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if input.repeated(Button::PovDown) {
+            self.selected = (self.selected + 1).min(self.articles.len().saturating_sub(1));
+        }
+        if input.start_pressed() {
+            self.refresh();
+        }
+        if input.just_pressed(Button::ActionA) {
+            if let Some(article) = self.articles.get(self.selected) {
+                let mut text = article.body.clone();
+                if !article.link.is_empty() {
+                    text += &format!("\n{}", article.link);
+                }
+                self.reader.set_text(&text);
+                self.reader
+                    .set_scroll(self.positions.get(&article.key()).unwrap_or(0));
+                self.reading = true;
+            }
+        }
+    }
+
+    fn draw_article(&mut self, input: &Input, frame: &mut Frame) {
+        if let Some(article) = self.articles.get(self.selected) {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                20,
+                36,
+                24.0,
+                LinSrgb::new(255, 255, 255),
+                &article.title,
+            );
+        }
+        self.reader.handle_input(input);
+        let (width, height) = (frame.width(), frame.height());
+        self.reader.draw(frame, 20, 50, width - 40, height - 60);
+
+        if input.just_pressed(Button::ActionB) {
+            self.reading = false;
+            if let Some(article) = self.articles.get(self.selected) {
+                self.positions.set(&article.key(), self.reader.scroll());
+                if let Err(e) = self.positions.save() {
+                    println!("Couldn't save the reading position: {}", e);
+                }
+            }
+        }
+    }
+}
+
+impl App for NewsApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        self.receive();
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+        if self.reading {
+            self.draw_article(input, frame);
+        } else {
+            self.draw_headlines(input, frame);
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        if !self.fetching.is_empty() {
+            // Check on the downloads a few times a second
+            Activity::Idle(Instant::now() + Duration::from_millis(250))
+        } else {
+            Activity::Waiting
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_feed() {
+        let rss = r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+                <title>Example</title>
+                <item>
+                    <title>First &amp; foremost</title>
+                    <link>https://example.com/1</link>
+                    <description>&lt;p&gt;Hello&lt;/p&gt;&lt;p&gt;world&lt;/p&gt;</description>
+                </item>
+            </channel></rss>"#;
+        let articles = parse_feed(rss).unwrap();
+        assert_eq!(articles.len(), 1);
+        assert_eq!(articles[0].feed, "Example");
+        assert_eq!(articles[0].title, "First & foremost");
+        assert_eq!(articles[0].body, "Hello\nworld");
+        assert_eq!(articles[0].link, "https://example.com/1");
+
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+                <title>Atom</title>
+                <entry>
+                    <title>Entry</title>
+                    <link href="https://example.com/2"/>
+                    <summary>Text</summary>
+                </entry>
+            </feed>"#;
+        let articles = parse_feed(atom).unwrap();
+        assert_eq!(articles[0].link, "https://example.com/2");
+        assert_eq!(articles[0].body, "Text");
+    }
+}
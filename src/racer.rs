@@ -0,0 +1,797 @@
+//! Racer, a pseudo-3D driving toy in the style of the old arcade racers.
+//! The road is a loop of short segments, each bending and climbing a
+//! little, projected onto the screen and filled in a scanline at a time
+//! from the nearest segment back, so hills hide what's behind them.
+//!
+//! A accelerates, B brakes and the stick or the D-pad steers. Corners push
+//! the car outwards and the grass slows it down. Y shows how long each
+//! frame takes to draw.
+
+use palette::{LinSrgb, Mix};
+use rand::{rngs::StdRng, Rng};
+
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::axes::Axis;
+use rg35xx::events::{AutoPause, Subscription};
+use rg35xx::profiles::Store;
+use rg35xx::time::{Duration, Instant};
+
+pub const MANIFEST: Manifest =
+    Manifest::new("0.1.0").description("Drive a winding road over the hills");
+
+/// World units along each segment of road
+const SEGMENT_LENGTH: f32 = 200.0;
+/// Segments in each stripe of the rumble strips
+const RUMBLE_LENGTH: usize = 3;
+/// Half the width of the road
+const ROAD_WIDTH: f32 = 2000.0;
+const LANES: usize = 3;
+const CAMERA_HEIGHT: f32 = 1000.0;
+/// Distance from the camera to the screen, for a field of view of 100°
+const CAMERA_DEPTH: f32 = 0.84;
+/// How far ahead the camera is from the player's car
+const PLAYER_Z: f32 = CAMERA_HEIGHT * CAMERA_DEPTH;
+/// Segments drawn ahead of the camera
+const DRAW_DISTANCE: usize = 160;
+/// One segment every sixtieth of a second
+const MAX_SPEED: f32 = SEGMENT_LENGTH * 60.0;
+const ACCELERATION: f32 = MAX_SPEED / 5.0;
+const BRAKING: f32 = -MAX_SPEED;
+const DECELERATION: f32 = -MAX_SPEED / 5.0;
+const OFF_ROAD_DECELERATION: f32 = -MAX_SPEED / 2.0;
+/// Above this off the road, the grass slows the car down
+const OFF_ROAD_LIMIT: f32 = MAX_SPEED / 4.0;
+/// How hard corners push the car outwards
+const CENTRIFUGAL: f32 = 0.3;
+const TRAFFIC: usize = 24;
+
+/// The colors of one stretch of road, alternating to show its speed
+struct Palette {
+    grass: LinSrgb<u8>,
+    rumble: LinSrgb<u8>,
+    road: LinSrgb<u8>,
+    lane: Option<LinSrgb<u8>>,
+}
+
+const LIGHT: Palette = Palette {
+    grass: LinSrgb::new(16, 170, 16),
+    rumble: LinSrgb::new(235, 235, 235),
+    road: LinSrgb::new(107, 107, 107),
+    lane: Some(LinSrgb::new(235, 235, 235)),
+};
+
+const DARK: Palette = Palette {
+    grass: LinSrgb::new(0, 154, 0),
+    rumble: LinSrgb::new(190, 30, 30),
+    road: LinSrgb::new(99, 99, 99),
+    lane: None,
+};
+
+const SKY: LinSrgb<u8> = LinSrgb::new(114, 215, 238);
+const HORIZON: LinSrgb<u8> = LinSrgb::new(200, 236, 246);
+/// Past the end of the road
+const GROUND: LinSrgb<u8> = LinSrgb::new(0, 81, 8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scenery {
+    Tree,
+    Post,
+    Billboard,
+}
+
+struct Segment {
+    /// How much the road bends over this segment
+    curve: f32,
+    /// Heights at its near and far ends
+    y: (f32, f32),
+    /// Things at the side of the road, at multiples of the road's half
+    /// width from its middle
+    scenery: Vec<(Scenery, f32)>,
+}
+
+/// A segment's near or far edge on the screen
+#[derive(Debug, Default, Clone, Copy)]
+struct Projected {
+    x: f32,
+    y: f32,
+    /// Half the road's width in pixels
+    w: f32,
+    scale: f32,
+}
+
+/// Where a point in the world is seen, `camera` being its position relative
+/// to the camera
+fn project(camera: (f32, f32, f32), width: f32, height: f32) -> Projected {
+    let scale = CAMERA_DEPTH / camera.2;
+    Projected {
+        x: (width / 2.0 + scale * camera.0 * width / 2.0).round(),
+        y: (height / 2.0 - scale * camera.1 * height / 2.0).round(),
+        w: (scale * ROAD_WIDTH * width / 2.0).round(),
+        scale,
+    }
+}
+
+fn ease_in(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t * t
+}
+
+fn ease_out(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * (1.0 - (1.0 - t) * (1.0 - t))
+}
+
+fn ease_in_out(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * (0.5 - (t * std::f32::consts::PI).cos() / 2.0)
+}
+
+/// A loop of road, built up from stretches that ease into a bend and a
+/// change of height and back out
+struct Track {
+    segments: Vec<Segment>,
+}
+
+impl Track {
+    fn new(rng: &mut StdRng) -> Self {
+        let mut track = Self { segments: vec![] };
+        // Segments in, held and out, how hard it bends and how far it climbs
+        let stretches: &[(usize, usize, usize, f32, f32)] = &[
+            (25, 25, 25, 0.0, 0.0),
+            (50, 50, 50, 4.0, 20.0),
+            (25, 25, 25, 0.0, -20.0),
+            (50, 50, 50, -2.0, 40.0),
+            (25, 25, 25, 0.0, 0.0),
+            (25, 25, 25, 6.0, -40.0),
+            (50, 100, 50, -4.0, 0.0),
+            (25, 25, 25, 0.0, 60.0),
+            (25, 25, 25, 2.0, -40.0),
+            (50, 50, 50, -6.0, 0.0),
+            (50, 50, 50, 0.0, -20.0),
+            (25, 50, 25, 4.0, 0.0),
+        ];
+        for &(enter, hold, leave, curve, hill) in stretches {
+            track.add(enter, hold, leave, curve, hill * SEGMENT_LENGTH);
+        }
+        // Back down to where it started, so it loops
+        let last = track.last_y();
+        track.add(50, 0, 50, 0.0, -last);
+
+        for (index, segment) in track.segments.iter_mut().enumerate() {
+            if index % 20 == 0 {
+                let side = if index % 40 == 0 { -1.0 } else { 1.0 };
+                segment.scenery.push((Scenery::Billboard, side * 1.4));
+            }
+            if index % 8 == 4 {
+                segment.scenery.push((Scenery::Post, -1.15));
+                segment.scenery.push((Scenery::Post, 1.15));
+            }
+            if rng.gen_bool(0.3) {
+                let side = if rng.gen_bool(0.5) { -1.0 } else { 1.0 };
+                segment
+                    .scenery
+                    .push((Scenery::Tree, side * rng.gen_range(1.8..5.0)));
+            }
+        }
+        track
+    }
+
+    fn last_y(&self) -> f32 {
+        self.segments.last().map_or(0.0, |segment| segment.y.1)
+    }
+
+    fn add(&mut self, enter: usize, hold: usize, leave: usize, curve: f32, height: f32) {
+        let start = self.last_y();
+        let end = start + height;
+        let total = (enter + hold + leave) as f32;
+        for n in 0..enter + hold + leave {
+            let curve = if n < enter {
+                ease_in(0.0, curve, n as f32 / enter as f32)
+            } else if n < enter + hold {
+                curve
+            } else {
+                ease_out(curve, 0.0, (n - enter - hold) as f32 / leave as f32)
+            };
+            let y = (
+                ease_in_out(start, end, n as f32 / total),
+                ease_in_out(start, end, (n + 1) as f32 / total),
+            );
+            self.segments.push(Segment {
+                curve,
+                y,
+                scenery: vec![],
+            });
+        }
+    }
+
+    fn length(&self) -> f32 {
+        self.segments.len() as f32 * SEGMENT_LENGTH
+    }
+
+    /// The index of the segment `z` is on
+    fn find(&self, z: f32) -> usize {
+        (z / SEGMENT_LENGTH).floor() as usize % self.segments.len()
+    }
+}
+
+struct Car {
+    /// Across the road, as a fraction of its half width
+    offset: f32,
+    z: f32,
+    speed: f32,
+    color: LinSrgb<u8>,
+}
+
+/// Something to draw at the side of or on the road, from the back
+enum Sprite {
+    Scenery(Scenery),
+    Car(LinSrgb<u8>),
+}
+
+/// Fills the pixels between `left` and `right` on row `y`
+fn span(frame: &mut Frame, y: usize, left: f32, right: f32, color: LinSrgb<u8>) {
+    let left = left.max(0.0) as usize;
+    let right = right.min(frame.width() as f32).max(0.0) as usize;
+    if right > left {
+        frame.fill_rect(left, y, right - left, 1, color);
+    }
+}
+
+/// Fills a rectangle, leaving out any of it below `clip`, where a hill in
+/// front hides it
+fn rect(frame: &mut Frame, (x, y): (f32, f32), (w, h): (f32, f32), clip: f32, color: LinSrgb<u8>) {
+    let bottom = (y + h).min(clip);
+    let (left, top) = (x.max(0.0), y.max(0.0));
+    let (right, bottom) = ((x + w).min(frame.width() as f32), bottom);
+    if right > left && bottom > top {
+        frame.fill_rect(
+            left as usize,
+            top as usize,
+            (right - left).ceil() as usize,
+            (bottom - top).ceil() as usize,
+            color,
+        );
+    }
+}
+
+/// Draws `sprite` standing on the point `x`, `y`, at `scale` pixels to the
+/// world unit
+fn draw_sprite(frame: &mut Frame, sprite: &Sprite, (x, y): (f32, f32), scale: f32, clip: f32) {
+    let size = |units: f32| units * scale;
+    match sprite {
+        Sprite::Scenery(Scenery::Tree) => {
+            rect(
+                frame,
+                (x - size(60.0), y - size(500.0)),
+                (size(120.0), size(500.0)),
+                clip,
+                LinSrgb::new(110, 70, 30),
+            );
+            let greens = [
+                LinSrgb::new(20, 110, 30),
+                LinSrgb::new(30, 130, 40),
+                LinSrgb::new(40, 150, 50),
+            ];
+            for (i, green) in greens.into_iter().enumerate() {
+                let (w, top) = (
+                    size(700.0 - i as f32 * 180.0),
+                    size(400.0 + i as f32 * 300.0),
+                );
+                rect(
+                    frame,
+                    (x - w / 2.0, y - top - size(350.0)),
+                    (w, size(400.0)),
+                    clip,
+                    green,
+                );
+            }
+        }
+        Sprite::Scenery(Scenery::Post) => {
+            rect(
+                frame,
+                (x - size(30.0), y - size(300.0)),
+                (size(60.0), size(300.0)),
+                clip,
+                LinSrgb::new(240, 240, 240),
+            );
+            rect(
+                frame,
+                (x - size(30.0), y - size(260.0)),
+                (size(60.0), size(40.0)),
+                clip,
+                LinSrgb::new(230, 60, 40),
+            );
+        }
+        Sprite::Scenery(Scenery::Billboard) => {
+            for post in [-300.0, 250.0] {
+                rect(
+                    frame,
+                    (x + size(post), y - size(700.0)),
+                    (size(50.0), size(700.0)),
+                    clip,
+                    LinSrgb::new(90, 90, 90),
+                );
+            }
+            rect(
+                frame,
+                (x - size(500.0), y - size(1100.0)),
+                (size(1000.0), size(450.0)),
+                clip,
+                LinSrgb::new(250, 200, 40),
+            );
+            rect(
+                frame,
+                (x - size(420.0), y - size(1020.0)),
+                (size(840.0), size(120.0)),
+                clip,
+                LinSrgb::new(200, 40, 40),
+            );
+            rect(
+                frame,
+                (x - size(420.0), y - size(860.0)),
+                (size(560.0), size(80.0)),
+                clip,
+                LinSrgb::new(40, 40, 160),
+            );
+        }
+        Sprite::Car(color) => draw_car(frame, (x, y), scale, clip, *color, 0.0),
+    }
+}
+
+/// A car seen from behind, leaning by `lean` pixels to the side as it turns
+fn draw_car(
+    frame: &mut Frame,
+    (x, y): (f32, f32),
+    scale: f32,
+    clip: f32,
+    color: LinSrgb<u8>,
+    lean: f32,
+) {
+    let size = |units: f32| units * scale;
+    let black = LinSrgb::new(20, 20, 20);
+    for wheel in [-420.0, 300.0] {
+        rect(
+            frame,
+            (x + size(wheel), y - size(160.0)),
+            (size(120.0), size(160.0)),
+            clip,
+            black,
+        );
+    }
+    rect(
+        frame,
+        (x - size(400.0), y - size(380.0)),
+        (size(800.0), size(260.0)),
+        clip,
+        color,
+    );
+    rect(
+        frame,
+        (x - size(300.0) + lean, y - size(560.0)),
+        (size(600.0), size(200.0)),
+        clip,
+        color,
+    );
+    rect(
+        frame,
+        (x - size(250.0) + lean, y - size(530.0)),
+        (size(500.0), size(130.0)),
+        clip,
+        LinSrgb::new(60, 80, 110),
+    );
+    for light in [-370.0, 260.0] {
+        rect(
+            frame,
+            (x + size(light), y - size(330.0)),
+            (size(110.0), size(60.0)),
+            clip,
+            LinSrgb::new(255, 60, 40),
+        );
+    }
+}
+
+/// -1 to steer fully left, 1 right
+fn steering(input: &Input) -> f32 {
+    if input.pressed(Button::PovLeft) {
+        -1.0
+    } else if input.pressed(Button::PovRight) {
+        1.0
+    } else {
+        input.axis(Axis::LeftX)
+    }
+}
+
+enum Phase {
+    Ready,
+    Driving,
+    Paused,
+}
+
+pub struct RacerApp {
+    track: Track,
+    traffic: Vec<Car>,
+    /// The camera's distance along the track
+    position: f32,
+    /// Across the road, as a fraction of its half width
+    player_x: f32,
+    speed: f32,
+    /// How far the far hills have scrolled, following the bends
+    sky_offset: f32,
+    phase: Phase,
+    lap_started: Instant,
+    last_lap: Option<Duration>,
+    best_lap: Option<u64>,
+    stats: Store,
+    /// How long the last frame took to draw, when it's being shown
+    timing: Option<Duration>,
+    auto_pause: Subscription<AutoPause>,
+}
+
+impl Default for RacerApp {
+    fn default() -> Self {
+        let mut rng = rg35xx::random::rng("racer");
+        let track = Track::new(&mut rng);
+        let colors = [
+            LinSrgb::new(220, 40, 40),
+            LinSrgb::new(40, 90, 220),
+            LinSrgb::new(240, 200, 40),
+            LinSrgb::new(240, 240, 240),
+            LinSrgb::new(60, 60, 60),
+        ];
+        let traffic = (0..TRAFFIC)
+            .map(|_| Car {
+                offset: [-0.66, 0.0, 0.66][rng.gen_range(0..LANES)],
+                z: rng.gen_range(0.0..track.length()),
+                speed: MAX_SPEED * rng.gen_range(0.2..0.55),
+                color: colors[rng.gen_range(0..colors.len())],
+            })
+            .collect();
+        let stats = Store::open("racer");
+        Self {
+            track,
+            traffic,
+            position: 0.0,
+            player_x: 0.0,
+            speed: 0.0,
+            sky_offset: 0.0,
+            phase: Phase::Ready,
+            lap_started: Instant::now(),
+            last_lap: None,
+            best_lap: stats.get("best_lap"),
+            auto_pause: Subscription::default(),
+            stats,
+            timing: None,
+        }
+    }
+}
+
+impl RacerApp {
+    fn drive(&mut self, input: &Input, dt: f32) {
+        let length = self.track.length();
+        let segment = &self.track.segments[self.track.find(self.position + PLAYER_Z)];
+        let speed_fraction = self.speed / MAX_SPEED;
+        // Steering works less the slower the car goes
+        let dx = dt * 2.0 * speed_fraction;
+
+        let steer = steering(input);
+        self.player_x += dx * steer;
+        self.player_x -= dx * speed_fraction * segment.curve * CENTRIFUGAL;
+        self.sky_offset += segment.curve * speed_fraction * dt * 40.0;
+
+        let acceleration = if input.pressed(Button::ActionA) {
+            ACCELERATION
+        } else if input.pressed(Button::ActionB) {
+            BRAKING
+        } else {
+            DECELERATION
+        };
+        self.speed += acceleration * dt;
+        if self.player_x.abs() > 1.0 && self.speed > OFF_ROAD_LIMIT {
+            self.speed += OFF_ROAD_DECELERATION * dt;
+        }
+        self.player_x = self.player_x.clamp(-2.5, 2.5);
+        self.speed = self.speed.clamp(0.0, MAX_SPEED);
+
+        // Running into the back of a car
+        let player_z = self.position + PLAYER_Z;
+        for car in &mut self.traffic {
+            car.z = (car.z + car.speed * dt).rem_euclid(length);
+            let behind = (car.z - player_z).rem_euclid(length);
+            if behind < SEGMENT_LENGTH
+                && (car.offset - self.player_x).abs() < 0.5
+                && self.speed > car.speed
+            {
+                self.speed = car.speed * 0.6;
+                self.position = (car.z - PLAYER_Z - SEGMENT_LENGTH).rem_euclid(length);
+            }
+        }
+
+        let next = self.position + self.speed * dt;
+        if next >= length {
+            let lap = self.lap_started.elapsed();
+            self.last_lap = Some(lap);
+            self.lap_started = Instant::now();
+            let millis = lap.as_millis() as u64;
+            if self.best_lap.is_none_or(|best| millis < best) {
+                self.best_lap = Some(millis);
+                self.stats.set("best_lap", millis);
+                if let Err(e) = self.stats.save() {
+                    println!("Couldn't save the best lap: {}", e);
+                }
+            }
+        }
+        self.position = next.rem_euclid(length);
+    }
+
+    fn draw_sky(&self, frame: &mut Frame) {
+        let (width, height) = (frame.width(), frame.height());
+        let horizon = height / 2;
+        for y in 0..horizon {
+            let t = y as f32 / horizon as f32;
+            let color = SKY.into_format::<f32>().mix(HORIZON.into_format(), t);
+            frame.fill_rect(0, y, width, 1, color.into_format());
+        }
+        // Far hills, moving round with the bends
+        let hills = LinSrgb::new(60, 140, 90);
+        for x in 0..width {
+            let at = (x as f32 + self.sky_offset) / 40.0;
+            let rise = 30.0 + 18.0 * at.sin() + 10.0 * (at * 2.3).sin();
+            let top = horizon.saturating_sub(rise as usize);
+            frame.fill_rect(x, top, 1, horizon - top, hills);
+        }
+        frame.fill_rect(0, horizon, width, height - horizon, GROUND);
+    }
+
+    fn draw_road(&self, frame: &mut Frame) {
+        let (width, height) = (frame.width() as f32, frame.height() as f32);
+        let track = &self.track;
+        let count = track.segments.len();
+        let base = track.find(self.position);
+        let base_fraction = (self.position % SEGMENT_LENGTH) / SEGMENT_LENGTH;
+        let player = &track.segments[track.find(self.position + PLAYER_Z)];
+        let player_fraction = ((self.position + PLAYER_Z) % SEGMENT_LENGTH) / SEGMENT_LENGTH;
+        let player_y = player.y.0 + (player.y.1 - player.y.0) * player_fraction;
+
+        // Cars bucketed by the segment they're on
+        let mut cars: Vec<Vec<&Car>> = (0..count).map(|_| vec![]).collect();
+        for car in &self.traffic {
+            cars[track.find(car.z)].push(car);
+        }
+
+        // Nearest first, each one only filling the rows above the last
+        let mut max_y = height;
+        let mut x = 0.0;
+        let mut dx = -(track.segments[base].curve * base_fraction);
+        let mut sprites = vec![];
+        for n in 0..DRAW_DISTANCE {
+            let index = (base + n) % count;
+            let segment = &track.segments[index];
+            let looped = if index < base { track.length() } else { 0.0 };
+            let z = index as f32 * SEGMENT_LENGTH + looped - self.position;
+            let camera_x = self.player_x * ROAD_WIDTH;
+            let camera_y = player_y + CAMERA_HEIGHT;
+            let near = project((-camera_x - x, segment.y.0 - camera_y, z), width, height);
+            let far = project(
+                (
+                    -camera_x - x - dx,
+                    segment.y.1 - camera_y,
+                    z + SEGMENT_LENGTH,
+                ),
+                width,
+                height,
+            );
+            x += dx;
+            dx += segment.curve;
+
+            let clip = max_y;
+            if z <= CAMERA_DEPTH || far.y >= near.y || far.y >= max_y {
+                continue;
+            }
+            let palette = if (index / RUMBLE_LENGTH).is_multiple_of(2) {
+                &LIGHT
+            } else {
+                &DARK
+            };
+            // Further away fades into the haze
+            let fog = (n as f32 / DRAW_DISTANCE as f32).powi(2) * 0.7;
+            let fade = |color: LinSrgb<u8>| -> LinSrgb<u8> {
+                color
+                    .into_format::<f32>()
+                    .mix(HORIZON.into_format(), fog)
+                    .into_format()
+            };
+            let (grass, rumble, road) = (
+                fade(palette.grass),
+                fade(palette.rumble),
+                fade(palette.road),
+            );
+            let lane = palette.lane.map(fade);
+
+            let top = far.y.max(0.0) as usize;
+            let bottom = near.y.min(max_y).min(height) as usize;
+            for y in top..bottom {
+                let t = (near.y - y as f32) / (near.y - far.y);
+                let center = near.x + (far.x - near.x) * t;
+                let w = near.w + (far.w - near.w) * t;
+                let r = w / 6.0;
+                span(frame, y, 0.0, width, grass);
+                span(frame, y, center - w - r, center + w + r, rumble);
+                span(frame, y, center - w, center + w, road);
+                if let Some(lane) = lane {
+                    let marking = w / 32.0;
+                    for k in 1..LANES {
+                        let lane_x = center - w + 2.0 * w * k as f32 / LANES as f32;
+                        span(
+                            frame,
+                            y,
+                            lane_x - marking / 2.0,
+                            lane_x + marking / 2.0,
+                            lane,
+                        );
+                    }
+                }
+            }
+            max_y = far.y;
+
+            for &(scenery, offset) in &segment.scenery {
+                let x = near.x + near.w * offset;
+                sprites.push((Sprite::Scenery(scenery), (x, near.y), near.scale, clip));
+            }
+            for car in &cars[index] {
+                let fraction = (car.z % SEGMENT_LENGTH) / SEGMENT_LENGTH;
+                let at = (
+                    near.x + (far.x - near.x) * fraction + near.w * car.offset,
+                    near.y + (far.y - near.y) * fraction,
+                );
+                sprites.push((Sprite::Car(car.color), at, near.scale, clip));
+            }
+        }
+
+        // From the back, so nearer things go over further ones
+        for (sprite, at, scale, clip) in sprites.iter().rev() {
+            draw_sprite(frame, sprite, *at, scale * width / 2.0, *clip);
+        }
+    }
+
+    fn draw_status(&self, frame: &mut Frame, input: &Input) {
+        let white = LinSrgb::new(255, 255, 255);
+        let shadow = LinSrgb::new(0, 0, 0);
+        let format_lap = |millis: u64| {
+            format!(
+                "{}:{:02}.{}",
+                millis / 60_000,
+                millis / 1000 % 60,
+                millis / 100 % 10
+            )
+        };
+        let speed = format!("{} km/h", (self.speed / MAX_SPEED * 240.0) as u32);
+        let lap = match self.phase {
+            Phase::Ready => format_lap(0),
+            _ => format_lap(self.lap_started.elapsed().as_millis() as u64),
+        };
+        let mut lines = vec![speed, format!("Lap {}", lap)];
+        if let Some(last) = self.last_lap {
+            lines.push(format!("Last {}", format_lap(last.as_millis() as u64)));
+        }
+        if let Some(best) = self.best_lap {
+            lines.push(format!("Best {}", format_lap(best)));
+        }
+        if let Some(timing) = self.timing {
+            lines.push(format!(
+                "Draw {:.1} ms, frame {:.1} ms",
+                timing.as_secs_f32() * 1000.0,
+                input.delta().as_secs_f32() * 1000.0
+            ));
+        }
+        for (i, line) in lines.iter().enumerate() {
+            let y = 30 + i * 24;
+            frame.text("fonts/Ubuntu-B.ttf", 17, y + 1, 20.0, shadow, line);
+            frame.text("fonts/Ubuntu-B.ttf", 16, y, 20.0, white, line);
+        }
+    }
+
+    fn draw_banner(frame: &mut Frame, lines: &[&str]) {
+        let height = 30 + lines.len() * 28;
+        let top = (frame.height() - height) / 2 - 60;
+        frame.fill_rect(100, top, frame.width() - 200, height, LinSrgb::new(0, 0, 0));
+        for (i, line) in lines.iter().enumerate() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                120,
+                top + 38 + i * 28,
+                20.0,
+                LinSrgb::new(255, 255, 255),
+                line,
+            );
+        }
+    }
+}
+
+impl App for RacerApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        let start = input.start_pressed();
+        let auto_pause = !self.auto_pause.take().is_empty();
+        if input.just_pressed(Button::ActionH) {
+            self.timing = match self.timing {
+                Some(_) => None,
+                None => Some(Duration::ZERO),
+            };
+        }
+        match self.phase {
+            Phase::Ready | Phase::Paused => {
+                if start || input.just_pressed(Button::ActionA) {
+                    if matches!(self.phase, Phase::Ready) {
+                        self.lap_started = Instant::now();
+                    }
+                    self.phase = Phase::Driving;
+                }
+            }
+            Phase::Driving if start || auto_pause => self.phase = Phase::Paused,
+            // Long frames are capped, so a stall doesn't jump the car ahead
+            Phase::Driving => self.drive(input, input.delta().as_secs_f32().min(0.1)),
+        }
+
+        let drawing = Instant::now();
+        self.draw_sky(frame);
+        self.draw_road(frame);
+        let (width, height) = (frame.width() as f32, frame.height() as f32);
+        let lean = match self.phase {
+            Phase::Driving => steering(input) * 6.0 * (self.speed / MAX_SPEED),
+            _ => 0.0,
+        };
+        // The player's car is always the same size, just ahead of the camera
+        draw_car(
+            frame,
+            (width / 2.0, height - 12.0),
+            0.14,
+            height,
+            LinSrgb::new(200, 20, 30),
+            lean,
+        );
+        if self.timing.is_some() {
+            self.timing = Some(drawing.elapsed());
+        }
+        self.draw_status(frame, input);
+
+        match self.phase {
+            Phase::Ready => Self::draw_banner(
+                frame,
+                &[
+                    "A to accelerate, B to brake",
+                    "Stick or D-pad to steer",
+                    "A or START to go",
+                ],
+            ),
+            Phase::Paused => Self::draw_banner(frame, &["Paused", "A or START to carry on"]),
+            Phase::Driving => (),
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        match self.phase {
+            Phase::Driving => Activity::Busy,
+            Phase::Ready | Phase::Paused => Activity::Waiting,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_track() {
+        let track = Track::new(&mut rg35xx::random::rng("racer-test"));
+        // It comes back down to where it started, without a step anywhere
+        assert!(track.last_y().abs() < 1.0);
+        for pair in track.segments.windows(2) {
+            assert!((pair[0].y.1 - pair[1].y.0).abs() < 0.01);
+        }
+        assert_eq!(track.find(0.0), 0);
+        assert_eq!(track.find(track.length() + SEGMENT_LENGTH * 1.5), 1);
+
+        // Straight ahead, the road's centered and gets narrower further off
+        let near = project((0.0, -CAMERA_HEIGHT, 1000.0), 640.0, 480.0);
+        let far = project((0.0, -CAMERA_HEIGHT, 4000.0), 640.0, 480.0);
+        assert_eq!(near.x, 320.0);
+        assert!(far.w < near.w && far.y < near.y && far.y > 240.0);
+    }
+}
@@ -0,0 +1,405 @@
+//! Reaction times, and input plus display latency. Either way it's timed from
+//! the update that draws the square to the one that sees the press, so it's
+//! to the frame (about 16 ms) and includes the launcher's own frame pacing.
+//!
+//! Reaction mode waits a random while, shows the square and times the press.
+//! Latency mode flashes the square on a steady beat to press along with:
+//! once the beat's learned the reaction time drops out, and the average of
+//! how late the presses land is the time from a frame being drawn to it
+//! being seen, plus from a press to the app seeing it.
+
+use palette::LinSrgb;
+use rand::{distributions::Uniform, prelude::*, rngs::StdRng};
+
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::profiles::Store;
+use rg35xx::storage::CsvWriter;
+use rg35xx::time::{Duration, Instant};
+
+pub const MANIFEST: Manifest =
+    Manifest::new("0.1.0").description("Reaction time and input latency");
+
+const SQUARE_SIZE: usize = 160;
+/// The shortest and longest wait before the square shows in reaction mode
+const MIN_WAIT: Duration = Duration::from_millis(1500);
+const MAX_WAIT: Duration = Duration::from_millis(4000);
+/// Latency mode's beat, and how long the square shows on each
+const BEAT: Duration = Duration::from_millis(800);
+const FLASH: Duration = Duration::from_millis(100);
+/// Histogram bars, each this many milliseconds wide
+const BIN_MS: f32 = 20.0;
+const BINS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Reaction,
+    Latency,
+}
+
+impl Mode {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Reaction => "reaction",
+            Self::Latency => "latency",
+        }
+    }
+
+    /// Where the histogram starts, in milliseconds. Latency can be early.
+    fn histogram_start(self) -> f32 {
+        match self {
+            Self::Reaction => 100.0,
+            Self::Latency => -100.0,
+        }
+    }
+}
+
+/// What's on screen in reaction mode
+enum Trial {
+    Ready,
+    /// Waiting to show the square at the instant
+    Waiting(Instant),
+    /// The square's up, drawn first at the instant once it has been
+    Showing(Option<Instant>),
+    /// The last press, or `None` if it came too soon
+    Done(Option<f32>),
+}
+
+/// The spread of a set of times, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Summary {
+    count: usize,
+    mean: f32,
+    median: f32,
+    min: f32,
+    max: f32,
+    std_dev: f32,
+}
+
+fn summarize(samples: &[f32]) -> Option<Summary> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let count = sorted.len();
+    let mean = sorted.iter().sum::<f32>() / count as f32;
+    let median = if count % 2 == 1 {
+        sorted[count / 2]
+    } else {
+        (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0
+    };
+    let variance = sorted.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / count as f32;
+    Some(Summary {
+        count,
+        mean,
+        median,
+        min: sorted[0],
+        max: sorted[count - 1],
+        std_dev: variance.sqrt(),
+    })
+}
+
+/// How far a press `elapsed` into latency mode is from the nearest flash,
+/// negative if it's early
+fn beat_offset(elapsed: Duration) -> f32 {
+    let beat = BEAT.as_secs_f32() * 1000.0;
+    let into = (elapsed.as_secs_f32() * 1000.0) % beat;
+    if into > beat / 2.0 {
+        into - beat
+    } else {
+        into
+    }
+}
+
+pub struct ReactionApp {
+    mode: Mode,
+    trial: Trial,
+    /// When latency mode's beat started, while it's running
+    beat_started: Option<Instant>,
+    reaction_times: Vec<f32>,
+    latencies: Vec<f32>,
+    rng: StdRng,
+    /// Holds the current profile's best reaction time
+    store: Store,
+    status: String,
+}
+
+impl Default for ReactionApp {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Reaction,
+            trial: Trial::Ready,
+            beat_started: None,
+            reaction_times: vec![],
+            latencies: vec![],
+            rng: rg35xx::random::rng("reaction"),
+            store: Store::open("reaction"),
+            status: String::new(),
+        }
+    }
+}
+
+impl ReactionApp {
+    fn samples(&self) -> &[f32] {
+        match self.mode {
+            Mode::Reaction => &self.reaction_times,
+            Mode::Latency => &self.latencies,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        matches!(self.trial, Trial::Waiting(_) | Trial::Showing(_)) || self.beat_started.is_some()
+    }
+
+    fn start_trial(&mut self) {
+        let wait = Uniform::new(MIN_WAIT, MAX_WAIT).sample(&mut self.rng);
+        self.trial = Trial::Waiting(Instant::now() + wait);
+    }
+
+    fn record_reaction(&mut self, ms: f32) {
+        self.reaction_times.push(ms);
+        let best = self.store.get::<f32>("best_ms");
+        if best.is_none_or(|best| ms < best) {
+            self.store.set("best_ms", ms.round());
+            if let Err(e) = self.store.save() {
+                println!("Couldn't save the best time: {}", e);
+            }
+        }
+    }
+
+    /// Writes every time this session to a CSV in the exports directory
+    fn export(&mut self) {
+        let rows = (self.reaction_times.iter().map(|ms| (Mode::Reaction, ms)))
+            .chain(self.latencies.iter().map(|ms| (Mode::Latency, ms)));
+        let result = CsvWriter::create("reaction", &["mode", "ms"]).and_then(|mut csv| {
+            for (mode, ms) in rows {
+                csv.row(&[mode.name().to_string(), format!("{:.1}", ms)])?;
+            }
+            Ok(csv.path().display().to_string())
+        });
+        self.status = match result {
+            Ok(path) => format!("Saved {}", path),
+            Err(e) => format!("Couldn't export: {}", e),
+        };
+    }
+
+    fn handle_input(&mut self, input: &Input) {
+        let press = input.just_pressed(Button::ActionA);
+        if !self.is_running() {
+            if input.just_pressed(Button::BumperL) || input.just_pressed(Button::BumperR) {
+                self.mode = match self.mode {
+                    Mode::Reaction => Mode::Latency,
+                    Mode::Latency => Mode::Reaction,
+                };
+                self.trial = Trial::Ready;
+                self.status.clear();
+            }
+            if input.just_pressed(Button::ActionH) {
+                match self.mode {
+                    Mode::Reaction => self.reaction_times.clear(),
+                    Mode::Latency => self.latencies.clear(),
+                }
+            }
+            if input.start_pressed() {
+                self.export();
+            }
+        }
+        if input.just_pressed(Button::ActionB) {
+            self.trial = Trial::Ready;
+            self.beat_started = None;
+        }
+
+        match self.mode {
+            Mode::Reaction => match self.trial {
+                Trial::Ready | Trial::Done(_) if press => self.start_trial(),
+                Trial::Waiting(_) if press => self.trial = Trial::Done(None),
+                Trial::Waiting(at) if Instant::now() >= at => self.trial = Trial::Showing(None),
+                Trial::Showing(Some(shown)) if press => {
+                    let ms = shown.elapsed().as_secs_f32() * 1000.0;
+                    self.record_reaction(ms);
+                    self.trial = Trial::Done(Some(ms));
+                }
+                _ => (),
+            },
+            Mode::Latency => match self.beat_started {
+                None if press => self.beat_started = Some(Instant::now()),
+                Some(started) if press => self.latencies.push(beat_offset(started.elapsed())),
+                _ => (),
+            },
+        }
+    }
+
+    fn draw_square(&mut self, frame: &mut Frame) {
+        let lit = match self.mode {
+            Mode::Reaction => matches!(self.trial, Trial::Showing(_)),
+            Mode::Latency => self.beat_started.is_some_and(|started| {
+                started.elapsed().as_nanos() % BEAT.as_nanos() < FLASH.as_nanos()
+            }),
+        };
+        let color = if lit {
+            LinSrgb::new(60, 255, 90)
+        } else {
+            LinSrgb::new(40, 40, 40)
+        };
+        let x = (frame.width() - SQUARE_SIZE) / 2;
+        frame.fill_rect(x, 70, SQUARE_SIZE, SQUARE_SIZE, color);
+        // Timed from when it's first drawn, not when it was due
+        if let Trial::Showing(shown @ None) = &mut self.trial {
+            *shown = Some(Instant::now());
+        }
+
+        let message = match (self.mode, &self.trial, self.beat_started) {
+            (Mode::Reaction, Trial::Ready, _) => {
+                "A to start, then A as soon as it lights".to_string()
+            }
+            (Mode::Reaction, Trial::Waiting(_), _) => "Wait for it...".to_string(),
+            (Mode::Reaction, Trial::Showing(_), _) => "Now!".to_string(),
+            (Mode::Reaction, Trial::Done(Some(ms)), _) => format!("{:.0} ms. A to go again", ms),
+            (Mode::Reaction, Trial::Done(None), _) => "Too soon! A to go again".to_string(),
+            (Mode::Latency, _, None) => "A to start, then press A on every flash".to_string(),
+            (Mode::Latency, _, Some(_)) => {
+                "Press A in time with the flashes, B to stop".to_string()
+            }
+        };
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            x,
+            70 + SQUARE_SIZE + 30,
+            18.0,
+            LinSrgb::new(255, 255, 255),
+            &message,
+        );
+    }
+
+    fn draw_histogram(&self, frame: &mut Frame, top: usize) {
+        let samples = self.samples();
+        let start = self.mode.histogram_start();
+        let mut bins = [0; BINS];
+        for &ms in samples {
+            let bin = ((ms - start) / BIN_MS)
+                .floor()
+                .clamp(0.0, (BINS - 1) as f32);
+            bins[bin as usize] += 1;
+        }
+        let tallest = bins.iter().copied().max().unwrap_or(0).max(1);
+        let (height, width) = (80, (frame.width() - 100) / BINS);
+        for (i, &count) in bins.iter().enumerate() {
+            let bar = count * height / tallest;
+            let x = 50 + i * width;
+            frame.fill_rect(x, top, width - 2, height, LinSrgb::new(25, 25, 25));
+            frame.fill_rect(
+                x,
+                top + height - bar,
+                width - 2,
+                bar,
+                LinSrgb::new(255, 0, 0),
+            );
+        }
+        let end = start + BIN_MS * BINS as f32;
+        for (x, label) in [
+            (50, format!("{:.0}", start)),
+            (50 + width * BINS - 50, format!("{:.0} ms", end)),
+        ] {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                x,
+                top + height + 18,
+                14.0,
+                LinSrgb::new(180, 180, 180),
+                &label,
+            );
+        }
+    }
+}
+
+impl App for ReactionApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        self.handle_input(input);
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+
+        for (i, mode) in [Mode::Reaction, Mode::Latency].into_iter().enumerate() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                50 + i * 160,
+                40,
+                24.0,
+                if mode == self.mode {
+                    LinSrgb::new(255, 255, 255)
+                } else {
+                    LinSrgb::new(255, 0, 0)
+                },
+                if mode == Mode::Reaction {
+                    "Reaction"
+                } else {
+                    "Latency"
+                },
+            );
+        }
+        self.draw_square(frame);
+
+        let stats = match summarize(self.samples()) {
+            Some(s) => format!(
+                "{} tries   mean {:.0}   median {:.0}   min {:.0}   max {:.0}   sd {:.0} ms",
+                s.count, s.mean, s.median, s.min, s.max, s.std_dev
+            ),
+            None => "No tries yet".to_string(),
+        };
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            320,
+            16.0,
+            LinSrgb::new(255, 255, 255),
+            &stats,
+        );
+        self.draw_histogram(frame, 335);
+
+        let best = self.store.get::<f32>("best_ms");
+        let hint = match (self.mode, best) {
+            _ if !self.status.is_empty() => self.status.clone(),
+            (Mode::Reaction, Some(best)) => {
+                format!("Best {:.0} ms   L1/R1 mode, Y clear, START export", best)
+            }
+            _ => "L1/R1 mode, Y clear, START export".to_string(),
+        };
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            frame.height() - 15,
+            16.0,
+            LinSrgb::new(180, 180, 180),
+            &hint,
+        );
+    }
+
+    fn activity(&self) -> Activity {
+        // Every frame while timing, so presses are seen as soon as they can be
+        if self.is_running() {
+            Activity::Busy
+        } else {
+            Activity::Waiting
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        assert_eq!(summarize(&[]), None);
+        let summary = summarize(&[300.0, 200.0, 250.0, 250.0]).unwrap();
+        assert_eq!(
+            (summary.count, summary.mean, summary.median),
+            (4, 250.0, 250.0)
+        );
+        assert_eq!((summary.min, summary.max), (200.0, 300.0));
+        assert!((summary.std_dev - 35.355).abs() < 0.01);
+
+        // Late after one flash, or early for the next
+        assert!((beat_offset(BEAT * 3 + Duration::from_millis(40)) - 40.0).abs() < 0.01);
+        assert!((beat_offset(BEAT * 2 - Duration::from_millis(30)) + 30.0).abs() < 0.01);
+    }
+}
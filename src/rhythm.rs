@@ -0,0 +1,873 @@
+//! Rhythm, a game of hitting notes in time with the music as they scroll
+//! down four lanes to a line near the bottom of the screen. The D-pad's left
+//! and down and the B and A buttons play the lanes, from left to right.
+//!
+//! Songs are beat maps, text files in `assets/rhythm/`, so more can be added
+//! without rebuilding:
+//!
+//! ```text
+//! # Comments start with a hash
+//! title = Warm Up
+//! bpm = 96
+//! # Rows a beat, 2 for eighth notes, 4 for sixteenths
+//! steps = 2
+//! # A WAV file to play, and how far into it the first row is, in
+//! # milliseconds. Without one, music is made up out of the notes.
+//! music = rhythm/warm-up.wav
+//! offset = 250
+//!
+//! x...
+//! ....
+//! .x.x
+//! ```
+//!
+//! After the header, each row is a step with a character for each lane, `x`
+//! for a note and `.` for none. The notes are timed from where the audio
+//! output says the speaker has got to, so what's seen and what's heard stay
+//! together; anything left over, from the screen or the buttons, is taken
+//! up by the offset set with L1 and R1 on the song list.
+
+use palette::LinSrgb;
+use rusttype::Scale;
+
+use rg35xx::achievements::{unlock, Achievement};
+use rg35xx::app::{load_font, Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::assets;
+use rg35xx::audio::{self, Sound};
+use rg35xx::profiles::Store;
+use rg35xx::tasks::{Task, Tasks};
+use rg35xx::time::{Duration, Instant};
+use rg35xx::widgets::text_width;
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0")
+    .description("Hit the notes in time with the music")
+    .achievements(&[Achievement::new(
+        "rhythm_full_combo",
+        "In the Groove",
+        "Finish a song in Rhythm without missing a note",
+    )]);
+
+const LANES: usize = 4;
+const LANE_BUTTONS: [Button; LANES] = [
+    Button::PovLeft,
+    Button::PovDown,
+    Button::ActionB,
+    Button::ActionA,
+];
+const LANE_NAMES: [&str; LANES] = ["Left", "Down", "B", "A"];
+const LANE_COLORS: [LinSrgb<u8>; LANES] = [
+    LinSrgb::new(235, 90, 200),
+    LinSrgb::new(80, 160, 255),
+    LinSrgb::new(255, 200, 60),
+    LinSrgb::new(90, 220, 120),
+];
+/// The notes made up into music when a map has none, C E G C
+const LANE_PITCHES: [f32; LANES] = [523.25, 659.25, 783.99, 1046.5];
+
+/// Seconds of count-in before the first row
+const LEAD_IN: f32 = 2.0;
+/// Seconds either side of a note for each judgement
+const PERFECT: f32 = 0.045;
+const GREAT: f32 = 0.090;
+const GOOD: f32 = 0.135;
+/// Seconds after the last note before the results
+const TAIL: f32 = 1.5;
+
+const LANE_LEFT: usize = 180;
+const LANE_WIDTH: usize = 70;
+const HIT_Y: usize = 400;
+/// Pixels a second the notes scroll at
+const SPEED: f32 = 420.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Note {
+    /// Seconds from the start of the music
+    time: f32,
+    lane: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Beatmap {
+    title: String,
+    bpm: f32,
+    /// Rows a beat
+    steps: u32,
+    /// A WAV asset to play instead of making music out of the notes
+    music: Option<String>,
+    /// Seconds into the music of the first row
+    offset: f32,
+    notes: Vec<Note>,
+    /// Seconds from the first row to the end of the last
+    length: f32,
+}
+
+impl Beatmap {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut map = Self {
+            title: "Untitled".to_string(),
+            bpm: 120.0,
+            steps: 2,
+            music: None,
+            offset: 0.0,
+            notes: vec![],
+            length: 0.0,
+        };
+        let mut rows = vec![];
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: &str| format!("line {}: {}", number + 1, message);
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim();
+                match key.trim() {
+                    "title" => map.title = value.to_string(),
+                    "bpm" => {
+                        map.bpm = value
+                            .parse()
+                            .ok()
+                            .filter(|&bpm: &f32| bpm > 0.0)
+                            .ok_or_else(|| error("the bpm should be a number above 0"))?
+                    }
+                    "steps" => {
+                        map.steps = value
+                            .parse()
+                            .ok()
+                            .filter(|&steps| steps > 0)
+                            .ok_or_else(|| error("steps should be a whole number above 0"))?
+                    }
+                    "music" => map.music = Some(value.to_string()),
+                    "offset" => {
+                        map.offset = value
+                            .parse::<f32>()
+                            .map_err(|_| error("the offset should be in milliseconds"))?
+                            / 1000.0
+                    }
+                    key => return Err(error(&format!("there's no setting called {}", key))),
+                }
+                continue;
+            }
+            if line.chars().count() != LANES || !line.chars().all(|c| c == 'x' || c == '.') {
+                return Err(error("rows should be four of x or ."));
+            }
+            rows.push(line);
+        }
+        if rows.is_empty() {
+            return Err("there are no rows".to_string());
+        }
+
+        let step = map.step();
+        for (row, line) in rows.iter().enumerate() {
+            for (lane, c) in line.chars().enumerate() {
+                if c == 'x' {
+                    map.notes.push(Note {
+                        time: map.offset + row as f32 * step,
+                        lane,
+                    });
+                }
+            }
+        }
+        map.length = rows.len() as f32 * step;
+        Ok(map)
+    }
+
+    /// Seconds between rows
+    fn step(&self) -> f32 {
+        60.0 / (self.bpm * self.steps as f32)
+    }
+
+    /// Music made out of the notes: a click on each beat, counting in
+    /// through the lead-in, and each note's pitch where it should be hit
+    fn make_music(&self) -> Sound {
+        let beat = 60.0 / self.bpm;
+        let tick = Sound::tone(1760.0, Duration::from_millis(25), 0.25);
+        let bar = Sound::tone(880.0, Duration::from_millis(60), 0.35);
+        let pitches = LANE_PITCHES.map(|pitch| Sound::tone(pitch, Duration::from_millis(140), 0.2));
+
+        let at = |time: f32| Duration::from_secs_f32((LEAD_IN + time).max(0.0));
+        let mut parts = vec![];
+        let first = -(LEAD_IN / beat).floor() as i32;
+        let last = (self.length / beat).ceil() as i32;
+        for i in first..last {
+            let sound = match i.rem_euclid(4) {
+                0 => &bar,
+                _ => &tick,
+            };
+            parts.push((at(self.offset + i as f32 * beat), sound));
+        }
+        for note in &self.notes {
+            parts.push((at(note.time), &pitches[note.lane]));
+        }
+        Sound::arrange(parts)
+    }
+
+    /// The map's music with the lead-in before it
+    fn music(&self) -> Sound {
+        let Some(path) = &self.music else {
+            return self.make_music();
+        };
+        match assets::load(path).and_then(|bytes| Sound::from_wav(&bytes)) {
+            Ok(music) => Sound::arrange([(Duration::from_secs_f32(LEAD_IN), &music)]),
+            Err(e) => {
+                println!("Couldn't play {}, making music instead: {}", path, e);
+                self.make_music()
+            }
+        }
+    }
+}
+
+/// The beat maps built in and in the assets directory
+fn load_maps() -> Vec<(String, Beatmap)> {
+    let mut paths: Vec<String> = crate::ASSETS
+        .get_dir("rhythm")
+        .into_iter()
+        .flat_map(|dir| dir.files())
+        .map(|file| file.path().to_string_lossy().into_owned())
+        .collect();
+    if let Ok(entries) = std::fs::read_dir(assets::dir().join("rhythm")) {
+        for entry in entries.flatten() {
+            paths.push(format!("rhythm/{}", entry.file_name().to_string_lossy()));
+        }
+    }
+    paths.retain(|path| path.ends_with(".txt"));
+    paths.sort();
+    paths.dedup();
+
+    let mut maps: Vec<(String, Beatmap)> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let parsed = assets::load(&path).and_then(|bytes| {
+                Beatmap::parse(&String::from_utf8_lossy(&bytes))
+                    .map_err(|e| format!("{}, {}", path, e))
+            });
+            match parsed {
+                Ok(map) => Some((path, map)),
+                Err(e) => {
+                    println!("Couldn't load a beat map: {}", e);
+                    None
+                }
+            }
+        })
+        .collect();
+    // Easiest first, by notes a second
+    maps.sort_by(|(_, a), (_, b)| {
+        let rate = |map: &Beatmap| map.notes.len() as f32 / map.length;
+        rate(a).total_cmp(&rate(b))
+    });
+    maps
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Judgement {
+    Perfect,
+    Great,
+    Good,
+    Miss,
+}
+
+impl Judgement {
+    const ALL: [Self; 4] = [Self::Perfect, Self::Great, Self::Good, Self::Miss];
+
+    /// How a hit `error` seconds off the note is judged, or `None` if it's
+    /// too far off to count for that note at all
+    fn of(error: f32) -> Option<Self> {
+        match error.abs() {
+            e if e <= PERFECT => Some(Self::Perfect),
+            e if e <= GREAT => Some(Self::Great),
+            e if e <= GOOD => Some(Self::Good),
+            _ => None,
+        }
+    }
+
+    fn points(self) -> u32 {
+        match self {
+            Self::Perfect => 1000,
+            Self::Great => 700,
+            Self::Good => 400,
+            Self::Miss => 0,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Perfect => "Perfect",
+            Self::Great => "Great",
+            Self::Good => "Good",
+            Self::Miss => "Miss",
+        }
+    }
+
+    fn color(self) -> LinSrgb<u8> {
+        match self {
+            Self::Perfect => LinSrgb::new(120, 230, 255),
+            Self::Great => LinSrgb::new(120, 240, 120),
+            Self::Good => LinSrgb::new(250, 220, 90),
+            Self::Miss => LinSrgb::new(240, 80, 80),
+        }
+    }
+}
+
+/// A song being played
+struct Song {
+    path: String,
+    map: Beatmap,
+    /// Each note's judgement once it has one, and how far off it was
+    judged: Vec<Option<(Judgement, f32)>>,
+    score: u32,
+    combo: u32,
+    max_combo: u32,
+    /// The music being read or made up in the background, until it's ready
+    music: Option<Task<Sound>>,
+    playing: Option<audio::Playing>,
+    /// When the music started, and the clock to go by without an output
+    started: Option<Instant>,
+    /// The last judgement, to show, and when it was
+    last: Option<(Judgement, f32, Instant)>,
+}
+
+impl Song {
+    fn new(path: String, map: Beatmap) -> Self {
+        Self {
+            judged: vec![None; map.notes.len()],
+            path,
+            map,
+            score: 0,
+            combo: 0,
+            max_combo: 0,
+            music: None,
+            playing: None,
+            started: None,
+            last: None,
+        }
+    }
+
+    /// Reads or makes up the music on `tasks`, since a long song can take a
+    /// while
+    fn load(&mut self, tasks: &mut Tasks) {
+        let map = self.map.clone();
+        self.music = Some(tasks.spawn(move || map.music()));
+    }
+
+    /// Starts the music once it's ready, saying whether it has
+    fn start_when_ready(&mut self) -> bool {
+        if self.started.is_some() {
+            return true;
+        }
+        let Some(task) = &mut self.music else {
+            return false;
+        };
+        let music = match task.poll() {
+            Some(music) => music,
+            None if task.is_pending() => return false,
+            // Playing to the clock if it failed, which it will have said why
+            None => Sound::default(),
+        };
+        self.music = None;
+        self.playing = Some(audio::play(&music));
+        self.started = Some(Instant::now());
+        true
+    }
+
+    fn stop(&mut self) {
+        self.music = None;
+        if let Some(playing) = self.playing.take() {
+            playing.stop();
+        }
+    }
+
+    /// Seconds into the map's music being heard now, less `offset` seconds,
+    /// and negative during the lead-in
+    fn time(&self, offset: f32) -> f32 {
+        let Some(started) = self.started else {
+            return -LEAD_IN - offset;
+        };
+        let elapsed = self
+            .playing
+            .and_then(|playing| playing.position())
+            .unwrap_or_else(|| started.elapsed());
+        elapsed.as_secs_f32() - LEAD_IN - offset
+    }
+
+    fn judge(&mut self, note: usize, judgement: Judgement, error: f32) {
+        self.judged[note] = Some((judgement, error));
+        self.score += judgement.points();
+        self.combo = match judgement {
+            Judgement::Miss => 0,
+            _ => self.combo + 1,
+        };
+        self.max_combo = self.max_combo.max(self.combo);
+        self.last = Some((judgement, error, Instant::now()));
+    }
+
+    /// Judges a press in `lane` at `time` against the closest note waiting
+    /// there, if any is close enough
+    fn hit(&mut self, lane: usize, time: f32) {
+        let closest = (0..self.map.notes.len())
+            .filter(|&i| self.judged[i].is_none() && self.map.notes[i].lane == lane)
+            .map(|i| (i, time - self.map.notes[i].time))
+            .min_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()));
+        if let Some((note, error)) = closest {
+            if let Some(judgement) = Judgement::of(error) {
+                self.judge(note, judgement, error);
+            }
+        }
+    }
+
+    /// Misses the notes it's too late to hit by `time`
+    fn sweep(&mut self, time: f32) {
+        for i in 0..self.map.notes.len() {
+            if self.judged[i].is_none() && time - self.map.notes[i].time > GOOD {
+                self.judge(i, Judgement::Miss, 0.0);
+            }
+        }
+    }
+
+    fn is_finished(&self, time: f32) -> bool {
+        let end = self.map.offset + self.map.length;
+        self.judged.iter().all(Option::is_some) && time > end + TAIL
+    }
+
+    fn count(&self, judgement: Judgement) -> usize {
+        self.judged
+            .iter()
+            .filter(|judged| matches!(judged, Some((j, _)) if *j == judgement))
+            .count()
+    }
+
+    /// The fraction of the best possible score for the notes so far
+    fn accuracy(&self) -> f32 {
+        let judged = self.judged.iter().filter(|judged| judged.is_some()).count();
+        let best = judged as u32 * Judgement::Perfect.points();
+        self.score as f32 / best.max(1) as f32
+    }
+
+    fn grade(&self) -> &'static str {
+        match self.accuracy() {
+            a if a >= 0.95 => "S",
+            a if a >= 0.9 => "A",
+            a if a >= 0.8 => "B",
+            a if a >= 0.7 => "C",
+            _ => "D",
+        }
+    }
+
+    /// Seconds late the hits were on average, early if negative, so the
+    /// offset can be put right
+    fn mean_error(&self) -> Option<f32> {
+        let errors: Vec<f32> = self
+            .judged
+            .iter()
+            .filter_map(|judged| match judged {
+                Some((Judgement::Miss, _)) | None => None,
+                Some((_, error)) => Some(*error),
+            })
+            .collect();
+        (errors.len() >= 8).then(|| errors.iter().sum::<f32>() / errors.len() as f32)
+    }
+}
+
+enum Phase {
+    Select,
+    Playing,
+    Results { best: bool },
+}
+
+pub struct RhythmApp {
+    maps: Vec<(String, Beatmap)>,
+    /// The highlighted song on the list
+    choice: usize,
+    song: Option<Song>,
+    phase: Phase,
+    /// Milliseconds late the notes are judged, to make up for latency the
+    /// outputs don't know about
+    offset_ms: i32,
+    stats: Store,
+}
+
+impl Default for RhythmApp {
+    fn default() -> Self {
+        let stats = Store::open("rhythm");
+        Self {
+            maps: load_maps(),
+            choice: 0,
+            song: None,
+            phase: Phase::Select,
+            offset_ms: stats.get("offset_ms").unwrap_or(0),
+            stats,
+        }
+    }
+}
+
+impl RhythmApp {
+    fn offset(&self) -> f32 {
+        self.offset_ms as f32 / 1000.0
+    }
+
+    /// The key the best score for the map at `path` is kept under
+    fn best_key(path: &str) -> String {
+        let name = path.trim_start_matches("rhythm/").trim_end_matches(".txt");
+        format!("best_{}", name)
+    }
+
+    fn save(&self) {
+        if let Err(e) = self.stats.save() {
+            println!("Couldn't save the Rhythm scores: {}", e);
+        }
+    }
+
+    fn update_select(&mut self, input: &Input, start: bool, tasks: &mut Tasks) {
+        let count = self.maps.len();
+        if count > 0 {
+            if input.just_pressed(Button::PovDown) {
+                self.choice = (self.choice + 1) % count;
+            }
+            if input.just_pressed(Button::PovUp) {
+                self.choice = (self.choice + count - 1) % count;
+            }
+        }
+        let step =
+            input.just_pressed(Button::BumperR) as i32 - input.just_pressed(Button::BumperL) as i32;
+        if step != 0 {
+            self.offset_ms = (self.offset_ms + step * 5).clamp(-300, 300);
+            self.stats.set("offset_ms", self.offset_ms);
+            self.save();
+        }
+        if start || input.just_pressed(Button::ActionA) {
+            if let Some((path, map)) = self.maps.get(self.choice) {
+                let mut song = Song::new(path.clone(), map.clone());
+                song.load(tasks);
+                self.song = Some(song);
+                self.phase = Phase::Playing;
+            }
+        }
+    }
+
+    fn update_playing(&mut self, input: &Input, start: bool) {
+        let offset = self.offset();
+        let Some(song) = &mut self.song else {
+            self.phase = Phase::Select;
+            return;
+        };
+        // Giving up, since the music can't be paused in time
+        if start {
+            song.stop();
+            self.song = None;
+            self.phase = Phase::Select;
+            return;
+        }
+        if !song.start_when_ready() {
+            return;
+        }
+        let time = song.time(offset);
+        for (lane, button) in LANE_BUTTONS.into_iter().enumerate() {
+            if input.just_pressed(button) {
+                song.hit(lane, time);
+            }
+        }
+        song.sweep(time);
+        if !song.is_finished(time) {
+            return;
+        }
+
+        song.stop();
+        if song.count(Judgement::Miss) == 0 {
+            unlock("rhythm_full_combo");
+        }
+        let key = Self::best_key(&song.path);
+        let best = song.score > self.stats.get(&key).unwrap_or(0);
+        if best {
+            self.stats.set(&key, song.score);
+            self.save();
+        }
+        self.phase = Phase::Results { best };
+    }
+
+    fn draw_centered(frame: &mut Frame, y: usize, size: f32, color: LinSrgb<u8>, s: &str) {
+        let width = text_width(&load_font("fonts/Ubuntu-B.ttf"), Scale::uniform(size), s);
+        let x = (frame.width() as f32 - width).max(0.0) as usize / 2;
+        frame.text("fonts/Ubuntu-B.ttf", x, y, size, color, s);
+    }
+
+    fn draw_select(&self, frame: &mut Frame) {
+        let white = LinSrgb::new(255, 255, 255);
+        let grey = LinSrgb::new(170, 170, 170);
+        frame.text("fonts/Ubuntu-B.ttf", 60, 80, 40.0, white, "Rhythm");
+        if self.maps.is_empty() {
+            let message = "There are no beat maps in assets/rhythm";
+            frame.text("fonts/Ubuntu-B.ttf", 70, 140, 20.0, grey, message);
+        }
+        for (i, (path, map)) in self.maps.iter().enumerate() {
+            let y = 140 + i * 44;
+            if i == self.choice {
+                frame.fill_rect(50, y - 30, 540, 40, LinSrgb::new(70, 40, 110));
+            }
+            frame.text("fonts/Ubuntu-B.ttf", 70, y, 26.0, white, &map.title);
+            let detail = match self.stats.get::<u32>(&Self::best_key(path)) {
+                Some(best) => format!("{} bpm, best {}", map.bpm, best),
+                None => format!("{} bpm, {} notes", map.bpm, map.notes.len()),
+            };
+            frame.text("fonts/Ubuntu-B.ttf", 330, y, 20.0, grey, &detail);
+        }
+
+        let offset = format!("Offset {:+} ms, L1 and R1 to change", self.offset_ms);
+        frame.text("fonts/Ubuntu-B.ttf", 60, 400, 20.0, grey, &offset);
+        let keys = "D-pad left and down, B and A play the lanes";
+        frame.text("fonts/Ubuntu-B.ttf", 60, 428, 20.0, grey, keys);
+        if !audio::has_output() {
+            let silent = "There's no sound here, so play along to the picture";
+            frame.text("fonts/Ubuntu-B.ttf", 60, 456, 20.0, grey, silent);
+        }
+    }
+
+    fn draw_playing(&self, song: &Song, input: &Input, frame: &mut Frame) {
+        let white = LinSrgb::new(255, 255, 255);
+        let grey = LinSrgb::new(150, 150, 160);
+        let time = song.time(self.offset());
+
+        for lane in 0..LANES {
+            let x = LANE_LEFT + lane * LANE_WIDTH;
+            let pressed = input.pressed(LANE_BUTTONS[lane]);
+            let shade = match (pressed, lane % 2) {
+                (true, _) => LinSrgb::new(50, 50, 70),
+                (false, 0) => LinSrgb::new(22, 22, 32),
+                (false, _) => LinSrgb::new(28, 28, 40),
+            };
+            frame.fill_rect(x, 0, LANE_WIDTH, frame.height(), shade);
+            let color = LANE_COLORS[lane];
+            // The target each note should be hit on, lit while pressed
+            let target = match pressed {
+                true => color,
+                false => LinSrgb::new(color.red / 3, color.green / 3, color.blue / 3),
+            };
+            frame.fill_rect(x + 4, HIT_Y - 8, LANE_WIDTH - 8, 16, target);
+            let name = LANE_NAMES[lane];
+            let width = text_width(&load_font("fonts/Ubuntu-B.ttf"), Scale::uniform(18.0), name);
+            let left = x + (LANE_WIDTH - width as usize) / 2;
+            frame.text("fonts/Ubuntu-B.ttf", left, HIT_Y + 40, 18.0, grey, name);
+        }
+        frame.fill_rect(LANE_LEFT, HIT_Y - 1, LANE_WIDTH * LANES, 2, white);
+
+        for (note, judged) in song.map.notes.iter().zip(&song.judged) {
+            let color = match judged {
+                None => LANE_COLORS[note.lane],
+                Some((Judgement::Miss, _)) => LinSrgb::new(70, 70, 80),
+                Some(_) => continue,
+            };
+            let y = HIT_Y as f32 - (note.time - time) * SPEED;
+            if !(-10.0..frame.height() as f32 + 10.0).contains(&y) {
+                continue;
+            }
+            let top = (y - 7.0).max(0.0) as usize;
+            let x = LANE_LEFT + note.lane * LANE_WIDTH;
+            frame.fill_rect(x + 6, top, LANE_WIDTH - 12, 14, color);
+        }
+
+        if let Some((judgement, error, at)) = song.last {
+            if at.elapsed() < Duration::from_millis(600) {
+                let center = LANE_LEFT + LANE_WIDTH * LANES / 2;
+                let name = judgement.name();
+                let font = load_font("fonts/Ubuntu-B.ttf");
+                let width = text_width(&font, Scale::uniform(36.0), name) as usize;
+                let color = judgement.color();
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    center - width / 2,
+                    300,
+                    36.0,
+                    color,
+                    name,
+                );
+                let ms = (error * 1000.0).round() as i32;
+                if judgement != Judgement::Miss && ms != 0 {
+                    let timing = match ms > 0 {
+                        true => format!("{} ms late", ms),
+                        false => format!("{} ms early", -ms),
+                    };
+                    let width = text_width(&font, Scale::uniform(18.0), &timing) as usize;
+                    frame.text(
+                        "fonts/Ubuntu-B.ttf",
+                        center - width / 2,
+                        326,
+                        18.0,
+                        grey,
+                        &timing,
+                    );
+                }
+            }
+        }
+
+        frame.text("fonts/Ubuntu-B.ttf", 16, 40, 20.0, white, &song.map.title);
+        let right = LANE_LEFT + LANE_WIDTH * LANES + 20;
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            right,
+            40,
+            28.0,
+            white,
+            &song.score.to_string(),
+        );
+        let accuracy = format!("{:.1}%", song.accuracy() * 100.0);
+        frame.text("fonts/Ubuntu-B.ttf", right, 68, 18.0, grey, &accuracy);
+        if song.combo >= 4 {
+            let combo = format!("{} combo", song.combo);
+            frame.text("fonts/Ubuntu-B.ttf", right, 200, 24.0, white, &combo);
+        }
+        if song.started.is_none() {
+            Self::draw_centered(frame, 220, 32.0, white, "Loading...");
+        } else if time < 0.0 {
+            let count = format!("{}", (-time).ceil());
+            Self::draw_centered(frame, 220, 48.0, white, &count);
+        }
+
+        let end = song.map.offset + song.map.length;
+        let progress = (time / end).clamp(0.0, 1.0);
+        let width = (progress * frame.width() as f32) as usize;
+        frame.fill_rect(0, 0, width, 4, LANE_COLORS[1]);
+    }
+
+    fn draw_results(&self, song: &Song, best: bool, frame: &mut Frame) {
+        let white = LinSrgb::new(255, 255, 255);
+        let grey = LinSrgb::new(170, 170, 170);
+        Self::draw_centered(frame, 70, 32.0, white, &song.map.title);
+        Self::draw_centered(frame, 170, 96.0, white, song.grade());
+        let score = match best {
+            true => format!("{}, a new best", song.score),
+            false => song.score.to_string(),
+        };
+        Self::draw_centered(frame, 220, 28.0, white, &score);
+
+        for (i, judgement) in Judgement::ALL.into_iter().enumerate() {
+            let y = 270 + i * 28;
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                200,
+                y,
+                22.0,
+                judgement.color(),
+                judgement.name(),
+            );
+            let count = song.count(judgement).to_string();
+            frame.text("fonts/Ubuntu-B.ttf", 330, y, 22.0, white, &count);
+        }
+        let combo = match song.count(Judgement::Miss) {
+            0 => "Full combo".to_string(),
+            _ => format!("Longest combo {}", song.max_combo),
+        };
+        let accuracy = format!("{}, {:.1}% accurate", combo, song.accuracy() * 100.0);
+        Self::draw_centered(frame, 404, 20.0, grey, &accuracy);
+        if let Some(error) = song.mean_error() {
+            let ms = (error * 1000.0).round() as i32;
+            if ms.abs() >= 10 {
+                let timing = match ms > 0 {
+                    true => format!("Hits were {} ms late on average,", ms),
+                    false => format!("Hits were {} ms early on average,", -ms),
+                };
+                Self::draw_centered(frame, 432, 18.0, grey, &timing);
+                let fix = "which the offset on the song list can make up for";
+                Self::draw_centered(frame, 454, 18.0, grey, fix);
+            }
+        }
+    }
+}
+
+impl App for RhythmApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        let start = input.start_pressed();
+        match self.phase {
+            Phase::Select => self.update_select(input, start, ctx.tasks),
+            Phase::Playing => self.update_playing(input, start),
+            Phase::Results { .. } => {
+                if start
+                    || input.just_pressed(Button::ActionA)
+                    || input.just_pressed(Button::ActionB)
+                {
+                    self.song = None;
+                    self.phase = Phase::Select;
+                }
+            }
+        }
+
+        frame.fill_rect(
+            0,
+            0,
+            frame.width(),
+            frame.height(),
+            LinSrgb::new(12, 12, 20),
+        );
+        match (&self.phase, &self.song) {
+            (Phase::Playing, Some(song)) => self.draw_playing(song, input, frame),
+            (Phase::Results { best }, Some(song)) => self.draw_results(song, *best, frame),
+            _ => self.draw_select(frame),
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        match self.phase {
+            Phase::Playing => Activity::Busy,
+            _ => Activity::Waiting,
+        }
+    }
+
+    fn on_exit(&mut self) {
+        if let Some(song) = &mut self.song {
+            song.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let map = Beatmap::parse(
+            "# A comment\ntitle = Test\nbpm = 120\nsteps = 2 # eighths\n\
+             offset = 500\n\nx..x\n....\n.x..\n",
+        )
+        .unwrap();
+        assert_eq!(map.title, "Test");
+        assert_eq!(map.step(), 0.25);
+        assert_eq!(map.length, 0.75);
+        let notes: Vec<(f32, usize)> = map.notes.iter().map(|n| (n.time, n.lane)).collect();
+        assert_eq!(notes, [(0.5, 0), (0.5, 3), (1.0, 1)]);
+
+        assert_eq!(
+            Beatmap::parse("x..\n").unwrap_err(),
+            "line 1: rows should be four of x or ."
+        );
+        assert!(Beatmap::parse("bpm = fast\nx...")
+            .unwrap_err()
+            .starts_with("line 1"));
+        assert!(Beatmap::parse("title = Empty").is_err());
+    }
+
+    #[test]
+    fn test_judging() {
+        assert_eq!(Judgement::of(-0.03), Some(Judgement::Perfect));
+        assert_eq!(Judgement::of(0.06), Some(Judgement::Great));
+        assert_eq!(Judgement::of(-0.1), Some(Judgement::Good));
+        assert_eq!(Judgement::of(0.2), None);
+
+        let map = Beatmap::parse("steps = 1\nbpm = 60\nx...\nx...\n.x..\n").unwrap();
+        let mut song = Song::new("rhythm/test.txt".to_string(), map);
+        // Too early to count, then just late for the first note, and nothing
+        // in the lane for a stray press
+        song.hit(0, -0.5);
+        song.hit(0, 0.06);
+        song.hit(3, 0.1);
+        assert_eq!(song.judged[0], Some((Judgement::Great, 0.06)));
+        assert_eq!(song.judged[1], None);
+        // The second note is missed once it's too late
+        song.sweep(1.0 + GOOD + 0.01);
+        assert_eq!(song.judged[1], Some((Judgement::Miss, 0.0)));
+        assert_eq!(song.combo, 0);
+        song.hit(1, 2.0);
+        assert_eq!(song.score, 1700);
+        assert_eq!(song.max_combo, 1);
+        assert!(!song.is_finished(2.0));
+        assert!(song.is_finished(3.0 + TAIL + 0.1));
+    }
+}
@@ -0,0 +1,858 @@
+use enum_iterator::{all, first, last, Sequence};
+use enum_map::EnumMap;
+use palette::LinSrgb;
+
+use rg35xx::actions::{set_confirm_swapped, ActionMap};
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::axes::{calibration, set_calibration, Axis, Calibration, Range};
+use rg35xx::config::{config, format_hundredths, set_config, Theme, UI_SCALE_RANGE};
+use rg35xx::display::{ColorFilter, Flip};
+use rg35xx::lock::{PinEvent, PinPad};
+use rg35xx::time::{Duration, Instant};
+use rg35xx::widgets::{FocusRing, Ui, UiEvent, Widget};
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0").description("Device status and options");
+
+/// What can be changed in Settings > Display, in order
+const DISPLAY_ROWS: [&str; 5] = [
+    "Screen",
+    "Red gamma",
+    "Green gamma",
+    "Blue gamma",
+    "Contrast",
+];
+
+/// What can be changed in Settings > Accessibility, in order
+const ACCESSIBILITY_ROWS: [&str; 5] = ["Theme", "UI scale", "Extra time", "Color filter", "Rumble"];
+
+/// How often the network page re-reads interface status
+const NETWORK_REFRESH: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
+enum Section {
+    Network,
+    Controls,
+    Display,
+    Accessibility,
+    Calibration,
+    Profiles,
+    Lock,
+}
+
+impl Section {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Network => "Network",
+            Self::Controls => "Controls",
+            Self::Display => "Display",
+            Self::Accessibility => "Accessibility",
+            Self::Calibration => "Calibration",
+            Self::Profiles => "Profiles",
+            Self::Lock => "PIN lock",
+        }
+    }
+}
+
+/// How far through changing the PIN
+enum PinStep {
+    /// Checking the current PIN first, then removing it or asking for a new one
+    Current {
+        remove: bool,
+    },
+    New,
+    /// Asking for the new PIN again
+    Repeat(Vec<Button>),
+}
+
+/// How far through recording a new calibration
+enum Recording {
+    /// Waiting for the sticks to be let go
+    Center,
+    /// Watching for the furthest each axis goes
+    Limits(EnumMap<Axis, Range>),
+}
+
+#[derive(Default)]
+pub struct SettingsApp {
+    /// Focus in the list of sections, by name
+    sections: FocusRing,
+    /// The open section, or `None` for the list of sections
+    open: Option<Section>,
+    #[cfg(not(target_arch = "wasm32"))]
+    interfaces: Vec<rg35xx::net::Interface>,
+    /// Saved Wi-Fi networks and the one shown as a QR code, while A's
+    /// showing them
+    #[cfg(not(target_arch = "wasm32"))]
+    wifi_qr: Option<(Vec<rg35xx::net::WifiNetwork>, usize)>,
+    last_refresh: Option<Instant>,
+    /// The result of the last change to the controls
+    controls_status: String,
+    /// The launcher-wide controls, read from controls.txt when Settings >
+    /// Controls first draws and again after they're changed
+    actions: Option<ActionMap>,
+    recording: Option<Recording>,
+    /// Index into the list of profiles
+    selected_profile: usize,
+    /// A profile Y was pressed on once, to be deleted if it's pressed again
+    deleting_profile: Option<String>,
+    pin_prompt: Option<(PinStep, PinPad)>,
+    /// Settings > Display's rows, built when it's first opened
+    display: Option<Ui>,
+    /// Whether Settings > Display is showing the test pattern
+    test_pattern: bool,
+    /// Settings > Accessibility's rows, built again when the UI scale
+    /// changes so they show it
+    accessibility: Option<Ui>,
+}
+
+impl SettingsApp {
+    fn draw_sections(&mut self, input: &Input, frame: &mut Frame) {
+        self.sections
+            .set_ids(all::<Section>().map(|section| section.name()));
+        self.sections.update(input);
+        for (i, section) in all::<Section>().enumerate() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                50,
+                50 + i * 40,
+                36.0,
+                if self.sections.is_focused(section.name()) {
+                    LinSrgb::new(255, 255, 255)
+                } else {
+                    LinSrgb::new(255, 0, 0)
+                },
+                section.name(),
+            );
+            if self.sections.activated(section.name()) {
+                self.open = Some(section);
+                self.last_refresh = None;
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn draw_network(&mut self, input: &Input, frame: &mut Frame) {
+        if self
+            .last_refresh
+            .is_none_or(|last| last.elapsed() >= NETWORK_REFRESH)
+        {
+            self.interfaces = rg35xx::net::interfaces();
+            self.last_refresh = Some(Instant::now());
+        }
+
+        let mut ctx = frame.context();
+        ctx.offset(50, 50);
+        ctx.set_fontsize(36.0);
+        ctx.text("Network");
+        ctx.set_fontsize(18.0);
+        ctx.offset(0, 10);
+
+        let interfaces = self
+            .interfaces
+            .iter()
+            .filter(|i| !i.is_loopback())
+            .collect::<Vec<_>>();
+        if interfaces.is_empty() {
+            ctx.offset(0, 30);
+            ctx.text("No network adapters found");
+        }
+        for interface in interfaces {
+            ctx.offset(0, 30);
+            ctx.set_color(LinSrgb::new(255, 255, 255));
+            ctx.text(&format!(
+                "{} ({}): {}",
+                interface.name,
+                if interface.wireless { "Wi-Fi" } else { "wired" },
+                if interface.up { "up" } else { "down" },
+            ));
+            ctx.set_color(LinSrgb::new(180, 180, 180));
+            for address in interface.addresses.iter() {
+                ctx.offset(0, 20);
+                ctx.text(&format!("    {}", address));
+            }
+        }
+        #[cfg(feature = "remote")]
+        if let Some(url) = rg35xx::remote::page_url() {
+            ctx.offset(0, 30);
+            ctx.set_color(LinSrgb::new(255, 255, 255));
+            ctx.text("Remote controller:");
+            ctx.offset(0, 20);
+            ctx.set_color(LinSrgb::new(180, 180, 180));
+            ctx.text(&format!("    {}", url));
+        }
+        self.draw_wifi_qr(input, frame);
+    }
+
+    /// A QR code a phone can scan to join the Wi-Fi network the device is
+    /// set up for, with left and right going through them if there are more
+    #[cfg(not(target_arch = "wasm32"))]
+    fn draw_wifi_qr(&mut self, input: &Input, frame: &mut Frame) {
+        let hint_y = frame.height() - 20;
+        let gray = LinSrgb::new(180, 180, 180);
+        if input.just_pressed(Button::ActionA) && self.wifi_qr.is_none() {
+            self.wifi_qr = Some((rg35xx::net::wifi_networks(), 0));
+        } else if input.just_pressed(Button::ActionB) {
+            self.wifi_qr = None;
+        }
+        let Some((networks, selected)) = &mut self.wifi_qr else {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                50,
+                hint_y,
+                18.0,
+                gray,
+                "A: QR code for joining the Wi-Fi",
+            );
+            return;
+        };
+        if networks.is_empty() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                50,
+                hint_y,
+                18.0,
+                gray,
+                "No saved Wi-Fi passwords found, B to go back",
+            );
+            return;
+        }
+        if input.just_pressed(Button::PovLeft) {
+            *selected = (*selected + networks.len() - 1) % networks.len();
+        }
+        if input.just_pressed(Button::PovRight) {
+            *selected = (*selected + 1) % networks.len();
+        }
+        let network = &networks[*selected];
+        let text = rg35xx::qr::wifi(&network.ssid, network.password.as_deref());
+        let size = 280;
+        let x = frame.width() - size - 30;
+        if let Ok(qr) = rg35xx::qr::Qr::new(&text) {
+            qr.draw(frame, x, 40, size);
+        }
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            x,
+            40 + size + 30,
+            22.0,
+            LinSrgb::new(255, 255, 255),
+            &network.ssid,
+        );
+        let hint = if networks.len() > 1 {
+            format!(
+                "{}/{}, left/right for others, B to hide",
+                *selected + 1,
+                networks.len()
+            )
+        } else {
+            "B to hide".to_string()
+        };
+        frame.text("fonts/Ubuntu-B.ttf", 50, hint_y, 18.0, gray, &hint);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn draw_network(&mut self, _input: &Input, frame: &mut Frame) {
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            50,
+            18.0,
+            LinSrgb::new(255, 255, 255),
+            "Network status isn't available in the browser",
+        );
+    }
+}
+
+impl SettingsApp {
+    fn draw_controls(&mut self, input: &Input, frame: &mut Frame) {
+        let swapped = self
+            .actions
+            .get_or_insert_with(ActionMap::global)
+            .confirm_swapped();
+        if input.just_pressed(Button::PovLeft) || input.just_pressed(Button::PovRight) {
+            self.controls_status = match set_confirm_swapped(!swapped) {
+                Ok(()) => "Saved".to_string(),
+                Err(e) => format!("Couldn't save: {}", e),
+            };
+            self.actions = None;
+        }
+        let swapped = self
+            .actions
+            .get_or_insert_with(ActionMap::global)
+            .confirm_swapped();
+
+        let mut ctx = frame.context();
+        ctx.offset(50, 50);
+        ctx.set_fontsize(36.0);
+        ctx.text("Controls");
+        ctx.set_fontsize(18.0);
+        ctx.offset(0, 40);
+        ctx.text(&format!(
+            "Confirm: < {} >   Back: {}",
+            if swapped { "B" } else { "A" },
+            if swapped { "A" } else { "B" },
+        ));
+        ctx.set_color(LinSrgb::new(180, 180, 180));
+        ctx.offset(0, 30);
+        ctx.text("For every app that uses the standard controls");
+        ctx.offset(0, 30);
+        ctx.text(&self.controls_status);
+    }
+}
+
+/// The lowest and highest gamma and contrast, in hundredths
+const COLOR_RANGE: (u16, u16) = (10, 500);
+
+/// Settings > Display, with a widget for each of [`DISPLAY_ROWS`] named
+/// after it: a value for the screen and sliders for the rest
+fn display_ui() -> Ui {
+    let mut rows = vec![Widget::title("Display")];
+    rows.push(Widget::value(DISPLAY_ROWS[0], DISPLAY_ROWS[0], ""));
+    rows.extend(
+        DISPLAY_ROWS[1..]
+            .iter()
+            .map(|name| Widget::slider(name, name, 200)),
+    );
+    rows.extend([
+        Widget::space(2),
+        Widget::label("Flip for grips that hold the device upside-down"),
+        Widget::label("Gamma and contrast match the screen to another"),
+        Widget::label("").with_id("status"),
+    ]);
+    Ui::new(Widget::column(rows))
+}
+
+impl SettingsApp {
+    fn draw_display(&mut self, input: &Input, frame: &mut Frame) {
+        if input.just_pressed(Button::ActionH) {
+            self.test_pattern = !self.test_pattern;
+        }
+
+        // Still changed while the test pattern's showing, to compare by it
+        let ui = self.display.get_or_insert_with(display_ui);
+        let mut config = config();
+        if let Some(UiEvent::Stepped(id, step)) = ui.handle_input(input) {
+            match DISPLAY_ROWS.iter().position(|&name| name == id) {
+                Some(0) if step < 0 => {
+                    config.flip = config.flip.previous().unwrap_or(last::<Flip>().unwrap());
+                }
+                Some(0) => config.flip = config.flip.next().unwrap_or(first::<Flip>().unwrap()),
+                Some(row) => {
+                    let value = match row {
+                        1..=3 => &mut config.color.gamma[row - 1],
+                        _ => &mut config.color.contrast,
+                    };
+                    *value = value
+                        .saturating_add_signed(step as i16 * 5)
+                        .clamp(COLOR_RANGE.0, COLOR_RANGE.1);
+                }
+                None => (),
+            }
+            self.controls_status = match set_config(config.clone()) {
+                Ok(()) => "Saved".to_string(),
+                Err(e) => format!("Couldn't save: {}", e),
+            };
+        }
+
+        if self.test_pattern {
+            draw_test_pattern(frame);
+            return;
+        }
+
+        ui.set_text(DISPLAY_ROWS[0], config.flip.label());
+        for (row, name) in DISPLAY_ROWS.iter().enumerate().skip(1) {
+            let value = match row {
+                1..=3 => config.color.gamma[row - 1],
+                _ => config.color.contrast,
+            };
+            ui.set_text(name, &format_hundredths(value));
+            if let Some(slider) = ui.get_mut(name) {
+                let (min, max) = COLOR_RANGE;
+                slider.set_value(value.saturating_sub(min) as f32 / (max - min) as f32);
+            }
+        }
+        ui.set_text("status", &self.controls_status);
+        ui.draw(frame, 50, 16);
+
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            frame.height() - 20,
+            18.0,
+            LinSrgb::new(180, 180, 180),
+            "Y test pattern",
+        );
+    }
+}
+
+/// Ramps of gray, red, green and blue to compare screens by, above stripes of
+/// black and white next to a gray of the same brightness. The two halves of
+/// the bottom match when the gamma is right.
+fn draw_test_pattern(frame: &mut Frame) {
+    let (width, height) = (frame.width(), frame.height());
+    let steps = 32;
+    let band = height / 5;
+    let colors: [fn(u8) -> LinSrgb<u8>; 4] = [
+        |v| LinSrgb::new(v, v, v),
+        |v| LinSrgb::new(v, 0, 0),
+        |v| LinSrgb::new(0, v, 0),
+        |v| LinSrgb::new(0, 0, v),
+    ];
+    for (i, color) in colors.iter().enumerate() {
+        for step in 0..steps {
+            let value = (step * 255 / (steps - 1)) as u8;
+            let left = step * width / steps;
+            let right = (step + 1) * width / steps;
+            frame.fill_rect(left, i * band, right - left, band, color(value));
+        }
+    }
+
+    let top = 4 * band;
+    for y in top..height {
+        let value = if y % 2 == 0 { 255 } else { 0 };
+        frame.fill_rect(0, y, width / 2, 1, LinSrgb::new(value, value, value));
+    }
+    // Half as much light as white, on a screen with a gamma of 2.2
+    frame.fill_rect(
+        width / 2,
+        top,
+        width - width / 2,
+        height - top,
+        LinSrgb::new(186, 186, 186),
+    );
+}
+
+/// Settings > Accessibility, with a widget for each of
+/// [`ACCESSIBILITY_ROWS`] named after it
+fn accessibility_ui() -> Ui {
+    let [theme, scale, extra_time, filter, rumble] = ACCESSIBILITY_ROWS;
+    Ui::new(Widget::column(vec![
+        Widget::title("Accessibility"),
+        Widget::value(theme, theme, ""),
+        Widget::slider(scale, scale, 200),
+        Widget::value(extra_time, extra_time, ""),
+        Widget::value(filter, filter, ""),
+        Widget::slider(rumble, rumble, 200),
+        Widget::space(2),
+        Widget::label("The UI scale makes the menu's and Settings' text bigger"),
+        Widget::label("Extra time gives timers and held buttons half as long again"),
+        Widget::label("Color filters keep colors apart for color-blind players"),
+        Widget::label("").with_id("status"),
+    ]))
+}
+
+impl SettingsApp {
+    fn draw_accessibility(&mut self, input: &Input, frame: &mut Frame) {
+        let ui = self.accessibility.get_or_insert_with(accessibility_ui);
+        let mut config = config();
+        if let Some(UiEvent::Stepped(id, step)) = ui.handle_input(input) {
+            match ACCESSIBILITY_ROWS.iter().position(|&name| name == id) {
+                Some(0) if step < 0 => {
+                    config.theme = config.theme.previous().unwrap_or(last::<Theme>().unwrap());
+                }
+                Some(0) => config.theme = config.theme.next().unwrap_or(first::<Theme>().unwrap()),
+                Some(1) => {
+                    let (min, max) = UI_SCALE_RANGE;
+                    config.ui_scale = config
+                        .ui_scale
+                        .saturating_add_signed(step as i16 * 25)
+                        .clamp(min, max);
+                }
+                Some(2) => config.extra_time = !config.extra_time,
+                Some(3) if step < 0 => {
+                    let filter = config.color.filter;
+                    config.color.filter =
+                        filter.previous().unwrap_or(last::<ColorFilter>().unwrap());
+                }
+                Some(3) => {
+                    let filter = config.color.filter;
+                    config.color.filter = filter.next().unwrap_or(first::<ColorFilter>().unwrap());
+                }
+                Some(_) => {
+                    config.haptics = config
+                        .haptics
+                        .saturating_add_signed(step as i8 * 25)
+                        .min(100);
+                }
+                None => (),
+            }
+            self.controls_status = match set_config(config.clone()) {
+                Ok(()) => "Saved".to_string(),
+                Err(e) => format!("Couldn't save: {}", e),
+            };
+            if id == ACCESSIBILITY_ROWS[1] {
+                let mut rebuilt = accessibility_ui();
+                rebuilt.focus(&id);
+                *ui = rebuilt;
+            }
+        }
+
+        ui.set_text(ACCESSIBILITY_ROWS[0], config.theme.label());
+        ui.set_text(ACCESSIBILITY_ROWS[1], &format!("{}%", config.ui_scale));
+        if let Some(slider) = ui.get_mut(ACCESSIBILITY_ROWS[1]) {
+            let (min, max) = UI_SCALE_RANGE;
+            slider.set_value((config.ui_scale - min) as f32 / (max - min) as f32);
+        }
+        ui.set_text(
+            ACCESSIBILITY_ROWS[2],
+            if config.extra_time { "On" } else { "Off" },
+        );
+        ui.set_text(ACCESSIBILITY_ROWS[3], &config.color.filter.label());
+        let rumble = match config.haptics {
+            0 => "Off".to_string(),
+            haptics => format!("{}%", haptics),
+        };
+        ui.set_text(ACCESSIBILITY_ROWS[4], &rumble);
+        if let Some(slider) = ui.get_mut(ACCESSIBILITY_ROWS[4]) {
+            slider.set_value(config.haptics as f32 / 100.0);
+        }
+        ui.set_text("status", &self.controls_status);
+        ui.draw(frame, 50, 16);
+    }
+}
+
+impl SettingsApp {
+    fn save_calibration(&mut self, calibration: Calibration) {
+        self.controls_status = match set_calibration(calibration) {
+            Ok(()) => "Saved".to_string(),
+            Err(e) => format!("Couldn't save: {}", e),
+        };
+    }
+
+    fn draw_calibration(&mut self, input: &Input, frame: &mut Frame) {
+        let mut current = calibration();
+        match &mut self.recording {
+            None => {
+                let step = |pressed: bool| if pressed { 0.05 } else { 0.0 };
+                let deadzone = current.deadzone + step(input.repeated(Button::PovRight))
+                    - step(input.repeated(Button::PovLeft));
+                let curve = current.curve + 5.0 * step(input.repeated(Button::BumperR))
+                    - 5.0 * step(input.repeated(Button::BumperL));
+                let (deadzone, curve) = (deadzone.clamp(0.0, 0.9), curve.clamp(0.25, 4.0));
+                if deadzone != current.deadzone || curve != current.curve {
+                    current.deadzone = deadzone;
+                    current.curve = curve;
+                    self.save_calibration(current.clone());
+                }
+                if input.just_pressed(Button::ActionA) {
+                    self.recording = Some(Recording::Center);
+                }
+            }
+            Some(Recording::Center) => {
+                if input.just_pressed(Button::ActionA) {
+                    self.recording = Some(Recording::Limits(EnumMap::from_fn(|axis| {
+                        let raw = input.raw_axis(axis);
+                        Range {
+                            min: raw,
+                            center: raw,
+                            max: raw,
+                        }
+                    })));
+                }
+            }
+            Some(Recording::Limits(ranges)) => {
+                for (axis, range) in ranges.iter_mut() {
+                    range.min = range.min.min(input.raw_axis(axis));
+                    range.max = range.max.max(input.raw_axis(axis));
+                }
+                if input.just_pressed(Button::ActionA) {
+                    // Axes that weren't moved, or that this device doesn't
+                    // have, keep what they had
+                    for (axis, range) in ranges.iter() {
+                        if range.max - range.min > 0.1 {
+                            current.ranges[axis] = *range;
+                        }
+                    }
+                    self.recording = None;
+                    self.save_calibration(current.clone());
+                }
+            }
+        }
+        if self.recording.is_some() && input.just_pressed(Button::ActionB) {
+            self.recording = None;
+            self.controls_status = "Cancelled".to_string();
+        }
+
+        let mut ctx = frame.context();
+        ctx.offset(50, 50);
+        ctx.set_fontsize(36.0);
+        ctx.text("Calibration");
+        ctx.set_fontsize(18.0);
+        ctx.offset(0, 40);
+        ctx.text(match self.recording {
+            None => "A to calibrate, B to go back",
+            Some(Recording::Center) => "Let go of the sticks and triggers, then press A",
+            Some(Recording::Limits(_)) => {
+                "Move every stick and trigger to its limits, then press A"
+            }
+        });
+        ctx.set_color(LinSrgb::new(180, 180, 180));
+        ctx.offset(0, 25);
+        ctx.text(&format!(
+            "Dead zone: < {:.2} >   Curve: L1 {:.2} R1",
+            current.deadzone, current.curve
+        ));
+
+        // Each axis's raw reading, and where it ends up after calibration
+        let bar_width = 200;
+        for (i, axis) in all::<Axis>().enumerate() {
+            let y = 170 + i * 30;
+            let raw = input.raw_axis(axis);
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                50,
+                y,
+                18.0,
+                LinSrgb::new(255, 255, 255),
+                &format!("{} {:+.2}", axis.name(), raw),
+            );
+            frame.fill_rect(250, y - 12, bar_width, 12, LinSrgb::new(60, 60, 60));
+            let value = current.apply(axis, raw);
+            let marker = ((value + 1.0) / 2.0 * (bar_width - 4) as f32) as usize;
+            frame.fill_rect(250 + marker, y - 12, 4, 12, LinSrgb::new(255, 0, 0));
+        }
+
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            frame.height() - 20,
+            18.0,
+            LinSrgb::new(180, 180, 180),
+            &self.controls_status,
+        );
+    }
+}
+
+impl SettingsApp {
+    fn draw_profiles(&mut self, input: &Input, frame: &mut Frame) {
+        let profiles = rg35xx::profiles::list();
+        let current = rg35xx::profiles::current();
+        if input.repeated(Button::PovUp) {
+            self.selected_profile = self.selected_profile.saturating_sub(1);
+        }
+        if input.repeated(Button::PovDown) {
+            self.selected_profile += 1;
+        }
+        self.selected_profile = self.selected_profile.min(profiles.len() - 1);
+        let selected = &profiles[self.selected_profile];
+
+        if input.just_pressed(Button::ActionA) {
+            self.controls_status = match rg35xx::profiles::switch(selected) {
+                Ok(()) => format!("Playing as {}", selected),
+                Err(e) => format!("Couldn't switch: {}", e),
+            };
+        }
+        if input.just_pressed(Button::ActionV) {
+            self.controls_status = match rg35xx::profiles::create() {
+                Ok(name) => format!("Made {}", name),
+                Err(e) => format!("Couldn't make a profile: {}", e),
+            };
+        }
+        if input.just_pressed(Button::ActionH) {
+            if self.deleting_profile.as_ref() == Some(selected) {
+                self.controls_status = match rg35xx::profiles::remove(selected) {
+                    Ok(()) => format!("Deleted {}", selected),
+                    Err(e) => format!("Couldn't delete {}: {}", selected, e),
+                };
+                self.deleting_profile = None;
+            } else {
+                self.controls_status = format!("Y again to delete {} and its saves", selected);
+                self.deleting_profile = Some(selected.clone());
+            }
+        } else if all::<Button>().any(|button| input.just_pressed(button)) {
+            self.deleting_profile = None;
+        }
+
+        let mut ctx = frame.context();
+        ctx.offset(50, 50);
+        ctx.set_fontsize(36.0);
+        ctx.text("Profiles");
+        ctx.set_fontsize(18.0);
+        ctx.offset(0, 10);
+        for (i, name) in profiles.iter().enumerate() {
+            ctx.offset(0, 30);
+            ctx.set_color(if i == self.selected_profile {
+                LinSrgb::new(255, 255, 255)
+            } else {
+                LinSrgb::new(255, 0, 0)
+            });
+            if *name == current {
+                ctx.text(&format!("{} (current)", name));
+            } else {
+                ctx.text(name);
+            }
+        }
+
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            frame.height() - 45,
+            18.0,
+            LinSrgb::new(180, 180, 180),
+            "A switch, X new, Y delete",
+        );
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            frame.height() - 20,
+            18.0,
+            LinSrgb::new(180, 180, 180),
+            &self.controls_status,
+        );
+    }
+}
+
+impl SettingsApp {
+    fn save_pin(&mut self, pin: Option<String>) {
+        let mut config = config();
+        let removed = pin.is_none();
+        config.pin = pin;
+        self.controls_status = match set_config(config) {
+            Ok(()) if removed => "PIN removed".to_string(),
+            Ok(()) => "PIN set".to_string(),
+            Err(e) => format!("Couldn't save: {}", e),
+        };
+    }
+
+    /// Moves on from `step` once a PIN's been entered for it
+    fn pin_entered(&mut self, step: PinStep, mut pad: PinPad, pin: Vec<Button>) {
+        match step {
+            PinStep::Current { .. } if !rg35xx::lock::check(&pin) => {
+                pad.reject("Wrong PIN");
+                self.pin_prompt = Some((step, pad));
+            }
+            PinStep::Current { remove: true } => self.save_pin(None),
+            PinStep::Current { remove: false } => {
+                self.pin_prompt = Some((PinStep::New, PinPad::new("New PIN")));
+            }
+            PinStep::New => {
+                self.pin_prompt = Some((PinStep::Repeat(pin), PinPad::new("New PIN again")));
+            }
+            PinStep::Repeat(first) if first == pin => {
+                self.save_pin(Some(rg35xx::lock::hash_pin(&pin)));
+            }
+            PinStep::Repeat(_) => {
+                self.controls_status = "The PINs didn't match".to_string();
+            }
+        }
+    }
+
+    fn draw_lock(&mut self, input: &Input, frame: &mut Frame) {
+        if let Some((step, mut pad)) = self.pin_prompt.take() {
+            let actions = self.actions.get_or_insert_with(ActionMap::global);
+            match pad.update(input, actions, frame) {
+                Some(PinEvent::Entered(pin)) => self.pin_entered(step, pad, pin),
+                Some(PinEvent::Cancelled) => (),
+                None => self.pin_prompt = Some((step, pad)),
+            }
+            return;
+        }
+
+        let config = config();
+        let has_pin = config.pin.is_some();
+        if input.just_pressed(Button::ActionA) {
+            let prompt = if has_pin {
+                (
+                    PinStep::Current { remove: false },
+                    PinPad::new("Current PIN"),
+                )
+            } else {
+                (PinStep::New, PinPad::new("New PIN"))
+            };
+            self.pin_prompt = Some(prompt);
+        }
+        if input.just_pressed(Button::ActionH) && has_pin {
+            self.pin_prompt = Some((
+                PinStep::Current { remove: true },
+                PinPad::new("Current PIN"),
+            ));
+        }
+
+        let mut ctx = frame.context();
+        ctx.offset(50, 50);
+        ctx.set_fontsize(36.0);
+        ctx.text("PIN lock");
+        ctx.set_fontsize(18.0);
+        ctx.offset(0, 40);
+        if has_pin {
+            ctx.text("A PIN is set");
+            ctx.offset(0, 30);
+            if config.locked.is_empty() {
+                ctx.text("Nothing is locked");
+            } else {
+                ctx.text(&format!("Locked: {}", config.locked.join(", ")));
+            }
+        } else {
+            ctx.text("No PIN is set, so nothing is locked");
+        }
+        ctx.set_color(LinSrgb::new(180, 180, 180));
+        ctx.offset(0, 30);
+        ctx.text("X on a menu entry locks or unlocks it");
+        ctx.offset(0, 30);
+        ctx.text(&self.controls_status);
+
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            frame.height() - 20,
+            18.0,
+            LinSrgb::new(180, 180, 180),
+            if has_pin {
+                "A change PIN, Y remove PIN"
+            } else {
+                "A set a PIN"
+            },
+        );
+    }
+}
+
+impl App for SettingsApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+
+        // B goes back a step in these before it leaves the page
+        let busy = self.recording.is_some() || self.pin_prompt.is_some();
+        #[cfg(not(target_arch = "wasm32"))]
+        let busy = busy || self.wifi_qr.is_some();
+        match self.open {
+            None => self.draw_sections(input, frame),
+            Some(Section::Network) => self.draw_network(input, frame),
+            Some(Section::Controls) => self.draw_controls(input, frame),
+            Some(Section::Display) => self.draw_display(input, frame),
+            Some(Section::Accessibility) => self.draw_accessibility(input, frame),
+            Some(Section::Calibration) => self.draw_calibration(input, frame),
+            Some(Section::Profiles) => self.draw_profiles(input, frame),
+            Some(Section::Lock) => self.draw_lock(input, frame),
+        }
+
+        if self.open.is_some() && input.just_pressed(Button::ActionB) && !busy {
+            self.open = None;
+            self.controls_status.clear();
+            self.test_pattern = false;
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        match (self.open, self.last_refresh) {
+            (Some(Section::Network), Some(last)) => Activity::Idle(last + NETWORK_REFRESH),
+            _ => Activity::Waiting,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rg35xx::testing::{assert_golden, Harness};
+
+    #[test]
+    fn test_golden_settings() {
+        let mut harness = Harness::new(SettingsApp::default(), 640, 480);
+        harness.press(Button::PovDown);
+        assert_golden(
+            harness.surface(),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/settings.png"),
+            8,
+        );
+    }
+}
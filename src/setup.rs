@@ -0,0 +1,320 @@
+//! What the launcher shows before the menu: a splash screen while it gets
+//! ready, then on the first run a few questions whose answers are saved to
+//! `launcher.txt`.
+
+use enum_iterator::{all, cardinality, next, previous, Sequence};
+use rusttype::Scale;
+
+use rg35xx::actions::{set_confirm_swapped, ActionMap};
+use rg35xx::app::{load_font, Button, Frame, Input};
+use rg35xx::config::{config, set_config, Config, Theme, LANGUAGES};
+use rg35xx::time::SystemTime;
+use rg35xx::widgets::{progress_bar, text_width};
+
+fn prepare_storage() {
+    if let Err(e) = std::fs::create_dir_all(rg35xx::storage::exports_dir()) {
+        println!("Couldn't create the exports directory: {}", e);
+    }
+}
+
+fn load_settings() {
+    config();
+    rg35xx::axes::calibration();
+}
+
+fn load_fonts() {
+    if let Err(e) = rg35xx::assets::font(rg35xx::assets::DEFAULT_FONT) {
+        println!("Couldn't load the default font: {}", e);
+    }
+}
+
+fn load_controls() {
+    ActionMap::global();
+}
+
+/// What gets ready while the splash is up, with what to show meanwhile
+const STEPS: [(&str, fn()); 4] = [
+    ("Preparing storage", prepare_storage),
+    ("Loading settings", load_settings),
+    ("Loading fonts", load_fonts),
+    ("Loading controls", load_controls),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
+enum Page {
+    Language,
+    Confirm,
+    Theme,
+    Clock,
+}
+
+impl Page {
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Language => "Language",
+            Self::Confirm => "Which button confirms?",
+            Self::Theme => "Theme",
+            Self::Clock => "Clock",
+        }
+    }
+}
+
+/// The first-run questions
+struct Wizard {
+    page: Page,
+    config: Config,
+    confirm_swapped: bool,
+    /// On the clock page, whether left and right change the offset rather
+    /// than the format
+    editing_offset: bool,
+}
+
+impl Wizard {
+    fn new() -> Self {
+        Self {
+            page: Page::Language,
+            config: config(),
+            confirm_swapped: ActionMap::global().confirm_swapped(),
+            editing_offset: false,
+        }
+    }
+
+    /// Saves the answers
+    fn finish(&mut self) {
+        self.config.set_up = true;
+        if let Err(e) = set_config(self.config.clone()) {
+            println!("Couldn't save launcher.txt: {}", e);
+        }
+        if let Err(e) = set_confirm_swapped(self.confirm_swapped) {
+            println!("Couldn't save controls.txt: {}", e);
+        }
+    }
+
+    /// Left and right change the answer, A moves on and B goes back. Returns
+    /// true once the last page is done.
+    fn update(&mut self, input: &Input, frame: &mut Frame) -> bool {
+        let step = i32::from(input.repeated(Button::PovRight))
+            - i32::from(input.repeated(Button::PovLeft));
+        match self.page {
+            Page::Language => {
+                let current = LANGUAGES
+                    .iter()
+                    .position(|(code, _)| *code == self.config.language)
+                    .unwrap_or(0);
+                let index = (current as i32 + step).rem_euclid(LANGUAGES.len() as i32);
+                self.config.language = LANGUAGES[index as usize].0.to_string();
+            }
+            Page::Confirm => {
+                if step != 0 {
+                    self.confirm_swapped = !self.confirm_swapped;
+                }
+            }
+            Page::Theme => {
+                let theme = self.config.theme;
+                self.config.theme = match step {
+                    1 => next(&theme).unwrap_or_default(),
+                    -1 => previous(&theme).or(all::<Theme>().last()).unwrap(),
+                    _ => theme,
+                };
+            }
+            Page::Clock => {
+                if input.just_pressed(Button::PovUp) || input.just_pressed(Button::PovDown) {
+                    self.editing_offset = !self.editing_offset;
+                }
+                if self.editing_offset {
+                    self.config.utc_offset = (self.config.utc_offset + step).clamp(-12, 14);
+                } else if step != 0 {
+                    self.config.clock_24h = !self.config.clock_24h;
+                }
+            }
+        }
+
+        if input.just_pressed(Button::ActionA) {
+            match next(&self.page) {
+                Some(page) => self.page = page,
+                None => {
+                    self.finish();
+                    return true;
+                }
+            }
+        } else if input.just_pressed(Button::ActionB) {
+            self.page = previous(&self.page).unwrap_or(self.page);
+        }
+
+        self.draw(frame);
+        false
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        // Drawn in the theme being picked, as a preview
+        let colors = self.config.theme.colors();
+        frame.fill_rect(0, 0, frame.width(), frame.height(), colors.background);
+        let mut ctx = frame.context();
+        ctx.offset(50, 60);
+        ctx.set_color(colors.dim);
+        ctx.set_fontsize(18.0);
+        ctx.text(&format!(
+            "Welcome! {} of {}",
+            all::<Page>().position(|page| page == self.page).unwrap() + 1,
+            cardinality::<Page>()
+        ));
+        ctx.offset(0, 50);
+        ctx.set_color(colors.selected);
+        ctx.set_fontsize(36.0);
+        ctx.text(self.page.title());
+        ctx.offset(0, 60);
+        ctx.set_fontsize(28.0);
+        match self.page {
+            Page::Language => {
+                let name = LANGUAGES
+                    .iter()
+                    .find(|(code, _)| *code == self.config.language)
+                    .map_or("?", |(_, name)| name);
+                ctx.text(&format!("< {} >", name));
+            }
+            Page::Confirm => {
+                let (confirm, back) = if self.confirm_swapped {
+                    ("B", "A")
+                } else {
+                    ("A", "B")
+                };
+                ctx.text(&format!("< {} >", confirm));
+                ctx.offset(0, 35);
+                ctx.set_color(colors.dim);
+                ctx.set_fontsize(18.0);
+                ctx.text(&format!(
+                    "{} goes back. This can be changed in Settings.",
+                    back
+                ));
+            }
+            Page::Theme => ctx.text(&format!("< {} >", self.config.theme.name())),
+            Page::Clock => {
+                let marker = |editing: bool| if editing { ">" } else { " " };
+                ctx.text(&format!(
+                    "{} {}-hour",
+                    marker(!self.editing_offset),
+                    if self.config.clock_24h { 24 } else { 12 }
+                ));
+                ctx.offset(0, 35);
+                ctx.text(&format!(
+                    "{} UTC{:+}",
+                    marker(self.editing_offset),
+                    self.config.utc_offset
+                ));
+                ctx.offset(0, 35);
+                ctx.set_color(colors.dim);
+                ctx.set_fontsize(18.0);
+                ctx.text(&format!("It's {}", self.config.clock(SystemTime::now())));
+            }
+        }
+
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            frame.height().saturating_sub(20),
+            18.0,
+            colors.dim,
+            if next(&self.page).is_some() {
+                "Left/right to change, A next, B back"
+            } else {
+                "Left/right to change, up/down to pick, A finish"
+            },
+        );
+    }
+}
+
+/// Drives the splash and, on the first run, the wizard
+#[derive(Default)]
+pub(crate) struct Setup {
+    /// How many of [`STEPS`] have run
+    steps_done: usize,
+    wizard: Option<Wizard>,
+    finished: bool,
+}
+
+impl Setup {
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Skips whatever's left, e.g. when launching straight into an app
+    pub(crate) fn finish(&mut self) {
+        for (_, step) in &STEPS[self.steps_done..] {
+            step();
+        }
+        self.steps_done = STEPS.len();
+        self.wizard = None;
+        self.finished = true;
+    }
+
+    /// Whether the splash is still running steps, so the menu should keep
+    /// updating
+    pub(crate) fn is_busy(&self) -> bool {
+        !self.finished && self.wizard.is_none()
+    }
+
+    /// Runs the next step or shows the wizard. Returns true once the menu
+    /// can take over.
+    pub(crate) fn update(&mut self, input: &Input, frame: &mut Frame) -> bool {
+        if self.finished {
+            return true;
+        }
+        if let Some(wizard) = self.wizard.as_mut() {
+            self.finished = wizard.update(input, frame);
+            return self.finished;
+        }
+
+        if let Some((_, step)) = STEPS.get(self.steps_done) {
+            step();
+            self.steps_done += 1;
+        }
+        if self.steps_done == STEPS.len() {
+            if config().set_up {
+                self.finished = true;
+                return true;
+            }
+            self.wizard = Some(Wizard::new());
+        }
+        draw_splash(frame, self.steps_done);
+        false
+    }
+}
+
+/// The launcher's name and a bar that fills as steps finish
+fn draw_splash(frame: &mut Frame, done: usize) {
+    let colors = config().theme.colors();
+    frame.fill_rect(0, 0, frame.width(), frame.height(), colors.background);
+    let (width, height) = (frame.width(), frame.height());
+    let title_width = text_width(
+        &load_font("fonts/Ubuntu-B.ttf"),
+        Scale::uniform(48.0),
+        "RG35XX",
+    );
+    frame.text(
+        "fonts/Ubuntu-B.ttf",
+        (width as f32 - title_width).max(0.0) as usize / 2,
+        (height / 2).saturating_sub(20),
+        48.0,
+        colors.selected,
+        "RG35XX",
+    );
+    let bar_width = width / 2;
+    let left = (width - bar_width) / 2;
+    progress_bar(
+        frame,
+        left,
+        height / 2 + 10,
+        bar_width,
+        8,
+        done as f32 / STEPS.len() as f32,
+    );
+    let label = STEPS.get(done).map_or("Ready", |(label, _)| label);
+    frame.text(
+        "fonts/Ubuntu-B.ttf",
+        left,
+        height / 2 + 45,
+        18.0,
+        colors.dim,
+        label,
+    );
+}
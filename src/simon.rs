@@ -0,0 +1,393 @@
+//! Simon: watch the pads light up in a sequence, then play it back on the
+//! action buttons. Each round adds one to the end and plays a little faster.
+//! Each pad has its own tone, played as it lights and as it's pressed, and a
+//! mistake buzzes.
+
+use enum_iterator::{all, Sequence};
+use palette::LinSrgb;
+use rand::{prelude::*, rngs::StdRng};
+
+use rg35xx::achievements::{unlock, Achievement};
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::audio::Sound;
+use rg35xx::config::config;
+use rg35xx::profiles::Store;
+use rg35xx::time::{Duration, Instant};
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0")
+    .description("Repeat the sequence of lights")
+    .achievements(&[
+        Achievement::new("simon_10", "Good memory", "Repeat a sequence of 10"),
+        Achievement::new("simon_20", "Elephant", "Repeat a sequence of 20"),
+    ]);
+
+const PAD_SIZE: usize = 120;
+const PAD_GAP: usize = 12;
+/// The pause before a sequence starts playing, to see it coming
+const LEAD_IN: Duration = Duration::from_millis(700);
+/// How long the player has for each press before the round's lost
+const PRESS_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a wrong or late press shows which pad it should have been
+const MISTAKE_TIME: Duration = Duration::from_millis(1500);
+/// How long a pad's tone plays when it's pressed
+const PRESS_TONE: Duration = Duration::from_millis(250);
+
+/// One of the four pads, laid out like the action buttons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
+enum Pad {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl Pad {
+    fn button(self) -> Button {
+        match self {
+            Self::Top => Button::ActionV,
+            Self::Right => Button::ActionA,
+            Self::Bottom => Button::ActionB,
+            Self::Left => Button::ActionH,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Top => "X",
+            Self::Right => "A",
+            Self::Bottom => "B",
+            Self::Left => "Y",
+        }
+    }
+
+    /// Lit and unlit
+    fn colors(self) -> (LinSrgb<u8>, LinSrgb<u8>) {
+        match self {
+            Self::Top => (LinSrgb::new(60, 255, 90), LinSrgb::new(10, 70, 20)),
+            Self::Right => (LinSrgb::new(255, 60, 60), LinSrgb::new(80, 10, 10)),
+            Self::Bottom => (LinSrgb::new(70, 130, 255), LinSrgb::new(10, 30, 80)),
+            Self::Left => (LinSrgb::new(255, 230, 60), LinSrgb::new(80, 70, 10)),
+        }
+    }
+
+    /// The original game's tones, in Hz, low for blue through high for green
+    fn frequency(self) -> f32 {
+        match self {
+            Self::Top => 415.0,
+            Self::Right => 310.0,
+            Self::Bottom => 209.0,
+            Self::Left => 252.0,
+        }
+    }
+
+    fn tone(self, length: Duration) -> Sound {
+        Sound::tone(self.frequency(), length, 0.4)
+    }
+
+    /// Cells of a 3x3 grid, leaving the corners and middle free
+    fn cell(self) -> (usize, usize) {
+        match self {
+            Self::Top => (1, 0),
+            Self::Right => (2, 1),
+            Self::Bottom => (1, 2),
+            Self::Left => (0, 1),
+        }
+    }
+}
+
+/// What came of a press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Press {
+    /// Right, with more of the sequence to go
+    Correct,
+    /// Right, and the last of the sequence
+    RoundDone,
+    Wrong,
+}
+
+/// The sequence so far and how much of it's been played back this round
+#[derive(Debug, Default)]
+struct Game {
+    sequence: Vec<Pad>,
+    entered: usize,
+}
+
+impl Game {
+    /// Adds a pad to the end and starts the round over
+    fn extend(&mut self, rng: &mut StdRng) {
+        let pads = all::<Pad>().collect::<Vec<_>>();
+        self.sequence.push(*pads.choose(rng).unwrap());
+        self.entered = 0;
+    }
+
+    fn press(&mut self, pad: Pad) -> Press {
+        if self.sequence.get(self.entered) != Some(&pad) {
+            return Press::Wrong;
+        }
+        self.entered += 1;
+        if self.entered == self.sequence.len() {
+            Press::RoundDone
+        } else {
+            Press::Correct
+        }
+    }
+
+    /// The pad that should be pressed next
+    fn expected(&self) -> Option<Pad> {
+        self.sequence.get(self.entered).copied()
+    }
+
+    /// Sequences repeated in full, the score
+    fn streak(&self) -> usize {
+        self.sequence.len().saturating_sub(1)
+    }
+
+    /// How long each pad's lit when the sequence plays, quicker as it grows
+    fn light_time(&self) -> Duration {
+        let shorter = 15 * self.sequence.len() as u64;
+        Duration::from_millis(450u64.saturating_sub(shorter).max(180))
+    }
+
+    /// The pad lit `elapsed` into playing the sequence, if any, or `None`
+    /// once it's over
+    fn shown_at(&self, elapsed: Duration) -> Option<Option<Pad>> {
+        let Some(elapsed) = elapsed.checked_sub(LEAD_IN) else {
+            return Some(None);
+        };
+        let on = self.light_time();
+        let step = on + on / 3;
+        let index = (elapsed.as_millis() / step.as_millis()) as usize;
+        let pad = *self.sequence.get(index)?;
+        let into = elapsed - step * index as u32;
+        Some((into < on).then_some(pad))
+    }
+}
+
+enum Phase {
+    Title,
+    /// The sequence playing, since the instant
+    Showing(Instant),
+    /// Waiting for the next press, until the instant
+    Repeating(Instant),
+    /// The press that lost, showing the pad it should have been
+    Mistake(Instant),
+    Over,
+}
+
+pub struct SimonApp {
+    game: Game,
+    phase: Phase,
+    rng: StdRng,
+    /// Holds the current profile's best streak
+    store: Store,
+    /// The pad lit while the sequence plays, so its tone starts once
+    showing: Option<Pad>,
+}
+
+impl Default for SimonApp {
+    fn default() -> Self {
+        Self {
+            game: Game::default(),
+            phase: Phase::Title,
+            rng: rg35xx::random::rng("simon"),
+            store: Store::open("simon"),
+            showing: None,
+        }
+    }
+}
+
+impl SimonApp {
+    fn best(&self) -> usize {
+        self.store.get("best_streak").unwrap_or(0)
+    }
+
+    fn start(&mut self) {
+        self.game = Game::default();
+        self.next_round();
+    }
+
+    fn next_round(&mut self) {
+        self.game.extend(&mut self.rng);
+        self.phase = Phase::Showing(Instant::now());
+    }
+
+    fn lose(&mut self, ctx: &AppContext) {
+        self.phase = Phase::Mistake(Instant::now());
+        ctx.play(&Sound::tone(110.0, Duration::from_millis(800), 0.5));
+        let streak = self.game.streak();
+        for (length, id) in [(10, "simon_10"), (20, "simon_20")] {
+            if streak >= length {
+                unlock(id);
+            }
+        }
+        if streak > self.best() {
+            self.store.set("best_streak", streak);
+            if let Err(e) = self.store.save() {
+                println!("Couldn't save the best streak: {}", e);
+            }
+        }
+    }
+
+    fn handle_input(&mut self, ctx: &AppContext) {
+        let input = ctx.input;
+        let start = input.start_pressed();
+        match self.phase {
+            Phase::Title | Phase::Over => {
+                if start || input.just_pressed(Button::ActionA) {
+                    self.start();
+                }
+            }
+            Phase::Showing(started) => match self.game.shown_at(started.elapsed()) {
+                Some(shown) => {
+                    if let Some(pad) = shown.filter(|_| shown != self.showing) {
+                        ctx.play(&pad.tone(self.game.light_time()));
+                    }
+                    self.showing = shown;
+                }
+                None => {
+                    self.showing = None;
+                    self.phase = Phase::Repeating(Instant::now() + config().timed(PRESS_TIMEOUT));
+                }
+            },
+            Phase::Repeating(deadline) => {
+                let pressed = all::<Pad>().find(|pad| input.just_pressed(pad.button()));
+                match pressed.map(|pad| (pad, self.game.press(pad))) {
+                    Some((pad, Press::Correct)) => {
+                        ctx.play(&pad.tone(PRESS_TONE));
+                        self.phase =
+                            Phase::Repeating(Instant::now() + config().timed(PRESS_TIMEOUT))
+                    }
+                    Some((pad, Press::RoundDone)) => {
+                        ctx.play(&pad.tone(PRESS_TONE));
+                        self.next_round();
+                    }
+                    Some((_, Press::Wrong)) => self.lose(ctx),
+                    None if Instant::now() >= deadline => self.lose(ctx),
+                    None => (),
+                }
+            }
+            Phase::Mistake(at) => {
+                if at.elapsed() >= MISTAKE_TIME {
+                    self.phase = Phase::Over;
+                }
+            }
+        }
+    }
+
+    fn draw_pads(&self, input: &Input, frame: &mut Frame) {
+        let lit = |pad: Pad| match self.phase {
+            Phase::Showing(started) => self.game.shown_at(started.elapsed()) == Some(Some(pad)),
+            Phase::Repeating(_) => input.pressed(pad.button()),
+            // Flashing the one that was wanted
+            Phase::Mistake(at) => {
+                self.game.expected() == Some(pad) && at.elapsed().as_millis() % 500 < 250
+            }
+            Phase::Title | Phase::Over => false,
+        };
+        let cell = PAD_SIZE + PAD_GAP;
+        let left = (frame.width() - cell * 3 + PAD_GAP) / 2;
+        let top = (frame.height() - cell * 3 + PAD_GAP) / 2;
+        for pad in all::<Pad>() {
+            let (column, row) = pad.cell();
+            let (x, y) = (left + column * cell, top + row * cell);
+            let (on, off) = pad.colors();
+            let (fill, text) = if lit(pad) {
+                (on, LinSrgb::new(0, 0, 0))
+            } else {
+                (off, on)
+            };
+            frame.fill_rect(x, y, PAD_SIZE, PAD_SIZE, fill);
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                x + PAD_SIZE / 2 - 10,
+                y + PAD_SIZE / 2 + 12,
+                36.0,
+                text,
+                pad.label(),
+            );
+        }
+
+        // The streak in the middle
+        let (x, y) = (left + cell, top + cell);
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            x + 30,
+            y + PAD_SIZE / 2 + 14,
+            40.0,
+            LinSrgb::new(255, 255, 255),
+            &format!("{:>2}", self.game.streak()),
+        );
+    }
+}
+
+impl App for SimonApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        self.handle_input(ctx);
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+        self.draw_pads(input, frame);
+
+        let message = match self.phase {
+            Phase::Title => "A or START to play".to_string(),
+            Phase::Showing(_) => "Watch".to_string(),
+            Phase::Repeating(_) => "Your turn".to_string(),
+            Phase::Mistake(_) => "Wrong!".to_string(),
+            Phase::Over => format!("Game over, streak {}. A to play again", self.game.streak()),
+        };
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            20,
+            30,
+            20.0,
+            LinSrgb::new(255, 255, 255),
+            &message,
+        );
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            20,
+            frame.height() - 15,
+            18.0,
+            LinSrgb::new(180, 180, 180),
+            &format!("Best for {}: {}", rg35xx::profiles::current(), self.best()),
+        );
+    }
+
+    fn activity(&self) -> Activity {
+        match self.phase {
+            Phase::Showing(_) | Phase::Mistake(_) => Activity::Busy,
+            Phase::Repeating(deadline) => Activity::Idle(deadline),
+            Phase::Title | Phase::Over => Activity::Waiting,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_game() {
+        let mut game = Game {
+            sequence: vec![Pad::Top, Pad::Left],
+            entered: 0,
+        };
+        assert_eq!(game.press(Pad::Top), Press::Correct);
+        assert_eq!(game.press(Pad::Left), Press::RoundDone);
+        assert_eq!(game.streak(), 1);
+
+        let mut rng = rg35xx::random::rng("simon-test");
+        game.extend(&mut rng);
+        assert_eq!((game.sequence.len(), game.entered), (3, 0));
+        assert_eq!(game.press(Pad::Bottom), Press::Wrong);
+        assert_eq!(game.expected(), Some(Pad::Top));
+
+        // Dark for the lead-in, then each pad lit with a gap after it
+        let on = game.light_time();
+        assert_eq!(game.shown_at(Duration::ZERO), Some(None));
+        assert_eq!(game.shown_at(LEAD_IN), Some(Some(Pad::Top)));
+        assert_eq!(game.shown_at(LEAD_IN + on), Some(None));
+        assert_eq!(game.shown_at(LEAD_IN + on + on / 3), Some(Some(Pad::Left)));
+        assert_eq!(game.shown_at(LEAD_IN + (on + on / 3) * 3), None);
+    }
+}
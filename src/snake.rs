@@ -1,73 +1,43 @@
-use std::{
-    collections::HashSet,
-    time::{Duration, Instant},
-};
+use std::collections::{HashSet, VecDeque};
 
 use palette::LinSrgb;
-use rand::{distributions::Uniform, prelude::*};
+use rand::{distributions::Uniform, prelude::*, rngs::StdRng};
+use serde::{Deserialize, Serialize};
 
-use crate::app::{App, Button};
+use enum_iterator::all;
+use rg35xx::achievements::{unlock, Achievement};
+use rg35xx::actions::{Action, ActionMap};
+use rg35xx::app::{Activity, App, AppArgs, AppContext, Input, Manifest, PowerHint};
+use rg35xx::grid::{Direction, Grid, Point};
+use rg35xx::particles::Emitter;
+use rg35xx::profiles::Store;
+use rg35xx::savestate::SaveState;
+use rg35xx::time::{Duration, Instant};
+use rg35xx::transition::{TransitionKind, Transitions};
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0")
+    .description("Classic snake, wrapping at the screen edges")
+    .achievements(&[
+        Achievement::new("snake_10", "Growing", "Reach a length of 10"),
+        Achievement::new("snake_50", "Long", "Reach a length of 50"),
+        Achievement::new("snake_100", "Centurion", "Reach a length of 100"),
+    ])
+    .demo();
 
 const BLOCK_WIDTH: usize = 10;
 const BLOCK_HEIGHT: usize = 10;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl Direction {
-    fn is_opposite(&self, other: Self) -> bool {
-        #[allow(clippy::match_like_matches_macro)]
-        match (self, other) {
-            (Self::Up, Self::Down)
-            | (Self::Down, Self::Up)
-            | (Self::Left, Self::Right)
-            | (Self::Right, Self::Left) => true,
-            _ => false,
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Point(usize, usize);
-
-impl Point {
-    fn in_wrapped_direction(&self, direction: Direction, width: usize, height: usize) -> Self {
-        let (dx, dy) = match direction {
-            Direction::Down => (0, 1),
-            Direction::Up => (0, -1),
-            Direction::Left => (-1, 0),
-            Direction::Right => (1, 0),
-        };
-
-        let new_x = if dx < 0 && self.0 == 0 {
-            width - 1
-        } else if dx > 0 && self.0 == width - 1 {
-            0
-        } else {
-            self.0.checked_add_signed(dx).unwrap()
-        };
-        let new_y = if dy < 0 && self.1 == 0 {
-            height - 1
-        } else if dy > 0 && self.1 == height - 1 {
-            0
-        } else {
-            self.1.checked_add_signed(dy).unwrap()
-        };
-
-        Self(new_x, new_y)
-    }
-}
-
+#[derive(Serialize, Deserialize)]
 struct State {
     dead: bool,
     width: usize,
     height: usize,
-    snake: Vec<Point>,
+    /// The head first
+    snake: VecDeque<Point>,
+    /// The cells in `snake`, for checking collisions and placing food without
+    /// walking the whole snake. Not saved, so it's rebuilt after loading.
+    #[serde(skip)]
+    occupied: Grid<bool>,
     foods: HashSet<Point>,
     direction: Direction,
     new_direction: Direction,
@@ -75,23 +45,74 @@ struct State {
 
 impl Default for State {
     fn default() -> Self {
-        Self {
+        Self::new(640 / BLOCK_WIDTH, 480 / BLOCK_HEIGHT)
+    }
+}
+
+impl SaveState for State {
+    const NAME: &'static str = "snake";
+}
+
+impl State {
+    /// A two-block snake heading right from the middle of a `width` x `height`
+    /// grid
+    fn new(width: usize, height: usize) -> Self {
+        let (x, y) = (width / 2, height / 2);
+        let head = Point(x, y);
+        let snake = VecDeque::from([head, head.wrapped(Direction::Left, width, height)]);
+        let mut state = Self {
             dead: false,
-            width: 640 / BLOCK_WIDTH,
-            height: 480 / BLOCK_HEIGHT,
-            snake: vec![Point(32, 24), Point(31, 24)],
+            width,
+            height,
+            occupied: Grid::default(),
+            snake,
             foods: HashSet::new(),
             direction: Direction::Right,
             new_direction: Direction::Right,
+        };
+        state.fill_occupied();
+        state
+    }
+
+    /// Which way a demo goes: towards the nearest food, without turning back
+    /// or into itself if there's any other way
+    fn demo_direction(&self) -> Direction {
+        let head = self.snake[0];
+        // The short way round, since the edges wrap
+        let apart = |a: usize, b: usize, size: usize| a.abs_diff(b).min(size - a.abs_diff(b));
+        let distance = |point: Point| {
+            self.foods
+                .iter()
+                .map(|food| {
+                    apart(point.0, food.0, self.width) + apart(point.1, food.1, self.height)
+                })
+                .min()
+                .unwrap_or(0)
+        };
+        // Keeping on the way it's going when that's as good
+        std::iter::once(self.direction)
+            .chain(all::<Direction>())
+            .filter(|direction| !direction.is_opposite(self.direction))
+            .map(|direction| (direction, head.wrapped(direction, self.width, self.height)))
+            .filter(|&(_, next)| self.occupied.get(next) != Some(&true))
+            .min_by_key(|&(_, next)| distance(next))
+            .map_or(self.direction, |(direction, _)| direction)
+    }
+
+    fn fill_occupied(&mut self) {
+        self.occupied = Grid::new(self.width, self.height, false);
+        for &point in &self.snake {
+            self.occupied[point] = true;
         }
     }
-}
 
-impl State {
-    fn step(&mut self) {
+    fn step(&mut self, rng: &mut impl Rng) {
         if self.dead {
             return;
         }
+        if (self.occupied.width(), self.occupied.height()) != (self.width, self.height) {
+            self.fill_occupied();
+        }
 
         if self.direction.is_opposite(self.new_direction) {
             self.new_direction = self.direction;
@@ -100,32 +121,32 @@ impl State {
 
         // Move the snake in the given direction
         let head = self.snake[0];
-        let new_head = head.in_wrapped_direction(self.direction, self.width, self.height);
+        let new_head = head.wrapped(self.direction, self.width, self.height);
 
         // Check that the snake didn't hit itself
-        if self.snake.contains(&new_head) {
+        if self.occupied[new_head] {
             self.dead = true;
             return;
         }
 
-        self.snake.insert(0, new_head);
+        self.snake.push_front(new_head);
+        self.occupied[new_head] = true;
 
         // Eat any foods at the new head
         if self.foods.remove(&new_head) {
             // Don't pop the snake's tail
-        } else {
-            self.snake.pop();
+        } else if let Some(tail) = self.snake.pop_back() {
+            self.occupied[tail] = false;
         }
 
         // Spawn foods as needed
         if self.foods.len() < 2 {
-            let mut rng = rand::thread_rng();
             for _ in 0..5 {
                 // Pick a point, check that it isn't already a food or a snake
                 let wdist = Uniform::from(0..self.width);
                 let hdist = Uniform::from(0..self.height);
-                let p = Point(wdist.sample(&mut rng), hdist.sample(&mut rng));
-                if !self.snake.contains(&p) && !self.foods.contains(&p) {
+                let p = Point(wdist.sample(rng), hdist.sample(rng));
+                if !self.occupied[p] && !self.foods.contains(&p) {
                     self.foods.insert(p);
                     break;
                 }
@@ -138,6 +159,15 @@ pub struct SnakeApp {
     state: Option<State>,
     last_step: Instant,
     difficulty: u32,
+    transitions: Transitions,
+    actions: ActionMap,
+    /// Holds the current profile's high score
+    store: Store,
+    /// The length of a game left for the menu, which A continues
+    saved: Option<usize>,
+    rng: StdRng,
+    /// Sparks where food was eaten
+    sparks: Emitter,
 }
 
 impl Default for SnakeApp {
@@ -146,31 +176,82 @@ impl Default for SnakeApp {
             state: None,
             last_step: Instant::now(),
             difficulty: 5,
+            transitions: Transitions::new(TransitionKind::Crossfade, Duration::from_millis(250)),
+            actions: ActionMap::for_app("snake"),
+            store: Store::open("snake"),
+            saved: State::load().map(|state| state.snake.len()),
+            rng: rg35xx::random::rng("snake"),
+            sparks: Emitter::new("snake", 200)
+                .with_lifetime(Duration::from_millis(250), Duration::from_millis(500))
+                .with_speed(40.0, 120.0)
+                .with_colors(&[LinSrgb::new(0, 0, 255), LinSrgb::new(120, 160, 255)])
+                .with_fade_to(LinSrgb::new(0, 0, 0))
+                .with_size(2),
+        }
+    }
+}
+
+impl SnakeApp {
+    /// Accepts `difficulty` (1-1000) as an argument
+    pub fn from_args(args: &AppArgs) -> Self {
+        let mut app = Self::default();
+        if let Some(difficulty) = args.parse::<u32>("difficulty") {
+            app.difficulty = difficulty.clamp(1, 1000);
         }
+        app
+    }
+
+    fn step_time(&self) -> Duration {
+        Duration::from_millis(1000 / self.difficulty as u64)
     }
 }
 
 impl App for SnakeApp {
-    fn update(&mut self, input: &crate::app::Input, frame: &mut crate::app::Frame) {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
         frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
 
+        let step_time = self.step_time();
         if let Some(state) = self.state.as_mut() {
             // Handle any input
-            for (button, dir) in [
-                (Button::PovDown, Direction::Down),
-                (Button::PovUp, Direction::Up),
-                (Button::PovLeft, Direction::Left),
-                (Button::PovRight, Direction::Right),
+            for (action, dir) in [
+                (Action::Down, Direction::Down),
+                (Action::Up, Direction::Up),
+                (Action::Left, Direction::Left),
+                (Action::Right, Direction::Right),
             ] {
-                if input.pressed(button) {
+                if self.actions.pressed(input, action) {
                     state.new_direction = dir;
                 }
             }
 
             // Step, if time has elapsed
-            if self.last_step.elapsed() > Duration::from_millis(1000 / self.difficulty as u64) {
-                state.step();
+            if self.last_step.elapsed() > step_time {
+                let length = state.snake.len();
+                state.step(&mut self.rng);
                 self.last_step = Instant::now();
+                let score = state.snake.len();
+                if score > length {
+                    let Point(x, y) = state.snake[0];
+                    self.sparks.burst(
+                        24,
+                        ((x * 2 + 1) * BLOCK_WIDTH) as f32 / 2.0,
+                        ((y * 2 + 1) * BLOCK_HEIGHT) as f32 / 2.0,
+                    );
+                }
+                if state.dead {
+                    for (length, id) in [(10, "snake_10"), (50, "snake_50"), (100, "snake_100")] {
+                        if score >= length {
+                            unlock(id);
+                        }
+                    }
+                }
+                if state.dead && score > self.store.get("high_score").unwrap_or(0) {
+                    self.store.set("high_score", score);
+                    if let Err(e) = self.store.save() {
+                        println!("Couldn't save the high score: {}", e);
+                    }
+                }
             }
 
             // Render the snake
@@ -181,7 +262,7 @@ impl App for SnakeApp {
                 BLOCK_HEIGHT,
                 LinSrgb::new(255, 0, 0),
             );
-            for body in state.snake[1..].iter() {
+            for body in state.snake.iter().skip(1) {
                 frame.fill_rect(
                     body.0 * BLOCK_WIDTH,
                     body.1 * BLOCK_HEIGHT,
@@ -202,6 +283,9 @@ impl App for SnakeApp {
                 );
             }
 
+            self.sparks.update(input.delta());
+            self.sparks.draw(frame);
+
             if state.dead {
                 frame.text(
                     "fonts/Ubuntu-B.ttf",
@@ -211,9 +295,24 @@ impl App for SnakeApp {
                     LinSrgb::new(255, 0, 0),
                     &format!("GAME OVER - Score: {}", state.snake.len()),
                 );
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    50,
+                    70,
+                    18.0,
+                    LinSrgb::new(255, 0, 0),
+                    &format!(
+                        "Best: {}",
+                        self.store.get::<usize>("high_score").unwrap_or(0)
+                    ),
+                );
 
-                if input.just_pressed(Button::MenuR) || input.just_pressed(Button::MenuL) {
+                if self.actions.just_pressed(input, Action::Start)
+                    || self.actions.just_pressed(input, Action::Confirm)
+                {
                     self.state = None;
+                    self.sparks.clear();
+                    self.transitions.start(frame);
                 }
             }
         } else {
@@ -223,7 +322,7 @@ impl App for SnakeApp {
                 50,
                 18.0,
                 LinSrgb::new(255, 0, 0),
-                "Press START",
+                &format!("Press {}", self.actions.label(Action::Start)),
             );
             frame.text(
                 "fonts/Ubuntu-B.ttf",
@@ -233,23 +332,205 @@ impl App for SnakeApp {
                 LinSrgb::new(255, 0, 0),
                 &format!("Difficulty: {}", self.difficulty),
             );
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                50,
+                90,
+                18.0,
+                LinSrgb::new(255, 0, 0),
+                &format!(
+                    "Best for {}: {}",
+                    rg35xx::profiles::current(),
+                    self.store.get::<usize>("high_score").unwrap_or(0)
+                ),
+            );
 
-            if input.just_pressed(Button::MenuR) {
-                self.state = Some(State::default());
+            if let Some(length) = self.saved {
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    50,
+                    110,
+                    18.0,
+                    LinSrgb::new(255, 0, 0),
+                    &format!(
+                        "{} to continue (length {})",
+                        self.actions.label(Action::Confirm),
+                        length
+                    ),
+                );
+            }
+
+            let continuing =
+                self.saved.is_some() && self.actions.just_pressed(input, Action::Confirm);
+            if self.actions.just_pressed(input, Action::Start) || continuing {
+                self.state = Some(if continuing {
+                    State::load().unwrap_or_default()
+                } else {
+                    State::default()
+                });
+                // Either way the save is used up
+                State::clear();
+                self.saved = None;
                 self.last_step = Instant::now();
+                self.transitions.start(frame);
             }
-            if input.just_pressed(Button::PovUp) {
+            if self.actions.repeated(input, Action::Up) {
                 self.difficulty += 1;
                 if self.difficulty > 1000 {
                     self.difficulty = 1000;
                 }
             }
-            if input.just_pressed(Button::PovDown) {
+            if self.actions.repeated(input, Action::Down) {
                 self.difficulty -= 1;
                 if self.difficulty < 1 {
                     self.difficulty = 1;
                 }
             }
         }
+
+        self.transitions.draw(frame);
+    }
+
+    fn demo_input(&mut self, input: &mut Input) {
+        let action = match self.state.as_ref() {
+            None => Action::Start,
+            Some(state) if state.dead => Action::Confirm,
+            Some(state) => match state.demo_direction() {
+                Direction::Up => Action::Up,
+                Direction::Down => Action::Down,
+                Direction::Left => Action::Left,
+                Direction::Right => Action::Right,
+            },
+        };
+        let Some(&button) = self.actions.buttons(action).first() else {
+            return;
+        };
+        // Starting again takes a fresh press each time
+        let tap = matches!(action, Action::Start | Action::Confirm);
+        match tap && input.pressed(button) {
+            true => input.hold_only(&[]),
+            false => input.hold_only(&[button]),
+        }
+    }
+
+    fn on_exit(&mut self) {
+        if let Some(state) = self.state.as_ref().filter(|state| !state.dead) {
+            if let Err(e) = state.save() {
+                println!("Couldn't save the game: {}", e);
+            }
+        }
+    }
+    fn activity(&self) -> Activity {
+        match self.state.as_ref() {
+            _ if self.transitions.is_active() || self.sparks.is_active() => Activity::Busy,
+            Some(state) if !state.dead => Activity::Idle(self.last_step + self.step_time()),
+            _ => Activity::Waiting,
+        }
+    }
+    fn power_hint(&self) -> PowerHint {
+        PowerHint::Performance
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn direction() -> impl Strategy<Value = Direction> {
+        prop_oneof![
+            Just(Direction::Up),
+            Just(Direction::Down),
+            Just(Direction::Left),
+            Just(Direction::Right),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_steps(seed: u64, width in 3usize..20, height in 3usize..20, turns in prop::collection::vec(direction(), 1..300)) {
+            let mut state = State::new(width, height);
+            let mut rng = StdRng::seed_from_u64(seed);
+            for direction in turns {
+                let (length, was_dead, previous) = (state.snake.len(), state.dead, state.direction);
+                state.new_direction = direction;
+                state.step(&mut rng);
+                if was_dead {
+                    prop_assert_eq!(state.snake.len(), length);
+                    continue;
+                }
+                // Turning back on itself is ignored, and the snake only grows
+                if previous.is_opposite(direction) {
+                    prop_assert_eq!(state.direction, previous);
+                } else {
+                    prop_assert_eq!(state.direction, direction);
+                }
+                prop_assert!(state.snake.len() >= length);
+                let unique = state.snake.iter().collect::<HashSet<_>>();
+                prop_assert_eq!(unique.len(), state.snake.len());
+                prop_assert!(state.snake.iter().chain(&state.foods).all(|p| p.0 < width && p.1 < height));
+                prop_assert!(state.foods.iter().all(|food| !state.snake.contains(food)));
+                let occupied = state.occupied.iter().filter(|&(_, &cell)| cell).map(|(point, _)| point);
+                prop_assert_eq!(occupied.collect::<HashSet<_>>(), unique.into_iter().copied().collect());
+            }
+        }
+
+        /// Steps the same as checking the whole snake each time, as it did
+        /// before `occupied`
+        #[test]
+        fn test_steps_unchanged(seed: u64, width in 3usize..12, height in 3usize..12, turns in prop::collection::vec(direction(), 1..300)) {
+            let mut state = State::new(width, height);
+            let mut snake = state.snake.iter().copied().collect::<Vec<_>>();
+            let mut foods = HashSet::new();
+            let mut dead = false;
+            let mut direction = state.direction;
+            let (mut rng, mut reference_rng) = (StdRng::seed_from_u64(seed), StdRng::seed_from_u64(seed));
+            for turn in turns {
+                state.new_direction = turn;
+                state.step(&mut rng);
+
+                if !dead {
+                    if !direction.is_opposite(turn) {
+                        direction = turn;
+                    }
+                    let new_head = snake[0].wrapped(direction, width, height);
+                    if snake.contains(&new_head) {
+                        dead = true;
+                    } else {
+                        snake.insert(0, new_head);
+                        if !foods.remove(&new_head) {
+                            snake.pop();
+                        }
+                        if foods.len() < 2 {
+                            for _ in 0..5 {
+                                let p = Point(
+                                    Uniform::from(0..width).sample(&mut reference_rng),
+                                    Uniform::from(0..height).sample(&mut reference_rng),
+                                );
+                                if !snake.contains(&p) && !foods.contains(&p) {
+                                    foods.insert(p);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                prop_assert_eq!(state.dead, dead);
+                prop_assert!(state.snake.iter().eq(snake.iter()));
+                prop_assert_eq!(&state.foods, &foods);
+            }
+        }
+    }
+
+    #[test]
+    fn test_loaded_state() {
+        let mut state = State::new(10, 10);
+        let mut rng = StdRng::seed_from_u64(1);
+        state.step(&mut rng);
+        // As it is after loading a save
+        state.occupied = Grid::default();
+        state.step(&mut rng);
+        assert!(state.snake.iter().all(|&point| state.occupied[point]));
     }
 }
@@ -0,0 +1,895 @@
+//! Klondike solitaire, played with a cursor rather than dragging: A picks up
+//! the card under the cursor, along with any on top of it, and A again puts
+//! them down wherever the cursor's been moved to.
+//!
+//! The card faces come from `cards.png` in the assets, a sheet of 72x96
+//! cards with a row per suit and the back after the spades' king, so they
+//! can be swapped for another deck without rebuilding.
+
+use enum_iterator::{all, Sequence};
+use palette::LinSrgb;
+use rand::{rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+
+use rg35xx::achievements::{unlock, Achievement};
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::particles::Emitter;
+use rg35xx::profiles::Store;
+use rg35xx::savestate::SaveState;
+use rg35xx::surface::Surface;
+use rg35xx::time::{Duration, Instant};
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0")
+    .description("Klondike, the classic patience game")
+    .achievements(&[
+        Achievement::new("solitaire_win", "Patience", "Win a game of Solitaire"),
+        Achievement::new(
+            "solitaire_fast",
+            "Card sharp",
+            "Win a game of Solitaire in under 3 minutes",
+        ),
+    ]);
+
+const SPRITES: &str = "cards.png";
+const CARD_WIDTH: usize = 72;
+const CARD_HEIGHT: usize = 96;
+/// From one pile to the next across the table
+const COLUMN_SPACING: usize = 86;
+const LEFT: usize = 26;
+const TOP: usize = 8;
+const TABLEAU_TOP: usize = TOP + CARD_HEIGHT + 14;
+/// How much of the card underneath shows in a tableau column, face down and
+/// up, before long columns are squeezed to fit
+const FACE_DOWN_OFFSET: usize = 10;
+const FACE_UP_OFFSET: usize = 24;
+/// Undo steps kept
+const HISTORY: usize = 500;
+/// Between each card going up when the game finishes itself
+const AUTO_COMPLETE_STEP: Duration = Duration::from_millis(70);
+const FAST_WIN: u64 = 3 * 60;
+const FELT: LinSrgb<u8> = LinSrgb::new(20, 100, 50);
+
+/// In the order of the rows of the sprite sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Sequence)]
+enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+impl Suit {
+    fn is_red(self) -> bool {
+        matches!(self, Self::Diamonds | Self::Hearts)
+    }
+
+    fn letter(self) -> &'static str {
+        match self {
+            Self::Clubs => "C",
+            Self::Diamonds => "D",
+            Self::Hearts => "H",
+            Self::Spades => "S",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Card {
+    /// Ace is 1, king 13
+    rank: u8,
+    suit: Suit,
+    face_up: bool,
+}
+
+impl Card {
+    fn rank_name(self) -> String {
+        match self.rank {
+            1 => "A".to_string(),
+            11 => "J".to_string(),
+            12 => "Q".to_string(),
+            13 => "K".to_string(),
+            rank => rank.to_string(),
+        }
+    }
+
+    /// Where its face is in the sprite sheet
+    fn sprite(self) -> usize {
+        self.suit as usize * 14 + self.rank as usize - 1
+    }
+}
+
+/// Where the card back is in the sprite sheet
+const BACK_SPRITE: usize = 13;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pile {
+    Stock,
+    Waste,
+    Foundation(usize),
+    Tableau(usize),
+}
+
+impl Pile {
+    /// Which of the seven columns across the table it's in
+    fn column(self) -> usize {
+        match self {
+            Self::Stock => 0,
+            Self::Waste => 1,
+            Self::Foundation(i) => i + 3,
+            Self::Tableau(i) => i,
+        }
+    }
+
+    /// The pile in the top row above `column`, where the gap after the waste
+    /// counts as the waste
+    fn top_row(column: usize) -> Self {
+        match column {
+            0 => Self::Stock,
+            1 | 2 => Self::Waste,
+            column => Self::Foundation(column - 3),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Game {
+    stock: Vec<Card>,
+    waste: Vec<Card>,
+    foundations: [Vec<Card>; 4],
+    tableau: [Vec<Card>; 7],
+    moves: u32,
+    /// Seconds played in earlier sittings
+    seconds: u64,
+}
+
+impl SaveState for Game {
+    const NAME: &'static str = "solitaire";
+}
+
+impl Game {
+    fn deal(rng: &mut StdRng) -> Self {
+        let mut deck = all::<Suit>()
+            .flat_map(|suit| {
+                (1..=13).map(move |rank| Card {
+                    rank,
+                    suit,
+                    face_up: false,
+                })
+            })
+            .collect::<Vec<_>>();
+        deck.shuffle(rng);
+        let tableau = std::array::from_fn(|column| {
+            let mut cards = deck.split_off(deck.len() - column - 1);
+            cards.last_mut().unwrap().face_up = true;
+            cards
+        });
+        Self {
+            stock: deck,
+            waste: Vec::new(),
+            foundations: Default::default(),
+            tableau,
+            moves: 0,
+            seconds: 0,
+        }
+    }
+
+    fn pile(&self, pile: Pile) -> &Vec<Card> {
+        match pile {
+            Pile::Stock => &self.stock,
+            Pile::Waste => &self.waste,
+            Pile::Foundation(i) => &self.foundations[i],
+            Pile::Tableau(i) => &self.tableau[i],
+        }
+    }
+
+    fn pile_mut(&mut self, pile: Pile) -> &mut Vec<Card> {
+        match pile {
+            Pile::Stock => &mut self.stock,
+            Pile::Waste => &mut self.waste,
+            Pile::Foundation(i) => &mut self.foundations[i],
+            Pile::Tableau(i) => &mut self.tableau[i],
+        }
+    }
+
+    /// Turns over the next card of the stock, or turns the waste back over
+    /// once it's run out. False if both are empty.
+    fn draw(&mut self) -> bool {
+        if let Some(mut card) = self.stock.pop() {
+            card.face_up = true;
+            self.waste.push(card);
+        } else if !self.waste.is_empty() {
+            self.stock = self.waste.drain(..).rev().collect();
+            for card in &mut self.stock {
+                card.face_up = false;
+            }
+        } else {
+            return false;
+        }
+        self.moves += 1;
+        true
+    }
+
+    /// Whether the cards from `index` up in `pile` can be picked up together
+    fn can_pick_up(&self, pile: Pile, index: usize) -> bool {
+        let cards = self.pile(pile);
+        match pile {
+            Pile::Stock => false,
+            Pile::Waste | Pile::Foundation(_) => index + 1 == cards.len(),
+            Pile::Tableau(_) => cards.get(index).is_some_and(|card| card.face_up),
+        }
+    }
+
+    fn can_place(&self, cards: &[Card], pile: Pile) -> bool {
+        let Some(&bottom) = cards.first() else {
+            return false;
+        };
+        let top = self.pile(pile).last();
+        match pile {
+            Pile::Foundation(_) => {
+                cards.len() == 1
+                    && match top {
+                        Some(top) => top.suit == bottom.suit && top.rank + 1 == bottom.rank,
+                        None => bottom.rank == 1,
+                    }
+            }
+            Pile::Tableau(_) => match top {
+                Some(top) => {
+                    top.face_up
+                        && top.suit.is_red() != bottom.suit.is_red()
+                        && top.rank == bottom.rank + 1
+                }
+                None => bottom.rank == 13,
+            },
+            Pile::Stock | Pile::Waste => false,
+        }
+    }
+
+    /// Moves the cards from `index` up in `from` onto `to`, turning over the
+    /// card they uncover. False if that isn't allowed.
+    fn move_cards(&mut self, from: Pile, index: usize, to: Pile) -> bool {
+        if from == to
+            || !self.can_pick_up(from, index)
+            || !self.can_place(&self.pile(from)[index..], to)
+        {
+            return false;
+        }
+        let cards = self.pile_mut(from).split_off(index);
+        self.pile_mut(to).extend(cards);
+        if let Some(uncovered) = self.pile_mut(from).last_mut() {
+            uncovered.face_up = true;
+        }
+        self.moves += 1;
+        true
+    }
+
+    /// Sends the top card of `from` up to whichever foundation takes it
+    fn send_to_foundation(&mut self, from: Pile) -> bool {
+        let Some(index) = self.pile(from).len().checked_sub(1) else {
+            return false;
+        };
+        (0..4).any(|i| self.move_cards(from, index, Pile::Foundation(i)))
+    }
+
+    fn is_won(&self) -> bool {
+        self.foundations
+            .iter()
+            .all(|foundation| foundation.len() == 13)
+    }
+
+    /// Whether everything's turned over, so the rest is only a matter of
+    /// putting the cards up in order
+    fn can_auto_complete(&self) -> bool {
+        self.stock.is_empty()
+            && self.waste.is_empty()
+            && self.tableau.iter().flatten().all(|card| card.face_up)
+    }
+
+    /// Sends up the lowest card that can go to a foundation
+    fn auto_step(&mut self) -> bool {
+        let lowest = (0..7)
+            .map(Pile::Tableau)
+            .chain([Pile::Waste])
+            .filter_map(|pile| self.pile(pile).last().map(|card| (card.rank, pile)))
+            .min_by_key(|&(rank, _)| rank);
+        lowest.is_some_and(|(_, pile)| self.send_to_foundation(pile))
+    }
+}
+
+/// How far down each card of a tableau column is drawn, squeezed together if
+/// the column's too long for the screen
+fn tableau_offsets(cards: &[Card], room: usize) -> Vec<usize> {
+    let face_down = cards.iter().filter(|card| !card.face_up).count();
+    let face_up = cards.len() - face_down;
+    let spare = room.saturating_sub(CARD_HEIGHT + face_down * FACE_DOWN_OFFSET);
+    let up_offset = match face_up {
+        0 | 1 => FACE_UP_OFFSET,
+        n => FACE_UP_OFFSET.min(spare / (n - 1)),
+    };
+    let mut y = 0;
+    cards
+        .iter()
+        .map(|card| {
+            let offset = y;
+            y += if card.face_up {
+                up_offset
+            } else {
+                FACE_DOWN_OFFSET
+            };
+            offset
+        })
+        .collect()
+}
+
+fn outline(frame: &mut Frame, x: usize, y: usize, width: usize, height: usize, color: LinSrgb<u8>) {
+    let (x, y) = (x.saturating_sub(3), y.saturating_sub(3));
+    let (width, height) = (width + 6, height + 6);
+    frame.fill_rect(x, y, width, 3, color);
+    frame.fill_rect(x, y + height - 3, width, 3, color);
+    frame.fill_rect(x, y, 3, height, color);
+    frame.fill_rect(x + width - 3, y, 3, height, color);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Playing,
+    /// Putting the cards up one by one, the next at the instant
+    AutoComplete(Instant),
+    Won,
+}
+
+pub struct SolitaireApp {
+    game: Game,
+    /// Games as they were before each move, for undo
+    history: Vec<Game>,
+    phase: Phase,
+    cursor: Pile,
+    /// Which card of a tableau column the cursor's on
+    depth: usize,
+    /// The pile and index of the cards picked up
+    held: Option<(Pile, usize)>,
+    /// When this sitting started, for timing the game
+    resumed: Instant,
+    /// Whether START's been pressed once to give up on a game in progress
+    confirm_deal: bool,
+    /// The card faces, then the back, or none if they couldn't be loaded
+    sprites: Option<Vec<Surface>>,
+    /// Games played and won, the fastest and the fewest moves
+    stats: Store,
+    confetti: Emitter,
+    rng: StdRng,
+}
+
+impl Default for SolitaireApp {
+    fn default() -> Self {
+        let sprites = rg35xx::assets::image(SPRITES)
+            .map(|sheet| Surface::sheet(&sheet, CARD_WIDTH as u32, CARD_HEIGHT as u32))
+            .ok()
+            .filter(|sprites| sprites.len() > 3 * 14 + 12);
+        let mut rng = rg35xx::random::rng("solitaire");
+        let saved = Game::load();
+        let fresh = saved.is_none();
+        let mut app = Self {
+            game: saved.unwrap_or_else(|| Game::deal(&mut rng)),
+            history: Vec::new(),
+            phase: Phase::Playing,
+            cursor: Pile::Tableau(0),
+            depth: 0,
+            held: None,
+            resumed: Instant::now(),
+            confirm_deal: false,
+            sprites,
+            stats: Store::open("solitaire"),
+            confetti: Emitter::new("solitaire", 400)
+                .with_lifetime(Duration::from_millis(1500), Duration::from_millis(2500))
+                .with_speed(250.0, 450.0)
+                .with_direction(-std::f32::consts::FRAC_PI_2, 0.5)
+                .with_gravity(300.0)
+                .with_colors(&[
+                    LinSrgb::new(255, 255, 255),
+                    LinSrgb::new(220, 40, 40),
+                    LinSrgb::new(255, 210, 60),
+                ])
+                .with_size(4),
+            rng,
+        };
+        if fresh {
+            app.record("played", 1, |played, new| played + new);
+        }
+        app.cursor_to(Pile::Tableau(0));
+        app
+    }
+}
+
+impl SolitaireApp {
+    fn new_game(&mut self) {
+        self.game = Game::deal(&mut self.rng);
+        self.history.clear();
+        self.phase = Phase::Playing;
+        self.held = None;
+        self.resumed = Instant::now();
+        self.confirm_deal = false;
+        self.cursor_to(Pile::Tableau(0));
+        self.record("played", 1, |played, new| played + new);
+    }
+
+    /// Seconds into the game
+    fn seconds(&self) -> u64 {
+        self.game.seconds + self.resumed.elapsed().as_secs()
+    }
+
+    /// Updates a statistic with `f` of the old and new values, or sets it if
+    /// it's the first
+    fn record(&mut self, key: &str, value: u64, f: impl Fn(u64, u64) -> u64) {
+        let updated = match self.stats.get(key) {
+            Some(old) => f(old, value),
+            None => value,
+        };
+        self.stats.set(key, updated);
+        if let Err(e) = self.stats.save() {
+            println!("Couldn't save the solitaire statistics: {}", e);
+        }
+    }
+
+    /// Does `f` to the game, keeping the old one for undo if it changed
+    fn act(&mut self, f: impl FnOnce(&mut Game) -> bool) {
+        let before = self.game.clone();
+        if f(&mut self.game) {
+            self.history.push(before);
+            if self.history.len() > HISTORY {
+                self.history.remove(0);
+            }
+            self.confirm_deal = false;
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(game) = self.history.pop() {
+            // The clock carries on from where it's got to
+            let seconds = self.game.seconds;
+            self.game = Game { seconds, ..game };
+            self.held = None;
+            self.cursor_to(self.cursor);
+        }
+    }
+
+    /// Moves the cursor to `pile`, on the top card if it's a tableau column
+    fn cursor_to(&mut self, pile: Pile) {
+        self.cursor = pile;
+        self.depth = self.game.pile(pile).len().saturating_sub(1);
+    }
+
+    /// The top face-up card of the tableau column the cursor's in, as far up
+    /// as the cursor can go
+    fn first_face_up(&self, column: usize) -> usize {
+        let cards = &self.game.tableau[column];
+        cards
+            .iter()
+            .position(|card| card.face_up)
+            .unwrap_or(cards.len().saturating_sub(1))
+    }
+
+    fn handle_movement(&mut self, input: &Input) {
+        let column = self.cursor.column();
+        let in_tableau = matches!(self.cursor, Pile::Tableau(_));
+        if input.repeated(Button::PovLeft) || input.repeated(Button::PovRight) {
+            let step = if input.repeated(Button::PovLeft) {
+                6
+            } else {
+                1
+            };
+            let mut next = (column + step) % 7;
+            if in_tableau {
+                self.cursor_to(Pile::Tableau(next));
+            } else {
+                if next == 2 {
+                    next = (next + step) % 7;
+                }
+                self.cursor_to(Pile::top_row(next));
+            }
+        }
+        if input.repeated(Button::PovUp) {
+            match self.cursor {
+                Pile::Tableau(column) if self.depth > self.first_face_up(column) => self.depth -= 1,
+                Pile::Tableau(column) => self.cursor_to(Pile::top_row(column)),
+                _ => (),
+            }
+        }
+        if input.repeated(Button::PovDown) {
+            match self.cursor {
+                Pile::Tableau(column) => {
+                    self.depth =
+                        (self.depth + 1).min(self.game.tableau[column].len().saturating_sub(1))
+                }
+                pile => self.cursor_to(Pile::Tableau(pile.column())),
+            }
+        }
+    }
+
+    fn handle_input(&mut self, input: &Input) {
+        let start = input.start_pressed();
+        if self.phase == Phase::Won {
+            if start || input.just_pressed(Button::ActionA) {
+                self.new_game();
+            }
+            return;
+        }
+        if start {
+            if self.confirm_deal || self.game.moves == 0 {
+                self.new_game();
+            } else {
+                self.confirm_deal = true;
+            }
+        }
+        if matches!(self.phase, Phase::AutoComplete(_)) {
+            return;
+        }
+
+        self.handle_movement(input);
+        if input.just_pressed(Button::ActionA) {
+            let index = match self.cursor {
+                Pile::Tableau(_) => self.depth,
+                pile => self.game.pile(pile).len().saturating_sub(1),
+            };
+            match self.held.take() {
+                Some((from, _)) if from == self.cursor => (),
+                Some((from, index)) => {
+                    let to = self.cursor;
+                    self.act(|game| game.move_cards(from, index, to));
+                }
+                None if self.cursor == Pile::Stock => self.act(Game::draw),
+                None if self.game.can_pick_up(self.cursor, index) => {
+                    self.held = Some((self.cursor, index))
+                }
+                None => (),
+            }
+            self.cursor_to(self.cursor);
+        }
+        // Putting back what's held, or sending the card up
+        if input.just_pressed(Button::ActionB) && self.held.take().is_none() {
+            let from = self.cursor;
+            self.act(|game| game.send_to_foundation(from));
+            self.cursor_to(self.cursor);
+        }
+        if input.just_pressed(Button::ActionV) {
+            self.undo();
+        }
+        if input.just_pressed(Button::ActionH) {
+            self.held = None;
+            self.act(Game::draw);
+        }
+    }
+
+    fn check_finished(&mut self, input: &Input) {
+        if let Phase::AutoComplete(next) = self.phase {
+            if Instant::now() >= next {
+                self.phase = if self.game.auto_step() {
+                    Phase::AutoComplete(next + AUTO_COMPLETE_STEP)
+                } else {
+                    Phase::Playing
+                };
+            }
+        } else if self.phase == Phase::Playing
+            && self.game.can_auto_complete()
+            && !self.game.is_won()
+        {
+            self.history.push(self.game.clone());
+            self.held = None;
+            self.phase = Phase::AutoComplete(Instant::now());
+        }
+
+        if self.phase != Phase::Won && self.game.is_won() {
+            self.phase = Phase::Won;
+            let seconds = self.seconds();
+            self.game.seconds = seconds;
+            self.record("won", 1, |won, new| won + new);
+            self.record("fastest", seconds, u64::min);
+            self.record("fewest_moves", self.game.moves as u64, u64::min);
+            unlock("solitaire_win");
+            if seconds < FAST_WIN {
+                unlock("solitaire_fast");
+            }
+            Game::clear();
+            self.confetti.burst(300, 320.0, 480.0);
+        }
+        self.confetti.update(input.delta());
+    }
+
+    fn pile_position(pile: Pile) -> (usize, usize) {
+        let x = LEFT + pile.column() * COLUMN_SPACING;
+        match pile {
+            Pile::Tableau(_) => (x, TABLEAU_TOP),
+            _ => (x, TOP),
+        }
+    }
+
+    fn draw_card(&self, frame: &mut Frame, card: Card, x: usize, y: usize) {
+        if let Some(sprites) = &self.sprites {
+            let sprite = if card.face_up {
+                card.sprite()
+            } else {
+                BACK_SPRITE
+            };
+            sprites[sprite].draw(frame, x as i32, y as i32);
+            return;
+        }
+        // Plain cards, if the sprites are missing
+        frame.fill_rect(x, y, CARD_WIDTH, CARD_HEIGHT, LinSrgb::new(40, 40, 40));
+        if !card.face_up {
+            frame.fill_rect(
+                x + 1,
+                y + 1,
+                CARD_WIDTH - 2,
+                CARD_HEIGHT - 2,
+                LinSrgb::new(40, 70, 160),
+            );
+            return;
+        }
+        frame.fill_rect(
+            x + 1,
+            y + 1,
+            CARD_WIDTH - 2,
+            CARD_HEIGHT - 2,
+            LinSrgb::new(250, 250, 250),
+        );
+        let color = if card.suit.is_red() {
+            LinSrgb::new(200, 20, 20)
+        } else {
+            LinSrgb::new(0, 0, 0)
+        };
+        let label = format!("{}{}", card.rank_name(), card.suit.letter());
+        frame.text("fonts/Ubuntu-B.ttf", x + 5, y + 20, 18.0, color, &label);
+    }
+
+    fn draw_slot(frame: &mut Frame, x: usize, y: usize, label: &str) {
+        frame.fill_rect(x, y, CARD_WIDTH, CARD_HEIGHT, LinSrgb::new(10, 70, 35));
+        frame.fill_rect(x + 2, y + 2, CARD_WIDTH - 4, CARD_HEIGHT - 4, FELT);
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            x + CARD_WIDTH / 2 - 8,
+            y + CARD_HEIGHT / 2 + 8,
+            22.0,
+            LinSrgb::new(10, 70, 35),
+            label,
+        );
+    }
+
+    /// The top left of where the cursor or held cards start, and the height
+    /// down to the bottom of the pile
+    fn highlight(&self, pile: Pile, index: usize, offsets: &[Vec<usize>]) -> (usize, usize, usize) {
+        let (x, y) = Self::pile_position(pile);
+        match pile {
+            Pile::Tableau(column) => {
+                let offsets = &offsets[column];
+                let top = offsets.get(index).copied().unwrap_or(0);
+                let bottom = offsets.last().copied().unwrap_or(0);
+                (x, y + top, bottom - top + CARD_HEIGHT)
+            }
+            _ => (x, y, CARD_HEIGHT),
+        }
+    }
+
+    fn draw_table(&self, frame: &mut Frame) {
+        let game = &self.game;
+        let (x, y) = Self::pile_position(Pile::Stock);
+        match game.stock.last() {
+            Some(&card) => self.draw_card(frame, card, x, y),
+            None => Self::draw_slot(frame, x, y, if game.waste.is_empty() { "" } else { "O" }),
+        }
+        let (x, y) = Self::pile_position(Pile::Waste);
+        match game.waste.last() {
+            Some(&card) => self.draw_card(frame, card, x, y),
+            None => Self::draw_slot(frame, x, y, ""),
+        }
+        for (i, foundation) in game.foundations.iter().enumerate() {
+            let (x, y) = Self::pile_position(Pile::Foundation(i));
+            match foundation.last() {
+                Some(&card) => self.draw_card(frame, card, x, y),
+                None => Self::draw_slot(frame, x, y, "A"),
+            }
+        }
+
+        let room = frame.height() - TABLEAU_TOP - TOP;
+        let offsets = game
+            .tableau
+            .iter()
+            .map(|cards| tableau_offsets(cards, room))
+            .collect::<Vec<_>>();
+        for (column, cards) in game.tableau.iter().enumerate() {
+            let (x, y) = Self::pile_position(Pile::Tableau(column));
+            if cards.is_empty() {
+                Self::draw_slot(frame, x, y, "K");
+            }
+            for (card, offset) in cards.iter().zip(&offsets[column]) {
+                self.draw_card(frame, *card, x, y + offset);
+            }
+        }
+
+        if let Some((pile, index)) = self.held {
+            let (x, y, height) = self.highlight(pile, index, &offsets);
+            outline(frame, x, y, CARD_WIDTH, height, LinSrgb::new(255, 210, 60));
+        }
+        if !matches!(self.phase, Phase::Playing) {
+            return;
+        }
+        let (x, y, height) = self.highlight(self.cursor, self.depth, &offsets);
+        let color = if self.held.is_some() {
+            LinSrgb::new(120, 255, 120)
+        } else {
+            LinSrgb::new(80, 200, 255)
+        };
+        outline(frame, x, y, CARD_WIDTH, height, color);
+    }
+
+    fn draw_status(&self, frame: &mut Frame) {
+        let seconds = self.seconds();
+        let lines = [
+            format!("{}:{:02}", seconds / 60, seconds % 60),
+            format!("{} moves", self.game.moves),
+            if self.confirm_deal {
+                "START: deal".to_string()
+            } else {
+                "X undo".to_string()
+            },
+            "Y draw".to_string(),
+        ];
+        let x = LEFT + 2 * COLUMN_SPACING - 4;
+        for (i, line) in lines.iter().enumerate() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                x,
+                TOP + 18 + i * 22,
+                16.0,
+                LinSrgb::new(220, 240, 220),
+                line,
+            );
+        }
+    }
+
+    fn draw_won(&self, frame: &mut Frame) {
+        let won = self.stats.get::<u64>("won").unwrap_or(0);
+        let played = self.stats.get::<u64>("played").unwrap_or(0);
+        let fastest = self.stats.get::<u64>("fastest").unwrap_or(0);
+        let lines = [
+            format!(
+                "Won in {}:{:02} and {} moves!",
+                self.game.seconds / 60,
+                self.game.seconds % 60,
+                self.game.moves
+            ),
+            format!("{} won of {} played", won, played),
+            format!(
+                "Fastest {}:{:02}, fewest moves {}",
+                fastest / 60,
+                fastest % 60,
+                self.stats.get::<u64>("fewest_moves").unwrap_or(0)
+            ),
+            "A or START to deal again".to_string(),
+        ];
+        let top = 160;
+        frame.fill_rect(80, top, frame.width() - 160, 150, LinSrgb::new(0, 0, 0));
+        for (i, line) in lines.iter().enumerate() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                100,
+                top + 36 + i * 30,
+                20.0,
+                LinSrgb::new(255, 255, 255),
+                line,
+            );
+        }
+    }
+}
+
+impl App for SolitaireApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        self.handle_input(input);
+        self.check_finished(input);
+
+        frame.fill_rect(0, 0, frame.width(), frame.height(), FELT);
+        self.draw_table(frame);
+        self.draw_status(frame);
+        if self.phase == Phase::Won {
+            self.draw_won(frame);
+        }
+        self.confetti.draw(frame);
+    }
+
+    fn activity(&self) -> Activity {
+        match self.phase {
+            Phase::AutoComplete(_) => Activity::Busy,
+            _ if self.confetti.is_active() => Activity::Busy,
+            // Ticking the clock over
+            Phase::Playing => {
+                let into_second = self.resumed.elapsed().subsec_nanos();
+                Activity::Idle(
+                    Instant::now() + Duration::from_nanos(1_000_000_000 - into_second as u64),
+                )
+            }
+            Phase::Won => Activity::Waiting,
+        }
+    }
+
+    fn on_exit(&mut self) {
+        if self.phase == Phase::Won {
+            return;
+        }
+        self.game.seconds = self.seconds();
+        self.resumed = Instant::now();
+        if let Err(e) = self.game.save() {
+            println!("Couldn't save the solitaire game: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn card(rank: u8, suit: Suit) -> Card {
+        Card {
+            rank,
+            suit,
+            face_up: true,
+        }
+    }
+
+    #[test]
+    fn test_moves() {
+        let mut game = Game::deal(&mut rg35xx::random::rng("solitaire-test"));
+        assert_eq!(game.stock.len(), 24);
+        assert_eq!(
+            game.tableau[6].iter().filter(|card| card.face_up).count(),
+            1
+        );
+
+        game.tableau = Default::default();
+        game.tableau[0] = vec![
+            Card {
+                face_up: false,
+                ..card(5, Suit::Clubs)
+            },
+            card(8, Suit::Spades),
+            card(7, Suit::Hearts),
+        ];
+        game.tableau[1] = vec![card(9, Suit::Diamonds)];
+        game.tableau[2] = vec![card(1, Suit::Hearts)];
+        // Only onto a card one higher of the other color, and kings into gaps
+        assert!(!game.move_cards(Pile::Tableau(0), 2, Pile::Tableau(1)));
+        assert!(!game.move_cards(Pile::Tableau(0), 0, Pile::Tableau(3)));
+        assert!(game.move_cards(Pile::Tableau(0), 1, Pile::Tableau(1)));
+        assert_eq!(game.tableau[1].len(), 3);
+        assert!(game.tableau[0][0].face_up);
+        assert!(game.send_to_foundation(Pile::Tableau(2)));
+        assert!(!game.send_to_foundation(Pile::Tableau(1)));
+
+        // The stock goes through the waste and back
+        game.stock = vec![card(2, Suit::Clubs), card(3, Suit::Clubs)];
+        game.waste.clear();
+        assert!(game.draw() && game.draw() && game.draw());
+        assert_eq!(game.stock.len(), 2);
+        assert_eq!(game.stock.last().map(|card| card.rank), Some(3));
+        assert!(game.stock.iter().all(|card| !card.face_up));
+
+        // Everything's turned over, so it can finish itself
+        game.stock.clear();
+        game.tableau = Default::default();
+        game.foundations = Default::default();
+        for suit in all::<Suit>() {
+            for rank in (1..=13).rev() {
+                game.tableau[suit as usize].push(card(rank, suit));
+            }
+        }
+        assert!(game.can_auto_complete());
+        while game.auto_step() {}
+        assert!(game.is_won());
+    }
+
+    #[test]
+    fn test_offsets() {
+        let column = vec![card(13, Suit::Clubs); 19];
+        let offsets = tableau_offsets(&column, 356);
+        assert_eq!(offsets[1], FACE_UP_OFFSET.min((356 - CARD_HEIGHT) / 18));
+        assert!(offsets.last().unwrap() + CARD_HEIGHT <= 356);
+    }
+}
@@ -0,0 +1,145 @@
+//! How long each app has been used, from what the menu records in
+//! `usage.txt`.
+
+use enum_iterator::all;
+use palette::LinSrgb;
+
+use rg35xx::app::{App, AppContext, Button, Frame, Manifest};
+use rg35xx::storage::{reset_usage, today, Usage};
+use rg35xx::widgets::Plot;
+
+pub const MANIFEST: Manifest =
+    Manifest::new("0.1.0").description("Time spent and launches for each app");
+
+/// How many days the chart covers
+const DAYS: usize = 7;
+/// How many apps fit in the list of totals
+const ROWS: usize = 5;
+
+/// `1h 05m`, or `4m 10s` under an hour
+fn format_duration(seconds: u64) -> String {
+    if seconds >= 3600 {
+        format!("{}h {:02}m", seconds / 3600, seconds / 60 % 60)
+    } else {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+pub struct StatsApp {
+    usage: Usage,
+    /// Y was pressed once, and resets everything if pressed again
+    confirming_reset: bool,
+}
+
+impl Default for StatsApp {
+    fn default() -> Self {
+        Self {
+            usage: Usage::load(),
+            confirming_reset: false,
+        }
+    }
+}
+
+impl StatsApp {
+    fn draw_totals(&self, frame: &mut Frame) {
+        let totals = self.usage.totals();
+        let mut ctx = frame.context();
+        ctx.offset(50, 50);
+        ctx.set_fontsize(36.0);
+        ctx.text("Stats");
+        ctx.set_fontsize(18.0);
+        ctx.offset(0, 10);
+        if totals.is_empty() {
+            ctx.offset(0, 30);
+            ctx.text("Nothing played yet");
+        }
+        for (app, usage) in totals.iter().take(ROWS) {
+            ctx.offset(0, 25);
+            ctx.text(&format!(
+                "{}: {}, {} launches",
+                app,
+                format_duration(usage.seconds),
+                usage.launches
+            ));
+        }
+    }
+
+    /// Minutes played on each of the last [`DAYS`] days, in all apps and in
+    /// the most played one
+    fn draw_chart(&self, frame: &mut Frame) {
+        let days = self.usage.daily(today(), DAYS);
+        let minutes = |days: Vec<(String, u64)>| {
+            days.into_iter()
+                .map(|(_, seconds)| seconds as f32 / 60.0)
+                .collect::<Vec<_>>()
+        };
+        let mut plot = Plot::new(frame.width() - 100, 150)
+            .with_series("all apps", LinSrgb::new(255, 0, 0))
+            .with_zero(true)
+            .with_ticks(4)
+            .with_x_labels(&days[0].0[5..], &days[DAYS - 1].0[5..]);
+        if let Some((app, _)) = self.usage.totals().first() {
+            plot = plot.with_series(app, LinSrgb::new(255, 255, 255));
+            plot.set_values(1, minutes(self.usage.daily_app(app, today(), DAYS)));
+        }
+        let longest = days.iter().map(|(_, seconds)| *seconds).max().unwrap_or(0);
+        plot.set_values(0, minutes(days));
+
+        let top = frame.height() - 220;
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            top,
+            14.0,
+            LinSrgb::new(180, 180, 180),
+            &format!("Minutes a day. Most in a day: {}", format_duration(longest)),
+        );
+        plot.draw(frame, 50, top + 10);
+    }
+}
+
+impl App for StatsApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        if input.just_pressed(Button::ActionH) {
+            if self.confirming_reset {
+                match reset_usage() {
+                    Ok(()) => self.usage = Usage::default(),
+                    Err(e) => println!("Couldn't reset usage.txt: {}", e),
+                }
+                self.confirming_reset = false;
+            } else {
+                self.confirming_reset = true;
+            }
+        } else if all::<Button>().any(|button| input.just_pressed(button)) {
+            self.confirming_reset = false;
+        }
+
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+        self.draw_totals(frame);
+        self.draw_chart(frame);
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            50,
+            frame.height() - 20,
+            18.0,
+            LinSrgb::new(180, 180, 180),
+            if self.confirming_reset {
+                "Y again to forget everything"
+            } else {
+                "Y to reset"
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(250), "4m 10s");
+        assert_eq!(format_duration(3900), "1h 05m");
+    }
+}
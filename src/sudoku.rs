@@ -0,0 +1,748 @@
+//! Sudoku, with puzzles generated on the device at a choice of difficulties.
+//! Every puzzle has exactly one solution. The daily puzzle is seeded from the
+//! date, so everyone playing it that day gets the same one.
+//!
+//! L1 and R1 pick a digit and A writes it in, or pencils it in as a mark
+//! with Y switching modes. Digits that clash with another in the same row,
+//! column or box are shown in red.
+
+use enum_iterator::{all, Sequence};
+use palette::LinSrgb;
+use rand::{rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+
+use rg35xx::achievements::{unlock, Achievement};
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::profiles::Store;
+use rg35xx::savestate::SaveState;
+use rg35xx::storage::today;
+use rg35xx::time::{Duration, Instant};
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0")
+    .description("Number puzzles, with a new daily one")
+    .achievements(&[
+        Achievement::new("sudoku_solve", "Logician", "Solve a Sudoku"),
+        Achievement::new(
+            "sudoku_expert",
+            "Expert",
+            "Solve an expert Sudoku without hints",
+        ),
+    ]);
+
+const CELL_SIZE: usize = 48;
+const BOARD_LEFT: usize = 24;
+const BOARD_TOP: usize = 24;
+/// Where the digits, clock and help go, right of the board
+const PANEL_LEFT: usize = BOARD_LEFT + CELL_SIZE * 9 + 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Sequence)]
+enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Medium => "Medium",
+            Self::Hard => "Hard",
+            Self::Expert => "Expert",
+        }
+    }
+
+    /// How many digits are given to start with. Expert puzzles can end up
+    /// with a few more, when no more can come out without a second solution.
+    fn givens(self) -> usize {
+        match self {
+            Self::Easy => 40,
+            Self::Medium => 32,
+            Self::Hard => 27,
+            Self::Expert => 22,
+        }
+    }
+
+    /// The key of the best time in the stats store
+    fn best_key(self) -> String {
+        format!("best_{}", self.name().to_lowercase())
+    }
+}
+
+/// The row, column and box around `index`, including it
+fn peers(index: usize) -> impl Iterator<Item = usize> {
+    let (row, column) = (index / 9, index % 9);
+    let (box_row, box_column) = (row / 3 * 3, column / 3 * 3);
+    (0..9).flat_map(move |i| {
+        [
+            row * 9 + i,
+            i * 9 + column,
+            (box_row + i / 3) * 9 + box_column + i % 3,
+        ]
+    })
+}
+
+/// The digits that could go at `index`, as bits 1 to 9
+fn candidates(cells: &[u8], index: usize) -> u16 {
+    let used = peers(index).fold(0u16, |used, peer| used | 1 << cells[peer]);
+    !used & 0b11_1111_1110
+}
+
+/// Counts the ways to fill in the empty cells, stopping at `limit`. The
+/// first solution is left filled in when the limit is reached, and digits
+/// are tried in a random order if there's an `rng`.
+fn solve(cells: &mut [u8], limit: usize, mut rng: Option<&mut StdRng>) -> usize {
+    // The cell with the fewest candidates, to keep the search narrow
+    let mut best: Option<(usize, u16)> = None;
+    for index in (0..81).filter(|&index| cells[index] == 0) {
+        let options = candidates(cells, index);
+        if best.is_none_or(|(_, fewest)| options.count_ones() < fewest.count_ones()) {
+            best = Some((index, options));
+            if options.count_ones() <= 1 {
+                break;
+            }
+        }
+    }
+    let Some((index, options)) = best else {
+        return 1;
+    };
+    let mut digits = (1..=9u8)
+        .filter(|digit| options & 1 << digit != 0)
+        .collect::<Vec<_>>();
+    if let Some(rng) = rng.as_deref_mut() {
+        digits.shuffle(rng);
+    }
+    let mut found = 0;
+    for digit in digits {
+        cells[index] = digit;
+        found += solve(cells, limit - found, rng.as_deref_mut());
+        if found >= limit {
+            return found;
+        }
+    }
+    cells[index] = 0;
+    found
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Puzzle {
+    /// 0 where the player has to fill in
+    givens: Vec<u8>,
+    solution: Vec<u8>,
+}
+
+impl Puzzle {
+    /// Fills a grid at random, then empties cells in a random order for as
+    /// long as there's still only one solution
+    fn generate(difficulty: Difficulty, rng: &mut StdRng) -> Self {
+        let mut solution = vec![0; 81];
+        solve(&mut solution, 1, Some(rng));
+        let mut givens = solution.clone();
+        let mut order = (0..81).collect::<Vec<_>>();
+        order.shuffle(rng);
+        let mut count = 81;
+        for index in order {
+            if count <= difficulty.givens() {
+                break;
+            }
+            let digit = std::mem::take(&mut givens[index]);
+            if solve(&mut givens.clone(), 2, None) == 1 {
+                count -= 1;
+            } else {
+                givens[index] = digit;
+            }
+        }
+        Self { givens, solution }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Game {
+    difficulty: Difficulty,
+    /// The day it's the puzzle of, if it's a daily one
+    daily: Option<i64>,
+    givens: Vec<u8>,
+    solution: Vec<u8>,
+    /// The player's digits, 0 where there's none
+    entries: Vec<u8>,
+    /// Pencil marks, as bits 1 to 9
+    marks: Vec<u16>,
+    /// Seconds played in earlier sittings
+    seconds: u64,
+    hints: u32,
+}
+
+impl SaveState for Game {
+    const NAME: &'static str = "sudoku";
+}
+
+impl Game {
+    fn new(puzzle: Puzzle, difficulty: Difficulty, daily: Option<i64>) -> Self {
+        Self {
+            difficulty,
+            daily,
+            entries: puzzle.givens.clone(),
+            givens: puzzle.givens,
+            solution: puzzle.solution,
+            marks: vec![0; 81],
+            seconds: 0,
+            hints: 0,
+        }
+    }
+
+    fn is_given(&self, index: usize) -> bool {
+        self.givens[index] != 0
+    }
+
+    /// Writes `digit` in, or rubs it out if it's there already, clearing
+    /// the marks it rules out around it
+    fn enter(&mut self, index: usize, digit: u8) {
+        if self.is_given(index) {
+            return;
+        }
+        if self.entries[index] == digit {
+            self.entries[index] = 0;
+            return;
+        }
+        self.entries[index] = digit;
+        self.marks[index] = 0;
+        for peer in peers(index) {
+            self.marks[peer] &= !(1 << digit);
+        }
+    }
+
+    fn toggle_mark(&mut self, index: usize, digit: u8) {
+        if self.entries[index] == 0 {
+            self.marks[index] ^= 1 << digit;
+        }
+    }
+
+    fn erase(&mut self, index: usize) {
+        if !self.is_given(index) {
+            self.entries[index] = 0;
+            self.marks[index] = 0;
+        }
+    }
+
+    /// Whether the digit at `index` is also somewhere else in its row,
+    /// column or box
+    fn clashes(&self, index: usize) -> bool {
+        let digit = self.entries[index];
+        digit != 0 && peers(index).any(|peer| peer != index && self.entries[peer] == digit)
+    }
+
+    /// Fills in the right digit at `index`, or if that's already right, the
+    /// first cell that's empty or wrong. Returns where it went.
+    fn hint(&mut self, index: usize) -> Option<usize> {
+        let wrong = |i: usize| self.entries[i] != self.solution[i];
+        let index = if wrong(index) {
+            index
+        } else {
+            (0..81).find(|&i| wrong(i))?
+        };
+        self.enter(index, self.solution[index]);
+        self.hints += 1;
+        Some(index)
+    }
+
+    fn is_solved(&self) -> bool {
+        self.entries == self.solution
+    }
+
+    /// How many of `digit` are still to be written in
+    fn remaining(&self, digit: u8) -> usize {
+        9usize.saturating_sub(self.entries.iter().filter(|&&d| d == digit).count())
+    }
+}
+
+/// What can be started from the title screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Continue,
+    Daily,
+    New(Difficulty),
+}
+
+enum Phase {
+    Title,
+    Playing,
+    Solved,
+}
+
+pub struct SudokuApp {
+    game: Option<Game>,
+    phase: Phase,
+    /// The highlighted choice on the title screen
+    choice: usize,
+    cursor: usize,
+    digit: u8,
+    pencil: bool,
+    /// When this sitting started, for timing the game
+    resumed: Instant,
+    /// Best times and the last daily puzzle solved
+    stats: Store,
+    rng: StdRng,
+}
+
+impl Default for SudokuApp {
+    fn default() -> Self {
+        Self {
+            game: Game::load(),
+            phase: Phase::Title,
+            choice: 0,
+            cursor: 40,
+            digit: 1,
+            pencil: false,
+            resumed: Instant::now(),
+            stats: Store::open("sudoku"),
+            rng: rg35xx::random::rng("sudoku"),
+        }
+    }
+}
+
+impl SudokuApp {
+    fn choices(&self) -> Vec<Choice> {
+        self.game
+            .is_some()
+            .then_some(Choice::Continue)
+            .into_iter()
+            .chain([Choice::Daily])
+            .chain(all::<Difficulty>().map(Choice::New))
+            .collect()
+    }
+
+    fn seconds(&self) -> u64 {
+        let sitting = match self.phase {
+            Phase::Playing => self.resumed.elapsed().as_secs(),
+            _ => 0,
+        };
+        self.game.as_ref().map_or(0, |game| game.seconds) + sitting
+    }
+
+    /// Adds the time since the game was last carried on with to its total
+    fn pause(&mut self) {
+        let seconds = self.seconds();
+        if let Some(game) = &mut self.game {
+            game.seconds = seconds;
+        }
+        self.resumed = Instant::now();
+    }
+
+    fn start(&mut self, choice: Choice) {
+        match choice {
+            Choice::Continue => (),
+            Choice::Daily => {
+                let day = today();
+                let mut rng = rg35xx::random::daily("sudoku", day);
+                let difficulty = Difficulty::Medium;
+                let puzzle = Puzzle::generate(difficulty, &mut rng);
+                self.game = Some(Game::new(puzzle, difficulty, Some(day)));
+                self.cursor = 40;
+            }
+            Choice::New(difficulty) => {
+                let puzzle = Puzzle::generate(difficulty, &mut self.rng);
+                self.game = Some(Game::new(puzzle, difficulty, None));
+                self.cursor = 40;
+            }
+        }
+        self.pencil = false;
+        self.resumed = Instant::now();
+        self.phase = Phase::Playing;
+    }
+
+    fn solved(&mut self) {
+        self.pause();
+        self.phase = Phase::Solved;
+        Game::clear();
+        let Some(game) = &self.game else {
+            return;
+        };
+        let seconds = game.seconds;
+        unlock("sudoku_solve");
+        if game.difficulty == Difficulty::Expert && game.hints == 0 {
+            unlock("sudoku_expert");
+        }
+        let key = game.difficulty.best_key();
+        if game.hints == 0
+            && self
+                .stats
+                .get::<u64>(&key)
+                .is_none_or(|best| seconds < best)
+        {
+            self.stats.set(&key, seconds);
+        }
+        if let Some(day) = game.daily {
+            self.stats.set("daily_day", day);
+            self.stats.set("daily_seconds", seconds);
+        }
+        if let Err(e) = self.stats.save() {
+            println!("Couldn't save the Sudoku times: {}", e);
+        }
+    }
+
+    /// Seconds it took to solve today's daily puzzle, if it's been done
+    fn daily_done(&self) -> Option<u64> {
+        (self.stats.get::<i64>("daily_day") == Some(today()))
+            .then(|| self.stats.get("daily_seconds"))
+            .flatten()
+    }
+
+    fn handle_title(&mut self, input: &Input, start: bool) {
+        let choices = self.choices();
+        if input.repeated(Button::PovUp) {
+            self.choice = self.choice.saturating_sub(1);
+        }
+        if input.repeated(Button::PovDown) {
+            self.choice += 1;
+        }
+        self.choice = self.choice.min(choices.len() - 1);
+        if start || input.just_pressed(Button::ActionA) {
+            self.start(choices[self.choice]);
+        }
+    }
+
+    fn handle_playing(&mut self, input: &Input, start: bool) {
+        if start {
+            self.pause();
+            self.phase = Phase::Title;
+            self.choice = 0;
+            return;
+        }
+        let (row, column) = (self.cursor / 9, self.cursor % 9);
+        let moves = [
+            (Button::PovUp, (row + 8) % 9, column),
+            (Button::PovDown, (row + 1) % 9, column),
+            (Button::PovLeft, row, (column + 8) % 9),
+            (Button::PovRight, row, (column + 1) % 9),
+        ];
+        for (button, row, column) in moves {
+            if input.repeated(button) {
+                self.cursor = row * 9 + column;
+            }
+        }
+        if input.repeated(Button::BumperL) {
+            self.digit = (self.digit + 7) % 9 + 1;
+        }
+        if input.repeated(Button::BumperR) {
+            self.digit = self.digit % 9 + 1;
+        }
+        if input.just_pressed(Button::ActionH) {
+            self.pencil = !self.pencil;
+        }
+
+        let Some(game) = &mut self.game else {
+            return;
+        };
+        if input.just_pressed(Button::ActionA) {
+            if game.is_given(self.cursor) {
+                // Picking up the digit, to write the same elsewhere
+                self.digit = game.givens[self.cursor];
+            } else if self.pencil {
+                game.toggle_mark(self.cursor, self.digit);
+            } else {
+                game.enter(self.cursor, self.digit);
+            }
+        }
+        if input.just_pressed(Button::ActionB) {
+            game.erase(self.cursor);
+        }
+        if input.just_pressed(Button::ActionV) {
+            if let Some(index) = game.hint(self.cursor) {
+                self.cursor = index;
+            }
+        }
+        if game.is_solved() {
+            self.solved();
+        }
+    }
+
+    fn draw_board(&self, frame: &mut Frame, game: &Game) {
+        let showing = matches!(self.phase, Phase::Playing);
+        let (row, column) = (self.cursor / 9, self.cursor % 9);
+        let selected = game.entries[self.cursor];
+        for index in 0..81 {
+            let (x, y) = (
+                BOARD_LEFT + index % 9 * CELL_SIZE,
+                BOARD_TOP + index / 9 * CELL_SIZE,
+            );
+            let digit = game.entries[index];
+            let same_unit = index / 9 == row
+                || index % 9 == column
+                || (index / 27 == row / 3 && index % 9 / 3 == column / 3);
+            let background = if showing && index == self.cursor {
+                LinSrgb::new(255, 225, 130)
+            } else if showing && digit != 0 && digit == selected {
+                LinSrgb::new(200, 220, 255)
+            } else if showing && same_unit {
+                LinSrgb::new(228, 234, 244)
+            } else {
+                LinSrgb::new(250, 250, 250)
+            };
+            frame.fill_rect(x, y, CELL_SIZE, CELL_SIZE, background);
+
+            if digit != 0 {
+                let color = if game.clashes(index) {
+                    LinSrgb::new(220, 30, 30)
+                } else if game.is_given(index) {
+                    LinSrgb::new(20, 20, 20)
+                } else {
+                    LinSrgb::new(30, 80, 200)
+                };
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    x + 16,
+                    y + 36,
+                    32.0,
+                    color,
+                    &digit.to_string(),
+                );
+            } else {
+                for mark in (1..=9u8).filter(|mark| game.marks[index] & 1 << mark != 0) {
+                    let (mark_x, mark_y) = ((mark as usize - 1) % 3, (mark as usize - 1) / 3);
+                    frame.text(
+                        "fonts/Ubuntu-B.ttf",
+                        x + 5 + mark_x * 14,
+                        y + 15 + mark_y * 15,
+                        13.0,
+                        LinSrgb::new(110, 110, 110),
+                        &mark.to_string(),
+                    );
+                }
+            }
+        }
+
+        // Thin lines between cells and thick ones around the boxes
+        for i in 0..=9 {
+            let (width, color) = if i % 3 == 0 {
+                (3, LinSrgb::new(20, 20, 20))
+            } else {
+                (1, LinSrgb::new(150, 150, 150))
+            };
+            let length = CELL_SIZE * 9 + 2;
+            let x = BOARD_LEFT + i * CELL_SIZE - width / 2;
+            frame.fill_rect(x, BOARD_TOP - 1, width, length, color);
+            let y = BOARD_TOP + i * CELL_SIZE - width / 2;
+            frame.fill_rect(BOARD_LEFT - 1, y, length, width, color);
+        }
+    }
+
+    fn draw_panel(&self, frame: &mut Frame, game: &Game) {
+        let white = LinSrgb::new(255, 255, 255);
+        let grey = LinSrgb::new(150, 150, 150);
+        let title = match game.daily {
+            Some(_) => format!("Daily ({})", game.difficulty.name()),
+            None => game.difficulty.name().to_string(),
+        };
+        frame.text("fonts/Ubuntu-B.ttf", PANEL_LEFT, 44, 20.0, white, &title);
+        let seconds = self.seconds();
+        let clock = format!("{}:{:02}", seconds / 60, seconds % 60);
+        frame.text("fonts/Ubuntu-B.ttf", PANEL_LEFT, 76, 26.0, white, &clock);
+
+        // The digits, with the one picked boxed and those all placed dimmed
+        for digit in 1..=9u8 {
+            let (x, y) = (
+                PANEL_LEFT + (digit as usize - 1) % 3 * 40,
+                100 + (digit as usize - 1) / 3 * 40,
+            );
+            if digit == self.digit {
+                frame.fill_rect(x, y, 36, 36, LinSrgb::new(255, 200, 60));
+            }
+            let color = match (digit == self.digit, game.remaining(digit)) {
+                (true, _) => LinSrgb::new(0, 0, 0),
+                (false, 0) => LinSrgb::new(80, 80, 80),
+                (false, _) => white,
+            };
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                x + 10,
+                y + 28,
+                26.0,
+                color,
+                &digit.to_string(),
+            );
+        }
+        let mode = if self.pencil { "Pencil" } else { "Pen" };
+        frame.text("fonts/Ubuntu-B.ttf", PANEL_LEFT, 248, 20.0, white, mode);
+
+        let help = [
+            "L1/R1 digit",
+            "A write",
+            "B rub out",
+            "Y pen/pencil",
+            "X hint",
+            "START menu",
+        ];
+        for (i, line) in help.iter().enumerate() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                PANEL_LEFT,
+                290 + i * 22,
+                16.0,
+                grey,
+                line,
+            );
+        }
+        if game.hints > 0 {
+            let hints = format!(
+                "{} hint{}",
+                game.hints,
+                if game.hints == 1 { "" } else { "s" }
+            );
+            frame.text("fonts/Ubuntu-B.ttf", PANEL_LEFT, 440, 16.0, grey, &hints);
+        }
+    }
+
+    fn draw_title(&self, frame: &mut Frame) {
+        let white = LinSrgb::new(255, 255, 255);
+        frame.text("fonts/Ubuntu-B.ttf", 60, 80, 40.0, white, "Sudoku");
+        for (i, choice) in self.choices().into_iter().enumerate() {
+            let y = 140 + i * 44;
+            if i == self.choice {
+                frame.fill_rect(50, y - 30, 540, 40, LinSrgb::new(50, 70, 120));
+            }
+            let format_time = |seconds: u64| format!("{}:{:02}", seconds / 60, seconds % 60);
+            let (label, detail) = match choice {
+                Choice::Continue => ("Continue".to_string(), None),
+                Choice::Daily => (
+                    "Daily puzzle".to_string(),
+                    self.daily_done()
+                        .map(|seconds| format!("done in {}", format_time(seconds))),
+                ),
+                Choice::New(difficulty) => (
+                    difficulty.name().to_string(),
+                    self.stats
+                        .get::<u64>(&difficulty.best_key())
+                        .map(|seconds| format!("best {}", format_time(seconds))),
+                ),
+            };
+            frame.text("fonts/Ubuntu-B.ttf", 70, y, 26.0, white, &label);
+            if let Some(detail) = detail {
+                let grey = LinSrgb::new(170, 170, 170);
+                frame.text("fonts/Ubuntu-B.ttf", 330, y, 22.0, grey, &detail);
+            }
+        }
+    }
+}
+
+impl App for SudokuApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        let start = input.start_pressed();
+        match self.phase {
+            Phase::Title => self.handle_title(input, start),
+            Phase::Playing => self.handle_playing(input, start),
+            Phase::Solved => {
+                if start || input.just_pressed(Button::ActionA) {
+                    self.game = None;
+                    self.phase = Phase::Title;
+                    self.choice = 0;
+                }
+            }
+        }
+
+        frame.fill_rect(
+            0,
+            0,
+            frame.width(),
+            frame.height(),
+            LinSrgb::new(30, 34, 44),
+        );
+        match (&self.phase, &self.game) {
+            (Phase::Title, _) | (_, None) => self.draw_title(frame),
+            (_, Some(game)) => {
+                self.draw_board(frame, game);
+                self.draw_panel(frame, game);
+            }
+        }
+        if let (Phase::Solved, Some(game)) = (&self.phase, &self.game) {
+            let text = format!(
+                "Solved in {}:{:02}! A to carry on",
+                game.seconds / 60,
+                game.seconds % 60
+            );
+            frame.fill_rect(
+                60,
+                200,
+                BOARD_LEFT + CELL_SIZE * 9 - 96,
+                60,
+                LinSrgb::new(0, 0, 0),
+            );
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                80,
+                238,
+                22.0,
+                LinSrgb::new(255, 255, 255),
+                &text,
+            );
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        match self.phase {
+            // Ticking the clock over
+            Phase::Playing => {
+                let into_second = self.resumed.elapsed().subsec_nanos() as u64;
+                Activity::Idle(Instant::now() + Duration::from_nanos(1_000_000_000 - into_second))
+            }
+            Phase::Title | Phase::Solved => Activity::Waiting,
+        }
+    }
+
+    fn on_exit(&mut self) {
+        if matches!(self.phase, Phase::Solved) {
+            return;
+        }
+        self.pause();
+        if let Some(game) = &self.game {
+            if let Err(e) = game.save() {
+                println!("Couldn't save the Sudoku: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let puzzle = Puzzle::generate(Difficulty::Hard, &mut rg35xx::random::daily("test", 0));
+        // A complete, valid grid
+        for index in 0..81 {
+            let digit = puzzle.solution[index];
+            assert!((1..=9).contains(&digit));
+            assert!(peers(index).all(|peer| peer == index || puzzle.solution[peer] != digit));
+        }
+        let givens = puzzle.givens.iter().filter(|&&digit| digit != 0).count();
+        assert_eq!(givens, Difficulty::Hard.givens());
+        assert!((0..81).all(|i| puzzle.givens[i] == 0 || puzzle.givens[i] == puzzle.solution[i]));
+        let mut solved = puzzle.givens.clone();
+        assert_eq!(solve(&mut solved, 2, None), 1);
+
+        // The same day gives the same puzzle
+        let again = Puzzle::generate(Difficulty::Hard, &mut rg35xx::random::daily("test", 0));
+        assert_eq!(puzzle, again);
+
+        let mut game = Game::new(puzzle, Difficulty::Hard, None);
+        let empty = (0..81).find(|&i| !game.is_given(i)).unwrap();
+        let digit = game.solution[empty];
+        let peer = peers(empty)
+            .find(|&peer| !game.is_given(peer) && peer != empty)
+            .unwrap();
+        game.toggle_mark(peer, digit);
+        game.enter(empty, digit);
+        assert_eq!(game.marks[peer], 0);
+        // A clashing digit, then a hint putting it right
+        let wrong = digit % 9 + 1;
+        game.enter(peer, digit);
+        assert!(game.clashes(peer) && game.clashes(empty));
+        assert_eq!(game.hint(peer), Some(peer));
+        assert!(!game.clashes(peer));
+        game.entries[peer] = wrong;
+        while game.hint(0).is_some() {}
+        assert!(game.is_solved());
+    }
+}
@@ -1,65 +1,170 @@
-use std::{collections::HashMap, time::Instant};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
 
 use palette::LinSrgb;
-use rand::prelude::*;
+use rand::{prelude::*, rngs::StdRng};
 
-use crate::app::{App, Button};
+use rg35xx::achievements::{unlock, Achievement};
+use rg35xx::app::{Activity, App, AppContext, Button, Manifest};
+use rg35xx::config::config;
+use rg35xx::lobby::{Advert, Browser, Host, Session};
+use rg35xx::ninepatch::draw_panel;
+use rg35xx::particles::Emitter;
+use rg35xx::profiles::Store;
+use rg35xx::text_block::{Align, TextBlock};
+use rg35xx::time::{Duration, Instant};
+use rg35xx::widgets::{BrowserAction, FileBrowser};
 
-static WORDS: &str = include_str!("../assets/words.csv");
+pub const MANIFEST: Manifest = Manifest::new("0.1.0")
+    .description("Party word game for teams or rotating pairs")
+    .achievements(&[
+        Achievement::new("taboo_first_turn", "Warmed up", "Finish a turn"),
+        Achievement::new(
+            "taboo_clean_sweep",
+            "Clean sweep",
+            "Get 5 or more cards in a turn without discarding any",
+        ),
+    ]);
 
-#[derive(Clone)]
-struct Card {
+/// How long each turn has, before any extra time
+const TURN_TIME: Duration = Duration::from_secs(60);
+
+/// The default deck, which can be replaced by putting a `words.csv` in the
+/// assets directory
+fn default_words() -> String {
+    rg35xx::assets::load("words.csv")
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+        .unwrap_or_default()
+}
+
+/// Where extra decks go, in the same format as `assets/words.csv`
+fn decks_dir() -> PathBuf {
+    rg35xx::storage::base_dir().join("decks")
+}
+
+/// Adds to the current profile's running totals
+fn record_stats(counts: &[(&str, u64)]) {
+    let mut store = Store::open("taboo");
+    for &(key, amount) in counts {
+        store.add(key, amount);
+    }
+    if let Err(e) = store.save() {
+        println!("Couldn't save Taboo statistics: {}", e);
+    }
+}
+
+pub struct Card {
     word: String,
     taboo: Vec<String>,
 }
 
+/// The cards in a deck file, one per line as `word,taboo,taboo...`. Duplicates
+/// and words with spaces or hyphens are left out.
+fn parse_deck(words: &str) -> Vec<Card> {
+    let mut seen = HashSet::new();
+    let mut cards = vec![];
+    for line in words.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let mut words = line.split(',').map(str::trim);
+        let word = words.next().unwrap();
+        if word.contains(' ') || word.contains('-') {
+            println!("Found multi-word {}!", word);
+        } else if !seen.insert(word) {
+            println!("Found duplicate {}!", word);
+        } else {
+            cards.push(Card {
+                word: word.to_string(),
+                taboo: words.map(str::to_string).collect(),
+            });
+        }
+    }
+    cards
+}
+
+/// A deck that's been parsed, so it's only read once however many games are
+/// played with it
+struct ParsedDeck {
+    /// `None` for the built-in deck
+    file: Option<PathBuf>,
+    /// When the file was last changed, so edits are picked up
+    modified: Option<SystemTime>,
+    cards: Arc<[Card]>,
+}
+
+static DECKS: Mutex<Vec<ParsedDeck>> = Mutex::new(vec![]);
+
+/// The cards in `deck_file`, or the built-in deck if it's `None` or has no
+/// usable cards
+fn load_deck(deck_file: Option<&Path>) -> Arc<[Card]> {
+    let modified = deck_file.and_then(|path| std::fs::metadata(path).ok()?.modified().ok());
+    let mut decks = DECKS.lock().unwrap();
+    if let Some(deck) = decks
+        .iter()
+        .find(|deck| deck.file.as_deref() == deck_file && deck.modified == modified)
+    {
+        return deck.cards.clone();
+    }
+
+    let words = match deck_file {
+        Some(path) => std::fs::read_to_string(path).unwrap_or_else(|e| {
+            println!(
+                "Couldn't read {}, using the built-in deck: {}",
+                path.display(),
+                e
+            );
+            String::new()
+        }),
+        None => default_words(),
+    };
+    let cards: Arc<[Card]> = parse_deck(&words).into();
+    println!("Found {} usable words", cards.len());
+    if cards.is_empty() && deck_file.is_some() {
+        println!("No usable cards, using the built-in deck");
+        drop(decks);
+        return load_deck(None);
+    }
+    decks.retain(|deck| deck.file.as_deref() != deck_file);
+    decks.push(ParsedDeck {
+        file: deck_file.map(Path::to_path_buf),
+        modified,
+        cards: cards.clone(),
+    });
+    cards
+}
+
 struct GameState {
     num_players: usize,
     teams: bool,
-    deck: Vec<Card>,
-    discards: Vec<Card>,
-    won_cards: Vec<Vec<Card>>,
-    all_cards: Vec<Card>,
+    cards: Arc<[Card]>,
+    /// Indices into `cards` still to be drawn, from the end
+    deck: Vec<usize>,
+    discards: Vec<usize>,
+    /// How many cards each team or player has won
+    won_cards: Vec<usize>,
+    /// The deck picked from `decks/`, or `None` for the built-in one
+    deck_file: Option<PathBuf>,
+    rng: StdRng,
 }
 
 impl GameState {
-    fn new(num_players: usize, teams: bool) -> Self {
-        let mut lines = WORDS
-            .split('\n')
-            .map(|line| line.trim())
-            .filter(|line| !line.is_empty())
-            .map(|line| line.split(',').map(|word| word.trim()))
-            .map(|mut words| {
-                let word = words.next().unwrap().to_string();
-                let taboo = words.map(|word| word.to_string()).collect::<Vec<_>>();
-                Card { word, taboo }
-            })
-            .collect::<Vec<_>>();
-
-        println!("Found {} words", lines.len());
-        let mut cards = HashMap::new();
-        for card in lines.drain(..) {
-            if cards.contains_key(&card.word) {
-                println!("Found duplicate {}!", card.word);
-            } else if card.word.contains(' ') || card.word.contains('-') {
-                println!("Found multi-word {}!", card.word);
-            } else {
-                cards.insert(card.word.clone(), card);
-            }
-        }
-        let mut lines = cards.into_values().collect::<Vec<_>>();
-        println!("{} words after removing duplicates", lines.len());
-
-        let mut rng = rand::thread_rng();
-        lines.shuffle(&mut rng);
+    fn new(num_players: usize, teams: bool, deck_file: Option<PathBuf>) -> Self {
+        let cards = load_deck(deck_file.as_deref());
+        let mut rng = rg35xx::random::rng("taboo");
+        let mut deck = (0..cards.len()).collect::<Vec<_>>();
+        deck.shuffle(&mut rng);
 
         Self {
             num_players,
             teams,
-            deck: lines.clone(),
+            cards,
+            deck,
             discards: vec![],
-            won_cards: (0..num_players).map(|_| vec![]).collect::<Vec<_>>(),
-            all_cards: lines,
+            won_cards: vec![0; num_players],
+            deck_file,
+            rng,
         }
     }
 
@@ -67,16 +172,16 @@ impl GameState {
         self.deck.len() + self.discards.len()
     }
 
-    fn draw_card(&mut self) -> Card {
-        let mut rng = rand::thread_rng();
+    /// The index of the next card
+    fn draw_card(&mut self) -> usize {
         if self.deck.is_empty() {
-            self.discards.shuffle(&mut rng);
+            self.discards.shuffle(&mut self.rng);
             std::mem::swap(&mut self.deck, &mut self.discards);
         }
         if self.deck.is_empty() {
             // Refill the deck
-            self.deck = self.all_cards.clone();
-            self.deck.shuffle(&mut rng);
+            self.deck.extend(0..self.cards.len());
+            self.deck.shuffle(&mut self.rng);
         }
         self.deck.pop().unwrap()
     }
@@ -101,14 +206,17 @@ impl CardResult {
 
 enum TurnState {
     ReadyingUp,
+    /// Cards are indices into [`GameState::cards`]
     Playing {
         start_time: Instant,
-        card: Card,
-        results: Vec<(Card, CardResult)>,
+        card: usize,
+        results: Vec<(usize, CardResult)>,
     },
     TurnEnded {
-        results: Vec<(Card, CardResult)>,
+        results: Vec<(usize, CardResult)>,
         showing: usize,
+        /// Thrown up from the bottom of the screen if any cards were got
+        confetti: Box<Emitter>,
     },
 }
 
@@ -152,15 +260,120 @@ impl CurrentTurn {
     }
 }
 
+const CONFETTI: [LinSrgb<u8>; 5] = [
+    LinSrgb::new(255, 60, 60),
+    LinSrgb::new(255, 200, 40),
+    LinSrgb::new(60, 220, 90),
+    LinSrgb::new(60, 140, 255),
+    LinSrgb::new(220, 90, 255),
+];
+
+/// Name of the game in lobby announcements
+const LOBBY_GAME: &str = "taboo";
+
+/// A second device that shows the other team the card, so they can buzz when
+/// a taboo word is said
+#[derive(Default)]
+pub struct Link {
+    host: Option<Host>,
+    session: Option<Session>,
+    /// The last state sent, so it's only sent again when it changes
+    sent: String,
+}
+
+impl Link {
+    fn is_active(&self) -> bool {
+        self.host.is_some() || self.session.is_some()
+    }
+
+    fn toggle_hosting(&mut self) {
+        if self.is_active() {
+            *self = Self::default();
+            return;
+        }
+        match Host::start(LOBBY_GAME) {
+            Ok(host) => self.host = Some(host),
+            Err(e) => println!("Couldn't host a game: {}", e),
+        }
+    }
+
+    /// Returns whether the other device buzzed
+    fn poll(&mut self) -> bool {
+        if let Some(host) = self.host.as_mut() {
+            match host.poll() {
+                Ok(Some(session)) => {
+                    self.session = Some(session);
+                    self.host = None;
+                }
+                Ok(None) => (),
+                Err(e) => {
+                    println!("Stopped hosting: {}", e);
+                    self.host = None;
+                }
+            }
+        }
+        let Some(session) = self.session.as_mut() else {
+            return false;
+        };
+        let buzzed = session.poll().iter().any(|m| m == "buzz");
+        if !session.is_connected() {
+            *self = Self::default();
+        }
+        buzzed
+    }
+
+    /// Shows `card` on the other device, or a waiting screen
+    fn show(&mut self, card: Option<&Card>) {
+        let Some(session) = self.session.as_mut() else {
+            return;
+        };
+        let message = match card {
+            Some(card) => format!("card {},{}", card.word, card.taboo.join(",")),
+            None => "wait".to_string(),
+        };
+        if message != self.sent {
+            session.send(&message);
+            session.poll();
+            self.sent = message;
+        }
+    }
+
+    fn status(&self) -> &'static str {
+        if self.session.is_some() {
+            "Second device connected, X to disconnect"
+        } else if self.host.is_some() {
+            "Waiting for a second device, X to stop"
+        } else {
+            "X: show cards on a second device, Y: be the second device"
+        }
+    }
+}
+
 pub enum TabooApp {
     Menu {
         players: usize,
         teams: bool,
+        link: Link,
+        deck: Option<PathBuf>,
+        /// Open while choosing a deck
+        picker: Option<Box<FileBrowser>>,
     },
     InGame {
-        game: GameState,
+        game: Box<GameState>,
         turn: TurnState,
         current_turn: CurrentTurn,
+        link: Link,
+    },
+    /// Looking for a game to be the second device for
+    Joining {
+        browser: Option<Browser>,
+        hosts: Vec<Advert>,
+        selected: usize,
+    },
+    /// Showing another device's card
+    Watching {
+        session: Session,
+        card: Option<Card>,
     },
 }
 
@@ -169,37 +382,111 @@ impl Default for TabooApp {
         Self::Menu {
             players: 2,
             teams: true,
+            link: Link::default(),
+            deck: None,
+            picker: None,
         }
     }
 }
 
-fn render_card(frame: &mut crate::app::Frame, card: &Card, x: usize, y: usize) {
-    frame.text(
-        "fonts/Ubuntu-B.ttf",
-        x,
-        y,
-        48.0,
-        LinSrgb::new(255, 255, 255),
-        &card.word,
-    );
-    for (i, taboo) in card.taboo.iter().enumerate() {
-        frame.text(
-            "fonts/Ubuntu-B.ttf",
-            x,
-            y + 35 + i * 40,
-            36.0,
-            LinSrgb::new(255, 0, 0),
-            taboo,
-        );
+impl TabooApp {
+    /// What a phone running the remote controller shows
+    #[cfg(feature = "remote")]
+    fn remote_page(&self) -> rg35xx::remote::Page {
+        let button = |label: &str, button| (label.to_string(), button);
+        let (title, lines, buttons) = match self {
+            Self::InGame {
+                game,
+                turn: TurnState::Playing { card, .. },
+                ..
+            } => (
+                game.cards[*card].word.clone(),
+                game.cards[*card].taboo.clone(),
+                vec![
+                    button("Taboo!", Button::ActionB),
+                    button("Got it", Button::ActionA),
+                ],
+            ),
+            Self::InGame {
+                turn: TurnState::ReadyingUp,
+                ..
+            } => (
+                "Next turn".to_string(),
+                vec![],
+                vec![button("Start turn", Button::ActionA)],
+            ),
+            Self::InGame {
+                turn: TurnState::TurnEnded { .. },
+                ..
+            } => (
+                "Turn over".to_string(),
+                vec![],
+                vec![button("Continue", Button::ActionA)],
+            ),
+            _ => (
+                "Taboo".to_string(),
+                vec!["Start a game on the device".to_string()],
+                vec![],
+            ),
+        };
+        rg35xx::remote::Page {
+            title,
+            lines,
+            buttons,
+        }
     }
 }
 
+/// Draws the word and its taboo words centered on a panel, wrapping any that
+/// are too long for it
+fn render_card(frame: &mut rg35xx::app::Frame, card: &Card, x: usize, y: usize) {
+    let (left, top) = (x.saturating_sub(30), y.saturating_sub(50));
+    let width = frame.width().saturating_sub(left * 2);
+    let block = |fontsize, color, text: &str| {
+        TextBlock::new("fonts/Ubuntu-B.ttf", fontsize, color)
+            .with_max_width(width.saturating_sub(60))
+            .with_align(Align::Center)
+            .with_line_spacing(40.0 / 36.0)
+            .with_text(text)
+    };
+    let word = block(48.0, LinSrgb::new(255, 255, 255), &card.word).with_line_spacing(1.0);
+    let taboo = block(36.0, LinSrgb::new(255, 0, 0), &card.taboo.join("\n"));
+    let (word_height, taboo_height) = (word.size().1, taboo.size().1);
+    draw_panel(frame, left, top, width, word_height + taboo_height + 20);
+    word.draw(frame, x, top + 10);
+    taboo.draw(frame, x, top + 10 + word_height);
+}
+
 impl App for TabooApp {
-    fn update(&mut self, input: &crate::app::Input, frame: &mut crate::app::Frame) {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
         frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
 
+        let buzzed = match self {
+            Self::Menu { link, .. } | Self::InGame { link, .. } => link.poll(),
+            _ => false,
+        };
+
         match self {
-            Self::Menu { players, teams } => {
+            Self::Menu { picker, deck, .. } if picker.is_some() => {
+                let browser = picker.as_mut().unwrap();
+                browser.draw(frame, 50, 30, frame.width() - 100, frame.height() - 60);
+                match browser.handle_input(input) {
+                    Some(BrowserAction::Open(path)) => {
+                        *deck = Some(path);
+                        *picker = None;
+                    }
+                    Some(BrowserAction::Leave) => *picker = None,
+                    None => (),
+                }
+            }
+            Self::Menu {
+                players,
+                teams,
+                link,
+                deck,
+                picker,
+            } => {
                 let mut ctx = frame.context();
                 ctx.set_fontsize(18.0);
                 ctx.set_color(LinSrgb::new(255, 0, 0));
@@ -213,11 +500,31 @@ impl App for TabooApp {
 
                 ctx.offset(0, 20);
                 ctx.text("Press START");
+                ctx.offset(0, 40);
+                ctx.text(link.status());
+                ctx.offset(0, 40);
+                ctx.text(&match deck.as_ref().and_then(|path| path.file_name()) {
+                    Some(name) => format!(
+                        "Deck: {} (L1 to change, R1 for built-in)",
+                        name.to_string_lossy()
+                    ),
+                    None => "Deck: built-in (L1 to change)".to_string(),
+                });
+                let stats = Store::open("taboo");
+                let stat = |key| stats.get::<u64>(key).unwrap_or(0);
+                ctx.offset(0, 40);
+                ctx.text(&format!(
+                    "{}: {} games, {} cards got, {} discarded",
+                    rg35xx::profiles::current(),
+                    stat("games"),
+                    stat("cards_won"),
+                    stat("cards_discarded")
+                ));
 
-                if input.just_pressed(Button::PovUp) {
+                if input.repeated(Button::PovUp) {
                     *players += 1;
                 }
-                if input.just_pressed(Button::PovDown) {
+                if input.repeated(Button::PovDown) {
                     *players -= 1;
                     if *players < 2 {
                         *players = 2;
@@ -226,22 +533,147 @@ impl App for TabooApp {
                 if input.just_pressed(Button::MenuL) {
                     *teams = !*teams;
                 }
-                if input.just_pressed(Button::MenuR) {
+                if input.just_pressed(Button::ActionV) {
+                    link.toggle_hosting();
+                }
+                if input.just_pressed(Button::BumperL) {
+                    *picker = Some(Box::new(
+                        FileBrowser::new(decks_dir()).with_extensions(&["csv", "txt"]),
+                    ));
+                }
+                if input.just_pressed(Button::BumperR) {
+                    *deck = None;
+                }
+                if input.just_pressed(Button::ActionH) {
+                    *self = Self::Joining {
+                        browser: Browser::start(LOBBY_GAME)
+                            .map_err(|e| println!("Couldn't look for games: {}", e))
+                            .ok(),
+                        hosts: vec![],
+                        selected: 0,
+                    };
+                } else if input.start_pressed() {
                     *self = Self::InGame {
-                        game: GameState::new(*players, *teams),
+                        game: Box::new(GameState::new(*players, *teams, deck.clone())),
                         turn: TurnState::ReadyingUp,
                         current_turn: if *teams {
                             CurrentTurn::Team(0)
                         } else {
                             CurrentTurn::Player { asker: 0, askee: 1 }
                         },
+                        link: std::mem::take(link),
                     };
                 }
             }
+            Self::Joining {
+                browser,
+                hosts,
+                selected,
+            } => {
+                if let Some(browser) = browser.as_mut() {
+                    *hosts = browser.poll();
+                }
+                *selected = (*selected).min(hosts.len().saturating_sub(1));
+
+                let mut ctx = frame.context();
+                ctx.set_fontsize(18.0);
+                ctx.offset(50, 50);
+                if browser.is_none() {
+                    ctx.text("Couldn't look for games on the network");
+                } else if hosts.is_empty() {
+                    ctx.text("Looking for games...");
+                }
+                for (i, host) in hosts.iter().enumerate() {
+                    ctx.set_color(if i == *selected {
+                        LinSrgb::new(255, 255, 255)
+                    } else {
+                        LinSrgb::new(255, 0, 0)
+                    });
+                    ctx.text(&format!("{} ({})", host.name, host.addr.ip()));
+                    ctx.offset(0, 20);
+                }
+                ctx.set_color(LinSrgb::new(255, 255, 255));
+                ctx.offset(0, 20);
+                ctx.text("A to join, B to go back");
+
+                if input.repeated(Button::PovUp) {
+                    *selected = selected.saturating_sub(1);
+                }
+                if input.repeated(Button::PovDown) {
+                    *selected = (*selected + 1).min(hosts.len().saturating_sub(1));
+                }
+                if input.just_pressed(Button::ActionB) {
+                    *self = Self::default();
+                } else if input.just_pressed(Button::ActionA) {
+                    if let Some(host) = hosts.get(*selected) {
+                        match Session::connect(host) {
+                            Ok(session) => {
+                                *self = Self::Watching {
+                                    session,
+                                    card: None,
+                                }
+                            }
+                            Err(e) => println!("Couldn't join {}: {}", host.name, e),
+                        }
+                    }
+                }
+            }
+            Self::Watching { session, card } => {
+                for message in session.poll() {
+                    if let Some(words) = message.strip_prefix("card ") {
+                        let mut words = words.split(',').map(str::to_string);
+                        *card = words.next().map(|word| Card {
+                            word,
+                            taboo: words.collect(),
+                        });
+                    } else if message == "wait" {
+                        *card = None;
+                    }
+                }
+
+                if !session.is_connected() {
+                    frame.text(
+                        "fonts/Ubuntu-B.ttf",
+                        50,
+                        50,
+                        18.0,
+                        LinSrgb::new(255, 255, 255),
+                        "Disconnected, B to go back",
+                    );
+                } else if let Some(card) = card.as_ref() {
+                    render_card(frame, card, 100, 140);
+                    frame.text(
+                        "fonts/Ubuntu-B.ttf",
+                        50,
+                        430,
+                        18.0,
+                        LinSrgb::new(255, 255, 255),
+                        "A to buzz a taboo word",
+                    );
+                    if input.just_pressed(Button::ActionA) {
+                        session.send("buzz");
+                        session.poll();
+                    }
+                } else {
+                    frame.text(
+                        "fonts/Ubuntu-B.ttf",
+                        50,
+                        50,
+                        18.0,
+                        LinSrgb::new(255, 255, 255),
+                        "Waiting for the next turn...",
+                    );
+                }
+
+                if input.just_pressed(Button::ActionB) {
+                    *self = Self::default();
+                }
+            }
             Self::InGame {
                 game,
                 turn,
                 current_turn,
+                link,
             } => match turn {
                 TurnState::ReadyingUp => {
                     let mut ctx = frame.context();
@@ -275,7 +707,7 @@ impl App for TabooApp {
                         } else {
                             ctx.set_color(LinSrgb::new(255, 0, 0));
                         }
-                        ctx.text(&format!("Team {}: {}", team, game.won_cards[team].len()));
+                        ctx.text(&format!("Team {}: {}", team, game.won_cards[team]));
                         ctx.offset(0, 20);
                     }
                     if input.just_pressed(Button::ActionA) {
@@ -286,9 +718,13 @@ impl App for TabooApp {
                         };
                     }
                     if input.just_pressed(Button::ActionB) {
+                        record_stats(&[("games", 1)]);
                         *self = Self::Menu {
                             players: game.num_players,
                             teams: game.teams,
+                            link: std::mem::take(link),
+                            deck: game.deck_file.clone(),
+                            picker: None,
                         };
                     }
                 }
@@ -297,7 +733,8 @@ impl App for TabooApp {
                     card,
                     results,
                 } => {
-                    let remaining = 60.0 - start_time.elapsed().as_secs_f32();
+                    let remaining = config().timed(TURN_TIME).as_secs_f32()
+                        - start_time.elapsed().as_secs_f32();
                     frame.text(
                         "fonts/Ubuntu-B.ttf",
                         50,
@@ -310,7 +747,7 @@ impl App for TabooApp {
                             results.iter().filter(|(_, x)| x.won()).count()
                         ),
                     );
-                    render_card(frame, card, 100, 140);
+                    render_card(frame, &game.cards[*card], 100, 140);
                     frame.text(
                         "fonts/Ubuntu-B.ttf",
                         50,
@@ -319,28 +756,39 @@ impl App for TabooApp {
                         LinSrgb::new(255, 255, 255),
                         "B discard, A got card",
                     );
-                    if remaining < 0.0 || input.just_pressed(Button::MenuR) {
-                        results.push((card.clone(), CardResult::Timeout));
-                        let mut cards2 = vec![];
-                        std::mem::swap(&mut cards2, results);
-                        let showing = cards2.len() - 1;
+                    if remaining < 0.0 || input.start_pressed() {
+                        results.push((*card, CardResult::Timeout));
+                        let results = std::mem::take(results);
+                        let showing = results.len() - 1;
+                        let mut confetti = Emitter::new("taboo", 300)
+                            .with_lifetime(Duration::from_millis(1500), Duration::from_millis(2500))
+                            .with_speed(250.0, 450.0)
+                            .with_direction(-std::f32::consts::FRAC_PI_2, 0.5)
+                            .with_gravity(300.0)
+                            .with_colors(&CONFETTI)
+                            .with_size(4);
+                        let won = results.iter().filter(|(_, x)| x.won()).count();
+                        confetti.burst(won * 30, frame.width() as f32 / 2.0, frame.height() as f32);
                         *turn = TurnState::TurnEnded {
-                            results: cards2,
+                            results,
                             showing,
+                            confetti: Box::new(confetti),
                         };
                     } else if input.just_pressed(Button::ActionA) {
                         // Guessed the card
-                        let mut next_card = game.draw_card();
-                        std::mem::swap(&mut next_card, card);
-                        results.push((next_card, CardResult::Won));
-                    } else if input.just_pressed(Button::ActionB) {
-                        // Give up/fail the card
-                        let mut next_card = game.draw_card();
-                        std::mem::swap(&mut next_card, card);
-                        results.push((next_card, CardResult::Discarded));
+                        let next_card = game.draw_card();
+                        results.push((std::mem::replace(card, next_card), CardResult::Won));
+                    } else if input.just_pressed(Button::ActionB) || buzzed {
+                        // Give up/fail the card, or the other team buzzed
+                        let next_card = game.draw_card();
+                        results.push((std::mem::replace(card, next_card), CardResult::Discarded));
                     }
                 }
-                TurnState::TurnEnded { results, showing } => {
+                TurnState::TurnEnded {
+                    results,
+                    showing,
+                    confetti,
+                } => {
                     frame.text(
                         "fonts/Ubuntu-B.ttf",
                         50,
@@ -375,7 +823,7 @@ impl App for TabooApp {
                             CardResult::Timeout => "Timed out",
                         },
                     );
-                    render_card(frame, &results[*showing].0, 100, 190);
+                    render_card(frame, &game.cards[results[*showing].0], 100, 190);
                     frame.text(
                         "fonts/Ubuntu-B.ttf",
                         50,
@@ -384,24 +832,42 @@ impl App for TabooApp {
                         LinSrgb::new(255, 255, 255),
                         "POV change cards, A to continue",
                     );
+                    confetti.update(input.delta());
+                    confetti.draw(frame);
 
-                    if input.just_pressed(Button::PovRight) {
+                    if input.repeated(Button::PovRight) {
                         *showing += 1;
                         if *showing >= results.len() {
                             *showing = results.len() - 1;
                         }
                     }
-                    if input.just_pressed(Button::PovLeft) {
+                    if input.repeated(Button::PovLeft) {
                         *showing = showing.saturating_sub(1);
                     }
                     if input.just_pressed(Button::ActionA) {
+                        unlock("taboo_first_turn");
+                        let won = results.iter().filter(|(_, x)| x.won()).count();
+                        if won >= 5 && !results.iter().any(|(_, x)| x.discarded()) {
+                            unlock("taboo_clean_sweep");
+                        }
+                        record_stats(&[
+                            ("turns", 1),
+                            (
+                                "cards_won",
+                                results.iter().filter(|(_, x)| x.won()).count() as u64,
+                            ),
+                            (
+                                "cards_discarded",
+                                results.iter().filter(|(_, x)| x.discarded()).count() as u64,
+                            ),
+                        ]);
                         for (card, card_result) in results.drain(..) {
                             if card_result.won() {
                                 match *current_turn {
-                                    CurrentTurn::Team(team) => game.won_cards[team].push(card),
+                                    CurrentTurn::Team(team) => game.won_cards[team] += 1,
                                     CurrentTurn::Player { asker, askee } => {
-                                        game.won_cards[asker].push(card.clone());
-                                        game.won_cards[askee].push(card);
+                                        game.won_cards[asker] += 1;
+                                        game.won_cards[askee] += 1;
                                     }
                                 }
                             } else {
@@ -414,12 +880,90 @@ impl App for TabooApp {
                 }
             },
         }
+
+        if let Self::InGame {
+            game, turn, link, ..
+        } = self
+        {
+            link.show(match turn {
+                TurnState::Playing { card, .. } => Some(&game.cards[*card]),
+                _ => None,
+            });
+        }
+        #[cfg(feature = "remote")]
+        rg35xx::remote::publish(self.remote_page());
+    }
+
+    fn activity(&self) -> Activity {
+        // Keep an eye on the network while there's someone to talk to
+        let network = Activity::Idle(Instant::now() + Duration::from_millis(100));
+        match self {
+            // The timer counts down in tenths of a second
+            Self::InGame {
+                turn: TurnState::Playing { .. },
+                ..
+            } => Activity::Busy,
+            Self::InGame {
+                turn: TurnState::TurnEnded { confetti, .. },
+                ..
+            } if confetti.is_active() => Activity::Busy,
+            Self::Menu { link, .. } | Self::InGame { link, .. } if link.is_active() => network,
+            // Long deck names scroll
+            Self::Menu {
+                picker: Some(picker),
+                ..
+            } if picker.is_animating() => {
+                Activity::Idle(Instant::now() + Duration::from_millis(200))
+            }
+            Self::Joining { .. } | Self::Watching { .. } => network,
+            _ => Activity::Waiting,
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        /// Every player asks every other player once before anyone repeats
+        #[test]
+        fn test_player_rotation(num_players in 2usize..12) {
+            let mut turn = CurrentTurn::Player { asker: 0, askee: 1 };
+            let mut seen = std::collections::HashSet::new();
+            for _ in 0..num_players * (num_players - 1) {
+                let CurrentTurn::Player { asker, askee } = turn else {
+                    unreachable!()
+                };
+                prop_assert!(asker < num_players && askee < num_players);
+                prop_assert_ne!(asker, askee);
+                prop_assert!(seen.insert((asker, askee)), "{:?} came round twice", (asker, askee));
+                turn.next(num_players);
+            }
+        }
+
+        #[test]
+        fn test_team_rotation(num_teams in 1usize..12, start in 0usize..12) {
+            let mut turn = CurrentTurn::Team(start % num_teams);
+            for i in 1..=num_teams {
+                turn.next(num_teams);
+                prop_assert!(matches!(turn, CurrentTurn::Team(team) if team == (start + i) % num_teams));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_deck() {
+        let cards = parse_deck("cat, meow, pet\n\nice cream,cold\ncat,dog\nx-ray\ndog\n");
+        let words = cards
+            .iter()
+            .map(|card| card.word.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(words, ["cat", "dog"]);
+        assert_eq!(cards[0].taboo, ["meow", "pet"]);
+        assert!(cards[1].taboo.is_empty());
+    }
 
     #[test]
     fn test_turn_increment() {
@@ -0,0 +1,552 @@
+//! Several kitchen timers counting down at once. They count by the clock, so
+//! they carry on while the app is closed and are saved when leaving it.
+//!
+//! Each timer's alarm is its own pattern, flashed on its row, beeped and
+//! rumbled, so two going off at once can be told apart.
+
+use std::collections::HashMap;
+
+use enum_iterator::{next_cycle, Sequence};
+use serde::{Deserialize, Serialize};
+
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::audio::{Playing, Sound};
+use rg35xx::config::config;
+use rg35xx::grid::Direction;
+use rg35xx::haptics::{self, Pattern, Pulse};
+use rg35xx::ninepatch::draw_panel;
+use rg35xx::savestate::SaveState;
+use rg35xx::time::{Duration, Instant, SystemTime};
+use rg35xx::widgets::{progress_bar, FocusRing, Strategy};
+
+pub const MANIFEST: Manifest =
+    Manifest::new("0.1.0").description("Kitchen timers, several at once");
+
+/// Picked from with START, since there's no keyboard
+const NAMES: [&str; 8] = [
+    "Timer", "Pasta", "Eggs", "Rice", "Tea", "Oven", "Bread", "Laundry",
+];
+/// As many as fit on the screen
+const MAX_TIMERS: usize = 6;
+const ROW_HEIGHT: usize = 60;
+/// Longest a timer can be set for, in milliseconds
+const MAX_DURATION: u64 = 100 * 3600 * 1000;
+const DEFAULT_DURATION: u64 = 5 * 60 * 1000;
+
+/// Milliseconds since 1970
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// `1:05:00` or `4:59`, rounding up so a timer only shows 0:00 when done
+fn format_remaining(ms: u64) -> String {
+    let seconds = ms.div_ceil(1000);
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// How a timer gets attention once it's done
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Sequence)]
+enum Alarm {
+    #[default]
+    Beeps,
+    Pulse,
+    Siren,
+}
+
+impl Alarm {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Beeps => "beeps",
+            Self::Pulse => "pulse",
+            Self::Siren => "siren",
+        }
+    }
+
+    /// Milliseconds on and off, repeating
+    fn pattern(&self) -> &'static [(bool, u64)] {
+        match self {
+            Self::Beeps => &[
+                (true, 150),
+                (false, 100),
+                (true, 150),
+                (false, 100),
+                (true, 150),
+                (false, 650),
+            ],
+            Self::Pulse => &[(true, 600), (false, 600)],
+            Self::Siren => &[(true, 80), (false, 80)],
+        }
+    }
+
+    /// Milliseconds before the pattern repeats
+    fn length(&self) -> u64 {
+        self.pattern().iter().map(|(_, ms)| ms).sum()
+    }
+
+    /// When each on part of the pattern starts and how long it lasts, in
+    /// milliseconds
+    fn on_parts(&self) -> impl Iterator<Item = (u64, u64)> {
+        self.pattern()
+            .iter()
+            .scan(0, |at, &(on, ms)| {
+                *at += ms;
+                Some((on, *at - ms, ms))
+            })
+            .filter(|&(on, _, _)| on)
+            .map(|(_, at, ms)| (at, ms))
+    }
+
+    /// The `round`th time through the pattern as sound, a tone while it's
+    /// on. The siren goes up and down between rounds.
+    fn sound(&self, round: u64) -> Sound {
+        let frequency = match self {
+            Self::Beeps => 1760.0,
+            Self::Pulse => 660.0,
+            Self::Siren if round.is_multiple_of(2) => 880.0,
+            Self::Siren => 1320.0,
+        };
+        let tones = self
+            .on_parts()
+            .map(|(at, ms)| {
+                let tone = Sound::tone(frequency, Duration::from_millis(ms), 0.3);
+                (Duration::from_millis(at), tone)
+            })
+            .collect::<Vec<_>>();
+        Sound::arrange(tones.iter().map(|(at, tone)| (*at, tone)))
+    }
+
+    /// Once through the pattern as rumble, buzzing while it's on
+    fn rumble(&self) -> Pattern {
+        let pulses = self
+            .on_parts()
+            .map(|(at, ms)| match self {
+                // Swelling and fading rather than on and off
+                Self::Pulse => Pulse::new(at, ms / 3, 0.8).with_envelope(ms / 3, ms / 3),
+                _ => Pulse::new(at, ms, 0.6),
+            })
+            .collect();
+        Pattern::from_pulses(pulses)
+    }
+
+    /// Whether the alarm is lit `ms` after it went off
+    fn is_on(&self, ms: u64) -> bool {
+        let mut at = ms % self.length();
+        for &(on, duration) in self.pattern() {
+            if at < duration {
+                return on;
+            }
+            at -= duration;
+        }
+        false
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Timer {
+    /// Stays the same as others are added and deleted, for focus
+    id: u32,
+    name: String,
+    /// What it counts down from, in milliseconds
+    duration: u64,
+    /// Left when it's stopped
+    remaining: u64,
+    /// When it goes off, in milliseconds since 1970, while it's running
+    ends_at: Option<u64>,
+    alarm: Alarm,
+}
+
+impl Timer {
+    fn new(id: u32, name: &str) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            duration: DEFAULT_DURATION,
+            remaining: DEFAULT_DURATION,
+            ends_at: None,
+            alarm: Alarm::default(),
+        }
+    }
+
+    fn remaining(&self, now: u64) -> u64 {
+        match self.ends_at {
+            Some(end) => end.saturating_sub(now),
+            None => self.remaining,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.ends_at.is_some()
+    }
+
+    /// Done and not dismissed yet
+    fn is_ringing(&self, now: u64) -> bool {
+        self.ends_at.is_some_and(|end| now >= end)
+    }
+
+    /// Starts or pauses it, or stops the alarm once it's done
+    fn toggle(&mut self, now: u64) {
+        if self.is_ringing(now) {
+            self.reset();
+            return;
+        }
+        match self.ends_at {
+            Some(end) => {
+                self.remaining = end.saturating_sub(now);
+                self.ends_at = None;
+            }
+            None if self.remaining > 0 => self.ends_at = Some(now + self.remaining),
+            None => (),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.ends_at = None;
+        self.remaining = self.duration;
+    }
+
+    /// Sets it `seconds` longer or shorter, if it isn't running
+    fn adjust(&mut self, seconds: i64) {
+        if self.is_running() {
+            return;
+        }
+        let duration = self.duration as i64 + seconds * 1000;
+        self.duration = (duration.max(1000) as u64).min(MAX_DURATION);
+        self.remaining = self.duration;
+    }
+
+    /// How far through it is, from 0 to 1
+    fn progress(&self, now: u64) -> f32 {
+        1.0 - self.remaining(now) as f32 / self.duration.max(1) as f32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Timers {
+    timers: Vec<Timer>,
+    next_id: u32,
+}
+
+impl Default for Timers {
+    fn default() -> Self {
+        Self {
+            timers: vec![Timer::new(0, NAMES[0])],
+            next_id: 1,
+        }
+    }
+}
+
+impl SaveState for Timers {
+    const NAME: &'static str = "timers";
+}
+
+impl Timers {
+    /// A new stopped timer named after the next of [`NAMES`], if there's room
+    fn add(&mut self) -> Option<u32> {
+        if self.timers.len() >= MAX_TIMERS {
+            return None;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let name = NAMES[self.timers.len() % NAMES.len()];
+        self.timers.push(Timer::new(id, name));
+        Some(id)
+    }
+
+    fn get_mut(&mut self, id: &str) -> Option<&mut Timer> {
+        self.timers
+            .iter_mut()
+            .find(|timer| timer.id.to_string() == id)
+    }
+}
+
+pub struct TimersApp {
+    timers: Timers,
+    focus: FocusRing,
+    /// Each ringing timer's round of its alarm pattern, and the sound of it
+    alarms: HashMap<u32, (u64, Playing)>,
+    /// Whether START was pressed while SELECT was held, which makes it the
+    /// chord for leaving rather than changing the alarm
+    select_chorded: bool,
+}
+
+impl Default for TimersApp {
+    fn default() -> Self {
+        let mut app = Self {
+            timers: Timers::load().unwrap_or_default(),
+            focus: FocusRing::new(Strategy::Ordered),
+            alarms: HashMap::new(),
+            select_chorded: false,
+        };
+        app.update_focus();
+        app
+    }
+}
+
+impl TimersApp {
+    fn update_focus(&mut self) {
+        self.focus
+            .set_ids(self.timers.timers.iter().map(|timer| timer.id.to_string()));
+    }
+
+    fn handle_input(&mut self, input: &Input, now: u64) {
+        // Left and right set the time, so only up and down move focus
+        for (button, direction) in [
+            (Button::PovUp, Direction::Up),
+            (Button::PovDown, Direction::Down),
+        ] {
+            if input.repeated(button) {
+                self.focus.step(direction);
+            }
+        }
+        if input.just_pressed(Button::ActionV) {
+            if let Some(id) = self.timers.add() {
+                self.update_focus();
+                self.focus.focus(&id.to_string());
+            }
+        }
+        let Some(id) = self.focus.focused().map(str::to_string) else {
+            return;
+        };
+        if input.just_pressed(Button::ActionH) {
+            self.timers
+                .timers
+                .retain(|timer| timer.id.to_string() != id);
+            self.update_focus();
+            return;
+        }
+        let Some(timer) = self.timers.get_mut(&id) else {
+            return;
+        };
+        if input.just_pressed(Button::ActionA) {
+            timer.toggle(now);
+        }
+        if input.just_pressed(Button::ActionB) {
+            timer.reset();
+        }
+        for (button, seconds) in [
+            (Button::PovLeft, -60),
+            (Button::PovRight, 60),
+            (Button::BumperL, -10),
+            (Button::BumperR, 10),
+        ] {
+            if input.repeated(button) {
+                timer.adjust(seconds);
+            }
+        }
+        if input.start_pressed() {
+            let current = NAMES.iter().position(|&name| name == timer.name);
+            timer.name = NAMES[current.map_or(0, |i| (i + 1) % NAMES.len())].to_string();
+        }
+        // On letting go, since SELECT is pressed first for SELECT+START
+        if input.just_pressed(Button::MenuL) {
+            self.select_chorded = input.pressed(Button::MenuR);
+        }
+        if input.pressed(Button::MenuL) && input.pressed(Button::MenuR) {
+            self.select_chorded = true;
+        }
+        if input.just_released(Button::MenuL) && !self.select_chorded {
+            timer.alarm = next_cycle(&timer.alarm);
+        }
+    }
+
+    /// Beeps and rumbles each ringing timer's alarm as each round of its
+    /// pattern starts, and silences the ones dismissed or deleted
+    fn ring(&mut self, ctx: &AppContext, now: u64) {
+        let mut alarms = HashMap::new();
+        for timer in &self.timers.timers {
+            let Some(end) = timer.ends_at.filter(|&end| now >= end) else {
+                continue;
+            };
+            let round = (now - end) / timer.alarm.length();
+            let playing = match self.alarms.remove(&timer.id) {
+                Some((last, playing)) if last == round => playing,
+                _ => {
+                    haptics::play(&timer.alarm.rumble());
+                    ctx.play(&timer.alarm.sound(round))
+                }
+            };
+            alarms.insert(timer.id, (round, playing));
+        }
+        for (_, playing) in std::mem::replace(&mut self.alarms, alarms).into_values() {
+            playing.stop();
+        }
+    }
+
+    fn draw_timer(&self, frame: &mut Frame, timer: &Timer, y: usize, now: u64) {
+        let colors = config().theme.colors();
+        let (left, width) = (40, frame.width().saturating_sub(80));
+        let ringing = timer.is_ringing(now);
+        let lit = ringing
+            && timer
+                .alarm
+                .is_on(now.saturating_sub(timer.ends_at.unwrap_or(now)));
+        if lit {
+            frame.fill_rect(left, y, width, ROW_HEIGHT - 6, colors.text);
+        } else if self.focus.is_focused(&timer.id.to_string()) {
+            draw_panel(frame, left, y, width, ROW_HEIGHT - 6);
+        }
+        let color = if lit {
+            colors.background
+        } else if timer.is_running() {
+            colors.selected
+        } else {
+            colors.text
+        };
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            left + 12,
+            y + 28,
+            24.0,
+            color,
+            &timer.name,
+        );
+        let state = if ringing {
+            "done"
+        } else if timer.is_running() {
+            "running"
+        } else if timer.remaining < timer.duration {
+            "paused"
+        } else {
+            ""
+        };
+        let details = format!("{} alarm  {}", timer.alarm.name(), state);
+        frame.text(
+            "fonts/Ubuntu-B-12.fnt",
+            left + 160,
+            y + 26,
+            12.0,
+            colors.dim,
+            &details,
+        );
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            (left + width).saturating_sub(130),
+            y + 30,
+            28.0,
+            color,
+            &format_remaining(timer.remaining(now)),
+        );
+        progress_bar(
+            frame,
+            left + 12,
+            y + 40,
+            width.saturating_sub(24),
+            6,
+            timer.progress(now),
+        );
+    }
+}
+
+impl App for TimersApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let now = now_ms();
+        self.handle_input(ctx.input, now);
+        self.ring(ctx, now);
+        let frame = &mut *ctx.frame;
+
+        let colors = config().theme.colors();
+        frame.fill_rect(0, 0, frame.width(), frame.height(), colors.background);
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            40,
+            50,
+            36.0,
+            colors.selected,
+            "Timers",
+        );
+        for (i, timer) in self.timers.timers.iter().enumerate() {
+            self.draw_timer(frame, timer, 70 + i * ROW_HEIGHT, now);
+        }
+        if self.timers.timers.is_empty() {
+            frame.text("fonts/Ubuntu-B.ttf", 40, 110, 18.0, colors.dim, "No timers");
+        }
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            40,
+            frame.height() - 35,
+            16.0,
+            colors.dim,
+            "A start/pause  B reset  X add  Y delete  left/right \u{b1}1m  L1/R1 \u{b1}10s",
+        );
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            40,
+            frame.height() - 15,
+            16.0,
+            colors.dim,
+            "START name  SELECT alarm",
+        );
+    }
+
+    /// Redrawn every second while counting, and for the flashing once done
+    fn activity(&self) -> Activity {
+        let now = now_ms();
+        let timers = &self.timers.timers;
+        if timers.iter().any(|timer| timer.is_ringing(now)) {
+            return Activity::Busy;
+        }
+        // Until the soonest shown second changes
+        let next = timers
+            .iter()
+            .filter(|timer| timer.is_running())
+            .map(|timer| timer.remaining(now) % 1000)
+            .min();
+        match next {
+            Some(ms) => Activity::Idle(Instant::now() + Duration::from_millis(ms.max(1))),
+            None => Activity::Waiting,
+        }
+    }
+
+    fn on_exit(&mut self) {
+        for (_, (_, playing)) in self.alarms.drain() {
+            playing.stop();
+        }
+        if let Err(e) = self.timers.save() {
+            println!("Couldn't save the timers: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_timer() {
+        let mut timer = Timer::new(0, "Tea");
+        timer.adjust(-4 * 60 - 30);
+        assert_eq!(format_remaining(timer.remaining(0)), "0:30");
+        timer.toggle(1000);
+        timer.adjust(60);
+        assert_eq!(timer.duration, 30_000);
+        // Paused part way through, then started again
+        timer.toggle(11_000);
+        assert_eq!(timer.remaining(50_000), 20_000);
+        timer.toggle(60_000);
+        assert!(!timer.is_ringing(79_999));
+        assert!(timer.is_ringing(80_000));
+        assert_eq!(timer.progress(90_000), 1.0);
+        timer.toggle(90_000);
+        assert_eq!(timer.remaining(90_000), 30_000);
+        assert_eq!(format_remaining(3_600_500), "1:00:01");
+
+        assert!(Alarm::Beeps.is_on(1300));
+        assert!(!Alarm::Beeps.is_on(1200));
+        // Sounding and rumbling while lit, up to the last beep
+        let ms = Duration::from_millis;
+        assert_eq!(Alarm::Beeps.rumble().length(), ms(650));
+        assert_eq!(Alarm::Pulse.rumble().length(), ms(600));
+        assert_eq!(Alarm::Siren.sound(0).duration(), ms(80));
+    }
+}
@@ -0,0 +1,526 @@
+//! One-time codes for two-factor logins (RFC 6238), worked out from each
+//! account's secret and the clock. The codes are only right if the device's
+//! clock is; time zones don't matter, as they count from 1970 in UTC.
+//!
+//! Secrets are typed in with the D-pad or imported from `totp.txt` next to the
+//! binary, one `otpauth://totp/...` link or `name = SECRET` line each. They're
+//! kept in the current profile encrypted with a PIN asked for on start; see
+//! [`rg35xx::storage::encrypted`].
+
+use enum_iterator::all;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::config::config;
+use rg35xx::ninepatch::draw_panel;
+use rg35xx::profiles;
+use rg35xx::savestate::SaveState;
+use rg35xx::storage::encrypted::{self, Key, UnlockEvent, UnlockPrompt};
+use rg35xx::time::{Duration, Instant, SystemTime};
+use rg35xx::url::{percent_decode, percent_encode, query_decode};
+use rg35xx::widgets::{Gauge, Keyboard, KeyboardEvent, BASE32_KEYS, TEXT_KEYS};
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0").description("Two-factor login codes");
+
+const IMPORT_FILE: &str = "totp.txt";
+const ROW_HEIGHT: usize = 60;
+const VISIBLE_ROWS: usize = 6;
+
+/// Where the accounts are kept, encrypted
+fn vault_path() -> std::path::PathBuf {
+    profiles::dir().join("totp.vault")
+}
+
+/// Milliseconds since 1970
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// The bytes in base32 `text`, ignoring case, spaces, dashes and padding
+fn decode_base32(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let (mut bits, mut count) = (0u32, 0);
+    for c in text.chars().filter(|c| !matches!(c, ' ' | '-' | '=')) {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return None,
+        };
+        bits = (bits << 5) | value;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+            bits &= (1 << count) - 1;
+        }
+    }
+    (!bytes.is_empty()).then_some(bytes)
+}
+
+/// The RFC 4226 code for `counter`, `digits` long
+fn hotp(key: &[u8], counter: u64, digits: u32) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0xf) as usize;
+    let code = u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+    code % 10u32.pow(digits)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Account {
+    name: String,
+    /// In base32, as it's usually given
+    secret: String,
+    digits: u32,
+    /// Seconds each code lasts
+    period: u64,
+}
+
+impl Account {
+    fn new(name: &str, secret: &str) -> Option<Self> {
+        let account = Self {
+            name: name.trim().to_string(),
+            secret: secret.to_ascii_uppercase().replace([' ', '-', '='], ""),
+            digits: 6,
+            period: 30,
+        };
+        account.is_valid().then_some(account)
+    }
+
+    fn is_valid(&self) -> bool {
+        decode_base32(&self.secret).is_some() && (6..=8).contains(&self.digits) && self.period > 0
+    }
+
+    /// From an `otpauth://totp/Issuer:name?secret=...` link, as in the QR
+    /// codes sites show. Only SHA-1 codes are supported, as most are.
+    fn from_uri(uri: &str) -> Option<Self> {
+        let rest = uri.strip_prefix("otpauth://totp/")?;
+        let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut account = Self {
+            name: percent_decode(label),
+            secret: String::new(),
+            digits: 6,
+            period: 30,
+        };
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = query_decode(value);
+            match key {
+                "secret" => account.secret = Self::new("", &value)?.secret,
+                "digits" => account.digits = value.parse().ok()?,
+                "period" => account.period = value.parse().ok()?,
+                "algorithm" if !value.eq_ignore_ascii_case("SHA1") => return None,
+                _ => (),
+            }
+        }
+        account.is_valid().then_some(account)
+    }
+
+    fn to_uri(&self) -> String {
+        format!(
+            "otpauth://totp/{}?secret={}&digits={}&period={}",
+            percent_encode(&self.name),
+            self.secret,
+            self.digits,
+            self.period
+        )
+    }
+
+    /// A line of the import file: a link, or `name = SECRET`
+    fn from_line(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.starts_with("otpauth://") {
+            return Self::from_uri(line);
+        }
+        let (name, secret) = line.split_once('=')?;
+        Self::new(name, secret)
+    }
+
+    /// The code for `unix_seconds`, padded with zeros and split in two to read
+    fn code(&self, unix_seconds: u64) -> String {
+        let key = decode_base32(&self.secret).unwrap_or_default();
+        let code = hotp(&key, unix_seconds / self.period, self.digits);
+        let code = format!("{:0width$}", code, width = self.digits as usize);
+        let (first, second) = code.split_at(code.len() / 2);
+        format!("{} {}", first, second)
+    }
+
+    /// Seconds until the code changes
+    fn remaining(&self, unix_seconds: u64) -> u64 {
+        self.period - unix_seconds % self.period
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Vault {
+    accounts: Vec<Account>,
+}
+
+/// Where the accounts were kept before they were encrypted, read once to
+/// move them over
+impl SaveState for Vault {
+    const NAME: &'static str = "totp";
+}
+
+impl Vault {
+    /// Adds the accounts in an import file that aren't here already,
+    /// returning how many were added and how many lines couldn't be read
+    fn import(&mut self, text: &str) -> (usize, usize) {
+        let (mut added, mut skipped) = (0, 0);
+        let lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        for line in lines {
+            match Account::from_line(line) {
+                Some(account) if self.accounts.iter().any(|a| a.secret == account.secret) => (),
+                Some(account) => {
+                    self.accounts.push(account);
+                    added += 1;
+                }
+                None => skipped += 1,
+            }
+        }
+        (added, skipped)
+    }
+
+    /// A link for each account, which [`Vault::import`] reads back
+    fn to_text(&self) -> String {
+        self.accounts
+            .iter()
+            .map(|account| account.to_uri() + "\n")
+            .collect()
+    }
+}
+
+enum Mode {
+    /// Waiting for the PIN
+    Locked(UnlockPrompt),
+    List,
+    /// Typing the name of a new account
+    Naming(Keyboard),
+    /// Typing its secret, once it's named
+    Secret(String, Keyboard),
+    /// Y was pressed once and needs pressing again
+    ConfirmDelete,
+}
+
+pub struct TotpApp {
+    vault: Vault,
+    /// Set once it's unlocked
+    key: Option<Key>,
+    selected: usize,
+    mode: Mode,
+    /// Shown along the bottom until the next button press
+    message: Option<String>,
+}
+
+impl Default for TotpApp {
+    fn default() -> Self {
+        Self {
+            vault: Vault::default(),
+            key: None,
+            selected: 0,
+            mode: Mode::Locked(UnlockPrompt::new(&vault_path(), "the authenticator")),
+            message: None,
+        }
+    }
+}
+
+impl TotpApp {
+    fn save(&mut self) {
+        let Some(key) = &self.key else {
+            return;
+        };
+        let text = self.vault.to_text();
+        if let Err(e) = encrypted::write(&vault_path(), key, text.as_bytes()) {
+            self.message = Some(format!("Couldn't save: {}", e));
+        }
+    }
+
+    fn unlocked(&mut self, key: Key, bytes: Option<Vec<u8>>) {
+        self.key = Some(key);
+        self.mode = Mode::List;
+        match bytes {
+            Some(bytes) => {
+                self.vault.import(&String::from_utf8_lossy(&bytes));
+            }
+            // A new vault, which takes over any accounts saved before
+            // encryption
+            None => {
+                if let Some(old) = Vault::load() {
+                    self.vault = old;
+                }
+                self.save();
+                Vault::clear();
+            }
+        }
+    }
+
+    fn import(&mut self) {
+        let path = rg35xx::storage::base_dir().join(IMPORT_FILE);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                self.message = Some(format!("Couldn't read {}: {}", IMPORT_FILE, e));
+                return;
+            }
+        };
+        let (added, skipped) = self.vault.import(&text);
+        self.message = Some(match skipped {
+            0 => format!("Added {}. {} can be deleted now", added, IMPORT_FILE),
+            _ => format!("Added {}, couldn't read {} lines", added, skipped),
+        });
+        self.save();
+    }
+
+    fn handle_input(&mut self, input: &Input) {
+        let any_pressed = all::<Button>().any(|button| input.just_pressed(button));
+        if any_pressed {
+            self.message = None;
+        }
+        match &mut self.mode {
+            // Handled as it's drawn
+            Mode::Locked(_) => (),
+            Mode::List => {
+                let count = self.vault.accounts.len();
+                if input.repeated(Button::PovUp) {
+                    self.selected = self.selected.saturating_sub(1);
+                }
+                if input.repeated(Button::PovDown) {
+                    self.selected = (self.selected + 1).min(count.saturating_sub(1));
+                }
+                if input.just_pressed(Button::ActionV) {
+                    self.mode = Mode::Naming(Keyboard::new(&TEXT_KEYS).with_max_len(24));
+                }
+                if input.just_pressed(Button::ActionH) && count > 0 {
+                    self.message = Some("Press Y again to delete".to_string());
+                    self.mode = Mode::ConfirmDelete;
+                }
+                if input.start_pressed() {
+                    self.import();
+                }
+            }
+            Mode::Naming(keyboard) => match keyboard.update(input) {
+                Some(KeyboardEvent::Done(name)) if !name.trim().is_empty() => {
+                    let keyboard = Keyboard::new(&BASE32_KEYS).with_max_len(64);
+                    self.mode = Mode::Secret(name, keyboard);
+                }
+                Some(KeyboardEvent::Done(_)) => (),
+                Some(KeyboardEvent::Cancelled) => self.mode = Mode::List,
+                None => (),
+            },
+            Mode::Secret(name, keyboard) => match keyboard.update(input) {
+                Some(KeyboardEvent::Done(secret)) => match Account::new(name, &secret) {
+                    Some(account) => {
+                        self.vault.accounts.push(account);
+                        self.selected = self.vault.accounts.len() - 1;
+                        self.mode = Mode::List;
+                        self.save();
+                    }
+                    None => self.message = Some("That isn't a base32 secret".to_string()),
+                },
+                Some(KeyboardEvent::Cancelled) => self.mode = Mode::List,
+                None => (),
+            },
+            Mode::ConfirmDelete => {
+                if input.just_pressed(Button::ActionH) {
+                    self.vault.accounts.remove(self.selected);
+                    self.selected = self
+                        .selected
+                        .min(self.vault.accounts.len().saturating_sub(1));
+                    self.mode = Mode::List;
+                    self.save();
+                } else if any_pressed {
+                    self.mode = Mode::List;
+                }
+            }
+        }
+    }
+
+    fn draw_account(
+        &self,
+        frame: &mut Frame,
+        account: &Account,
+        y: usize,
+        selected: bool,
+        now: u64,
+    ) {
+        let colors = config().theme.colors();
+        let (left, width) = (40, frame.width() - 80);
+        if selected {
+            draw_panel(frame, left, y, width, ROW_HEIGHT - 6);
+        }
+        frame.text(
+            "fonts/Ubuntu-B-12.fnt",
+            left + 12,
+            y + 16,
+            12.0,
+            colors.dim,
+            &account.name,
+        );
+        let seconds = now / 1000;
+        let color = if selected {
+            colors.selected
+        } else {
+            colors.text
+        };
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            left + 12,
+            y + 46,
+            30.0,
+            color,
+            &account.code(seconds),
+        );
+        // Counts down to the next code
+        Gauge::new(0.0, account.period as f32).with_label("s").draw(
+            frame,
+            left + width - 36,
+            y + (ROW_HEIGHT - 6) / 2,
+            22,
+            account.remaining(seconds) as f32,
+        );
+    }
+
+    fn draw_list(&self, frame: &mut Frame, now: u64) {
+        let colors = config().theme.colors();
+        let first = self.selected.saturating_sub(VISIBLE_ROWS - 1);
+        let accounts = self.vault.accounts.iter().enumerate().skip(first);
+        for (row, (i, account)) in accounts.take(VISIBLE_ROWS).enumerate() {
+            self.draw_account(
+                frame,
+                account,
+                70 + row * ROW_HEIGHT,
+                i == self.selected,
+                now,
+            );
+        }
+        if self.vault.accounts.is_empty() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                40,
+                110,
+                18.0,
+                colors.dim,
+                "No accounts. Add one with X, or put them in totp.txt and press START",
+            );
+        }
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            40,
+            frame.height() - 15,
+            16.0,
+            colors.dim,
+            "X add  Y delete  START import totp.txt",
+        );
+    }
+}
+
+impl App for TotpApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        self.handle_input(input);
+
+        let colors = config().theme.colors();
+        if let Mode::Locked(prompt) = &mut self.mode {
+            match prompt.update(input, frame) {
+                Some(UnlockEvent::Unlocked(key, bytes)) => self.unlocked(key, bytes),
+                Some(UnlockEvent::Cancelled) => {
+                    self.message = Some("SELECT+START leaves".to_string());
+                    self.mode = Mode::Locked(UnlockPrompt::new(&vault_path(), "the authenticator"));
+                }
+                None => (),
+            }
+            if let Mode::Locked(_) = self.mode {
+                if let Some(message) = &self.message {
+                    frame.text(
+                        "fonts/Ubuntu-B.ttf",
+                        50,
+                        frame.height() - 50,
+                        18.0,
+                        colors.text,
+                        message,
+                    );
+                }
+                return;
+            }
+        }
+        frame.fill_rect(0, 0, frame.width(), frame.height(), colors.background);
+        let title = match &self.mode {
+            Mode::Naming(_) => "Name the account",
+            Mode::Secret(..) => "Type its secret",
+            _ => "Authenticator",
+        };
+        frame.text("fonts/Ubuntu-B.ttf", 40, 50, 36.0, colors.selected, title);
+        match &self.mode {
+            Mode::Locked(_) | Mode::List | Mode::ConfirmDelete => self.draw_list(frame, now_ms()),
+            Mode::Naming(keyboard) | Mode::Secret(_, keyboard) => keyboard.draw(frame, 40, 90),
+        }
+        if let Some(message) = &self.message {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                40,
+                frame.height() - 40,
+                16.0,
+                colors.text,
+                message,
+            );
+        }
+    }
+
+    /// Redrawn every second for the countdown
+    fn activity(&self) -> Activity {
+        match self.mode {
+            Mode::List | Mode::ConfirmDelete if !self.vault.accounts.is_empty() => {
+                let ms = 1000 - now_ms() % 1000;
+                Activity::Idle(Instant::now() + Duration::from_millis(ms))
+            }
+            _ => Activity::Waiting,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_totp() {
+        // The SHA-1 test vectors from RFC 6238
+        let secret = decode_base32("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap();
+        assert_eq!(secret, b"12345678901234567890");
+        let mut account = Account::new("test", "gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap();
+        account.digits = 8;
+        for (time, code) in [
+            (59, "9428 7082"),
+            (1111111109, "0708 1804"),
+            (1234567890, "8900 5924"),
+            (20000000000, "6535 3130"),
+        ] {
+            assert_eq!(account.code(time), code);
+        }
+        assert_eq!(account.remaining(59), 1);
+        assert!(decode_base32("GEZ1").is_none());
+
+        let mut vault = Vault::default();
+        let file = "# exported\n\
+            otpauth://totp/Example:alice%40example.com?secret=JBSWY3DPEHPK3PXP&issuer=Example\n\
+            otpauth://totp/x?secret=JBSWY3DPEHPK3PXP&algorithm=SHA256\n\
+            Mail = JBSW Y3DP EHPK 3PXP\n\
+            Bank = GEZDGNBVGY3TQOJQ";
+        assert_eq!(vault.import(file), (2, 1));
+        assert_eq!(vault.accounts[0].name, "Example:alice@example.com");
+        assert_eq!(vault.accounts[1].name, "Bank");
+
+        // Saved as links and read back the same
+        let mut reread = Vault::default();
+        assert_eq!(reread.import(&vault.to_text()), (2, 0));
+        assert_eq!(reread, vault);
+    }
+}
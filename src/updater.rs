@@ -0,0 +1,563 @@
+//! Updates the launcher without taking the SD card out. `update.txt` next to
+//! the binary says where releases are, one per line, and the Ed25519 public
+//! key they're signed with, in hex:
+//!
+//! ```text
+//! url = https://example.com/rg35xx/
+//! path = /mnt/usb/rg35xx/
+//! key = 3b6a27bc...
+//! ```
+//!
+//! Each place holds a `latest.txt` describing the newest release, and the
+//! binary it names:
+//!
+//! ```text
+//! version = 0.2.0
+//! sha256 = 5f0c...
+//! signature = 9c1e...
+//! file = rg35xx
+//! ```
+//!
+//! The signature covers the release's [`payload`](Release::payload), its
+//! version, hash and file name, so none of them can be changed, e.g. to
+//! pass an older build off as newer. A newer binary is checked against the
+//! hash and staged
+//! next to the running one, then [`apply_staged`] swaps it in the next time
+//! the launcher starts. The old binary is kept, and put back if the new one
+//! doesn't get as far as the menu.
+
+use std::path::{Path, PathBuf};
+
+use palette::LinSrgb;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use sha2::{Digest, Sha256};
+
+use rg35xx::app::{Activity, App, AppContext, Button, Manifest};
+use rg35xx::storage::base_dir;
+use rg35xx::tasks::{Task, Tasks};
+use rg35xx::time::{Duration, Instant};
+
+pub const MANIFEST: Manifest =
+    Manifest::new("0.1.0").description("Download and install launcher updates");
+
+/// How often to check on the background work
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Somewhere releases can be found
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Source {
+    /// A directory on a web server
+    Url(String),
+    /// A directory on a mounted USB stick or the SD card
+    Path(PathBuf),
+}
+
+impl Source {
+    fn fetch(&self, name: &str) -> Result<Vec<u8>, String> {
+        match self {
+            #[cfg(feature = "http")]
+            Self::Url(url) => {
+                rg35xx::http::get_bytes(&format!("{}/{}", url.trim_end_matches('/'), name))
+            }
+            #[cfg(not(feature = "http"))]
+            Self::Url(_) => Err("This build can't download, use a path instead".to_string()),
+            Self::Path(dir) => {
+                let path = dir.join(name);
+                std::fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Url(url) => url.clone(),
+            Self::Path(dir) => dir.display().to_string(),
+        }
+    }
+}
+
+/// What `update.txt` lists
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Settings {
+    /// Where to look, in order
+    sources: Vec<Source>,
+    /// Public keys a release may be signed with
+    keys: Vec<Vec<u8>>,
+}
+
+impl Settings {
+    fn load() -> Self {
+        Self::parse(&std::fs::read_to_string(base_dir().join("update.txt")).unwrap_or_default())
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut settings = Self::default();
+        let lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        for line in lines {
+            let (key, value) = line.split_once('=').unwrap_or((line, ""));
+            let value = value.trim();
+            match key.trim() {
+                "url" => settings.sources.push(Source::Url(value.into())),
+                "path" => settings.sources.push(Source::Path(value.into())),
+                "key" => match from_hex(value).filter(|key| key.len() == 32) {
+                    Some(key) => settings.keys.push(key),
+                    None => println!("Ignoring `{}` in update.txt: not a key", line),
+                },
+                _ => println!("Ignoring `{}` in update.txt", line),
+            }
+        }
+        settings
+    }
+}
+
+/// Bytes written as hex, or `None` if it isn't hex
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// What a `latest.txt` describes
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Release {
+    version: String,
+    /// Lowercase hex
+    sha256: String,
+    /// The [payload](Release::payload) signed with the release key
+    signature: Vec<u8>,
+    file: String,
+}
+
+impl Release {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut release = Self {
+            version: String::new(),
+            sha256: String::new(),
+            signature: vec![],
+            file: "rg35xx".to_string(),
+        };
+        for line in text.lines().map(str::trim) {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().to_string();
+            match key.trim() {
+                "version" => release.version = value,
+                "sha256" => release.sha256 = value.to_lowercase(),
+                "signature" => release.signature = from_hex(&value).unwrap_or_default(),
+                "file" => release.file = value,
+                _ => (),
+            }
+        }
+        if release.version.is_empty() {
+            return Err("latest.txt has no version".to_string());
+        }
+        if release.sha256.len() != 64 {
+            return Err("latest.txt has no sha256".to_string());
+        }
+        if release.signature.len() != 64 {
+            return Err("latest.txt isn't signed".to_string());
+        }
+        if release.file.contains('/') {
+            return Err("latest.txt names a file elsewhere".to_string());
+        }
+        Ok(release)
+    }
+
+    /// What the signature is over: the version, hash and file name, one per
+    /// line as in `latest.txt`
+    fn payload(&self) -> String {
+        format!(
+            "version = {}\nsha256 = {}\nfile = {}\n",
+            self.version, self.sha256, self.file
+        )
+    }
+
+    /// Checks it was signed with one of `keys`, before anything in it is
+    /// trusted
+    fn authenticate(&self, keys: &[Vec<u8>]) -> Result<(), String> {
+        let signed = keys.iter().any(|key| {
+            UnparsedPublicKey::new(&ED25519, key)
+                .verify(self.payload().as_bytes(), &self.signature)
+                .is_ok()
+        });
+        if !signed {
+            return Err("Not signed with a key in update.txt".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Whether `version` comes after `current`, comparing dotted numbers
+fn is_newer(version: &str, current: &str) -> bool {
+    let parse = |v: &str| {
+        v.trim_start_matches('v')
+            .split('.')
+            .map(|part| part.parse::<u64>().unwrap_or(0))
+            .collect::<Vec<_>>()
+    };
+    parse(version) > parse(current)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Where an update waits to be swapped in
+fn staged_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.with_extension("new"))
+}
+
+/// Checks `bytes` are the release, whole, and the release is signed with one
+/// of `keys`
+fn verify(release: &Release, bytes: &[u8], keys: &[Vec<u8>]) -> Result<(), String> {
+    release.authenticate(keys)?;
+    let hash = sha256_hex(bytes);
+    if hash != release.sha256 {
+        return Err(format!("Checksum mismatch, got {}", &hash[..12]));
+    }
+    Ok(())
+}
+
+/// Checks the release and writes `bytes` where [`apply_staged`] will find
+/// them
+fn stage(release: &Release, bytes: &[u8], keys: &[Vec<u8>]) -> Result<(), String> {
+    verify(release, bytes, keys)?;
+    let path = staged_path().ok_or("Can't find the launcher binary")?;
+    // Written under another name first, so a half-written file is never staged
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, bytes).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&partial, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| e.to_string())?;
+    }
+    std::fs::rename(&partial, &path).map_err(|e| e.to_string())
+}
+
+/// Written next to the binary when an update is swapped in, and removed by
+/// [`confirm_update`] once it's reached the menu
+fn trial_path(exe: &Path) -> PathBuf {
+    exe.with_extension("trial")
+}
+
+/// Puts the binary kept as `.old` back, after an update that didn't work
+fn roll_back(exe: &Path) -> bool {
+    let _ = std::fs::remove_file(trial_path(exe));
+    match std::fs::rename(exe.with_extension("old"), exe) {
+        Ok(()) => true,
+        Err(e) => {
+            println!("Couldn't put the previous binary back: {}", e);
+            false
+        }
+    }
+}
+
+/// Starts the binary at `exe` in place of this one with the same arguments,
+/// returning only if it couldn't
+#[cfg(unix)]
+fn restart(exe: &Path) -> std::io::Error {
+    use std::os::unix::process::CommandExt;
+    std::process::Command::new(exe)
+        .args(std::env::args_os().skip(1))
+        .exec()
+}
+
+/// Swaps in an update staged by the Updater app, keeping the old binary as
+/// `.old`, and restarts into it with the same arguments. If an update was
+/// installed last time and never reached the menu, puts the old binary back
+/// and restarts into that instead. Call this first thing in `main`; it only
+/// returns if there's nothing to do or it failed.
+pub fn apply_staged() {
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+    let trial = trial_path(&exe);
+    match std::fs::read_to_string(&trial).as_deref() {
+        // The update's first start, which confirm_update ends
+        Ok("installed") => {
+            if let Err(e) = std::fs::write(&trial, "started") {
+                println!("Couldn't note the update started: {}", e);
+            }
+        }
+        Ok(_) => {
+            println!("The update didn't start last time, going back to the previous binary");
+            if roll_back(&exe) {
+                #[cfg(unix)]
+                println!(
+                    "Couldn't restart into the previous binary: {}",
+                    restart(&exe)
+                );
+            }
+        }
+        Err(_) => (),
+    }
+
+    let staged = exe.with_extension("new");
+    if !staged.exists() {
+        return;
+    }
+    println!("Installing the update in {}", staged.display());
+    let old = exe.with_extension("old");
+    if let Err(e) = std::fs::rename(&exe, &old) {
+        println!("Couldn't move the old binary aside: {}", e);
+        return;
+    }
+    if let Err(e) = std::fs::rename(&staged, &exe) {
+        println!("Couldn't install the update: {}", e);
+        let _ = std::fs::rename(&old, &exe);
+        return;
+    }
+    if let Err(e) = std::fs::write(&trial, "installed") {
+        println!("Couldn't note the update was installed: {}", e);
+    }
+    #[cfg(unix)]
+    {
+        println!("Couldn't restart into the update: {}", restart(&exe));
+        roll_back(&exe);
+    }
+}
+
+/// Keeps an update [`apply_staged`] installed, now that it's shown the menu
+pub fn confirm_update() {
+    static CONFIRMED: std::sync::Once = std::sync::Once::new();
+    CONFIRMED.call_once(|| {
+        let Ok(exe) = std::env::current_exe() else {
+            return;
+        };
+        if std::fs::remove_file(trial_path(&exe)).is_ok() {
+            println!("The update started, keeping it");
+        }
+    });
+}
+
+/// Results from the background work
+enum Message {
+    Checked(Result<Option<(Source, Release)>, String>),
+    Staged(Result<String, String>),
+}
+
+enum State {
+    Working(&'static str),
+    UpToDate,
+    Available(Source, Release),
+    Staged(String),
+    Failed(String),
+}
+
+pub struct UpdaterApp {
+    state: State,
+    work: Option<Task<Message>>,
+    tasks: Tasks,
+}
+
+impl Default for UpdaterApp {
+    fn default() -> Self {
+        let mut app = Self {
+            state: State::UpToDate,
+            work: None,
+            tasks: Tasks::new(1),
+        };
+        app.check();
+        app
+    }
+}
+
+impl UpdaterApp {
+    /// Looks for a newer release in the background, trying each source until
+    /// one answers
+    fn check(&mut self) {
+        let Settings { sources, keys } = Settings::load();
+        if sources.is_empty() {
+            self.state = State::Failed("Add update.txt next to the launcher first".to_string());
+            return;
+        }
+        if keys.is_empty() {
+            self.state = State::Failed("Add the release key to update.txt first".to_string());
+            return;
+        }
+        let work = self.tasks.spawn(move || {
+            let mut errors = vec![];
+            for source in sources {
+                let release = source
+                    .fetch("latest.txt")
+                    .and_then(|text| Release::parse(&String::from_utf8_lossy(&text)))
+                    .and_then(|release| release.authenticate(&keys).map(|()| release));
+                match release {
+                    // Only a signed version is compared
+                    Ok(release) => {
+                        let newer = is_newer(&release.version, env!("CARGO_PKG_VERSION"));
+                        return Message::Checked(Ok(newer.then_some((source, release))));
+                    }
+                    Err(e) => errors.push(format!("{}: {}", source.describe(), e)),
+                }
+            }
+            Message::Checked(Err(errors.join("; ")))
+        });
+        self.state = State::Working("Checking for updates...");
+        self.work = Some(work);
+    }
+
+    fn download(&mut self, source: Source, release: Release) {
+        let keys = Settings::load().keys;
+        let work = self.tasks.spawn(move || {
+            let result = source
+                .fetch(&release.file)
+                .and_then(|bytes| stage(&release, &bytes, &keys))
+                .map(|()| release.version);
+            Message::Staged(result)
+        });
+        self.state = State::Working("Downloading...");
+        self.work = Some(work);
+    }
+
+    fn receive(&mut self) {
+        let Some(work) = self.work.as_mut() else {
+            return;
+        };
+        let message = match work.poll() {
+            Some(message) => message,
+            None if work.is_pending() => return,
+            None => Message::Checked(Err("The update stopped unexpectedly".to_string())),
+        };
+        self.work = None;
+        self.state = match message {
+            Message::Checked(Ok(Some((source, release)))) => State::Available(source, release),
+            Message::Checked(Ok(None)) => State::UpToDate,
+            Message::Staged(Ok(version)) => State::Staged(version),
+            Message::Checked(Err(e)) | Message::Staged(Err(e)) => State::Failed(e),
+        };
+    }
+}
+
+impl App for UpdaterApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        self.receive();
+        frame.fill_rect(0, 0, frame.width(), frame.height(), LinSrgb::new(0, 0, 0));
+
+        let mut ctx = frame.context();
+        ctx.offset(50, 50);
+        ctx.set_fontsize(36.0);
+        ctx.text("Updater");
+        ctx.set_fontsize(18.0);
+        ctx.offset(0, 40);
+        ctx.text(&format!("Installed: {}", env!("CARGO_PKG_VERSION")));
+        ctx.offset(0, 30);
+        match &self.state {
+            State::Working(what) => ctx.text(what),
+            State::UpToDate => {
+                ctx.text("Up to date");
+                ctx.offset(0, 30);
+                ctx.set_color(LinSrgb::new(180, 180, 180));
+                ctx.text("START to check again");
+            }
+            State::Available(source, release) => {
+                ctx.text(&format!("Version {} is available", release.version));
+                ctx.offset(0, 30);
+                ctx.set_color(LinSrgb::new(180, 180, 180));
+                ctx.text(&format!("From {}", source.describe()));
+                ctx.offset(0, 30);
+                ctx.text("A to download and install it");
+            }
+            State::Staged(version) => {
+                ctx.text(&format!("Version {} will be installed", version));
+                ctx.offset(0, 30);
+                ctx.set_color(LinSrgb::new(180, 180, 180));
+                ctx.text("the next time the launcher starts");
+            }
+            State::Failed(e) => {
+                ctx.text("Couldn't update");
+                ctx.offset(0, 30);
+                ctx.set_color(LinSrgb::new(180, 180, 180));
+                ctx.text(e);
+                ctx.offset(0, 30);
+                ctx.text("START to try again");
+            }
+        }
+
+        if self.work.is_some() {
+            return;
+        }
+        if input.start_pressed() {
+            self.check();
+        }
+        if input.just_pressed(Button::ActionA) {
+            if let State::Available(source, release) = &self.state {
+                self.download(source.clone(), release.clone());
+            }
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        if self.work.is_some() {
+            Activity::Idle(Instant::now() + POLL_INTERVAL)
+        } else {
+            Activity::Waiting
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    #[test]
+    fn test_release() {
+        let random = ring::rand::SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&random).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let hex = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        };
+        let settings = Settings::parse(&format!(
+            "path = /mnt/usb\nkey = {}\nkey = 12",
+            hex(pair.public_key().as_ref())
+        ));
+        assert_eq!(settings.sources, [Source::Path("/mnt/usb".into())]);
+        assert_eq!(settings.keys.len(), 1);
+
+        let hash = sha256_hex(b"launcher");
+        let payload = format!("version = 0.2.0\nsha256 = {}\nfile = rg35xx\n", hash);
+        let signature = hex(pair.sign(payload.as_bytes()).as_ref());
+        let text = format!(
+            "version = 0.2.0\nsha256 = {}\nsignature = {}\n",
+            hash, signature
+        );
+        let release = Release::parse(&text).unwrap();
+        assert_eq!(release.file, "rg35xx");
+        assert_eq!(release.payload(), payload);
+        assert!(verify(&release, b"launcher", &settings.keys).is_ok());
+        assert!(verify(&release, b"launcher", &[vec![0; 32]]).is_err());
+        assert!(verify(&release, b"launches", &settings.keys).is_err());
+        // Nothing signed can be changed, e.g. an old build's version raised
+        let raised = Release::parse(&text.replace("0.2.0", "9.0.0")).unwrap();
+        assert!(raised.authenticate(&settings.keys).is_err());
+        let renamed = Release::parse(&(text.clone() + "file = rg35xx.sh")).unwrap();
+        assert!(renamed.authenticate(&settings.keys).is_err());
+        assert!(Release::parse("version = 0.2.0").is_err());
+        assert!(Release::parse(&format!("version = 0.2.0\nsha256 = {}", hash)).is_err());
+        assert!(Release::parse(&(text + "file = ../x")).is_err());
+
+        assert!(is_newer("0.2.0", "0.1.9"));
+        assert!(is_newer("v0.10.0", "0.9.0"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1", "0.1.0"));
+    }
+}
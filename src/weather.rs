@@ -0,0 +1,837 @@
+//! Aviation weather: the latest METAR and TAF for each station listed in
+//! `weather.txt` next to the launcher binary, one ICAO code per line. Reports
+//! come from aviationweather.gov and the last copy of each is kept in
+//! `weather/` so there's something to show offline.
+//!
+//! METARs are decoded into wind, visibility, weather, clouds, temperature and
+//! pressure, with the flight category worked out the way the FAA does. TAFs
+//! are shown as they come.
+
+use std::path::PathBuf;
+
+use palette::LinSrgb;
+
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Manifest};
+use rg35xx::config::config;
+use rg35xx::storage::base_dir;
+use rg35xx::tasks::{Task, Tasks};
+use rg35xx::time::{Duration, Instant};
+use rg35xx::units::{AltitudeUnit, SpeedUnit};
+use rg35xx::widgets::{wrap, Reader};
+
+pub const MANIFEST: Manifest =
+    Manifest::new("0.1.0").description("METARs and TAFs for your airports");
+
+const METERS_PER_MILE: f32 = 1609.344;
+
+fn cache_path(station: &str, kind: &str) -> PathBuf {
+    base_dir()
+        .join("weather")
+        .join(format!("{}.{}", station, kind))
+}
+
+fn report_url(station: &str, kind: &str) -> String {
+    format!(
+        "https://aviationweather.gov/api/data/{}?ids={}&format=raw",
+        kind, station
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cover {
+    Few,
+    Scattered,
+    Broken,
+    Overcast,
+    /// Vertical visibility into fog or the like
+    Obscured,
+}
+
+impl Cover {
+    fn parse(code: &str) -> Option<Self> {
+        Some(match code {
+            "FEW" => Self::Few,
+            "SCT" => Self::Scattered,
+            "BKN" => Self::Broken,
+            "OVC" => Self::Overcast,
+            "VV" => Self::Obscured,
+            _ => return None,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Few => "few",
+            Self::Scattered => "scattered",
+            Self::Broken => "broken",
+            Self::Overcast => "overcast",
+            Self::Obscured => "obscured",
+        }
+    }
+
+    /// Whether a layer like this counts as the ceiling
+    fn is_ceiling(&self) -> bool {
+        matches!(self, Self::Broken | Self::Overcast | Self::Obscured)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Wind {
+    /// Degrees true, or `None` when it's variable
+    direction: Option<u32>,
+    knots: u32,
+    gust: Option<u32>,
+}
+
+impl Wind {
+    /// `27015G25KT`, `VRB03KT` or `05008MPS`
+    fn parse(token: &str) -> Option<Self> {
+        let (token, to_knots) = if let Some(t) = token.strip_suffix("KT") {
+            (t, 1.0)
+        } else if let Some(t) = token.strip_suffix("MPS") {
+            (t, 1.943_844)
+        } else if let Some(t) = token.strip_suffix("KMH") {
+            (t, 0.539_957)
+        } else {
+            return None;
+        };
+        let speeds = token.get(3..)?;
+        let direction = match token.get(..3)? {
+            "VRB" => None,
+            degrees => Some(degrees.parse().ok()?),
+        };
+        let (speed, gust) = match speeds.split_once('G') {
+            Some((speed, gust)) => (speed, Some(gust)),
+            None => (speeds, None),
+        };
+        let knots = |text: &str| {
+            let value = text.parse::<u32>().ok()?;
+            Some((value as f32 * to_knots).round() as u32)
+        };
+        Some(Self {
+            direction,
+            knots: knots(speed)?,
+            gust: match gust {
+                Some(gust) => Some(knots(gust)?),
+                None => None,
+            },
+        })
+    }
+
+    fn describe(&self) -> String {
+        if self.knots == 0 {
+            return "calm".to_string();
+        }
+        let unit = config().units.speed;
+        let speed = |knots: u32| unit.from_mps(SpeedUnit::Knots.to_mps(knots as f64));
+        let direction = match self.direction {
+            Some(degrees) => format!("{:03}\u{b0}", degrees),
+            None => "variable".to_string(),
+        };
+        let mut text = format!("{} at {:.0} {}", direction, speed(self.knots), unit.name());
+        if let Some(gust) = self.gust {
+            text += &format!(", gusting {:.0}", speed(gust));
+        }
+        text
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Visibility {
+    meters: f32,
+    /// As reported, e.g. `1 1/2 sm` or `800 m`
+    text: String,
+}
+
+/// `10`, `1/2` or `M1/4`, in statute miles
+fn parse_miles(text: &str) -> Option<f32> {
+    let text = text.trim_start_matches(['M', 'P']);
+    match text.split_once('/') {
+        Some((top, bottom)) => Some(top.parse::<f32>().ok()? / bottom.parse::<f32>().ok()?),
+        None => text.parse().ok(),
+    }
+}
+
+/// `M05` is -5
+fn parse_celsius(text: &str) -> Option<i32> {
+    match text.strip_prefix('M') {
+        Some(below) => below.parse::<i32>().ok().map(|t| -t),
+        None => text.parse().ok(),
+    }
+}
+
+const DESCRIPTORS: [(&str, &str); 8] = [
+    ("MI", "shallow"),
+    ("BC", "patches of"),
+    ("PR", "partial"),
+    ("DR", "drifting"),
+    ("BL", "blowing"),
+    ("SH", "showers of"),
+    ("TS", "thunderstorm"),
+    ("FZ", "freezing"),
+];
+const PHENOMENA: [(&str, &str); 22] = [
+    ("DZ", "drizzle"),
+    ("RA", "rain"),
+    ("SN", "snow"),
+    ("SG", "snow grains"),
+    ("IC", "ice crystals"),
+    ("PL", "ice pellets"),
+    ("GR", "hail"),
+    ("GS", "small hail"),
+    ("UP", "precipitation"),
+    ("BR", "mist"),
+    ("FG", "fog"),
+    ("FU", "smoke"),
+    ("VA", "volcanic ash"),
+    ("DU", "dust"),
+    ("SA", "sand"),
+    ("HZ", "haze"),
+    ("PY", "spray"),
+    ("PO", "dust whirls"),
+    ("SQ", "squalls"),
+    ("FC", "funnel cloud"),
+    ("SS", "sandstorm"),
+    ("DS", "duststorm"),
+];
+
+/// The words for each two letter code in a weather group like `+TSRA`, if
+/// that's what it is
+fn weather_words(code: &str) -> Option<Vec<&'static str>> {
+    let code = code.trim_start_matches(['+', '-']);
+    let code = code.strip_prefix("VC").unwrap_or(code);
+    if code.is_empty() || code.len() % 2 == 1 {
+        return None;
+    }
+    (0..code.len())
+        .step_by(2)
+        .map(|i| {
+            let pair = code.get(i..i + 2)?;
+            DESCRIPTORS
+                .iter()
+                .chain(&PHENOMENA)
+                .find(|(abbreviation, _)| *abbreviation == pair)
+                .map(|(_, word)| *word)
+        })
+        .collect()
+}
+
+/// `-SHRA` as `light showers of rain`
+fn describe_weather(code: &str) -> String {
+    let mut words = weather_words(code).unwrap_or_default();
+    // Showers on their own, usually nearby
+    if words.last() == Some(&"showers of") {
+        words.pop();
+        words.push("showers");
+    }
+    let text = words.join(" ");
+    if code.starts_with('+') {
+        format!("heavy {}", text)
+    } else if code.starts_with('-') {
+        format!("light {}", text)
+    } else if code.starts_with("VC") {
+        format!("{} nearby", text)
+    } else {
+        text
+    }
+}
+
+/// Worked out from the ceiling and visibility, best first, the FAA's way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Category {
+    Vfr,
+    Mvfr,
+    Ifr,
+    Lifr,
+}
+
+impl Category {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Vfr => "VFR",
+            Self::Mvfr => "MVFR",
+            Self::Ifr => "IFR",
+            Self::Lifr => "LIFR",
+        }
+    }
+
+    /// The colors charts use
+    fn color(&self) -> LinSrgb<u8> {
+        match self {
+            Self::Vfr => LinSrgb::new(0, 180, 0),
+            Self::Mvfr => LinSrgb::new(40, 100, 255),
+            Self::Ifr => LinSrgb::new(220, 0, 0),
+            Self::Lifr => LinSrgb::new(210, 0, 210),
+        }
+    }
+}
+
+/// What to draw for the conditions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Icon {
+    Clear,
+    PartlyCloudy,
+    Cloudy,
+    Rain,
+    Snow,
+    Thunder,
+    Fog,
+}
+
+/// A decoded METAR. Anything it doesn't understand, and the remarks, are
+/// left out.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Metar {
+    station: String,
+    /// `12 18:53Z`, the day of the month and time
+    time: String,
+    wind: Option<Wind>,
+    visibility: Option<Visibility>,
+    /// Groups like `-RA` and `BR`
+    weather: Vec<String>,
+    /// Layers and their heights in feet above the ground
+    clouds: Vec<(Cover, u32)>,
+    temperature: Option<i32>,
+    dewpoint: Option<i32>,
+    /// In hectopascals
+    pressure: Option<f32>,
+}
+
+impl Metar {
+    fn parse(text: &str) -> Option<Self> {
+        let mut tokens = text.split_whitespace().peekable();
+        // Some sources start with the report type
+        tokens.next_if(|t| matches!(*t, "METAR" | "SPECI"));
+        let mut metar = Self {
+            station: tokens.next()?.to_string(),
+            ..Self::default()
+        };
+        // The `1` of `1 1/2SM`
+        let mut whole_miles: Option<f32> = None;
+        for token in tokens {
+            let digits = digits_only(token);
+            if token == "RMK" {
+                break;
+            } else if token.len() == 7 && token.ends_with('Z') && digits_only(&token[..6]) {
+                metar.time = format!("{} {}:{}Z", &token[..2], &token[2..4], &token[4..6]);
+            } else if let Some(wind) = Wind::parse(token) {
+                metar.wind = Some(wind);
+            } else if token == "CAVOK" {
+                metar.visibility = Some(Visibility {
+                    meters: 10_000.0,
+                    text: "10+ km".to_string(),
+                });
+            } else if let Some(miles) = token.strip_suffix("SM").and_then(parse_miles) {
+                let whole = whole_miles.take();
+                let prefix = whole.map_or(String::new(), |w| format!("{} ", w));
+                metar.visibility = Some(Visibility {
+                    meters: (whole.unwrap_or(0.0) + miles) * METERS_PER_MILE,
+                    text: format!("{}{} sm", prefix, token.trim_end_matches("SM")),
+                });
+            } else if digits && token.len() == 1 {
+                whole_miles = token.parse().ok();
+            } else if digits && token.len() == 4 && metar.visibility.is_none() {
+                // 9999 is 10 km or more
+                metar.visibility = Some(match token {
+                    "9999" => Visibility {
+                        meters: 10_000.0,
+                        text: "10+ km".to_string(),
+                    },
+                    _ => Visibility {
+                        meters: token.parse().ok()?,
+                        text: format!("{} m", token.trim_start_matches('0')),
+                    },
+                });
+            } else if let Some(layer) = parse_layer(token) {
+                metar.clouds.push(layer);
+            } else if let Some((temperature, dewpoint)) = token
+                .split_once('/')
+                .filter(|(t, _)| parse_celsius(t).is_some())
+            {
+                metar.temperature = parse_celsius(temperature);
+                metar.dewpoint = parse_celsius(dewpoint);
+            } else if let Some(inches) = token.strip_prefix('A').filter(|t| digits_only(t)) {
+                let inches = inches.parse::<f32>().ok()? / 100.0;
+                metar.pressure = Some(inches * 33.8639);
+            } else if let Some(hpa) = token.strip_prefix('Q').filter(|t| digits_only(t)) {
+                metar.pressure = hpa.parse().ok();
+            } else if weather_words(token).is_some() {
+                metar.weather.push(token.to_string());
+            }
+        }
+        Some(metar)
+    }
+
+    /// The lowest broken or overcast layer, in feet
+    fn ceiling(&self) -> Option<u32> {
+        self.clouds
+            .iter()
+            .filter(|(cover, _)| cover.is_ceiling())
+            .map(|&(_, height)| height)
+            .min()
+    }
+
+    fn category(&self) -> Category {
+        let by_ceiling = match self.ceiling() {
+            Some(feet) if feet < 500 => Category::Lifr,
+            Some(feet) if feet < 1000 => Category::Ifr,
+            Some(feet) if feet <= 3000 => Category::Mvfr,
+            _ => Category::Vfr,
+        };
+        let miles = self
+            .visibility
+            .as_ref()
+            .map_or(10.0, |v| v.meters / METERS_PER_MILE);
+        let by_visibility = if miles < 1.0 {
+            Category::Lifr
+        } else if miles < 3.0 {
+            Category::Ifr
+        } else if miles <= 5.0 {
+            Category::Mvfr
+        } else {
+            Category::Vfr
+        };
+        by_ceiling.max(by_visibility)
+    }
+
+    fn icon(&self) -> Icon {
+        let has = |codes: &[&str]| {
+            self.weather.iter().any(|weather| {
+                let weather = weather.trim_start_matches(['+', '-']);
+                codes.iter().any(|code| weather.contains(code))
+            })
+        };
+        if has(&["TS"]) {
+            Icon::Thunder
+        } else if has(&["SN", "SG", "PL", "GR", "GS", "IC"]) {
+            Icon::Snow
+        } else if has(&["RA", "DZ", "SH", "UP"]) {
+            Icon::Rain
+        } else if has(&["FG", "BR", "HZ", "FU", "DU", "SA"]) {
+            Icon::Fog
+        } else if self.ceiling().is_some() {
+            Icon::Cloudy
+        } else if !self.clouds.is_empty() {
+            Icon::PartlyCloudy
+        } else {
+            Icon::Clear
+        }
+    }
+
+    /// Each decoded part as a label and its value
+    fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut lines = vec![];
+        if let Some(wind) = &self.wind {
+            lines.push(("Wind", wind.describe()));
+        }
+        if let Some(visibility) = &self.visibility {
+            lines.push(("Visibility", visibility.text.clone()));
+        }
+        if !self.weather.is_empty() {
+            let weather = self.weather.iter().map(|w| describe_weather(w));
+            lines.push(("Weather", weather.collect::<Vec<_>>().join(", ")));
+        }
+        let unit = config().units.altitude;
+        let clouds = self
+            .clouds
+            .iter()
+            .map(|&(cover, feet)| {
+                let height = unit.from_meters(AltitudeUnit::Feet.to_meters(feet as f64));
+                format!("{} {:.0} {}", cover.name(), height, unit.name())
+            })
+            .collect::<Vec<_>>();
+        lines.push((
+            "Clouds",
+            match clouds.is_empty() {
+                true => "none reported".to_string(),
+                false => clouds.join(", "),
+            },
+        ));
+        if let Some(temperature) = self.temperature {
+            let mut text = format!("{}\u{b0}C", temperature);
+            if let Some(dewpoint) = self.dewpoint {
+                text += &format!(", dew point {}\u{b0}C", dewpoint);
+            }
+            lines.push(("Temperature", text));
+        }
+        if let Some(hpa) = self.pressure {
+            let text = format!("{:.0} hPa, {:.2} inHg", hpa, hpa / 33.8639);
+            lines.push(("Pressure", text));
+        }
+        lines
+    }
+}
+
+fn digits_only(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())
+}
+
+/// `BKN045`, `OVC008CB` or `VV002`
+fn parse_layer(token: &str) -> Option<(Cover, u32)> {
+    let split = token.find(|c: char| c.is_ascii_digit())?;
+    let cover = Cover::parse(&token[..split])?;
+    let hundreds = token.get(split..split + 3)?.parse::<u32>().ok()?;
+    Some((cover, hundreds * 100))
+}
+
+fn disc(frame: &mut Frame, x: usize, y: usize, radius: usize, color: LinSrgb<u8>) {
+    let r = radius as isize;
+    for dy in -r..=r {
+        for dx in -r..=r {
+            if dx * dx + dy * dy <= r * r {
+                let (px, py) = (x as isize + dx, y as isize + dy);
+                if px >= 0 && py >= 0 {
+                    frame.put_pixel(px as usize, py as usize, color);
+                }
+            }
+        }
+    }
+}
+
+/// Draws `icon` in about 100 pixels square, centered on `x`, `y`
+fn draw_icon(frame: &mut Frame, icon: Icon, x: usize, y: usize) {
+    let sun = LinSrgb::new(255, 200, 0);
+    let grey = LinSrgb::new(170, 170, 180);
+    let dark = LinSrgb::new(100, 100, 115);
+    let sun_at = |frame: &mut Frame, x: usize, y: usize| {
+        for i in 0..8 {
+            let (sin, cos) = (i as f32 * std::f32::consts::FRAC_PI_4).sin_cos();
+            let (rx, ry) = (x as f32 + cos * 34.0, y as f32 + sin * 34.0);
+            disc(frame, rx as usize, ry as usize, 4, sun);
+        }
+        disc(frame, x, y, 24, sun);
+    };
+    let cloud_at = |frame: &mut Frame, x: usize, y: usize, color| {
+        disc(frame, x - 22, y + 6, 16, color);
+        disc(frame, x + 22, y + 6, 16, color);
+        disc(frame, x, y - 6, 22, color);
+        frame.fill_rect(x - 22, y + 6, 44, 17, color);
+    };
+    match icon {
+        Icon::Clear => sun_at(frame, x, y),
+        Icon::PartlyCloudy => {
+            sun_at(frame, x - 12, y - 14);
+            cloud_at(frame, x + 8, y + 12, grey);
+        }
+        Icon::Cloudy => cloud_at(frame, x, y, grey),
+        Icon::Rain => {
+            cloud_at(frame, x, y - 12, dark);
+            for i in 0..4 {
+                frame.fill_rect(
+                    x - 27 + i * 18,
+                    y + 20 + i % 2 * 8,
+                    3,
+                    14,
+                    LinSrgb::new(60, 140, 255),
+                );
+            }
+        }
+        Icon::Snow => {
+            cloud_at(frame, x, y - 12, grey);
+            for i in 0..4 {
+                disc(
+                    frame,
+                    x - 27 + i * 18,
+                    y + 26 + i % 2 * 10,
+                    3,
+                    LinSrgb::new(255, 255, 255),
+                );
+            }
+        }
+        Icon::Thunder => {
+            cloud_at(frame, x, y - 12, dark);
+            // A bolt stepping down and to the left
+            for i in 0..6 {
+                frame.fill_rect(x + 6 - i * 4, y + 12 + i * 5, 10, 5, sun);
+            }
+        }
+        Icon::Fog => {
+            for i in 0..5 {
+                let inset = (i % 2) * 12;
+                frame.fill_rect(x - 40 + inset, y - 30 + i * 14, 80 - inset, 6, grey);
+            }
+        }
+    }
+}
+
+struct Station {
+    id: String,
+    metar: Option<String>,
+    taf: Option<String>,
+    /// Why the last update failed, if it did
+    error: Option<String>,
+}
+
+pub struct WeatherApp {
+    stations: Vec<Station>,
+    selected: usize,
+    /// Downloads in progress, which report back with the station's index
+    /// and its METAR and TAF
+    fetching: Vec<Task<(usize, Reports)>>,
+    tasks: Tasks,
+    /// Showing the forecast rather than the decoded METAR
+    reading: bool,
+    reader: Reader,
+}
+
+impl Default for WeatherApp {
+    fn default() -> Self {
+        let stations = std::fs::read_to_string(base_dir().join("weather.txt"))
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|id| {
+                let id = id.to_ascii_uppercase();
+                Station {
+                    metar: std::fs::read_to_string(cache_path(&id, "metar")).ok(),
+                    taf: std::fs::read_to_string(cache_path(&id, "taf")).ok(),
+                    error: None,
+                    id,
+                }
+            })
+            .collect();
+        let mut app = Self {
+            stations,
+            selected: 0,
+            fetching: vec![],
+            tasks: Tasks::new(4),
+            reading: false,
+            reader: Reader::new(18.0),
+        };
+        app.refresh();
+        app
+    }
+}
+
+/// A station's METAR and TAF, or why they couldn't be had
+type Reports = Result<(String, String), String>;
+
+/// The newest METAR and TAF for `station`. A missing TAF isn't an error,
+/// as plenty of airports don't have one.
+fn fetch(station: &str) -> Reports {
+    let metar = rg35xx::http::get(&report_url(station, "metar"))?;
+    let metar = metar.lines().next().unwrap_or("").trim().to_string();
+    if Metar::parse(&metar).is_none() {
+        return Err(format!("No METAR for {}", station));
+    }
+    let taf = rg35xx::http::get(&report_url(station, "taf")).unwrap_or_default();
+    Ok((metar, taf.trim().to_string()))
+}
+
+impl WeatherApp {
+    /// Starts downloading every station's reports in the background
+    fn refresh(&mut self) {
+        if !self.fetching.is_empty() || self.stations.is_empty() {
+            return;
+        }
+        for (i, station) in self.stations.iter().enumerate() {
+            let id = station.id.clone();
+            let download = self.tasks.spawn(move || (i, fetch(&id)));
+            self.fetching.push(download);
+        }
+    }
+
+    fn receive(&mut self) {
+        let mut finished = vec![];
+        self.fetching.retain_mut(|download| {
+            finished.extend(download.poll());
+            download.is_pending()
+        });
+        for (i, result) in finished {
+            let station = &mut self.stations[i];
+            match result {
+                Ok((metar, taf)) => {
+                    for (kind, text) in [("metar", &metar), ("taf", &taf)] {
+                        let path = cache_path(&station.id, kind);
+                        let saved = path
+                            .parent()
+                            .map_or(Ok(()), std::fs::create_dir_all)
+                            .and_then(|_| std::fs::write(&path, text));
+                        if let Err(e) = saved {
+                            println!("Couldn't cache the {} for {}: {}", kind, station.id, e);
+                        }
+                    }
+                    station.metar = Some(metar);
+                    station.taf = Some(taf);
+                    station.error = None;
+                }
+                Err(e) => station.error = Some(e),
+            }
+        }
+    }
+
+    fn draw_station(&self, frame: &mut Frame, station: &Station) {
+        let colors = config().theme.colors();
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            20,
+            44,
+            36.0,
+            colors.selected,
+            &station.id,
+        );
+        let Some(metar) = station.metar.as_deref().and_then(Metar::parse) else {
+            let text = station.error.as_deref().unwrap_or("No report yet");
+            frame.text("fonts/Ubuntu-B.ttf", 20, 90, 18.0, colors.text, text);
+            return;
+        };
+
+        let category = metar.category();
+        frame.fill_rect(130, 16, 70, 32, category.color());
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            138,
+            40,
+            20.0,
+            LinSrgb::new(255, 255, 255),
+            category.name(),
+        );
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            220,
+            40,
+            18.0,
+            colors.dim,
+            &format!("observed {}", metar.time),
+        );
+
+        draw_icon(frame, metar.icon(), 90, 150);
+        for (i, (label, value)) in metar.describe().iter().enumerate() {
+            let y = 100 + i * 32;
+            frame.text("fonts/Ubuntu-B.ttf", 190, y, 14.0, colors.dim, label);
+            frame.text("fonts/Ubuntu-B.ttf", 300, y, 18.0, colors.text, value);
+        }
+
+        let raw = station.metar.as_deref().unwrap_or("");
+        for (i, line) in wrap(raw, 14.0, frame.width() - 40).iter().enumerate() {
+            let y = 330 + i * 18;
+            frame.text("fonts/Ubuntu-B.ttf", 20, y, 14.0, colors.dim, line);
+        }
+    }
+}
+
+impl App for WeatherApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        self.receive();
+        let colors = config().theme.colors();
+        frame.fill_rect(0, 0, frame.width(), frame.height(), colors.background);
+
+        if self.stations.is_empty() {
+            let mut ctx = frame.context();
+            ctx.offset(20, 40);
+            ctx.text("No stations yet. Add ICAO codes like KSEA to");
+            ctx.offset(0, 20);
+            ctx.text("weather.txt, one per line, next to the launcher.");
+            return;
+        }
+        let station = &self.stations[self.selected];
+
+        if self.reading {
+            let title = format!("{} forecast", station.id);
+            frame.text("fonts/Ubuntu-B.ttf", 20, 36, 24.0, colors.selected, &title);
+            self.reader.handle_input(input);
+            let (width, height) = (frame.width(), frame.height());
+            self.reader.draw(frame, 20, 50, width - 40, height - 60);
+            if input.just_pressed(Button::ActionB) {
+                self.reading = false;
+            }
+            return;
+        }
+
+        self.draw_station(frame, station);
+        let status = if !self.fetching.is_empty() {
+            format!("Updating {} station(s)...", self.fetching.len())
+        } else if let Some(error) = station.error.as_ref().filter(|_| station.metar.is_some()) {
+            format!("Showing the last copy: {}", error)
+        } else {
+            format!(
+                "{}/{}  left/right station  A forecast  START refresh",
+                self.selected + 1,
+                self.stations.len()
+            )
+        };
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            20,
+            frame.height() - 10,
+            14.0,
+            colors.dim,
+            &status,
+        );
+
+        let count = self.stations.len();
+        if input.repeated(Button::PovLeft) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if input.repeated(Button::PovRight) {
+            self.selected = (self.selected + 1) % count;
+        }
+        if input.start_pressed() {
+            self.refresh();
+        }
+        if input.just_pressed(Button::ActionA) {
+            let station = &self.stations[self.selected];
+            let taf = match station.taf.as_deref() {
+                Some(taf) if !taf.is_empty() => taf.to_string(),
+                _ => format!("No forecast for {}", station.id),
+            };
+            self.reader.set_text(&taf);
+            self.reader.set_scroll(0);
+            self.reading = true;
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        if !self.fetching.is_empty() {
+            // Check on the downloads a few times a second
+            Activity::Idle(Instant::now() + Duration::from_millis(250))
+        } else {
+            Activity::Waiting
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_metar() {
+        let metar = Metar::parse(
+            "KSEA 121853Z 18012G20KT 1 1/2SM -SHRA BR FEW008 BKN020 OVC045 12/M01 A2992 RMK AO2",
+        )
+        .unwrap();
+        assert_eq!(metar.station, "KSEA");
+        assert_eq!(metar.time, "12 18:53Z");
+        assert_eq!(
+            metar.wind,
+            Some(Wind {
+                direction: Some(180),
+                knots: 12,
+                gust: Some(20)
+            })
+        );
+        assert_eq!(metar.visibility.as_ref().unwrap().text, "1 1/2 sm");
+        assert_eq!(metar.weather, ["-SHRA", "BR"]);
+        assert_eq!(describe_weather("-SHRA"), "light showers of rain");
+        assert_eq!(describe_weather("VCSH"), "showers nearby");
+        assert_eq!(metar.ceiling(), Some(2000));
+        assert_eq!((metar.temperature, metar.dewpoint), (Some(12), Some(-1)));
+        assert_eq!(metar.pressure.unwrap().round(), 1013.0);
+        assert_eq!(metar.category(), Category::Ifr);
+        assert_eq!(metar.icon(), Icon::Rain);
+
+        let metar = Metar::parse("METAR EGLL 121850Z 24004MPS 0800 FG VV002 08/08 Q1009").unwrap();
+        assert_eq!(metar.station, "EGLL");
+        assert_eq!(metar.wind.unwrap().knots, 8);
+        assert_eq!(metar.visibility.as_ref().unwrap().meters, 800.0);
+        assert_eq!(metar.category(), Category::Lifr);
+        assert_eq!(metar.icon(), Icon::Fog);
+
+        let metar = Metar::parse("LFPG 121830Z VRB02KT CAVOK 21/10 Q1021").unwrap();
+        assert_eq!(metar.category(), Category::Vfr);
+        assert_eq!(metar.icon(), Icon::Clear);
+    }
+}
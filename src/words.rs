@@ -0,0 +1,684 @@
+//! Words: guess a five-letter word in six tries, told after each which
+//! letters are in the right place and which are in the word somewhere else.
+//! The words come from Taboo's deck, `assets/words.csv`, so adding a deck
+//! there adds words here too.
+//!
+//! There's one daily word, the same for everyone on the same day and deck,
+//! then practice rounds after it. Letters are typed on an on-screen keyboard,
+//! and any five letters count as a guess, since the deck is far from a full
+//! dictionary.
+
+use palette::LinSrgb;
+use rand::{rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+
+use rg35xx::achievements::{unlock, Achievement};
+use rg35xx::app::{Activity, App, AppContext, Button, Frame, Input, Manifest};
+use rg35xx::profiles::Store;
+use rg35xx::qr::Qr;
+use rg35xx::savestate::SaveState;
+use rg35xx::storage::{date_of_day, today};
+use rg35xx::time::{Duration, Instant};
+
+pub const MANIFEST: Manifest = Manifest::new("0.1.0")
+    .description("Guess the word in six tries")
+    .achievements(&[
+        Achievement::new("words_two", "Lucky guess", "Guess a word in two tries"),
+        Achievement::new("words_streak", "On a roll", "Guess 10 words in a row"),
+    ]);
+
+const LENGTH: usize = 5;
+const GUESSES: usize = 6;
+const TILE_SIZE: usize = 44;
+const GAP: usize = 6;
+const BOARD_TOP: usize = 12;
+const KEYBOARD_TOP: usize = BOARD_TOP + GUESSES * (TILE_SIZE + GAP) + 10;
+const KEY_HEIGHT: usize = 44;
+const KEY_WIDTH: usize = 44;
+/// The enter and delete keys, either end of the bottom row
+const WIDE_KEY_WIDTH: usize = 69;
+const MESSAGE_TIME: Duration = Duration::from_secs(2);
+const STREAK_ACHIEVEMENT: u64 = 10;
+
+/// How a letter of a guess compares with the answer, in order of how much
+/// it gives away
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Mark {
+    Absent,
+    Present,
+    Correct,
+}
+
+impl Mark {
+    fn color(self) -> LinSrgb<u8> {
+        match self {
+            Self::Absent => LinSrgb::new(58, 58, 60),
+            Self::Present => LinSrgb::new(181, 159, 59),
+            Self::Correct => LinSrgb::new(83, 141, 78),
+        }
+    }
+
+    fn emoji(self) -> char {
+        match self {
+            Self::Absent => '⬛',
+            Self::Present => '🟨',
+            Self::Correct => '🟩',
+        }
+    }
+}
+
+/// Marks each letter of `guess` against `answer`. A letter that's in the
+/// answer fewer times than it's guessed is only marked present as many
+/// times as it's there, right places first.
+fn score(guess: &str, answer: &str) -> [Mark; LENGTH] {
+    let (guess, answer) = (guess.as_bytes(), answer.as_bytes());
+    let mut marks = [Mark::Absent; LENGTH];
+    let mut unmatched = Vec::new();
+    for i in 0..LENGTH {
+        if guess[i] == answer[i] {
+            marks[i] = Mark::Correct;
+        } else {
+            unmatched.push(answer[i]);
+        }
+    }
+    for i in 0..LENGTH {
+        if marks[i] == Mark::Correct {
+            continue;
+        }
+        if let Some(found) = unmatched.iter().position(|&letter| letter == guess[i]) {
+            unmatched.swap_remove(found);
+            marks[i] = Mark::Present;
+        }
+    }
+    marks
+}
+
+/// Every five-letter word in a deck, capitalized, in order and once each
+fn dictionary(csv: &str) -> Vec<String> {
+    let mut words = csv
+        .lines()
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .filter(|word| word.len() == LENGTH && word.bytes().all(|b| b.is_ascii_alphabetic()))
+        .map(str::to_ascii_uppercase)
+        .collect::<Vec<_>>();
+    words.sort();
+    words.dedup();
+    words
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Round {
+    answer: String,
+    guesses: Vec<String>,
+    /// The guess being typed
+    typing: String,
+    /// The day it's the word of, or none for practice
+    daily: Option<i64>,
+}
+
+impl SaveState for Round {
+    const NAME: &'static str = "words";
+}
+
+impl Round {
+    fn new(answer: String, daily: Option<i64>) -> Self {
+        Self {
+            answer,
+            guesses: Vec::new(),
+            typing: String::new(),
+            daily,
+        }
+    }
+
+    fn is_won(&self) -> bool {
+        self.guesses.last() == Some(&self.answer)
+    }
+
+    fn is_over(&self) -> bool {
+        self.is_won() || self.guesses.len() == GUESSES
+    }
+
+    fn type_letter(&mut self, letter: char) {
+        if self.typing.len() < LENGTH && !self.is_over() {
+            self.typing.push(letter);
+        }
+    }
+
+    /// Takes the typed guess, or says why not
+    fn submit(&mut self) -> Result<(), &'static str> {
+        if self.is_over() {
+            return Ok(());
+        }
+        if self.typing.len() < LENGTH {
+            return Err("Not enough letters");
+        }
+        self.guesses.push(std::mem::take(&mut self.typing));
+        Ok(())
+    }
+
+    /// The best that's known about each letter guessed so far
+    fn known(&self, letter: u8) -> Option<Mark> {
+        self.guesses
+            .iter()
+            .flat_map(|guess| guess.bytes().zip(score(guess, &self.answer)))
+            .filter(|&(guessed, _)| guessed == letter)
+            .map(|(_, mark)| mark)
+            .max()
+    }
+
+    /// The colored squares of each guess, to share without giving the word
+    /// away
+    fn share_text(&self) -> String {
+        let title = match self.daily {
+            Some(day) => format!("Words {}", date_of_day(day)),
+            None => "Words practice".to_string(),
+        };
+        let tries = if self.is_won() {
+            self.guesses.len().to_string()
+        } else {
+            "X".to_string()
+        };
+        let rows = self.guesses.iter().map(|guess| {
+            score(guess, &self.answer)
+                .map(Mark::emoji)
+                .iter()
+                .collect::<String>()
+        });
+        std::iter::once(format!("{} {}/{}", title, tries, GUESSES))
+            .chain(rows)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Letter(char),
+    Enter,
+    Delete,
+}
+
+impl Key {
+    fn label(self) -> String {
+        match self {
+            Self::Letter(letter) => letter.to_string(),
+            Self::Enter => "Enter".to_string(),
+            Self::Delete => "Del".to_string(),
+        }
+    }
+
+    fn width(self) -> usize {
+        match self {
+            Self::Letter(_) => KEY_WIDTH,
+            Self::Enter | Self::Delete => WIDE_KEY_WIDTH,
+        }
+    }
+}
+
+fn keyboard() -> Vec<Vec<Key>> {
+    let letters = |row: &str| row.chars().map(Key::Letter).collect::<Vec<_>>();
+    let mut bottom = vec![Key::Enter];
+    bottom.extend(letters("ZXCVBNM"));
+    bottom.push(Key::Delete);
+    vec![letters("QWERTYUIOP"), letters("ASDFGHJKL"), bottom]
+}
+
+/// The left edge and width of each key of a row, centered on the screen
+fn key_spans(row: &[Key], screen_width: usize) -> Vec<(usize, usize)> {
+    let width = row.iter().map(|key| key.width() + GAP).sum::<usize>() - GAP;
+    let mut x = (screen_width - width) / 2;
+    row.iter()
+        .map(|key| {
+            let span = (x, key.width());
+            x += key.width() + GAP;
+            span
+        })
+        .collect()
+}
+
+pub struct WordsApp {
+    words: Vec<String>,
+    round: Option<Round>,
+    keys: Vec<Vec<Key>>,
+    /// The row and key the cursor's on
+    cursor: (usize, usize),
+    /// Shown over the board until the instant
+    message: Option<(String, Instant)>,
+    /// Whether the result's showing as a QR code
+    sharing: bool,
+    /// The screen width, which the keyboard's centered in
+    width: usize,
+    /// Games played and won, streaks and how many tries wins took
+    stats: Store,
+    rng: StdRng,
+}
+
+impl Default for WordsApp {
+    fn default() -> Self {
+        let words = rg35xx::assets::load("words.csv")
+            .map(|bytes| dictionary(&String::from_utf8_lossy(&bytes)))
+            .unwrap_or_default();
+        let mut app = Self {
+            words,
+            round: Round::load(),
+            keys: keyboard(),
+            cursor: (0, 0),
+            message: None,
+            sharing: false,
+            width: 640,
+            stats: Store::open("words"),
+            rng: rg35xx::random::rng("words"),
+        };
+        if app.round.is_none() {
+            app.next_round();
+        }
+        app
+    }
+}
+
+impl WordsApp {
+    /// Today's word if it hasn't been played yet, otherwise practice
+    fn next_round(&mut self) {
+        self.sharing = false;
+        if self.words.is_empty() {
+            self.round = None;
+            return;
+        }
+        let day = today();
+        self.round = Some(if self.stats.get::<i64>("daily_day") != Some(day) {
+            let mut rng = rg35xx::random::daily("words", day);
+            Round::new(self.words.choose(&mut rng).unwrap().clone(), Some(day))
+        } else {
+            Round::new(self.words.choose(&mut self.rng).unwrap().clone(), None)
+        });
+    }
+
+    fn show(&mut self, message: impl Into<String>) {
+        self.message = Some((message.into(), Instant::now() + MESSAGE_TIME));
+    }
+
+    fn finished(&mut self) {
+        let Some(round) = &self.round else {
+            return;
+        };
+        Round::clear();
+        let (won, tries, daily) = (round.is_won(), round.guesses.len(), round.daily);
+        self.stats.add("played", 1);
+        if let Some(day) = daily {
+            self.stats.set("daily_day", day);
+        }
+        if won {
+            self.stats.add("won", 1);
+            self.stats.add(&format!("tries_{}", tries), 1);
+            self.stats.add("streak", 1);
+            let streak = self.stats.get::<u64>("streak").unwrap_or(0);
+            if streak > self.stats.get("best_streak").unwrap_or(0) {
+                self.stats.set("best_streak", streak);
+            }
+            if tries <= 2 {
+                unlock("words_two");
+            }
+            if streak >= STREAK_ACHIEVEMENT {
+                unlock("words_streak");
+            }
+        } else {
+            self.stats.set("streak", 0);
+        }
+        if let Err(e) = self.stats.save() {
+            println!("Couldn't save the Words statistics: {}", e);
+        }
+    }
+
+    fn press(&mut self, key: Key) {
+        let Some(round) = &mut self.round else {
+            return;
+        };
+        match key {
+            Key::Letter(letter) => round.type_letter(letter),
+            Key::Delete => {
+                round.typing.pop();
+            }
+            Key::Enter => match round.submit() {
+                Ok(()) if round.is_over() => self.finished(),
+                Ok(()) => (),
+                Err(e) => self.show(e),
+            },
+        }
+    }
+
+    fn handle_input(&mut self, input: &Input) {
+        let start = input.start_pressed();
+        if self.round.as_ref().is_none_or(Round::is_over) {
+            if input.just_pressed(Button::ActionA) || start {
+                self.next_round();
+            }
+            if input.just_pressed(Button::ActionH) {
+                self.sharing = !self.sharing;
+            }
+            return;
+        }
+
+        let (mut row, mut column) = self.cursor;
+        if input.repeated(Button::PovLeft) {
+            column = (column + self.keys[row].len() - 1) % self.keys[row].len();
+        }
+        if input.repeated(Button::PovRight) {
+            column = (column + 1) % self.keys[row].len();
+        }
+        for (button, step) in [(Button::PovUp, self.keys.len() - 1), (Button::PovDown, 1)] {
+            if input.repeated(button) {
+                // The key in the next row nearest to straight above or below
+                let (x, width) = key_spans(&self.keys[row], self.width)[column];
+                let middle = x + width / 2;
+                row = (row + step) % self.keys.len();
+                column = key_spans(&self.keys[row], self.width)
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, (x, width))| (x + width / 2).abs_diff(middle))
+                    .map_or(0, |(i, _)| i);
+            }
+        }
+        self.cursor = (row, column);
+
+        if input.just_pressed(Button::ActionA) {
+            self.press(self.keys[row][column]);
+        }
+        if input.repeated(Button::ActionB) {
+            self.press(Key::Delete);
+        }
+        if start {
+            self.press(Key::Enter);
+        }
+    }
+
+    fn draw_board(&self, frame: &mut Frame, round: &Round) {
+        let width = LENGTH * (TILE_SIZE + GAP) - GAP;
+        let left = (frame.width() - width) / 2;
+        for row in 0..GUESSES {
+            let y = BOARD_TOP + row * (TILE_SIZE + GAP);
+            let (letters, marks) = match round.guesses.get(row) {
+                Some(guess) => (guess.as_str(), Some(score(guess, &round.answer))),
+                None if row == round.guesses.len() => (round.typing.as_str(), None),
+                None => ("", None),
+            };
+            for i in 0..LENGTH {
+                let x = left + i * (TILE_SIZE + GAP);
+                let letter = letters.chars().nth(i);
+                match marks {
+                    Some(marks) => frame.fill_rect(x, y, TILE_SIZE, TILE_SIZE, marks[i].color()),
+                    None => {
+                        let border = if letter.is_some() {
+                            LinSrgb::new(135, 138, 140)
+                        } else {
+                            LinSrgb::new(60, 60, 64)
+                        };
+                        frame.fill_rect(x, y, TILE_SIZE, TILE_SIZE, border);
+                        frame.fill_rect(
+                            x + 2,
+                            y + 2,
+                            TILE_SIZE - 4,
+                            TILE_SIZE - 4,
+                            LinSrgb::new(18, 18, 19),
+                        );
+                    }
+                }
+                if let Some(letter) = letter {
+                    frame.text(
+                        "fonts/Ubuntu-B.ttf",
+                        x + 12,
+                        y + 32,
+                        28.0,
+                        LinSrgb::new(255, 255, 255),
+                        &letter.to_string(),
+                    );
+                }
+            }
+        }
+    }
+
+    fn draw_keyboard(&self, frame: &mut Frame, round: &Round) {
+        for (row, keys) in self.keys.iter().enumerate() {
+            let y = KEYBOARD_TOP + row * (KEY_HEIGHT + GAP);
+            for (column, (key, (x, width))) in
+                keys.iter().zip(key_spans(keys, frame.width())).enumerate()
+            {
+                let color = match key {
+                    Key::Letter(letter) => round.known(*letter as u8).map(Mark::color),
+                    _ => None,
+                }
+                .unwrap_or(LinSrgb::new(129, 131, 132));
+                if self.cursor == (row, column) && !round.is_over() {
+                    frame.fill_rect(
+                        x - 3,
+                        y - 3,
+                        width + 6,
+                        KEY_HEIGHT + 6,
+                        LinSrgb::new(255, 255, 255),
+                    );
+                }
+                frame.fill_rect(x, y, width, KEY_HEIGHT, color);
+                let label = key.label();
+                let (size, offset) = if label.len() > 1 {
+                    (18.0, 14)
+                } else {
+                    (24.0, 14)
+                };
+                frame.text(
+                    "fonts/Ubuntu-B.ttf",
+                    x + offset,
+                    y + 30,
+                    size,
+                    LinSrgb::new(255, 255, 255),
+                    &label,
+                );
+            }
+        }
+    }
+
+    fn draw_result(&self, frame: &mut Frame, round: &Round) {
+        let (left, top) = (40, 40);
+        frame.fill_rect(left, top, frame.width() - 80, 400, LinSrgb::new(30, 30, 34));
+        let white = LinSrgb::new(255, 255, 255);
+        let grey = LinSrgb::new(170, 170, 170);
+        let headline = if round.is_won() {
+            format!("Got it in {}!", round.guesses.len())
+        } else {
+            format!("The word was {}", round.answer)
+        };
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            left + 20,
+            top + 40,
+            26.0,
+            white,
+            &headline,
+        );
+
+        let get = |key: &str| self.stats.get::<u64>(key).unwrap_or(0);
+        let played = get("played");
+        let percent = (get("won") * 100).checked_div(played).unwrap_or(0);
+        let lines = [
+            format!("Played {}, won {}%", played, percent),
+            format!("Streak {}, best {}", get("streak"), get("best_streak")),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                left + 20,
+                top + 80 + i * 26,
+                20.0,
+                white,
+                line,
+            );
+        }
+
+        // How many tries each win took
+        let most = (1..=GUESSES)
+            .map(|tries| get(&format!("tries_{}", tries)))
+            .max()
+            .unwrap_or(0);
+        for tries in 1..=GUESSES {
+            let y = top + 140 + (tries - 1) * 30;
+            let count = get(&format!("tries_{}", tries));
+            let width = 20 + (count * 200).checked_div(most).unwrap_or(0) as usize;
+            let color = if round.is_won() && tries == round.guesses.len() {
+                Mark::Correct.color()
+            } else {
+                LinSrgb::new(80, 80, 84)
+            };
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                left + 20,
+                y + 20,
+                18.0,
+                white,
+                &tries.to_string(),
+            );
+            frame.fill_rect(left + 40, y + 2, width, 24, color);
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                left + 44,
+                y + 20,
+                16.0,
+                white,
+                &count.to_string(),
+            );
+        }
+
+        let right = left + 320;
+        if self.sharing {
+            if let Ok(qr) = Qr::new(&round.share_text()) {
+                qr.draw(frame, right - 20, top + 60, 240);
+            }
+        } else {
+            // The same squares as the share text
+            let square = 24;
+            for (row, guess) in round.guesses.iter().enumerate() {
+                for (i, mark) in score(guess, &round.answer).iter().enumerate() {
+                    let (x, y) = (right + i * (square + 4), top + 70 + row * (square + 4));
+                    frame.fill_rect(x, y, square, square, mark.color());
+                }
+            }
+        }
+        let help = if self.sharing {
+            "Y hide the QR code"
+        } else {
+            "Y share as a QR code"
+        };
+        frame.text("fonts/Ubuntu-B.ttf", right, top + 320, 16.0, grey, help);
+        frame.text(
+            "fonts/Ubuntu-B.ttf",
+            left + 20,
+            top + 380,
+            18.0,
+            grey,
+            "A for a practice word",
+        );
+    }
+}
+
+impl App for WordsApp {
+    fn update(&mut self, ctx: &mut AppContext) {
+        let (input, frame) = (ctx.input, &mut *ctx.frame);
+        self.width = frame.width();
+        self.handle_input(input);
+        if self
+            .message
+            .as_ref()
+            .is_some_and(|(_, until)| Instant::now() >= *until)
+        {
+            self.message = None;
+        }
+
+        frame.fill_rect(
+            0,
+            0,
+            frame.width(),
+            frame.height(),
+            LinSrgb::new(18, 18, 19),
+        );
+        let Some(round) = &self.round else {
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                30,
+                60,
+                20.0,
+                LinSrgb::new(255, 255, 255),
+                "There are no five-letter words in words.csv",
+            );
+            return;
+        };
+        self.draw_board(frame, round);
+        self.draw_keyboard(frame, round);
+        if round.is_over() {
+            self.draw_result(frame, round);
+        }
+        if let Some((message, _)) = &self.message {
+            let y = BOARD_TOP + 2 * (TILE_SIZE + GAP);
+            frame.fill_rect(200, y, frame.width() - 400, 44, LinSrgb::new(255, 255, 255));
+            frame.text(
+                "fonts/Ubuntu-B.ttf",
+                220,
+                y + 29,
+                20.0,
+                LinSrgb::new(0, 0, 0),
+                message,
+            );
+        }
+    }
+
+    fn activity(&self) -> Activity {
+        match &self.message {
+            Some((_, until)) => Activity::Idle(*until),
+            None => Activity::Waiting,
+        }
+    }
+
+    fn on_exit(&mut self) {
+        if let Some(round) = self.round.as_ref().filter(|round| !round.is_over()) {
+            if let Err(e) = round.save() {
+                println!("Couldn't save the Words round: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        use Mark::*;
+        assert_eq!(
+            score("LLAMA", "ALLEY"),
+            [Present, Correct, Present, Absent, Absent]
+        );
+        assert_eq!(
+            score("SPEED", "ABIDE"),
+            [Absent, Absent, Present, Absent, Present]
+        );
+        assert_eq!(score("ALLEY", "ALLEY"), [Correct; 5]);
+        assert_eq!(
+            dictionary("Banana,Fruit,Peel\nApple,fruit, Bubble Gum,Lemon\n"),
+            ["APPLE", "FRUIT", "LEMON"]
+        );
+
+        let mut round = Round::new("CHAIR".to_string(), Some(0));
+        for letter in "CHAINS".chars() {
+            round.type_letter(letter);
+        }
+        assert_eq!(round.typing, "CHAIN");
+        assert!(round.submit().is_ok());
+        assert_eq!(round.known(b'N'), Some(Absent));
+        assert_eq!(round.known(b'R'), None);
+        round.typing = "CHAIR".to_string();
+        round.submit().unwrap();
+        assert!(round.is_won() && round.is_over());
+        assert_eq!(
+            round.share_text(),
+            "Words 1970-01-01 2/6\n🟩🟩🟩🟩⬛\n🟩🟩🟩🟩🟩"
+        );
+    }
+}